@@ -0,0 +1,256 @@
+use std::collections::HashSet;
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use tesutil::{tes3, tes4};
+
+use crate::config::*;
+
+use anyhow::{anyhow, Context, Result};
+
+/// The first four bytes of a Morrowind BSA, which are its version number
+const MW_BSA_MAGIC: &[u8; 4] = b"\0\x01\0\0";
+/// The first four bytes of an Oblivion BSA
+const OB_BSA_MAGIC: &[u8; 4] = b"BSA\0";
+
+/// A BSA archive from either game
+enum AnyBsa {
+    Morrowind(tes3::Bsa<BufReader<File>>),
+    Oblivion(tes4::Bsa<BufReader<File>>),
+}
+
+impl AnyBsa {
+    /// Opens an archive, telling which game it's from by its header
+    fn open(path: &Path) -> Result<AnyBsa> {
+        let f = File::open(path)
+            .with_context(|| format!("Could not open archive {}", path.display()))?;
+        let mut reader = BufReader::new(f);
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        reader.rewind()?;
+
+        Ok(match &magic {
+            OB_BSA_MAGIC => AnyBsa::Oblivion(tes4::Bsa::read(reader)?),
+            MW_BSA_MAGIC => AnyBsa::Morrowind(tes3::Bsa::read(reader)?),
+            _ => {
+                return Err(anyhow!(
+                    "{} is not a Morrowind or Oblivion BSA archive",
+                    path.display()
+                ))
+            }
+        })
+    }
+}
+
+/// Converts a path in an archive to a path under a directory
+///
+/// Fails if the path would point outside the directory, which a well-formed archive never does.
+fn output_path(dir: &Path, archive_path: &str) -> Result<PathBuf> {
+    let mut path = dir.to_path_buf();
+    for component in archive_path.split('\\').filter(|c| !c.is_empty()) {
+        if component == "." || component == ".." || component.contains(['/', ':']) {
+            return Err(anyhow!("Archive file {} has an unsafe path", archive_path));
+        }
+        path.push(component);
+    }
+
+    if path == dir {
+        return Err(anyhow!("Archive file {} has an empty path", archive_path));
+    }
+
+    Ok(path)
+}
+
+/// Lists the files in an archive with their sizes
+fn list_archive(archive_path: &str) -> Result<()> {
+    match AnyBsa::open(Path::new(archive_path))? {
+        AnyBsa::Morrowind(bsa) => {
+            for file in bsa.files() {
+                println!("{:>10}  {}", file.size(), file.path());
+            }
+        }
+        AnyBsa::Oblivion(bsa) => {
+            for file in bsa.files() {
+                let compressed = if file.is_compressed() {
+                    " (compressed)"
+                } else {
+                    ""
+                };
+                println!("{:>10}  {}{}", file.size(), file.path(), compressed);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Extracts all the files in an archive to a directory
+///
+/// Files are decompressed and written on as many threads as the system can run at once, since
+/// extracting one of the large texture archives on a single thread takes minutes.
+fn extract_archive(archive_path: &str, output_dir: &str) -> Result<()> {
+    let output_dir = Path::new(output_dir);
+    let write_file = |path: &str, data: Vec<u8>| -> Result<()> {
+        let file_path = output_path(output_dir, path)?;
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(&file_path, data)
+            .with_context(|| format!("Could not write {}", file_path.display()))
+    };
+
+    let num_threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let num_files = match AnyBsa::open(Path::new(archive_path))? {
+        AnyBsa::Morrowind(mut bsa) => {
+            bsa.extract_all(num_threads, |file, data| write_file(file.path(), data))?;
+            bsa.len()
+        }
+        AnyBsa::Oblivion(mut bsa) => {
+            bsa.extract_all(num_threads, |file, data| write_file(file.path(), data))?;
+            bsa.len()
+        }
+    };
+
+    println!("Extracted {} files to {}", num_files, output_dir.display());
+    Ok(())
+}
+
+/// Finds the files in a directory and its subdirectories, along with their paths in an archive
+///
+/// Symbolic links are followed, except for links to a directory that's already being searched,
+/// which would loop forever. `searching` holds the canonical paths of those directories.
+fn find_files(
+    dir: &Path,
+    prefix: &str,
+    files: &mut Vec<(String, PathBuf)>,
+    searching: &mut HashSet<PathBuf>,
+) -> Result<()> {
+    let canonical_dir = dir.canonicalize()?;
+    if !searching.insert(canonical_dir.clone()) {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry
+            .file_name()
+            .into_string()
+            .map_err(|n| anyhow!("File name {:?} is not valid UTF-8", n))?;
+        let archive_path = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}\\{}", prefix, name)
+        };
+
+        // unlike the entry's file type, the path's metadata follows symbolic links
+        let path = entry.path();
+        if fs::metadata(&path)
+            .with_context(|| format!("Could not read {}", path.display()))?
+            .is_dir()
+        {
+            find_files(&path, &archive_path, files, searching)?;
+        } else {
+            files.push((archive_path, path));
+        }
+    }
+
+    searching.remove(&canonical_dir);
+    Ok(())
+}
+
+/// Creates an archive from the files in a directory
+///
+/// The files' paths in the archive are their paths relative to the directory, so the directory
+/// should be laid out like a data directory, with folders like meshes and textures at the top.
+fn create_archive(game: Game, source_dir: &str, archive_path: &str, compress: bool) -> Result<()> {
+    let mut files = vec![];
+    find_files(Path::new(source_dir), "", &mut files, &mut HashSet::new())
+        .with_context(|| format!("Could not read directory {}", source_dir))?;
+
+    let mut mw_builder = tes3::BsaBuilder::new();
+    let mut ob_builder = tes4::BsaBuilder::new();
+    ob_builder.set_compressed(compress);
+    for (path, file_path) in &files {
+        let data = fs::read(file_path)
+            .with_context(|| format!("Could not read {}", file_path.display()))?;
+        match game {
+            Game::Morrowind => mw_builder.add_file(path, data),
+            Game::Oblivion => ob_builder.add_file(path, data),
+        }
+        .with_context(|| format!("Could not add {} to the archive", path))?;
+    }
+
+    let f = File::create(archive_path)
+        .with_context(|| format!("Could not create archive {}", archive_path))?;
+    let mut writer = BufWriter::new(f);
+    match game {
+        Game::Morrowind => mw_builder.write(&mut writer)?,
+        Game::Oblivion => ob_builder.write(&mut writer)?,
+    }
+    writer.flush()?;
+
+    println!("Created {} with {} files", archive_path, files.len());
+    Ok(())
+}
+
+/// Lists, extracts, or creates a BSA archive
+pub fn run_archive_command(command: &ArchiveCommand) -> Result<()> {
+    match command {
+        ArchiveCommand::List(archive) => list_archive(archive),
+        ArchiveCommand::Extract {
+            archive,
+            output_dir,
+        } => extract_archive(archive, output_dir),
+        ArchiveCommand::Create {
+            game,
+            source_dir,
+            archive,
+            compress,
+        } => create_archive(*game, source_dir, archive, *compress),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_output_paths() {
+        let dir = Path::new("out");
+        assert_eq!(
+            output_path(dir, "meshes\\m\\misc_com_bottle_01.nif").unwrap(),
+            ["out", "meshes", "m", "misc_com_bottle_01.nif"]
+                .iter()
+                .collect::<PathBuf>()
+        );
+        assert!(output_path(dir, "meshes\\..\\..\\evil.dll").is_err());
+        assert!(output_path(dir, "c:\\windows\\evil.dll").is_err());
+        assert!(output_path(dir, "\\").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn find_linked_files() {
+        let dir =
+            std::env::temp_dir().join(format!("tesconvert_find_files_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("textures")).unwrap();
+        fs::write(dir.join("textures/a.dds"), b"a").unwrap();
+        std::os::unix::fs::symlink(dir.join("textures"), dir.join("meshes")).unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("textures/loop")).unwrap();
+
+        let mut files = vec![];
+        let result = find_files(&dir, "", &mut files, &mut HashSet::new());
+        fs::remove_dir_all(&dir).unwrap();
+        result.unwrap();
+
+        let mut paths: Vec<_> = files.into_iter().map(|(p, _)| p).collect();
+        paths.sort();
+        assert_eq!(paths, ["meshes\\a.dds", "textures\\a.dds"]);
+    }
+}
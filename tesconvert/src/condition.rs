@@ -0,0 +1,93 @@
+/// The condition and enchantment charge an item has when it's undamaged and fully charged
+///
+/// Both games store an item's current condition and charge as absolute values, but the item's
+/// maximums usually differ between games (see the equipment durability ratio), so the values have
+/// to be rescaled to keep the same fraction remaining.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct ItemBaseStats {
+    /// The item's maximum condition, or `None` if it can't be damaged
+    pub max_condition: Option<u32>,
+    /// The item's maximum enchantment charge, or `None` if it isn't enchanted
+    pub max_charge: Option<f32>,
+}
+
+impl ItemBaseStats {
+    /// Creates base stats for an item
+    pub fn new(max_condition: Option<u32>, max_charge: Option<f32>) -> ItemBaseStats {
+        ItemBaseStats {
+            max_condition,
+            max_charge,
+        }
+    }
+}
+
+/// Scales a value out of one maximum to the same fraction of another maximum
+///
+/// The fraction is clamped to between 0 and 1. If the source maximum is 0, the item is treated as
+/// being at full strength.
+fn rescale(value: f32, from_max: f32, to_max: f32) -> f32 {
+    if from_max <= 0. {
+        to_max
+    } else {
+        (value / from_max).clamp(0., 1.) * to_max
+    }
+}
+
+/// Converts a Morrowind item's remaining condition to Oblivion item health
+///
+/// Returns `None` if the item can't be damaged in one of the games.
+pub fn mw_condition_to_ob(condition: u32, mw: &ItemBaseStats, ob: &ItemBaseStats) -> Option<f32> {
+    Some(rescale(
+        condition as f32,
+        mw.max_condition? as f32,
+        ob.max_condition? as f32,
+    ))
+}
+
+/// Converts Oblivion item health to a Morrowind item's remaining condition
+///
+/// Returns `None` if the item can't be damaged in one of the games.
+pub fn ob_condition_to_mw(health: f32, ob: &ItemBaseStats, mw: &ItemBaseStats) -> Option<u32> {
+    Some(rescale(health, ob.max_condition? as f32, mw.max_condition? as f32).round() as u32)
+}
+
+/// Converts a Morrowind item's remaining enchantment charge to Oblivion enchantment points
+///
+/// Returns `None` if the item isn't enchanted in one of the games.
+pub fn mw_charge_to_ob(charge: f32, mw: &ItemBaseStats, ob: &ItemBaseStats) -> Option<f32> {
+    Some(rescale(charge, mw.max_charge?, ob.max_charge?))
+}
+
+/// Converts Oblivion enchantment points to a Morrowind item's remaining enchantment charge
+///
+/// Returns `None` if the item isn't enchanted in one of the games.
+pub fn ob_charge_to_mw(charge: f32, ob: &ItemBaseStats, mw: &ItemBaseStats) -> Option<f32> {
+    Some(rescale(charge, ob.max_charge?, mw.max_charge?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rescale_condition_and_charge() {
+        let mw = ItemBaseStats::new(Some(500), Some(120.));
+        let ob = ItemBaseStats::new(Some(100), Some(1500.));
+
+        assert_eq!(mw_condition_to_ob(250, &mw, &ob), Some(50.));
+        assert_eq!(ob_condition_to_mw(50., &ob, &mw), Some(250));
+        assert_eq!(mw_condition_to_ob(900, &mw, &ob), Some(100.));
+        assert_eq!(mw_charge_to_ob(30., &mw, &ob), Some(375.));
+        assert_eq!(ob_charge_to_mw(375., &ob, &mw), Some(30.));
+
+        let unenchanted = ItemBaseStats::new(Some(100), None);
+        assert_eq!(mw_charge_to_ob(30., &mw, &unenchanted), None);
+        assert_eq!(
+            ob_condition_to_mw(10., &ob, &ItemBaseStats::default()),
+            None
+        );
+
+        let broken = ItemBaseStats::new(Some(0), Some(0.));
+        assert_eq!(mw_condition_to_ob(0, &broken, &ob), Some(100.));
+    }
+}
@@ -1,13 +1,25 @@
 use std::cmp;
 use std::fs;
 use std::ops::{Add, Div};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use clap::{App, Arg, SubCommand};
 use ini::Ini;
 use num::{Float, One};
+use tesutil::{Attribute, IndexCache};
+
+use crate::morrowind::COMPANION_MOD_NAME;
+use crate::names::NamePolicy;
+use crate::report::ReportFormat;
+
+/// A game supported by the converter
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Game {
+    Morrowind,
+    Oblivion,
+}
 
 /// The command to be executed
 #[derive(Debug, PartialEq)]
@@ -16,6 +28,107 @@ pub enum Command {
     MorrowindToOblivion,
     /// Convert an Oblivion character to Morrowind
     OblivionToMorrowind,
+    /// List a game's saves and choose one
+    ChooseSave(Game),
+    /// Edit the player in a game's save
+    EditSave(Game),
+    /// Port the content of a Morrowind plugin to an Oblivion plugin
+    PortPlugin,
+    /// Convert a single Morrowind spell and print the result
+    TrySpell(String),
+    /// Convert a single Morrowind item and print the result
+    TryItem(String),
+    /// List, extract, or create a BSA archive
+    Archive(ArchiveCommand),
+}
+
+/// An operation on a BSA archive
+#[derive(Debug, PartialEq)]
+pub enum ArchiveCommand {
+    /// List the files in the archive at a path
+    List(String),
+    /// Extract all the files in an archive to a directory
+    Extract { archive: String, output_dir: String },
+    /// Create an archive for a game from the files in a directory
+    Create {
+        game: Game,
+        source_dir: String,
+        archive: String,
+        /// Whether to compress the archive's files, which only Oblivion supports
+        compress: bool,
+    },
+}
+
+/// Where the forms created during a conversion are stored
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FormTarget {
+    /// A plugin that the save is updated to depend on
+    Plugin,
+    /// The save itself, like the spells and potions the player makes in game
+    Save,
+}
+
+/// What to do with a Morrowind player who is a werewolf
+///
+/// Oblivion has no werewolves, so lycanthropy can't be carried over.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WerewolfPolicy {
+    /// Fail the conversion
+    Refuse,
+    /// Convert the player as if they'd been cured of lycanthropy
+    Cure,
+}
+
+/// What to do with the permanent effects of Morrowind afflictions
+///
+/// Afflictions are blights and curses like corprus. Oblivion has neither, but their effects on the
+/// player's stats can be kept.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AfflictionPolicy {
+    /// Make the effects part of the player's base stats
+    Bake,
+    /// Replicate each affliction with an Oblivion ability
+    Ability,
+}
+
+/// Changes to make to the player when editing a save
+#[derive(Debug, Default, PartialEq)]
+pub struct SaveEdits {
+    /// New name for the player
+    pub name: Option<String>,
+    /// New level for the player
+    pub level: Option<u16>,
+    /// Amount of gold to give the player, or to take away if negative
+    pub gold: Option<i32>,
+    /// IDs of spells to add to the player's spell list
+    ///
+    /// For Oblivion, these are form IDs in hexadecimal.
+    pub add_spells: Vec<String>,
+    /// IDs of spells to remove from the player's spell list
+    pub remove_spells: Vec<String>,
+    /// New values for the player's base attributes
+    pub attributes: Vec<(Attribute, u8)>,
+}
+
+/// Parses an attribute assignment of the form `name=value`
+fn parse_attribute(value: &str) -> Result<(Attribute, u8)> {
+    let (name, amount) = value
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Expected attribute=value, got {}", value))?;
+    let attribute = match name.trim().to_lowercase().as_str() {
+        "strength" => Attribute::Strength,
+        "intelligence" => Attribute::Intelligence,
+        "willpower" => Attribute::Willpower,
+        "agility" => Attribute::Agility,
+        "speed" => Attribute::Speed,
+        "endurance" => Attribute::Endurance,
+        "personality" => Attribute::Personality,
+        "luck" => Attribute::Luck,
+        _ => return Err(anyhow!("Unknown attribute {}", name)),
+    };
+    let amount = u8::from_str(amount.trim())
+        .with_context(|| format!("Invalid value for attribute {}", name))?;
+    Ok((attribute, amount))
 }
 
 /// Strategy to use when combining values
@@ -59,39 +172,157 @@ impl CombineStrategy {
 }
 
 /// Iterate through INI files in a given directory
+///
+/// Files are returned in order by name so that, when several files map the same ID, which one
+/// wins doesn't depend on the order the filesystem happens to list them in.
 pub fn iter_form_map<P: AsRef<Path>>(ini_dir: P) -> Result<impl Iterator<Item = Ini>> {
-    let mut files = vec![];
     let ini_dir = ini_dir.as_ref();
+    let mut paths = vec![];
     for entry in
         fs::read_dir(ini_dir).with_context(|| format!("Error reading directory {:?}", ini_dir))?
     {
-        files.push(Ini::load_from_file(entry?.path())?);
+        paths.push(entry?.path());
+    }
+    paths.sort();
+
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths {
+        files
+            .push(Ini::load_from_file(&path).with_context(|| format!("Error reading {:?}", path))?);
     }
 
     Ok(files.into_iter())
 }
 
+/// Mapping files compiled into the binary, keyed by the name of the mapping directory they belong to
+///
+/// These are the mappings for vanilla content, so a fresh install can convert a character without
+/// any mapping files on disk.
+#[cfg(feature = "embedded-mappings")]
+const EMBEDDED_MAPPINGS: [(&str, &str); 1] = [("mwob", include_str!("../mwob/default.ini"))];
+
+/// Iterate through INI files in a mapping directory across all configuration layers
+///
+/// Embedded mappings come first if the `embedded-mappings` feature is enabled, followed by the
+/// built-in configuration, then the user's configuration, and finally the configuration given for
+/// this run, so mappings from later files should override mappings from earlier ones.
+pub fn iter_layered_form_map(config: &Config, name: &str) -> Result<impl Iterator<Item = Ini>> {
+    let mut files = vec![];
+
+    #[cfg(feature = "embedded-mappings")]
+    for (_, data) in EMBEDDED_MAPPINGS.iter().filter(|(n, _)| *n == name) {
+        files.push(
+            Ini::load_from_str(data)
+                .with_context(|| format!("Error reading embedded {} mappings", name))?,
+        );
+    }
+
+    for dir in config.config_layers(name) {
+        files.extend(iter_form_map(dir)?);
+    }
+
+    Ok(files.into_iter())
+}
+
+/// Gets the user's configuration directory for tesconvert
+#[cfg(windows)]
+fn user_config_dir() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(|path| PathBuf::from(path).join("tesconvert"))
+}
+
+/// Gets the user's configuration directory for tesconvert
+#[cfg(not(windows))]
+fn user_config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|path| PathBuf::from(path).join(".config")))
+        .map(|path| path.join("tesconvert"))
+}
+
+/// Gets the user's cache directory for tesconvert
+#[cfg(windows)]
+fn user_cache_dir() -> Option<PathBuf> {
+    std::env::var_os("LOCALAPPDATA").map(|path| PathBuf::from(path).join("tesconvert"))
+}
+
+/// Gets the user's cache directory for tesconvert
+#[cfg(not(windows))]
+fn user_cache_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|path| PathBuf::from(path).join(".cache")))
+        .map(|path| path.join("tesconvert"))
+}
+
+/// Name of the file plugin indexes are cached in, in the user's cache directory
+const INDEX_CACHE_FILE: &str = "index.cache";
+
 /// Configuration options for a conversion
 #[derive(Debug)]
 pub struct Config {
     /// The conversion command to execute
     pub command: Command,
     /// Path to the save file that the character is being taken from
-    pub source_path: String,
+    ///
+    /// If this is `None`, the most recent save will be used. When porting a plugin, this is the
+    /// path to the Morrowind plugin.
+    pub source_path: Option<String>,
     /// Path to the save file that the character is being added to
     pub target_path: String,
-    /// Path to the new save file that will be created
+    /// Path to the new save file that will be created, or the new plugin when porting a plugin
     pub output_path: String,
-    /// Path to the directory where our configuration files are stored
+    /// Path to the directory where our built-in configuration files are stored
     pub config_path: String,
+    /// Path to the user's configuration directory, if one could be determined
+    pub user_config_path: Option<String>,
+    /// Path to an additional configuration directory for this run only
+    pub override_config_path: Option<String>,
+    /// Path to the file plugin indexes are cached in between runs
+    ///
+    /// If this is `None`, indexes are only kept for the current run.
+    pub index_cache_path: Option<String>,
     /// Path to the Morrowind directory
     pub mw_path: Option<String>,
     /// Path to the Oblivion directory
     pub ob_path: Option<String>,
+    /// Paths to Morrowind data directories to use instead of the one in the Morrowind directory
+    ///
+    /// These are given from lowest to highest precedence.
+    pub mw_data: Vec<String>,
+    /// Paths to Oblivion data directories to use instead of the one in the Oblivion directory
+    ///
+    /// These are given from lowest to highest precedence.
+    pub ob_data: Vec<String>,
+    /// Path to the directory to list saves from
+    pub save_dir: Option<String>,
+    /// Changes to make when editing a save
+    pub save_edits: SaveEdits,
     /// Strategy to use when combining skills
     pub combine_strategy: CombineStrategy,
     /// MW:OB equipment durability ratio
     pub equipment_durability_ratio: f32,
+    /// What to do with names that can't be used in the target game as they are
+    pub name_policy: NamePolicy,
+    /// Path to write the conversion report to
+    ///
+    /// If this is `None`, the report is written to stderr.
+    pub report_path: Option<String>,
+    /// Format to write the conversion report in
+    pub report_format: ReportFormat,
+    /// Whether to load an Oblivion save even if some of the plugins it depends on aren't installed
+    pub allow_missing_plugins: bool,
+    /// Where to store the forms created for converted spells and items
+    pub form_target: FormTarget,
+    /// Name of the plugin to store created forms in when the target is a plugin
+    pub plugin_name: String,
+    /// Whether to store the player's Morrowind Mark location in the cosave
+    pub store_mark: bool,
+    /// What to do if the Morrowind player is a werewolf
+    pub werewolf_policy: WerewolfPolicy,
+    /// What to do with the effects of the Morrowind player's afflictions
+    pub affliction_policy: AfflictionPolicy,
+    /// Whether to unlock every converted effect for spellmaking, not just the effects of spells
+    pub unlock_all_effects: bool,
 }
 
 impl Config {
@@ -117,6 +348,43 @@ impl Config {
                     .value_name("PATH")
                     .help("Path to the Oblivion directory")
             )
+            .arg(
+                Arg::with_name("mw_data")
+                    .long("morrowind-data")
+                    .takes_value(true)
+                    .multiple_occurrences(true)
+                    .value_name("PATH")
+                    .help("Path to a Morrowind data directory")
+                    .long_help(
+                        "Path to a Morrowind data directory to load plugins from instead of the Data Files directory \
+                        in the Morrowind directory. May be given more than once, e.g. for the data paths of an OpenMW \
+                        install; when a plugin is in more than one data directory, the one given last is used."
+                    )
+            )
+            .arg(
+                Arg::with_name("ob_data")
+                    .long("oblivion-data")
+                    .takes_value(true)
+                    .multiple_occurrences(true)
+                    .value_name("PATH")
+                    .help("Path to an Oblivion data directory")
+                    .long_help(
+                        "Path to an Oblivion data directory to load plugins from instead of the Data directory in the \
+                        Oblivion directory. May be given more than once; when a plugin is in more than one data \
+                        directory, the one given last is used."
+                    )
+            )
+            .arg(
+                Arg::with_name("config_dir")
+                    .long("config-dir")
+                    .takes_value(true)
+                    .value_name("PATH")
+                    .help("Path to an additional configuration directory")
+                    .long_help(
+                        "Path to an additional configuration directory. Mappings in this directory override both the \
+                        built-in mappings and those in the user configuration directory."
+                    )
+            )
             .arg(
                 Arg::with_name("combine")
                     .short('c')
@@ -148,23 +416,360 @@ impl Config {
                         (coming from Oblivion) by this ratio to scale the values from one game to be reasonable in the other."
                     )
             )
+            .arg(
+                Arg::with_name("names")
+                    .long("names")
+                    .takes_value(true)
+                    .value_name("POLICY")
+                    .possible_values(&["fix", "strict"])
+                    .help("What to do with names that can't be used in the target game")
+                    .long_help(
+                        "Names may contain characters the target game can't display or be longer than it allows. \
+                        'fix', the default, replaces or removes those characters and truncates long names, listing \
+                        each change in a warning. 'strict' fails the conversion instead."
+                    )
+            )
+            .arg(
+                Arg::with_name("no_index_cache")
+                    .long("no-index-cache")
+                    .help("Don't cache plugin indexes between runs")
+                    .long_help(
+                        "Don't cache plugin indexes between runs. By default, an index of each plugin's records is \
+                        kept in the user's cache directory so that plugins that haven't changed load faster the \
+                        next time."
+                    )
+            )
+            .arg(
+                Arg::with_name("allow_missing_plugins")
+                    .long("allow-missing-plugins")
+                    .help("Convert into an Oblivion save even if some of its plugins aren't installed")
+                    .long_help(
+                        "Convert into an Oblivion save even if some of the plugins it depends on aren't installed. \
+                        Anything in the save that comes from a missing plugin is left as it is, and a warning is \
+                        given for each missing plugin."
+                    )
+            )
+            .arg(
+                Arg::with_name("report")
+                    .long("report")
+                    .takes_value(true)
+                    .value_name("PATH")
+                    .help("Path to write the conversion report to")
+                    .long_help(
+                        "Path to write the conversion report to. The report lists what was converted and what was \
+                        skipped, along with any warnings. If omitted, the report is written to the console."
+                    )
+            )
+            .arg(
+                Arg::with_name("report_format")
+                    .long("report-format")
+                    .takes_value(true)
+                    .value_name("FORMAT")
+                    .possible_values(&["text", "json", "html"])
+                    .help("Format of the conversion report")
+                    .long_help(
+                        "Format of the conversion report. 'text', the default, is meant to be read in the console. \
+                        'json' is meant to be read by other tools. 'html' is a web page with summary tables."
+                    )
+            )
             .subcommand(
                 SubCommand::with_name("mw2ob")
                     .about("Converts a Morrowind character to Oblivion")
                     .arg(
-                        Arg::with_name("SOURCE_PATH")
+                        Arg::with_name("TARGET_PATH")
+                            .required(true)
+                            .help("Path to the input Oblivion save file")
+                    )
+                    .arg(
+                        Arg::with_name("OUTPUT_PATH")
                             .required(true)
+                            .help("Path to the output Oblivion save file")
+                    )
+                    .arg(
+                        Arg::with_name("SOURCE_PATH")
                             .help("Path to the Morrowind save file")
+                            .long_help(
+                                "Path to the Morrowind save file. If omitted, the most recent Morrowind save is used."
+                            )
                     )
                     .arg(
-                        Arg::with_name("TARGET_PATH")
+                        Arg::with_name("forms")
+                            .long("forms")
+                            .takes_value(true)
+                            .value_name("TARGET")
+                            .possible_values(&["plugin", "save"])
+                            .help("Where to store the forms created for converted spells and items")
+                            .long_help(
+                                "Where to store the forms created for converted spells and items. 'plugin', the \
+                                default, adds them to a plugin that the save is made to depend on (see --plugin). \
+                                'save' stores them in the save itself, like spells and potions made in game, so no \
+                                plugin is needed."
+                            )
+                    )
+                    .arg(
+                        Arg::with_name("plugin")
+                            .long("plugin")
+                            .takes_value(true)
+                            .value_name("NAME")
+                            .help("Name of the plugin to store created forms in")
+                            .long_help(
+                                "Name of the plugin in the Oblivion Data directory to store created forms in. If the \
+                                save already uses a plugin by this name, the forms are added to it; its masters must \
+                                be exactly the plugins loaded before it. Otherwise, the plugin is created, replacing \
+                                any existing file. Defaults to mw2ob.esp."
+                            )
+                    )
+                    .arg(
+                        Arg::with_name("store_mark")
+                            .long("store-mark")
+                            .help("Store the player's Mark location in the cosave")
+                            .long_help(
+                                "Store the location the player last cast Mark at in the cosave, where an OBSE mod \
+                                can make use of it. Oblivion has no equivalent of Mark, so otherwise the location is \
+                                only listed in the conversion report."
+                            )
+                    )
+                    .arg(
+                        Arg::with_name("werewolf")
+                            .long("werewolf")
+                            .takes_value(true)
+                            .value_name("POLICY")
+                            .possible_values(&["refuse", "cure"])
+                            .help("What to do if the Morrowind player is a werewolf")
+                            .long_help(
+                                "What to do if the Morrowind player is a werewolf. Oblivion has no werewolves, so \
+                                'refuse', the default, fails the conversion. 'cure' converts the player as if they'd \
+                                been cured of lycanthropy, leaving out their werewolf spells and effects."
+                            )
+                    )
+                    .arg(
+                        Arg::with_name("afflictions")
+                            .long("afflictions")
+                            .takes_value(true)
+                            .value_name("POLICY")
+                            .possible_values(&["ability", "bake"])
+                            .help("How to keep the effects of blights and curses like corprus")
+                            .long_help(
+                                "How to keep the effects of blights and curses like corprus, which Oblivion \
+                                doesn't have. 'ability', the default, gives the player an Oblivion ability with the \
+                                same effects for each one. 'bake' makes the effects part of the player's base stats \
+                                instead. Either way, the conversion report lists what was done with each one."
+                            )
+                    )
+                    .arg(
+                        Arg::with_name("unlock_all_effects")
+                            .long("unlock-all-effects")
+                            .help("Unlock the effects of all converted spells for spellmaking")
+                            .long_help(
+                                "Unlock the effects of all of the player's converted spells, powers, and abilities \
+                                for spellmaking. Like Oblivion, the conversion otherwise only unlocks the effects of \
+                                regular spells. Effects that can't be used to make spells are never unlocked."
+                            )
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("port")
+                    .about("Ports the content of a Morrowind plugin to an Oblivion plugin")
+                    .long_about(
+                        "Ports the content of a Morrowind plugin to an Oblivion plugin. The spells, potions, and \
+                        classes the plugin defines are converted the same way they would be for a character; \
+                        no saves are involved."
+                    )
+                    .arg(
+                        Arg::with_name("PLUGIN_PATH")
                             .required(true)
-                            .help("Path to the input Oblivion save file")
+                            .help("Path to the Morrowind plugin")
                     )
                     .arg(
                         Arg::with_name("OUTPUT_PATH")
                             .required(true)
-                            .help("Path to the output Oblivion save file")
+                            .help("Path to the Oblivion plugin to create")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("try-spell")
+                    .about("Converts a single Morrowind spell and prints the Oblivion spell")
+                    .long_about(
+                        "Converts a single Morrowind spell the same way it would be converted for a character \
+                        and prints the Oblivion spell, without changing any files. This is useful for checking \
+                        the effect of mapping files and options like --combine."
+                    )
+                    .arg(
+                        Arg::with_name("ID")
+                            .required(true)
+                            .help("ID of the Morrowind spell")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("try-item")
+                    .about("Converts a single Morrowind item and prints the Oblivion item")
+                    .long_about(
+                        "Converts a single Morrowind item and prints the Oblivion item, without changing any \
+                        files. If the item is mapped to an Oblivion item, the mapped item is printed. Otherwise, \
+                        only potions can be converted, since other items need forms for their models and \
+                        enchantments that only exist during a character conversion."
+                    )
+                    .arg(
+                        Arg::with_name("ID")
+                            .required(true)
+                            .help("ID of the Morrowind item")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("saves")
+                    .about("Lists saves and prompts to choose one")
+                    .arg(
+                        Arg::with_name("oblivion")
+                            .long("oblivion")
+                            .help("List Oblivion saves instead of Morrowind saves")
+                    )
+                    .arg(
+                        Arg::with_name("SAVE_DIR")
+                            .help("Directory containing the saves")
+                            .long_help(
+                                "Directory containing the saves. If omitted, the Saves directory in the Morrowind \
+                                directory or the Oblivion saves directory in My Games is used."
+                            )
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("edit")
+                    .about("Edits the player in a save")
+                    .arg(
+                        Arg::with_name("save")
+                            .long("save")
+                            .required(true)
+                            .takes_value(true)
+                            .value_name("PATH")
+                            .help("Path to the save file to edit")
+                    )
+                    .arg(
+                        Arg::with_name("output")
+                            .long("output")
+                            .takes_value(true)
+                            .value_name("PATH")
+                            .help("Path to write the edited save to")
+                            .long_help(
+                                "Path to write the edited save to. If omitted, the save is edited in place."
+                            )
+                    )
+                    .arg(
+                        Arg::with_name("oblivion")
+                            .long("oblivion")
+                            .help("Edit an Oblivion save instead of a Morrowind save")
+                    )
+                    .arg(
+                        Arg::with_name("set_name")
+                            .long("set-name")
+                            .takes_value(true)
+                            .value_name("NAME")
+                            .help("Renames the player")
+                    )
+                    .arg(
+                        Arg::with_name("set_level")
+                            .long("set-level")
+                            .takes_value(true)
+                            .value_name("LEVEL")
+                            .help("Sets the player's level")
+                    )
+                    .arg(
+                        Arg::with_name("add_gold")
+                            .long("add-gold")
+                            .takes_value(true)
+                            .allow_hyphen_values(true)
+                            .value_name("AMOUNT")
+                            .help("Gives the player gold, or takes it away if negative")
+                    )
+                    .arg(
+                        Arg::with_name("add_spell")
+                            .long("add-spell")
+                            .takes_value(true)
+                            .multiple_occurrences(true)
+                            .value_name("ID")
+                            .help("Adds a spell to the player's spell list")
+                            .long_help(
+                                "Adds a spell to the player's spell list. For Morrowind, this is the spell's ID; for \
+                                Oblivion, it's the spell's form ID in hexadecimal."
+                            )
+                    )
+                    .arg(
+                        Arg::with_name("remove_spell")
+                            .long("remove-spell")
+                            .takes_value(true)
+                            .multiple_occurrences(true)
+                            .value_name("ID")
+                            .help("Removes a spell from the player's spell list")
+                    )
+                    .arg(
+                        Arg::with_name("set_attribute")
+                            .long("set-attribute")
+                            .takes_value(true)
+                            .multiple_occurrences(true)
+                            .value_name("ATTRIBUTE=VALUE")
+                            .help("Sets one of the player's base attributes, e.g. luck=60")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("archive")
+                    .about("Lists, extracts, or creates BSA archives")
+                    .long_about(
+                        "Lists, extracts, or creates BSA archives. Morrowind and Oblivion archives are both \
+                        supported, and when reading an archive, which game it's from is detected automatically."
+                    )
+                    .subcommand_required(true)
+                    .subcommand(
+                        SubCommand::with_name("list")
+                            .about("Lists the files in an archive")
+                            .arg(
+                                Arg::with_name("ARCHIVE")
+                                    .required(true)
+                                    .help("Path to the archive")
+                            )
+                    )
+                    .subcommand(
+                        SubCommand::with_name("extract")
+                            .about("Extracts all the files in an archive")
+                            .arg(
+                                Arg::with_name("ARCHIVE")
+                                    .required(true)
+                                    .help("Path to the archive")
+                            )
+                            .arg(
+                                Arg::with_name("OUTPUT_DIR")
+                                    .required(true)
+                                    .help("Directory to extract the files to")
+                            )
+                    )
+                    .subcommand(
+                        SubCommand::with_name("create")
+                            .about("Creates an archive from the files in a directory")
+                            .long_about(
+                                "Creates an archive from the files in a directory. Each file's path in the \
+                                archive is its path relative to the directory, so the directory should be laid \
+                                out like a data directory, with folders like meshes and textures at the top. Any \
+                                existing archive at the path is replaced."
+                            )
+                            .arg(
+                                Arg::with_name("SOURCE_DIR")
+                                    .required(true)
+                                    .help("Directory containing the files to archive")
+                            )
+                            .arg(
+                                Arg::with_name("ARCHIVE")
+                                    .required(true)
+                                    .help("Path to the archive to create")
+                            )
+                            .arg(
+                                Arg::with_name("oblivion")
+                                    .long("oblivion")
+                                    .help("Create an Oblivion archive instead of a Morrowind archive")
+                            )
+                            .arg(
+                                Arg::with_name("compress")
+                                    .long("compress")
+                                    .requires("oblivion")
+                                    .help("Compress the archive's files (Oblivion only)")
+                            )
                     )
             );
 
@@ -178,17 +783,161 @@ impl Config {
 
         let (sub_command, sub_matches) = matches.subcommand().unwrap();
 
+        // clap panics when asked for an argument that the subcommand doesn't define, so each
+        // subcommand only reads its own arguments, and everything else keeps its default
+        let mut source_path = None;
+        let mut target_path = String::new();
+        let mut output_path = String::new();
+        let mut save_dir = None;
+        let mut save_edits = SaveEdits::default();
+        let mut form_target = FormTarget::Plugin;
+        let mut plugin_name = String::from(COMPANION_MOD_NAME);
+        let mut store_mark = false;
+        let mut werewolf_policy = WerewolfPolicy::Refuse;
+        let mut affliction_policy = AfflictionPolicy::Ability;
+        let mut unlock_all_effects = false;
+
+        let command = match sub_command {
+            "mw2ob" => {
+                // the source path may be omitted, in which case the latest save is used
+                source_path = sub_matches.value_of("SOURCE_PATH").map(String::from);
+                target_path = String::from(sub_matches.value_of("TARGET_PATH").unwrap());
+                output_path = String::from(sub_matches.value_of("OUTPUT_PATH").unwrap());
+
+                form_target = match sub_matches.value_of("forms").unwrap_or("plugin") {
+                    "plugin" => FormTarget::Plugin,
+                    "save" => FormTarget::Save,
+                    _ => unreachable!(),
+                };
+                if let Some(plugin) = sub_matches.value_of("plugin") {
+                    plugin_name = String::from(plugin);
+                }
+                store_mark = sub_matches.is_present("store_mark");
+                werewolf_policy = match sub_matches.value_of("werewolf").unwrap_or("refuse") {
+                    "refuse" => WerewolfPolicy::Refuse,
+                    "cure" => WerewolfPolicy::Cure,
+                    _ => unreachable!(),
+                };
+                affliction_policy = match sub_matches.value_of("afflictions").unwrap_or("ability") {
+                    "ability" => AfflictionPolicy::Ability,
+                    "bake" => AfflictionPolicy::Bake,
+                    _ => unreachable!(),
+                };
+                unlock_all_effects = sub_matches.is_present("unlock_all_effects");
+
+                Command::MorrowindToOblivion
+            }
+            "port" => {
+                // porting a plugin doesn't involve a target save
+                source_path = sub_matches.value_of("PLUGIN_PATH").map(String::from);
+                output_path = String::from(sub_matches.value_of("OUTPUT_PATH").unwrap());
+                Command::PortPlugin
+            }
+            "saves" => {
+                save_dir = sub_matches.value_of("SAVE_DIR").map(String::from);
+                Command::ChooseSave(if sub_matches.is_present("oblivion") {
+                    Game::Oblivion
+                } else {
+                    Game::Morrowind
+                })
+            }
+            "edit" => {
+                // the edit command reads and writes a single save
+                let save = sub_matches.value_of("save").unwrap();
+                target_path = String::from(save);
+                output_path = String::from(sub_matches.value_of("output").unwrap_or(save));
+
+                save_edits = SaveEdits {
+                    name: sub_matches.value_of("set_name").map(String::from),
+                    level: sub_matches
+                        .value_of("set_level")
+                        .map(u16::from_str)
+                        .transpose()
+                        .context("Invalid level")?,
+                    gold: sub_matches
+                        .value_of("add_gold")
+                        .map(i32::from_str)
+                        .transpose()
+                        .context("Invalid amount of gold")?,
+                    add_spells: sub_matches
+                        .values_of("add_spell")
+                        .map(|v| v.map(String::from).collect())
+                        .unwrap_or_default(),
+                    remove_spells: sub_matches
+                        .values_of("remove_spell")
+                        .map(|v| v.map(String::from).collect())
+                        .unwrap_or_default(),
+                    attributes: sub_matches
+                        .values_of("set_attribute")
+                        .map(|v| v.map(parse_attribute).collect::<Result<Vec<_>>>())
+                        .transpose()?
+                        .unwrap_or_default(),
+                };
+
+                Command::EditSave(if sub_matches.is_present("oblivion") {
+                    Game::Oblivion
+                } else {
+                    Game::Morrowind
+                })
+            }
+            "try-spell" => Command::TrySpell(String::from(sub_matches.value_of("ID").unwrap())),
+            "try-item" => Command::TryItem(String::from(sub_matches.value_of("ID").unwrap())),
+            "archive" => {
+                let (operation, archive_matches) = sub_matches.subcommand().unwrap();
+                let archive = String::from(archive_matches.value_of("ARCHIVE").unwrap());
+                Command::Archive(match operation {
+                    "list" => ArchiveCommand::List(archive),
+                    "extract" => ArchiveCommand::Extract {
+                        archive,
+                        output_dir: String::from(archive_matches.value_of("OUTPUT_DIR").unwrap()),
+                    },
+                    "create" => ArchiveCommand::Create {
+                        game: if archive_matches.is_present("oblivion") {
+                            Game::Oblivion
+                        } else {
+                            Game::Morrowind
+                        },
+                        source_dir: String::from(archive_matches.value_of("SOURCE_DIR").unwrap()),
+                        archive,
+                        compress: archive_matches.is_present("compress"),
+                    },
+                    _ => unreachable!(),
+                })
+            }
+            _ => unreachable!(),
+        };
+
         Ok(Config {
-            command: match sub_command {
-                "mw2ob" => Command::MorrowindToOblivion,
-                _ => unreachable!(),
-            },
-            source_path: String::from(sub_matches.value_of("SOURCE_PATH").unwrap()),
-            target_path: String::from(sub_matches.value_of("TARGET_PATH").unwrap()),
-            output_path: String::from(sub_matches.value_of("OUTPUT_PATH").unwrap()),
+            command,
+            source_path,
+            target_path,
+            output_path,
             config_path: String::from("."),
+            user_config_path: user_config_dir()
+                .and_then(|path| path.into_os_string().into_string().ok()),
+            override_config_path: matches.value_of("config_dir").map(String::from),
+            index_cache_path: if matches.is_present("no_index_cache") {
+                None
+            } else {
+                user_cache_dir().and_then(|path| {
+                    path.join(INDEX_CACHE_FILE)
+                        .into_os_string()
+                        .into_string()
+                        .ok()
+                })
+            },
             mw_path: matches.value_of("mw_path").map(String::from),
             ob_path: matches.value_of("ob_path").map(String::from),
+            mw_data: matches
+                .values_of("mw_data")
+                .map(|v| v.map(String::from).collect())
+                .unwrap_or_default(),
+            ob_data: matches
+                .values_of("ob_data")
+                .map(|v| v.map(String::from).collect())
+                .unwrap_or_default(),
+            save_dir,
+            save_edits,
             combine_strategy: match matches.value_of("combine").unwrap_or("highest") {
                 "highest" => CombineStrategy::Highest,
                 "average" => CombineStrategy::Average,
@@ -199,9 +948,97 @@ impl Config {
                 .value_of("durability_ratio")
                 .map(|v| f32::from_str(v))
                 .unwrap_or(Ok(5.))?,
+            name_policy: match matches.value_of("names").unwrap_or("fix") {
+                "fix" => NamePolicy::Fix,
+                "strict" => NamePolicy::Strict,
+                _ => unreachable!(),
+            },
+            report_path: matches.value_of("report").map(String::from),
+            report_format: match matches.value_of("report_format").unwrap_or("text") {
+                "text" => ReportFormat::Text,
+                "json" => ReportFormat::Json,
+                "html" => ReportFormat::Html,
+                _ => unreachable!(),
+            },
+            allow_missing_plugins: matches.is_present("allow_missing_plugins"),
+            form_target,
+            plugin_name,
+            store_mark,
+            werewolf_policy,
+            affliction_policy,
+            unlock_all_effects,
         })
     }
 
+    /// Gets the directories for a type of configuration from each layer, lowest priority first
+    ///
+    /// The per-run directory is always included so that a missing directory is reported as an
+    /// error, but the user directory is only included if it exists. The built-in directory is
+    /// required unless the `embedded-mappings` feature is enabled, in which case it's optional
+    /// like the user directory.
+    pub fn config_layers(&self, name: &str) -> Vec<PathBuf> {
+        let mut layers = vec![];
+
+        let builtin_dir = Path::new(&self.config_path).join(name);
+        if cfg!(not(feature = "embedded-mappings")) || builtin_dir.is_dir() {
+            layers.push(builtin_dir);
+        }
+
+        if let Some(ref user_path) = self.user_config_path {
+            let user_dir = Path::new(user_path).join(name);
+            if user_dir.is_dir() {
+                layers.push(user_dir);
+            }
+        }
+
+        if let Some(ref override_path) = self.override_config_path {
+            layers.push(Path::new(override_path).join(name));
+        }
+
+        layers
+    }
+
+    /// Opens the plugin index cache
+    ///
+    /// If caching is turned off, or the cache can't be opened, the cache is only kept in memory.
+    /// In the latter case, a warning saying why is returned along with the cache.
+    pub fn open_index_cache(&self) -> (IndexCache, Option<String>) {
+        match self.index_cache_path {
+            Some(ref path) => match IndexCache::open(path) {
+                Ok(cache) => (cache, None),
+                Err(e) => (
+                    IndexCache::new(),
+                    Some(format!("Could not open the index cache {}: {}", path, e)),
+                ),
+            },
+            None => (IndexCache::new(), None),
+        }
+    }
+
+    /// Writes the plugin index cache back to disk, if it changed
+    ///
+    /// The cache only saves time, so failing to write it isn't an error; instead, a warning saying
+    /// why is returned.
+    pub fn save_index_cache(&self, cache: &IndexCache) -> Option<String> {
+        if let Some(dir) = self
+            .index_cache_path
+            .as_ref()
+            .and_then(|p| Path::new(p).parent())
+        {
+            if let Err(e) = fs::create_dir_all(dir) {
+                return Some(format!(
+                    "Could not create the cache directory {:?}: {}",
+                    dir, e
+                ));
+            }
+        }
+
+        cache
+            .save()
+            .err()
+            .map(|e| format!("Could not save the index cache: {}", e))
+    }
+
     /// Gets configuration from the command line
     ///
     /// # Panics
@@ -233,20 +1070,387 @@ mod tests {
                 "--combine",
                 "lowest",
                 "mw2ob",
-                "source",
                 "target",
                 "output",
+                "source",
             ]),
             true,
         )
         .unwrap();
         assert_eq!(config.command, Command::MorrowindToOblivion);
         assert_eq!(config.combine_strategy, CombineStrategy::Lowest);
-        assert_eq!(config.source_path, "source");
+        assert_eq!(config.name_policy, NamePolicy::Fix);
+        assert_eq!(config.report_path, None);
+        assert_eq!(config.report_format, ReportFormat::Text);
+        assert!(!config.allow_missing_plugins);
+        assert!(config.mw_data.is_empty());
+        assert!(config.ob_data.is_empty());
+        assert_eq!(config.source_path.as_deref(), Some("source"));
+        assert_eq!(config.target_path, "target");
+        assert_eq!(config.output_path, "output");
+    }
+
+    #[test]
+    fn test_index_cache_args() {
+        let config = Config::get(
+            Some(vec![
+                "tesconvert",
+                "--no-index-cache",
+                "mw2ob",
+                "target",
+                "output",
+            ]),
+            true,
+        )
+        .unwrap();
+        assert_eq!(config.index_cache_path, None);
+        let (cache, warning) = config.open_index_cache();
+        assert_eq!(warning, None);
+        assert_eq!(config.save_index_cache(&cache), None);
+
+        let config =
+            Config::get(Some(vec!["tesconvert", "mw2ob", "target", "output"]), true).unwrap();
+        // whether there's a cache directory depends on the environment, but if there is one, the
+        // cache has to be used
+        let expected_path = user_cache_dir().map(|path| path.join(INDEX_CACHE_FILE));
+        assert_eq!(config.index_cache_path.map(PathBuf::from), expected_path);
+    }
+
+    #[test]
+    fn test_name_policy_args() {
+        let config = Config::get(
+            Some(vec![
+                "tesconvert",
+                "--names",
+                "strict",
+                "mw2ob",
+                "target",
+                "output",
+            ]),
+            true,
+        )
+        .unwrap();
+        assert_eq!(config.name_policy, NamePolicy::Strict);
+
+        assert!(Config::get(
+            Some(vec![
+                "tesconvert",
+                "--names",
+                "bogus",
+                "mw2ob",
+                "target",
+                "output"
+            ]),
+            true
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_report_args() {
+        let config = Config::get(
+            Some(vec![
+                "tesconvert",
+                "--report",
+                "report.html",
+                "--report-format",
+                "html",
+                "--allow-missing-plugins",
+                "port",
+                "plugin.esp",
+                "output.esp",
+            ]),
+            true,
+        )
+        .unwrap();
+        assert_eq!(config.report_path.as_deref(), Some("report.html"));
+        assert_eq!(config.report_format, ReportFormat::Html);
+        assert!(config.allow_missing_plugins);
+    }
+
+    #[test]
+    fn test_data_dir_args() {
+        let config = Config::get(
+            Some(vec![
+                "tesconvert",
+                "--morrowind-data",
+                "vanilla",
+                "--morrowind-data",
+                "mods",
+                "--oblivion-data",
+                "ob_data",
+                "mw2ob",
+                "target",
+                "output",
+            ]),
+            true,
+        )
+        .unwrap();
+        assert_eq!(config.mw_data, ["vanilla", "mods"]);
+        assert_eq!(config.ob_data, ["ob_data"]);
+        assert_eq!(config.mw_path, None);
+    }
+
+    #[test]
+    fn test_form_target_args() {
+        let config =
+            Config::get(Some(vec!["tesconvert", "mw2ob", "target", "output"]), true).unwrap();
+        assert_eq!(config.form_target, FormTarget::Plugin);
+        assert_eq!(config.plugin_name, COMPANION_MOD_NAME);
+        assert!(!config.store_mark);
+        assert_eq!(config.werewolf_policy, WerewolfPolicy::Refuse);
+        assert_eq!(config.affliction_policy, AfflictionPolicy::Ability);
+        assert!(!config.unlock_all_effects);
+
+        let config = Config::get(
+            Some(vec![
+                "tesconvert",
+                "mw2ob",
+                "--forms",
+                "save",
+                "--plugin",
+                "MW Conversion.esp",
+                "--store-mark",
+                "--werewolf",
+                "cure",
+                "--afflictions",
+                "bake",
+                "--unlock-all-effects",
+                "target",
+                "output",
+            ]),
+            true,
+        )
+        .unwrap();
+        assert_eq!(config.form_target, FormTarget::Save);
+        assert_eq!(config.plugin_name, "MW Conversion.esp");
+        assert!(config.store_mark);
+        assert_eq!(config.werewolf_policy, WerewolfPolicy::Cure);
+        assert_eq!(config.affliction_policy, AfflictionPolicy::Bake);
+        assert!(config.unlock_all_effects);
+    }
+
+    #[test]
+    fn test_latest_save_args() {
+        let config =
+            Config::get(Some(vec!["tesconvert", "mw2ob", "target", "output"]), true).unwrap();
+        assert_eq!(config.source_path, None);
         assert_eq!(config.target_path, "target");
         assert_eq!(config.output_path, "output");
     }
 
+    #[test]
+    fn test_port_args() {
+        let config = Config::get(
+            Some(vec!["tesconvert", "port", "mymod.esp", "mymod_ob.esp"]),
+            true,
+        )
+        .unwrap();
+        assert_eq!(config.command, Command::PortPlugin);
+        assert_eq!(config.source_path.as_deref(), Some("mymod.esp"));
+        assert_eq!(config.output_path, "mymod_ob.esp");
+
+        assert!(Config::get(Some(vec!["tesconvert", "port", "mymod.esp"]), true).is_err());
+    }
+
+    #[test]
+    fn test_try_args() {
+        let config = Config::get(
+            Some(vec![
+                "tesconvert",
+                "--combine",
+                "average",
+                "try-spell",
+                "fireball",
+            ]),
+            true,
+        )
+        .unwrap();
+        assert_eq!(config.command, Command::TrySpell(String::from("fireball")));
+        assert_eq!(config.combine_strategy, CombineStrategy::Average);
+
+        let config = Config::get(
+            Some(vec!["tesconvert", "try-item", "p_restore_health_s"]),
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            config.command,
+            Command::TryItem(String::from("p_restore_health_s"))
+        );
+
+        assert!(Config::get(Some(vec!["tesconvert", "try-item"]), true).is_err());
+    }
+
+    #[test]
+    fn test_archive_args() {
+        let config = Config::get(
+            Some(vec!["tesconvert", "archive", "list", "Morrowind.bsa"]),
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            config.command,
+            Command::Archive(ArchiveCommand::List(String::from("Morrowind.bsa")))
+        );
+
+        let config = Config::get(
+            Some(vec![
+                "tesconvert",
+                "archive",
+                "extract",
+                "Oblivion - Meshes.bsa",
+                "out",
+            ]),
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            config.command,
+            Command::Archive(ArchiveCommand::Extract {
+                archive: String::from("Oblivion - Meshes.bsa"),
+                output_dir: String::from("out"),
+            })
+        );
+
+        let config = Config::get(
+            Some(vec![
+                "tesconvert",
+                "archive",
+                "create",
+                "--oblivion",
+                "--compress",
+                "Data",
+                "mod.bsa",
+            ]),
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            config.command,
+            Command::Archive(ArchiveCommand::Create {
+                game: Game::Oblivion,
+                source_dir: String::from("Data"),
+                archive: String::from("mod.bsa"),
+                compress: true,
+            })
+        );
+
+        // only Oblivion archives can be compressed
+        assert!(Config::get(
+            Some(vec![
+                "tesconvert",
+                "archive",
+                "create",
+                "--compress",
+                "Data",
+                "mod.bsa"
+            ]),
+            true
+        )
+        .is_err());
+        assert!(Config::get(Some(vec!["tesconvert", "archive"]), true).is_err());
+    }
+
+    #[test]
+    fn test_saves_args() {
+        let config =
+            Config::get(Some(vec!["tesconvert", "saves", "--oblivion", "dir"]), true).unwrap();
+        assert_eq!(config.command, Command::ChooseSave(Game::Oblivion));
+        assert_eq!(config.save_dir.as_deref(), Some("dir"));
+
+        let config = Config::get(Some(vec!["tesconvert", "saves"]), true).unwrap();
+        assert_eq!(config.command, Command::ChooseSave(Game::Morrowind));
+        assert_eq!(config.save_dir, None);
+    }
+
+    #[test]
+    fn test_edit_args() {
+        let config = Config::get(
+            Some(vec![
+                "tesconvert",
+                "edit",
+                "--save",
+                "foo.ess",
+                "--oblivion",
+                "--set-name",
+                "Bar",
+                "--add-gold",
+                "-100",
+                "--add-spell",
+                "a97b5",
+                "--add-spell",
+                "0x14",
+                "--set-attribute",
+                "Luck=60",
+            ]),
+            true,
+        )
+        .unwrap();
+        assert_eq!(config.command, Command::EditSave(Game::Oblivion));
+        assert_eq!(config.target_path, "foo.ess");
+        assert_eq!(config.output_path, "foo.ess");
+        assert_eq!(config.save_edits.name.as_deref(), Some("Bar"));
+        assert_eq!(config.save_edits.gold, Some(-100));
+        assert_eq!(config.save_edits.add_spells, vec!["a97b5", "0x14"]);
+        assert_eq!(config.save_edits.attributes, vec![(Attribute::Luck, 60)]);
+
+        let config = Config::get(
+            Some(vec![
+                "tesconvert",
+                "edit",
+                "--save",
+                "foo.ess",
+                "--output",
+                "bar.ess",
+                "--set-level",
+                "5",
+            ]),
+            true,
+        )
+        .unwrap();
+        assert_eq!(config.command, Command::EditSave(Game::Morrowind));
+        assert_eq!(config.output_path, "bar.ess");
+        assert_eq!(config.save_edits.level, Some(5));
+
+        assert!(Config::get(
+            Some(vec![
+                "tesconvert",
+                "edit",
+                "--save",
+                "foo.ess",
+                "--set-attribute",
+                "charm=5"
+            ]),
+            true
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_config_layers() {
+        let mut config = Config::get(
+            Some(vec![
+                "tesconvert",
+                "--config-dir",
+                "custom",
+                "mw2ob",
+                "target",
+                "output",
+            ]),
+            true,
+        )
+        .unwrap();
+        config.user_config_path = None;
+        assert_eq!(
+            config.config_layers("mwob"),
+            vec![
+                Path::new(".").join("mwob"),
+                Path::new("custom").join("mwob")
+            ]
+        );
+    }
+
     #[test]
     fn test_empty_args() {
         assert!(Config::get(Some(vec!["tesconvert"]), true).is_err());
@@ -271,7 +1475,24 @@ mod tests {
 
     #[test]
     fn test_insufficient_args() {
-        assert!(Config::get(Some(vec!["tesconvert", "mw2ob", "source"]), true).is_err());
+        assert!(Config::get(Some(vec!["tesconvert", "mw2ob", "target"]), true).is_err());
+        assert!(Config::get(Some(vec!["tesconvert", "mw2ob"]), true).is_err());
+    }
+
+    #[test]
+    fn test_too_many_args() {
+        assert!(Config::get(
+            Some(vec![
+                "tesconvert",
+                "mw2ob",
+                "source",
+                "target",
+                "output",
+                "extra"
+            ]),
+            true
+        )
+        .is_err());
     }
 
     #[test]
@@ -0,0 +1,120 @@
+use tesutil::tes3::{SaveEditor as Tes3SaveEditor, Tes3Plugin};
+use tesutil::tes4::{FormId, Save, SaveEditor as Tes4SaveEditor};
+use tesutil::Plugin;
+
+use crate::config::*;
+
+use anyhow::{Context, Result};
+
+/// Parses an Oblivion form ID given in hexadecimal, with or without a leading 0x
+fn parse_form_id(value: &str) -> Result<FormId> {
+    let digits = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    u32::from_str_radix(digits, 16)
+        .map(FormId)
+        .with_context(|| format!("Invalid form ID {}", value))
+}
+
+/// Applies edits to a Morrowind save
+fn edit_morrowind(edits: &SaveEdits, save: &mut Tes3Plugin) -> Result<()> {
+    let mut editor = Tes3SaveEditor::new(save)?;
+    if let Some(ref name) = edits.name {
+        editor.set_player_name(name)?;
+    }
+
+    if let Some(level) = edits.level {
+        editor.set_level(level)?;
+    }
+
+    for spell in &edits.remove_spells {
+        if !editor.remove_spell(spell) {
+            eprintln!("Warning: player doesn't know spell {}", spell);
+        }
+    }
+
+    for spell in &edits.add_spells {
+        if !editor.add_spell(spell)? {
+            eprintln!("Warning: player already knows spell {}", spell);
+        }
+    }
+
+    if let Some(gold) = edits.gold {
+        editor.add_gold(gold)?;
+    }
+
+    for &(attribute, value) in &edits.attributes {
+        editor.set_attribute(attribute, value as f32)?;
+    }
+
+    editor.commit()?;
+    Ok(())
+}
+
+/// Applies edits to an Oblivion save
+fn edit_oblivion(edits: &SaveEdits, save: &mut Save) -> Result<()> {
+    let mut editor = Tes4SaveEditor::new(save)?;
+    if let Some(ref name) = edits.name {
+        editor.set_player_name(name)?;
+    }
+
+    if let Some(level) = edits.level {
+        editor.set_level(level)?;
+    }
+
+    for spell in &edits.remove_spells {
+        if !editor.remove_spell(parse_form_id(spell)?) {
+            eprintln!("Warning: player doesn't know spell {}", spell);
+        }
+    }
+
+    for spell in &edits.add_spells {
+        if !editor.add_spell(parse_form_id(spell)?) {
+            eprintln!("Warning: player already knows spell {}", spell);
+        }
+    }
+
+    if let Some(gold) = edits.gold {
+        editor.add_gold(gold);
+    }
+
+    for &(attribute, value) in &edits.attributes {
+        editor.set_attribute(attribute, value)?;
+    }
+
+    editor.commit()?;
+    Ok(())
+}
+
+/// Edits the player in a save according to the configuration
+pub fn edit_save(config: &Config, game: Game) -> Result<()> {
+    let path = &config.target_path;
+    match game {
+        Game::Morrowind => {
+            let mut save = Tes3Plugin::load_file(path)
+                .with_context(|| format!("Failed to load Morrowind save {}", path))?;
+            edit_morrowind(&config.save_edits, &mut save)?;
+            save.save_file(&config.output_path)
+        }
+        Game::Oblivion => {
+            let mut save = Save::load_file(path)
+                .with_context(|| format!("Failed to load Oblivion save {}", path))?;
+            edit_oblivion(&config.save_edits, &mut save)?;
+            save.save_file(&config.output_path)
+        }
+    }
+    .with_context(|| format!("Failed to write save {}", config.output_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn form_id() {
+        assert_eq!(parse_form_id("0x000a97b5").unwrap(), FormId(0xa97b5));
+        assert_eq!(parse_form_id("A97B5").unwrap(), FormId(0xa97b5));
+        assert!(parse_form_id("fireball").is_err());
+    }
+}
@@ -1,19 +1,69 @@
 use anyhow::*;
 
+mod archive;
+pub use archive::*;
+
+mod condition;
+pub use condition::*;
+
 mod config;
 pub use config::*;
 
+mod edit;
+pub use edit::*;
+
 mod morrowind;
+
+mod names;
+pub use names::*;
+
+mod numeric;
 mod oblivion;
+mod port;
+
+mod report;
+pub use report::*;
+
+mod saves;
+pub use saves::*;
+
+mod trial;
 
 use morrowind::*;
+use port::*;
+use trial::*;
 
 pub fn convert(config: Config) -> Result<()> {
     match config.command {
         Command::MorrowindToOblivion => {
+            let (report_path, report_format) = (config.report_path.clone(), config.report_format);
             let mw2ob = MorrowindToOblivion::load(config)?;
-            mw2ob.convert()
+            let result = mw2ob.convert();
+            mw2ob.report().save(report_path.as_deref(), report_format)?;
+            result
+        }
+        Command::ChooseSave(game) => {
+            let path = choose_save(&config, game)?;
+            println!("{}", path.display());
+            Ok(())
+        }
+        Command::EditSave(game) => edit_save(&config, game),
+        Command::PortPlugin => {
+            let (report_path, report_format) = (config.report_path.clone(), config.report_format);
+            let port = MorrowindPluginToOblivion::load(config)?;
+            let result = port.convert();
+            port.report().save(report_path.as_deref(), report_format)?;
+            result
+        }
+        Command::TrySpell(ref id) => {
+            let id = id.clone();
+            ConversionTrial::load(config)?.try_spell(&id)
+        }
+        Command::TryItem(ref id) => {
+            let id = id.clone();
+            ConversionTrial::load(config)?.try_item(&id)
         }
+        Command::Archive(ref command) => run_archive_command(command),
         _ => unimplemented!(),
     }
 }
@@ -1,8 +1,10 @@
 use std::cell::RefCell;
 use std::cmp;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Display;
 use std::iter::repeat;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::thread;
 
 use tesutil::tes3::Magic as Tes3Magic;
@@ -13,19 +15,28 @@ use tesutil::tes4::cosave::{CoSave, ObConvert, OPCODE_BASE};
 use tesutil::tes4::save::*;
 use tesutil::tes4::{
     ActorValue, Enchantable as Tes4Enchantable, FindForm, FormId, Item as Tes4Item, Tes4Field,
-    Tes4Record, Tes4World,
+    Tes4Record, Tes4World, FORM_LOCKPICK, FORM_REPAIR_HAMMER, FORM_SKELETON_KEY,
 };
 use tesutil::tes4::{Magic as Tes4Magic, Tes4Plugin};
-use tesutil::{tes3, EffectRange, Plugin, World};
+#[cfg(feature = "image")]
+use tesutil::Screenshot;
+use tesutil::{tes3, EffectRange, Field, GamePaths, IndexCache, LoadOptions, Plugin, World};
 use tesutil::{tes4, Record};
-use tesutil::{Attribute, Attributes, Form, TesError};
+use tesutil::{Attribute, Attributes, Form, SoulSize, SoulSizes, TesError};
 
+use crate::condition::*;
 use crate::config::*;
+use crate::names::Names;
+use crate::numeric::clamp_cast;
 use crate::oblivion::Oblivion;
+use crate::report::{ConversionReport, PlayerProperty, ReportCategory, StoredContainer};
+use crate::saves::latest_save;
+use crate::trial::{print_form, Describe};
 
 use anyhow::{anyhow, Context, Result};
-use enum_map::{enum_map, EnumMap};
+use enum_map::enum_map;
 use lazy_static::lazy_static;
+use num::{Bounded, NumCast, ToPrimitive};
 use regex::{Captures, Regex};
 #[cfg(windows)]
 use winreg::enums::*;
@@ -40,13 +51,15 @@ lazy_static! {
 /// Container for Morrowind-related state and functionality
 #[derive(Debug)]
 pub struct Morrowind {
-    game_dir: PathBuf,
+    paths: GamePaths,
     pub world: Tes3World,
     major_skill_bonus: f32,
     minor_skill_bonus: f32,
     misc_skill_bonus: f32,
     spec_skill_bonus: f32,
     soul_gem_mult: f32,
+    days_in_prison_mod: i32,
+    warnings: Vec<String>,
 }
 
 impl Morrowind {
@@ -59,6 +72,26 @@ impl Morrowind {
         })
     }
 
+    fn get_int_setting(world: &Tes3World, name: &str, default: i32) -> Result<i32> {
+        Ok(match world.get::<tes3::GameSetting>(name)? {
+            Some(setting) => setting
+                .get_int()
+                .ok_or_else(|| anyhow!("Invalid value for Morrowind game setting {}", name))?,
+            None => default,
+        })
+    }
+
+    fn get_string_setting(world: &Tes3World, name: &str, default: &str) -> Result<String> {
+        Ok(match world.get::<tes3::GameSetting>(name)? {
+            Some(setting) => String::from(
+                setting
+                    .get_string()
+                    .ok_or_else(|| anyhow!("Invalid value for Morrowind game setting {}", name))?,
+            ),
+            None => String::from(default),
+        })
+    }
+
     #[cfg(windows)]
     fn detect_dir() -> Result<String> {
         let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
@@ -98,8 +131,21 @@ impl Morrowind {
         Err(anyhow!("Could not detect Morrowind install path"))
     }
 
-    /// Capture Morrowind state
-    pub fn load<P, Q>(game_dir: Option<P>, save_path: Q) -> Result<Morrowind>
+    /// Gets the path to the Morrowind saves directory
+    pub fn saves_dir<P: AsRef<Path>>(game_dir: Option<P>) -> Result<PathBuf> {
+        let morrowind_dir = match game_dir {
+            Some(path) => PathBuf::from(path.as_ref()),
+            None => Morrowind::detect_dir()?.into(),
+        };
+
+        Ok(morrowind_dir.join("Saves"))
+    }
+
+    /// Gets the paths to the Morrowind install, detecting the install directory if it isn't given
+    ///
+    /// If any data directories are given, they replace the Data Files directory in the install
+    /// directory, from lowest to highest precedence.
+    pub fn game_paths<P, Q>(game_dir: Option<P>, data_dirs: &[Q]) -> Result<GamePaths>
     where
         P: AsRef<Path>,
         Q: AsRef<Path>,
@@ -109,25 +155,75 @@ impl Morrowind {
             None => Morrowind::detect_dir()?.into(),
         };
 
+        let mut paths = Tes3World::game_paths(morrowind_dir);
+        paths.set_data_dirs(data_dirs);
+        Ok(paths)
+    }
+
+    /// Capture Morrowind state
+    pub fn load<P: AsRef<Path>>(
+        paths: GamePaths,
+        save_path: P,
+        cache: &IndexCache,
+    ) -> Result<Morrowind> {
         let world =
-            Tes3World::load_from_save(<PathBuf as AsRef<Path>>::as_ref(&morrowind_dir), save_path)?;
+            Tes3World::load_from_save_with(&paths, save_path, LoadOptions::new().cache(cache))?;
+        Morrowind::from_world(paths, world)
+    }
+
+    /// Capture Morrowind state from the plugins in Morrowind.ini, without a save
+    pub fn load_world(paths: GamePaths, cache: &IndexCache) -> Result<Morrowind> {
+        let world = Tes3World::load_world_with(&paths, LoadOptions::new().cache(cache))?;
+        Morrowind::from_world(paths, world)
+    }
+
+    /// Capture Morrowind state from a list of plugins, without a save
+    ///
+    /// Plugin names are looked up in the data directories; absolute paths are used as they are.
+    pub fn load_plugins<P, T>(paths: GamePaths, plugins: T, cache: &IndexCache) -> Result<Morrowind>
+    where
+        P: AsRef<Path>,
+        T: IntoIterator<Item = P>,
+    {
+        let world = Tes3World::from_plugins_with(
+            plugins.into_iter().map(|p| paths.data_file(p)),
+            LoadOptions::new().cache(cache),
+        )?;
+        Morrowind::from_world(paths, world)
+    }
+
+    fn from_world(paths: GamePaths, world: Tes3World) -> Result<Morrowind> {
+        let warnings = world
+            .vfs()
+            .bad_archives()
+            .map(|(path, e)| format!("Skipped Morrowind archive {}: {}", path.display(), e))
+            .collect();
+
         let major_skill_bonus = Morrowind::get_float_setting(&world, "fMajorSkillBonus", 0.75)?;
         let minor_skill_bonus = Morrowind::get_float_setting(&world, "fMinorSkillBonus", 1.0)?;
         let misc_skill_bonus = Morrowind::get_float_setting(&world, "fMiscSkillBonus", 1.25)?;
         let spec_skill_bonus = Morrowind::get_float_setting(&world, "fSpecialSkillBonus", 0.8)?;
         let soul_gem_mult = Morrowind::get_float_setting(&world, "fSoulGemMult", 3.0)?;
+        let days_in_prison_mod = Morrowind::get_int_setting(&world, "iDaysinPrisonMod", 100)?;
 
         Ok(Morrowind {
-            game_dir: morrowind_dir,
+            paths,
             world,
             major_skill_bonus,
             minor_skill_bonus,
             misc_skill_bonus,
             spec_skill_bonus,
             soul_gem_mult,
+            days_in_prison_mod,
+            warnings,
         })
     }
 
+    /// Gets the non-fatal problems encountered while loading Morrowind
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
     /// Gets the Oblivion skill equivalent to a given Morrowind skill, if one exists
     pub fn oblivion_skill(skill: tes3::Skill) -> Option<tes4::Skill> {
         match skill {
@@ -274,6 +370,335 @@ impl Morrowind {
         }
     }
 
+    /// Converts a Morrowind spell effect to Oblivion
+    ///
+    /// Returns `None` if the effect has no Oblivion equivalent. If a range is given and the Oblivion
+    /// effect allows it, it's used instead of the Morrowind effect's range.
+    pub fn convert_effect(
+        effect: &tes3::SpellEffect,
+        range: Option<EffectRange>,
+        combine_strategy: &CombineStrategy,
+    ) -> Result<Option<tes4::SpellEffect>> {
+        Ok(
+            if let Some(effect_type) = Morrowind::oblivion_effect(effect.effect()) {
+                // TODO: what should we do if a spell includes both e.g. a Calm Creature and Calm Humanoid effect?
+                let mut ob_effect = tes4::SpellEffect::new(effect_type);
+
+                // Night-Eye has a magnitude in Morrowind, but not Oblivion, so we'll just leave it at the default of 0
+                if effect_type != tes4::MagicEffectType::NightEye {
+                    let (min, max) = effect.magnitude();
+                    ob_effect.set_magnitude(combine_strategy.combine(min, max))?;
+                }
+
+                ob_effect.set_range(match effect_type {
+                    // in Morrowind, Telekinesis always has Self range, but in Oblivion, it's always Target
+                    tes4::MagicEffectType::Telekinesis => EffectRange::Target,
+                    // for some reason, Oblivion doesn't allow Absorb Skill to be cast on Target
+                    tes4::MagicEffectType::AbsorbSkill => EffectRange::Touch,
+                    _ => match range {
+                        Some(range) if tes4::MAGIC_EFFECTS[effect_type].allows_range(range) => {
+                            range
+                        }
+                        _ => effect.range(),
+                    },
+                })?;
+                ob_effect.set_duration(effect.duration())?;
+                ob_effect.set_area(effect.area())?;
+
+                ob_effect.set_actor_value(if let Some(mw_skill) = effect.skill() {
+                    tes4::ActorValue::from(match Morrowind::oblivion_skill(mw_skill) {
+                        Some(skill) => skill,
+                        None => return Ok(None),
+                    })
+                } else if let Some(attribute) = effect.attribute() {
+                    tes4::ActorValue::from(attribute)
+                } else {
+                    effect_type.default_actor_value()
+                })?;
+
+                Some(ob_effect)
+            } else {
+                None
+            },
+        )
+    }
+
+    /// Converts a Morrowind spell to Oblivion
+    ///
+    /// Returns `None` if the spell's type doesn't exist in Oblivion or none of its effects could be
+    /// converted.
+    pub fn convert_spell(
+        &self,
+        mw_spell: &tes3::Spell,
+        ob: &Oblivion,
+        combine_strategy: &CombineStrategy,
+        names: &Names,
+    ) -> Result<Option<tes4::Spell>> {
+        let spell_type = match mw_spell.spell_type() {
+            tes3::SpellType::Spell => tes4::SpellType::Spell,
+            tes3::SpellType::Ability => tes4::SpellType::Ability,
+            tes3::SpellType::Power => tes4::SpellType::Power,
+            tes3::SpellType::Disease => tes4::SpellType::Disease,
+            _ => return Ok(None),
+        };
+
+        self.convert_spell_as(mw_spell, spell_type, ob, combine_strategy, names)
+    }
+
+    /// Decides whether a Morrowind spell's cost should be treated as auto-calculated
+    ///
+    /// The flag and the stored cost can disagree when a spell's effects or cost were edited
+    /// without updating the other, so the cost is checked against the auto-calc formula both ways.
+    /// A spell whose cost is exactly what the formula gives is treated as auto-calc, so the cost
+    /// is rebalanced for Oblivion, even if it isn't flagged. A spell flagged as auto-calc whose
+    /// cost isn't what the formula gives keeps its stored cost, since that's what the game charges.
+    /// If the cost couldn't be calculated (e.g. because of a missing magic effect definition), the
+    /// flag is trusted.
+    fn is_auto_calc_spell(mw_spell: &tes3::Spell, calculated_cost: Option<u32>) -> bool {
+        match calculated_cost {
+            Some(cost) => cost == mw_spell.cost(),
+            None => mw_spell.is_auto_calc(),
+        }
+    }
+
+    /// Converts a Morrowind spell to an Oblivion spell of the given type
+    ///
+    /// This is for spells whose type doesn't exist in Oblivion, like blights, which can still be
+    /// represented by another type. Returns `None` if none of the spell's effects could be
+    /// converted.
+    pub fn convert_spell_as(
+        &self,
+        mw_spell: &tes3::Spell,
+        spell_type: tes4::SpellType,
+        ob: &Oblivion,
+        combine_strategy: &CombineStrategy,
+        names: &Names,
+    ) -> Result<Option<tes4::Spell>> {
+        let name = names.convert(mw_spell.name(), &format!("spell {}", mw_spell.id()))?;
+        let mut ob_spell = tes4::Spell::new(None, Some(name));
+        let calculated_cost = if mw_spell.spell_type() == tes3::SpellType::Spell {
+            self.world.calculate_magic_cost(mw_spell).ok()
+        } else {
+            None
+        };
+        if Morrowind::is_auto_calc_spell(mw_spell, calculated_cost) {
+            ob_spell.set_auto_calc(true);
+        } else {
+            ob_spell.set_auto_calc(false);
+            ob_spell.cost = mw_spell.cost();
+        }
+        // choosing not to set this flag right now because I don't think it's desirable to add new player start spells in the save
+        // ob_spell.set_player_start_spell(spell.is_player_start_spell());
+        ob_spell.spell_type = spell_type;
+
+        let mut converted_any = false;
+        for effect in mw_spell.iter_effects() {
+            if let Some(ob_effect) = Morrowind::convert_effect(effect, None, combine_strategy)? {
+                ob_spell.add_effect(ob_effect);
+                converted_any = true;
+            }
+        }
+
+        // only add the spell if we successfully converted at least one effect
+        if converted_any {
+            ob.calculate_spell_cost(&mut ob_spell)?;
+            Ok(Some(ob_spell))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Converts a Morrowind potion to Oblivion
+    ///
+    /// Returns `None` if the potion is scripted or none of its effects could be converted.
+    pub fn convert_potion(
+        mw_potion: &tes3::Potion,
+        graphics: &tes4::PotionGraphicsTable,
+        combine_strategy: &CombineStrategy,
+        names: &Names,
+    ) -> Result<Option<tes4::Potion>> {
+        // can't convert scripted items
+        if mw_potion.script().is_some() {
+            return Ok(None);
+        }
+
+        let name = names.convert(
+            mw_potion.name.as_deref().unwrap_or(""),
+            &format!("potion {}", mw_potion.id),
+        )?;
+        let mut ob_potion = tes4::Potion::new(mw_potion.id.clone(), name);
+
+        ob_potion.is_auto_calc = mw_potion.alchemy_data.is_auto_calc;
+        ob_potion.value = mw_potion.alchemy_data.value;
+        ob_potion.weight = mw_potion.alchemy_data.weight;
+
+        // Morrowind has no concept of poisons, so a potion whose effects are all hostile becomes an
+        // Oblivion poison. poisons are applied to the target when a poisoned weapon strikes, so we
+        // convert their effects with Touch range.
+        let is_poison = mw_potion
+            .effects
+            .iter()
+            .filter_map(|e| Morrowind::oblivion_effect(e.effect()))
+            .fold(None, |is_poison, effect_type| {
+                Some(is_poison.unwrap_or(true) && tes4::MAGIC_EFFECTS[effect_type].is_hostile())
+            })
+            .unwrap_or(false);
+        let range = if is_poison {
+            Some(EffectRange::Touch)
+        } else {
+            None
+        };
+
+        let mut converted_any = false;
+        for effect in &mw_potion.effects {
+            if let Some(ob_effect) = Morrowind::convert_effect(effect, range, combine_strategy)? {
+                ob_potion.add_effect(ob_effect);
+                converted_any = true;
+            }
+        }
+
+        // only add the potion if we successfully converted at least one effect
+        Ok(if converted_any {
+            if ob_potion.is_poison() {
+                // poisons can't be eaten
+                ob_potion.is_food_item = false;
+            }
+            ob_potion.use_auto_graphics_from(graphics);
+            Some(ob_potion)
+        } else {
+            None
+        })
+    }
+
+    /// Converts a Morrowind class to Oblivion
+    ///
+    /// Both games use the same specializations, and in both a class's specialization only gives
+    /// bonuses to its skills, so it carries over unchanged. Neither game's classes affect magicka;
+    /// that comes from the race and birthsign. The major skills are chosen by
+    /// [`oblivion_major_skills`], ranked by `skill_levels` if this is a character's class, and the
+    /// favored attributes by [`oblivion_favored_attributes`].
+    ///
+    /// [`oblivion_major_skills`]: #method.oblivion_major_skills
+    /// [`oblivion_favored_attributes`]: #method.oblivion_favored_attributes
+    pub fn convert_class(
+        mw_class: &tes3::Class,
+        skill_levels: Option<&tes3::Skills<i32>>,
+        names: &Names,
+    ) -> Result<tes4::Class> {
+        let name = names.convert(mw_class.name(), &format!("class {}", mw_class.id()))?;
+        let mut ob_class = tes4::Class::new(name)?;
+        ob_class.set_editor_id(Some(String::from(mw_class.id())));
+        ob_class.specialization = mw_class.specialization;
+        ob_class.is_playable = mw_class.is_playable;
+
+        let num_skills = ob_class.major_skills().len();
+        let new_skills = Morrowind::oblivion_major_skills(mw_class, num_skills, skill_levels);
+        ob_class.set_major_skills(new_skills.as_ref())?;
+        ob_class.set_primary_attributes(&Morrowind::oblivion_favored_attributes(
+            mw_class,
+            &new_skills,
+        ))?;
+        Ok(ob_class)
+    }
+
+    /// Picks the Oblivion major skills for a Morrowind class
+    ///
+    /// Oblivion classes have 7 major skills to Morrowind's 5, and some Morrowind skills were
+    /// consolidated or dropped, so the class's major skills come first and the remaining slots are
+    /// filled from its minor skills and then its miscellaneous skills. Within the minor and
+    /// miscellaneous skills, skills are ranked from highest to lowest by `skill_levels`, if given,
+    /// with ties going to skills in the class's specialization, since Oblivion gives them the same
+    /// specialization bonus as the major skills; otherwise, skills keep their order. Morrowind
+    /// skills that map to the same Oblivion skill, like Axe and Blunt Weapon, only take one slot.
+    pub fn oblivion_major_skills(
+        mw_class: &tes3::Class,
+        num_skills: usize,
+        skill_levels: Option<&tes3::Skills<i32>>,
+    ) -> Vec<tes4::Skill> {
+        let rank = |skills: Vec<tes3::Skill>| -> Vec<tes4::Skill> {
+            let mut ranked: Vec<_> = skills
+                .into_iter()
+                .filter_map(|s| {
+                    Morrowind::oblivion_skill(s)
+                        .map(|ob_skill| (skill_levels.map_or(0, |l| l[s]), ob_skill))
+                })
+                .collect();
+            // the sort is stable, so skills keep their order when they're otherwise tied
+            ranked.sort_by_key(|(level, s)| {
+                (
+                    cmp::Reverse(*level),
+                    s.specialization() != mw_class.specialization,
+                )
+            });
+            ranked.into_iter().map(|(_, s)| s).collect()
+        };
+
+        let major = mw_class
+            .major_skills()
+            .iter()
+            .filter_map(|s| Morrowind::oblivion_skill(*s));
+        let minor = rank(mw_class.minor_skills().to_vec());
+        let misc = rank(
+            tes3::Skill::iter()
+                .filter(|s| mw_class.get_skill_type(*s) == SkillType::Miscellaneous)
+                .collect(),
+        );
+
+        let mut new_skills = Vec::with_capacity(num_skills);
+        for skill in major.chain(minor).chain(misc) {
+            if !new_skills.contains(&skill) {
+                new_skills.push(skill);
+
+                if new_skills.len() == num_skills {
+                    break;
+                }
+            }
+        }
+
+        new_skills
+    }
+
+    /// Picks the Oblivion favored attributes for a Morrowind class
+    ///
+    /// A favored attribute starts out 5 points higher, and in Oblivion an attribute only grows
+    /// with the skills it governs, so a favored attribute is only worth having if it governs some
+    /// of the class's major skills. The class's Morrowind favored attributes are kept if they
+    /// govern at least one of `major_skills`; any that don't, like Luck, which governs no skills,
+    /// are replaced by the attributes governing the most major skills, with ties going to the
+    /// attribute of the earlier skill.
+    pub fn oblivion_favored_attributes(
+        mw_class: &tes3::Class,
+        major_skills: &[tes4::Skill],
+    ) -> [Attribute; 2] {
+        let num_governed =
+            |a: Attribute| major_skills.iter().filter(|s| s.attribute() == a).count();
+
+        let mut candidates: Vec<_> = major_skills.iter().map(|s| s.attribute()).collect();
+        // the sort is stable, so attributes of earlier skills win ties
+        candidates.sort_by_key(|a| cmp::Reverse(num_governed(*a)));
+
+        let mut attributes = Vec::with_capacity(2);
+        for attribute in mw_class
+            .primary_attributes()
+            .iter()
+            .copied()
+            .filter(|a| num_governed(*a) > 0)
+            .chain(candidates)
+            // only needed if there are fewer than two attributes governing the major skills
+            .chain(mw_class.primary_attributes().iter().copied())
+        {
+            if !attributes.contains(&attribute) {
+                attributes.push(attribute);
+
+                if attributes.len() == 2 {
+                    break;
+                }
+            }
+        }
+
+        [attributes[0], attributes[1]]
+    }
+
     /// Calculates the XP required to level a skill up
     pub fn calculate_skill_xp<T: Into<f32>>(
         &self,
@@ -298,12 +723,14 @@ impl Morrowind {
 
     /// Get the path to the game directory
     pub fn game_dir(&self) -> &Path {
-        self.game_dir.as_ref()
+        self.paths.root()
     }
 
     /// Get the path to the game's data directory
+    ///
+    /// If the game has more than one data directory, this is the one with the highest precedence.
     pub fn data_dir(&self) -> PathBuf {
-        self.game_dir.join(Tes3World::PLUGIN_DIR)
+        self.paths.primary_data_dir().to_path_buf()
     }
 }
 
@@ -320,21 +747,247 @@ pub struct MorrowindToOblivion {
     player_data: tes3::PlayerData,
     class: tes3::Class,
     active_spells: tes3::ActiveSpellList,
-    soul_map: EnumMap<tes4::SoulType, (u32, u32)>,
+    soul_capacities: SoulSizes<u32>,
     model_map: HashMap<String, Vec<FormId>>,
     icon_map: HashMap<String, Vec<FormId>>,
+    names: Names,
+    warnings: RefCell<Vec<String>>,
+    report: RefCell<ConversionReport>,
+}
+
+/// The player's racial and birthsign spells in each game
+#[derive(Debug)]
+struct RacialSpells {
+    /// IDs of the Morrowind specials, which aren't converted because the Oblivion race and birthsign
+    /// provide their own, and of the player's lycanthropy spells if they're being cured
+    mw_suppressed: HashSet<String>,
+    /// Oblivion specials that go in the player's spell list
+    ob_spell_list: Vec<FormId>,
+    /// Oblivion specials that the OBSE plugin adds to the player as active spells
+    ob_abilities: Vec<FormId>,
+}
+
+/// Gets the name of a Morrowind cell as the game displays it
+///
+/// Unnamed exterior cells are named by their grid coordinates.
+fn cell_display_name(cell: &tes3::Tes3Record) -> Result<String, TesError> {
+    // references have NAME fields too, so stop before the first one
+    if let Some(field) = cell
+        .iter()
+        .take_while(|f| f.name() != b"FRMR")
+        .find(|f| f.name() == b"NAME")
+    {
+        let name = field.get_zstring()?;
+        if !name.is_empty() {
+            return Ok(String::from(name));
+        }
+    }
+
+    Ok(match tes3::CellId::read(cell)? {
+        tes3::CellId::Exterior(grid) => format!("Exterior ({}, {})", grid.x, grid.y),
+        tes3::CellId::Interior(name) => name,
+    })
+}
+
+/// Splits the Oblivion racial and birthsign specials into spell list entries and abilities
+///
+/// Powers and spells go in the spell list; everything else (abilities and diseases) has to be
+/// added by the OBSE plugin. A special granted by both the race and the birthsign is only included
+/// once.
+fn split_specials<I: IntoIterator<Item = (FormId, tes4::SpellType)>>(
+    specials: I,
+) -> (Vec<FormId>, Vec<FormId>) {
+    let mut seen = HashSet::new();
+    let mut spell_list = vec![];
+    let mut abilities = vec![];
+    for (id, spell_type) in specials {
+        if !seen.insert(id) {
+            continue;
+        }
+
+        if matches!(
+            spell_type,
+            tes4::SpellType::Power | tes4::SpellType::LesserPower | tes4::SpellType::Spell
+        ) {
+            spell_list.push(id);
+        } else {
+            abilities.push(id);
+        }
+    }
+
+    (spell_list, abilities)
+}
+
+/// Returns whether a Morrowind spell is part of Bloodmoon's lycanthropy
+///
+/// These are the werewolf spells, whose IDs all start with "werewolf", and Sanies Lupinus, the
+/// disease that turns the player into a werewolf.
+fn is_lycanthropy_spell(id: &str) -> bool {
+    let id = id.to_lowercase();
+    id.starts_with("werewolf") || id == "sanies lupinus"
+}
+
+/// Collects the lycanthropy spells from a list of Morrowind spell IDs
+///
+/// Each spell is listed once, even if the player has it both as a known spell and an active one.
+fn lycanthropy_spell_ids<'a, I: IntoIterator<Item = &'a str>>(ids: I) -> HashSet<String> {
+    ids.into_iter()
+        .filter(|id| is_lycanthropy_spell(id))
+        .map(String::from)
+        .collect()
+}
+
+/// Applies the werewolf policy to a player
+///
+/// Returns whether the player's lycanthropy should be cured, or an error if the policy refuses to
+/// convert a werewolf.
+fn apply_werewolf_policy(policy: WerewolfPolicy, is_werewolf: bool) -> Result<bool> {
+    if !is_werewolf {
+        return Ok(false);
+    }
+
+    match policy {
+        WerewolfPolicy::Refuse => Err(anyhow!(
+            "The Morrowind player is a werewolf, and Oblivion has no werewolves. Use --werewolf cure to convert them as if they'd been cured."
+        )),
+        WerewolfPolicy::Cure => Ok(true),
+    }
+}
+
+/// Gets the skill changes from serving a jail sentence
+///
+/// Each day of the sentence, the game picks a random skill and lowers it by a point, except that
+/// Security and Sneak go up instead. The same number of days is spread over the skills in turn
+/// here so that conversions are repeatable.
+fn jail_skill_changes(days: u32) -> tes3::Skills<i32> {
+    let num_skills = tes3::Skill::iter().count() as u32;
+    let mut changes = tes3::Skills::default();
+    for (i, skill) in tes3::Skill::iter().enumerate() {
+        let skill_days = (days / num_skills + u32::from((i as u32) < days % num_skills)) as i32;
+        changes[skill] = match skill {
+            tes3::Skill::Security | tes3::Skill::Sneak => skill_days,
+            _ => -skill_days,
+        };
+    }
+    changes
+}
+
+/// Returns whether a Morrowind spell type is an affliction, meaning a blight or a curse
+///
+/// Oblivion has neither, so afflictions can't be converted as they are. Morrowind applies them the
+/// same way it applies abilities.
+fn is_affliction(spell_type: tes3::SpellType) -> bool {
+    matches!(spell_type, tes3::SpellType::Blight | tes3::SpellType::Curse)
+}
+
+/// Scales an enchantment's charge so it allows as many uses at a new cost as it did at the old one
+fn rescale_charge(charge: u32, old_cost: u32, new_cost: u32) -> u32 {
+    if old_cost == 0 {
+        return charge;
+    }
+
+    let charge = u64::from(charge) * u64::from(new_cost) / u64::from(old_cost);
+    charge.min(u64::from(u32::MAX)) as u32
+}
+
+/// Gets the Oblivion form a Morrowind lockpick or repair item becomes when it has no mapping
+///
+/// Oblivion has only one kind of lockpick and one kind of repair hammer, so every Morrowind tool of
+/// those types converts to them, except that the Skeleton Key becomes Oblivion's Skeleton Key.
+/// Probes have nothing to become, since Oblivion has no traps to disarm.
+fn ob_tool(mw_id: &str, record_type: &[u8; 4]) -> Option<FormId> {
+    match record_type {
+        b"LOCK" if mw_id.eq_ignore_ascii_case("skeleton_key") => Some(FORM_SKELETON_KEY),
+        b"LOCK" => Some(FORM_LOCKPICK),
+        b"REPA" => Some(FORM_REPAIR_HAMMER),
+        _ => None,
+    }
+}
+
+/// Returns whether the player learns the effects of an Oblivion spell type for spellmaking
+///
+/// Only regular spells teach their effects. Oblivion doesn't count the effects of the player's
+/// powers, lesser powers, abilities, or diseases, so a birthsign power alone never unlocks an
+/// effect at the spellmaking altar.
+fn teaches_effects(spell_type: tes4::SpellType) -> bool {
+    spell_type == tes4::SpellType::Spell
+}
+
+/// Collects the IDs of the magic effects the player can use for spellmaking
+///
+/// The effects of spells that [teach their effects] are unlocked, or the effects of all spells if
+/// `unlock_all` is set. Either way, an effect is only unlocked if `allowed_for_spellmaking` says
+/// it can be used at a spellmaking altar, because the game doesn't expect the player to know any
+/// other effects.
+///
+/// [teach their effects]: fn.teaches_effects.html
+fn known_magic_effects<'a, I, F>(
+    spells: I,
+    unlock_all: bool,
+    mut allowed_for_spellmaking: F,
+) -> Result<BTreeSet<[u8; 4]>>
+where
+    I: IntoIterator<Item = &'a tes4::Spell>,
+    F: FnMut(tes4::MagicEffectType) -> Result<bool>,
+{
+    let mut known_effects = BTreeSet::new();
+    for spell in spells
+        .into_iter()
+        .filter(|s| unlock_all || teaches_effects(s.spell_type))
+    {
+        for effect in spell.iter_effects() {
+            let effect_type = effect.effect_type();
+            if allowed_for_spellmaking(effect_type)? {
+                known_effects.insert(effect_type.id());
+            }
+        }
+    }
+
+    Ok(known_effects)
+}
+
+/// Pairs active effects with the effects of the spell they came from
+///
+/// Active effects refer to their spell's effects by index, but a mod may have changed the spell's
+/// effects since the save was made. Rather than attributing a magnitude to the wrong effect,
+/// active effects whose index is out of range for the spell are returned separately.
+fn match_effects_by_index<'a, T, U, I>(
+    base_effects: &'a [T],
+    active_effects: I,
+) -> (Vec<(U, &'a T)>, Vec<U>)
+where
+    I: IntoIterator<Item = (i32, U)>,
+{
+    let mut matched = vec![];
+    let mut unmatched = vec![];
+    for (index, effect) in active_effects {
+        let base_effect = if index < 0 {
+            None
+        } else {
+            base_effects.get(index as usize)
+        };
+        match base_effect {
+            Some(base_effect) => matched.push((effect, base_effect)),
+            None => unmatched.push(effect),
+        }
+    }
+
+    (matched, unmatched)
 }
 
 /// Name of the companion mod generated during the conversion
 pub const COMPANION_MOD_NAME: &str = "mw2ob.esp";
 
+/// Maximum number of similar IDs to suggest when a mapping is missing
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Bloodmoon global variable that's set once the player has become a werewolf
+const WEREWOLF_GLOBAL: &str = "PCWerewolf";
+
 impl MorrowindToOblivion {
-    fn load_map<P: AsRef<Path>>(
-        config_dir: P,
-        world: &Tes4World,
-    ) -> Result<HashMap<String, FormId>> {
+    pub(crate) fn load_map(config: &Config, world: &Tes4World) -> Result<HashMap<String, FormId>> {
         let mut map = HashMap::new();
-        let inis = iter_form_map(config_dir.as_ref().join("mwob"))
+        let inis = iter_layered_form_map(config, "mwob")
             .with_context(|| "Failed to load Morrowind-to-Oblivion mapping")?;
         for ini in inis {
             for (plugin, values) in &ini {
@@ -358,13 +1011,25 @@ impl MorrowindToOblivion {
     pub fn load(config: Config) -> Result<MorrowindToOblivion> {
         // the compiler can't tell that config lives long enough for our threads to take references
         // to these values, so we have to clone them so we can give owned values to the threads.
-        let mw_path = config.mw_path.clone();
-        let ob_path = config.ob_path.clone();
-        let source_path = config.source_path.clone();
+        let mw_paths = Morrowind::game_paths(config.mw_path.as_ref(), &config.mw_data)?;
+        let ob_paths = Oblivion::game_paths(config.ob_path.as_ref(), &config.ob_data)?;
+        let source_path = match config.source_path {
+            Some(ref path) => PathBuf::from(path),
+            None => latest_save(&config, Game::Morrowind)
+                .with_context(|| "Could not find the most recent Morrowind save")?,
+        };
         let target_path = config.target_path.clone();
-
-        let mw_thread = thread::spawn(|| Morrowind::load(mw_path, source_path));
-        let ob_thread = thread::spawn(|| Oblivion::load(ob_path, target_path));
+        let allow_missing_plugins = config.allow_missing_plugins;
+        let (cache, cache_warning) = config.open_index_cache();
+        let mut warnings: Vec<String> = cache_warning.into_iter().collect();
+        let cache = Arc::new(cache);
+        let mw_cache = Arc::clone(&cache);
+        let ob_cache = Arc::clone(&cache);
+
+        let mw_thread = thread::spawn(move || Morrowind::load(mw_paths, source_path, &mw_cache));
+        let ob_thread = thread::spawn(move || {
+            Oblivion::load(ob_paths, target_path, allow_missing_plugins, &ob_cache)
+        });
 
         // the map_err handles the case where join() failed and the with_context adds context to the
         // case where the load failed
@@ -376,16 +1041,20 @@ impl MorrowindToOblivion {
             .join()
             .map_err(|_| anyhow!("Oblivion load failed"))?
             .with_context(|| "Oblivion load failed")?;
-        let form_map = RefCell::new(MorrowindToOblivion::load_map(
-            &config.config_path,
-            &ob.world(),
-        )?);
+        warnings.extend(config.save_index_cache(&cache));
+        warnings.extend(mw.warnings().iter().cloned());
+        warnings.extend(ob.warnings().iter().cloned());
+        let form_map = RefCell::new(MorrowindToOblivion::load_map(&config, &ob.world())?);
 
         // we'll use these mappings later to determine appropriate Oblivion models and icons to use
         // for Morrowind items that aren't explicitly mapped to an Oblivion item
         let mut model_map: HashMap<_, Vec<FormId>> = HashMap::new();
         let mut icon_map: HashMap<_, Vec<FormId>> = HashMap::new();
-        for (mw_id, ob_id) in form_map.borrow().iter() {
+        // the first usable item for a model or icon wins, so visit the mappings in a stable order
+        let mappings = form_map.borrow();
+        let mut sorted_mappings: Vec<_> = mappings.iter().collect();
+        sorted_mappings.sort_unstable_by_key(|(mw_id, _)| *mw_id);
+        for (mw_id, ob_id) in sorted_mappings {
             // we just ignore errors on this step for now
             if let Ok(Some(item)) = mw.world.get_item(mw_id.as_str()) {
                 if let Some(model) = item.model() {
@@ -399,6 +1068,7 @@ impl MorrowindToOblivion {
                 }
             }
         }
+        drop(mappings);
 
         let player_base: tes3::Npc = mw
             .world
@@ -415,24 +1085,50 @@ impl MorrowindToOblivion {
             .get("PlayerSaveGame")?
             .ok_or_else(|| anyhow!("Missing player change record in Morrowind save"))?;
 
+        {
+            let ob_world = ob.world();
+            for plugin in ob_world.missing_plugins() {
+                warnings.push(format!(
+                    "Oblivion save depends on {}, which is not installed",
+                    plugin
+                ));
+            }
+
+            let num_affected = ob_world.change_records_from_missing_plugins().len();
+            if num_affected > 0 {
+                warnings.push(format!(
+                    "{} change records in the Oblivion save belong to missing plugins and will be left as they are",
+                    num_affected
+                ));
+            }
+        }
+
+        // very early saves and saves generated by external tools may be missing some of the
+        // player's records. we can still convert those, so we just warn and use defaults.
         let player_data = {
             let save = mw.world.get_save().unwrap();
-            let record = save
-                .get_records_by_type(b"PCDT")
-                .ok_or_else(|| anyhow!("Missing player data record (PCDT) in Morrowind save"))?
-                .next()
-                .ok_or_else(|| anyhow!("Missing player data record (PCDT) in Morrowind save"))?;
-            tes3::PlayerData::read(&record)?
+            match save.get_records_by_type(b"PCDT").and_then(|mut r| r.next()) {
+                Some(record) => tes3::PlayerData::read(&record)?,
+                None => {
+                    warnings.push(String::from(
+                        "Missing player data record (PCDT) in Morrowind save; birthsign and level progress will not be converted",
+                    ));
+                    tes3::PlayerData::default()
+                }
+            }
         };
 
         let active_spells = {
             let save = mw.world.get_save().unwrap();
-            let record = save
-                .get_records_by_type(b"SPLM")
-                .ok_or_else(|| anyhow!("Missing active spells record (SPLM) in Morrowind save"))?
-                .next()
-                .ok_or_else(|| anyhow!("Missing active spells record (SPLM) in Morrowind save"))?;
-            tes3::ActiveSpellList::read(&record)?
+            match save.get_records_by_type(b"SPLM").and_then(|mut r| r.next()) {
+                Some(record) => tes3::ActiveSpellList::read(&record)?,
+                None => {
+                    warnings.push(String::from(
+                        "Missing active spells record (SPLM) in Morrowind save; no active spells will be converted",
+                    ));
+                    tes3::ActiveSpellList::default()
+                }
+            }
         };
 
         let class = mw
@@ -450,17 +1146,20 @@ impl MorrowindToOblivion {
         let common_value = (common.value() as f32 * mw.soul_gem_mult) as u32;
         let greater_value = (greater.value() as f32 * mw.soul_gem_mult) as u32;
 
-        let soul_map = enum_map! {
-            tes4::SoulType::None => (0, 0),
-            tes4::SoulType::Petty => (1, petty_value),
-            tes4::SoulType::Lesser => (petty_value + 1, lesser_value),
-            tes4::SoulType::Common => (lesser_value + 1, common_value),
-            tes4::SoulType::Greater => (common_value + 1, greater_value),
-            tes4::SoulType::Grand => (greater_value + 1, u32::MAX),
+        let soul_capacities = enum_map! {
+            SoulSize::None => 0,
+            SoulSize::Petty => petty_value,
+            SoulSize::Lesser => lesser_value,
+            SoulSize::Common => common_value,
+            SoulSize::Greater => greater_value,
+            SoulSize::Grand => u32::MAX,
         };
 
-        // ensure that the companion mod exists
-        ob.world_mut().get_companion_mod(COMPANION_MOD_NAME)?;
+        if config.form_target == FormTarget::Plugin {
+            MorrowindToOblivion::prepare_companion_mod(&mut ob.world_mut(), &config.plugin_name)?;
+        }
+
+        let names = Names::new(config.name_policy);
 
         Ok(MorrowindToOblivion {
             config,
@@ -473,23 +1172,97 @@ impl MorrowindToOblivion {
             player_data,
             class,
             active_spells,
-            soul_map,
+            soul_capacities,
             model_map,
             icon_map,
+            names,
+            warnings: RefCell::new(warnings),
+            report: RefCell::new(ConversionReport::new()),
         })
     }
 
-    fn add_form_to_mod<T>(&self, mw_id: &str, form: &T) -> Result<FormId>
+    /// Records a non-fatal problem encountered during the conversion
+    fn warn<T: Into<String>>(&self, message: T) {
+        self.warnings.borrow_mut().push(message.into());
+    }
+
+    /// Converts one of the player's stats to the type Oblivion stores it as
+    ///
+    /// Values Oblivion can't represent are clamped to the nearest value it can, with a warning.
+    fn convert_stat<T, U>(&self, value: T, stat: &str) -> U
     where
-        T: Form<Field = Tes4Field, Record = Tes4Record>,
+        T: ToPrimitive + Copy + Display,
+        U: NumCast + Bounded + Display,
     {
-        let mut record = Tes4Record::new(T::RECORD_TYPE);
-        form.write(&mut record)?;
-        let form_id = self.with_companion_mod(|plugin| plugin.add_new_record(record))?;
-        self.form_map
-            .borrow_mut()
-            .insert(String::from(mw_id), form_id);
-        Ok(form_id)
+        let (converted, clamped) = clamp_cast(value);
+        if clamped {
+            self.warn(format!(
+                "The player's {} of {} is out of range for Oblivion and was changed to {}",
+                stat, value, converted
+            ));
+        }
+        converted
+    }
+
+    /// Gets the non-fatal problems encountered during the conversion so far
+    ///
+    /// Changes made to names are listed last.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = self.warnings.borrow().clone();
+        warnings.extend(self.names.reports());
+        warnings
+    }
+
+    /// Records something that was converted in the conversion report
+    fn report_converted(&self, category: ReportCategory, id: &str) {
+        self.report.borrow_mut().add_converted(category, id);
+    }
+
+    /// Records something that was skipped in the conversion report
+    fn report_skipped(&self, category: ReportCategory, id: &str, reason: &str) {
+        self.report.borrow_mut().add_skipped(category, id, reason);
+    }
+
+    /// Gets the report of what the conversion has done so far, including warnings
+    pub fn report(&self) -> ConversionReport {
+        let mut report = self.report.borrow().clone();
+        report.add_warnings(self.warnings());
+        report
+    }
+
+    /// Describes Oblivion records similar to a Morrowind ID that has no mapping
+    ///
+    /// The result is meant to be appended to an error or warning message to help the user fix their
+    /// mapping files. Suggestions are only a convenience, so if they can't be looked up, the result
+    /// is empty rather than an error that would hide the message it's appended to.
+    fn suggest_mapping(&self, mw_id: &str, record_type: &[u8; 4]) -> String {
+        let suggestions = self
+            .ob
+            .world()
+            .find_similar_editor_ids(mw_id, record_type, MAX_SUGGESTIONS)
+            .unwrap_or_default();
+        if suggestions.is_empty() {
+            String::new()
+        } else {
+            let suggestions: Vec<_> = suggestions
+                .iter()
+                .map(|(editor_id, form_id)| format!("{} ({})", editor_id, form_id))
+                .collect();
+            format!("; similar Oblivion IDs: {}", suggestions.join(", "))
+        }
+    }
+
+    fn add_form_to_mod<T>(&self, mw_id: &str, form: &T) -> Result<FormId>
+    where
+        T: Form<Field = Tes4Field, Record = Tes4Record>,
+    {
+        let mut record = Tes4Record::new(T::RECORD_TYPE);
+        form.write(&mut record)?;
+        let form_id = self.with_companion_mod(|plugin| plugin.add_new_record(record))?;
+        self.form_map
+            .borrow_mut()
+            .insert(String::from(mw_id), form_id);
+        Ok(form_id)
     }
 
     fn add_form_to_save<T>(&self, mw_id: &str, form: &T) -> Result<(FormId, u32)>
@@ -507,13 +1280,83 @@ impl MorrowindToOblivion {
         Ok((form_id, iref))
     }
 
-    fn add_form_to_both<T>(&self, mw_id: &str, form: &T) -> Result<(FormId, u32)>
+    /// Adds a converted form to the plugin or save, depending on the configured form target
+    ///
+    /// Returns the new form's ID and its iref in the save.
+    fn add_converted_form<T>(&self, mw_id: &str, form: &T) -> Result<(FormId, u32)>
     where
         T: Form<Field = Tes4Field, Record = Tes4Record>,
     {
-        let form_id = self.add_form_to_mod(mw_id, form)?;
-        let iref = self.with_save_mut(|ob_save| ob_save.insert_form_id(form_id));
-        Ok((form_id, iref))
+        match self.config.form_target {
+            FormTarget::Plugin => {
+                let form_id = self.add_form_to_mod(mw_id, form)?;
+                let iref = self.with_save_mut(|ob_save| ob_save.insert_form_id(form_id));
+                Ok((form_id, iref))
+            }
+            FormTarget::Save => self.add_form_to_save(mw_id, form),
+        }
+    }
+
+    /// Adds a batch of converted forms to the plugin or save, like `add_converted_form`
+    ///
+    /// Each form in `forms` is converted from the Morrowind ID at the same position in `mw_ids`.
+    /// Returns each new form's ID and its iref in the save, in the same order.
+    fn add_converted_forms<T>(&self, mw_ids: &[&str], forms: &[T]) -> Result<Vec<(FormId, u32)>>
+    where
+        T: Form<Field = Tes4Field, Record = Tes4Record> + Sync,
+    {
+        match self.config.form_target {
+            FormTarget::Plugin => mw_ids
+                .iter()
+                .zip(forms)
+                .map(|(mw_id, form)| self.add_converted_form(mw_id, form))
+                .collect(),
+            FormTarget::Save => {
+                let added = self.with_save_mut(|ob_save| {
+                    ob_save.add_forms(forms).map(|irefs| {
+                        irefs
+                            .map(|i| (ob_save.iref_to_form_id(i).unwrap(), i))
+                            .collect::<Vec<_>>()
+                    })
+                })?;
+                let mut form_map = self.form_map.borrow_mut();
+                for (mw_id, (form_id, _)) in mw_ids.iter().zip(&added) {
+                    form_map.insert(String::from(*mw_id), *form_id);
+                }
+                Ok(added)
+            }
+        }
+    }
+
+    /// Makes sure the plugin that will hold converted forms exists and can hold them
+    ///
+    /// The plugin is created if the save doesn't already use it. Form IDs for new records are
+    /// allocated from the plugin's own index, so if the save already uses the plugin, its masters
+    /// have to be exactly the plugins loaded before it for those form IDs to mean the same thing in
+    /// the save.
+    fn prepare_companion_mod(ob_world: &mut Tes4World, name: &str) -> Result<()> {
+        let search = name.to_lowercase();
+        let loaded_before: Vec<_> = ob_world
+            .get_save()
+            .map(|save| {
+                save.iter_plugins()
+                    .map(str::to_lowercase)
+                    .take_while(|p| *p != search)
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Some(plugin) = ob_world.get_plugin(name) {
+            let masters: Vec<_> = plugin.iter_masters().map(str::to_lowercase).collect();
+            if masters != loaded_before {
+                return Err(anyhow!(
+                    "Can't add converted forms to {} because its masters don't match the plugins loaded before it",
+                    name
+                ));
+            }
+        }
+
+        ob_world.get_companion_mod(name)?;
+        Ok(())
     }
 
     // there's no immutable version of this method because get_companion_mod is always mutable -
@@ -523,7 +1366,9 @@ impl MorrowindToOblivion {
         F: FnOnce(&mut Tes4Plugin) -> T,
     {
         let mut ob_world = self.ob.world_mut();
-        f(ob_world.get_companion_mod(COMPANION_MOD_NAME).unwrap())
+        f(ob_world
+            .get_companion_mod(&self.config.plugin_name)
+            .unwrap())
     }
 
     fn with_save<T, F>(&self, f: F) -> T
@@ -552,24 +1397,29 @@ impl MorrowindToOblivion {
 
     fn convert_race(&self, ob_player_ref: &mut PlayerReferenceChange) -> Result<()> {
         let mw_race = self.player_base.race();
-        let form_id = self
-            .form_map
-            .borrow()
-            .get(mw_race)
-            .copied()
-            .ok_or_else(|| {
-                anyhow!(format!(
-                    "Could not find Oblivion equivalent for race {}",
-                    mw_race
+        let form_id = match self.form_map.borrow().get(mw_race).copied() {
+            Some(form_id) => form_id,
+            None => {
+                return Err(anyhow!(
+                    "Could not find Oblivion equivalent for race {}{}",
+                    mw_race,
+                    self.suggest_mapping(mw_race, b"RACE")
                 ))
-            })?;
+            }
+        };
 
-        // TODO: UESP says this is optional, but can the player not have a birthsign? probably not.
-        //  at a minimum we should warn if there is no birthsign
-        let bs_form_id = self
-            .player_data
-            .birthsign()
-            .and_then(|id| self.form_map.borrow().get(id).copied());
+        // UESP says this is optional, but the player should always have a birthsign in practice
+        let mw_birthsign = self.player_data.birthsign();
+        let bs_form_id = mw_birthsign.and_then(|id| self.form_map.borrow().get(id).copied());
+        match (mw_birthsign, bs_form_id) {
+            (None, _) => self.warn("Player has no birthsign"),
+            (Some(mw_birthsign), None) => self.warn(format!(
+                "Could not find Oblivion equivalent for birthsign {}{}",
+                mw_birthsign,
+                self.suggest_mapping(mw_birthsign, b"BSGN")
+            )),
+            _ => (),
+        }
 
         self.with_save_mut(|ob_save| {
             let iref = ob_save.insert_form_id(form_id);
@@ -592,233 +1442,180 @@ impl MorrowindToOblivion {
                 (self.ob.world().get(&search)?.unwrap(), *class_form_id)
             }
             None => {
-                // the Morrowind class needs to be converted as a custom class
-                let mut new_class = tes4::Class::new(String::from(self.class.name()))?;
-                new_class.set_primary_attributes(self.class.primary_attributes())?;
-                new_class.specialization = self.class.specialization;
-
-                // we need to map the Morrowind major and minor skills to Oblivion major skills. because
-                // Oblivion has 7 major skills to Morrowind's 5, we will need to take at least some
-                // minor skills as major skills in Oblivion. because Oblivion removed and consolidated a
-                // number of skills, we may also end up needing to take some miscellaneous skills. we
-                // will sort the minor and miscellaneous skills in order of level, then take the highest
-                // ones.
-                let mut minor_skills = self.class.minor_skills().to_vec();
-                minor_skills.sort_by(|a, b| {
-                    self.player_ref.skills[*a]
-                        .base
-                        .cmp(&self.player_ref.skills[*b].base)
-                });
-
-                let mut misc_skills: Vec<_> = tes3::Skill::iter()
-                    .filter(|s| self.class.get_skill_type(*s) == SkillType::Miscellaneous)
-                    .collect();
-                misc_skills.sort_by(|a, b| {
-                    self.player_ref.skills[*a]
-                        .base
-                        .cmp(&self.player_ref.skills[*b].base)
-                });
-
-                let num_skills = new_class.major_skills().len();
-                let mut new_skills = Vec::with_capacity(num_skills);
-                for skill in self
-                    .class
-                    .major_skills()
-                    .iter()
-                    .chain(&minor_skills)
-                    .chain(&misc_skills)
-                {
-                    // if this skill has an Oblivion equivalent
-                    if let Some(ob_skill) = Morrowind::oblivion_skill(*skill) {
-                        // if this skill is not already in the list of major skills
-                        if !new_skills.iter().any(|s| *s == ob_skill) {
-                            new_skills.push(ob_skill);
-
-                            if new_skills.len() == num_skills {
-                                break;
-                            }
-                        }
-                    }
-                }
-
-                new_class.set_major_skills(new_skills.as_ref())?;
-
+                // the Morrowind class needs to be converted as a custom class. the player's
+                // highest skills are the ones they've been using, so those are the ones that get
+                // promoted to major skills.
+                let skill_levels = enum_map! { skill => self.player_ref.skills[skill].base };
+                let mut new_class =
+                    Morrowind::convert_class(&self.class, Some(&skill_levels), &self.names)?;
+                new_class.set_editor_id(None);
                 new_class.is_playable = true;
+                // nobody has the player's custom class, so it never offers training
+                new_class.set_training(None);
                 (new_class, FORM_PLAYER_CUSTOM_CLASS)
             }
         })
     }
 
     fn convert_effect(&self, effect: &tes3::SpellEffect) -> Result<Option<tes4::SpellEffect>> {
-        Ok(
-            if let Some(effect_type) = Morrowind::oblivion_effect(effect.effect()) {
-                // TODO: what should we do if a spell includes both e.g. a Calm Creature and Calm Humanoid effect?
-                let mut ob_effect = tes4::SpellEffect::new(effect_type);
-
-                // Night-Eye has a magnitude in Morrowind, but not Oblivion, so we'll just leave it at the default of 0
-                if effect_type != tes4::MagicEffectType::NightEye {
-                    let (min, max) = effect.magnitude();
-                    ob_effect.set_magnitude(self.config.combine_strategy.combine(min, max))?;
-                }
-
-                ob_effect.set_range(match effect_type {
-                    // in Morrowind, Telekinesis always has Self range, but in Oblivion, it's always Target
-                    tes4::MagicEffectType::Telekinesis => EffectRange::Target,
-                    // for some reason, Oblivion doesn't allow Absorb Skill to be cast on Target
-                    tes4::MagicEffectType::AbsorbSkill => EffectRange::Touch,
-                    _ => effect.range(),
-                })?;
-                ob_effect.set_duration(effect.duration())?;
-                ob_effect.set_area(effect.area())?;
+        Morrowind::convert_effect(effect, None, &self.config.combine_strategy)
+    }
 
-                ob_effect.set_actor_value(if let Some(mw_skill) = effect.skill() {
-                    tes4::ActorValue::from(match Morrowind::oblivion_skill(mw_skill) {
-                        Some(skill) => skill,
-                        None => return Ok(None),
-                    })
-                } else if let Some(attribute) = effect.attribute() {
-                    tes4::ActorValue::from(attribute)
-                } else {
-                    effect_type.default_actor_value()
-                })?;
+    fn convert_spell(&self, mw_spell: &tes3::Spell) -> Result<Option<tes4::Spell>> {
+        self.mw.convert_spell(
+            mw_spell,
+            &self.ob,
+            &self.config.combine_strategy,
+            &self.names,
+        )
+    }
 
-                Some(ob_effect)
-            } else {
-                None
-            },
+    /// Converts a Morrowind affliction to an Oblivion ability with the same effects
+    fn convert_affliction(&self, mw_spell: &tes3::Spell) -> Result<Option<tes4::Spell>> {
+        self.mw.convert_spell_as(
+            mw_spell,
+            tes4::SpellType::Ability,
+            &self.ob,
+            &self.config.combine_strategy,
+            &self.names,
         )
     }
 
-    fn convert_spell(&self, mw_spell: &tes3::Spell) -> Result<Option<tes4::Spell>> {
-        let mut ob_spell = tes4::Spell::new(None, Some(String::from(mw_spell.name())));
-        if mw_spell.is_auto_calc() {
-            ob_spell.set_auto_calc(true);
-        } else {
-            ob_spell.set_auto_calc(false);
-            ob_spell.cost = mw_spell.cost();
-        }
-        // choosing not to set this flag right now because I don't think it's desirable to add new player start spells in the save
-        // ob_spell.set_player_start_spell(spell.is_player_start_spell());
-        ob_spell.spell_type = match mw_spell.spell_type() {
-            tes3::SpellType::Spell => tes4::SpellType::Spell,
-            tes3::SpellType::Ability => tes4::SpellType::Ability,
-            tes3::SpellType::Power => tes4::SpellType::Power,
-            tes3::SpellType::Disease => tes4::SpellType::Disease,
-            _ => return Ok(None),
+    /// Works out which racial and birthsign spells the player has in each game
+    fn racial_spells(&self, ob_player_ref: &PlayerReferenceChange) -> Result<RacialSpells> {
+        let mw_race: tes3::Race = self
+            .mw
+            .world
+            .get(self.player_base.race())?
+            .ok_or_else(|| anyhow!("Could not find Morrowind player race"))?;
+        let mw_sign: Option<tes3::Birthsign> = match self.player_data.birthsign() {
+            Some(birthsign) => self.mw.world.get(birthsign)?,
+            None => None,
         };
-
-        let mut converted_any = false;
-        for effect in mw_spell.iter_effects() {
-            if let Some(ob_effect) = self.convert_effect(effect)? {
-                ob_spell.add_effect(ob_effect);
-                converted_any = true;
-            }
+        let mut mw_suppressed: HashSet<_> = mw_race.specials().map(String::from).collect();
+        if let Some(ref sign) = mw_sign {
+            mw_suppressed.extend(sign.spells().map(String::from));
         }
 
-        // only add the spell if we successfully converted at least one effect
-        if converted_any {
-            self.ob.calculate_spell_cost(&mut ob_spell)?;
-            Ok(Some(ob_spell))
-        } else {
-            Ok(None)
+        let (race_id, birthsign_id) = self.with_save(|save| {
+            (
+                save.iref_to_form_id(ob_player_ref.race()),
+                save.iref_to_form_id(ob_player_ref.birthsign()),
+            )
+        });
+        let race_id = race_id.ok_or_else(|| anyhow!("Invalid Oblivion player race"))?;
+        let birthsign_id =
+            birthsign_id.ok_or_else(|| anyhow!("Invalid Oblivion player birthsign"))?;
+        let ob_race: tes4::Race = self
+            .ob
+            .world()
+            .get(&FindForm::ByIndex(race_id))?
+            .ok_or_else(|| anyhow!("Could not find Oblivion player race"))?;
+        let ob_birthsign: tes4::Birthsign = self
+            .ob
+            .world()
+            .get(&FindForm::ByIndex(birthsign_id))?
+            .ok_or_else(|| anyhow!("Could not find Oblivion player birthsign"))?;
+
+        let mut specials = vec![];
+        for id in ob_race.spells().chain(ob_birthsign.spells()) {
+            let spell: tes4::Spell = self
+                .ob
+                .world()
+                .get(&FindForm::ByIndex(id))?
+                .ok_or_else(|| anyhow!("Could not find Oblivion special {}", id))?;
+            specials.push((id, spell.spell_type));
         }
+        let (ob_spell_list, ob_abilities) = split_specials(specials);
+
+        Ok(RacialSpells {
+            mw_suppressed,
+            ob_spell_list,
+            ob_abilities,
+        })
     }
 
     fn convert_spells(
         &self,
         ob_player_base: &mut ActorChange,
         ob_player_ref: &mut PlayerReferenceChange,
+        racial_spells: &RacialSpells,
     ) -> Result<()> {
-        let mw_race: tes3::Race = self
-            .mw
-            .world
-            .get(self.player_base.race())?
-            .ok_or_else(|| anyhow!("Could not find Morrowind player race"))?;
-        let mw_sign: Option<tes3::Birthsign> = match self.player_data.birthsign() {
-            Some(birthsign) => self.mw.world.get(birthsign)?,
-            None => None,
-        };
-        let spells_to_suppress: HashSet<_> = if let Some(ref sign) = mw_sign {
-            mw_race.specials().chain(sign.spells()).collect()
-        } else {
-            mw_race.specials().collect()
-        };
+        let spells_to_suppress = &racial_spells.mw_suppressed;
 
         // TODO: make spell conversion errors warnings
-        let ob_spells: HashMap<_, _> = self
+        // keep the player's spell order so created forms get the same irefs on every run
+        let mut seen = HashSet::new();
+        let ob_spells: Vec<_> = self
             .player_base
             .spells()
             .filter_map(|id| {
-                if spells_to_suppress.contains(id) {
-                    None
-                } else {
-                    Some((
-                        id,
-                        self.convert_spell(&self.mw.world.get(id).ok()??).unwrap()?,
-                    ))
+                if spells_to_suppress.contains(id) || !seen.insert(id) {
+                    return None;
                 }
-            })
-            .collect();
-        let known_effects: HashSet<_> = ob_spells
-            .iter()
-            .filter(|(_, s)| s.spell_type == tes4::SpellType::Spell)
-            .flat_map(|(_, s)| s.iter_effects().map(|e| e.effect_type()))
-            .collect();
 
+                let mw_spell: tes3::Spell = match self.mw.world.get(id).ok()? {
+                    Some(mw_spell) => mw_spell,
+                    None => {
+                        self.report_skipped(ReportCategory::Spell, id, "the spell does not exist");
+                        return None;
+                    }
+                };
+                // afflictions are converted along with the player's active spells
+                if is_affliction(mw_spell.spell_type()) {
+                    return None;
+                }
+                // errors like a name the name policy won't fix stop the conversion
+                match self.convert_spell(&mw_spell) {
+                    Ok(Some(ob_spell)) => {
+                        self.report_converted(ReportCategory::Spell, id);
+                        Some(Ok((id, ob_spell)))
+                    }
+                    Ok(None) => {
+                        self.report_skipped(ReportCategory::Spell, id, "no Oblivion equivalent");
+                        None
+                    }
+                    Err(e) => Some(Err(e)),
+                }
+            })
+            .collect::<Result<_>>()?;
         // set known magic effects
-        // TODO: do powers contribute to known effects?
-        ob_player_ref.set_known_magic_effects(known_effects.iter().map(|e| e.id()).collect());
-
-        let ob_race: tes4::Race = self
-            .with_save(|save| {
-                let form_id = save.iref_to_form_id(ob_player_ref.race()).unwrap();
-                self.ob.world().get(&FindForm::ByIndex(form_id))
-            })?
-            .unwrap();
-
-        // FIXME: these unwraps are not safe
-        let ob_birthsign: tes4::Birthsign = self
-            .with_save(|save| {
-                let form_id = save.iref_to_form_id(ob_player_ref.birthsign()).unwrap();
-                self.ob.world().get(&FindForm::ByIndex(form_id))
-            })?
-            .unwrap();
-
-        // we have to collect the specials here because if we leave them as an iterator we'll end up
-        // trying to borrow the world while it's already mutably borrowed below
-        let specials: Vec<_> = ob_race
-            .spells()
-            .chain(ob_birthsign.spells())
-            .filter(|id| {
-                let spell: tes4::Spell = self
+        let known_effects = known_magic_effects(
+            ob_spells.iter().map(|(_, spell)| spell),
+            self.config.unlock_all_effects,
+            |effect_type| {
+                Ok(self
                     .ob
                     .world()
-                    .get(&FindForm::ByIndex(*id))
-                    .unwrap()
-                    .unwrap();
+                    .get_magic_effect(effect_type)?
+                    .allowed_for_spellmaking())
+            },
+        )?;
+        ob_player_ref.set_known_magic_effects(known_effects.into_iter().collect());
+
+        // we don't put abilities and diseases in the spell list because those need to be added to the player by the OBSE plugin
+        let (spell_ids, spells): (Vec<_>, Vec<_>) = ob_spells
+            .into_iter()
+            .filter(|(_, spell)| {
                 matches!(
                     spell.spell_type,
                     tes4::SpellType::Power | tes4::SpellType::LesserPower | tes4::SpellType::Spell
                 )
             })
+            .unzip();
+        let mut spell_irefs: Vec<_> = self
+            .add_converted_forms(&spell_ids, &spells)?
+            .into_iter()
+            .map(|(_, iref)| iref)
             .collect();
 
-        let mut spell_irefs = Vec::with_capacity(ob_spells.len());
-        for (id, spell) in ob_spells {
-            // we don't put abilities and diseases in the spell list because those need to be added to the player by the OBSE plugin
-            if matches!(
-                spell.spell_type,
-                tes4::SpellType::Power | tes4::SpellType::LesserPower | tes4::SpellType::Spell
-            ) {
-                let (_, iref) = self.add_form_to_both(id, &spell)?;
-                spell_irefs.push(iref);
-            }
-        }
-
         self.with_save_mut(|save| {
-            for special in specials {
-                let iref = save.insert_form_id(special);
-                spell_irefs.push(iref);
+            for special in &racial_spells.ob_spell_list {
+                let iref = save.insert_form_id(*special);
+                // a converted spell could have been mapped to one of the specials
+                if !spell_irefs.contains(&iref) {
+                    spell_irefs.push(iref);
+                }
             }
 
             ob_player_base.set_spells(spell_irefs);
@@ -832,21 +1629,14 @@ impl MorrowindToOblivion {
         ob_player_base: &mut ActorChange,
         ob_player_ref: &mut PlayerReferenceChange,
         ob_class: &tes4::Class,
+        racial_spells: &RacialSpells,
     ) -> Result<()> {
         let mw_race: tes3::Race = self
             .mw
             .world
             .get(self.player_base.race())?
             .ok_or_else(|| anyhow!("Could not find Morrowind player race"))?;
-        let mw_sign: Option<tes3::Birthsign> = match self.player_data.birthsign() {
-            Some(birthsign) => self.mw.world.get(birthsign)?,
-            None => None,
-        };
-        let spells_to_suppress: HashSet<_> = if let Some(ref sign) = mw_sign {
-            mw_race.specials().chain(sign.spells()).collect()
-        } else {
-            mw_race.specials().collect()
-        };
+        let spells_to_suppress = &racial_spells.mw_suppressed;
 
         // we're going to start by pulling in the active spells. we need this here so we can subtract
         // effects before we convert the stats.
@@ -862,11 +1652,26 @@ impl MorrowindToOblivion {
         let mut current_magicka_modifier = 0.;
         let mut current_fatigue_modifier = 0.;
 
+        if self.player_data.is_in_jail() {
+            // the sentence is served when the player is released, so its skill changes aren't in
+            // the save yet
+            let days = (self.player_data.bounty() / self.mw.days_in_prison_mod.max(1)).max(1);
+            for (skill, change) in jail_skill_changes(days as u32) {
+                let base = self.player_ref.skills[skill].base;
+                base_skill_modifiers[skill] += (base + change).clamp(0, 100) - base;
+            }
+            self.warn(format!(
+                "The Morrowind save was made while the player was in jail; the converted player \
+                has served the {} day(s) of their sentence and gained or lost skills for it",
+                days
+            ));
+        }
+
         let active_player_spells = self
             .active_spells
             .iter()
             .filter(|s| s.effects().any(|e| e.affected_actor() == "PlayerSaveGame"));
-        let mut new_active_spells = HashMap::new();
+        let mut new_active_spells = BTreeMap::new();
         for active_spell in active_player_spells {
             let id = active_spell.id();
 
@@ -875,166 +1680,175 @@ impl MorrowindToOblivion {
                 None => continue,
             };
 
+            let is_affliction = is_affliction(mw_spell.spell_type());
+            if is_affliction && self.config.affliction_policy == AfflictionPolicy::Bake {
+                // the effects are already part of the Morrowind base stats, so leaving them there
+                // bakes them into the converted stats
+                self.report_skipped(
+                    ReportCategory::Spell,
+                    id,
+                    "affliction; its effects were made part of the player's base stats",
+                );
+                continue;
+            }
+            // effects that are part of the Morrowind base stats, which have to be removed from them
+            let is_permanent = is_affliction || mw_spell.spell_type() == tes3::SpellType::Ability;
+
             if !spells_to_suppress.contains(id) {
                 let form_id = match self.form_map.borrow().get(active_spell.id()) {
                     Some(form_id) => *form_id,
                     None => {
-                        if let Some(ob_spell) = self.convert_spell(&mw_spell)? {
+                        let ob_spell = if is_affliction {
+                            self.convert_affliction(&mw_spell)?
+                        } else {
+                            self.convert_spell(&mw_spell)?
+                        };
+                        if let Some(ob_spell) = ob_spell {
                             self.with_save_mut::<Result<FormId, TesError>, _>(|save| {
                                 let iref = save.add_form(&ob_spell)?;
                                 Ok(save.iref_to_form_id(iref).unwrap())
                             })?
                         } else {
+                            if is_affliction {
+                                self.report_skipped(
+                                    ReportCategory::Spell,
+                                    id,
+                                    "affliction; none of its effects could be converted",
+                                );
+                            }
                             continue;
                         }
                     }
                 };
 
+                if is_affliction {
+                    self.report_converted(ReportCategory::Spell, id);
+                }
+
                 let seconds_active = active_spell.effects().last().unwrap().seconds_active();
                 new_active_spells.insert(form_id, seconds_active);
             }
 
-            let (attribute_modifiers, skill_modifiers) =
-                if mw_spell.spell_type() == tes3::SpellType::Ability {
-                    (&mut base_attribute_modifiers, &mut base_skill_modifiers)
-                } else {
-                    (
-                        &mut current_attribute_modifiers,
-                        &mut current_skill_modifiers,
-                    )
-                };
+            let (attribute_modifiers, skill_modifiers) = if is_permanent {
+                (&mut base_attribute_modifiers, &mut base_skill_modifiers)
+            } else {
+                (
+                    &mut current_attribute_modifiers,
+                    &mut current_skill_modifiers,
+                )
+            };
 
-            for effect in active_spell
-                .effects()
-                .filter(|e| e.affected_actor() == "PlayerSaveGame")
-            {
-                if let Some(base_effect) = mw_spell
-                    .iter_effects()
-                    .enumerate()
-                    .filter(|(p, _)| *p as i32 == effect.index())
-                    .map(|(_, e)| e)
-                    .last()
-                {
-                    match base_effect.effect() {
-                        tes3::MagicEffectType::FortifyAttribute => {
-                            let magnitude = effect.magnitude() as f32;
-                            let attribute = base_effect.attribute().unwrap();
-                            attribute_modifiers[attribute] -= magnitude;
-                            match attribute {
-                                Attribute::Strength
-                                | Attribute::Willpower
-                                | Attribute::Agility
-                                | Attribute::Endurance => base_fatigue_modifier -= magnitude,
-                                Attribute::Intelligence => base_magicka_modifier -= magnitude,
-                                _ => (),
-                            }
-                        }
-                        tes3::MagicEffectType::FortifySkill => {
-                            skill_modifiers[base_effect.skill().unwrap()] -= effect.magnitude()
-                        }
-                        tes3::MagicEffectType::AbsorbAttribute
-                        | tes3::MagicEffectType::DrainAttribute => {
-                            let magnitude = effect.magnitude() as f32;
-                            let attribute = base_effect.attribute().unwrap();
-                            attribute_modifiers[attribute] += magnitude;
-                            match attribute {
-                                Attribute::Strength
-                                | Attribute::Willpower
-                                | Attribute::Agility
-                                | Attribute::Endurance => base_fatigue_modifier += magnitude,
-                                Attribute::Intelligence => base_magicka_modifier += magnitude,
-                                _ => (),
-                            }
-                        }
-                        tes3::MagicEffectType::AbsorbSkill | tes3::MagicEffectType::DrainSkill => {
-                            skill_modifiers[base_effect.skill().unwrap()] += effect.magnitude()
-                        }
-                        tes3::MagicEffectType::FortifyHealth => {
-                            *if mw_spell.spell_type() == tes3::SpellType::Ability {
-                                &mut base_health_modifier
-                            } else {
-                                &mut current_health_modifier
-                            } -= effect.magnitude() as f32
-                        }
-                        tes3::MagicEffectType::DrainHealth => {
-                            *if mw_spell.spell_type() == tes3::SpellType::Ability {
-                                &mut base_health_modifier
-                            } else {
-                                &mut current_health_modifier
-                            } += effect.magnitude() as f32
-                        }
-                        tes3::MagicEffectType::FortifyMaximumMagicka => {
-                            *if mw_spell.spell_type() == tes3::SpellType::Ability {
-                                &mut base_magicka_modifier
-                            } else {
-                                &mut current_magicka_modifier
-                            } -= (effect.magnitude() as f32) / 10.
-                                * self.player_ref.attributes[Attribute::Intelligence].current
-                        }
-                        tes3::MagicEffectType::FortifyMagicka => {
-                            *if mw_spell.spell_type() == tes3::SpellType::Ability {
-                                &mut base_magicka_modifier
-                            } else {
-                                &mut current_magicka_modifier
-                            } -= effect.magnitude() as f32
-                        }
-                        tes3::MagicEffectType::DrainMagicka => {
-                            *if mw_spell.spell_type() == tes3::SpellType::Ability {
-                                &mut base_magicka_modifier
-                            } else {
-                                &mut current_magicka_modifier
-                            } += effect.magnitude() as f32
-                        }
-                        tes3::MagicEffectType::FortifyFatigue => {
-                            *if mw_spell.spell_type() == tes3::SpellType::Ability {
-                                &mut base_fatigue_modifier
-                            } else {
-                                &mut current_fatigue_modifier
-                            } -= effect.magnitude() as f32
+            let base_effects: Vec<_> = mw_spell.iter_effects().collect();
+            let (matched_effects, unmatched_effects) = match_effects_by_index(
+                &base_effects,
+                active_spell
+                    .effects()
+                    .filter(|e| e.affected_actor() == "PlayerSaveGame")
+                    .map(|e| (e.index(), e)),
+            );
+            if !unmatched_effects.is_empty() {
+                self.warn(format!(
+                    "{} active effect(s) on the player from spell {} refer to effects the spell \
+                    doesn't have, possibly because a mod changed the spell after the save was made; \
+                    those effects were ignored",
+                    unmatched_effects.len(),
+                    id
+                ));
+            }
+
+            for (effect, base_effect) in matched_effects {
+                match base_effect.effect() {
+                    tes3::MagicEffectType::FortifyAttribute => {
+                        let magnitude = effect.magnitude() as f32;
+                        let attribute = base_effect.attribute().unwrap();
+                        attribute_modifiers[attribute] -= magnitude;
+                        match attribute {
+                            Attribute::Strength
+                            | Attribute::Willpower
+                            | Attribute::Agility
+                            | Attribute::Endurance => base_fatigue_modifier -= magnitude,
+                            Attribute::Intelligence => base_magicka_modifier -= magnitude,
+                            _ => (),
                         }
-                        tes3::MagicEffectType::DrainFatigue => {
-                            *if mw_spell.spell_type() == tes3::SpellType::Ability {
-                                &mut base_fatigue_modifier
-                            } else {
-                                &mut current_fatigue_modifier
-                            } += effect.magnitude() as f32
+                    }
+                    tes3::MagicEffectType::FortifySkill => {
+                        skill_modifiers[base_effect.skill().unwrap()] -= effect.magnitude()
+                    }
+                    tes3::MagicEffectType::AbsorbAttribute
+                    | tes3::MagicEffectType::DrainAttribute => {
+                        let magnitude = effect.magnitude() as f32;
+                        let attribute = base_effect.attribute().unwrap();
+                        attribute_modifiers[attribute] += magnitude;
+                        match attribute {
+                            Attribute::Strength
+                            | Attribute::Willpower
+                            | Attribute::Agility
+                            | Attribute::Endurance => base_fatigue_modifier += magnitude,
+                            Attribute::Intelligence => base_magicka_modifier += magnitude,
+                            _ => (),
                         }
-                        _ => (),
                     }
+                    tes3::MagicEffectType::AbsorbSkill | tes3::MagicEffectType::DrainSkill => {
+                        skill_modifiers[base_effect.skill().unwrap()] += effect.magnitude()
+                    }
+                    tes3::MagicEffectType::FortifyHealth => {
+                        *if is_permanent {
+                            &mut base_health_modifier
+                        } else {
+                            &mut current_health_modifier
+                        } -= effect.magnitude() as f32
+                    }
+                    tes3::MagicEffectType::DrainHealth => {
+                        *if is_permanent {
+                            &mut base_health_modifier
+                        } else {
+                            &mut current_health_modifier
+                        } += effect.magnitude() as f32
+                    }
+                    tes3::MagicEffectType::FortifyMaximumMagicka => {
+                        *if is_permanent {
+                            &mut base_magicka_modifier
+                        } else {
+                            &mut current_magicka_modifier
+                        } -= (effect.magnitude() as f32) / 10.
+                            * self.player_ref.attributes[Attribute::Intelligence].current
+                    }
+                    tes3::MagicEffectType::FortifyMagicka => {
+                        *if is_permanent {
+                            &mut base_magicka_modifier
+                        } else {
+                            &mut current_magicka_modifier
+                        } -= effect.magnitude() as f32
+                    }
+                    tes3::MagicEffectType::DrainMagicka => {
+                        *if is_permanent {
+                            &mut base_magicka_modifier
+                        } else {
+                            &mut current_magicka_modifier
+                        } += effect.magnitude() as f32
+                    }
+                    tes3::MagicEffectType::FortifyFatigue => {
+                        *if is_permanent {
+                            &mut base_fatigue_modifier
+                        } else {
+                            &mut current_fatigue_modifier
+                        } -= effect.magnitude() as f32
+                    }
+                    tes3::MagicEffectType::DrainFatigue => {
+                        *if is_permanent {
+                            &mut base_fatigue_modifier
+                        } else {
+                            &mut current_fatigue_modifier
+                        } += effect.magnitude() as f32
+                    }
+                    _ => (),
                 }
             }
         }
 
-        let ob_race: tes4::Race = self
-            .with_save(|save| {
-                let form_id = save.iref_to_form_id(ob_player_ref.race()).unwrap();
-                self.ob.world().get(&FindForm::ByIndex(form_id))
-            })?
-            .unwrap();
-
-        // FIXME: these unwraps are not safe
-        let ob_birthsign: tes4::Birthsign = self
-            .with_save(|save| {
-                let form_id = save.iref_to_form_id(ob_player_ref.birthsign()).unwrap();
-                self.ob.world().get(&FindForm::ByIndex(form_id))
-            })?
-            .unwrap();
-
-        for special in ob_race.spells().chain(ob_birthsign.spells()).filter(|id| {
-            let spell: tes4::Spell = self
-                .ob
-                .world()
-                .get(&FindForm::ByIndex(*id))
-                .ok()
-                .unwrap()
-                .unwrap();
-            !matches!(
-                spell.spell_type,
-                tes4::SpellType::Power | tes4::SpellType::LesserPower | tes4::SpellType::Spell
-            )
-        }) {
+        for special in &racial_spells.ob_abilities {
             // seconds active doesn't matter for abilities
-            new_active_spells.insert(special, 0.);
+            new_active_spells.insert(*special, 0.);
         }
 
         self.with_cosave_mut(|cosave| {
@@ -1048,9 +1862,7 @@ impl MorrowindToOblivion {
         ob_player_ref.is_female = self.player_base.is_female();
 
         let damage = ob_player_ref.damage_modifiers_mut();
-        for (_, value) in damage.iter_mut() {
-            *value = 0.;
-        }
+        damage.clear();
 
         // set attributes
         let attributes = ob_player_base
@@ -1062,9 +1874,8 @@ impl MorrowindToOblivion {
             let current = self.player_ref.attributes[attribute].current
                 + base_attribute_modifiers[attribute]
                 + current_attribute_modifiers[attribute];
-            *value = base as u8;
-            // FIXME: should these be negative?
-            damage[ActorValue::from(attribute)] = current - base;
+            *value = self.convert_stat(base, &format!("{:?} attribute", attribute));
+            damage.set_from_values(ActorValue::from(attribute), base, current);
         }
 
         // set skills
@@ -1072,7 +1883,7 @@ impl MorrowindToOblivion {
             .skills_mut()
             .ok_or_else(|| anyhow!("Oblivion player base has no skills"))?;
         for (skill, value) in skills.iter_mut() {
-            *value = match Oblivion::morrowind_skill(skill) {
+            let base = match Oblivion::morrowind_skill(skill) {
                 tes3::Skill::LongBlade => {
                     let base = self.config.combine_strategy.combine(
                         self.player_ref.skills[tes3::Skill::LongBlade].base
@@ -1088,8 +1899,7 @@ impl MorrowindToOblivion {
                             + base_skill_modifiers[tes3::Skill::ShortBlade]
                             + current_skill_modifiers[tes3::Skill::ShortBlade],
                     );
-                    // FIXME: should these be negative?
-                    damage[ActorValue::Blade] = (current - base) as f32;
+                    damage.set_from_values(ActorValue::Blade, base as f32, current as f32);
                     base
                 }
                 tes3::Skill::Blunt => {
@@ -1107,8 +1917,7 @@ impl MorrowindToOblivion {
                             + base_skill_modifiers[tes3::Skill::Blunt]
                             + current_skill_modifiers[tes3::Skill::Blunt],
                     );
-                    // FIXME: should these be negative?
-                    damage[ActorValue::Blunt] = (current - base) as f32;
+                    damage.set_from_values(ActorValue::Blunt, base as f32, current as f32);
                     base
                 }
                 mw_skill => {
@@ -1117,11 +1926,11 @@ impl MorrowindToOblivion {
                     let current = self.player_ref.skills[mw_skill].current
                         + base_skill_modifiers[mw_skill]
                         + current_skill_modifiers[mw_skill];
-                    // FIXME: should these be negative?
-                    damage[ActorValue::from(skill)] = (current - base) as f32;
+                    damage.set_from_values(ActorValue::from(skill), base as f32, current as f32);
                     base
                 }
-            } as u8;
+            };
+            *value = self.convert_stat(base, &format!("{:?} skill", skill));
         }
 
         // remove any active effects
@@ -1199,16 +2008,24 @@ impl MorrowindToOblivion {
         let health_delta = max_health * (1. - health_ratio);
         ob_player_ref.set_health_delta(-health_delta);
 
-        ob_player_base.set_base_health(Some(level_health as u32));
+        ob_player_base.set_base_health(Some(self.convert_stat(level_health, "level-up health")));
+        let level = self.convert_stat(self.player_base.level, "level");
         let mut base = ob_player_base.actor_base_mut().unwrap();
-        base.level = self.player_base.level as i16;
+        base.level = level;
         // this will be recalculated once we add back racial and birthsign bonuses
         base.magicka = 0;
         base.fatigue = 0;
 
-        ob_player_ref.set_name(String::from(self.player_base.name().unwrap_or("")))?;
+        let name = self
+            .names
+            .convert(self.player_base.name().unwrap_or(""), "the player")?;
+        ob_player_ref.set_name(name)?;
 
         ob_player_ref.major_skill_advancements = self.player_data.level_progress;
+        // Morrowind doesn't limit how often the player can train per level, so its saves have no
+        // count of training sessions to carry over. starting from zero gives the player the full
+        // number of sessions for the rest of the level.
+        ob_player_ref.set_training_sessions_used(0);
 
         for (spec, value) in ob_player_ref.spec_increases.iter_mut() {
             *value = self.player_data.spec_increases[spec];
@@ -1241,7 +2058,7 @@ impl MorrowindToOblivion {
             *value = self.player_data.skill_progress[skill]
                 / self.mw.calculate_skill_xp(
                     skill,
-                    self.player_ref.skills[skill].base as u16,
+                    clamp_cast(self.player_ref.skills[skill].base).0,
                     &self.class,
                 );
         }
@@ -1282,6 +2099,23 @@ impl MorrowindToOblivion {
         None
     }
 
+    /// Finds an Oblivion form of type `T` mapped from a Morrowind item with the given model
+    fn get_ob_template<T>(&self, mw_model: &str) -> Option<T>
+    where
+        T: Form<Field = Tes4Field, Record = Tes4Record>,
+    {
+        let ob_items = self.model_map.get(&mw_model.to_lowercase())?;
+        let world = self.ob.world();
+        ob_items.iter().find_map(|ob_item| {
+            let record = world.get_record(&FindForm::ByIndex(*ob_item))?;
+            if record.name() == T::RECORD_TYPE {
+                T::read(&*record).ok()
+            } else {
+                None
+            }
+        })
+    }
+
     fn get_ob_icon(&self, mw_icon: &str) -> Option<String> {
         if let Some(ob_items) = self.icon_map.get(&mw_icon.to_lowercase()) {
             for ob_item in ob_items {
@@ -1297,35 +2131,12 @@ impl MorrowindToOblivion {
     }
 
     fn convert_potion(&self, mw_potion: &tes3::Potion) -> Result<Option<tes4::Potion>> {
-        // can't convert scripted items
-        if mw_potion.script().is_some() {
-            return Ok(None);
-        }
-
-        let mut ob_potion = tes4::Potion::new(
-            mw_potion.id.clone(),
-            mw_potion.name.clone().unwrap_or_else(String::new),
-        );
-
-        ob_potion.is_auto_calc = mw_potion.alchemy_data.is_auto_calc;
-        ob_potion.value = mw_potion.alchemy_data.value;
-        ob_potion.weight = mw_potion.alchemy_data.weight;
-
-        let mut converted_any = false;
-        for effect in &mw_potion.effects {
-            if let Some(ob_effect) = self.convert_effect(effect)? {
-                ob_potion.add_effect(ob_effect);
-                converted_any = true;
-            }
-        }
-
-        // only add the potion if we successfully converted at least one effect
-        Ok(if converted_any {
-            ob_potion.use_auto_graphics();
-            Some(ob_potion)
-        } else {
-            None
-        })
+        Morrowind::convert_potion(
+            mw_potion,
+            self.ob.potion_graphics(),
+            &self.config.combine_strategy,
+            &self.names,
+        )
     }
 
     fn convert_enchantment(
@@ -1352,19 +2163,91 @@ impl MorrowindToOblivion {
         ob_enchantment.data.is_auto_calc = mw_enchantment.data.is_auto_calc;
 
         let mut converted_any = false;
+        let mut dropped_any = false;
         for effect in mw_enchantment.iter_effects() {
-            if let Some(ob_effect) = self.convert_effect(effect)? {
-                ob_enchantment.add_effect(ob_effect);
-                converted_any = true;
+            let effect_id = format!(
+                "{:?} effect of enchantment {}",
+                effect.effect(),
+                mw_enchantment.id
+            );
+            let ob_effect = match self.convert_effect(effect)? {
+                Some(ob_effect) => ob_effect,
+                None => {
+                    self.report_skipped(
+                        ReportCategory::Spell,
+                        &effect_id,
+                        "no Oblivion equivalent",
+                    );
+                    dropped_any = true;
+                    continue;
+                }
+            };
+
+            // scrolls can carry any spell effect, but enchanted equipment is limited to the
+            // effects the player could enchant with
+            if enchantment_type != tes4::EnchantmentType::Scroll {
+                let allowed = match self.ob.world().get_magic_effect(ob_effect.effect_type()) {
+                    Ok(base_effect) => base_effect.allowed_for_enchanting(),
+                    Err(e) => {
+                        self.warn(format!(
+                            "Could not look up the Oblivion magic effect for the {}: {}",
+                            effect_id, e
+                        ));
+                        false
+                    }
+                };
+
+                if !allowed {
+                    self.report_skipped(
+                        ReportCategory::Spell,
+                        &effect_id,
+                        "the effect can't be used to enchant equipment in Oblivion",
+                    );
+                    dropped_any = true;
+                    continue;
+                }
             }
+
+            ob_enchantment.add_effect(ob_effect);
+            converted_any = true;
         }
 
         // only add the enchantment if we successfully converted at least one effect
-        Ok(if converted_any {
-            Some(ob_enchantment)
-        } else {
-            None
-        })
+        if !converted_any {
+            return Ok(None);
+        }
+
+        if dropped_any {
+            self.recalculate_enchantment_cost(&mut ob_enchantment, &mw_enchantment.id);
+        }
+
+        Ok(Some(ob_enchantment))
+    }
+
+    /// Recalculates the cost of an enchantment that lost some of its effects in conversion
+    ///
+    /// The cost is recalculated from the remaining effects, and the charge is scaled by the same
+    /// amount so the item can still be used as many times as before. If the cost can't be
+    /// calculated, the enchantment keeps its original cost and charge.
+    fn recalculate_enchantment_cost(&self, ob_enchantment: &mut tes4::Enchantment, id: &str) {
+        let cost = match self.ob.world().calculate_magic_cost(ob_enchantment) {
+            Ok(cost) => cost,
+            Err(e) => {
+                self.warn(format!(
+                    "Could not recalculate the cost of enchantment {}: {}",
+                    id, e
+                ));
+                return;
+            }
+        };
+
+        let new_cost = cost.round() as u32;
+        ob_enchantment.data.charge = rescale_charge(
+            ob_enchantment.data.charge,
+            ob_enchantment.data.cost,
+            new_cost,
+        );
+        ob_enchantment.data.cost = new_cost;
     }
 
     fn convert_item<T: Tes3Item, U: Tes4Item>(&self, mw_item: &T, ob_item: &mut U) -> bool {
@@ -1410,7 +2293,8 @@ impl MorrowindToOblivion {
                 if let Some(ob_enchantment) =
                     self.convert_enchantment(&mw_enchantment, ob_enchantable.enchantment_type())?
                 {
-                    let form_id = self.add_form_to_mod(mw_enchantment_id, &ob_enchantment)?;
+                    let (form_id, _) =
+                        self.add_converted_form(mw_enchantment_id, &ob_enchantment)?;
 
                     ob_enchantable.set_enchantment(Some(form_id));
                     ob_enchantable
@@ -1431,10 +2315,11 @@ impl MorrowindToOblivion {
             return Ok(None);
         }
 
-        let mut ob_ammo = tes4::Ammo::new(
-            String::from(mw_weapon.id()),
-            String::from(mw_weapon.name().unwrap_or("")),
-        );
+        let name = self.names.convert(
+            mw_weapon.name().unwrap_or(""),
+            &format!("ammunition {}", mw_weapon.id()),
+        )?;
+        let mut ob_ammo = tes4::Ammo::new(String::from(mw_weapon.id()), name);
 
         if !self.convert_item(mw_weapon, &mut ob_ammo) {
             return Ok(None);
@@ -1457,10 +2342,11 @@ impl MorrowindToOblivion {
             return Ok(None);
         }
 
-        let mut ob_weapon = tes4::Weapon::new(
-            String::from(mw_weapon.id()),
-            String::from(mw_weapon.name().unwrap_or("")),
-        );
+        let name = self.names.convert(
+            mw_weapon.name().unwrap_or(""),
+            &format!("weapon {}", mw_weapon.id()),
+        )?;
+        let mut ob_weapon = tes4::Weapon::new(String::from(mw_weapon.id()), name);
 
         if !self.convert_item(mw_weapon, &mut ob_weapon) {
             return Ok(None);
@@ -1496,6 +2382,77 @@ impl MorrowindToOblivion {
         Ok(Some(ob_weapon))
     }
 
+    /// Converts armor by copying the Oblivion armor that an item with the same model maps to
+    ///
+    /// Oblivion armor needs male and female models for both the ground and the body, plus the
+    /// slots it covers, so only the item's stats come from Morrowind. The armor rating and weight
+    /// class are kept from the Oblivion armor, because the two games scale armor ratings
+    /// differently.
+    fn convert_armor(&self, mw_armor: &tes3::Armor) -> Result<Option<tes4::Armor>> {
+        // can't convert scripted items
+        if mw_armor.script().is_some() {
+            return Ok(None);
+        }
+
+        let template: Option<tes4::Armor> = mw_armor.model().and_then(|m| self.get_ob_template(m));
+        let mut ob_armor = match template {
+            Some(ob_armor) => ob_armor,
+            None => return Ok(None),
+        };
+
+        let name = self.names.convert(
+            mw_armor.name().unwrap_or(""),
+            &format!("armor {}", mw_armor.id()),
+        )?;
+        ob_armor.set_editor_id(String::from(mw_armor.id()));
+        ob_armor.set_name(name);
+        ob_armor.set_script(None);
+        ob_armor.set_enchantment(None);
+        ob_armor.set_enchantment_points(None);
+        ob_armor.set_value(mw_armor.value());
+        ob_armor.set_weight(mw_armor.weight());
+
+        self.convert_enchantable(mw_armor, &mut ob_armor)?;
+
+        ob_armor.data.health =
+            (mw_armor.data.health as f32 / self.config.equipment_durability_ratio) as u32;
+
+        Ok(Some(ob_armor))
+    }
+
+    /// Converts clothing by copying the Oblivion clothing that an item with the same model maps to
+    ///
+    /// See [`convert_armor`](#method.convert_armor).
+    fn convert_clothing(&self, mw_clothing: &tes3::Clothing) -> Result<Option<tes4::Clothing>> {
+        // can't convert scripted items
+        if mw_clothing.script().is_some() {
+            return Ok(None);
+        }
+
+        let template: Option<tes4::Clothing> =
+            mw_clothing.model().and_then(|m| self.get_ob_template(m));
+        let mut ob_clothing = match template {
+            Some(ob_clothing) => ob_clothing,
+            None => return Ok(None),
+        };
+
+        let name = self.names.convert(
+            mw_clothing.name().unwrap_or(""),
+            &format!("clothing {}", mw_clothing.id()),
+        )?;
+        ob_clothing.set_editor_id(String::from(mw_clothing.id()));
+        ob_clothing.set_name(name);
+        ob_clothing.set_script(None);
+        ob_clothing.set_enchantment(None);
+        ob_clothing.set_enchantment_points(None);
+        ob_clothing.set_value(mw_clothing.value());
+        ob_clothing.set_weight(mw_clothing.weight());
+
+        self.convert_enchantable(mw_clothing, &mut ob_clothing)?;
+
+        Ok(Some(ob_clothing))
+    }
+
     fn convert_book(&self, mw_book: &tes3::Book) -> Result<Option<tes4::Book>> {
         // can't convert scripted items
         if mw_book.script().is_some() {
@@ -1515,26 +2472,167 @@ impl MorrowindToOblivion {
             }),
         );
 
-        let mut ob_book = tes4::Book::new(
-            String::from(mw_book.id()),
-            String::from(mw_book.name().unwrap_or("")),
-            ob_text,
-        );
+        let name = self.names.convert(
+            mw_book.name().unwrap_or(""),
+            &format!("book {}", mw_book.id()),
+        )?;
+        let mut ob_book = tes4::Book::new(String::from(mw_book.id()), name, ob_text);
+
+        if !self.convert_item(mw_book, &mut ob_book) {
+            return Ok(None);
+        }
+
+        self.convert_enchantable(mw_book, &mut ob_book)?;
+
+        if let Some(mw_skill) = mw_book.data.skill {
+            // in this case, we just lose the skill if it can't be converted; at least you'll still be able to read it
+            ob_book.data.skill = Morrowind::oblivion_skill(mw_skill);
+        }
+
+        ob_book.set_is_scroll(mw_book.data.is_scroll);
+
+        Ok(Some(ob_book))
+    }
+
+    /// Gets the maximum condition and charge of a Morrowind item
+    fn mw_item_base_stats(&self, mw_id: &str) -> Result<ItemBaseStats> {
+        let record = match self.mw.world.get_record(mw_id)? {
+            Some(record) => record,
+            None => return Ok(ItemBaseStats::default()),
+        };
+
+        let (max_condition, enchantment) = match record.name() {
+            tes3::Weapon::RECORD_TYPE => {
+                let mw_weapon = tes3::Weapon::read(&*record)?;
+                (
+                    Some(mw_weapon.data.health as u32),
+                    mw_weapon.enchantment().map(String::from),
+                )
+            }
+            tes3::Armor::RECORD_TYPE => {
+                let mw_armor = tes3::Armor::read(&*record)?;
+                (
+                    Some(mw_armor.data.health),
+                    mw_armor.enchantment().map(String::from),
+                )
+            }
+            tes3::Clothing::RECORD_TYPE => {
+                let mw_clothing = tes3::Clothing::read(&*record)?;
+                (None, mw_clothing.enchantment().map(String::from))
+            }
+            tes3::Book::RECORD_TYPE => {
+                let mw_book = tes3::Book::read(&*record)?;
+                (None, mw_book.enchantment().map(String::from))
+            }
+            _ => (None, None),
+        };
+
+        let max_charge = match enchantment {
+            Some(id) => self
+                .mw
+                .world
+                .get::<tes3::Enchantment>(&id)?
+                .map(|e| e.data.charge as f32),
+            None => None,
+        };
 
-        if !self.convert_item(mw_book, &mut ob_book) {
-            return Ok(None);
-        }
+        Ok(ItemBaseStats::new(max_condition, max_charge))
+    }
 
-        self.convert_enchantable(mw_book, &mut ob_book)?;
+    /// Gets the maximum condition and charge of an Oblivion item
+    fn ob_enchantable_stats<T: Tes4Enchantable>(
+        ob_item: &T,
+        max_condition: Option<u32>,
+    ) -> ItemBaseStats {
+        ItemBaseStats::new(
+            max_condition,
+            ob_item.enchantment_points().map(|p| p as f32),
+        )
+    }
 
-        if let Some(mw_skill) = mw_book.data.skill {
-            // in this case, we just lose the skill if it can't be converted; at least you'll still be able to read it
-            ob_book.data.skill = Morrowind::oblivion_skill(mw_skill);
-        }
+    /// Gets the maximum condition and charge of an Oblivion item that already exists
+    fn ob_item_base_stats(&self, form_id: FormId) -> Result<ItemBaseStats> {
+        let world = self.ob.world();
+        let record = match world.get_record(&FindForm::ByIndex(form_id)) {
+            Some(record) => record,
+            None => return Ok(ItemBaseStats::default()),
+        };
 
-        ob_book.set_is_scroll(mw_book.data.is_scroll);
+        Ok(match record.name() {
+            tes4::Weapon::RECORD_TYPE => {
+                let ob_weapon = tes4::Weapon::read(&*record)?;
+                MorrowindToOblivion::ob_enchantable_stats(&ob_weapon, Some(ob_weapon.data.health))
+            }
+            tes4::Ammo::RECORD_TYPE => {
+                MorrowindToOblivion::ob_enchantable_stats(&tes4::Ammo::read(&*record)?, None)
+            }
+            tes4::Armor::RECORD_TYPE => {
+                let ob_armor = tes4::Armor::read(&*record)?;
+                MorrowindToOblivion::ob_enchantable_stats(&ob_armor, Some(ob_armor.data.health))
+            }
+            tes4::Clothing::RECORD_TYPE => {
+                MorrowindToOblivion::ob_enchantable_stats(&tes4::Clothing::read(&*record)?, None)
+            }
+            tes4::Book::RECORD_TYPE => {
+                MorrowindToOblivion::ob_enchantable_stats(&tes4::Book::read(&*record)?, None)
+            }
+            _ => ItemBaseStats::default(),
+        })
+    }
 
-        Ok(Some(ob_book))
+    /// Is an existing Oblivion item a ring or amulet?
+    fn ob_is_accessory(&self, form_id: FormId) -> Result<bool> {
+        let world = self.ob.world();
+        Ok(match world.get_record(&FindForm::ByIndex(form_id)) {
+            Some(record) if record.name() == tes4::Clothing::RECORD_TYPE => {
+                tes4::Clothing::read(&*record)?.is_accessory()
+            }
+            _ => false,
+        })
+    }
+
+    /// Gets the IDs of the bound items summoned by the player's active effects, in lowercase
+    ///
+    /// Bound items vanish when the effects that summoned them end, so they aren't really part of
+    /// the player's inventory. Effects from spells are converted with the player's other active
+    /// spells, which summons the Oblivion equivalents when they're re-applied, but effects from
+    /// enchantments and potions aren't. Each ID maps to whether any effect that summoned the item
+    /// is converted.
+    fn active_bound_items(&self) -> Result<HashMap<String, bool>> {
+        let world = &self.mw.world;
+        let mut bound_items = HashMap::new();
+        for active_spell in self.active_spells.iter() {
+            let id = active_spell.id();
+            let base_effects: Vec<_> = match active_spell.magic_type() {
+                tes3::MagicType::Spell => world
+                    .get::<tes3::Spell>(id)?
+                    .map(|s| s.iter_effects().map(|e| e.effect()).collect()),
+                tes3::MagicType::Enchantment => world
+                    .get::<tes3::Enchantment>(id)?
+                    .map(|e| e.iter_effects().map(|e| e.effect()).collect()),
+                tes3::MagicType::Potion => world
+                    .get::<tes3::Potion>(id)?
+                    .map(|p| p.iter_effects().map(|e| e.effect()).collect()),
+            }
+            .unwrap_or_default();
+
+            let is_converted = active_spell.magic_type() == tes3::MagicType::Spell;
+            let (matched_effects, _) = match_effects_by_index(
+                &base_effects,
+                active_spell
+                    .effects()
+                    .filter(|e| e.affected_actor() == "PlayerSaveGame")
+                    .map(|e| (e.index(), ())),
+            );
+            for (_, effect_type) in matched_effects {
+                for (setting, default) in effect_type.bound_item_settings() {
+                    let item_id = Morrowind::get_string_setting(world, setting, default)?;
+                    *bound_items.entry(item_id.to_lowercase()).or_insert(false) |= is_converted;
+                }
+            }
+        }
+
+        Ok(bound_items)
     }
 
     fn convert_inventory(&self, ob_player_ref: &mut PlayerReferenceChange) -> Result<()> {
@@ -1544,11 +2642,35 @@ impl MorrowindToOblivion {
             .get(&FindForm::ByIndex(FORM_PLAYER))?
             .ok_or_else(|| anyhow!("Missing Oblivion player NPC record"))?;
 
-        let mut stacks = HashMap::new();
+        // stacks are keyed by iref so the inventory is written in the same order on every run
+        let mut stacks = BTreeMap::new();
         ob_player_ref.clear_inventory();
+        // bound items are left out of the list of unconverted items too, because they shouldn't
+        // be given back to the player later
+        let bound_items = self.active_bound_items()?;
         let mut mw_inventory: Vec<(&InventoryItem, bool)> = self
             .player_change
             .iter_inventory()
+            .filter(|item| match bound_items.get(&item.id.to_lowercase()) {
+                Some(true) => {
+                    self.report_skipped(
+                        ReportCategory::Item,
+                        &item.id,
+                        "bound item summoned by an active spell, which summons it again",
+                    );
+                    false
+                }
+                Some(false) => {
+                    self.report_skipped(
+                        ReportCategory::Item,
+                        &item.id,
+                        "bound item summoned by an enchantment or potion, whose effects aren't \
+                        converted; the item is lost",
+                    );
+                    false
+                }
+                None => true,
+            })
             .zip(repeat(false))
             .collect();
         // TODO: Oblivion stacks non-pristine items with the same properties but Morrowind doesn't.
@@ -1558,11 +2680,20 @@ impl MorrowindToOblivion {
             // the whole if block and we get errors when we call save_form
             let existing_mapping = self.form_map.borrow().get(&mw_item.id).copied();
             if mw_item.script.is_some() && existing_mapping.is_none() {
-                continue; // can't convert scripted items
+                self.report_skipped(
+                    ReportCategory::Item,
+                    &mw_item.id,
+                    "scripted items can't be converted",
+                );
+                continue;
             }
 
-            let iref = if let Some(form_id) = existing_mapping {
-                self.with_save_mut(|ob_save| ob_save.insert_form_id(form_id))
+            let (iref, ob_stats, is_accessory) = if let Some(form_id) = existing_mapping {
+                (
+                    self.with_save_mut(|ob_save| ob_save.insert_form_id(form_id)),
+                    self.ob_item_base_stats(form_id)?,
+                    self.ob_is_accessory(form_id)?,
+                )
             } else if let Some(record) = self.mw.world.get_record(&mw_item.id)? {
                 match record.name() {
                     tes3::Book::RECORD_TYPE => {
@@ -1571,16 +2702,34 @@ impl MorrowindToOblivion {
                             // FIXME: I don't think books work right when added in the save. no text appears,
                             //  but when I copy the same record into an ESP, it displays correctly. need
                             //  companion mod functionality.
-                            self.add_form_to_both(&mw_item.id, &ob_book)?.1
+                            (
+                                self.add_converted_form(&mw_item.id, &ob_book)?.1,
+                                MorrowindToOblivion::ob_enchantable_stats(&ob_book, None),
+                                false,
+                            )
                         } else {
+                            self.report_skipped(
+                                ReportCategory::Item,
+                                &mw_item.id,
+                                "no Oblivion equivalent",
+                            );
                             continue;
                         }
                     }
                     tes3::Potion::RECORD_TYPE => {
                         let mw_potion = tes3::Potion::read(&*record)?;
                         if let Some(ob_potion) = self.convert_potion(&mw_potion)? {
-                            self.add_form_to_both(&mw_item.id, &ob_potion)?.1
+                            (
+                                self.add_converted_form(&mw_item.id, &ob_potion)?.1,
+                                ItemBaseStats::default(),
+                                false,
+                            )
                         } else {
+                            self.report_skipped(
+                                ReportCategory::Item,
+                                &mw_item.id,
+                                "no Oblivion equivalent",
+                            );
                             continue;
                         }
                     }
@@ -1588,21 +2737,94 @@ impl MorrowindToOblivion {
                         let mw_weapon = tes3::Weapon::read(&*record)?;
                         if mw_weapon.data.weapon_type == tes3::WeaponType::Arrow {
                             if let Some(ob_ammo) = self.convert_ammo(&mw_weapon)? {
-                                self.add_form_to_both(&mw_item.id, &ob_ammo)?.1
+                                (
+                                    self.add_converted_form(&mw_item.id, &ob_ammo)?.1,
+                                    MorrowindToOblivion::ob_enchantable_stats(&ob_ammo, None),
+                                    false,
+                                )
                             } else {
+                                self.report_skipped(
+                                    ReportCategory::Item,
+                                    &mw_item.id,
+                                    "no Oblivion equivalent",
+                                );
                                 continue;
                             }
                         } else {
                             if let Some(ob_weapon) = self.convert_weapon(&mw_weapon)? {
-                                self.add_form_to_both(&mw_item.id, &ob_weapon)?.1
+                                (
+                                    self.add_converted_form(&mw_item.id, &ob_weapon)?.1,
+                                    MorrowindToOblivion::ob_enchantable_stats(
+                                        &ob_weapon,
+                                        Some(ob_weapon.data.health),
+                                    ),
+                                    false,
+                                )
                             } else {
+                                self.report_skipped(
+                                    ReportCategory::Item,
+                                    &mw_item.id,
+                                    "no Oblivion equivalent",
+                                );
                                 continue;
                             }
                         }
                     }
-                    _ => continue,
+                    tes3::Armor::RECORD_TYPE => {
+                        let mw_armor = tes3::Armor::read(&*record)?;
+                        if let Some(ob_armor) = self.convert_armor(&mw_armor)? {
+                            (
+                                self.add_converted_form(&mw_item.id, &ob_armor)?.1,
+                                MorrowindToOblivion::ob_enchantable_stats(
+                                    &ob_armor,
+                                    Some(ob_armor.data.health),
+                                ),
+                                false,
+                            )
+                        } else {
+                            self.report_skipped(
+                                ReportCategory::Item,
+                                &mw_item.id,
+                                "no Oblivion equivalent",
+                            );
+                            continue;
+                        }
+                    }
+                    tes3::Clothing::RECORD_TYPE => {
+                        let mw_clothing = tes3::Clothing::read(&*record)?;
+                        if let Some(ob_clothing) = self.convert_clothing(&mw_clothing)? {
+                            (
+                                self.add_converted_form(&mw_item.id, &ob_clothing)?.1,
+                                MorrowindToOblivion::ob_enchantable_stats(&ob_clothing, None),
+                                ob_clothing.is_accessory(),
+                            )
+                        } else {
+                            self.report_skipped(
+                                ReportCategory::Item,
+                                &mw_item.id,
+                                "no Oblivion equivalent",
+                            );
+                            continue;
+                        }
+                    }
+                    record_type => match ob_tool(&mw_item.id, record_type) {
+                        Some(form_id) => (
+                            self.with_save_mut(|ob_save| ob_save.insert_form_id(form_id)),
+                            self.ob_item_base_stats(form_id)?,
+                            false,
+                        ),
+                        None => {
+                            self.report_skipped(
+                                ReportCategory::Item,
+                                &mw_item.id,
+                                "items of this type can't be converted yet",
+                            );
+                            continue;
+                        }
+                    },
                 }
             } else {
+                self.report_skipped(ReportCategory::Item, &mw_item.id, "the item does not exist");
                 continue;
             };
 
@@ -1615,39 +2837,40 @@ impl MorrowindToOblivion {
 
             let mut properties = vec![];
             if mw_item.is_equipped {
-                // FIXME: look up clothing type of Oblivion form and use EquippedAccessory if ring or amulet
-                properties.push(Property::EquippedItem);
+                properties.push(if is_accessory {
+                    Property::EquippedAccessory
+                } else {
+                    Property::EquippedItem
+                });
             }
 
             if mw_item.count > 1 {
                 properties.push(Property::AffectedItemCount(mw_item.count as u16));
             }
 
-            if let Some(durability) = mw_item.remaining_durability {
-                // TODO: convert Morrowind integer health to Oblivion float health
-            }
+            if mw_item.remaining_durability.is_some() || mw_item.enchantment_charge.is_some() {
+                let mw_stats = self.mw_item_base_stats(&mw_item.id)?;
+
+                if let Some(health) = mw_item
+                    .remaining_durability
+                    .and_then(|d| mw_condition_to_ob(d, &mw_stats, &ob_stats))
+                {
+                    properties.push(Property::ItemHealth(health));
+                }
 
-            if let Some(charge) = mw_item.enchantment_charge {
-                properties.push(Property::EnchantmentPoints(charge));
+                if let Some(charge) = mw_item
+                    .enchantment_charge
+                    .and_then(|c| mw_charge_to_ob(c, &mw_stats, &ob_stats))
+                {
+                    properties.push(Property::EnchantmentPoints(charge));
+                }
             }
 
             if let Some(ref soul) = mw_item.soul {
                 if let Some(creature) = self.mw.world.get::<tes3::Creature>(soul)? {
-                    let soul_size = creature.data().soul;
-                    let soul_type = self
-                        .soul_map
-                        .iter()
-                        .find_map(|(s, (min, max))| {
-                            if soul_size >= *min && soul_size <= *max {
-                                Some(s)
-                            } else {
-                                None
-                            }
-                        })
-                        // this unwrap is safe because the soul map values span the entire range of u32,
-                        // so we'll always find a match
-                        .unwrap();
-                    properties.push(Property::Soul(soul_type));
+                    let soul_size =
+                        SoulSize::from_tes3(creature.data().soul, &self.soul_capacities);
+                    properties.push(Property::Soul(soul_size));
                 }
             }
 
@@ -1656,6 +2879,7 @@ impl MorrowindToOblivion {
             }
 
             *was_converted = true;
+            self.report_converted(ReportCategory::Item, &mw_item.id);
         }
 
         // remove default items
@@ -1672,8 +2896,13 @@ impl MorrowindToOblivion {
             }
         }
 
-        // update inventory
-        for (_, item) in stacks {
+        // update inventory. the converted stacks are permanent items, so none of them may be marked
+        // as summoned by an effect or as coming from a leveled list.
+        for (_, mut item) in stacks {
+            if item.is_bound() {
+                continue;
+            }
+            item.retain_properties(|p| !matches!(p, Property::LeveledItem(_)));
             ob_player_ref.add_item(item);
         }
 
@@ -1695,6 +2924,245 @@ impl MorrowindToOblivion {
         Ok(())
     }
 
+    /// Stores the player's Mark location in the cosave, or reports that it wasn't converted
+    ///
+    /// Oblivion has nothing like Mark, so the location is only kept for an OBSE mod to use, and
+    /// only if the user asked for it.
+    fn convert_mark(&self) -> Result<()> {
+        let mark = match self.player_data.mark() {
+            Some(mark) => mark,
+            None => return Ok(()),
+        };
+
+        let id = format!("Mark in {}", mark.cell);
+        if !self.config.store_mark {
+            self.report_skipped(
+                ReportCategory::Location,
+                &id,
+                "no Oblivion equivalent; use --store-mark to keep it in the cosave",
+            );
+            return Ok(());
+        }
+
+        self.report_converted(ReportCategory::Location, &id);
+        self.with_cosave_mut(|cosave| {
+            let plugin = cosave.get_plugin_by_opcode(OPCODE_BASE).unwrap();
+            let mut obconvert = ObConvert::read(plugin)?;
+            obconvert.set_morrowind_mark(Some(mark));
+            let plugin = cosave.get_plugin_by_opcode_mut(OPCODE_BASE).unwrap();
+            obconvert.write(plugin)
+        })?;
+
+        Ok(())
+    }
+
+    /// Checks whether a Morrowind global variable is set to a non-zero value
+    fn is_global_set(&self, id: &str) -> Result<bool> {
+        let record = match self.mw.world.get_record_with_type(id, b"GLOB")? {
+            Some(record) => record,
+            None => return Ok(false),
+        };
+
+        for field in record.iter() {
+            if field.name() == b"FLTV" {
+                return Ok(field.get_f32()? != 0.);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Checks whether the player is a werewolf and applies the werewolf policy if they are
+    ///
+    /// Returns whether the player's lycanthropy should be left out of the conversion.
+    fn check_werewolf(&self) -> Result<bool> {
+        let is_werewolf = self.player_data.is_werewolf() || self.is_global_set(WEREWOLF_GLOBAL)?;
+        let cure = apply_werewolf_policy(self.config.werewolf_policy, is_werewolf)?;
+        if cure {
+            self.warn(
+                "The Morrowind player is a werewolf and was converted as if they'd been cured",
+            );
+        }
+        Ok(cure)
+    }
+
+    /// Gets the IDs of the player's lycanthropy spells, including active ones
+    fn lycanthropy_spells(&self) -> HashSet<String> {
+        lycanthropy_spell_ids(
+            self.player_base
+                .spells()
+                .chain(self.active_spells.iter().map(|s| s.id())),
+        )
+    }
+
+    /// Lists the cells where the player owns things in the conversion report
+    ///
+    /// Nothing in the player's properties is converted, so this lets users see what they'd leave
+    /// behind. Only references whose owning NPC is the player count; a set ownership global
+    /// variable only means the player can use a reference without stealing it, not that it's
+    /// theirs.
+    fn report_player_properties(&self) -> Result<()> {
+        let world = &self.mw.world;
+        let mw_save = world.get_save().unwrap();
+
+        // the contents of containers that have changed are stored separately from the containers
+        let mut container_changes = HashMap::new();
+        if let Some(records) = mw_save.get_records_by_type(b"CNTC") {
+            for record in records {
+                let change = tes3::ContainerChange::read(&record)?;
+                container_changes.insert((change.id().to_lowercase(), change.index()), change);
+            }
+        }
+
+        let mut owned = vec![];
+        world.for_each_reference(|cell, reference| {
+            match reference.ownership().and_then(|o| o.npc.as_ref()) {
+                Some(npc) if npc.eq_ignore_ascii_case("player") => (),
+                _ => return,
+            }
+
+            let (base_id, index) = match reference.indexed_id() {
+                Some((id, index)) => (String::from(id), Some(index)),
+                None => (String::from(reference.base_id()), None),
+            };
+            owned.push((cell_display_name(cell), base_id, index));
+        })?;
+
+        let mut properties: Vec<PlayerProperty> = vec![];
+        for (cell, base_id, index) in owned {
+            let cell = cell?;
+            let property = match properties.iter().position(|p| p.cell == cell) {
+                Some(i) => &mut properties[i],
+                None => {
+                    properties.push(PlayerProperty {
+                        cell,
+                        containers: vec![],
+                    });
+                    properties.last_mut().unwrap()
+                }
+            };
+
+            if world.get_record_with_type(&base_id, b"CONT")?.is_none() {
+                continue;
+            }
+
+            let contents = index
+                .and_then(|i| container_changes.get(&(base_id.to_lowercase(), i)))
+                .map(|change| {
+                    change
+                        .iter_inventory()
+                        .map(|item| (item.id.clone(), item.count))
+                        .collect()
+                });
+            property.containers.push(StoredContainer {
+                id: base_id,
+                contents,
+            });
+        }
+
+        let mut report = self.report.borrow_mut();
+        for property in properties {
+            report.add_property(property);
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the Oblivion save's screenshot with the Morrowind save's
+    ///
+    /// This needs the `image` feature, which is off by default; without it, the Oblivion save keeps
+    /// its own screenshot.
+    #[cfg(feature = "image")]
+    fn convert_screenshot(&self) -> Result<()> {
+        let mw_screenshot = match self.mw.world.get_save().unwrap().screenshot() {
+            Some(screenshot) => screenshot,
+            None => {
+                self.warn("Morrowind save has no screenshot");
+                return Ok(());
+            }
+        };
+
+        let (width, height) = self.with_save(|ob_save| {
+            let screenshot = ob_save.screenshot();
+            (screenshot.width, screenshot.height)
+        });
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        let screenshot = fit_screenshot(mw_screenshot, width, height)?;
+        self.with_save_mut(|ob_save| ob_save.set_screenshot(screenshot))?;
+
+        Ok(())
+    }
+
+    /// Prints an item converted for a trial, followed by the enchantment converted for it
+    fn print_trial_item<T, U>(&self, mw_item: &T, ob_item: Option<U>) -> Result<()>
+    where
+        T: Tes3Item + Tes3Enchantable,
+        U: Tes4Enchantable + Describe,
+    {
+        let ob_item = match ob_item {
+            Some(ob_item) => ob_item,
+            None => {
+                println!("{} has no Oblivion equivalent", mw_item.id());
+                return Ok(());
+            }
+        };
+
+        print_form(mw_item.id(), &ob_item);
+        if let (Some(mw_enchantment_id), Some(form_id)) =
+            (mw_item.enchantment(), ob_item.enchantment())
+        {
+            let world = self.ob.world();
+            if let Some(ob_enchantment) =
+                world.get::<tes4::Enchantment>(&FindForm::ByIndex(form_id))?
+            {
+                print_form(mw_enchantment_id, &ob_enchantment);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Converts a Morrowind item the way the player's inventory would be converted and prints it
+    ///
+    /// Forms the item depends on, like its enchantment, are added to the Oblivion save as usual,
+    /// but the save is never written, so it only serves as a scratch save.
+    pub(crate) fn try_item(&self, id: &str) -> Result<()> {
+        let record = self
+            .mw
+            .world
+            .get_record(id)?
+            .ok_or_else(|| anyhow!("No Morrowind item has the ID {}", id))?;
+
+        match record.name() {
+            tes3::Armor::RECORD_TYPE => {
+                let mw_armor = tes3::Armor::read(&*record)?;
+                self.print_trial_item(&mw_armor, self.convert_armor(&mw_armor)?)?;
+            }
+            tes3::Book::RECORD_TYPE => {
+                let mw_book = tes3::Book::read(&*record)?;
+                self.print_trial_item(&mw_book, self.convert_book(&mw_book)?)?;
+            }
+            tes3::Clothing::RECORD_TYPE => {
+                let mw_clothing = tes3::Clothing::read(&*record)?;
+                self.print_trial_item(&mw_clothing, self.convert_clothing(&mw_clothing)?)?;
+            }
+            tes3::Weapon::RECORD_TYPE => {
+                let mw_weapon = tes3::Weapon::read(&*record)?;
+                if mw_weapon.data.weapon_type == tes3::WeaponType::Arrow {
+                    self.print_trial_item(&mw_weapon, self.convert_ammo(&mw_weapon)?)?;
+                } else {
+                    self.print_trial_item(&mw_weapon, self.convert_weapon(&mw_weapon)?)?;
+                }
+            }
+            _ => return Err(anyhow!("{} is not an item that can be converted", id)),
+        }
+
+        Ok(())
+    }
+
     /// Perform a Morrowind-to-Oblivion conversion
     pub fn convert(&self) -> Result<()> {
         let (mut ob_player_base, mut ob_player_ref) = {
@@ -1713,21 +3181,42 @@ impl MorrowindToOblivion {
             (ob_player_base, ob_player_ref)
         };
 
+        // this can refuse the conversion, so do it before converting anything
+        let cure_lycanthropy = self.check_werewolf()?;
+
         // convert data
         self.convert_race(&mut ob_player_ref)?;
         let (ob_class, ob_class_form_id) = self.convert_class()?;
-        self.convert_spells(&mut ob_player_base, &mut ob_player_ref)?;
-        self.convert_stats(&mut ob_player_base, &mut ob_player_ref, &ob_class)?;
+        let mut racial_spells = self.racial_spells(&ob_player_ref)?;
+        if cure_lycanthropy {
+            // suppressed spells still have their effects removed from the player's stats
+            racial_spells
+                .mw_suppressed
+                .extend(self.lycanthropy_spells());
+        }
+        self.convert_spells(&mut ob_player_base, &mut ob_player_ref, &racial_spells)?;
+        self.convert_stats(
+            &mut ob_player_base,
+            &mut ob_player_ref,
+            &ob_class,
+            &racial_spells,
+        )?;
         self.convert_inventory(&mut ob_player_ref)?;
+        self.convert_mark()?;
+        self.report_player_properties()?;
+        #[cfg(feature = "image")]
+        self.convert_screenshot()?;
 
         // apply changes to save
-        self.with_companion_mod::<Result<()>, _>(|plugin| {
-            let data_dir = self.ob.data_dir();
-            let plugin_path = data_dir.join(COMPANION_MOD_NAME);
-            plugin.save_file(&plugin_path)?;
+        if self.config.form_target == FormTarget::Plugin {
+            self.with_companion_mod::<Result<()>, _>(|plugin| {
+                let data_dir = self.ob.data_dir();
+                let plugin_path = data_dir.join(&self.config.plugin_name);
+                plugin.save_file(&plugin_path)?;
 
-            Ok(())
-        })?;
+                Ok(())
+            })?;
+        }
 
         self.with_save_mut::<Result<()>, _>(|ob_save| {
             // finalize converted class (we have to wait and do this here because this might take
@@ -1749,7 +3238,16 @@ impl MorrowindToOblivion {
             let mw_save_info = mw_save
                 .get_save_info()
                 .ok_or_else(|| anyhow!("Morrowind plugin did not contain save information"))?;
-            ob_save.set_player_name(String::from(mw_save_info.player_name()))?;
+            let player_name = self
+                .names
+                .convert(mw_save_info.player_name(), "the player in the save list")?;
+            ob_save.set_player_name(player_name)?;
+            ob_save.set_player_level(self.player_base.level);
+            let location = self.names.convert(
+                mw_save_info.current_cell(),
+                "the player's location in the save list",
+            )?;
+            ob_save.set_player_location(location)?;
 
             ob_save.update_form_change(&ob_player_base, FORM_PLAYER)?;
             ob_save.update_form_change(&ob_player_ref, FORM_PLAYER_REF)?;
@@ -1767,3 +3265,546 @@ impl MorrowindToOblivion {
         })
     }
 }
+
+/// Scales a screenshot to a new size
+///
+/// The screenshot is cropped to the new aspect ratio before scaling so the image isn't stretched.
+/// Whichever dimension is too long for the new aspect ratio is cropped evenly from both sides.
+#[cfg(feature = "image")]
+fn fit_screenshot(screenshot: Screenshot, width: u32, height: u32) -> Result<Screenshot> {
+    use image::imageops;
+    use image::RgbImage;
+
+    let (old_width, old_height) = (screenshot.width, screenshot.height);
+    let image = RgbImage::from_raw(old_width, old_height, screenshot.data)
+        .ok_or_else(|| anyhow!("Invalid Morrowind save screenshot"))?;
+
+    // compare the aspect ratios without dividing so we don't lose precision
+    let (crop_width, crop_height) =
+        if u64::from(old_width) * u64::from(height) > u64::from(old_height) * u64::from(width) {
+            (
+                (u64::from(old_height) * u64::from(width) / u64::from(height)) as u32,
+                old_height,
+            )
+        } else {
+            (
+                old_width,
+                (u64::from(old_width) * u64::from(height) / u64::from(width)) as u32,
+            )
+        };
+    let crop_x = (old_width - crop_width) / 2;
+    let crop_y = (old_height - crop_height) / 2;
+    let cropped = imageops::crop_imm(&image, crop_x, crop_y, crop_width, crop_height).to_image();
+    let resized = imageops::resize(&cropped, width, height, imageops::FilterType::Triangle);
+
+    Ok(Screenshot {
+        width,
+        height,
+        data: resized.into_raw(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::names::NamePolicy;
+    use std::fs;
+    use std::io;
+    use tesutil::Specialization;
+
+    #[test]
+    fn convert_class() {
+        let mut mw_class =
+            tes3::Class::new(String::from("Spellsword"), String::from("Spellsword")).unwrap();
+        mw_class
+            .set_primary_attributes([Attribute::Willpower, Attribute::Endurance])
+            .unwrap();
+        mw_class.specialization = Specialization::Magic;
+        mw_class
+            .set_skills(
+                [
+                    tes3::Skill::Block,
+                    tes3::Skill::Restoration,
+                    tes3::Skill::LongBlade,
+                    tes3::Skill::ShortBlade,
+                    tes3::Skill::Destruction,
+                ],
+                [
+                    tes3::Skill::Blunt,
+                    tes3::Skill::Enchant,
+                    tes3::Skill::Security,
+                    tes3::Skill::MediumArmor,
+                    tes3::Skill::Alchemy,
+                ],
+            )
+            .unwrap();
+
+        let ob_class =
+            Morrowind::convert_class(&mw_class, None, &Names::new(NamePolicy::Fix)).unwrap();
+        assert_eq!(
+            ob_class.primary_attribute(),
+            &[Attribute::Willpower, Attribute::Endurance]
+        );
+        assert_eq!(ob_class.specialization, Specialization::Magic);
+        // long and short blade are both blade, enchant and medium armor don't exist, and the magic
+        // skills are preferred over the others to fill the remaining slots
+        assert_eq!(
+            ob_class.major_skills(),
+            &[
+                tes4::Skill::Block,
+                tes4::Skill::Restoration,
+                tes4::Skill::Blade,
+                tes4::Skill::Destruction,
+                tes4::Skill::Alchemy,
+                tes4::Skill::Blunt,
+                tes4::Skill::Security,
+            ]
+        );
+    }
+
+    #[test]
+    fn convert_player_class() {
+        let mut mw_class =
+            tes3::Class::new(String::from("Gambler"), String::from("Gambler")).unwrap();
+        mw_class
+            .set_primary_attributes([Attribute::Luck, Attribute::Personality])
+            .unwrap();
+        mw_class.specialization = Specialization::Stealth;
+        mw_class
+            .set_skills(
+                [
+                    tes3::Skill::Mercantile,
+                    tes3::Skill::Speechcraft,
+                    tes3::Skill::Security,
+                    tes3::Skill::Sneak,
+                    tes3::Skill::Marksman,
+                ],
+                [
+                    tes3::Skill::Illusion,
+                    tes3::Skill::Alchemy,
+                    tes3::Skill::Athletics,
+                    tes3::Skill::LightArmor,
+                    tes3::Skill::Acrobatics,
+                ],
+            )
+            .unwrap();
+        let mut skill_levels = tes3::Skills::default();
+        skill_levels[tes3::Skill::Illusion] = 15;
+        skill_levels[tes3::Skill::Alchemy] = 40;
+        skill_levels[tes3::Skill::Athletics] = 30;
+        skill_levels[tes3::Skill::LightArmor] = 30;
+        skill_levels[tes3::Skill::Acrobatics] = 10;
+        let names = Names::new(NamePolicy::Fix);
+
+        // without skill levels, the stealth skills fill the remaining slots
+        let ob_class = Morrowind::convert_class(&mw_class, None, &names).unwrap();
+        assert_eq!(
+            &ob_class.major_skills()[5..],
+            &[tes4::Skill::LightArmor, tes4::Skill::Acrobatics]
+        );
+
+        // with them, the highest skills do, and the stealth skill wins the tie
+        let ob_class = Morrowind::convert_class(&mw_class, Some(&skill_levels), &names).unwrap();
+        assert_eq!(
+            ob_class.major_skills(),
+            &[
+                tes4::Skill::Mercantile,
+                tes4::Skill::Speechcraft,
+                tes4::Skill::Security,
+                tes4::Skill::Sneak,
+                tes4::Skill::Marksman,
+                tes4::Skill::Alchemy,
+                tes4::Skill::LightArmor,
+            ]
+        );
+        // luck governs no skills, so it's replaced by agility, which governs the most major skills
+        assert_eq!(
+            ob_class.primary_attribute(),
+            &[Attribute::Personality, Attribute::Agility]
+        );
+    }
+
+    #[test]
+    fn split_racial_specials() {
+        let (spell_list, abilities) = split_specials(vec![
+            (FormId(0x14d23), tes4::SpellType::Ability),
+            (FormId(0x14d24), tes4::SpellType::Power),
+            (FormId(0x14d25), tes4::SpellType::Spell),
+            (FormId(0x14d26), tes4::SpellType::Disease),
+            // the birthsign grants an ability the race already has
+            (FormId(0x14d23), tes4::SpellType::Ability),
+            (FormId(0x14d27), tes4::SpellType::LesserPower),
+        ]);
+        assert_eq!(
+            spell_list,
+            vec![FormId(0x14d24), FormId(0x14d25), FormId(0x14d27)]
+        );
+        assert_eq!(abilities, vec![FormId(0x14d23), FormId(0x14d26)]);
+    }
+
+    #[test]
+    fn lycanthropy_spells() {
+        assert!(is_lycanthropy_spell("werewolf vision"));
+        assert!(is_lycanthropy_spell("Sanies Lupinus"));
+        assert!(!is_lycanthropy_spell("vampire blood quarra"));
+    }
+
+    #[test]
+    fn werewolf_policy() {
+        assert!(!apply_werewolf_policy(WerewolfPolicy::Refuse, false).unwrap());
+        assert!(!apply_werewolf_policy(WerewolfPolicy::Cure, false).unwrap());
+        assert!(apply_werewolf_policy(WerewolfPolicy::Refuse, true).is_err());
+        assert!(apply_werewolf_policy(WerewolfPolicy::Cure, true).unwrap());
+
+        // a cure removes every lycanthropy spell the player knows or has active, once each
+        let cured = lycanthropy_spell_ids([
+            "werewolf vision",
+            "Sanies Lupinus",
+            "vampire blood quarra",
+            "werewolf vision",
+            "fireball",
+        ]);
+        assert_eq!(
+            cured,
+            HashSet::from([
+                String::from("werewolf vision"),
+                String::from("Sanies Lupinus")
+            ])
+        );
+    }
+
+    #[test]
+    fn jail_sentence() {
+        let changes = jail_skill_changes(30);
+        // one day for every skill, and three more for the first skills in order
+        assert_eq!(changes[tes3::Skill::Block], -2);
+        assert_eq!(changes[tes3::Skill::HeavyArmor], -1);
+        assert_eq!(changes[tes3::Skill::Security], 1);
+        assert_eq!(changes[tes3::Skill::Sneak], 1);
+        assert_eq!(changes.values().sum::<i32>(), -26);
+
+        let changes = jail_skill_changes(1);
+        assert_eq!(changes[tes3::Skill::Block], -1);
+        assert_eq!(changes.values().filter(|c| **c != 0).count(), 1);
+    }
+
+    #[test]
+    fn afflictions() {
+        assert!(is_affliction(tes3::SpellType::Blight));
+        assert!(is_affliction(tes3::SpellType::Curse));
+        assert!(!is_affliction(tes3::SpellType::Disease));
+        assert!(!is_affliction(tes3::SpellType::Ability));
+    }
+
+    #[test]
+    fn tools() {
+        assert_eq!(ob_tool("pick_apprentice_01", b"LOCK"), Some(FORM_LOCKPICK));
+        assert_eq!(ob_tool("Skeleton_Key", b"LOCK"), Some(FORM_SKELETON_KEY));
+        assert_eq!(ob_tool("hammer_repair", b"REPA"), Some(FORM_REPAIR_HAMMER));
+        assert_eq!(ob_tool("probe_apprentice_01", b"PROB"), None);
+    }
+
+    #[test]
+    fn rescale_enchantment_charge() {
+        assert_eq!(rescale_charge(400, 20, 5), 100);
+        assert_eq!(rescale_charge(400, 0, 5), 400);
+        assert_eq!(rescale_charge(u32::MAX, 1, 2), u32::MAX);
+    }
+
+    #[test]
+    fn known_effects() {
+        assert!(teaches_effects(tes4::SpellType::Spell));
+        assert!(!teaches_effects(tes4::SpellType::Power));
+        assert!(!teaches_effects(tes4::SpellType::LesserPower));
+        assert!(!teaches_effects(tes4::SpellType::Ability));
+        assert!(!teaches_effects(tes4::SpellType::Disease));
+    }
+
+    #[test]
+    fn unlock_known_effects() {
+        fn spell(spell_type: tes4::SpellType, effects: &[tes4::MagicEffectType]) -> tes4::Spell {
+            let mut spell = tes4::Spell::new(None, None);
+            spell.spell_type = spell_type;
+            for effect in effects {
+                spell.add_effect(tes4::SpellEffect::new(*effect));
+            }
+            spell
+        }
+
+        let spells = [
+            spell(
+                tes4::SpellType::Spell,
+                &[
+                    tes4::MagicEffectType::FireDamage,
+                    tes4::MagicEffectType::Telekinesis,
+                ],
+            ),
+            spell(
+                tes4::SpellType::Power,
+                &[tes4::MagicEffectType::RestoreHealth],
+            ),
+            spell(tes4::SpellType::Spell, &[tes4::MagicEffectType::FireDamage]),
+        ];
+        // pretend telekinesis can't be used for spellmaking
+        let allowed = |t: tes4::MagicEffectType| -> Result<bool> {
+            Ok(t != tes4::MagicEffectType::Telekinesis)
+        };
+
+        let known = known_magic_effects(&spells, false, allowed).unwrap();
+        assert_eq!(known.into_iter().collect::<Vec<_>>(), [*b"FIDG"]);
+
+        let known = known_magic_effects(&spells, true, allowed).unwrap();
+        assert_eq!(known.into_iter().collect::<Vec<_>>(), [*b"FIDG", *b"REHE"]);
+
+        assert!(known_magic_effects(&spells, false, |_| Err(anyhow!("no effects"))).is_err());
+    }
+
+    #[test]
+    fn match_active_effects() {
+        let base_effects = ["fortify strength", "drain luck"];
+        let (matched, unmatched) = match_effects_by_index(
+            &base_effects,
+            vec![(1, 'a'), (0, 'b'), (2, 'c'), (-1, 'd'), (1, 'e')],
+        );
+        assert_eq!(
+            matched,
+            vec![
+                ('a', &"drain luck"),
+                ('b', &"fortify strength"),
+                ('e', &"drain luck"),
+            ]
+        );
+        assert_eq!(unmatched, vec!['c', 'd']);
+    }
+
+    fn mw_potion(effects: &[[u8; 24]]) -> tes3::Potion {
+        let mut record = tes3::Tes3Record::new(b"ALCH");
+        record.add_field(tes3::Tes3Field::new_zstring(b"NAME", String::from("p_test")).unwrap());
+        record.add_field(tes3::Tes3Field::new_zstring(b"FNAM", String::from("Test")).unwrap());
+        record.add_field(tes3::Tes3Field::new(b"ALDT", vec![0; 12]).unwrap());
+        for effect in effects {
+            record.add_field(tes3::Tes3Field::new(b"ENAM", effect.to_vec()).unwrap());
+        }
+        tes3::Potion::read(&record).unwrap()
+    }
+
+    static NPC_RECORD: &[u8] = include_bytes!("../../tesutil/src/tes3/plugin/test/npc_record.bin");
+    static REFR_RECORD: &[u8] =
+        include_bytes!("../../tesutil/src/tes3/plugin/test/refr_record.bin");
+    static OB_SAVE: &[u8] = include_bytes!("../../tesutil/src/tes4/save/test/quicksave.ess");
+    static OB_TEST_DIR: &str = "../tesutil/src/tes4/plugin/test";
+
+    /// Sets up a conversion of the test player, with Morrowind's records coming from `mw_plugin`
+    /// and Oblivion's from the test Oblivion.esm
+    fn test_converter(mw_plugin: &Path, name_policy: &str) -> MorrowindToOblivion {
+        let cache = IndexCache::new();
+        let mw = Morrowind::load_plugins(
+            Tes3World::game_paths(mw_plugin.parent().unwrap()),
+            [mw_plugin],
+            &cache,
+        )
+        .unwrap();
+        let ob_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(OB_TEST_DIR);
+        let ob = Oblivion::load_base(Tes4World::game_paths(ob_dir), &cache).unwrap();
+
+        let npc_record = tes3::Tes3Record::read(io::Cursor::new(NPC_RECORD)).unwrap();
+        let refr_record = tes3::Tes3Record::read(io::Cursor::new(REFR_RECORD)).unwrap();
+        let class = tes3::Class::new(String::from("Gambler"), String::from("Gambler")).unwrap();
+        let config = Config::get_from_strings(vec![
+            "tesconvert",
+            "--names",
+            name_policy,
+            "mw2ob",
+            "target",
+            "output",
+            "source",
+        ]);
+        let names = Names::new(config.name_policy);
+
+        MorrowindToOblivion {
+            config,
+            mw,
+            ob,
+            form_map: RefCell::new(HashMap::new()),
+            player_base: tes3::Npc::read(&npc_record).unwrap(),
+            player_ref: tes3::PlayerReference::read(&refr_record).unwrap(),
+            player_change: tes3::NpcChange::new(String::from("PlayerSaveGame")).unwrap(),
+            player_data: tes3::PlayerData::default(),
+            class,
+            active_spells: tes3::ActiveSpellList::new(),
+            soul_capacities: enum_map! { _ => 0 },
+            model_map: HashMap::new(),
+            icon_map: HashMap::new(),
+            names,
+            warnings: RefCell::new(vec![]),
+            report: RefCell::new(ConversionReport::new()),
+        }
+    }
+
+    #[test]
+    fn convert_spells_with_strict_names() {
+        // the test player knows fireball
+        let mut spdt = vec![0; 4];
+        spdt.extend_from_slice(&10u32.to_le_bytes());
+        spdt.extend_from_slice(&0u32.to_le_bytes());
+        let mut record = tes3::Tes3Record::new(b"SPEL");
+        record.add_field(tes3::Tes3Field::new_zstring(b"NAME", String::from("fireball")).unwrap());
+        record.add_field(
+            tes3::Tes3Field::new_zstring(b"FNAM", String::from("Kula Ognia Ł")).unwrap(),
+        );
+        record.add_field(tes3::Tes3Field::new(b"SPDT", spdt).unwrap());
+        record.add_field(tes3::Tes3Field::new(b"ENAM", FIRE_DAMAGE.to_vec()).unwrap());
+
+        let dir =
+            std::env::temp_dir().join(format!("tesconvert_strict_names_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let plugin_path = dir.join("strict_names.esp");
+        let mut plugin = tes3::Tes3Plugin::new(String::new(), String::new()).unwrap();
+        plugin.add_record(record).unwrap();
+        plugin.save_file(&plugin_path).unwrap();
+
+        let converter = test_converter(&plugin_path, "strict");
+        let save = Save::read(io::Cursor::new(OB_SAVE)).unwrap();
+        let mut ob_player_base: ActorChange = save.get_form_change(FORM_PLAYER).unwrap().unwrap();
+        let mut ob_player_ref: PlayerReferenceChange =
+            save.get_form_change(FORM_PLAYER_REF).unwrap().unwrap();
+        let racial_spells = RacialSpells {
+            mw_suppressed: HashSet::new(),
+            ob_spell_list: vec![],
+            ob_abilities: vec![],
+        };
+        let result =
+            converter.convert_spells(&mut ob_player_base, &mut ob_player_ref, &racial_spells);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let error = result.unwrap_err();
+        assert!(format!("{:#}", error).contains("cannot be used in Oblivion"));
+        let report = converter.report();
+        assert_eq!(report.converted(ReportCategory::Spell).count(), 0);
+    }
+
+    fn mw_spell(cost: u32, is_auto_calc: bool) -> tes3::Spell {
+        let mut spdt = vec![0; 4];
+        spdt.extend_from_slice(&cost.to_le_bytes());
+        spdt.extend_from_slice(&u32::from(is_auto_calc).to_le_bytes());
+        let mut record = tes3::Tes3Record::new(b"SPEL");
+        record.add_field(tes3::Tes3Field::new_zstring(b"NAME", String::from("s_test")).unwrap());
+        record.add_field(tes3::Tes3Field::new_zstring(b"FNAM", String::from("Test")).unwrap());
+        record.add_field(tes3::Tes3Field::new(b"SPDT", spdt).unwrap());
+        record.add_field(tes3::Tes3Field::new(b"ENAM", RESTORE_HEALTH.to_vec()).unwrap());
+        tes3::Spell::read(&record).unwrap()
+    }
+
+    #[test]
+    fn check_auto_calc() {
+        // flagged, and the stored cost matches the formula
+        assert!(Morrowind::is_auto_calc_spell(&mw_spell(7, true), Some(7)));
+        // not flagged, but the stored cost matches the formula
+        assert!(Morrowind::is_auto_calc_spell(&mw_spell(7, false), Some(7)));
+        // flagged, but the stored cost was changed, so the stored cost is kept
+        assert!(!Morrowind::is_auto_calc_spell(&mw_spell(3, true), Some(7)));
+        assert!(!Morrowind::is_auto_calc_spell(&mw_spell(3, false), Some(7)));
+        // without a calculated cost, the flag decides
+        assert!(Morrowind::is_auto_calc_spell(&mw_spell(3, true), None));
+        assert!(!Morrowind::is_auto_calc_spell(&mw_spell(3, false), None));
+    }
+
+    // Fire Damage 10-20 pts for 5 secs on self
+    const FIRE_DAMAGE: [u8; 24] = [
+        14, 0, 0xff, 0xff, 0, 0, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 10, 0, 0, 0, 20, 0, 0, 0,
+    ];
+    // Restore Health 10 pts for 1 sec on self
+    const RESTORE_HEALTH: [u8; 24] = [
+        75, 0, 0xff, 0xff, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 10, 0, 0, 0, 10, 0, 0, 0,
+    ];
+
+    /// Makes a 4x4 screenshot with red and blue edges on two opposite sides and green in between
+    #[cfg(feature = "image")]
+    fn edged_screenshot(vertical_edges: bool) -> Screenshot {
+        let mut data = vec![];
+        for y in 0..4 {
+            for x in 0..4 {
+                let pixel = match if vertical_edges { x } else { y } {
+                    0 => [255, 0, 0],
+                    3 => [0, 0, 255],
+                    _ => [0, 255, 0],
+                };
+                data.extend_from_slice(&pixel);
+            }
+        }
+
+        Screenshot {
+            width: 4,
+            height: 4,
+            data,
+        }
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn fit_screenshot_to_wider() {
+        // cropping to 2:1 keeps only the middle rows, so there should be no red or blue left
+        let fitted = fit_screenshot(edged_screenshot(false), 4, 2).unwrap();
+        assert_eq!((fitted.width, fitted.height), (4, 2));
+        assert_eq!(fitted.data.len(), 4 * 2 * 3);
+        assert!(fitted.data.chunks(3).all(|p| p == [0, 255, 0]));
+
+        let bad = Screenshot {
+            width: 4,
+            height: 4,
+            data: vec![0; 5],
+        };
+        assert!(fit_screenshot(bad, 4, 2).is_err());
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn fit_screenshot_to_taller() {
+        // cropping to 1:2 keeps only the middle columns
+        let fitted = fit_screenshot(edged_screenshot(true), 2, 4).unwrap();
+        assert_eq!((fitted.width, fitted.height), (2, 4));
+        assert_eq!(fitted.data.len(), 2 * 4 * 3);
+        assert!(fitted.data.chunks(3).all(|p| p == [0, 255, 0]));
+    }
+
+    #[test]
+    fn convert_poison() {
+        let ob_potion = Morrowind::convert_potion(
+            &mw_potion(&[FIRE_DAMAGE]),
+            &tes4::PotionGraphicsTable::new(),
+            &CombineStrategy::Average,
+            &Names::new(NamePolicy::Fix),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert!(ob_potion.is_poison());
+        assert!(!ob_potion.is_food_item);
+        let effects: Vec<_> = ob_potion.iter_effects().collect();
+        assert_eq!(effects.len(), 1);
+        assert_eq!(effects[0].effect_type(), tes4::MagicEffectType::FireDamage);
+        // poisons are applied by weapon strikes, so their effects become Touch
+        assert_eq!(effects[0].range(), EffectRange::Touch);
+        assert_eq!(effects[0].magnitude(), 15);
+        assert_eq!(ob_potion.model(), Some(r"Clutter\Potions\PotionPoison.NIF"));
+    }
+
+    #[test]
+    fn convert_mixed_potion() {
+        let ob_potion = Morrowind::convert_potion(
+            &mw_potion(&[RESTORE_HEALTH, FIRE_DAMAGE]),
+            &tes4::PotionGraphicsTable::new(),
+            &CombineStrategy::Average,
+            &Names::new(NamePolicy::Fix),
+        )
+        .unwrap()
+        .unwrap();
+
+        // one beneficial effect is enough to keep it a potion, and its effects keep their range
+        assert!(!ob_potion.is_poison());
+        assert!(ob_potion
+            .iter_effects()
+            .all(|e| e.range() == EffectRange::Self_));
+    }
+}
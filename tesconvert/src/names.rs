@@ -0,0 +1,218 @@
+use std::cell::RefCell;
+
+use tesutil::MAX_BSTRING;
+
+use anyhow::{anyhow, Result};
+
+/// Maximum length of a name in Oblivion, in bytes
+pub const MAX_NAME_LENGTH: usize = MAX_BSTRING;
+
+/// Replacements for characters that can't be used in Oblivion names, grouped by replacement
+///
+/// Oblivion reads text in the Windows-1252 code page, but tesutil writes strings as UTF-8, so only
+/// ASCII characters mean the same thing to both. These cover the accented letters used by
+/// localized versions of Morrowind and the typographic punctuation Windows-1252 adds to Latin-1.
+const TRANSLITERATIONS: [(&str, &str); 63] = [
+    ("ÀÁÂÃÄÅĀĂĄ", "A"),
+    ("àáâãäåāăą", "a"),
+    ("Æ", "AE"),
+    ("æ", "ae"),
+    ("ÇĆĈĊČ", "C"),
+    ("çćĉċč", "c"),
+    ("ÐĎĐ", "D"),
+    ("ðďđ", "d"),
+    ("ÈÉÊËĒĔĖĘĚ", "E"),
+    ("èéêëēĕėęě", "e"),
+    ("ƒ", "f"),
+    ("ĜĞĠĢ", "G"),
+    ("ĝğġģ", "g"),
+    ("ĤĦ", "H"),
+    ("ĥħ", "h"),
+    ("ÌÍÎÏĨĪĬĮİ", "I"),
+    ("ìíîïĩīĭįı", "i"),
+    ("Ĳ", "IJ"),
+    ("ĳ", "ij"),
+    ("Ĵ", "J"),
+    ("ĵ", "j"),
+    ("Ķ", "K"),
+    ("ķ", "k"),
+    ("ĹĻĽĿŁ", "L"),
+    ("ĺļľŀł", "l"),
+    ("ÑŃŅŇ", "N"),
+    ("ñńņň", "n"),
+    ("ÒÓÔÕÖØŌŎŐ", "O"),
+    ("òóôõöøōŏő", "o"),
+    ("Œ", "OE"),
+    ("œ", "oe"),
+    ("ŔŖŘ", "R"),
+    ("ŕŗř", "r"),
+    ("ŠŚŜŞ", "S"),
+    ("šśŝş", "s"),
+    ("ß", "ss"),
+    ("ŢŤŦ", "T"),
+    ("ţťŧ", "t"),
+    ("Þ", "Th"),
+    ("þ", "th"),
+    ("ÙÚÛÜŨŪŬŮŰŲ", "U"),
+    ("ùúûüũūŭůűų", "u"),
+    ("Ŵ", "W"),
+    ("ŵ", "w"),
+    ("ÝŸŶ", "Y"),
+    ("ýÿŷ", "y"),
+    ("ŽŹŻ", "Z"),
+    ("žźż", "z"),
+    ("‐‑‒–—―−", "-"),
+    ("‘’‚′‹›", "'"),
+    ("“”„″«»", "\""),
+    ("…", "..."),
+    ("•", "*"),
+    ("ˆ", "^"),
+    ("˜", "~"),
+    ("×", "x"),
+    ("¡", "!"),
+    ("¿", "?"),
+    ("€", "EUR"),
+    ("©", "(c)"),
+    ("®", "(R)"),
+    ("™", "(TM)"),
+    ("\u{a0}\t\n\r", " "),
+];
+
+/// What to do with a name that can't be used in Oblivion as it is
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum NamePolicy {
+    /// Transliterate or drop unsupported characters and truncate long names, reporting each change
+    Fix,
+    /// Fail the conversion
+    Strict,
+}
+
+/// Checks whether a character can be written to an Oblivion name as it is
+///
+/// Only printable ASCII is encoded the same in UTF-8 and Windows-1252.
+fn is_supported(c: char) -> bool {
+    matches!(c, ' '..='~')
+}
+
+/// Gets the replacement for a character Oblivion can't display, if there is one
+fn transliterate(c: char) -> Option<&'static str> {
+    TRANSLITERATIONS
+        .iter()
+        .find(|(chars, _)| chars.contains(c))
+        .map(|(_, replacement)| *replacement)
+}
+
+/// Converts names of forms and characters for use in Oblivion
+///
+/// Every name a conversion writes goes through here so that names are handled the same way
+/// everywhere. Changes made to names are kept as reports to show the user at the end of the
+/// conversion.
+#[derive(Debug)]
+pub struct Names {
+    policy: NamePolicy,
+    reports: RefCell<Vec<String>>,
+}
+
+impl Names {
+    /// Creates a name converter with the given policy
+    pub fn new(policy: NamePolicy) -> Names {
+        Names {
+            policy,
+            reports: RefCell::new(vec![]),
+        }
+    }
+
+    /// Converts a name for use in Oblivion
+    ///
+    /// `description` identifies what's being named in reports and errors, e.g. "spell fireball".
+    ///
+    /// # Errors
+    ///
+    /// Fails if the name had to be changed and the policy is [`NamePolicy::Strict`].
+    ///
+    /// [`NamePolicy::Strict`]: enum.NamePolicy.html#variant.Strict
+    pub fn convert(&self, name: &str, description: &str) -> Result<String> {
+        let mut converted = String::with_capacity(name.len());
+        let mut reasons = vec![];
+        for c in name.chars() {
+            if is_supported(c) {
+                converted.push(c);
+            } else if let Some(replacement) = transliterate(c) {
+                converted.push_str(replacement);
+                if !reasons.contains(&"unsupported characters were replaced") {
+                    reasons.push("unsupported characters were replaced");
+                }
+            } else if !reasons.contains(&"unsupported characters were removed") {
+                reasons.push("unsupported characters were removed");
+            }
+        }
+
+        // the name is all ASCII now, so its length in UTF-8 is its length in the game's code page
+        if converted.len() > MAX_NAME_LENGTH {
+            converted.truncate(MAX_NAME_LENGTH);
+            reasons.push("the name was too long and was truncated");
+        }
+
+        if !reasons.is_empty() {
+            match self.policy {
+                NamePolicy::Fix => self.reports.borrow_mut().push(format!(
+                    "Name of {} changed from {:?} to {:?} because {}",
+                    description,
+                    name,
+                    converted,
+                    reasons.join(" and ")
+                )),
+                NamePolicy::Strict => {
+                    return Err(anyhow!(
+                        "Name of {} ({:?}) cannot be used in Oblivion: {}",
+                        description,
+                        name,
+                        reasons.join(" and ")
+                    ))
+                }
+            }
+        }
+
+        Ok(converted)
+    }
+
+    /// Gets the changes made to names so far
+    pub fn reports(&self) -> Vec<String> {
+        self.reports.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_names() {
+        let names = Names::new(NamePolicy::Fix);
+        assert_eq!(names.convert("Ebony Mail", "item a").unwrap(), "Ebony Mail");
+        assert!(names.reports().is_empty());
+
+        assert_eq!(names.convert("Ébène Mail", "item b").unwrap(), "Ebene Mail");
+        assert_eq!(
+            names.convert("Miecz Łowcy\u{0}", "item c").unwrap(),
+            "Miecz Lowcy"
+        );
+        assert_eq!(
+            names.convert("Nerevar’s “Moon” – Star", "item d").unwrap(),
+            "Nerevar's \"Moon\" - Star"
+        );
+        assert_eq!(names.convert("Меч", "item e").unwrap(), "");
+        // the limit is on encoded bytes, and each "é" becomes a single byte
+        let long_name = "é".repeat(300);
+        assert_eq!(
+            names.convert(&long_name, "item f").unwrap(),
+            "e".repeat(MAX_NAME_LENGTH)
+        );
+        assert_eq!(names.reports().len(), 5);
+
+        let names = Names::new(NamePolicy::Strict);
+        assert!(names.convert("Fireball", "spell fireball").is_ok());
+        assert!(names.convert("Kula Ognia Ł", "spell fireball").is_err());
+        assert!(names.reports().is_empty());
+    }
+}
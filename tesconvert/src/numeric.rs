@@ -0,0 +1,36 @@
+use num::{Bounded, NumCast, ToPrimitive};
+
+/// Converts a number to another numeric type, clamping it to the target type's range
+///
+/// Fractional values are truncated as with `as`, but values outside the target type's range are
+/// clamped to the nearest bound instead of being silently wrapped or saturated. NaN becomes the
+/// target type's minimum. The second element of the result indicates whether clamping occurred.
+pub fn clamp_cast<T, U>(value: T) -> (U, bool)
+where
+    T: ToPrimitive + Copy,
+    U: NumCast + Bounded,
+{
+    match U::from(value) {
+        Some(converted) => (converted, false),
+        None => match value.to_f64() {
+            Some(f) if f > 0. => (U::max_value(), true),
+            _ => (U::min_value(), true),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamp_casts() {
+        assert_eq!(clamp_cast::<f32, u8>(54.9), (54, false));
+        assert_eq!(clamp_cast::<f32, u8>(300.), (255, true));
+        assert_eq!(clamp_cast::<f32, u8>(-12.), (0, true));
+        assert_eq!(clamp_cast::<f32, u8>(f32::NAN), (0, true));
+        assert_eq!(clamp_cast::<i32, u8>(-5), (0, true));
+        assert_eq!(clamp_cast::<u16, i16>(40000), (i16::MAX, true));
+        assert_eq!(clamp_cast::<f32, u32>(-0.5), (0, false));
+    }
+}
@@ -1,23 +1,28 @@
 use std::cell::RefCell;
+use std::iter;
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
 
 use tesutil::tes4;
-use tesutil::tes4::{Magic, Tes4World};
+use tesutil::tes4::{Magic, OblivionIni, Tes4World, INI_FILE};
 use tesutil::EffectRange;
-use tesutil::{tes3, World};
+use tesutil::{tes3, GamePaths, IndexCache, LoadOptions, MissingPluginPolicy, World};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 #[cfg(windows)]
 use winreg::enums::*;
 #[cfg(windows)]
 use winreg::RegKey;
 
+/// Name of the base game's master file
+pub const BASE_GAME: &str = "Oblivion.esm";
+
 /// Container for Oblivion-related state and functionality
 #[derive(Debug)]
 pub struct Oblivion {
-    game_dir: PathBuf,
+    paths: GamePaths,
     world: RefCell<Tes4World>,
+    potion_graphics: tes4::PotionGraphicsTable,
     // skill XP settings
     skill_use_exp: f32,
     skill_use_factor: f32,
@@ -34,11 +39,15 @@ pub struct Oblivion {
     journeyman_min: f32,
     expert_min: f32,
     master_min: f32,
+    warnings: Vec<String>,
 }
 
 impl Oblivion {
-    /// Capture Oblivion state
-    pub fn load<P, Q>(game_dir: Option<P>, save_path: Q) -> Result<Oblivion>
+    /// Gets the paths to the Oblivion install, detecting the install directory if it isn't given
+    ///
+    /// If any data directories are given, they replace the Data directory in the install
+    /// directory, from lowest to highest precedence.
+    pub fn game_paths<P, Q>(game_dir: Option<P>, data_dirs: &[Q]) -> Result<GamePaths>
     where
         P: AsRef<Path>,
         Q: AsRef<Path>,
@@ -48,8 +57,54 @@ impl Oblivion {
             None => Oblivion::detect_dir()?.into(),
         };
 
-        let world =
-            Tes4World::load_from_save(<PathBuf as AsRef<Path>>::as_ref(&oblivion_dir), save_path)?;
+        let mut paths = Tes4World::game_paths(oblivion_dir);
+        paths.set_data_dirs(data_dirs);
+        Ok(paths)
+    }
+
+    /// Capture Oblivion state
+    ///
+    /// If `allow_missing_plugins` is true, plugins the save depends on that aren't installed are
+    /// replaced with empty placeholders instead of failing the load.
+    pub fn load<P: AsRef<Path>>(
+        paths: GamePaths,
+        save_path: P,
+        allow_missing_plugins: bool,
+        cache: &IndexCache,
+    ) -> Result<Oblivion> {
+        let policy = if allow_missing_plugins {
+            MissingPluginPolicy::Placeholder
+        } else {
+            MissingPluginPolicy::Fail
+        };
+        let options = LoadOptions::new().cache(cache).missing_plugins(policy);
+        let world = Tes4World::load_from_save_with(&paths, save_path, options)?;
+        Oblivion::from_world(paths, world)
+    }
+
+    /// Capture the state of the base game, without a save or any plugins other than Oblivion.esm
+    pub fn load_base(paths: GamePaths, cache: &IndexCache) -> Result<Oblivion> {
+        let world = Tes4World::from_plugins_with(
+            iter::once(paths.data_file(BASE_GAME)),
+            LoadOptions::new().cache(cache),
+        )?;
+        Oblivion::from_world(paths, world)
+    }
+
+    fn from_world(paths: GamePaths, mut world: Tes4World) -> Result<Oblivion> {
+        let mut warnings = vec![];
+        // the INI only adjusts a few details, so a bad one shouldn't stop the conversion
+        match Oblivion::load_ini(Some(paths.root())) {
+            Ok(ini) => world.set_ini(ini.map(|(_, ini)| ini)),
+            Err(e) => warnings.push(format!("Ignored {}: {:#}", INI_FILE, e)),
+        }
+        for (path, e) in world.vfs().bad_archives() {
+            warnings.push(format!(
+                "Skipped Oblivion archive {}: {}",
+                path.display(),
+                e
+            ));
+        }
 
         // the defaults here are the hard-coded defaults in the exe, as you can see when opening
         // the CS without any plugins loaded.
@@ -69,9 +124,12 @@ impl Oblivion {
         let expert_min = world.get_float_setting("fMagicSpellLevelExpertMin", 75.0)?;
         let master_min = world.get_float_setting("fMagicSpellLevelMasterMin", 100.0)?;
 
+        let potion_graphics = world.potion_graphics();
+
         Ok(Oblivion {
-            game_dir: oblivion_dir,
+            paths,
             world: RefCell::new(world),
+            potion_graphics,
             skill_use_exp,
             skill_use_factor,
             major_skill_mult,
@@ -85,9 +143,15 @@ impl Oblivion {
             journeyman_min,
             expert_min,
             master_min,
+            warnings,
         })
     }
 
+    /// Gets the non-fatal problems encountered while loading Oblivion
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
     #[cfg(windows)]
     fn detect_dir() -> Result<String> {
         let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
@@ -109,6 +173,71 @@ impl Oblivion {
         Err(anyhow!("Could not detect Morrowind install path"))
     }
 
+    /// Gets the path to Oblivion's folder in the user's My Games directory
+    #[cfg(windows)]
+    fn my_games_dir() -> Result<PathBuf> {
+        let profile = std::env::var_os("USERPROFILE")
+            .ok_or_else(|| anyhow!("Could not detect the My Games directory"))?;
+        Ok(PathBuf::from(profile).join(r"Documents\My Games\Oblivion"))
+    }
+
+    #[cfg(not(windows))]
+    fn my_games_dir() -> Result<PathBuf> {
+        Err(anyhow!("Could not detect the My Games directory"))
+    }
+
+    /// Finds and reads the Oblivion.ini the game uses
+    ///
+    /// The copy of Oblivion.ini in the install directory decides where the game keeps its
+    /// settings: if it turns off bUseMyGamesDirectory, that copy is used, and otherwise the copy in
+    /// the user's My Games directory is. Returns the directory the INI is in along with its
+    /// settings, or `None` if there's no INI to read.
+    pub fn load_ini<P: AsRef<Path>>(game_dir: Option<P>) -> Result<Option<(PathBuf, OblivionIni)>> {
+        let load = |dir: &Path| -> Result<Option<OblivionIni>> {
+            let path = dir.join(INI_FILE);
+            if path.is_file() {
+                let ini = OblivionIni::load(&path)
+                    .with_context(|| format!("Error reading {:?}", path))?;
+                Ok(Some(ini))
+            } else {
+                Ok(None)
+            }
+        };
+
+        if let Some(dir) = game_dir {
+            let dir = dir.as_ref();
+            if let Some(ini) = load(dir)? {
+                if !ini.uses_my_games_directory()? {
+                    return Ok(Some((dir.to_path_buf(), ini)));
+                }
+            }
+        }
+
+        match Oblivion::my_games_dir() {
+            Ok(dir) => Ok(load(&dir)?.map(|ini| (dir, ini))),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Gets the path to the Oblivion saves directory
+    ///
+    /// Unlike Morrowind, Oblivion usually keeps saves in the user's documents folder rather than
+    /// the game directory. The saves directory is relative to Oblivion.ini and can be changed with
+    /// its SLocalSavePath setting.
+    pub fn saves_dir<P: AsRef<Path>>(game_dir: Option<P>) -> Result<PathBuf> {
+        let game_dir = match game_dir {
+            Some(path) => Some(PathBuf::from(path.as_ref())),
+            None => Oblivion::detect_dir().ok().map(PathBuf::from),
+        };
+
+        match Oblivion::load_ini(game_dir)? {
+            Some((ini_dir, ini)) => Ok(ini.saves_dir(ini_dir)),
+            None => Oblivion::my_games_dir()
+                .map(|dir| dir.join("Saves"))
+                .map_err(|_| anyhow!("Could not detect Oblivion saves path")),
+        }
+    }
+
     /// Gets the Morrowind skill equivalent to a given Oblivion skill, if one exists
     pub fn morrowind_skill(skill: tes4::Skill) -> tes3::Skill {
         match skill {
@@ -208,6 +337,11 @@ impl Oblivion {
         self.world.borrow()
     }
 
+    /// Gets the graphics of the world's potions, used to pick graphics for converted potions
+    pub fn potion_graphics(&self) -> &tes4::PotionGraphicsTable {
+        &self.potion_graphics
+    }
+
     /// Gets the Oblivion world mutably
     pub fn world_mut(&self) -> impl Deref<Target = Tes4World> + DerefMut<Target = Tes4World> + '_ {
         self.world.borrow_mut()
@@ -215,11 +349,13 @@ impl Oblivion {
 
     /// Get the path to the game directory
     pub fn game_dir(&self) -> &Path {
-        self.game_dir.as_ref()
+        self.paths.root()
     }
 
     /// Get the path to the game's data directory
+    ///
+    /// If the game has more than one data directory, this is the one with the highest precedence.
     pub fn data_dir(&self) -> PathBuf {
-        self.game_dir.join(Tes4World::PLUGIN_DIR)
+        self.paths.primary_data_dir().to_path_buf()
     }
 }
@@ -0,0 +1,236 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+use tesutil::tes3::{Tes3Field, Tes3Plugin, Tes3Record};
+use tesutil::tes4::{FormId, Tes4Field, Tes4Plugin, Tes4Record};
+use tesutil::{tes3, Form, Plugin, Record};
+
+use crate::config::*;
+use crate::morrowind::{Morrowind, MorrowindToOblivion};
+use crate::names::Names;
+use crate::oblivion::{Oblivion, BASE_GAME};
+use crate::report::{ConversionReport, ReportCategory};
+
+use anyhow::{anyhow, Context, Result};
+
+/// Container for the state of porting a Morrowind plugin's content to an Oblivion plugin
+///
+/// Unlike a character conversion, this doesn't involve any saves. The forms the plugin defines are
+/// converted with the same rules used for a converted character's spells and items and written to
+/// a new plugin that depends only on Oblivion.esm.
+#[derive(Debug)]
+pub struct MorrowindPluginToOblivion {
+    config: Config,
+    mw: Morrowind,
+    ob: Oblivion,
+    source: Tes3Plugin,
+    form_map: HashMap<String, FormId>,
+    names: Names,
+    warnings: RefCell<Vec<String>>,
+    report: RefCell<ConversionReport>,
+}
+
+impl MorrowindPluginToOblivion {
+    /// Prepare a plugin port based on the provided configuration
+    pub fn load(config: Config) -> Result<MorrowindPluginToOblivion> {
+        let source_path = config
+            .source_path
+            .as_ref()
+            .ok_or_else(|| anyhow!("No Morrowind plugin to port"))?;
+        // the plugin's masters are looked up in the Morrowind data directory, but the plugin itself
+        // could be anywhere, so we need its absolute path
+        let source_path = env::current_dir()?.join(source_path);
+        let source = Tes3Plugin::load_file(&source_path)
+            .with_context(|| format!("Failed to read Morrowind plugin {:?}", source_path))?;
+        let plugins: Vec<_> = source
+            .iter_masters()
+            .map(PathBuf::from)
+            .chain(iter::once(source_path))
+            .collect();
+
+        let mw_paths = Morrowind::game_paths(config.mw_path.as_ref(), &config.mw_data)?;
+        let ob_paths = Oblivion::game_paths(config.ob_path.as_ref(), &config.ob_data)?;
+
+        let (cache, cache_warning) = config.open_index_cache();
+        let mut warnings: Vec<String> = cache_warning.into_iter().collect();
+        let cache = Arc::new(cache);
+        let mw_cache = Arc::clone(&cache);
+        let ob_cache = Arc::clone(&cache);
+
+        let mw_thread =
+            thread::spawn(move || Morrowind::load_plugins(mw_paths, plugins, &mw_cache));
+        let ob_thread = thread::spawn(move || Oblivion::load_base(ob_paths, &ob_cache));
+
+        let mw = mw_thread
+            .join()
+            .map_err(|_| anyhow!("Morrowind load failed"))?
+            .with_context(|| "Morrowind load failed")?;
+        let ob = ob_thread
+            .join()
+            .map_err(|_| anyhow!("Oblivion load failed"))?
+            .with_context(|| "Oblivion load failed")?;
+        warnings.extend(config.save_index_cache(&cache));
+        warnings.extend(mw.warnings().iter().cloned());
+        warnings.extend(ob.warnings().iter().cloned());
+        let form_map = MorrowindToOblivion::load_map(&config, &ob.world())?;
+        let names = Names::new(config.name_policy);
+
+        Ok(MorrowindPluginToOblivion {
+            config,
+            mw,
+            ob,
+            source,
+            form_map,
+            names,
+            warnings: RefCell::new(warnings),
+            report: RefCell::new(ConversionReport::new()),
+        })
+    }
+
+    /// Records a non-fatal problem encountered during the port
+    fn warn<T: Into<String>>(&self, message: T) {
+        self.warnings.borrow_mut().push(message.into());
+    }
+
+    /// Gets the non-fatal problems encountered during the port so far
+    ///
+    /// Changes made to names are listed last.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = self.warnings.borrow().clone();
+        warnings.extend(self.names.reports());
+        warnings
+    }
+
+    /// Gets the report of what the port has done so far, including warnings
+    pub fn report(&self) -> ConversionReport {
+        let mut report = self.report.borrow().clone();
+        report.add_warnings(self.warnings());
+        report
+    }
+
+    /// Converts each of the source plugin's forms of one type and adds them to the Oblivion plugin
+    ///
+    /// Forms that are already mapped to an Oblivion equivalent are skipped, since they're edits of
+    /// content Oblivion already has.
+    fn port_forms<T, U, F>(
+        &self,
+        ob_plugin: &mut Tes4Plugin,
+        category: ReportCategory,
+        convert: F,
+    ) -> Result<usize>
+    where
+        T: Form<Field = Tes3Field, Record = Tes3Record>,
+        U: Form<Field = Tes4Field, Record = Tes4Record>,
+        F: Fn(&T) -> Result<Option<U>>,
+    {
+        let records = match self.source.get_records_by_type(T::RECORD_TYPE) {
+            Some(records) => records,
+            None => return Ok(0),
+        };
+
+        let mut count = 0;
+        for mw_record in records {
+            let mw_id = mw_record.id().unwrap_or_default();
+            if self.form_map.contains_key(mw_id) {
+                self.report.borrow_mut().add_skipped(
+                    category,
+                    mw_id,
+                    "it's already mapped to an Oblivion form",
+                );
+                continue;
+            }
+
+            let mw_form = T::read(&mw_record)
+                .with_context(|| format!("Failed to read {}", mw_record.display_name()))?;
+            match convert(&mw_form)? {
+                Some(ob_form) => {
+                    let mut ob_record = Tes4Record::new(U::RECORD_TYPE);
+                    ob_form.write(&mut ob_record)?;
+                    ob_plugin.add_new_record(ob_record)?;
+                    self.report.borrow_mut().add_converted(category, mw_id);
+                    count += 1;
+                }
+                None => {
+                    self.report
+                        .borrow_mut()
+                        .add_skipped(category, mw_id, "no Oblivion equivalent")
+                }
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Ports the plugin's content and writes the Oblivion plugin
+    pub fn convert(&self) -> Result<()> {
+        let source_name = self
+            .config
+            .source_path
+            .as_deref()
+            .and_then(|p| Path::new(p).file_name())
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut ob_plugin = Tes4Plugin::new(
+            Some(String::from("tesconvert")),
+            Some(format!("Ported from Morrowind plugin {}", source_name)),
+        );
+        ob_plugin.add_master(String::from(BASE_GAME))?;
+
+        let combine_strategy = &self.config.combine_strategy;
+        let mut count = 0;
+        count += self.port_forms(
+            &mut ob_plugin,
+            ReportCategory::Spell,
+            |spell: &tes3::Spell| {
+                let ob_spell =
+                    self.mw
+                        .convert_spell(spell, &self.ob, combine_strategy, &self.names)?;
+                Ok(ob_spell.map(|mut ob_spell| {
+                    ob_spell.set_editor_id(Some(String::from(spell.id())));
+                    ob_spell
+                }))
+            },
+        )?;
+        count += self.port_forms(
+            &mut ob_plugin,
+            ReportCategory::Item,
+            |potion: &tes3::Potion| {
+                Morrowind::convert_potion(
+                    potion,
+                    self.ob.potion_graphics(),
+                    combine_strategy,
+                    &self.names,
+                )
+            },
+        )?;
+        count += self.port_forms(
+            &mut ob_plugin,
+            ReportCategory::Class,
+            |class: &tes3::Class| Morrowind::convert_class(class, None, &self.names).map(Some),
+        )?;
+
+        // Oblivion races have far more data than Morrowind races (faces, hair, eyes, voices), and
+        // we can't write race records yet
+        if let Some(races) = self.source.get_records_by_type(b"RACE") {
+            for race in races {
+                self.warn(format!(
+                    "Races are not supported yet; {} was not converted",
+                    race.display_name()
+                ));
+            }
+        }
+
+        if count == 0 {
+            self.warn("The plugin does not define anything that could be converted");
+        }
+
+        ob_plugin.save_file(&self.config.output_path)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,522 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use anyhow::{Context, Result};
+
+/// A format the conversion report can be written in
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ReportFormat {
+    /// Plain text meant to be read in a terminal
+    Text,
+    /// JSON for other tools to parse
+    Json,
+    /// A standalone HTML page with summary tables
+    Html,
+}
+
+/// The kind of thing a report entry is about
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ReportCategory {
+    Spell,
+    Item,
+    Class,
+    Location,
+}
+
+impl ReportCategory {
+    /// All categories, in the order they appear in a report
+    pub const ALL: [ReportCategory; 4] = [
+        ReportCategory::Spell,
+        ReportCategory::Item,
+        ReportCategory::Class,
+        ReportCategory::Location,
+    ];
+
+    /// Gets the category's name as it appears in machine-readable reports
+    pub fn name(self) -> &'static str {
+        match self {
+            ReportCategory::Spell => "spell",
+            ReportCategory::Item => "item",
+            ReportCategory::Class => "class",
+            ReportCategory::Location => "location",
+        }
+    }
+
+    /// Gets the category's heading as it appears in human-readable reports
+    pub fn heading(self) -> &'static str {
+        match self {
+            ReportCategory::Spell => "Spells",
+            ReportCategory::Item => "Items",
+            ReportCategory::Class => "Classes",
+            ReportCategory::Location => "Locations",
+        }
+    }
+}
+
+/// Something the conversion either converted or skipped
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportEntry {
+    pub category: ReportCategory,
+    /// The ID of the thing in the source game
+    pub id: String,
+    /// Why the thing was skipped, or `None` if it was converted
+    pub skip_reason: Option<String>,
+}
+
+/// A container the player owns, along with what's stored in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredContainer {
+    /// The ID of the container in the source game
+    pub id: String,
+    /// The ID and count of each stack of items in the container, or `None` if the container is
+    /// unchanged from the game's data, meaning nothing has been stored in it
+    pub contents: Option<Vec<(String, u32)>>,
+}
+
+/// A cell in the source game where the player owns things
+///
+/// Nothing left in the player's properties is converted, so they're listed in the report to let
+/// users retrieve anything they want to keep first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerProperty {
+    /// The name of the cell
+    pub cell: String,
+    /// Containers the player owns in the cell
+    pub containers: Vec<StoredContainer>,
+}
+
+/// Summary of what a conversion did
+///
+/// Users can share the report when asking for help, so it lists everything that was converted
+/// or skipped along with any warnings.
+#[derive(Debug, Clone, Default)]
+pub struct ConversionReport {
+    entries: Vec<ReportEntry>,
+    properties: Vec<PlayerProperty>,
+    warnings: Vec<String>,
+}
+
+/// Escapes a string for use in JSON, including the surrounding quotes
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Formats a stored container as a JSON object
+fn json_container(container: &StoredContainer) -> String {
+    let contents = match container.contents {
+        Some(ref contents) => {
+            let items: Vec<_> = contents
+                .iter()
+                .map(|(id, count)| format!("{{\"id\": {}, \"count\": {}}}", json_string(id), count))
+                .collect();
+            format!("[{}]", items.join(", "))
+        }
+        None => String::from("null"),
+    };
+
+    format!(
+        "{{\"id\": {}, \"contents\": {}}}",
+        json_string(&container.id),
+        contents
+    )
+}
+
+/// Escapes a string for use in HTML text or attribute values
+fn html_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl ConversionReport {
+    /// Creates an empty report
+    pub fn new() -> ConversionReport {
+        ConversionReport::default()
+    }
+
+    /// Records that something was converted
+    pub fn add_converted<T: Into<String>>(&mut self, category: ReportCategory, id: T) {
+        self.entries.push(ReportEntry {
+            category,
+            id: id.into(),
+            skip_reason: None,
+        });
+    }
+
+    /// Records that something was skipped and why
+    pub fn add_skipped<T: Into<String>, U: Into<String>>(
+        &mut self,
+        category: ReportCategory,
+        id: T,
+        reason: U,
+    ) {
+        self.entries.push(ReportEntry {
+            category,
+            id: id.into(),
+            skip_reason: Some(reason.into()),
+        });
+    }
+
+    /// Adds one of the player's properties to the report
+    pub fn add_property(&mut self, property: PlayerProperty) {
+        self.properties.push(property);
+    }
+
+    /// Adds warnings to the report
+    pub fn add_warnings<I: IntoIterator<Item = String>>(&mut self, warnings: I) {
+        self.warnings.extend(warnings);
+    }
+
+    /// Gets the entries for things that were converted in a category
+    pub fn converted(&self, category: ReportCategory) -> impl Iterator<Item = &ReportEntry> {
+        self.entries
+            .iter()
+            .filter(move |e| e.category == category && e.skip_reason.is_none())
+    }
+
+    /// Gets the entries for things that were skipped in a category
+    pub fn skipped(&self, category: ReportCategory) -> impl Iterator<Item = &ReportEntry> {
+        self.entries
+            .iter()
+            .filter(move |e| e.category == category && e.skip_reason.is_some())
+    }
+
+    /// Gets the player's properties in the report
+    pub fn properties(&self) -> impl Iterator<Item = &PlayerProperty> {
+        self.properties.iter()
+    }
+
+    /// Gets the warnings in the report
+    pub fn warnings(&self) -> impl Iterator<Item = &str> {
+        self.warnings.iter().map(String::as_str)
+    }
+
+    /// Gets the categories that have any entries
+    fn categories(&self) -> impl Iterator<Item = ReportCategory> + '_ {
+        ReportCategory::ALL
+            .into_iter()
+            .filter(|c| self.entries.iter().any(|e| e.category == *c))
+    }
+
+    /// Writes the report in the given format
+    pub fn write<T: Write>(&self, f: T, format: ReportFormat) -> io::Result<()> {
+        match format {
+            ReportFormat::Text => self.write_text(f),
+            ReportFormat::Json => self.write_json(f),
+            ReportFormat::Html => self.write_html(f),
+        }
+    }
+
+    /// Writes the report to a file in the given format, or to stderr if no path is given
+    pub fn save(&self, path: Option<&str>, format: ReportFormat) -> Result<()> {
+        match path {
+            Some(path) => {
+                let f = File::create(path)
+                    .with_context(|| format!("Failed to create report {:?}", path))?;
+                let mut writer = BufWriter::new(f);
+                self.write(&mut writer, format)?;
+                writer.flush()?;
+            }
+            None => self.write(io::stderr().lock(), format)?,
+        }
+
+        Ok(())
+    }
+
+    fn write_text<T: Write>(&self, mut f: T) -> io::Result<()> {
+        for category in self.categories() {
+            writeln!(
+                f,
+                "{}: {} converted, {} skipped",
+                category.heading(),
+                self.converted(category).count(),
+                self.skipped(category).count()
+            )?;
+        }
+
+        for category in self.categories() {
+            if self.skipped(category).next().is_some() {
+                writeln!(f, "\nSkipped {}:", category.heading().to_lowercase())?;
+                for entry in self.skipped(category) {
+                    writeln!(
+                        f,
+                        "  {}: {}",
+                        entry.id,
+                        entry.skip_reason.as_deref().unwrap_or_default()
+                    )?;
+                }
+            }
+        }
+
+        if !self.properties.is_empty() {
+            writeln!(f, "\nPlayer properties (not converted):")?;
+            for property in &self.properties {
+                writeln!(f, "  {}", property.cell)?;
+                for container in &property.containers {
+                    match container.contents {
+                        Some(ref contents) if contents.is_empty() => {
+                            writeln!(f, "    {}: empty", container.id)?
+                        }
+                        Some(ref contents) => {
+                            writeln!(f, "    {}:", container.id)?;
+                            for (id, count) in contents {
+                                writeln!(f, "      {} x{}", id, count)?;
+                            }
+                        }
+                        None => writeln!(f, "    {}: unchanged", container.id)?,
+                    }
+                }
+            }
+        }
+
+        if !self.warnings.is_empty() {
+            writeln!(f)?;
+            for warning in &self.warnings {
+                writeln!(f, "Warning: {}", warning)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_json<T: Write>(&self, mut f: T) -> io::Result<()> {
+        writeln!(f, "{{")?;
+        writeln!(f, "  \"summary\": {{")?;
+        let categories: Vec<_> = self.categories().collect();
+        for (i, category) in categories.iter().enumerate() {
+            writeln!(
+                f,
+                "    {}: {{\"converted\": {}, \"skipped\": {}}}{}",
+                json_string(category.name()),
+                self.converted(*category).count(),
+                self.skipped(*category).count(),
+                if i + 1 < categories.len() { "," } else { "" }
+            )?;
+        }
+        writeln!(f, "  }},")?;
+
+        writeln!(f, "  \"entries\": [")?;
+        for (i, entry) in self.entries.iter().enumerate() {
+            let reason = match entry.skip_reason {
+                Some(ref reason) => json_string(reason),
+                None => String::from("null"),
+            };
+            writeln!(
+                f,
+                "    {{\"category\": {}, \"id\": {}, \"converted\": {}, \"reason\": {}}}{}",
+                json_string(entry.category.name()),
+                json_string(&entry.id),
+                entry.skip_reason.is_none(),
+                reason,
+                if i + 1 < self.entries.len() { "," } else { "" }
+            )?;
+        }
+        writeln!(f, "  ],")?;
+
+        writeln!(f, "  \"properties\": [")?;
+        for (i, property) in self.properties.iter().enumerate() {
+            let containers: Vec<_> = property.containers.iter().map(json_container).collect();
+            writeln!(
+                f,
+                "    {{\"cell\": {}, \"containers\": [{}]}}{}",
+                json_string(&property.cell),
+                containers.join(", "),
+                if i + 1 < self.properties.len() {
+                    ","
+                } else {
+                    ""
+                }
+            )?;
+        }
+        writeln!(f, "  ],")?;
+
+        writeln!(f, "  \"warnings\": [")?;
+        for (i, warning) in self.warnings.iter().enumerate() {
+            writeln!(
+                f,
+                "    {}{}",
+                json_string(warning),
+                if i + 1 < self.warnings.len() { "," } else { "" }
+            )?;
+        }
+        writeln!(f, "  ]")?;
+        writeln!(f, "}}")
+    }
+
+    fn write_html<T: Write>(&self, mut f: T) -> io::Result<()> {
+        writeln!(f, "<!DOCTYPE html>")?;
+        writeln!(f, "<html>")?;
+        writeln!(f, "<head>")?;
+        writeln!(f, "<meta charset=\"utf-8\">")?;
+        writeln!(f, "<title>tesconvert report</title>")?;
+        writeln!(f, "</head>")?;
+        writeln!(f, "<body>")?;
+        writeln!(f, "<h1>Conversion report</h1>")?;
+
+        writeln!(f, "<table>")?;
+        writeln!(
+            f,
+            "<tr><th>Category</th><th>Converted</th><th>Skipped</th></tr>"
+        )?;
+        for category in self.categories() {
+            writeln!(
+                f,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                category.heading(),
+                self.converted(category).count(),
+                self.skipped(category).count()
+            )?;
+        }
+        writeln!(f, "</table>")?;
+
+        for category in self.categories() {
+            writeln!(f, "<h2>{}</h2>", category.heading())?;
+            writeln!(f, "<table>")?;
+            writeln!(f, "<tr><th>ID</th><th>Result</th></tr>")?;
+            for entry in self.converted(category).chain(self.skipped(category)) {
+                let result = match entry.skip_reason {
+                    Some(ref reason) => format!("Skipped: {}", html_escape(reason)),
+                    None => String::from("Converted"),
+                };
+                writeln!(
+                    f,
+                    "<tr><td>{}</td><td>{}</td></tr>",
+                    html_escape(&entry.id),
+                    result
+                )?;
+            }
+            writeln!(f, "</table>")?;
+        }
+
+        if !self.properties.is_empty() {
+            writeln!(f, "<h2>Player properties</h2>")?;
+            writeln!(f, "<table>")?;
+            writeln!(
+                f,
+                "<tr><th>Cell</th><th>Container</th><th>Contents</th></tr>"
+            )?;
+            for property in &self.properties {
+                if property.containers.is_empty() {
+                    writeln!(
+                        f,
+                        "<tr><td>{}</td><td></td><td></td></tr>",
+                        html_escape(&property.cell)
+                    )?;
+                }
+
+                for container in &property.containers {
+                    let contents = match container.contents {
+                        Some(ref contents) => contents
+                            .iter()
+                            .map(|(id, count)| format!("{} x{}", html_escape(id), count))
+                            .collect::<Vec<_>>()
+                            .join("<br>"),
+                        None => String::from("Unchanged"),
+                    };
+                    writeln!(
+                        f,
+                        "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                        html_escape(&property.cell),
+                        html_escape(&container.id),
+                        contents
+                    )?;
+                }
+            }
+            writeln!(f, "</table>")?;
+        }
+
+        if !self.warnings.is_empty() {
+            writeln!(f, "<h2>Warnings</h2>")?;
+            writeln!(f, "<ul>")?;
+            for warning in &self.warnings {
+                writeln!(f, "<li>{}</li>", html_escape(warning))?;
+            }
+            writeln!(f, "</ul>")?;
+        }
+
+        writeln!(f, "</body>")?;
+        writeln!(f, "</html>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_report(report: &ConversionReport, format: ReportFormat) -> String {
+        let mut buf = vec![];
+        report.write(&mut buf, format).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn write_formats() {
+        let mut report = ConversionReport::new();
+        report.add_converted(ReportCategory::Spell, "fireball");
+        report.add_skipped(ReportCategory::Spell, "summon \"dremora\"", "no equivalent");
+        report.add_skipped(
+            ReportCategory::Item,
+            "<scripted>",
+            "scripted items are not supported",
+        );
+        report.add_property(PlayerProperty {
+            cell: String::from("Balmora, Player's House"),
+            containers: vec![
+                StoredContainer {
+                    id: String::from("chest_small_01"),
+                    contents: Some(vec![(String::from("iron dagger"), 2)]),
+                },
+                StoredContainer {
+                    id: String::from("barrel_01"),
+                    contents: None,
+                },
+            ],
+        });
+        report.add_warnings(vec![String::from("Player has no birthsign")]);
+
+        let text = write_report(&report, ReportFormat::Text);
+        assert!(text.starts_with("Spells: 1 converted, 1 skipped\n"));
+        assert!(text.contains("\nItems: 0 converted, 1 skipped\n"));
+        assert!(text
+            .contains("\n  Balmora, Player's House\n    chest_small_01:\n      iron dagger x2\n"));
+        assert!(text.contains("    barrel_01: unchanged\n"));
+        assert!(text.contains("Warning: Player has no birthsign"));
+
+        let json = write_report(&report, ReportFormat::Json);
+        assert!(json.contains("\"spell\": {\"converted\": 1, \"skipped\": 1},"));
+        assert!(json.contains("\"id\": \"summon \\\"dremora\\\"\""));
+        assert!(json.contains("\"reason\": null"));
+        assert!(json.contains(
+            "{\"id\": \"chest_small_01\", \"contents\": [{\"id\": \"iron dagger\", \"count\": 2}]}"
+        ));
+
+        let html = write_report(&report, ReportFormat::Html);
+        assert!(html.contains("<td>&lt;scripted&gt;</td>"));
+        assert!(!html.contains("<h2>Classes</h2>"));
+        assert!(html
+            .contains("<td>Balmora, Player&#39;s House</td><td>barrel_01</td><td>Unchanged</td>"));
+    }
+}
@@ -0,0 +1,101 @@
+use std::io;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use tesutil::tes3::Tes3World;
+use tesutil::tes4::Tes4World;
+use tesutil::{SaveList, SaveMetadata};
+
+use crate::config::*;
+use crate::morrowind::Morrowind;
+use crate::oblivion::Oblivion;
+
+use anyhow::{anyhow, Context, Result};
+
+/// Gets the directory to look for a game's saves in
+fn saves_dir(config: &Config, game: Game) -> Result<PathBuf> {
+    Ok(match (&config.save_dir, game) {
+        (Some(dir), _) => PathBuf::from(dir),
+        (None, Game::Morrowind) => Morrowind::saves_dir(config.mw_path.as_ref())?,
+        (None, Game::Oblivion) => Oblivion::saves_dir(config.ob_path.as_ref())?,
+    })
+}
+
+/// Lists a game's saves, from most to least recently modified
+///
+/// Files that can't be read as saves are listed separately, so the caller can decide how to tell
+/// the user about them.
+pub fn list_saves(config: &Config, game: Game) -> Result<SaveList> {
+    let dir = saves_dir(config, game)?;
+    match game {
+        Game::Morrowind => Tes3World::list_saves(&dir),
+        Game::Oblivion => Tes4World::list_saves(&dir),
+    }
+    .with_context(|| format!("Failed to list saves in {:?}", dir))
+}
+
+/// Gets the path to a game's most recent save
+pub fn latest_save(config: &Config, game: Game) -> Result<PathBuf> {
+    let dir = saves_dir(config, game)?;
+    match game {
+        Game::Morrowind => Tes3World::latest_save(&dir),
+        Game::Oblivion => Tes4World::latest_save(&dir),
+    }
+    .with_context(|| format!("Failed to list saves in {:?}", dir))?
+    .map(|save| save.path)
+    .ok_or_else(|| anyhow!("No saves found in {:?}", dir))
+}
+
+/// Describes a save in a single line
+fn describe(save: &SaveMetadata) -> String {
+    let mut description = save.player_name.clone();
+    if let Some(level) = save.player_level {
+        description.push_str(&format!(", level {}", level));
+    }
+
+    description.push_str(&format!(", {}", save.location));
+
+    if let Some(play_time) = save.play_time {
+        let minutes = play_time.as_secs() / 60;
+        description.push_str(&format!(", played {}h {:02}m", minutes / 60, minutes % 60));
+    }
+
+    if let Some(file_name) = save.path.file_name() {
+        description.push_str(&format!(" ({})", file_name.to_string_lossy()));
+    }
+
+    description
+}
+
+/// Lists a game's saves and prompts the user to choose one
+pub fn choose_save(config: &Config, game: Game) -> Result<PathBuf> {
+    let SaveList {
+        mut saves,
+        unreadable,
+    } = list_saves(config, game)?;
+    for (path, error) in &unreadable {
+        println!("Skipped unreadable save {:?}: {}", path, error);
+    }
+
+    if saves.is_empty() {
+        return Err(anyhow!("No saves found"));
+    }
+
+    for (i, save) in saves.iter().enumerate() {
+        println!("{:>3}. {}", i + 1, describe(save));
+    }
+
+    print!("Choose a save [1-{}]: ", saves.len());
+    io::stdout().flush()?;
+
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    let choice = line.trim();
+    let index = choice
+        .parse::<usize>()
+        .ok()
+        .filter(|i| (1..=saves.len()).contains(i))
+        .ok_or_else(|| anyhow!("Invalid choice {}", choice))?;
+
+    Ok(saves.swap_remove(index - 1).path)
+}
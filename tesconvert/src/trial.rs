@@ -0,0 +1,382 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+
+use tesutil::tes4::{
+    Enchantable, FindForm, FormId, Item, Magic, SpellEffect, Tes4Field, Tes4Record,
+};
+use tesutil::{tes3, tes4, Form, Record};
+
+use crate::config::*;
+use crate::morrowind::{Morrowind, MorrowindToOblivion};
+use crate::names::Names;
+use crate::oblivion::Oblivion;
+use crate::saves::latest_save;
+
+use anyhow::{anyhow, Context, Result};
+
+/// An Oblivion form that can be printed as text by the try commands
+pub(crate) trait Describe {
+    /// Gets the form's fields as label-value pairs, in the order they should be printed
+    fn describe(&self) -> Vec<(&'static str, String)>;
+}
+
+/// Formats an optional value, or "none" if there isn't one
+fn or_none<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| String::from("none"), |v| v.to_string())
+}
+
+/// Describes the fields every item has
+fn describe_item<T: Item>(item: &T) -> Vec<(&'static str, String)> {
+    vec![
+        ("Editor ID", String::from(item.editor_id())),
+        ("Name", String::from(item.name())),
+        ("Value", item.value().to_string()),
+        ("Weight", item.weight().to_string()),
+        ("Script", or_none(item.script())),
+        ("Model", or_none(item.model())),
+        ("Icon", or_none(item.icon())),
+    ]
+}
+
+/// Describes an item's enchantment
+fn describe_enchantable<T: Enchantable>(item: &T) -> Vec<(&'static str, String)> {
+    vec![
+        ("Enchantment", or_none(item.enchantment())),
+        ("Enchantment points", or_none(item.enchantment_points())),
+    ]
+}
+
+/// Describes one effect of a spell, enchantment, or potion
+fn describe_effect(effect: &SpellEffect) -> String {
+    let mut description = format!(
+        "{:?} {} pts in {} ft for {} secs on {:?}",
+        effect.effect_type(),
+        effect.magnitude(),
+        effect.area(),
+        effect.duration(),
+        effect.range(),
+    );
+    if let Some(script_effect) = effect.script_effect() {
+        description.push_str(&format!(
+            " (script {}, \"{}\")",
+            script_effect.script(),
+            script_effect.name()
+        ));
+    }
+    description
+}
+
+/// Describes each effect of a spell, enchantment, or potion
+fn describe_effects<T: Magic>(magic: &T) -> Vec<(&'static str, String)> {
+    magic
+        .iter_effects()
+        .map(|e| ("Effect", describe_effect(e)))
+        .collect()
+}
+
+impl Describe for tes4::Spell {
+    fn describe(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("Editor ID", or_none(self.editor_id())),
+            ("Name", or_none(Magic::name(self))),
+            ("Type", format!("{:?}", self.spell_type)),
+            ("Level", format!("{:?}", self.level)),
+            ("Cost", self.cost.to_string()),
+            ("Auto-calculated", self.is_auto_calc().to_string()),
+        ];
+        fields.extend(describe_effects(self));
+        fields
+    }
+}
+
+impl Describe for tes4::Enchantment {
+    fn describe(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("Name", or_none(Magic::name(self))),
+            ("Type", format!("{:?}", self.data.enchantment_type)),
+            ("Charge", self.data.charge.to_string()),
+            ("Cost", self.data.cost.to_string()),
+            ("Auto-calculated", self.data.is_auto_calc.to_string()),
+        ];
+        fields.extend(describe_effects(self));
+        fields
+    }
+}
+
+impl Describe for tes4::Potion {
+    fn describe(&self) -> Vec<(&'static str, String)> {
+        let mut fields = describe_item(self);
+        fields.push(("Food", self.is_food_item.to_string()));
+        fields.push(("Poison", self.is_poison().to_string()));
+        fields.extend(describe_effects(self));
+        fields
+    }
+}
+
+impl Describe for tes4::Ammo {
+    fn describe(&self) -> Vec<(&'static str, String)> {
+        let mut fields = describe_item(self);
+        fields.extend(describe_enchantable(self));
+        fields.push(("Speed", self.data.speed.to_string()));
+        fields.push(("Damage", self.data.damage.to_string()));
+        fields
+    }
+}
+
+impl Describe for tes4::Book {
+    fn describe(&self) -> Vec<(&'static str, String)> {
+        let mut fields = describe_item(self);
+        fields.extend(describe_enchantable(self));
+        fields.push(("Scroll", self.is_scroll().to_string()));
+        fields.push((
+            "Skill",
+            or_none(self.data.skill.map(|s| format!("{:?}", s))),
+        ));
+        fields.push(("Text", self.text.clone()));
+        fields
+    }
+}
+
+impl Describe for tes4::Weapon {
+    fn describe(&self) -> Vec<(&'static str, String)> {
+        let mut fields = describe_item(self);
+        fields.extend(describe_enchantable(self));
+        fields.push(("Type", format!("{:?}", self.data.weapon_type)));
+        fields.push(("Speed", self.data.speed.to_string()));
+        fields.push(("Reach", self.data.reach.to_string()));
+        fields.push(("Health", self.data.health.to_string()));
+        fields.push(("Damage", self.data.damage.to_string()));
+        fields
+    }
+}
+
+impl Describe for tes4::Armor {
+    fn describe(&self) -> Vec<(&'static str, String)> {
+        let mut fields = describe_item(self);
+        fields.extend(describe_enchantable(self));
+        fields.push(("Slots", format!("{:?}", self.biped.slots)));
+        fields.push(("Heavy armor", self.biped.is_heavy_armor().to_string()));
+        fields.push((
+            "Armor rating",
+            (self.data.armor_rating as f32 / 100.).to_string(),
+        ));
+        fields.push(("Health", self.data.health.to_string()));
+        fields
+    }
+}
+
+impl Describe for tes4::Clothing {
+    fn describe(&self) -> Vec<(&'static str, String)> {
+        let mut fields = describe_item(self);
+        fields.extend(describe_enchantable(self));
+        fields.push(("Slots", format!("{:?}", self.biped.slots)));
+        fields
+    }
+}
+
+/// Prints an Oblivion form along with the ID of the Morrowind form it came from
+pub(crate) fn print_form<T: Describe>(mw_id: &str, ob_form: &T) {
+    println!("{} converts to:", mw_id);
+    for (label, value) in ob_form.describe() {
+        println!("  {}: {}", label, value);
+    }
+}
+
+/// Container for the state of trying out the conversion of a single Morrowind form
+///
+/// This runs the same conversion routine a character conversion would for one spell or item and
+/// prints the resulting Oblivion form instead of saving it, which makes it quick to see the effect
+/// of changes to the mapping files and balancing options. Morrowind is loaded with the plugins in
+/// Morrowind.ini, but only Oblivion.esm is loaded for Oblivion, so mappings to forms from other
+/// Oblivion plugins are ignored.
+#[derive(Debug)]
+pub struct ConversionTrial {
+    config: Config,
+    mw: Morrowind,
+    ob: Oblivion,
+    form_map: HashMap<String, FormId>,
+    names: Names,
+}
+
+impl ConversionTrial {
+    /// Loads both games based on the provided configuration
+    pub fn load(config: Config) -> Result<ConversionTrial> {
+        let mw_paths = Morrowind::game_paths(config.mw_path.as_ref(), &config.mw_data)?;
+        let ob_paths = Oblivion::game_paths(config.ob_path.as_ref(), &config.ob_data)?;
+
+        let (cache, cache_warning) = config.open_index_cache();
+        let mut warnings: Vec<String> = cache_warning.into_iter().collect();
+        let cache = Arc::new(cache);
+        let mw_cache = Arc::clone(&cache);
+        let ob_cache = Arc::clone(&cache);
+
+        let mw_thread = thread::spawn(move || Morrowind::load_world(mw_paths, &mw_cache));
+        let ob_thread = thread::spawn(move || Oblivion::load_base(ob_paths, &ob_cache));
+
+        let mw = mw_thread
+            .join()
+            .map_err(|_| anyhow!("Morrowind load failed"))?
+            .with_context(|| "Morrowind load failed")?;
+        let ob = ob_thread
+            .join()
+            .map_err(|_| anyhow!("Oblivion load failed"))?
+            .with_context(|| "Oblivion load failed")?;
+        warnings.extend(config.save_index_cache(&cache));
+        warnings.extend(mw.warnings().iter().cloned());
+        warnings.extend(ob.warnings().iter().cloned());
+        let form_map = MorrowindToOblivion::load_map(&config, &ob.world())?;
+        let names = Names::new(config.name_policy);
+        // a trial has no report, so problems loading the games are printed along with its output
+        for warning in &warnings {
+            eprintln!("{}", warning);
+        }
+
+        Ok(ConversionTrial {
+            config,
+            mw,
+            ob,
+            form_map,
+            names,
+        })
+    }
+
+    /// Prints the changes made to names, if any
+    fn print_name_changes(&self) {
+        for report in self.names.reports() {
+            eprintln!("{}", report);
+        }
+    }
+
+    /// Converts a Morrowind spell and prints the Oblivion spell
+    ///
+    /// Like a character's spells, the spell is converted even if it's mapped to an Oblivion form.
+    pub fn try_spell(&self, id: &str) -> Result<()> {
+        let mw_spell: tes3::Spell = self
+            .mw
+            .world
+            .get(id)?
+            .ok_or_else(|| anyhow!("No Morrowind spell has the ID {}", id))?;
+
+        match self.mw.convert_spell(
+            &mw_spell,
+            &self.ob,
+            &self.config.combine_strategy,
+            &self.names,
+        )? {
+            Some(ob_spell) => print_form(id, &ob_spell),
+            None => println!("{} has no Oblivion equivalent", id),
+        }
+
+        self.print_name_changes();
+        Ok(())
+    }
+
+    /// Reads an Oblivion form and prints it
+    fn print_mapped_form<T>(&self, mw_id: &str, record: &Tes4Record) -> Result<()>
+    where
+        T: Form<Field = Tes4Field, Record = Tes4Record> + Describe,
+    {
+        print_form(mw_id, &T::read(record)?);
+        Ok(())
+    }
+
+    /// Converts an unmapped item the way a character conversion would and prints it
+    fn try_item_in_conversion(self, id: &str) -> Result<()> {
+        // free this trial's worlds before loading the conversion's
+        let ConversionTrial { mut config, .. } = self;
+        config.target_path = latest_save(&config, Game::Oblivion)
+            .with_context(|| "Could not find the most recent Oblivion save")?
+            .to_string_lossy()
+            .into_owned();
+        let mw2ob = MorrowindToOblivion::load(config)?;
+        mw2ob.try_item(id)?;
+        for warning in mw2ob.warnings() {
+            eprintln!("{}", warning);
+        }
+        Ok(())
+    }
+
+    /// Converts a Morrowind item and prints the Oblivion item
+    ///
+    /// If the item is mapped to an Oblivion item, the mapped item is printed instead. Other items
+    /// need their models looked up from the mappings and their enchantments added to a save, so
+    /// they're converted by a full character conversion loaded from the most recent save of each
+    /// game. The Oblivion save only serves as a scratch save for the new forms and is never
+    /// written.
+    pub fn try_item(self, id: &str) -> Result<()> {
+        let mw_record = self
+            .mw
+            .world
+            .get_record(id)?
+            .ok_or_else(|| anyhow!("No Morrowind item has the ID {}", id))?;
+
+        if let Some(form_id) = self.form_map.get(id).copied() {
+            let world = self.ob.world();
+            let ob_record = world
+                .get_record(&FindForm::ByIndex(form_id))
+                .ok_or_else(|| anyhow!("{} is mapped to missing form {:08X}", id, form_id.0))?;
+            println!("{} is mapped to Oblivion form {:08X}", id, form_id.0);
+            return match ob_record.name() {
+                tes4::Ammo::RECORD_TYPE => self.print_mapped_form::<tes4::Ammo>(id, &ob_record),
+                tes4::Armor::RECORD_TYPE => self.print_mapped_form::<tes4::Armor>(id, &ob_record),
+                tes4::Book::RECORD_TYPE => self.print_mapped_form::<tes4::Book>(id, &ob_record),
+                tes4::Clothing::RECORD_TYPE => {
+                    self.print_mapped_form::<tes4::Clothing>(id, &ob_record)
+                }
+                tes4::Potion::RECORD_TYPE => self.print_mapped_form::<tes4::Potion>(id, &ob_record),
+                tes4::Weapon::RECORD_TYPE => self.print_mapped_form::<tes4::Weapon>(id, &ob_record),
+                _ => {
+                    println!("Forms of this type can't be displayed yet");
+                    Ok(())
+                }
+            };
+        }
+
+        let record_type = *mw_record.name();
+        match &record_type {
+            tes3::Potion::RECORD_TYPE => {
+                let mw_potion = tes3::Potion::read(&*mw_record)?;
+                match Morrowind::convert_potion(
+                    &mw_potion,
+                    self.ob.potion_graphics(),
+                    &self.config.combine_strategy,
+                    &self.names,
+                )? {
+                    Some(ob_potion) => print_form(id, &ob_potion),
+                    None => println!("{} has no Oblivion equivalent", id),
+                }
+            }
+            tes3::Armor::RECORD_TYPE
+            | tes3::Book::RECORD_TYPE
+            | tes3::Clothing::RECORD_TYPE
+            | tes3::Weapon::RECORD_TYPE => {
+                drop(mw_record);
+                return self.try_item_in_conversion(id);
+            }
+            _ => return Err(anyhow!("{} is not an item that can be converted", id)),
+        }
+
+        self.print_name_changes();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_weapon() {
+        let mut weapon =
+            tes4::Weapon::new(String::from("iron dagger"), String::from("Iron Dagger"));
+        weapon.data.value = 10;
+        weapon.data.damage = 7;
+
+        let fields = weapon.describe();
+        assert!(fields.contains(&("Name", String::from("Iron Dagger"))));
+        assert!(fields.contains(&("Damage", String::from("7"))));
+        assert!(fields.contains(&("Enchantment", String::from("none"))));
+        assert!(fields.contains(&("Model", String::from("none"))));
+    }
+}
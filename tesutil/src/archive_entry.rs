@@ -0,0 +1,334 @@
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use crate::TesError;
+
+#[derive(Debug)]
+struct SharedState<R> {
+    reader: R,
+    // None when a failed read or seek left the reader's position unknown
+    pos: Option<u64>,
+}
+
+/// A handle to a reader that's shared between an archive and the entries streamed from it
+///
+/// Each handle has its own position, starting at the beginning of the reader, so entries opened
+/// from the same archive can be read independently of each other. Reads lock the underlying reader
+/// and only seek it when another handle has moved it since.
+#[derive(Debug)]
+pub struct SharedReader<R> {
+    shared: Arc<Mutex<SharedState<R>>>,
+    pos: u64,
+}
+
+impl<R> SharedReader<R> {
+    /// Shares a reader
+    pub fn new(reader: R) -> SharedReader<R> {
+        SharedReader {
+            shared: Arc::new(Mutex::new(SharedState { reader, pos: None })),
+            pos: 0,
+        }
+    }
+}
+
+impl<R> Clone for SharedReader<R> {
+    fn clone(&self) -> Self {
+        SharedReader {
+            shared: Arc::clone(&self.shared),
+            pos: self.pos,
+        }
+    }
+}
+
+impl<R: Read + Seek> Read for SharedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut state = self.shared.lock().unwrap();
+        if state.pos.take() != Some(self.pos) {
+            state.reader.seek(SeekFrom::Start(self.pos))?;
+        }
+
+        let bytes_read = state.reader.read(buf)?;
+        self.pos += bytes_read as u64;
+        state.pos = Some(self.pos);
+        Ok(bytes_read)
+    }
+}
+
+impl<R: Read + Seek> Seek for SharedReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.pos = match pos {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Seek to a position before the start of a reader",
+                )
+            })?,
+            SeekFrom::End(_) => {
+                let mut state = self.shared.lock().unwrap();
+                state.pos = None;
+                let new_pos = state.reader.seek(pos)?;
+                state.pos = Some(new_pos);
+                new_pos
+            }
+        };
+        Ok(self.pos)
+    }
+}
+
+/// A reader over the stored data of a single file in an archive
+///
+/// The entry only reads the bytes that belong to the file, so a large file can be streamed without
+/// buffering all of it. Positions are relative to the start of the file's data.
+#[derive(Debug)]
+pub struct ArchiveEntry<R: Read + Seek> {
+    reader: R,
+    start: u64,
+    len: u64,
+    pos: u64,
+    // whether the underlying reader needs to be moved to `pos` before the next read
+    needs_seek: bool,
+}
+
+impl<R: Read + Seek> ArchiveEntry<R> {
+    /// Creates an entry for the `len` bytes starting at offset `start` in a reader
+    pub(crate) fn new(reader: R, start: u64, len: u64) -> ArchiveEntry<R> {
+        ArchiveEntry {
+            reader,
+            start,
+            len,
+            pos: 0,
+            needs_seek: true,
+        }
+    }
+
+    /// Gets the size of the entry's data in bytes
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns whether the entry has no data
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<R: Read + Seek> Read for ArchiveEntry<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.pos);
+        if remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.needs_seek {
+            self.reader.seek(SeekFrom::Start(self.start + self.pos))?;
+            self.needs_seek = false;
+        }
+
+        let size = buf.len().min(remaining.try_into().unwrap_or(usize::MAX));
+        let bytes_read = self.reader.read(&mut buf[..size])?;
+        self.pos += bytes_read as u64;
+        Ok(bytes_read)
+    }
+}
+
+impl<R: Read + Seek> Seek for ArchiveEntry<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Seek to a position before the start of an archive entry",
+            )
+        })?;
+
+        if new_pos != self.pos {
+            self.pos = new_pos;
+            self.needs_seek = true;
+        }
+        Ok(self.pos)
+    }
+}
+
+/// Reads entries from an archive and processes their data on worker threads
+///
+/// `entries` gives the index, offset, and size of each entry. Since the entries share a reader,
+/// they're read in order on the calling thread, and `num_threads` threads (at least one) process
+/// them as they're read. Processing stops at the first error, which is returned.
+pub(crate) fn process_entries_in_parallel<R, I, F, E>(
+    reader: &mut R,
+    entries: I,
+    num_threads: usize,
+    process: F,
+) -> Result<(), E>
+where
+    R: Read + Seek,
+    I: IntoIterator<Item = (usize, u64, u64)>,
+    F: Fn(usize, Vec<u8>) -> Result<(), E> + Sync,
+    E: From<TesError> + Send,
+{
+    let num_threads = num_threads.max(1);
+    // bound the queue so a fast reader doesn't pull the whole archive into memory
+    let (sender, receiver) = mpsc::sync_channel::<(usize, Vec<u8>)>(num_threads * 2);
+    let receiver = Mutex::new(receiver);
+    let error = Mutex::new(None);
+
+    thread::scope(|scope| {
+        for _ in 0..num_threads {
+            scope.spawn(|| loop {
+                let next = receiver.lock().unwrap().recv();
+                let (index, data) = match next {
+                    Ok(entry) => entry,
+                    Err(_) => break,
+                };
+
+                // once something has failed, keep draining the queue so the reader isn't blocked
+                if error.lock().unwrap().is_some() {
+                    continue;
+                }
+
+                if let Err(e) = process(index, data) {
+                    error.lock().unwrap().get_or_insert(e);
+                }
+            });
+        }
+
+        for (index, offset, size) in entries {
+            if error.lock().unwrap().is_some() {
+                break;
+            }
+
+            let mut data = vec![0u8; size as usize];
+            let result = reader
+                .seek(SeekFrom::Start(offset))
+                .and_then(|_| reader.read_exact(&mut data));
+            if let Err(e) = result {
+                error
+                    .lock()
+                    .unwrap()
+                    .get_or_insert(E::from(TesError::from(e)));
+                break;
+            }
+
+            if sender.send((index, data)).is_err() {
+                break;
+            }
+        }
+
+        // closing the queue lets the workers finish
+        drop(sender);
+    });
+
+    match error.into_inner().unwrap() {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn read_entry() {
+        let mut archive = Cursor::new(b"headerdata of the fileother file".to_vec());
+        let mut entry = ArchiveEntry::new(&mut archive, 6, 16);
+        assert_eq!(entry.len(), 16);
+
+        let mut data = String::new();
+        entry.read_to_string(&mut data).unwrap();
+        assert_eq!(data, "data of the file");
+
+        assert_eq!(entry.seek(SeekFrom::End(-4)).unwrap(), 12);
+        let mut buf = [0u8; 8];
+        assert_eq!(entry.read(&mut buf).unwrap(), 4);
+        assert_eq!(&buf[..4], b"file");
+
+        entry.seek(SeekFrom::Start(5)).unwrap();
+        assert_eq!(entry.seek(SeekFrom::Current(-1)).unwrap(), 4);
+        entry.read_exact(&mut buf[..6]).unwrap();
+        assert_eq!(&buf[..6], b" of th");
+
+        assert!(entry.seek(SeekFrom::Current(-20)).is_err());
+        entry.seek(SeekFrom::Start(40)).unwrap();
+        assert_eq!(entry.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn share_reader() {
+        let mut first = SharedReader::new(Cursor::new(b"0123456789".to_vec()));
+        let mut second = first.clone();
+        let mut buf = [0u8; 3];
+
+        first.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"012");
+        second.seek(SeekFrom::Start(6)).unwrap();
+        second.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"678");
+        // each handle picks up where it left off
+        first.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"345");
+
+        let mut third = second.clone();
+        assert_eq!(third.seek(SeekFrom::End(-1)).unwrap(), 9);
+        assert_eq!(third.read(&mut buf).unwrap(), 1);
+        assert_eq!(buf[0], b'9');
+        assert_eq!(second.seek(SeekFrom::Current(-2)).unwrap(), 7);
+        second.read_exact(&mut buf[..2]).unwrap();
+        assert_eq!(&buf[..2], b"78");
+        assert!(first.seek(SeekFrom::Current(-7)).is_err());
+
+        // entries can be streamed from separate handles at the same time
+        let mut entry1 = ArchiveEntry::new(first.clone(), 0, 4);
+        let mut entry2 = ArchiveEntry::new(first, 4, 4);
+        entry1.read_exact(&mut buf[..2]).unwrap();
+        entry2.read_exact(&mut buf[2..]).unwrap();
+        assert_eq!(&buf, b"014");
+    }
+
+    #[test]
+    fn process_entries() {
+        let mut archive = Cursor::new(b"aabbbcccc".to_vec());
+        let entries = vec![(0, 0, 2), (1, 2, 3), (2, 5, 4)];
+        let results = Mutex::new(vec![]);
+        process_entries_in_parallel(&mut archive, entries, 2, |index, data| {
+            results.lock().unwrap().push((index, data));
+            Ok::<(), TesError>(())
+        })
+        .unwrap();
+
+        let mut results = results.into_inner().unwrap();
+        results.sort();
+        assert_eq!(
+            results,
+            vec![
+                (0, b"aa".to_vec()),
+                (1, b"bbb".to_vec()),
+                (2, b"cccc".to_vec())
+            ]
+        );
+
+        let result =
+            process_entries_in_parallel(&mut archive, vec![(0, 0, 2), (1, 5, 4)], 3, |_, data| {
+                if data == b"cccc" {
+                    Err(TesError::RequirementFailed(String::from("bad entry")))
+                } else {
+                    Ok(())
+                }
+            });
+        assert!(result.is_err());
+
+        // reading past the end of the archive fails
+        let result = process_entries_in_parallel(&mut archive, vec![(0, 8, 4)], 1, |_, _| {
+            Ok::<_, TesError>(())
+        });
+        assert!(result.is_err());
+    }
+}
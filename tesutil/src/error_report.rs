@@ -0,0 +1,109 @@
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::TesError;
+
+/// A machine-readable description of a [`TesError`]
+///
+/// Front-ends that want to show their own (e.g., localized) messages can map [`code`] to their
+/// own text instead of matching on the error's English message.
+///
+/// [`TesError`]: enum.TesError.html
+/// [`code`]: #structfield.code
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ErrorReport {
+    /// Stable code identifying the kind of error; see [`TesError::code`]
+    ///
+    /// [`TesError::code`]: enum.TesError.html#method.code
+    pub code: &'static str,
+    /// English description of the error
+    pub message: String,
+    /// The record the error occurred in, if known
+    pub record: Option<String>,
+    /// Offset of that record in its file, if known
+    pub offset: Option<u64>,
+}
+
+impl TesError {
+    /// Gets a code identifying the kind of error
+    ///
+    /// Codes are stable across versions, unlike error messages. Errors that only add context
+    /// report the code of the error they wrap.
+    pub fn code(&self) -> &'static str {
+        match self {
+            TesError::DuplicateId(_) => "duplicate_id",
+            TesError::AmbiguousId { .. } => "ambiguous_id",
+            TesError::DuplicateMaster(_) => "duplicate_master",
+            TesError::LimitExceeded { .. } => "limit_exceeded",
+            TesError::OutOfRange { .. } => "out_of_range",
+            TesError::RequirementFailed(_) => "requirement_failed",
+            TesError::InvalidMapping(_, _) => "invalid_mapping",
+            TesError::InvalidId(_) => "invalid_id",
+            #[cfg(feature = "tes4")]
+            TesError::InvalidFormId { .. } => "invalid_form_id",
+            #[cfg(feature = "tes4")]
+            TesError::InvalidPluginForm { .. } => "invalid_plugin_form",
+            TesError::DecodeFailed { .. } => "decode_failed",
+            TesError::IoError(_) => "io_error",
+            #[cfg(feature = "world")]
+            TesError::IniError(_) => "ini_error",
+            TesError::BinaryDataError(_) => "binary_data_error",
+            TesError::InRecord { source, .. } => source.code(),
+        }
+    }
+
+    /// Builds a machine-readable description of this error
+    pub fn report(&self) -> ErrorReport {
+        let mut error = self;
+        let mut record = None;
+        let mut offset = None;
+        while let TesError::InRecord {
+            record: error_record,
+            offset: error_offset,
+            source,
+        } = error
+        {
+            // the outermost context is the one that locates the record in the file
+            record = record.or_else(|| Some(error_record.clone()));
+            offset = offset.or(*error_offset);
+            error = source.as_ref();
+        }
+
+        ErrorReport {
+            code: error.code(),
+            message: error.to_string(),
+            record,
+            offset,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_failed;
+
+    #[test]
+    fn report_in_record() {
+        let error = TesError::InRecord {
+            record: String::from("CREA BM_bear_black_summon"),
+            offset: Some(0x1f4),
+            source: Box::new(decode_failed("Unexpected field XYZW")),
+        };
+        assert_eq!(error.code(), "decode_failed");
+        assert_eq!(
+            error.report(),
+            ErrorReport {
+                code: "decode_failed",
+                message: String::from("Decode failed: Unexpected field XYZW"),
+                record: Some(String::from("CREA BM_bear_black_summon")),
+                offset: Some(0x1f4),
+            }
+        );
+
+        let report = TesError::InvalidId(String::from("fireball")).report();
+        assert_eq!(report.code, "invalid_id");
+        assert_eq!(report.record, None);
+    }
+}
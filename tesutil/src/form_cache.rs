@@ -0,0 +1,130 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, RwLock};
+
+use crate::TesError;
+
+type CachedForm = Arc<dyn Any + Send + Sync>;
+
+/// A cache of forms that have already been read from their records
+///
+/// Reading a form from its record means parsing all of the record's fields, which adds up when a
+/// converter looks up the same races, classes, or spells over and over. The cache keeps each form
+/// the first time it's read, keyed by the ID of its record and the form's type, so the same record
+/// can be cached as more than one type of form. The ID must identify the record across the whole
+/// load order; this is the caller's responsibility.
+///
+/// The cache doesn't know when a record changes, so the owner must [`invalidate`] a record whenever
+/// it's borrowed mutably, or [`clear`] the cache when a whole plugin is.
+///
+/// [`invalidate`]: #method.invalidate
+/// [`clear`]: #method.clear
+#[derive(Debug)]
+pub struct FormCache<K> {
+    forms: RwLock<HashMap<(K, TypeId), CachedForm>>,
+}
+
+impl<K: Eq + Hash> Default for FormCache<K> {
+    fn default() -> FormCache<K> {
+        FormCache::new()
+    }
+}
+
+impl<K: Eq + Hash> FormCache<K> {
+    /// Creates an empty cache
+    pub fn new() -> FormCache<K> {
+        FormCache {
+            forms: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Gets a cached form, reading and caching it with `read` if it isn't cached yet
+    ///
+    /// If `read` returns `None`, nothing is cached, so the form will be read again on the next call.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `read` fails.
+    pub fn get_or_read<T, F>(&self, id: K, read: F) -> Result<Option<Arc<T>>, TesError>
+    where
+        T: Any + Send + Sync,
+        F: FnOnce() -> Result<Option<T>, TesError>,
+    {
+        let key = (id, TypeId::of::<T>());
+        if let Some(form) = self.forms.read().unwrap().get(&key) {
+            // the TypeId in the key guarantees the downcast will succeed
+            return Ok(Arc::clone(form).downcast().ok());
+        }
+
+        Ok(match read()? {
+            Some(form) => {
+                let form = Arc::new(form);
+                self.forms
+                    .write()
+                    .unwrap()
+                    .insert(key, Arc::clone(&form) as CachedForm);
+                Some(form)
+            }
+            None => None,
+        })
+    }
+
+    /// Removes every cached form read from the record with the given ID
+    pub fn invalidate(&self, id: &K) {
+        self.forms.write().unwrap().retain(|(k, _), _| k != id);
+    }
+
+    /// Removes all cached forms
+    pub fn clear(&self) {
+        self.forms.write().unwrap().clear();
+    }
+
+    /// Gets the number of cached forms
+    pub fn len(&self) -> usize {
+        self.forms.read().unwrap().len()
+    }
+
+    /// Returns whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.forms.read().unwrap().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_and_invalidate() {
+        let cache: FormCache<u32> = FormCache::new();
+        let form = cache
+            .get_or_read(1, || Ok(Some(String::from("a"))))
+            .unwrap();
+        assert_eq!(form.as_deref().map(String::as_str), Some("a"));
+
+        // cached, so the new value is ignored
+        let form = cache
+            .get_or_read(1, || Ok(Some(String::from("b"))))
+            .unwrap();
+        assert_eq!(form.as_deref().map(String::as_str), Some("a"));
+
+        // same ID as a different type
+        let form = cache.get_or_read(1, || Ok(Some(7u8))).unwrap();
+        assert_eq!(form.as_deref(), Some(&7));
+        assert_eq!(cache.len(), 2);
+
+        assert!(cache
+            .get_or_read::<String, _>(2, || Ok(None))
+            .unwrap()
+            .is_none());
+        assert_eq!(cache.len(), 2);
+
+        cache.invalidate(&1);
+        assert!(cache.is_empty());
+        let form = cache
+            .get_or_read(1, || Ok(Some(String::from("b"))))
+            .unwrap();
+        assert_eq!(form.as_deref().map(String::as_str), Some("b"));
+    }
+}
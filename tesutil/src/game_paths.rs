@@ -0,0 +1,129 @@
+use std::path::{Path, PathBuf};
+
+/// Where a game's files are found
+///
+/// A game has an install root, which holds files like its INI, and one or more data directories
+/// that plugins are loaded from. Usually the only data directory is the one in the install root,
+/// but portable installs and OpenMW can put data directories elsewhere, and OpenMW can layer
+/// several of them. Data directories are searched from last to first, so when a file exists in
+/// more than one of them, the copy in the last one wins.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GamePaths {
+    root: PathBuf,
+    data_dirs: Vec<PathBuf>,
+}
+
+impl GamePaths {
+    /// Creates the paths for a game with a single data directory
+    ///
+    /// [`World::game_paths`] creates paths with the data directory in its usual place in the
+    /// install root.
+    ///
+    /// [`World::game_paths`]: trait.World.html#method.game_paths
+    pub fn new<P, Q>(root: P, data_dir: Q) -> GamePaths
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        GamePaths {
+            root: root.as_ref().to_path_buf(),
+            data_dirs: vec![data_dir.as_ref().to_path_buf()],
+        }
+    }
+
+    /// Gets the game's install root
+    pub fn root(&self) -> &Path {
+        self.root.as_path()
+    }
+
+    /// Iterates through the data directories, from lowest to highest precedence
+    pub fn data_dirs(&self) -> impl Iterator<Item = &Path> {
+        self.data_dirs.iter().map(PathBuf::as_path)
+    }
+
+    /// Gets the data directory with the highest precedence
+    ///
+    /// This is where files that the game should load are written.
+    pub fn primary_data_dir(&self) -> &Path {
+        // there's always at least one data directory
+        self.data_dirs.last().unwrap().as_path()
+    }
+
+    /// Adds a data directory with a higher precedence than all the existing ones
+    pub fn add_data_dir<P: AsRef<Path>>(&mut self, dir: P) {
+        self.data_dirs.push(dir.as_ref().to_path_buf());
+    }
+
+    /// Replaces the data directories, given from lowest to highest precedence
+    ///
+    /// If `dirs` is empty, the existing data directories are kept.
+    pub fn set_data_dirs<P, T>(&mut self, dirs: T)
+    where
+        P: AsRef<Path>,
+        T: IntoIterator<Item = P>,
+    {
+        let dirs: Vec<_> = dirs.into_iter().map(|d| d.as_ref().to_path_buf()).collect();
+        if !dirs.is_empty() {
+            self.data_dirs = dirs;
+        }
+    }
+
+    /// Finds a file in the data directories
+    ///
+    /// Returns the path to the file in the data directory with the highest precedence that has it,
+    /// or `None` if none of them do.
+    pub fn find_data_file<P: AsRef<Path>>(&self, name: P) -> Option<PathBuf> {
+        let name = name.as_ref();
+        self.data_dirs
+            .iter()
+            .rev()
+            .map(|d| d.join(name))
+            .find(|p| p.is_file())
+    }
+
+    /// Gets the path a file in the data directories should be read from
+    ///
+    /// This is the same as [`find_data_file`], except that if the file isn't in any of the data
+    /// directories, the path it would have in the primary data directory is returned instead.
+    ///
+    /// [`find_data_file`]: #method.find_data_file
+    pub fn data_file<P: AsRef<Path>>(&self, name: P) -> PathBuf {
+        let name = name.as_ref();
+        self.find_data_file(name)
+            .unwrap_or_else(|| self.primary_data_dir().join(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layered_data_dirs() {
+        let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let mw_data = base_dir.join("src/tes3/plugin/test/Data Files");
+        let ob_data = base_dir.join("src/tes4/plugin/test/Data");
+
+        let mut paths = GamePaths::new(base_dir, &mw_data);
+        assert_eq!(paths.primary_data_dir(), mw_data);
+        assert_eq!(paths.find_data_file("Oblivion.esm"), None);
+        assert_eq!(
+            paths.data_file("Oblivion.esm"),
+            mw_data.join("Oblivion.esm")
+        );
+
+        paths.add_data_dir(&ob_data);
+        assert_eq!(paths.primary_data_dir(), ob_data);
+        assert_eq!(
+            paths.find_data_file("Oblivion.esm"),
+            Some(ob_data.join("Oblivion.esm"))
+        );
+        assert_eq!(paths.data_file("test1.esp"), mw_data.join("test1.esp"));
+
+        paths.set_data_dirs(Vec::<PathBuf>::new());
+        assert_eq!(paths.data_dirs().count(), 2);
+        paths.set_data_dirs([&ob_data]);
+        assert_eq!(paths.data_dirs().collect::<Vec<_>>(), [ob_data.as_path()]);
+        assert_eq!(paths.root(), base_dir);
+    }
+}
@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Cursor, ErrorKind, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{checked_len, decode_failed, Plugin, RecordIndex, TesError};
+
+use binrw::{BinReaderExt, BinWriterExt};
+
+const CACHE_MAGIC: &[u8; 4] = b"TESX";
+const CACHE_VERSION: u32 = 1;
+
+/// Identifies the exact contents of a plugin file an index was built from
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct FileStamp {
+    size: u64,
+    modified: (u64, u32),
+    hash: u64,
+}
+
+impl FileStamp {
+    fn new(size: u64, modified: Option<SystemTime>, data: &[u8]) -> FileStamp {
+        FileStamp {
+            size,
+            modified: file_time(modified),
+            hash: content_hash(data),
+        }
+    }
+
+    /// Checks whether a file's size and modification time are the ones in this stamp
+    fn has_same_metadata(&self, size: u64, modified: (u64, u32)) -> bool {
+        self.size == size && self.modified == modified
+    }
+}
+
+/// Converts a file modification time to seconds and nanoseconds since the Unix epoch
+fn file_time(modified: Option<SystemTime>) -> (u64, u32) {
+    modified
+        .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+        .map_or((0, 0), |d| (d.as_secs(), d.subsec_nanos()))
+}
+
+/// Hashes file contents with 64-bit FNV-1a
+///
+/// The hash is written to disk, so it has to be stable between runs and builds, which the standard
+/// library's hashers don't promise.
+fn content_hash(data: &[u8]) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for byte in data {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+fn read_cache_string<T: Read + Seek>(mut f: T) -> Result<String, TesError> {
+    let len: u32 = f.read_le()?;
+    let mut buf = vec![0u8; len as usize];
+    f.read_exact(&mut buf)?;
+    String::from_utf8(buf).map_err(|_| decode_failed("Invalid string in index cache"))
+}
+
+fn write_cache_string<T: Write + Seek>(mut f: T, value: &str) -> Result<(), TesError> {
+    f.write_le(&checked_len::<u32>(value.len(), "Index cache string")?)?;
+    f.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+/// An on-disk cache of plugin [`RecordIndex`]es, so unchanged plugins load faster on later runs
+///
+/// Each index is stored along with the size, modification time, and content hash of the plugin
+/// file it was built from. If the file's size and modification time still match, the index is used
+/// without hashing the file again; if they don't, the file is hashed, and the index is still used
+/// if the contents turn out to be the same. Otherwise, the plugin is read in full and its index is
+/// replaced. The cache can be shared between threads loading
+/// different plugins; changes are only written to disk by [`save`].
+///
+/// The cache is disposable: if the cache file is missing or can't be decoded, the cache starts out
+/// empty instead of failing.
+///
+/// [`RecordIndex`]: struct.RecordIndex.html
+/// [`save`]: #method.save
+#[derive(Debug, Default)]
+pub struct IndexCache {
+    path: Option<PathBuf>,
+    entries: Mutex<HashMap<PathBuf, (FileStamp, Arc<RecordIndex>)>>,
+    changed: AtomicBool,
+}
+
+impl IndexCache {
+    /// Creates an empty cache that's kept only in memory
+    pub fn new() -> IndexCache {
+        IndexCache::default()
+    }
+
+    /// Opens the cache stored in a file
+    ///
+    /// The file doesn't have to exist yet; it will be created when the cache is saved.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error other than the file not existing occurs while reading the cache.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<IndexCache, TesError> {
+        let path = path.as_ref();
+        let mut cache = IndexCache {
+            path: Some(path.to_path_buf()),
+            ..IndexCache::default()
+        };
+
+        let f = match File::open(path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(cache),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut data = vec![];
+        BufReader::new(f).read_to_end(&mut data)?;
+        // a cache we can't decode is treated the same as no cache at all
+        if let Ok(entries) = IndexCache::read_entries(Cursor::new(&data)) {
+            cache.entries = Mutex::new(entries);
+        }
+
+        Ok(cache)
+    }
+
+    fn read_entries<T: Read + Seek>(
+        mut f: T,
+    ) -> Result<HashMap<PathBuf, (FileStamp, Arc<RecordIndex>)>, TesError> {
+        let mut magic = [0u8; 4];
+        f.read_exact(&mut magic)?;
+        let version: u32 = f.read_le()?;
+        if magic != *CACHE_MAGIC || version != CACHE_VERSION {
+            return Err(decode_failed("Not a supported index cache"));
+        }
+
+        let num_entries: u32 = f.read_le()?;
+        let mut entries = HashMap::new();
+        for _ in 0..num_entries {
+            let path = PathBuf::from(read_cache_string(&mut f)?);
+            let stamp = FileStamp {
+                size: f.read_le()?,
+                modified: (f.read_le()?, f.read_le()?),
+                hash: f.read_le()?,
+            };
+
+            let num_records: u32 = f.read_le()?;
+            let mut index = RecordIndex::new();
+            for _ in 0..num_records {
+                let offset = f.read_le()?;
+                let mut record_type = [0u8; 4];
+                f.read_exact(&mut record_type)?;
+                index.insert(offset, record_type, read_cache_string(&mut f)?);
+            }
+
+            entries.insert(path, (stamp, Arc::new(index)));
+        }
+
+        Ok(entries)
+    }
+
+    /// Gets the number of plugins in the cache
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Returns whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.lock().unwrap().is_empty()
+    }
+
+    /// Loads a plugin from a file, using and updating the cached index for it
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs or if the plugin file is invalid.
+    pub fn load_plugin<T: Plugin, P: AsRef<Path>>(&self, path: P) -> Result<T, TesError> {
+        let path = path.as_ref();
+        let key = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let meta = fs::metadata(path)?;
+        let size = meta.len();
+        let modified = file_time(meta.modified().ok());
+        let data = fs::read(path)?;
+
+        // clone the entry out so other threads aren't blocked while we read the plugin
+        let entry = self
+            .entries
+            .lock()
+            .unwrap()
+            .get(&key)
+            .map(|(stamp, index)| (*stamp, Arc::clone(index)));
+
+        // hashing a large master takes about as long as indexing it, so only hash files that
+        // don't look the same as when they were cached
+        let (stamp, cached) = match entry {
+            Some((old_stamp, index)) if old_stamp.has_same_metadata(size, modified) => {
+                (old_stamp, Some(index))
+            }
+            entry => {
+                let stamp = FileStamp {
+                    size,
+                    modified,
+                    hash: content_hash(&data),
+                };
+                // the file may only have been touched, in which case the index is still good
+                let cached = entry
+                    .filter(|(old_stamp, _)| old_stamp.hash == stamp.hash && old_stamp.size == size)
+                    .map(|(_, index)| index);
+                (stamp, cached)
+            }
+        };
+
+        let (plugin, index) = T::read_indexed(Cursor::new(&data), cached.as_deref())?;
+        let mut entries = self.entries.lock().unwrap();
+        let is_current = matches!(entries.get(&key), Some((old_stamp, _)) if *old_stamp == stamp);
+        if !is_current {
+            // an index that's still good only needs its stamp updated
+            let index = cached.unwrap_or_else(|| Arc::new(index));
+            entries.insert(key, (stamp, index));
+            self.changed.store(true, Ordering::Relaxed);
+        }
+
+        Ok(plugin)
+    }
+
+    /// Removes all entries from the cache
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+        self.changed.store(true, Ordering::Relaxed);
+    }
+
+    /// Writes the cache back to the file it was opened from
+    ///
+    /// Does nothing if the cache is only kept in memory or hasn't changed since it was opened.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs.
+    pub fn save(&self) -> Result<(), TesError> {
+        let path = match self.path {
+            Some(ref path) if self.changed.load(Ordering::Relaxed) => path,
+            _ => return Ok(()),
+        };
+
+        let mut buf = vec![];
+        self.write(Cursor::new(&mut buf))?;
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(&buf)?;
+        writer.flush()?;
+
+        self.changed.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn write<T: Write + Seek>(&self, mut f: T) -> Result<(), TesError> {
+        let entries = self.entries.lock().unwrap();
+        f.write_all(CACHE_MAGIC)?;
+        f.write_le(&CACHE_VERSION)?;
+        f.write_le(&checked_len::<u32>(entries.len(), "Index cache entries")?)?;
+        for (path, (stamp, index)) in entries.iter() {
+            write_cache_string(&mut f, &path.to_string_lossy())?;
+            f.write_le(&stamp.size)?;
+            f.write_le(&stamp.modified.0)?;
+            f.write_le(&stamp.modified.1)?;
+            f.write_le(&stamp.hash)?;
+
+            f.write_le(&checked_len::<u32>(index.len(), "Record index")?)?;
+            for (offset, record_type, id) in index.iter() {
+                f.write_le(&offset)?;
+                f.write_all(record_type)?;
+                write_cache_string(&mut f, id)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tes3::Tes3Plugin;
+
+    static TEST_PLUGIN: &[u8] = include_bytes!("tes3/plugin/test/Data Files/test1.esp");
+
+    #[test]
+    fn write_and_read_entries() {
+        let mut index = RecordIndex::new();
+        index.insert(0x1f4, *b"NPC_", String::from("fargoth"));
+        index.insert(0x38a, *b"GLOB", String::from("PCRace"));
+        let stamp = FileStamp::new(1024, Some(UNIX_EPOCH), b"plugin data");
+
+        let cache = IndexCache::new();
+        cache.entries.lock().unwrap().insert(
+            PathBuf::from("Data Files/Test.esp"),
+            (stamp, Arc::new(index)),
+        );
+
+        let mut buf = vec![];
+        cache.write(Cursor::new(&mut buf)).unwrap();
+        let entries = IndexCache::read_entries(Cursor::new(&buf)).unwrap();
+        let (read_stamp, read_index) = &entries[&PathBuf::from("Data Files/Test.esp")];
+        assert_eq!(*read_stamp, stamp);
+        assert_eq!(read_index.get(0x1f4, b"NPC_"), Some("fargoth"));
+        assert_eq!(read_index.get(0x38a, b"NPC_"), None);
+        assert_eq!(read_index.len(), 2);
+
+        assert!(IndexCache::read_entries(Cursor::new(b"garbage")).is_err());
+        assert_ne!(
+            FileStamp::new(1024, Some(UNIX_EPOCH), b"plugin dat4"),
+            stamp
+        );
+    }
+
+    #[test]
+    fn load_plugin_hashes_only_changed_files() {
+        let dir = std::env::temp_dir().join(format!("tesutil_index_cache_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test1.esp");
+        fs::write(&path, TEST_PLUGIN).unwrap();
+        let key = fs::canonicalize(&path).unwrap();
+
+        let cache = IndexCache::new();
+        let entry = |cache: &IndexCache| {
+            let entries = cache.entries.lock().unwrap();
+            let (stamp, index) = &entries[&key];
+            (*stamp, Arc::clone(index))
+        };
+        let set_stamp = |cache: &IndexCache, stamp: FileStamp| {
+            cache.entries.lock().unwrap().get_mut(&key).unwrap().0 = stamp;
+            cache.changed.store(false, Ordering::Relaxed);
+        };
+
+        cache.load_plugin::<Tes3Plugin, _>(&path).unwrap();
+        let (stamp, index) = entry(&cache);
+        assert_eq!(stamp.hash, content_hash(TEST_PLUGIN));
+
+        // the size and modification time match, so the stored hash is trusted
+        set_stamp(&cache, FileStamp { hash: 0, ..stamp });
+        cache.load_plugin::<Tes3Plugin, _>(&path).unwrap();
+        assert_eq!(entry(&cache).0.hash, 0);
+        assert!(!cache.changed.load(Ordering::Relaxed));
+
+        // the file was touched, so it's hashed again, but the contents are the same, so the index
+        // is kept
+        set_stamp(
+            &cache,
+            FileStamp {
+                modified: (0, 0),
+                ..stamp
+            },
+        );
+        cache.load_plugin::<Tes3Plugin, _>(&path).unwrap();
+        let (new_stamp, new_index) = entry(&cache);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(new_stamp, stamp);
+        assert!(Arc::ptr_eq(&new_index, &index));
+        assert!(cache.changed.load(Ordering::Relaxed));
+    }
+}
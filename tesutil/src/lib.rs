@@ -1,25 +1,78 @@
 //! Utilities for working with the files of The Elder Scrolls III and IV
 //!
 //! This crate contains utilities for reading and writing file formats associated with The Elder
-//! Scrolls III: Morrowind and The Elder Scrolls IV: Oblivion. Currently, only plugin files (.esm,
-//! .esp, .ess) are implemented, but support for archives (.bsa) will be added in the future, and
-//! potentially other formats as well.
+//! Scrolls III: Morrowind and The Elder Scrolls IV: Oblivion. Currently, plugin files (.esm, .esp,
+//! .ess) can be read and written and archives (.bsa) can be read for both games. Support for other
+//! formats may be added in the future.
+//!
+//! Each game's support is behind a feature, `tes3` or `tes4`, so a consumer that only needs to
+//! parse one game's files doesn't pull in the other's code and dependencies. The `cosave` feature
+//! adds OBSE co-saves, and the `world` feature adds load orders and looking up forms across them.
+//! All features are enabled by default. The optional `serde` feature makes [`ErrorReport`]
+//! serializable.
+//!
+//! The [`prelude`] module gathers the common traits and types, so `use tesutil::prelude::*;` is
+//! usually all the importing a consumer needs.
+//!
+//! [`prelude`]: prelude/index.html
+//! [`ErrorReport`]: struct.ErrorReport.html
 
+#[cfg(feature = "tes3")]
 pub mod tes3;
+#[cfg(feature = "tes4")]
 pub mod tes4;
 
+pub mod prelude;
+
 mod plugin;
 pub use plugin::*;
 
+#[cfg(any(feature = "tes3", feature = "tes4"))]
+mod archive_entry;
+#[cfg(any(feature = "tes3", feature = "tes4"))]
+pub use archive_entry::*;
+
+#[cfg(feature = "world")]
 mod world;
+#[cfg(feature = "world")]
 pub use world::*;
 
-use std::convert::TryFrom;
+#[cfg(feature = "world")]
+mod game_paths;
+#[cfg(feature = "world")]
+pub use game_paths::*;
+
+#[cfg(feature = "world")]
+mod vfs;
+#[cfg(feature = "world")]
+pub use vfs::*;
+
+#[cfg(feature = "world")]
+mod form_cache;
+#[cfg(feature = "world")]
+pub use form_cache::*;
+
+#[cfg(feature = "world")]
+mod index_cache;
+#[cfg(feature = "world")]
+pub use index_cache::*;
+
+mod error_report;
+pub use error_report::*;
+
+mod save_metadata;
+pub use save_metadata::*;
+
+mod similarity;
+pub use similarity::*;
+
+use std::borrow::Cow;
 use std::error;
 use std::ffi::CStr;
 use std::io;
 use std::io::{Error, ErrorKind, Read, SeekFrom, Write};
 use std::iter;
+use std::marker::PhantomData;
 use std::ops::Deref;
 use std::str;
 
@@ -63,6 +116,7 @@ pub enum Attribute {
     Luck,
 }
 
+#[cfg(feature = "tes4")]
 impl TryFrom<tes4::ActorValue> for Attribute {
     type Error = TesError;
 
@@ -98,6 +152,53 @@ pub enum EffectRange {
     Target,
 }
 
+impl EffectRange {
+    /// Reads a range as stored in Morrowind ENAM fields
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value isn't a valid range.
+    pub fn from_tes3(value: u32) -> Result<EffectRange, TesError> {
+        EffectRange::try_from(value)
+    }
+
+    /// Gets the value stored in Morrowind ENAM fields for this range
+    pub fn to_tes3(self) -> u32 {
+        self.into()
+    }
+
+    /// Reads a range as stored in Oblivion EFIT fields
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value isn't a valid range.
+    pub fn from_tes4(value: u32) -> Result<EffectRange, TesError> {
+        EffectRange::try_from(value)
+    }
+
+    /// Gets the value stored in Oblivion EFIT fields for this range
+    pub fn to_tes4(self) -> u32 {
+        self.into()
+    }
+}
+
+impl TryFrom<u32> for EffectRange {
+    type Error = TesError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        u8::try_from(value)
+            .ok()
+            .and_then(|v| EffectRange::try_from(v).ok())
+            .ok_or_else(|| decode_failed(format!("Invalid effect range {}", value)))
+    }
+}
+
+impl From<EffectRange> for u32 {
+    fn from(range: EffectRange) -> Self {
+        u8::from(range) as u32
+    }
+}
+
 /// A school of magic
 #[derive(Debug, Copy, Clone, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
 #[repr(u8)]
@@ -110,6 +211,54 @@ pub enum MagicSchool {
     Restoration,
 }
 
+impl MagicSchool {
+    /// Reads a school as stored in the MEDT field of a Morrowind MGEF record
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value isn't a valid school.
+    pub fn from_tes3(value: u32) -> Result<MagicSchool, TesError> {
+        MagicSchool::try_from(value)
+    }
+
+    /// Gets the value stored in the MEDT field of a Morrowind MGEF record for this school
+    pub fn to_tes3(self) -> u32 {
+        self.into()
+    }
+
+    /// Reads a school as stored in the DATA field of an Oblivion MGEF record or in an SCIT field
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value isn't a valid school.
+    pub fn from_tes4(value: u32) -> Result<MagicSchool, TesError> {
+        // Oblivion happens to number the schools the same way Morrowind does
+        MagicSchool::try_from(value)
+    }
+
+    /// Gets the value stored in Oblivion records for this school
+    pub fn to_tes4(self) -> u32 {
+        self.into()
+    }
+}
+
+impl TryFrom<u32> for MagicSchool {
+    type Error = TesError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        u8::try_from(value)
+            .ok()
+            .and_then(|v| MagicSchool::try_from(v).ok())
+            .ok_or_else(|| decode_failed(format!("Invalid magic school {}", value)))
+    }
+}
+
+impl From<MagicSchool> for u32 {
+    fn from(school: MagicSchool) -> Self {
+        u8::from(school) as u32
+    }
+}
+
 /// All possible specializations
 #[derive(Copy, Clone, Debug, Enum, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
@@ -122,6 +271,62 @@ pub enum Specialization {
 /// Specialization mapping
 pub type Specializations<T> = EnumMap<Specialization, T>;
 
+/// Size of a soul, or of the largest soul a soul gem can hold
+///
+/// Oblivion stores soul sizes directly. Morrowind instead gives each creature a soul value and
+/// each soul gem a capacity derived from its value, so converting a Morrowind soul means finding
+/// the smallest soul gem it fits in.
+#[binrw]
+#[derive(Copy, Clone, Debug, Default, Enum, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[repr(u8)]
+#[brw(repr = u8)]
+pub enum SoulSize {
+    #[default]
+    None,
+    Petty,
+    Lesser,
+    Common,
+    Greater,
+    Grand,
+}
+
+/// Soul size mapping
+pub type SoulSizes<T> = EnumMap<SoulSize, T>;
+
+impl SoulSize {
+    /// Gets the size of a Morrowind creature's soul
+    ///
+    /// `capacities` is the largest soul value each size of soul gem can hold. Souls too large for
+    /// every capacity are grand souls.
+    pub fn from_tes3(soul_value: u32, capacities: &SoulSizes<u32>) -> SoulSize {
+        capacities
+            .iter()
+            .find(|(_, capacity)| soul_value <= **capacity)
+            .map_or(SoulSize::Grand, |(size, _)| size)
+    }
+
+    /// Gets the largest Morrowind soul value of this size
+    ///
+    /// `capacities` is the largest soul value each size of soul gem can hold.
+    pub fn to_tes3(self, capacities: &SoulSizes<u32>) -> u32 {
+        capacities[self]
+    }
+
+    /// Reads a soul size as stored in Oblivion records and saves
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value isn't a valid soul size.
+    pub fn from_tes4(value: u8) -> Result<SoulSize, TesError> {
+        SoulSize::try_from(value).map_err(|_| decode_failed(format!("Invalid soul size {}", value)))
+    }
+
+    /// Gets the value stored in Oblivion records and saves for this soul size
+    pub fn to_tes4(self) -> u8 {
+        self.into()
+    }
+}
+
 /// Error type for utility errors
 ///
 /// A type for errors that may occur while manipulating game files.
@@ -130,6 +335,13 @@ pub enum TesError {
     /// Multiple records in the plugin have the same ID string
     #[error("ID {0} already in use")]
     DuplicateId(String),
+    /// An ID lookup matched records of more than one type
+    #[error("ID {id} is used by more than one record: {}", .record_types.join(", "))]
+    AmbiguousId {
+        id: String,
+        /// Types of all the records with the ID
+        record_types: Vec<String>,
+    },
     /// The same master file is referenced by the plugin multiple times
     #[error("Master {0} already present")]
     DuplicateMaster(String),
@@ -158,10 +370,12 @@ pub enum TesError {
     #[error("Invalid ID {0}")]
     InvalidId(String),
     /// A provided form ID is not valid
-    #[error("Invalid form ID {:08X}", .form_id.0)]
+    #[cfg(feature = "tes4")]
+    #[error("Invalid form ID {form_id}")]
     InvalidFormId { form_id: tes4::FormId },
     /// A plugin/form ID combination was not found when one was required
-    #[error("Invalid form ID {:06X} in plugin {}", .form_id.0, .plugin)]
+    #[cfg(feature = "tes4")]
+    #[error("Invalid form ID {:06X} in plugin {}", .form_id.local_id(), .plugin)]
     InvalidPluginForm {
         plugin: String,
         form_id: tes4::FormId,
@@ -177,11 +391,21 @@ pub enum TesError {
     #[error(transparent)]
     IoError(#[from] io::Error),
     /// Error parsing an INI file
+    #[cfg(feature = "world")]
     #[error(transparent)]
     IniError(#[from] ini::Error),
     /// Error during binary data I/O
     #[error(transparent)]
     BinaryDataError(#[from] binrw::Error),
+    /// An error occurred in a particular record
+    #[error("In record {record}: {source}")]
+    InRecord {
+        /// Type of the record, followed by its ID if it has one
+        record: String,
+        /// Offset of the record in its file, if known
+        offset: Option<u64>,
+        source: Box<TesError>,
+    },
 }
 
 /// A concrete game object, as opposed to a generic record
@@ -211,6 +435,47 @@ pub trait Form: Sized {
     fn write(&self, record: &mut Self::Record) -> Result<(), TesError>;
 }
 
+/// A form that preserves fields it doesn't recognize
+///
+/// Mods sometimes add fields to records that the games and this library don't know about. Rather
+/// than failing to read such records, forms implementing this trait collect any unrecognized
+/// fields and write them back out unchanged, after all the fields the form does understand.
+pub trait ExtraFields {
+    type Field: Field;
+
+    /// Gets the fields that weren't recognized when this form was read
+    fn extra_fields(&self) -> &[Self::Field];
+
+    /// Gets the unrecognized fields mutably
+    fn extra_fields_mut(&mut self) -> &mut Vec<Self::Field>;
+}
+
+/// Characters for bytes 0x80 to 0x9f in Windows-1252; the rest of the upper half matches Latin-1
+const CP1252_HIGH: [char; 32] = [
+    '€', '\u{fffd}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{fffd}', 'Ž',
+    '\u{fffd}', '\u{fffd}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{fffd}',
+    'ž', 'Ÿ',
+];
+
+/// Decodes text that may have been written in the Windows-1252 code page
+///
+/// The games store text in whatever code page the author's system used, which for most content is
+/// Windows-1252. Valid UTF-8 is returned as it is; anything else is decoded as Windows-1252, with
+/// the bytes that code page leaves undefined replaced by U+FFFD.
+pub(crate) fn decode_legacy(data: &[u8]) -> Cow<str> {
+    match str::from_utf8(data) {
+        Ok(s) => Cow::Borrowed(s),
+        Err(_) => Cow::Owned(
+            data.iter()
+                .map(|&b| match b {
+                    0x80..=0x9f => CP1252_HIGH[usize::from(b - 0x80)],
+                    _ => char::from(b),
+                })
+                .collect(),
+        ),
+    }
+}
+
 fn read_string_bytes<'a, T: Into<&'a [u8]>>(buf: T) -> io::Result<String> {
     let buf = buf.into();
     // ensure there is exactly one null byte at the end of the string
@@ -307,6 +572,13 @@ fn write_bzstring<T: Write>(mut f: T, data: &str) -> io::Result<()> {
     Ok(())
 }
 
+/// Normalizes a path for lookup in a BSA archive
+///
+/// Both games treat paths as case-insensitive and store them with backslashes.
+fn normalize_archive_path(path: &str) -> String {
+    path.replace('/', "\\").to_lowercase()
+}
+
 fn io_error<E>(e: E) -> Error
 where
     E: Into<Box<dyn error::Error + Send + Sync>>,
@@ -331,6 +603,86 @@ fn check_size<T: Len + ?Sized>(data: &T, max_size: usize, msg: &str) -> Result<(
     }
 }
 
+/// An integer type that the length of a string or collection is written as
+trait LengthPrefix: TryFrom<usize> {
+    const MAX: usize;
+
+    fn read_len<T: Read>(f: T) -> Result<usize, TesError>;
+
+    fn write_len<T: Write>(f: T, len: usize, msg: &str) -> Result<(), TesError>;
+}
+
+macro_rules! length_prefix {
+    ($($t:ty),+) => {
+        $(
+            impl LengthPrefix for $t {
+                const MAX: usize = <$t>::MAX as usize;
+
+                fn read_len<T: Read>(mut f: T) -> Result<usize, TesError> {
+                    let mut buf = [0u8; std::mem::size_of::<$t>()];
+                    f.read_exact(&mut buf)?;
+                    Ok(<$t>::from_le_bytes(buf) as usize)
+                }
+
+                fn write_len<T: Write>(mut f: T, len: usize, msg: &str) -> Result<(), TesError> {
+                    f.write_all(&checked_len::<$t>(len, msg)?.to_le_bytes())?;
+                    Ok(())
+                }
+            }
+        )+
+    };
+}
+
+length_prefix!(u8, u16, u32);
+
+/// Converts a length to the integer type it's written as, failing if it doesn't fit
+fn checked_len<T: LengthPrefix>(len: usize, msg: &str) -> Result<T, TesError> {
+    T::try_from(len).map_err(|_| TesError::LimitExceeded {
+        description: String::from(msg),
+        max_size: T::MAX,
+        actual_size: len,
+    })
+}
+
+/// A list of values preceded by a count of type `P`
+///
+/// Structures define an alias of this type for each of their lists so that the width of the count
+/// is only given once, and reading and writing can't disagree about it.
+struct PrefixedList<P>(PhantomData<P>);
+
+impl<P: LengthPrefix> PrefixedList<P> {
+    /// Reads the count followed by that many values read with `read_item`
+    fn read<R, T, F>(mut f: R, mut read_item: F) -> Result<Vec<T>, TesError>
+    where
+        R: Read,
+        F: FnMut(&mut R) -> Result<T, TesError>,
+    {
+        let len = P::read_len(&mut f)?;
+        let mut items = Vec::with_capacity(len);
+        for _ in 0..len {
+            items.push(read_item(&mut f)?);
+        }
+
+        Ok(items)
+    }
+
+    /// Writes the count of `items` followed by each item written with `write_item`
+    ///
+    /// Fails with [`TesError::LimitExceeded`] if the count doesn't fit in `P`.
+    fn write<W, T, F>(mut f: W, items: &[T], msg: &str, mut write_item: F) -> Result<(), TesError>
+    where
+        W: Write,
+        F: FnMut(&mut W, &T) -> Result<(), TesError>,
+    {
+        P::write_len(&mut f, items.len(), msg)?;
+        for item in items {
+            write_item(&mut f, item)?;
+        }
+
+        Ok(())
+    }
+}
+
 fn check_range<T: Into<f64> + PartialOrd>(
     value: T,
     min: T,
@@ -367,6 +719,14 @@ fn decode_failed<T: Into<String>>(msg: T) -> TesError {
     }
 }
 
+fn in_record<T: Into<String>>(record: T, offset: Option<u64>, e: TesError) -> TesError {
+    TesError::InRecord {
+        record: record.into(),
+        offset,
+        source: Box::new(e),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -378,6 +738,86 @@ mod tests {
         assert_eq!(s, "abcd");
     }
 
+    #[test]
+    fn magic_school_values() {
+        assert_eq!(MagicSchool::from_tes3(2).unwrap(), MagicSchool::Destruction);
+        assert_eq!(MagicSchool::from_tes4(5).unwrap(), MagicSchool::Restoration);
+        assert_eq!(MagicSchool::Mysticism.to_tes3(), 4);
+        assert_eq!(MagicSchool::Conjuration.to_tes4(), 1);
+        assert!(MagicSchool::from_tes3(6).is_err());
+        // the value used to be truncated to a byte, which let this through as Alteration
+        assert!(MagicSchool::from_tes4(0x100).is_err());
+    }
+
+    #[test]
+    fn effect_range_values() {
+        assert_eq!(EffectRange::from_tes3(1).unwrap(), EffectRange::Touch);
+        assert_eq!(EffectRange::from_tes4(2).unwrap(), EffectRange::Target);
+        assert_eq!(EffectRange::Self_.to_tes3(), 0);
+        assert_eq!(EffectRange::Target.to_tes4(), 2);
+        assert!(EffectRange::from_tes3(3).is_err());
+        assert!(EffectRange::from_tes4(0x101).is_err());
+    }
+
+    #[test]
+    fn test_checked_len() {
+        assert_eq!(checked_len::<u8>(255, "test").unwrap(), 255u8);
+        assert_eq!(checked_len::<u16>(0x1234, "test").unwrap(), 0x1234u16);
+        assert!(matches!(
+            checked_len::<u8>(256, "test"),
+            Err(TesError::LimitExceeded {
+                max_size: 255,
+                actual_size: 256,
+                ..
+            })
+        ));
+        assert!(checked_len::<u16>(0x10000, "test").is_err());
+    }
+
+    #[test]
+    fn prefixed_list_round_trip() {
+        let mut buf = vec![];
+        PrefixedList::<u16>::write(&mut buf, &[1u32, 2, 3], "test", |f, v| {
+            f.write_all(&v.to_le_bytes())?;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(buf.len(), 14);
+        assert_eq!(&buf[..2], b"\x03\x00");
+
+        let items = PrefixedList::<u16>::read(buf.as_slice(), |f| {
+            let mut value = [0u8; 4];
+            f.read_exact(&mut value)?;
+            Ok(u32::from_le_bytes(value))
+        })
+        .unwrap();
+        assert_eq!(items, vec![1, 2, 3]);
+
+        let mut buf = vec![];
+        assert!(PrefixedList::<u8>::write(&mut buf, &[0u8; 256], "test", |_, _| Ok(())).is_err());
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn soul_size_values() {
+        let capacities = enum_map::enum_map! {
+            SoulSize::None => 0,
+            SoulSize::Petty => 30,
+            SoulSize::Lesser => 60,
+            SoulSize::Common => 120,
+            SoulSize::Greater => 180,
+            SoulSize::Grand => 600,
+        };
+        assert_eq!(SoulSize::from_tes3(0, &capacities), SoulSize::None);
+        assert_eq!(SoulSize::from_tes3(30, &capacities), SoulSize::Petty);
+        assert_eq!(SoulSize::from_tes3(31, &capacities), SoulSize::Lesser);
+        assert_eq!(SoulSize::from_tes3(5000, &capacities), SoulSize::Grand);
+        assert_eq!(SoulSize::Common.to_tes3(&capacities), 120);
+        assert_eq!(SoulSize::from_tes4(4).unwrap(), SoulSize::Greater);
+        assert_eq!(SoulSize::Grand.to_tes4(), 5);
+        assert!(SoulSize::from_tes4(6).is_err());
+    }
+
     #[test]
     fn test_serialize_str() {
         let mut buf = [0u8; 10];
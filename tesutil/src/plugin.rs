@@ -15,13 +15,66 @@ use crate::TesError;
 mod field;
 pub use field::*;
 
+mod field_order;
+pub use field_order::*;
+
+mod index;
+pub use index::*;
+
+mod journal;
+pub use journal::*;
+
 mod record;
 pub use record::*;
 
+mod registry;
+pub use registry::*;
+
+mod schema;
+pub use schema::*;
+
+mod stats;
+pub use stats::*;
+
+/// Coordinates of an exterior cell in the cell grid
+///
+/// Cell (0, 0) has its south-west corner at the world origin; x increases to the east and y to the
+/// north.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct GridCoords {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl GridCoords {
+    pub fn new(x: i32, y: i32) -> GridCoords {
+        GridCoords { x, y }
+    }
+}
+
 /// Common functionality between different games' plugin implementations
 pub trait Plugin: Sized + Send + Sync {
     fn read<T: Read + Seek>(f: T) -> Result<Self, TesError>;
 
+    /// Reads a plugin using an index of its record IDs, and builds a new index
+    ///
+    /// Records found in the index don't have to be decoded until they're used. The index must have
+    /// been built from exactly the same data; entries that don't match a record are ignored, but a
+    /// stale entry that happens to match will give the record the wrong ID. Plugins that don't
+    /// support indexing read normally and return an empty index.
+    ///
+    /// # Errors
+    ///
+    /// Fails for the same reasons as [`read`].
+    ///
+    /// [`read`]: #tymethod.read
+    fn read_indexed<T: Read + Seek>(
+        f: T,
+        _index: Option<&RecordIndex>,
+    ) -> Result<(Self, RecordIndex), TesError> {
+        Ok((Self::read(f)?, RecordIndex::new()))
+    }
+
     /// Loads a plugin from a file
     ///
     /// # Errors
@@ -0,0 +1,118 @@
+use super::{Field, Record};
+
+/// The order in which a record's fields appeared when it was read
+///
+/// Forms write their fields in a fixed, canonical order, but records in the wild don't always
+/// follow it, and some game code and tools are sensitive to field order. A form that was read from
+/// a record keeps the record's field order and uses it to put written fields back where they were.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct FieldOrder(Vec<[u8; 4]>);
+
+impl FieldOrder {
+    /// Creates an empty field order, which leaves fields in the order they were written
+    pub fn new() -> FieldOrder {
+        FieldOrder(vec![])
+    }
+
+    /// Captures the field order of a record
+    pub fn from_record<F: Field, R: Record<F>>(record: &R) -> FieldOrder {
+        FieldOrder::from_fields(record.iter())
+    }
+
+    /// Captures the order of a sequence of fields
+    ///
+    /// This is useful for groups of fields within a record that are read and written as a unit.
+    pub fn from_fields<'a, F: Field + 'a, I: IntoIterator<Item = &'a F>>(fields: I) -> FieldOrder {
+        FieldOrder(
+            fields
+                .into_iter()
+                .map(|f| {
+                    let mut name = [0u8; 4];
+                    name.copy_from_slice(f.name());
+                    name
+                })
+                .collect(),
+        )
+    }
+
+    /// Is this order empty?
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Rearranges fields to match this order
+    ///
+    /// The nth field with a given name is moved to the position of the nth field with that name in
+    /// the original record. Fields that weren't in the original record stay after the field they
+    /// were written after, and fields before any matched field stay at the front.
+    pub fn apply<F: Field>(&self, fields: &mut Vec<F>) {
+        if self.0.is_empty() {
+            return;
+        }
+
+        let mut used = vec![false; self.0.len()];
+        // sort key: (position in the original record of the field this one is anchored to, offset
+        // from that anchor)
+        let mut anchor = None;
+        let mut offset = 0usize;
+        let mut keys = Vec::with_capacity(fields.len());
+        for field in fields.iter() {
+            let position = self
+                .0
+                .iter()
+                .enumerate()
+                .position(|(i, name)| !used[i] && &name[..] == field.name());
+            match position {
+                Some(i) => {
+                    used[i] = true;
+                    anchor = Some(i);
+                    offset = 0;
+                }
+                None => offset += 1,
+            }
+            keys.push((anchor, offset));
+        }
+
+        let mut keyed: Vec<_> = keys.into_iter().zip(fields.drain(..)).collect();
+        keyed.sort_unstable_by_key(|(k, _)| *k);
+        fields.extend(keyed.into_iter().map(|(_, f)| f));
+    }
+}
+
+#[cfg(all(test, feature = "tes4"))]
+mod tests {
+    use super::*;
+    use crate::tes4::{Tes4Field, Tes4Record};
+
+    fn names(fields: &[Tes4Field]) -> Vec<&str> {
+        fields.iter().map(|f| f.name_as_str()).collect()
+    }
+
+    #[test]
+    fn restore_field_order() {
+        let mut record = Tes4Record::new(b"SPEL");
+        for name in [
+            b"EDID", b"SPIT", b"FULL", b"EFID", b"EFIT", b"EFID", b"EFIT",
+        ] {
+            record.add_field(Tes4Field::new(name, vec![]).unwrap());
+        }
+        let order = FieldOrder::from_record(&record);
+
+        let mut fields: Vec<Tes4Field> = [
+            b"EDID", b"FULL", b"SPIT", b"EFID", b"EFIT", b"SCIT", b"EFID", b"EFIT",
+        ]
+        .into_iter()
+        .map(|n| Tes4Field::new(n, vec![]).unwrap())
+        .collect();
+        order.apply(&mut fields);
+
+        assert_eq!(
+            names(&fields),
+            ["EDID", "SPIT", "FULL", "EFID", "EFIT", "SCIT", "EFID", "EFIT"]
+        );
+
+        let mut fields = vec![Tes4Field::new(b"DATA", vec![]).unwrap()];
+        FieldOrder::new().apply(&mut fields);
+        assert_eq!(names(&fields), ["DATA"]);
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+/// The IDs of a plugin's records, by where each record starts in the plugin file
+///
+/// Finding a record's ID means decoding its fields, which is most of the work of loading a large
+/// plugin. An index built the first time a plugin is read lets later reads of the same file take
+/// IDs from the index instead and only decode records when they're used. Indexes are only valid
+/// for the exact file they were built from; see [`IndexCache`] for keeping them between runs.
+///
+/// [`IndexCache`]: struct.IndexCache.html
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct RecordIndex {
+    ids: HashMap<u64, ([u8; 4], String)>,
+}
+
+impl RecordIndex {
+    /// Creates an empty index
+    pub fn new() -> RecordIndex {
+        RecordIndex::default()
+    }
+
+    /// Adds the ID of the record of the given type starting at an offset
+    pub fn insert(&mut self, offset: u64, record_type: [u8; 4], id: String) {
+        self.ids.insert(offset, (record_type, id));
+    }
+
+    /// Gets the ID of the record starting at an offset
+    ///
+    /// Returns `None` if the index doesn't have an ID for that offset, or if the record there was
+    /// of a different type when the index was built.
+    pub fn get(&self, offset: u64, record_type: &[u8; 4]) -> Option<&str> {
+        match self.ids.get(&offset) {
+            Some((t, id)) if t == record_type => Some(id.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Iterates through the offset, type, and ID of each record in the index, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &[u8; 4], &str)> + '_ {
+        self.ids
+            .iter()
+            .map(|(offset, (record_type, id))| (*offset, record_type, id.as_str()))
+    }
+
+    /// Gets the number of records in the index
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns whether the index is empty
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
@@ -0,0 +1,231 @@
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A record whose changes can be recorded in a [`Journal`]
+///
+/// [`Journal`]: struct.Journal.html
+pub trait JournalRecord: Sized {
+    /// Copies the state of the record that reverting a modification should restore
+    fn snapshot(&self) -> Self;
+
+    /// Restores the state of the record from a [`snapshot`]
+    ///
+    /// [`snapshot`]: #tymethod.snapshot
+    fn restore(&mut self, snapshot: Self);
+}
+
+/// How a record was changed
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MutationKind {
+    /// The record was added to the plugin
+    Added,
+    /// The record was borrowed mutably, and may have been changed
+    Modified,
+    /// The record was removed from the plugin
+    Removed,
+}
+
+/// A change to one of a plugin's records, as recorded in a [`Journal`]
+///
+/// [`Journal`]: struct.Journal.html
+#[derive(Debug)]
+pub struct Mutation<R> {
+    pub(crate) kind: MutationKind,
+    pub(crate) id: Option<String>,
+    pub(crate) record: Arc<RwLock<R>>,
+    /// The record as it was before it was modified, once it's been accessed mutably
+    pub(crate) previous: Arc<Mutex<Option<R>>>,
+    /// Records with the same ID that an added record replaced in lookups
+    pub(crate) replaced: Vec<Arc<RwLock<R>>>,
+    /// Where a removed record was in the plugin
+    pub(crate) position: usize,
+}
+
+impl<R> Mutation<R> {
+    pub(crate) fn new(
+        kind: MutationKind,
+        id: Option<String>,
+        record: &Arc<RwLock<R>>,
+    ) -> Mutation<R> {
+        Mutation {
+            kind,
+            id,
+            record: Arc::clone(record),
+            previous: Arc::new(Mutex::new(None)),
+            replaced: vec![],
+            position: 0,
+        }
+    }
+
+    /// Gets how the record was changed
+    pub fn kind(&self) -> MutationKind {
+        self.kind
+    }
+
+    /// Gets the ID of the changed record, if it has one
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Locks the changed record for reading
+    ///
+    /// This is the record as it is now, so it reflects any later changes.
+    pub fn record(&self) -> RwLockReadGuard<'_, R> {
+        self.record.read().unwrap()
+    }
+
+    /// Returns whether the record was actually accessed mutably
+    ///
+    /// This is always `true` for records that were added or removed. A record that was borrowed
+    /// mutably but only read has nothing to revert.
+    pub fn is_changed(&self) -> bool {
+        self.kind != MutationKind::Modified || self.previous.lock().unwrap().is_some()
+    }
+
+    /// Takes the record's state from before it was modified, if it was
+    pub(crate) fn take_previous(&self) -> Option<R> {
+        self.previous.lock().unwrap().take()
+    }
+}
+
+/// A record locked for writing
+///
+/// If the record's plugin is keeping a [`Journal`], the record's state is copied for the journal
+/// the first time the record is accessed mutably through this guard, so a record that is only read
+/// is never copied.
+///
+/// [`Journal`]: struct.Journal.html
+pub struct RecordMut<'a, R: JournalRecord> {
+    guard: RwLockWriteGuard<'a, R>,
+    previous: Option<Arc<Mutex<Option<R>>>>,
+}
+
+impl<'a, R: JournalRecord> RecordMut<'a, R> {
+    /// Wraps a locked record, saving its state for a modification in a journal if there is one
+    pub(crate) fn new(guard: RwLockWriteGuard<'a, R>, mutation: Option<&Mutation<R>>) -> Self {
+        RecordMut {
+            guard,
+            previous: mutation.map(|m| Arc::clone(&m.previous)),
+        }
+    }
+}
+
+impl<R: JournalRecord> Deref for RecordMut<'_, R> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        &self.guard
+    }
+}
+
+impl<R: JournalRecord> DerefMut for RecordMut<'_, R> {
+    fn deref_mut(&mut self) -> &mut R {
+        if let Some(previous) = self.previous.take() {
+            *previous.lock().unwrap() = Some(self.guard.snapshot());
+        }
+        &mut self.guard
+    }
+}
+
+impl<R: JournalRecord + fmt::Debug> fmt::Debug for RecordMut<'_, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.guard, f)
+    }
+}
+
+/// The changes made to a plugin's records while it's being edited, in the order they were made
+///
+/// A plugin only keeps a journal once it's been asked to, and each change in the journal can be
+/// reverted individually through the plugin until it's written. Every mutable borrow of a record
+/// counts as a modification, but the record is only copied if it's actually accessed mutably; see
+/// [`Mutation::is_changed`].
+///
+/// [`Mutation::is_changed`]: struct.Mutation.html#method.is_changed
+#[derive(Debug)]
+pub struct Journal<R> {
+    mutations: Vec<Mutation<R>>,
+}
+
+impl<R> Journal<R> {
+    /// Creates an empty journal
+    pub fn new() -> Journal<R> {
+        Journal { mutations: vec![] }
+    }
+
+    /// Adds a change to the journal, returning it so the caller can fill in the details
+    pub(crate) fn push(&mut self, mutation: Mutation<R>) -> &Mutation<R> {
+        self.mutations.push(mutation);
+        self.mutations.last().unwrap()
+    }
+
+    /// Removes the change at an index, if there is one
+    pub(crate) fn take(&mut self, index: usize) -> Option<Mutation<R>> {
+        if index < self.mutations.len() {
+            Some(self.mutations.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Iterates through the changes in the order they were made
+    pub fn iter(&self) -> impl Iterator<Item = &Mutation<R>> + '_ {
+        self.mutations.iter()
+    }
+
+    /// Gets the number of changes in the journal
+    pub fn len(&self) -> usize {
+        self.mutations.len()
+    }
+
+    /// Returns whether the journal is empty
+    pub fn is_empty(&self) -> bool {
+        self.mutations.is_empty()
+    }
+}
+
+impl<R> Default for Journal<R> {
+    fn default() -> Journal<R> {
+        Journal::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Counter(u32);
+
+    impl JournalRecord for Counter {
+        fn snapshot(&self) -> Counter {
+            self.clone()
+        }
+
+        fn restore(&mut self, snapshot: Counter) {
+            *self = snapshot;
+        }
+    }
+
+    #[test]
+    fn snapshot_lazily() {
+        let record = Arc::new(RwLock::new(Counter(1)));
+        let mut journal = Journal::new();
+
+        let mutation = journal.push(Mutation::new(MutationKind::Modified, None, &record));
+        let guard = RecordMut::new(record.write().unwrap(), Some(mutation));
+        assert_eq!(guard.0, 1);
+        drop(guard);
+        assert!(!journal.iter().next().unwrap().is_changed());
+
+        let mutation = journal.push(Mutation::new(MutationKind::Modified, None, &record));
+        let mut guard = RecordMut::new(record.write().unwrap(), Some(mutation));
+        guard.0 = 2;
+        guard.0 = 3;
+        drop(guard);
+        let mutation = journal.take(1).unwrap();
+        assert!(mutation.is_changed());
+        assert_eq!(mutation.take_previous(), Some(Counter(1)));
+        assert_eq!(*mutation.record(), Counter(3));
+    }
+}
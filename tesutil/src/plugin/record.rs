@@ -1,8 +1,9 @@
+use std::borrow::Cow;
 use std::io::{Read, Seek, Write};
 use std::str;
 
-use super::Field;
-use crate::TesError;
+use super::{Field, FieldKind};
+use crate::{decode_failed, decode_legacy, TesError};
 
 /// Initialization status of a lazy-loaded record
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
@@ -13,6 +14,79 @@ pub enum RecordStatus {
     Failed,
 }
 
+/// Where a record was found in the file it was read from
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct RecordSpan {
+    /// Offset of the start of the record's header from the start of the file
+    pub offset: u64,
+    /// Length of the record in bytes, including its header
+    pub length: u64,
+}
+
+impl RecordSpan {
+    /// Gets the offset of the first byte after the record
+    pub fn end(&self) -> u64 {
+        self.offset + self.length
+    }
+}
+
+/// A field's value, decoded on a best-effort basis
+///
+/// See [`Record::iter_typed`].
+///
+/// [`Record::iter_typed`]: trait.Record.html#method.iter_typed
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue<'a> {
+    /// A string, without any terminating or padding nulls
+    ///
+    /// Text that isn't valid UTF-8 is decoded as Windows-1252.
+    String(Cow<'a, str>),
+    /// A signed integer of 1, 2, or 4 bytes
+    Int(i32),
+    Float(f32),
+    /// A raw form ID, which is not adjusted for the record's plugin
+    FormId(u32),
+    /// A list of raw form IDs
+    FormIds(Vec<u32>),
+    Bytes(&'a [u8]),
+}
+
+impl<'a> FieldValue<'a> {
+    /// Decodes field data as the given kind of value
+    ///
+    /// If the data can't be decoded as that kind, e.g. because it's the wrong size, the raw bytes
+    /// are returned instead.
+    pub fn decode(data: &'a [u8], kind: FieldKind) -> FieldValue<'a> {
+        match (kind, data.len()) {
+            (FieldKind::String, _) => {
+                let end = data.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+                if data[..end].contains(&0) {
+                    FieldValue::Bytes(data)
+                } else {
+                    FieldValue::String(decode_legacy(&data[..end]))
+                }
+            }
+            (FieldKind::Int, 1) => FieldValue::Int(data[0] as i8 as i32),
+            (FieldKind::Int, 2) => FieldValue::Int(i16::from_le_bytes([data[0], data[1]]) as i32),
+            (FieldKind::Int, 4) => FieldValue::Int(i32::from_le_bytes(four_bytes(data))),
+            (FieldKind::Float, 4) => FieldValue::Float(f32::from_le_bytes(four_bytes(data))),
+            (FieldKind::FormId, 4) => FieldValue::FormId(u32::from_le_bytes(four_bytes(data))),
+            (FieldKind::FormIdList, len) if len % 4 == 0 => FieldValue::FormIds(
+                data.chunks_exact(4)
+                    .map(|c| u32::from_le_bytes(four_bytes(c)))
+                    .collect(),
+            ),
+            _ => FieldValue::Bytes(data),
+        }
+    }
+}
+
+fn four_bytes(data: &[u8]) -> [u8; 4] {
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(data);
+    buf
+}
+
 /// A record
 ///
 /// This trait is a general interface to the record types of different games.
@@ -34,6 +108,17 @@ pub trait Record<F: Field>: IntoIterator<Item = F> + Sized {
 
     fn name(&self) -> &[u8; 4];
 
+    /// Gets where this record was found in the file it was read from
+    ///
+    /// Returns `None` for records that weren't read from a file. The span is not updated when the
+    /// record is changed, so it always refers to the original data.
+    fn span(&self) -> Option<RecordSpan>;
+
+    /// Gets the approximate number of bytes of record data held in memory
+    ///
+    /// This includes both the raw data the record was read from and any decoded fields.
+    fn data_size(&self) -> usize;
+
     /// Returns the record name as a string
     ///
     /// If the record name cannot be decoded as UTF-8 (which will never happen in a valid plugin
@@ -52,5 +137,54 @@ pub trait Record<F: Field>: IntoIterator<Item = F> + Sized {
 
     fn iter_mut(&mut self) -> Box<dyn Iterator<Item = &mut F> + '_>;
 
+    /// Guesses the kind of value a field of this record holds
+    ///
+    /// The guess is based on the field's name and this record's type, using the game's
+    /// [`RecordSchema`]. Unknown fields are treated as raw bytes.
+    ///
+    /// [`RecordSchema`]: struct.RecordSchema.html
+    fn field_kind(&self, field_name: &[u8]) -> FieldKind;
+
+    /// Iterates through the fields of this record along with a best-effort decoding of their values
+    ///
+    /// This is meant for generic tools, such as dumping or diffing plugins, that need to show field
+    /// values without a dedicated [`Form`] for every record type. Because the decoding is based on
+    /// heuristics, a field may be decoded as the wrong kind of value.
+    ///
+    /// [`Form`]: trait.Form.html
+    fn iter_typed(&self) -> Box<dyn Iterator<Item = (&F, FieldValue<'_>)> + '_> {
+        Box::new(self.iter().map(move |field| {
+            let value = FieldValue::decode(field.get(), self.field_kind(field.name()));
+            (field, value)
+        }))
+    }
+
+    /// Checks that the fields of this record hold the kinds of values the game's schema expects
+    ///
+    /// Only fields the schema knows about are checked; anything is accepted for other fields.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a field's data can't be decoded as the kind of value the schema expects, e.g.
+    /// because it's the wrong size.
+    fn check_fields(&self) -> Result<(), TesError> {
+        for field in self.iter() {
+            let kind = self.field_kind(field.name());
+            if kind != FieldKind::Bytes {
+                if let FieldValue::Bytes(data) = FieldValue::decode(field.get(), kind) {
+                    return Err(decode_failed(format!(
+                        "{} field in {} record should be {:?}, but its data ({} bytes) isn't",
+                        field.name_as_str(),
+                        self.display_name(),
+                        kind,
+                        data.len()
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn write<T: Write + Seek>(&self, f: &mut T) -> Result<(), TesError>;
 }
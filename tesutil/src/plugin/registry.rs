@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::{Arc, RwLock};
+
+use crate::{Form, TesError};
+
+/// Callbacks for a record type that this crate doesn't natively understand
+///
+/// Mods and script extenders sometimes introduce their own record types. By default, these are
+/// passed through as raw records. Registering a handler for the type in a game's record registry
+/// lets plugin reading and writing parse and serialize them instead. Both callbacks are given a
+/// fully loaded record.
+pub trait RecordHandler<R>: Send + Sync {
+    /// Parses a record of this type that was just read from a plugin
+    ///
+    /// The handler may validate or normalize the record in place. Returning an error fails the
+    /// plugin load.
+    fn parse(&self, record: &mut R) -> Result<(), TesError>;
+
+    /// Serializes a record of this type that's about to be written to a plugin
+    ///
+    /// The handler may update the record in place. Returning an error fails the plugin write.
+    fn serialize(&self, record: &mut R) -> Result<(), TesError>;
+}
+
+/// A record handler backed by a [`Form`] implementation
+///
+/// Parsing validates that the record can be read as the form, and serializing normalizes the
+/// record by reading it and writing it back out.
+///
+/// [`Form`]: trait.Form.html
+pub struct FormHandler<T> {
+    _form: PhantomData<fn() -> T>,
+}
+
+impl<T> FormHandler<T> {
+    /// Creates a new form-backed record handler
+    pub fn new() -> FormHandler<T> {
+        FormHandler { _form: PhantomData }
+    }
+}
+
+impl<T> Default for FormHandler<T> {
+    fn default() -> Self {
+        FormHandler::new()
+    }
+}
+
+impl<T: Form> RecordHandler<T::Record> for FormHandler<T> {
+    fn parse(&self, record: &mut T::Record) -> Result<(), TesError> {
+        T::read(record).map(|_| ())
+    }
+
+    fn serialize(&self, record: &mut T::Record) -> Result<(), TesError> {
+        T::read(record)?.write(record)
+    }
+}
+
+/// Registry of handlers for custom record types
+///
+/// Each game has a global registry that its plugin type consults when reading and writing.
+pub struct RecordRegistry<R> {
+    handlers: RwLock<HashMap<[u8; 4], Arc<dyn RecordHandler<R>>>>,
+}
+
+impl<R> RecordRegistry<R> {
+    /// Creates an empty registry
+    pub fn new() -> RecordRegistry<R> {
+        RecordRegistry {
+            handlers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Registers a handler for a record type
+    ///
+    /// Returns the handler previously registered for this type, if any.
+    pub fn register<H>(
+        &self,
+        record_type: &[u8; 4],
+        handler: H,
+    ) -> Option<Arc<dyn RecordHandler<R>>>
+    where
+        H: RecordHandler<R> + 'static,
+    {
+        self.handlers
+            .write()
+            .unwrap()
+            .insert(*record_type, Arc::new(handler))
+    }
+
+    /// Removes the handler for a record type, returning it if there was one
+    pub fn unregister(&self, record_type: &[u8; 4]) -> Option<Arc<dyn RecordHandler<R>>> {
+        self.handlers.write().unwrap().remove(record_type)
+    }
+
+    /// Gets the handler for a record type
+    pub fn get(&self, record_type: &[u8; 4]) -> Option<Arc<dyn RecordHandler<R>>> {
+        self.handlers.read().unwrap().get(record_type).cloned()
+    }
+
+    /// Is there a handler registered for a record type?
+    pub fn is_registered(&self, record_type: &[u8; 4]) -> bool {
+        self.handlers.read().unwrap().contains_key(record_type)
+    }
+
+    /// Are there no handlers registered?
+    pub fn is_empty(&self) -> bool {
+        self.handlers.read().unwrap().is_empty()
+    }
+}
+
+impl<R> Default for RecordRegistry<R> {
+    fn default() -> Self {
+        RecordRegistry::new()
+    }
+}
+
+impl<R> std::fmt::Debug for RecordRegistry<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let handlers = self.handlers.read().unwrap();
+        let mut types: Vec<_> = handlers
+            .keys()
+            .map(|t| String::from_utf8_lossy(t).into_owned())
+            .collect();
+        types.sort();
+        f.debug_struct("RecordRegistry")
+            .field("types", &types)
+            .finish()
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::RwLock;
+
+/// The kind of value a field is likely to hold
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FieldKind {
+    String,
+    Int,
+    Float,
+    FormId,
+    /// A list of form IDs, one after another
+    FormIdList,
+    Bytes,
+}
+
+/// A built-in schema entry
+///
+/// The entries are the field name, the record type the entry applies to (or `None` for all record
+/// types), and the kind of value.
+pub(crate) type FieldKindRule = (&'static [u8; 4], Option<&'static [u8; 4]>, FieldKind);
+
+/// The kinds of values fields hold, by record type and field name
+///
+/// Each game has a global schema describing the fields this crate knows about, which is used to
+/// decode and check records that don't have a dedicated [`Form`]. Mods can add fields of their
+/// own; defining them in the schema lets [`Record::iter_typed`] and [`Record::check_fields`]
+/// understand them too.
+///
+/// A field can be defined for a specific record type or for all record types. A definition for a
+/// specific record type takes precedence.
+///
+/// [`Form`]: trait.Form.html
+/// [`Record::iter_typed`]: trait.Record.html#method.iter_typed
+/// [`Record::check_fields`]: trait.Record.html#method.check_fields
+pub struct RecordSchema {
+    kinds: RwLock<HashMap<([u8; 4], Option<[u8; 4]>), FieldKind>>,
+}
+
+impl RecordSchema {
+    /// Creates an empty schema
+    pub fn new() -> RecordSchema {
+        RecordSchema {
+            kinds: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Creates a schema from a built-in table
+    pub(crate) fn with_rules(rules: &[FieldKindRule]) -> RecordSchema {
+        let kinds = rules
+            .iter()
+            .map(|(field_name, record_type, kind)| ((**field_name, record_type.copied()), *kind))
+            .collect();
+        RecordSchema {
+            kinds: RwLock::new(kinds),
+        }
+    }
+
+    /// Defines the kind of value a field holds
+    ///
+    /// If `record_type` is `None`, the definition applies to all record types that don't have
+    /// their own definition for the field. Returns the previous definition, if any.
+    pub fn define(
+        &self,
+        record_type: Option<&[u8; 4]>,
+        field_name: &[u8; 4],
+        kind: FieldKind,
+    ) -> Option<FieldKind> {
+        self.kinds
+            .write()
+            .unwrap()
+            .insert((*field_name, record_type.copied()), kind)
+    }
+
+    /// Removes the definition of a field, returning it if there was one
+    pub fn undefine(
+        &self,
+        record_type: Option<&[u8; 4]>,
+        field_name: &[u8; 4],
+    ) -> Option<FieldKind> {
+        self.kinds
+            .write()
+            .unwrap()
+            .remove(&(*field_name, record_type.copied()))
+    }
+
+    /// Gets the kind of value a field holds in a particular record type
+    ///
+    /// Fields the schema doesn't define are treated as raw bytes.
+    pub fn field_kind(&self, record_type: &[u8; 4], field_name: &[u8]) -> FieldKind {
+        let field_name = match <[u8; 4]>::try_from(field_name) {
+            Ok(field_name) => field_name,
+            Err(_) => return FieldKind::Bytes,
+        };
+
+        let kinds = self.kinds.read().unwrap();
+        kinds
+            .get(&(field_name, Some(*record_type)))
+            .or_else(|| kinds.get(&(field_name, None)))
+            .copied()
+            .unwrap_or(FieldKind::Bytes)
+    }
+
+    /// Gets the number of definitions in the schema
+    pub fn len(&self) -> usize {
+        self.kinds.read().unwrap().len()
+    }
+
+    /// Does the schema have no definitions?
+    pub fn is_empty(&self) -> bool {
+        self.kinds.read().unwrap().is_empty()
+    }
+}
+
+impl Default for RecordSchema {
+    fn default() -> Self {
+        RecordSchema::new()
+    }
+}
+
+impl std::fmt::Debug for RecordSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecordSchema")
+            .field("definitions", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn define_fields() {
+        let schema = RecordSchema::with_rules(&[
+            (b"EDID", None, FieldKind::String),
+            (b"DATA", Some(b"ALCH"), FieldKind::Float),
+        ]);
+        assert_eq!(schema.field_kind(b"WEAP", b"EDID"), FieldKind::String);
+        assert_eq!(schema.field_kind(b"ALCH", b"DATA"), FieldKind::Float);
+        assert_eq!(schema.field_kind(b"WEAP", b"DATA"), FieldKind::Bytes);
+        assert_eq!(schema.field_kind(b"WEAP", b"XXXXX"), FieldKind::Bytes);
+
+        // a mod-added field
+        assert!(schema.define(None, b"XMOD", FieldKind::FormId).is_none());
+        schema.define(Some(b"WEAP"), b"XMOD", FieldKind::Int);
+        assert_eq!(schema.field_kind(b"WEAP", b"XMOD"), FieldKind::Int);
+        assert_eq!(schema.field_kind(b"ARMO", b"XMOD"), FieldKind::FormId);
+
+        assert_eq!(
+            schema.undefine(Some(b"WEAP"), b"XMOD"),
+            Some(FieldKind::Int)
+        );
+        assert_eq!(schema.field_kind(b"WEAP", b"XMOD"), FieldKind::FormId);
+        assert_eq!(schema.len(), 3);
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use super::{Field, Record, RecordStatus};
+
+/// Load statistics for the records of one type
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct RecordStats {
+    /// Number of records loaded
+    pub count: usize,
+    /// Number of records whose fields have been decoded
+    pub finalized: usize,
+    /// Number of records whose fields haven't been decoded yet
+    pub lazy: usize,
+    /// Number of records whose fields failed to decode
+    pub failed: usize,
+    /// Approximate number of bytes of record data held in memory
+    pub bytes: usize,
+}
+
+impl RecordStats {
+    fn merge(&mut self, other: &RecordStats) {
+        self.count += other.count;
+        self.finalized += other.finalized;
+        self.lazy += other.lazy;
+        self.failed += other.failed;
+        self.bytes += other.bytes;
+    }
+}
+
+/// Statistics about the records held by a plugin or world, by record type
+///
+/// These are meant for diagnosing slow loads and high memory use on large load orders. Byte counts
+/// are approximate: they include the record data and decoded fields, but not the overhead of the
+/// data structures holding them.
+#[derive(Debug, Clone, Default)]
+pub struct LoadStats {
+    by_type: HashMap<[u8; 4], RecordStats>,
+}
+
+impl LoadStats {
+    /// Creates empty statistics
+    pub fn new() -> LoadStats {
+        LoadStats::default()
+    }
+
+    /// Adds a record to the statistics
+    pub fn add<F: Field, R: Record<F>>(&mut self, record: &R) {
+        let stats = self.by_type.entry(*record.name()).or_default();
+        stats.count += 1;
+        match record.status() {
+            RecordStatus::Initialized => stats.lazy += 1,
+            RecordStatus::Finalized => stats.finalized += 1,
+            RecordStatus::Failed => stats.failed += 1,
+        }
+        stats.bytes += record.data_size();
+    }
+
+    /// Adds another set of statistics to these
+    pub fn merge(&mut self, other: &LoadStats) {
+        for (record_type, stats) in &other.by_type {
+            self.by_type.entry(*record_type).or_default().merge(stats);
+        }
+    }
+
+    /// Gets the statistics for one record type
+    pub fn get(&self, record_type: &[u8; 4]) -> RecordStats {
+        self.by_type.get(record_type).copied().unwrap_or_default()
+    }
+
+    /// Gets the statistics for each record type, ordered by record type
+    pub fn by_type(&self) -> Vec<([u8; 4], RecordStats)> {
+        let mut by_type: Vec<_> = self.by_type.iter().map(|(t, s)| (*t, *s)).collect();
+        by_type.sort_by_key(|(t, _)| *t);
+        by_type
+    }
+
+    /// Gets the statistics for all record types combined
+    pub fn total(&self) -> RecordStats {
+        let mut total = RecordStats::default();
+        for stats in self.by_type.values() {
+            total.merge(stats);
+        }
+        total
+    }
+}
@@ -0,0 +1,24 @@
+//! The traits and types most code working with plugins and saves needs
+//!
+//! Most of this crate's functionality is in trait methods, which can only be called with the trait
+//! in scope, so `use tesutil::prelude::*;` saves importing them one by one. Everything here is also
+//! available from its usual path; the prelude only gathers it in one place, so code that uses it
+//! isn't affected when items move between modules.
+
+pub use crate::{
+    Attribute, EffectRange, ExtraFields, Field, FieldValue, Form, MagicSchool, OwnedOrRef, Plugin,
+    Record, SoulSize, Specialization, TesError,
+};
+
+#[cfg(feature = "world")]
+pub use crate::{GamePaths, Provenance, SearchMatch, World};
+
+#[cfg(all(feature = "tes3", feature = "world"))]
+pub use crate::tes3::Tes3World;
+#[cfg(feature = "tes3")]
+pub use crate::tes3::{self, Tes3Field, Tes3Plugin, Tes3Record};
+
+#[cfg(all(feature = "tes4", feature = "world", feature = "cosave"))]
+pub use crate::tes4::Tes4World;
+#[cfg(feature = "tes4")]
+pub use crate::tes4::{self, FindForm, FormId, Tes4Field, Tes4Plugin, Tes4Record};
@@ -0,0 +1,95 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::TesError;
+
+/// A save game screenshot
+#[derive(Debug, Clone)]
+pub struct Screenshot {
+    /// Width of the screenshot in pixels
+    pub width: u32,
+    /// Height of the screenshot in pixels
+    pub height: u32,
+    /// Pixel data as 24-bit RGB, row by row from the top
+    pub data: Vec<u8>,
+}
+
+/// Summary information about a save game
+///
+/// This is the information a game would show in its load menu. It can be read from the save's
+/// header without parsing the rest of the file.
+#[derive(Debug, Clone)]
+pub struct SaveMetadata {
+    /// Path to the save file
+    pub path: PathBuf,
+    /// Last time the save file was modified
+    pub modified: Option<SystemTime>,
+    /// Name of the player character
+    pub player_name: String,
+    /// Level of the player character, if the save header records it
+    pub player_level: Option<u16>,
+    /// Name of the player's current location
+    pub location: String,
+    /// Total time played, if the save header records it
+    pub play_time: Option<Duration>,
+    /// Screenshot taken when the game was saved
+    pub screenshot: Option<Screenshot>,
+}
+
+/// The saves in a directory
+#[derive(Debug, Default)]
+pub struct SaveList {
+    /// Metadata for each save, from most to least recently modified
+    pub saves: Vec<SaveMetadata>,
+    /// Files with the save extension that couldn't be read as saves, and why
+    pub unreadable: Vec<(PathBuf, TesError)>,
+}
+
+/// Reads metadata for all saves in a directory, sorted from most to least recently modified
+///
+/// Files that don't have the given extension are skipped. Files that do but can't be read as saves
+/// are listed in [`SaveList::unreadable`].
+///
+/// [`SaveList::unreadable`]: struct.SaveList.html#structfield.unreadable
+pub(crate) fn list_saves<P, F>(
+    dir: P,
+    extension: &str,
+    read_metadata: F,
+) -> Result<SaveList, TesError>
+where
+    P: AsRef<Path>,
+    F: Fn(BufReader<File>) -> Result<SaveMetadata, TesError>,
+{
+    let mut list = SaveList::default();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_save = path
+            .extension()
+            .and_then(OsStr::to_str)
+            .map_or(false, |e| e.eq_ignore_ascii_case(extension));
+        if !is_save || !path.is_file() {
+            continue;
+        }
+
+        match File::open(&path)
+            .map_err(TesError::from)
+            .and_then(|f| read_metadata(BufReader::new(f)))
+        {
+            Ok(mut metadata) => {
+                metadata.modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+                metadata.path = path;
+                list.saves.push(metadata);
+            }
+            Err(e) => list.unreadable.push((path, e)),
+        }
+    }
+
+    // most recent first
+    list.saves.sort_by(|a, b| b.modified.cmp(&a.modified));
+
+    Ok(list)
+}
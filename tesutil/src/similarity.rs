@@ -0,0 +1,72 @@
+/// Calculates the case-insensitive Levenshtein distance between two strings
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().flat_map(char::to_lowercase).collect();
+    let b: Vec<char> = b.chars().flat_map(char::to_lowercase).collect();
+
+    // distances from the previous row of the matrix; we only need to keep one row at a time
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + if ca == cb { 0 } else { 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Finds the candidates most similar to a search string
+///
+/// Candidates are compared case-insensitively and returned closest first. Candidates that are too
+/// different from the search string to plausibly be a typo of it are excluded, so the result may
+/// contain fewer than `max_results` entries.
+pub fn closest_matches<'a, T>(search: &str, candidates: T, max_results: usize) -> Vec<&'a str>
+where
+    T: IntoIterator<Item = &'a str>,
+{
+    // allow roughly one edit for every three characters, but always allow a couple
+    let max_distance = (search.chars().count() / 3).max(2);
+    let mut matches: Vec<_> = candidates
+        .into_iter()
+        .map(|c| (edit_distance(search, c), c))
+        .filter(|(d, _)| *d <= max_distance)
+        .collect();
+
+    // sort by distance, then by ID so the result doesn't depend on the order of the candidates.
+    // comparing the exact IDs last decides which of several candidates differing only in case
+    // survives the dedup.
+    matches.sort_by_key(|(d, c)| (*d, c.to_lowercase(), *c));
+    matches.dedup_by(|(_, a), (_, b)| a.eq_ignore_ascii_case(b));
+    matches.truncate(max_results);
+    matches.into_iter().map(|(_, c)| c).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn similar_ids() {
+        assert_eq!(edit_distance("Dark Elf", "dark elf"), 0);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(
+            closest_matches("Dark Elf", ["DarkElf", "HighElf", "WoodElf", "Khajiit"], 5),
+            vec!["DarkElf"]
+        );
+        assert_eq!(
+            closest_matches("Imperal", ["Breton", "Imperial", "imperial"], 5),
+            vec!["Imperial"]
+        );
+        assert_eq!(
+            closest_matches("Imperal", ["imperial", "Breton", "Imperial"], 5),
+            vec!["Imperial"]
+        );
+        assert_eq!(
+            closest_matches("Nord", ["Nords", "Lord", "Nor"], 5),
+            vec!["Lord", "Nor", "Nords"]
+        );
+    }
+}
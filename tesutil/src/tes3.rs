@@ -8,9 +8,17 @@ use num_enum::TryFromPrimitive;
 mod plugin;
 pub use plugin::*;
 
+mod bsa;
+pub use bsa::*;
+
+#[cfg(feature = "world")]
 mod world;
+#[cfg(feature = "world")]
 pub use world::*;
 
+mod save_editor;
+pub use save_editor::*;
+
 /// All possible skills
 #[derive(Copy, Clone, Debug, Enum, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
@@ -0,0 +1,525 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::{
+    checked_len, decode_failed, decode_legacy, normalize_archive_path, process_entries_in_parallel,
+    ArchiveEntry, SharedReader, TesError,
+};
+
+use binrw::BinReaderExt;
+
+const BSA_VERSION: u32 = 0x100;
+const HEADER_SIZE: u64 = 12;
+/// Size of each file's entry in the size/offset table plus its entry in the name offset table
+const FILE_ENTRY_SIZE: u64 = 12;
+const HASH_SIZE: u64 = 8;
+
+/// Computes the hash Morrowind uses to look up a file in a BSA archive
+///
+/// The path is matched case-insensitively, and either `/` or `\` may be used as the separator.
+/// The low 32 bits of the hash are calculated from the first half of the path and the high 32
+/// bits from the second half, and are stored in the archive's hash table in that order.
+pub fn bsa_hash(path: &str) -> u64 {
+    let path = normalize_archive_path(path);
+    let (first, second) = path.as_bytes().split_at(path.len() / 2);
+
+    let mut low = 0u32;
+    for (i, c) in first.iter().enumerate() {
+        low ^= (*c as u32) << ((i * 8) & 0x1f);
+    }
+
+    let mut high = 0u32;
+    for (i, c) in second.iter().enumerate() {
+        let value = (*c as u32) << ((i * 8) & 0x1f);
+        high = (high ^ value).rotate_right(value & 0x1f);
+    }
+
+    ((high as u64) << 32) | low as u64
+}
+
+/// A file stored in a BSA archive
+#[derive(Debug, Clone)]
+pub struct BsaFile {
+    path: String,
+    size: u32,
+    offset: u32,
+}
+
+impl BsaFile {
+    /// Gets the file's path within the archive, such as `meshes\m\misc_com_bottle_01.nif`
+    pub fn path(&self) -> &str {
+        self.path.as_str()
+    }
+
+    /// Gets the size of the file's data in bytes
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+}
+
+/// A Morrowind BSA archive
+///
+/// Only the archive's file list is read when it's opened; file data is read from the underlying
+/// reader as it's extracted. Paths are matched case-insensitively, and either `/` or `\` may be
+/// used as the separator.
+#[derive(Debug)]
+pub struct Bsa<T: Read + Seek> {
+    reader: SharedReader<T>,
+    files: Vec<BsaFile>,
+    paths: HashMap<String, usize>,
+    data_offset: u64,
+}
+
+impl Bsa<BufReader<File>> {
+    /// Opens a BSA archive file
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs or if the archive is invalid.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Bsa<BufReader<File>>, TesError> {
+        let f = File::open(path)?;
+        Bsa::read(BufReader::new(f))
+    }
+}
+
+impl<T: Read + Seek> Bsa<T> {
+    /// Reads an archive's file list from a reader positioned at the start of the archive
+    ///
+    /// File names that aren't valid UTF-8 are decoded as Windows-1252, like the game's own text.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs or if the archive is invalid.
+    pub fn read(mut reader: T) -> Result<Bsa<T>, TesError> {
+        let start = reader.stream_position()?;
+        let end = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(start))?;
+
+        let version: u32 = reader.read_le()?;
+        if version != BSA_VERSION {
+            return Err(decode_failed(format!(
+                "Unsupported BSA version {:#x}",
+                version
+            )));
+        }
+        // the hash table offset is relative to the end of the header
+        let hash_offset: u32 = reader.read_le()?;
+        let num_files: u32 = reader.read_le()?;
+
+        let mut sizes_and_offsets = vec![];
+        for _ in 0..num_files {
+            let size: u32 = reader.read_le()?;
+            let offset: u32 = reader.read_le()?;
+            sizes_and_offsets.push((size, offset));
+        }
+
+        let mut name_offsets = vec![];
+        for _ in 0..num_files {
+            let name_offset: u32 = reader.read_le()?;
+            name_offsets.push(name_offset as usize);
+        }
+
+        let names_size = (hash_offset as u64)
+            .checked_sub(num_files as u64 * FILE_ENTRY_SIZE)
+            .ok_or_else(|| decode_failed("Invalid BSA hash table offset"))?;
+        // the sizes come from the header, so check them against the archive before allocating
+        if names_size > end - reader.stream_position()? {
+            return Err(decode_failed(
+                "BSA file name table extends past the end of the archive",
+            ));
+        }
+        let mut names = vec![0u8; names_size as usize];
+        reader.read_exact(&mut names)?;
+
+        let data_offset = start + HEADER_SIZE + hash_offset as u64 + num_files as u64 * HASH_SIZE;
+        let mut files = vec![];
+        let mut paths = HashMap::new();
+        for ((size, offset), name_offset) in sizes_and_offsets.into_iter().zip(name_offsets) {
+            let name = names
+                .get(name_offset..)
+                .and_then(|n| n.split(|b| *b == 0).next())
+                .filter(|n| name_offset + n.len() < names.len())
+                .ok_or_else(|| decode_failed("Invalid file name offset in BSA"))?;
+            let path = decode_legacy(name).into_owned();
+            if data_offset + offset as u64 + size as u64 > end {
+                return Err(decode_failed(format!(
+                    "BSA file {} extends past the end of the archive",
+                    path
+                )));
+            }
+
+            paths.insert(normalize_archive_path(&path), files.len());
+            files.push(BsaFile { path, size, offset });
+        }
+
+        Ok(Bsa {
+            reader: SharedReader::new(reader),
+            files,
+            paths,
+            data_offset,
+        })
+    }
+
+    /// Iterates through the files in the archive
+    pub fn files(&self) -> impl Iterator<Item = &BsaFile> + '_ {
+        self.files.iter()
+    }
+
+    /// Gets the number of files in the archive
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns whether the archive contains no files
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Gets the file at a path in the archive, if it exists
+    pub fn get(&self, path: &str) -> Option<&BsaFile> {
+        self.paths
+            .get(&normalize_archive_path(path))
+            .map(|index| &self.files[*index])
+    }
+
+    /// Returns whether the archive contains a file at a path
+    pub fn contains(&self, path: &str) -> bool {
+        self.paths.contains_key(&normalize_archive_path(path))
+    }
+
+    /// Reads the data of the file at a path in the archive
+    ///
+    /// Returns `None` if the archive has no file at that path.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs.
+    pub fn extract(&mut self, path: &str) -> Result<Option<Vec<u8>>, TesError> {
+        let file = match self.paths.get(&normalize_archive_path(path)) {
+            Some(index) => &self.files[*index],
+            None => return Ok(None),
+        };
+
+        self.reader
+            .seek(SeekFrom::Start(self.data_offset + file.offset as u64))?;
+        let mut data = vec![0u8; file.size as usize];
+        self.reader.read_exact(&mut data)?;
+        Ok(Some(data))
+    }
+
+    /// Extracts every file in the archive, handing the files to multiple threads
+    ///
+    /// The files' data is read in order on the calling thread, and `num_threads` worker threads
+    /// call `f` with each file and its data. Since `f` is called from several threads at once, it
+    /// can write the files out concurrently. Extraction stops at the first error.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs or if `f` fails.
+    pub fn extract_all<F, E>(&mut self, num_threads: usize, f: F) -> Result<(), E>
+    where
+        F: Fn(&BsaFile, Vec<u8>) -> Result<(), E> + Sync,
+        E: From<TesError> + Send,
+    {
+        let data_offset = self.data_offset;
+        let files = &self.files;
+        process_entries_in_parallel(
+            &mut self.reader,
+            files
+                .iter()
+                .enumerate()
+                .map(|(i, file)| (i, data_offset + file.offset as u64, file.size as u64)),
+            num_threads,
+            |index, data| f(&files[index], data),
+        )
+    }
+
+    /// Opens the file at a path in the archive for streaming
+    ///
+    /// Unlike [`extract`], this doesn't read the file's data up front, so it's better suited to
+    /// large files. The entry doesn't borrow the archive, and several entries can be read at once.
+    ///
+    /// Returns `None` if the archive has no file at that path.
+    ///
+    /// [`extract`]: #method.extract
+    pub fn open_entry(&self, path: &str) -> Option<ArchiveEntry<SharedReader<T>>> {
+        let file = &self.files[*self.paths.get(&normalize_archive_path(path))?];
+        Some(ArchiveEntry::new(
+            self.reader.clone(),
+            self.data_offset + file.offset as u64,
+            file.size as u64,
+        ))
+    }
+}
+
+/// Builds a new Morrowind BSA archive
+///
+/// Paths are stored in lowercase with backslashes, like in the archives that come with the game,
+/// and the files are written in hash order so that the game can look them up.
+#[derive(Debug, Default)]
+pub struct BsaBuilder {
+    files: HashMap<String, Vec<u8>>,
+}
+
+impl BsaBuilder {
+    /// Starts building an empty archive
+    pub fn new() -> BsaBuilder {
+        BsaBuilder::default()
+    }
+
+    /// Adds a file to the archive, replacing any file already added at the same path
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file is too large to be stored in a BSA.
+    pub fn add_file(&mut self, path: &str, data: Vec<u8>) -> Result<(), TesError> {
+        checked_len::<u32>(data.len(), "File too large for BSA")?;
+        self.files.insert(normalize_archive_path(path), data);
+        Ok(())
+    }
+
+    /// Gets the number of files that have been added
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns whether no files have been added
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Writes the archive
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs or if the archive would be too large.
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<(), TesError> {
+        let mut files: Vec<_> = self
+            .files
+            .iter()
+            .map(|(path, data)| (bsa_hash(path), path, data))
+            .collect();
+        files.sort_by_key(|(hash, _, _)| *hash);
+
+        let names_size: usize = files.iter().map(|(_, path, _)| path.len() + 1).sum();
+        let hash_offset: u32 = checked_len(
+            files.len() * FILE_ENTRY_SIZE as usize + names_size,
+            "Too many files for BSA",
+        )?;
+        let num_files: u32 = checked_len(files.len(), "Too many files for BSA")?;
+        writer.write_all(&BSA_VERSION.to_le_bytes())?;
+        writer.write_all(&hash_offset.to_le_bytes())?;
+        writer.write_all(&num_files.to_le_bytes())?;
+
+        let mut offset = 0;
+        for (_, _, data) in &files {
+            writer.write_all(&(data.len() as u32).to_le_bytes())?;
+            writer.write_all(&checked_len::<u32>(offset, "BSA too large")?.to_le_bytes())?;
+            offset += data.len();
+        }
+
+        let mut name_offset = 0;
+        for (_, path, _) in &files {
+            writer.write_all(&(name_offset as u32).to_le_bytes())?;
+            name_offset += path.len() + 1;
+        }
+
+        for (_, path, _) in &files {
+            writer.write_all(path.as_bytes())?;
+            writer.write_all(b"\0")?;
+        }
+
+        for (hash, _, _) in &files {
+            writer.write_all(&hash.to_le_bytes())?;
+        }
+
+        for (_, _, data) in &files {
+            writer.write_all(data)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "world")]
+impl<T: Read + Seek + Send + std::fmt::Debug + 'static> crate::Archive for Bsa<T> {
+    fn contains(&self, path: &str) -> bool {
+        Bsa::contains(self, path)
+    }
+
+    fn extract(&mut self, path: &str) -> Result<Option<Vec<u8>>, TesError> {
+        Bsa::extract(self, path)
+    }
+
+    fn open_entry(&mut self, path: &str) -> Result<Option<Box<dyn crate::AssetStream>>, TesError> {
+        Ok(Bsa::open_entry(self, path).map(|entry| Box::new(entry) as Box<dyn crate::AssetStream>))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn make_archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut entries = vec![];
+        let mut name_offsets = vec![];
+        let mut names = vec![];
+        let mut data = vec![];
+        for (path, contents) in files {
+            entries.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            entries.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            name_offsets.extend_from_slice(&(names.len() as u32).to_le_bytes());
+            names.extend_from_slice(path.as_bytes());
+            names.push(0);
+            data.extend_from_slice(contents);
+        }
+
+        let mut archive = vec![];
+        archive.extend_from_slice(&BSA_VERSION.to_le_bytes());
+        let hash_offset = entries.len() + name_offsets.len() + names.len();
+        archive.extend_from_slice(&(hash_offset as u32).to_le_bytes());
+        archive.extend_from_slice(&(files.len() as u32).to_le_bytes());
+        archive.extend(entries);
+        archive.extend(name_offsets);
+        archive.extend(names);
+        // the hashes aren't used for lookups, so their values don't matter here
+        archive.extend(vec![0u8; files.len() * HASH_SIZE as usize]);
+        archive.extend(data);
+        archive
+    }
+
+    #[test]
+    fn read_archive() {
+        let data = make_archive(&[
+            ("meshes\\m\\misc_com_bottle_01.nif", &b"NIF data"[..]),
+            ("icons\\m\\misc_com_bottle_01.dds", &b"DDS"[..]),
+        ]);
+        let mut bsa = Bsa::read(Cursor::new(data)).unwrap();
+
+        assert_eq!(bsa.len(), 2);
+        assert_eq!(
+            bsa.files().map(BsaFile::path).collect::<Vec<_>>(),
+            vec![
+                "meshes\\m\\misc_com_bottle_01.nif",
+                "icons\\m\\misc_com_bottle_01.dds"
+            ]
+        );
+        assert_eq!(bsa.get("Icons/M/Misc_Com_Bottle_01.dds").unwrap().size(), 3);
+        assert_eq!(
+            bsa.extract("icons/m/misc_com_bottle_01.dds").unwrap(),
+            Some(b"DDS".to_vec())
+        );
+        assert_eq!(
+            bsa.extract("meshes\\m\\misc_com_bottle_01.nif").unwrap(),
+            Some(b"NIF data".to_vec())
+        );
+        let mut entry = bsa.open_entry("meshes/m/misc_com_bottle_01.nif").unwrap();
+        let mut data = vec![];
+        entry.seek(SeekFrom::Start(4)).unwrap();
+        entry.read_exact(&mut [0u8; 1]).unwrap();
+        // the entry doesn't borrow the archive, so other files can be read in the meantime
+        assert_eq!(
+            bsa.extract("icons/m/misc_com_bottle_01.dds").unwrap(),
+            Some(b"DDS".to_vec())
+        );
+        entry.read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"ata");
+        assert!(bsa
+            .open_entry("meshes\\m\\misc_com_bottle_02.nif")
+            .is_none());
+        assert!(!bsa.contains("meshes\\m\\misc_com_bottle_02.nif"));
+        assert_eq!(
+            bsa.extract("meshes\\m\\misc_com_bottle_02.nif").unwrap(),
+            None
+        );
+
+        assert!(Bsa::read(Cursor::new(b"BSA\0\0\0\0\0\0\0\0\0".to_vec())).is_err());
+    }
+
+    #[test]
+    fn read_bad_archives() {
+        // file names in Windows-1252 rather than UTF-8
+        let mut data = make_archive(&[("icons\\m\\cafe.dds", &b"DDS"[..])]);
+        let name_pos = data.windows(4).position(|w| w == b"cafe").unwrap();
+        data[name_pos + 3] = 0xe9;
+        let bsa = Bsa::read(Cursor::new(data.clone())).unwrap();
+        assert_eq!(
+            bsa.files().next().unwrap().path(),
+            "icons\\m\\caf\u{e9}.dds"
+        );
+
+        // a file whose data runs past the end of the archive
+        data.pop();
+        assert!(Bsa::read(Cursor::new(data)).is_err());
+
+        // a hash table offset far past the end of the archive
+        let mut data = make_archive(&[("splash.tga", &b""[..])]);
+        data[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(Bsa::read(Cursor::new(data)).is_err());
+    }
+
+    #[test]
+    fn build_archive() {
+        let mut builder = BsaBuilder::new();
+        builder
+            .add_file("Meshes/M/Misc_Com_Bottle_01.NIF", b"NIF data".to_vec())
+            .unwrap();
+        builder
+            .add_file("icons\\m\\misc_com_bottle_01.dds", b"DDS".to_vec())
+            .unwrap();
+        builder.add_file("splash.tga", vec![]).unwrap();
+        assert_eq!(builder.len(), 3);
+
+        let mut data = vec![];
+        builder.write(&mut data).unwrap();
+        let mut bsa = Bsa::read(Cursor::new(data)).unwrap();
+
+        assert_eq!(bsa.len(), 3);
+        // the game requires the files to be in hash order
+        let hashes: Vec<_> = bsa.files().map(|f| bsa_hash(f.path())).collect();
+        assert!(hashes.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(
+            bsa.get("meshes/m/misc_com_bottle_01.nif").unwrap().path(),
+            "meshes\\m\\misc_com_bottle_01.nif"
+        );
+        assert_eq!(
+            bsa.extract("meshes/m/misc_com_bottle_01.nif").unwrap(),
+            Some(b"NIF data".to_vec())
+        );
+        assert_eq!(
+            bsa.extract("icons/m/misc_com_bottle_01.dds").unwrap(),
+            Some(b"DDS".to_vec())
+        );
+        assert_eq!(bsa.extract("splash.tga").unwrap(), Some(vec![]));
+
+        let extracted = std::sync::Mutex::new(HashMap::new());
+        bsa.extract_all(3, |file, data| {
+            extracted
+                .lock()
+                .unwrap()
+                .insert(String::from(file.path()), data);
+            Ok::<_, TesError>(())
+        })
+        .unwrap();
+        let extracted = extracted.into_inner().unwrap();
+        assert_eq!(extracted.len(), 3);
+        assert_eq!(extracted["icons\\m\\misc_com_bottle_01.dds"], b"DDS");
+    }
+
+    #[test]
+    fn hash_paths() {
+        assert_eq!(bsa_hash("a"), 0x80000030 << 32);
+        assert_eq!(bsa_hash("ab"), 0x80000018 << 32 | 0x61);
+        assert_eq!(
+            bsa_hash("Meshes/M/Misc_Com_Bottle_01.NIF"),
+            bsa_hash("meshes\\m\\misc_com_bottle_01.nif")
+        );
+        assert_ne!(
+            bsa_hash("meshes\\m\\misc_com_bottle_01.nif"),
+            bsa_hash("meshes\\m\\misc_com_bottle_02.nif")
+        );
+    }
+}
@@ -7,6 +7,7 @@ use crate::plugin::*;
 use crate::*;
 
 use binrw::{BinReaderExt, BinWriterExt};
+use lazy_static::lazy_static;
 
 mod field;
 pub use field::*;
@@ -23,9 +24,27 @@ pub use record::*;
 mod reference;
 pub use reference::*;
 
+mod cell_reference;
+pub use cell_reference::*;
+
+mod cell;
+pub use cell::*;
+
+mod landscape;
+pub use landscape::*;
+
+mod script;
+pub use script::*;
+
+mod travel;
+pub use travel::*;
+
 mod npc_change;
 pub use npc_change::*;
 
+mod container_change;
+pub use container_change::*;
+
 mod class;
 pub use class::*;
 
@@ -59,9 +78,33 @@ pub use misc_item::*;
 mod weapon;
 pub use weapon::*;
 
+mod wearable;
+pub use wearable::*;
+
+mod armor;
+pub use armor::*;
+
+mod clothing;
+pub use clothing::*;
+
 mod book;
 pub use book::*;
 
+mod container;
+pub use container::*;
+
+mod leveled_list;
+pub use leveled_list::*;
+
+mod leveled_item;
+pub use leveled_item::*;
+
+mod leveled_creature;
+pub use leveled_creature::*;
+
+mod faction;
+pub use faction::*;
+
 mod enchantment;
 pub use enchantment::*;
 
@@ -74,6 +117,12 @@ pub use magic::*;
 mod enchantable;
 pub use enchantable::*;
 
+mod magic_effect;
+pub use magic_effect::*;
+
+mod game_state;
+pub use game_state::*;
+
 /// Save game information
 ///
 /// For saves (.ess files), this information is included in the TES3 record.
@@ -188,6 +237,31 @@ impl SaveInfo {
     }
 }
 
+/// What to do when a record is added to a plugin with the same ID and type as an existing record
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DuplicateIdPolicy {
+    /// Fail with [`TesError::DuplicateId`]
+    ///
+    /// [`TesError::DuplicateId`]: ../enum.TesError.html#variant.DuplicateId
+    Reject,
+    /// Keep all the records
+    ///
+    /// Lookups by ID return the last record added, and
+    /// [`Tes3Plugin::get_records_with_type`] returns all of them.
+    ///
+    /// [`Tes3Plugin::get_records_with_type`]: struct.Tes3Plugin.html#method.get_records_with_type
+    Allow,
+    /// Lookups by ID return the last record added
+    ///
+    /// Earlier records stay in the plugin, but can only be reached by iterating through records.
+    LastWins,
+}
+
+/// Record types that allow duplicate IDs unless configured otherwise
+///
+/// Saves legitimately contain multiple creature changes for the same creature.
+const ALLOW_DUPLICATE_ID_TYPES: [&[u8; 4]; 1] = [b"CREC"];
+
 /// Represents a plugin file
 ///
 /// This type can be used to read and write plugin (mod) files. These include .esm files (masters,
@@ -221,12 +295,15 @@ pub struct Tes3Plugin {
     screen_data: Vec<u8>,
     masters: Vec<(String, u64)>,
     records: Vec<Arc<RwLock<Tes3Record>>>,
-    id_map: HashMap<String, HashMap<[u8; 4], Arc<RwLock<Tes3Record>>>>,
+    id_map: HashMap<String, HashMap<[u8; 4], Vec<Arc<RwLock<Tes3Record>>>>>,
     type_map: HashMap<[u8; 4], Vec<Arc<RwLock<Tes3Record>>>>,
+    duplicate_id_policies: HashMap<[u8; 4], DuplicateIdPolicy>,
+    journal: Option<Journal<Tes3Record>>,
 }
 
 const HEADER_LENGTH: usize = 300;
 const FLAG_MASTER: u32 = 0x1;
+const CELL_INTERIOR: u32 = 0x1;
 
 /// Maximum length of the plugin author string
 pub const AUTHOR_LENGTH: usize = 32;
@@ -236,12 +313,72 @@ pub const DESCRIPTION_LENGTH: usize = 256;
 pub const NAME_LENGTH: usize = 32;
 /// Maximum length of player cell name
 pub const CELL_LENGTH: usize = 64;
+/// Width and height of a save game screenshot
+pub const SCREENSHOT_SIZE: usize = 128;
 
 /// Morrowind version 1.2
 pub const VERSION_1_2: f32 = 1.2;
 /// Morrowind version 1.3
 pub const VERSION_1_3: f32 = 1.3;
 
+/// The kinds of values held by well-known Morrowind fields, which make up the built-in schema
+const FIELD_KINDS: [FieldKindRule; 33] = [
+    (b"NAME", None, FieldKind::String),
+    (b"FNAM", None, FieldKind::String),
+    (b"MODL", None, FieldKind::String),
+    (b"ITEX", None, FieldKind::String),
+    (b"SCRI", None, FieldKind::String),
+    (b"TEXT", None, FieldKind::String),
+    (b"DESC", None, FieldKind::String),
+    (b"ENAM", None, FieldKind::String),
+    (b"ENAM", Some(b"SPEL"), FieldKind::Bytes),
+    (b"ENAM", Some(b"ENCH"), FieldKind::Bytes),
+    (b"ENAM", Some(b"ALCH"), FieldKind::Bytes),
+    (b"ANAM", None, FieldKind::String),
+    (b"BNAM", None, FieldKind::String),
+    (b"CNAM", None, FieldKind::String),
+    (b"KNAM", None, FieldKind::String),
+    (b"RNAM", None, FieldKind::String),
+    (b"SNAM", None, FieldKind::String),
+    // a region's map color and its sounds, each a fixed-length ID and a chance
+    (b"CNAM", Some(b"REGN"), FieldKind::Bytes),
+    (b"SNAM", Some(b"REGN"), FieldKind::Bytes),
+    (b"DNAM", None, FieldKind::String),
+    (b"ONAM", None, FieldKind::String),
+    (b"NPCS", None, FieldKind::String),
+    (b"SCTX", None, FieldKind::String),
+    (b"STRV", None, FieldKind::String),
+    (b"MAST", None, FieldKind::String),
+    (b"INTV", None, FieldKind::Int),
+    (b"INDX", None, FieldKind::Int),
+    (b"FRMR", None, FieldKind::Int),
+    (b"NAM9", None, FieldKind::Int),
+    (b"DELE", None, FieldKind::Int),
+    (b"FLTV", None, FieldKind::Float),
+    (b"XSCL", None, FieldKind::Float),
+    (b"WHGT", None, FieldKind::Float),
+];
+
+lazy_static! {
+    /// Handlers for custom record types, used when reading and writing Morrowind plugins
+    pub static ref RECORD_REGISTRY: RecordRegistry<Tes3Record> = RecordRegistry::new();
+    /// Kinds of values held by Morrowind fields, used for records without a dedicated form
+    pub static ref RECORD_SCHEMA: RecordSchema = RecordSchema::with_rules(&FIELD_KINDS);
+}
+
+fn ambiguous_id_error<'a, I>(id: &str, record_types: I) -> TesError
+where
+    I: IntoIterator<Item = &'a [u8; 4]>,
+{
+    TesError::AmbiguousId {
+        id: String::from(id),
+        record_types: record_types
+            .into_iter()
+            .map(|t| String::from_utf8_lossy(t).into_owned())
+            .collect(),
+    }
+}
+
 impl Tes3Plugin {
     /// Create a new, empty plugin
     ///
@@ -289,6 +426,8 @@ impl Tes3Plugin {
             records: vec![],
             id_map: HashMap::new(),
             type_map: HashMap::new(),
+            duplicate_id_policies: HashMap::new(),
+            journal: None,
         })
     }
 
@@ -470,8 +609,8 @@ impl Tes3Plugin {
     ///
     /// # Errors
     ///
-    /// Returns a [`PluginError::DuplicateId`] if the record has the same ID as an existing record
-    /// in this plugin.
+    /// Returns a [`PluginError::DuplicateId`] if the record has the same ID and type as an existing
+    /// record in this plugin and the [`DuplicateIdPolicy`] for its type is `Reject`.
     ///
     /// # Examples
     ///
@@ -491,39 +630,349 @@ impl Tes3Plugin {
     ///
     /// [`PluginError::LimitExceeded`]: enum.PluginError.html#variant.LimitExceeded
     /// [`PluginError::DuplicateId`]: enum.PluginError.html#variant.DuplicateId
+    /// [`DuplicateIdPolicy`]: enum.DuplicateIdPolicy.html
     pub fn add_record(&mut self, mut record: Tes3Record) -> Result<(), TesError> {
         record.finalize()?;
-        let r = Arc::new(RwLock::new(record));
-        let rb = r.read().unwrap();
-        let key = *rb.name();
-        let has_id = rb.has_id();
-        drop(rb);
-        // if this is a record type that doesn't have an ID, we can avoid finalizing it now
-        if has_id {
-            r.write().unwrap().finalize()?;
-            let rb = r.read().unwrap();
-            if let Some(id) = rb.id() {
-                let key = String::from(id);
-                let name = rb.name();
-                let type_map = self.id_map.entry(key).or_insert_with(HashMap::new);
-                // FIXME: it appears there are duplicates in the save file even among the same type (specifically CREC records)
+        let id = record.id().map(String::from);
+        self.insert_record(record, id)
+    }
 
-                type_map.insert(*name, Arc::clone(&r));
+    /// Adds a record whose ID is already known, without decoding its fields
+    fn insert_record(&mut self, record: Tes3Record, id: Option<String>) -> Result<(), TesError> {
+        let key = *record.name();
+        let r = Arc::new(RwLock::new(record));
+        let mut replaced = vec![];
+        if let Some(ref id) = id {
+            let policy = self.duplicate_id_policy(&key);
+            let records = self
+                .id_map
+                .entry(id.clone())
+                .or_insert_with(HashMap::new)
+                .entry(key)
+                .or_insert_with(Vec::new);
+            match policy {
+                _ if records.is_empty() => (),
+                DuplicateIdPolicy::Reject => return Err(TesError::DuplicateId(id.clone())),
+                DuplicateIdPolicy::Allow => (),
+                DuplicateIdPolicy::LastWins => replaced.append(records),
             }
+
+            records.push(Arc::clone(&r));
         }
         let records = self.type_map.entry(key).or_insert_with(Vec::new);
         records.push(Arc::clone(&r));
+        if let Some(ref mut journal) = self.journal {
+            let mut mutation = Mutation::new(MutationKind::Added, id, &r);
+            mutation.replaced = replaced;
+            journal.push(mutation);
+        }
         self.records.push(r);
         Ok(())
     }
 
+    /// Removes a record from this plugin
+    ///
+    /// If the record's type allows duplicate IDs, the last record added with the ID is removed.
+    /// Returns whether there was a record to remove.
+    ///
+    /// Note that this is different from marking the record deleted; see
+    /// [`Tes3Record::is_deleted`].
+    ///
+    /// [`Tes3Record::is_deleted`]: struct.Tes3Record.html#structfield.is_deleted
+    pub fn remove_record(&mut self, id: &str, record_type: &[u8; 4]) -> bool {
+        let record = match self
+            .id_map
+            .get_mut(id)
+            .and_then(|m| m.get_mut(record_type))
+            .and_then(|v| v.pop())
+        {
+            Some(record) => record,
+            None => return false,
+        };
+
+        if let Some(records) = self.type_map.get_mut(record_type) {
+            records.retain(|r| !Arc::ptr_eq(r, &record));
+        }
+        let position = self.records.iter().position(|r| Arc::ptr_eq(r, &record));
+        if let Some(position) = position {
+            self.records.remove(position);
+        }
+
+        if let Some(ref mut journal) = self.journal {
+            let mut mutation =
+                Mutation::new(MutationKind::Removed, Some(String::from(id)), &record);
+            mutation.position = position.unwrap_or(self.records.len());
+            journal.push(mutation);
+        }
+
+        true
+    }
+
+    /// Starts keeping a journal of the changes made to this plugin's records
+    ///
+    /// Any journal that was already being kept is discarded. Changes to the plugin's header, such
+    /// as its author or masters, are not recorded.
+    pub fn start_journal(&mut self) {
+        self.journal = Some(Journal::new());
+    }
+
+    /// Stops keeping a journal and returns the changes that were recorded
+    pub fn stop_journal(&mut self) -> Option<Journal<Tes3Record>> {
+        self.journal.take()
+    }
+
+    /// Gets the journal of changes made since [`start_journal`] was called, if one is being kept
+    ///
+    /// [`start_journal`]: #method.start_journal
+    pub fn journal(&self) -> Option<&Journal<Tes3Record>> {
+        self.journal.as_ref()
+    }
+
+    /// Reverts the change at an index in the journal and removes it from the journal
+    ///
+    /// Returns `false` if no journal is being kept or it has no change at that index. Reverting a
+    /// change doesn't revert later changes to the same record, so changes should be reverted from
+    /// newest to oldest. A removed record is restored to its original position in the plugin, but
+    /// lookups by ID will find it ahead of any records with the same ID added since it was removed.
+    pub fn revert(&mut self, index: usize) -> bool {
+        let mutation = match self.journal.as_mut().and_then(|j| j.take(index)) {
+            Some(mutation) => mutation,
+            None => return false,
+        };
+
+        let key = *mutation.record().name();
+        match mutation.kind {
+            MutationKind::Added => {
+                self.records.retain(|r| !Arc::ptr_eq(r, &mutation.record));
+                if let Some(records) = self.type_map.get_mut(&key) {
+                    records.retain(|r| !Arc::ptr_eq(r, &mutation.record));
+                }
+                if let Some(ref id) = mutation.id {
+                    if let Some(records) = self.id_map.get_mut(id).and_then(|m| m.get_mut(&key)) {
+                        records.retain(|r| !Arc::ptr_eq(r, &mutation.record));
+                        let mut replaced = mutation.replaced;
+                        replaced.append(records);
+                        *records = replaced;
+                    }
+                }
+            }
+            MutationKind::Modified => {
+                if let Some(previous) = mutation.take_previous() {
+                    mutation.record.write().unwrap().restore(previous);
+                }
+            }
+            MutationKind::Removed => {
+                let position = mutation.position.min(self.records.len());
+                self.records.insert(position, Arc::clone(&mutation.record));
+                self.type_map
+                    .entry(key)
+                    .or_insert_with(Vec::new)
+                    .push(Arc::clone(&mutation.record));
+                if let Some(id) = mutation.id {
+                    self.id_map
+                        .entry(id)
+                        .or_insert_with(HashMap::new)
+                        .entry(key)
+                        .or_insert_with(Vec::new)
+                        .push(mutation.record);
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Locks the last record with a given ID and type for writing, and journals the modification
+    fn modify_record(
+        &mut self,
+        id: &str,
+        name: &[u8; 4],
+    ) -> Result<Option<RecordMut<Tes3Record>>, TesError> {
+        let record = match self
+            .id_map
+            .get(id)
+            .and_then(|m| m.get(name))
+            .and_then(|v| v.last())
+        {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+
+        let rbm = Tes3Plugin::write_record(record)?;
+        let mutation = self.journal.as_mut().map(|j| {
+            j.push(Mutation::new(
+                MutationKind::Modified,
+                Some(String::from(id)),
+                record,
+            ))
+        });
+
+        Ok(Some(RecordMut::new(rbm, mutation)))
+    }
+
+    /// Gets the policy for records of a given type that have the same ID
+    ///
+    /// Unless configured otherwise, duplicate creature changes are allowed and other duplicates
+    /// replace the earlier record.
+    pub fn duplicate_id_policy(&self, record_type: &[u8; 4]) -> DuplicateIdPolicy {
+        match self.duplicate_id_policies.get(record_type) {
+            Some(policy) => *policy,
+            None if ALLOW_DUPLICATE_ID_TYPES.contains(&record_type) => DuplicateIdPolicy::Allow,
+            None => DuplicateIdPolicy::LastWins,
+        }
+    }
+
+    /// Sets the policy for records of a given type that have the same ID
+    ///
+    /// The policy applies to records added after it's set; records already in the plugin are not
+    /// checked against it. To apply policies while reading a plugin, use [`read_with_policies`].
+    ///
+    /// [`read_with_policies`]: #method.read_with_policies
+    pub fn set_duplicate_id_policy(&mut self, record_type: &[u8; 4], policy: DuplicateIdPolicy) {
+        self.duplicate_id_policies.insert(*record_type, policy);
+    }
+
+    /// Reads a plugin, applying the given duplicate ID policies as records are added
+    ///
+    /// # Errors
+    ///
+    /// Fails for the same reasons as [`read`], or if a record violates a `Reject` policy.
+    ///
+    /// [`read`]: #method.read
+    pub fn read_with_policies<T: Read + Seek>(
+        mut f: T,
+        policies: &[(&[u8; 4], DuplicateIdPolicy)],
+    ) -> Result<Tes3Plugin, TesError> {
+        let mut plugin = Tes3Plugin::read_header(&mut f)?;
+        for (record_type, policy) in policies {
+            plugin.set_duplicate_id_policy(record_type, *policy);
+        }
+
+        plugin.read_records(f, None)?;
+        Ok(plugin)
+    }
+
+    /// Reads the plugin's records, taking IDs from an index where possible
+    ///
+    /// Returns an index of the IDs of all the records that were read.
+    fn read_records<T: Read + Seek>(
+        &mut self,
+        mut f: T,
+        index: Option<&RecordIndex>,
+    ) -> Result<RecordIndex, TesError> {
+        let mut new_index = RecordIndex::new();
+
+        // num_records is actually not guaranteed to be correct, so we ignore it and just read until we hit EOF
+        let mut here = f.seek(SeekFrom::Current(0))?;
+        let eof = f.seek(SeekFrom::End(0))?;
+        f.seek(SeekFrom::Start(here))?;
+
+        while here != eof {
+            let mut record = Tes3Record::read_lazy(&mut f)?;
+            let record_name = String::from(record.display_name());
+            let context = |e| in_record(record_name.as_str(), Some(here), e);
+            if let Some(handler) = RECORD_REGISTRY.get(record.name()) {
+                record.finalize().map_err(context)?;
+                handler.parse(&mut record).map_err(context)?;
+            }
+
+            // with an index, records can stay undecoded until they're used if their ID is in the
+            // index or if they're a type that doesn't have an ID
+            let indexed_id = match index {
+                Some(index) if record.status() == RecordStatus::Initialized => {
+                    if record.has_id() {
+                        index
+                            .get(here, record.name())
+                            .map(|id| Some(String::from(id)))
+                    } else {
+                        Some(None)
+                    }
+                }
+                _ => None,
+            };
+            let id = match indexed_id {
+                Some(id) => id,
+                None => {
+                    record.finalize().map_err(context)?;
+                    record.id().map(String::from)
+                }
+            };
+
+            if let Some(ref id) = id {
+                new_index.insert(here, *record.name(), id.clone());
+            }
+
+            self.insert_record(record, id).map_err(context)?;
+            here = f.seek(SeekFrom::Current(0))?;
+        }
+
+        Ok(new_index)
+    }
+
+    /// Locks a record for reading, decoding its fields first if it hasn't been yet
+    ///
+    /// Records are only left undecoded when a plugin is read with an index.
+    fn read_record(
+        record: &Arc<RwLock<Tes3Record>>,
+    ) -> Result<RwLockReadGuard<Tes3Record>, TesError> {
+        if record.read().unwrap().status() == RecordStatus::Initialized {
+            record.write().unwrap().finalize()?;
+        }
+
+        let rb = record.read().unwrap();
+        match rb.status() {
+            RecordStatus::Failed => Err(decode_failed(format!(
+                "{} record could not be decoded",
+                rb.display_name()
+            ))),
+            _ => Ok(rb),
+        }
+    }
+
+    /// Locks a record for writing, decoding its fields first if it hasn't been yet
+    fn write_record(
+        record: &Arc<RwLock<Tes3Record>>,
+    ) -> Result<RwLockWriteGuard<Tes3Record>, TesError> {
+        let mut rbm = record.write().unwrap();
+        rbm.finalize()?;
+        match rbm.status() {
+            RecordStatus::Failed => Err(decode_failed(format!(
+                "{} record could not be decoded",
+                rbm.display_name()
+            ))),
+            _ => Ok(rbm),
+        }
+    }
+
+    /// Gets the records with a given ID, grouped by type
+    ///
+    /// Types with duplicates only have more than one record if their [`DuplicateIdPolicy`] is
+    /// `Allow`.
+    ///
+    /// [`DuplicateIdPolicy`]: enum.DuplicateIdPolicy.html
+    fn records_with_id(&self, id: &str) -> Vec<(&[u8; 4], &Vec<Arc<RwLock<Tes3Record>>>)> {
+        let mut records: Vec<_> = self
+            .id_map
+            .get(id)
+            .into_iter()
+            .flatten()
+            .filter(|(_, records)| !records.is_empty())
+            .collect();
+        records.sort_by_key(|(record_type, _)| **record_type);
+        records
+    }
+
     /// Finds a record by ID
     ///
     /// If no record exists with the given ID, the return value will be `None`.
     ///
     /// # Errors
     ///
-    /// Fails if there is more than one record with the given ID.
+    /// Returns a [`TesError::AmbiguousId`] listing the candidates if records of more than one type
+    /// have the given ID. Use [`get_record_with_type`] to pick one.
+    ///
+    /// [`TesError::AmbiguousId`]: ../enum.TesError.html#variant.AmbiguousId
+    /// [`get_record_with_type`]: #method.get_record_with_type
     ///
     /// # Examples
     ///
@@ -541,30 +990,63 @@ impl Tes3Plugin {
     /// # }
     /// ```
     pub fn get_record(&self, id: &str) -> Result<Option<RwLockReadGuard<Tes3Record>>, TesError> {
-        if let Some(type_map) = self.id_map.get(id) {
-            if type_map.is_empty() {
-                Ok(None)
-            } else if type_map.len() > 1 {
-                Err(TesError::LimitExceeded {
-                    description: format!("More than one record with ID {}", id),
-                    max_size: 1,
-                    actual_size: type_map.len(),
-                })
-            } else {
-                Ok(Some(type_map.values().next().unwrap().read().unwrap()))
-            }
-        } else {
-            Ok(None)
+        let candidates = self.records_with_id(id);
+        match candidates.as_slice() {
+            [] => Ok(None),
+            [(_, records)] => records.last().map(Tes3Plugin::read_record).transpose(),
+            _ => Err(ambiguous_id_error(id, candidates.iter().map(|(t, _)| *t))),
         }
     }
 
     /// Finds a record by ID and type
+    ///
+    /// If there are multiple records with this ID and type, the last one added is returned.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the record hasn't been decoded yet and its data is invalid.
     pub fn get_record_with_type(
         &self,
         id: &str,
         name: &[u8; 4],
-    ) -> Option<RwLockReadGuard<Tes3Record>> {
-        self.id_map.get(id)?.get(name).map(|v| v.read().unwrap())
+    ) -> Result<Option<RwLockReadGuard<Tes3Record>>, TesError> {
+        self.id_map
+            .get(id)
+            .and_then(|m| m.get(name))
+            .and_then(|v| v.last())
+            .map(Tes3Plugin::read_record)
+            .transpose()
+    }
+
+    /// Finds all records with a given ID and type
+    ///
+    /// There can only be more than one if the [`DuplicateIdPolicy`] for the type is `Allow`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of the records haven't been decoded yet and their data is invalid.
+    ///
+    /// [`DuplicateIdPolicy`]: enum.DuplicateIdPolicy.html
+    pub fn get_records_with_type(
+        &self,
+        id: &str,
+        name: &[u8; 4],
+    ) -> Result<Vec<RwLockReadGuard<Tes3Record>>, TesError> {
+        self.id_map
+            .get(id)
+            .and_then(|type_map| type_map.get(name))
+            .into_iter()
+            .flatten()
+            .map(Tes3Plugin::read_record)
+            .collect()
+    }
+
+    /// Gets the position of a record among all the records in this plugin
+    ///
+    /// Returns `None` if there's no record with the given ID and type.
+    pub fn record_index(&self, id: &str, name: &[u8; 4]) -> Option<usize> {
+        let record = self.id_map.get(id)?.get(name)?.last()?;
+        self.records.iter().position(|r| Arc::ptr_eq(r, record))
     }
 
     /// Gets an iterator over fields with a particular type
@@ -587,13 +1069,81 @@ impl Tes3Plugin {
         })
     }
 
+    /// Gets an iterator over all records in this plugin
+    ///
+    /// Records that fail to load are skipped.
+    pub fn records(&self) -> impl Iterator<Item = RwLockReadGuard<Tes3Record>> {
+        self.records.iter().filter_map(|r| {
+            if r.read().unwrap().status() == RecordStatus::Initialized {
+                let _ = r.write().unwrap().finalize();
+            }
+
+            let rb = r.read().unwrap();
+            match rb.status() {
+                RecordStatus::Finalized => Some(rb),
+                _ => None,
+            }
+        })
+    }
+
+    /// Gets statistics about the records in this plugin
+    ///
+    /// Unlike [`records`], this doesn't finalize any records.
+    ///
+    /// [`records`]: #method.records
+    pub fn load_stats(&self) -> LoadStats {
+        let mut stats = LoadStats::new();
+        for record in &self.records {
+            stats.add(&*record.read().unwrap());
+        }
+        stats
+    }
+
+    /// Gets the exterior cell at the given grid coordinates
+    ///
+    /// Returns `None` if this plugin doesn't contain the cell.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a CELL record has an invalid DATA field.
+    pub fn get_exterior_cell(
+        &self,
+        grid: GridCoords,
+    ) -> Result<Option<RwLockReadGuard<Tes3Record>>, TesError> {
+        if let Some(cells) = self.get_records_by_type(b"CELL") {
+            for cell in cells {
+                let data = match cell.iter().find(|f| f.name() == b"DATA") {
+                    Some(data) => data,
+                    None => continue,
+                };
+
+                let mut reader = data.reader();
+                let flags: u32 = reader.read_le()?;
+                if flags & CELL_INTERIOR != 0 {
+                    continue;
+                }
+
+                let x: i32 = reader.read_le()?;
+                let y: i32 = reader.read_le()?;
+                if x == grid.x && y == grid.y {
+                    return Ok(Some(cell));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
     /// Finds a record by ID and returns a mutable reference
     ///
     /// If no record exists with the given ID, the return value will be `None`.
     ///
     /// # Errors
     ///
-    /// Fails if there is more than one record with the given ID.
+    /// Returns a [`TesError::AmbiguousId`] listing the candidates if records of more than one type
+    /// have the given ID.
+    ///
+    /// [`TesError::AmbiguousId`]: ../enum.TesError.html#variant.AmbiguousId
     ///
     /// # Examples
     ///
@@ -610,25 +1160,15 @@ impl Tes3Plugin {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn get_record_mut(
-        &mut self,
-        id: &str,
-    ) -> Result<Option<RwLockWriteGuard<Tes3Record>>, TesError> {
-        if let Some(type_map) = self.id_map.get_mut(id) {
-            if type_map.is_empty() {
-                Ok(None)
-            } else if type_map.len() > 1 {
-                Err(TesError::LimitExceeded {
-                    description: format!("More than one record with ID {}", id),
-                    max_size: 1,
-                    actual_size: type_map.len(),
-                })
-            } else {
-                Ok(Some(type_map.values_mut().next().unwrap().write().unwrap()))
-            }
-        } else {
-            Ok(None)
-        }
+    pub fn get_record_mut(&mut self, id: &str) -> Result<Option<RecordMut<Tes3Record>>, TesError> {
+        let candidates = self.records_with_id(id);
+        let record_type = match candidates.as_slice() {
+            [] => return Ok(None),
+            [(record_type, _)] => **record_type,
+            _ => return Err(ambiguous_id_error(id, candidates.iter().map(|(t, _)| *t))),
+        };
+
+        self.modify_record(id, &record_type)
     }
 
     /// Loads a form by ID and type
@@ -640,51 +1180,21 @@ impl Tes3Plugin {
         &self,
         id: &str,
     ) -> Result<Option<T>, TesError> {
-        Ok(match self.get_record_with_type(id, T::RECORD_TYPE) {
+        Ok(match self.get_record_with_type(id, T::RECORD_TYPE)? {
             Some(record) => Some(T::read(&*record)?),
             None => None,
         })
     }
 
-    /// Finds a record by ID and type and returns a mutable reference
-    pub fn get_record_with_type_mut(
-        &mut self,
-        id: &str,
-        name: &[u8; 4],
-    ) -> Option<RwLockWriteGuard<Tes3Record>> {
-        self.id_map
-            .get_mut(id)?
-            .get_mut(name)
-            .map(|v| v.write().unwrap())
-    }
-}
-
-impl Plugin for Tes3Plugin {
-    /// Read a plugin file from the provided reader
+    /// Reads only the header of a plugin, without any of its records
     ///
-    /// Reads a plugin from any type that implements [`Read`] or a mutable reference to such a type.
+    /// This is much faster than reading the full plugin and is sufficient to get the plugin's
+    /// masters and save game info.
     ///
     /// # Errors
     ///
-    /// Returns an error if the format of the plugin data is invalid or if an I/O error
-    /// occurs while reading the plugin data.
-    ///
-    /// # Examples
-    ///
-    /// ```no_run
-    /// use tesutil::tes3::*;
-    /// use tesutil::Plugin;
-    ///
-    /// # fn main() -> std::io::Result<()> {
-    /// let buf: Vec<u8> = vec![/* raw plugin data */];
-    /// let plugin = Tes3Plugin::read(&mut &buf[..])?;
-    /// println!("Plugin info: author {}, description {}", plugin.author(), plugin.description());
-    /// # Ok(())
-    /// # }
-    /// ```
-    ///
-    /// [`Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
-    fn read<T: Read + Seek>(mut f: T) -> Result<Tes3Plugin, TesError> {
+    /// Fails if an I/O error occurs or if the header is invalid.
+    pub fn read_header<T: Read + Seek>(mut f: T) -> Result<Tes3Plugin, TesError> {
         let header = Tes3Record::read(&mut f)?;
         if header.name() != b"TES3" {
             return Err(decode_failed(format!(
@@ -723,6 +1233,8 @@ impl Plugin for Tes3Plugin {
             records: Vec::with_capacity(num_records),
             id_map: HashMap::with_capacity(num_records),
             type_map: HashMap::new(),
+            duplicate_id_policies: HashMap::new(),
+            journal: None,
         };
 
         let mut master_name = None;
@@ -784,17 +1296,137 @@ impl Plugin for Tes3Plugin {
             return Err(decode_failed(format!("Missing size for master {}", name)));
         }
 
-        // num_records is actually not guaranteed to be correct, so we ignore it and just read until we hit EOF
-        let mut here = f.seek(SeekFrom::Current(0))?;
-        let eof = f.seek(SeekFrom::End(0))?;
-        f.seek(SeekFrom::Start(here))?;
+        Ok(plugin)
+    }
 
-        while here != eof {
-            plugin.add_record(Tes3Record::read_lazy(&mut f)?)?;
-            here = f.seek(SeekFrom::Current(0))?;
+    /// Gets the save game screenshot if any is present
+    pub fn screenshot(&self) -> Option<Screenshot> {
+        if self.screen_data.len() != SCREENSHOT_SIZE * SCREENSHOT_SIZE * 4 {
+            return None;
         }
 
-        Ok(plugin)
+        // pixels are stored as 32-bit BGRA
+        let data = self
+            .screen_data
+            .chunks_exact(4)
+            .flat_map(|p| [p[2], p[1], p[0]])
+            .collect();
+        Some(Screenshot {
+            width: SCREENSHOT_SIZE as u32,
+            height: SCREENSHOT_SIZE as u32,
+            data,
+        })
+    }
+
+    /// Gets the magic effect definition for the given effect type if this plugin contains one
+    ///
+    /// # Errors
+    ///
+    /// Fails if the matching record contains invalid data.
+    pub fn get_magic_effect(
+        &self,
+        effect_type: MagicEffectType,
+    ) -> Result<Option<MagicEffect>, TesError> {
+        if let Some(records) = self.get_records_by_type(MagicEffect::RECORD_TYPE) {
+            for record in records {
+                if MagicEffect::read_effect_type(&record)? == effect_type {
+                    return Ok(Some(MagicEffect::read(&record)?));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Sets a game setting, adding it to the plugin if it isn't already present
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value is not of the type indicated by the setting's ID.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tesutil::*;
+    /// use tesutil::tes3::*;
+    ///
+    /// # fn main() -> Result<(), TesError> {
+    /// let mut plugin = Tes3Plugin::new(String::from("test"), String::from("GMST fixes"))?;
+    /// plugin.set_gmst("iDispKilling", -50)?;
+    /// plugin.set_gmst("sLevelUpMsg", "You have leveled up!")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_gmst<V: Into<GameSettingValue>>(
+        &mut self,
+        id: &str,
+        value: V,
+    ) -> Result<(), TesError> {
+        let setting = GameSetting::new(String::from(id), value.into())?;
+        if let Some(mut record) = self.get_record_with_type_mut(id, GameSetting::RECORD_TYPE)? {
+            return setting.write(&mut record);
+        }
+
+        let mut record = Tes3Record::new(GameSetting::RECORD_TYPE);
+        setting.write(&mut record)?;
+        self.add_record(record)
+    }
+
+    /// Finds a record by ID and type and returns a mutable reference
+    ///
+    /// # Errors
+    ///
+    /// Fails if the record hasn't been decoded yet and its data is invalid.
+    pub fn get_record_with_type_mut(
+        &mut self,
+        id: &str,
+        name: &[u8; 4],
+    ) -> Result<Option<RecordMut<Tes3Record>>, TesError> {
+        self.modify_record(id, name)
+    }
+}
+
+impl Plugin for Tes3Plugin {
+    /// Read a plugin file from the provided reader
+    ///
+    /// Reads a plugin from any type that implements [`Read`] or a mutable reference to such a type.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the format of the plugin data is invalid or if an I/O error
+    /// occurs while reading the plugin data.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tesutil::tes3::*;
+    /// use tesutil::Plugin;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let buf: Vec<u8> = vec![/* raw plugin data */];
+    /// let plugin = Tes3Plugin::read(&mut &buf[..])?;
+    /// println!("Plugin info: author {}, description {}", plugin.author(), plugin.description());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
+    fn read<T: Read + Seek>(f: T) -> Result<Tes3Plugin, TesError> {
+        Tes3Plugin::read_with_policies(f, &[])
+    }
+
+    /// Reads a plugin using an index of its record IDs
+    ///
+    /// Records whose IDs are in the index, and records of types that don't have IDs, aren't
+    /// decoded until they're first accessed. Errors in those records are reported then instead of
+    /// when the plugin is read.
+    fn read_indexed<T: Read + Seek>(
+        mut f: T,
+        index: Option<&RecordIndex>,
+    ) -> Result<(Tes3Plugin, RecordIndex), TesError> {
+        let mut plugin = Tes3Plugin::read_header(&mut f)?;
+        let index = plugin.read_records(f, index)?;
+        Ok((plugin, index))
     }
 
     /// Returns whether this plugin is a master on which other plugins can depend
@@ -897,6 +1529,11 @@ impl Plugin for Tes3Plugin {
         header.write(&mut f)?;
 
         for record in self.records.iter() {
+            let handler = RECORD_REGISTRY.get(record.read().unwrap().name());
+            if let Some(handler) = handler {
+                handler.serialize(&mut record.write().unwrap())?;
+            }
+
             record.read().unwrap().write(&mut f)?;
         }
 
@@ -926,6 +1563,41 @@ mod tests {
         assert_eq!(plugin.records.len(), 8);
     }
 
+    #[test]
+    fn read_indexed_plugin() {
+        let (plugin, index) = Tes3Plugin::read_indexed(Cursor::new(TEST_PLUGIN), None).unwrap();
+        assert_eq!(plugin.load_stats().total().lazy, 0);
+        assert!(!index.is_empty());
+
+        let (indexed, new_index) =
+            Tes3Plugin::read_indexed(Cursor::new(TEST_PLUGIN), Some(&index)).unwrap();
+        assert_eq!(new_index, index);
+        assert!(indexed.load_stats().total().lazy > 0);
+        for (offset, record_type, id) in index.iter() {
+            let record = indexed
+                .get_record_with_type(id, record_type)
+                .unwrap()
+                .unwrap();
+            assert_eq!(record.span().unwrap().offset, offset);
+            assert_eq!(record.id(), Some(id));
+        }
+    }
+
+    #[test]
+    fn set_gmst() {
+        let mut plugin = Tes3Plugin::new(String::from("test"), String::from("GMST fixes")).unwrap();
+        plugin.set_gmst("iDispKilling", -50).unwrap();
+        plugin.set_gmst("iDispKilling", -40).unwrap();
+        plugin.set_gmst("sLevelUpMsg", "Leveled up").unwrap();
+        assert!(plugin.set_gmst("fFatigueBase", 5).is_err());
+
+        assert_eq!(plugin.records.len(), 2);
+        let setting: GameSetting = plugin.get("iDispKilling").unwrap().unwrap();
+        assert_eq!(setting.get_int(), Some(-40));
+        let setting: GameSetting = plugin.get("sLevelUpMsg").unwrap().unwrap();
+        assert_eq!(setting.get_string(), Some("Leveled up"));
+    }
+
     #[test]
     fn write_plugin() {
         let buf: Vec<u8> = Vec::with_capacity(EXPECTED_PLUGIN.len());
@@ -951,6 +1623,170 @@ mod tests {
         assert_eq!(cursor.into_inner(), EXPECTED_PLUGIN);
     }
 
+    struct StampHandler;
+
+    impl RecordHandler<Tes3Record> for StampHandler {
+        fn parse(&self, record: &mut Tes3Record) -> Result<(), TesError> {
+            if record.iter().any(|f| f.name() == b"STMP") {
+                Ok(())
+            } else {
+                Err(decode_failed("Custom record is missing its stamp"))
+            }
+        }
+
+        fn serialize(&self, record: &mut Tes3Record) -> Result<(), TesError> {
+            if !record.iter().any(|f| f.name() == b"STMP") {
+                record.add_field(Tes3Field::new_u32(b"STMP", 1));
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn custom_record_handler() {
+        RECORD_REGISTRY.register(b"XTST", StampHandler);
+
+        let mut plugin = Tes3Plugin::new(String::new(), String::new()).unwrap();
+        let mut record = Tes3Record::new(b"XTST");
+        record.add_field(Tes3Field::new_zstring(b"NAME", String::from("custom")).unwrap());
+        plugin.add_record(record).unwrap();
+
+        let mut cursor = Cursor::new(vec![]);
+        plugin.write(&mut cursor).unwrap();
+        cursor.set_position(0);
+        let plugin = Tes3Plugin::read(cursor).unwrap();
+
+        RECORD_REGISTRY.unregister(b"XTST");
+
+        let record = plugin.get_record("custom").unwrap().unwrap();
+        assert!(record.iter().any(|f| f.name() == b"STMP"));
+    }
+
+    fn named_record(name: &[u8; 4], id: &str, value: i32) -> Tes3Record {
+        let mut record = Tes3Record::new(name);
+        record.add_field(Tes3Field::new_zstring(b"NAME", String::from(id)).unwrap());
+        record.add_field(Tes3Field::new_i32(b"INDX", value));
+        record
+    }
+
+    #[test]
+    fn duplicate_ids() {
+        let mut plugin = Tes3Plugin::new(String::new(), String::new()).unwrap();
+        plugin.add_record(named_record(b"CREC", "rat", 1)).unwrap();
+        plugin.add_record(named_record(b"CREC", "rat", 2)).unwrap();
+        assert_eq!(
+            plugin.get_records_with_type("rat", b"CREC").unwrap().len(),
+            2
+        );
+        let record = plugin.get_record("rat").unwrap().unwrap();
+        assert_eq!(record.iter().nth(1).unwrap().get_i32().unwrap(), 2);
+        drop(record);
+
+        plugin
+            .add_record(named_record(b"NPC_", "fargoth", 1))
+            .unwrap();
+        plugin
+            .add_record(named_record(b"NPC_", "fargoth", 2))
+            .unwrap();
+        assert_eq!(
+            plugin
+                .get_records_with_type("fargoth", b"NPC_")
+                .unwrap()
+                .len(),
+            1
+        );
+
+        plugin.set_duplicate_id_policy(b"NPC_", DuplicateIdPolicy::Reject);
+        assert!(matches!(
+            plugin.add_record(named_record(b"NPC_", "fargoth", 3)),
+            Err(TesError::DuplicateId(_))
+        ));
+
+        plugin
+            .add_record(named_record(b"CREA", "fargoth", 1))
+            .unwrap();
+        match plugin.get_record("fargoth") {
+            Err(TesError::AmbiguousId { record_types, .. }) => {
+                assert_eq!(record_types, ["CREA", "NPC_"])
+            }
+            _ => panic!("Expected an ambiguous ID"),
+        }
+        assert!(plugin
+            .get_record_with_type("fargoth", b"CREA")
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn journal_and_revert() {
+        let value = |plugin: &Tes3Plugin, id| {
+            let record = plugin.get_record_with_type(id, b"NPC_").unwrap().unwrap();
+            record.iter().nth(1).unwrap().get_i32().unwrap()
+        };
+
+        let mut plugin = Tes3Plugin::new(String::new(), String::new()).unwrap();
+        plugin
+            .add_record(named_record(b"NPC_", "fargoth", 1))
+            .unwrap();
+        plugin
+            .add_record(named_record(b"NPC_", "hrisskar", 1))
+            .unwrap();
+        assert!(plugin.journal().is_none());
+
+        plugin.start_journal();
+        plugin
+            .add_record(named_record(b"NPC_", "fargoth", 2))
+            .unwrap();
+        plugin
+            .add_record(named_record(b"NPC_", "arrille", 1))
+            .unwrap();
+        {
+            let mut record = plugin.get_record_mut("hrisskar").unwrap().unwrap();
+            *record = named_record(b"NPC_", "hrisskar", 5);
+        }
+        assert!(plugin.remove_record("arrille", b"NPC_"));
+        assert!(!plugin.remove_record("arrille", b"NPC_"));
+
+        let journal = plugin.journal().unwrap();
+        assert_eq!(
+            journal
+                .iter()
+                .map(|m| (m.kind(), m.id().unwrap()))
+                .collect::<Vec<_>>(),
+            [
+                (MutationKind::Added, "fargoth"),
+                (MutationKind::Added, "arrille"),
+                (MutationKind::Modified, "hrisskar"),
+                (MutationKind::Removed, "arrille"),
+            ]
+        );
+        assert_eq!(plugin.records.len(), 3);
+        assert_eq!(value(&plugin, "fargoth"), 2);
+        assert_eq!(value(&plugin, "hrisskar"), 5);
+
+        assert!(plugin.revert(3));
+        assert_eq!(value(&plugin, "arrille"), 1);
+        assert!(plugin.revert(2));
+        assert_eq!(value(&plugin, "hrisskar"), 1);
+        assert!(plugin.revert(0));
+        assert_eq!(value(&plugin, "fargoth"), 1);
+        assert_eq!(plugin.records.len(), 3);
+        assert!(!plugin.revert(1));
+
+        // a record that's only read through a mutable borrow is never copied
+        {
+            let record = plugin.get_record_mut("hrisskar").unwrap().unwrap();
+            assert_eq!(record.name(), b"NPC_");
+        }
+
+        let journal = plugin.stop_journal().unwrap();
+        assert_eq!(journal.len(), 2);
+        assert_eq!(journal.iter().next().unwrap().id(), Some("arrille"));
+        assert!(!journal.iter().nth(1).unwrap().is_changed());
+        assert!(!plugin.revert(0));
+    }
+
     #[test]
     fn fetch_record() {
         let cursor = Cursor::new(TEST_PLUGIN);
@@ -970,4 +1806,36 @@ mod tests {
             }
         }
     }
+
+    /// Round-trips every magic effect in a real Morrowind.esm
+    ///
+    /// Run with `TESUTIL_MORROWIND_ESM=path/to/Morrowind.esm cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn round_trip_vanilla_forms() {
+        let path = std::env::var("TESUTIL_MORROWIND_ESM")
+            .expect("TESUTIL_MORROWIND_ESM must be set to the path of Morrowind.esm");
+        let plugin = Tes3Plugin::load_file(path).unwrap();
+
+        let names = |record: &Tes3Record| -> Vec<String> {
+            record
+                .iter()
+                .map(|f| String::from(f.name_as_str()))
+                .collect()
+        };
+        for record in plugin
+            .get_records_by_type(MagicEffect::RECORD_TYPE)
+            .unwrap()
+        {
+            let effect = MagicEffect::read(&record).unwrap();
+            let mut written = Tes3Record::new(MagicEffect::RECORD_TYPE);
+            effect.write(&mut written).unwrap();
+            assert_eq!(
+                names(&written),
+                names(&record),
+                "Field order of magic effect {:?} changed",
+                effect.effect_type()
+            );
+        }
+    }
 }
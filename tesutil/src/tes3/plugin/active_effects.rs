@@ -1,11 +1,14 @@
 use std::convert::TryFrom;
 
 use super::{Tes3Field, Tes3Record};
-use crate::{decode_failed, decode_failed_because, read_string, Field, Form, Record, TesError};
+use crate::{
+    check_range, check_size, decode_failed, decode_failed_because, make_str_vec, read_string,
+    Field, Form, Record, TesError,
+};
 
-use binrw::BinReaderExt;
+use binrw::{BinReaderExt, BinWriterExt};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 
 /// Type of magical spell/item an effect originated from
 #[derive(Debug, Copy, Clone, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
@@ -17,15 +20,25 @@ pub enum MagicType {
 }
 
 /// An associated item of an active magical effect
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EffectAssociatedItem {
     unknown1: i32,
     unknown2: u8,
     id: String,
 }
 
+impl EffectAssociatedItem {
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+// the contents of the NAM0 and XNAM end markers are unknown, so we preserve whatever we read and
+// use a single null byte for new effects and spells
+const DEFAULT_END_MARKER: [u8; 1] = [0];
+
 /// An active effect of a magical spell
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ActiveEffect {
     // NPDT
     affected_actor: String,
@@ -42,13 +55,60 @@ pub struct ActiveEffect {
     // VNAM
     // TODO: figure out what's in here
     vampirism: Vec<u8>,
+    // NAM0
+    end_marker: Vec<u8>,
 }
 
 impl ActiveEffect {
+    /// Creates a new active effect
+    ///
+    /// `index` is the index of the effect in the spell's list of effects.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `affected_actor` is longer than [`ID_LENGTH`] or `seconds_active` is negative.
+    ///
+    /// [`ID_LENGTH`]: constant.ID_LENGTH.html
+    pub fn new(
+        affected_actor: &str,
+        index: i32,
+        magnitude: i32,
+        seconds_active: f32,
+    ) -> Result<ActiveEffect, TesError> {
+        let mut effect = ActiveEffect {
+            affected_actor: String::new(),
+            index,
+            unknown1: [0; 4],
+            magnitude,
+            seconds_active: 0.,
+            unknown2: [0; 8],
+            associated_items: vec![],
+            summon: None,
+            vampirism: vec![],
+            end_marker: DEFAULT_END_MARKER.to_vec(),
+        };
+        effect.set_affected_actor(String::from(affected_actor))?;
+        effect.set_seconds_active(seconds_active)?;
+        Ok(effect)
+    }
+
     pub fn affected_actor(&self) -> &str {
         self.affected_actor.as_str()
     }
 
+    /// Sets the ID of the actor affected by this effect
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ID is longer than [`ID_LENGTH`].
+    ///
+    /// [`ID_LENGTH`]: constant.ID_LENGTH.html
+    pub fn set_affected_actor(&mut self, affected_actor: String) -> Result<(), TesError> {
+        check_size(&affected_actor, ID_LENGTH, "Affected actor ID too long")?;
+        self.affected_actor = affected_actor;
+        Ok(())
+    }
+
     pub fn index(&self) -> i32 {
         self.index
     }
@@ -57,13 +117,78 @@ impl ActiveEffect {
         self.magnitude
     }
 
+    pub fn set_magnitude(&mut self, magnitude: i32) {
+        self.magnitude = magnitude;
+    }
+
     pub fn seconds_active(&self) -> f32 {
         self.seconds_active
     }
+
+    /// Sets how long this effect has been active
+    ///
+    /// The effect ends once it's been active for the duration of the spell effect it came from, so
+    /// decreasing this extends the effect and increasing it shortens the effect.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `seconds_active` is negative.
+    pub fn set_seconds_active(&mut self, seconds_active: f32) -> Result<(), TesError> {
+        check_range(
+            seconds_active,
+            0.,
+            f32::MAX,
+            "Seconds active must not be negative",
+        )?;
+        self.seconds_active = seconds_active;
+        Ok(())
+    }
+
+    pub fn associated_items(&self) -> impl Iterator<Item = &EffectAssociatedItem> + '_ {
+        self.associated_items.iter()
+    }
+
+    pub fn summon(&self) -> Option<&str> {
+        self.summon.as_deref()
+    }
+
+    fn write(&self, record: &mut Tes3Record) -> Result<(), TesError> {
+        let mut buf = vec![];
+        let mut writer = Cursor::new(&mut buf);
+        writer.write_all(&make_str_vec(&self.affected_actor, ID_LENGTH))?;
+        writer.write_le(&self.index)?;
+        writer.write_all(&self.unknown1)?;
+        writer.write_le(&self.magnitude)?;
+        writer.write_le(&self.seconds_active)?;
+        writer.write_all(&self.unknown2)?;
+        record.add_field(Tes3Field::new(b"NPDT", buf)?);
+
+        for item in &self.associated_items {
+            let mut buf = vec![];
+            let mut writer = Cursor::new(&mut buf);
+            writer.write_le(&item.unknown1)?;
+            writer.write_le(&item.unknown2)?;
+            writer.write_all(&make_str_vec(&item.id, ASSOCIATED_ID_LENGTH))?;
+            record.add_field(Tes3Field::new(b"INAM", buf)?);
+        }
+
+        if let Some(ref summon) = self.summon {
+            let mut buf = vec![0; 4];
+            buf.extend(make_str_vec(summon, ID_LENGTH));
+            record.add_field(Tes3Field::new(b"CNAM", buf)?);
+        }
+
+        if !self.vampirism.is_empty() {
+            record.add_field(Tes3Field::new(b"VNAM", self.vampirism.clone())?);
+        }
+
+        record.add_field(Tes3Field::new(b"NAM0", self.end_marker.clone())?);
+        Ok(())
+    }
 }
 
 /// An active magical spell
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ActiveSpell {
     // NAME
     index: i32,
@@ -77,16 +202,116 @@ pub struct ActiveSpell {
     // TNAM
     target: Option<String>,
     effects: Vec<ActiveEffect>,
+    // XNAM
+    end_marker: Vec<u8>,
 }
 
 impl ActiveSpell {
+    /// Creates a new active spell with no effects
+    ///
+    /// `id` is the ID of the spell, enchantment, or potion, `caster` is the ID of the actor that
+    /// cast it, and `source` is the ID of the item it came from, if any.
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of the IDs are longer than [`ID_LENGTH`].
+    ///
+    /// [`ID_LENGTH`]: constant.ID_LENGTH.html
+    pub fn new(
+        magic_type: MagicType,
+        id: &str,
+        caster: &str,
+        source: &str,
+    ) -> Result<ActiveSpell, TesError> {
+        check_size(id, ID_LENGTH, "Active spell ID too long")?;
+        check_size(caster, ID_LENGTH, "Active spell caster ID too long")?;
+        check_size(source, ID_LENGTH, "Active spell source ID too long")?;
+        Ok(ActiveSpell {
+            index: 0,
+            magic_type,
+            id: String::from(id),
+            unknown1: [0; 16],
+            caster: String::from(caster),
+            source: String::from(source),
+            unknown2: [0; 44],
+            target: None,
+            effects: vec![],
+            end_marker: DEFAULT_END_MARKER.to_vec(),
+        })
+    }
+
+    /// Gets this spell's index in the save's list of active spells
+    pub fn index(&self) -> i32 {
+        self.index
+    }
+
+    pub fn magic_type(&self) -> MagicType {
+        self.magic_type
+    }
+
     pub fn id(&self) -> &str {
         self.id.as_str()
     }
 
+    pub fn caster(&self) -> &str {
+        self.caster.as_str()
+    }
+
+    pub fn source(&self) -> &str {
+        self.source.as_str()
+    }
+
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    pub fn set_target(&mut self, target: Option<String>) {
+        self.target = target;
+    }
+
     pub fn effects(&self) -> impl Iterator<Item = &ActiveEffect> + '_ {
         self.effects.iter()
     }
+
+    pub fn effects_mut(&mut self) -> impl Iterator<Item = &mut ActiveEffect> + '_ {
+        self.effects.iter_mut()
+    }
+
+    pub fn add_effect(&mut self, effect: ActiveEffect) {
+        self.effects.push(effect);
+    }
+
+    /// Removes the effect with the given index in the spell's list of effects, if present
+    pub fn remove_effect(&mut self, index: i32) -> Option<ActiveEffect> {
+        let position = self.effects.iter().position(|e| e.index == index)?;
+        Some(self.effects.remove(position))
+    }
+
+    fn write(&self, record: &mut Tes3Record) -> Result<(), TesError> {
+        record.add_field(Tes3Field::new_i32(b"NAME", self.index));
+
+        let mut buf = vec![];
+        let mut writer = Cursor::new(&mut buf);
+        let magic_type: u8 = self.magic_type.into();
+        writer.write_le(&(magic_type as u32))?;
+        writer.write_all(&make_str_vec(&self.id, ID_LENGTH))?;
+        writer.write_all(&self.unknown1)?;
+        writer.write_all(&make_str_vec(&self.caster, ID_LENGTH))?;
+        writer.write_all(&make_str_vec(&self.source, ID_LENGTH))?;
+        writer.write_all(&self.unknown2)?;
+        record.add_field(Tes3Field::new(b"SPDT", buf)?);
+
+        if let Some(ref target) = self.target {
+            record.add_field(Tes3Field::new_string(b"TNAM", target.clone())?);
+        }
+
+        for effect in &self.effects {
+            effect.write(record)?;
+        }
+
+        record.add_field(Tes3Field::new(b"XNAM", self.end_marker.clone())?);
+        Ok(())
+    }
 }
 
 /// Maximum length of an ID on an ActiveEffect
@@ -96,7 +321,7 @@ pub const ID_LENGTH: usize = 32;
 pub const ASSOCIATED_ID_LENGTH: usize = 35;
 
 /// All active magical spells in the save game
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ActiveSpellList(Vec<ActiveSpell>);
 
 impl Form for ActiveSpellList {
@@ -122,6 +347,7 @@ impl Form for ActiveSpellList {
                     unknown2: [0; 44],
                     target: None,
                     effects: vec![],
+                    end_marker: vec![],
                 }),
                 b"SPDT" => {
                     let spell = list
@@ -168,6 +394,7 @@ impl Form for ActiveSpellList {
                         associated_items: vec![],
                         summon: None,
                         vampirism: vec![],
+                        end_marker: vec![],
                     };
 
                     spell.effects.push(effect);
@@ -213,8 +440,21 @@ impl Form for ActiveSpellList {
                         .ok_or_else(|| decode_failed("Orphaned VNAM in SPLM"))?;
                     effect.vampirism = field.get().to_vec();
                 }
-                b"NAM0" => (), // end of effect
-                b"XNAM" => (), // end of spell
+                b"NAM0" => {
+                    // end of effect
+                    list.0
+                        .last_mut()
+                        .and_then(|s| s.effects.last_mut())
+                        .ok_or_else(|| decode_failed("Orphaned NAM0 in SPLM"))?
+                        .end_marker = field.get().to_vec();
+                }
+                b"XNAM" => {
+                    // end of spell
+                    list.0
+                        .last_mut()
+                        .ok_or_else(|| decode_failed("Orphaned XNAM in SPLM"))?
+                        .end_marker = field.get().to_vec();
+                }
                 _ => {
                     return Err(decode_failed(format!(
                         "Unexpected field {}",
@@ -227,8 +467,15 @@ impl Form for ActiveSpellList {
         Ok(list)
     }
 
-    fn write(&self, _: &mut Self::Record) -> Result<(), TesError> {
-        unimplemented!()
+    fn write(&self, record: &mut Self::Record) -> Result<(), TesError> {
+        ActiveSpellList::assert(record)?;
+
+        record.clear();
+        for spell in &self.0 {
+            spell.write(record)?;
+        }
+
+        Ok(())
     }
 }
 
@@ -242,6 +489,10 @@ impl IntoIterator for ActiveSpellList {
 }
 
 impl ActiveSpellList {
+    pub fn new() -> ActiveSpellList {
+        ActiveSpellList(vec![])
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &ActiveSpell> + '_ {
         self.0.iter()
     }
@@ -249,4 +500,95 @@ impl ActiveSpellList {
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut ActiveSpell> + '_ {
         self.0.iter_mut()
     }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Finds an active spell by its index
+    pub fn get(&self, index: i32) -> Option<&ActiveSpell> {
+        self.0.iter().find(|s| s.index == index)
+    }
+
+    /// Finds an active spell by its index and returns a mutable reference
+    pub fn get_mut(&mut self, index: i32) -> Option<&mut ActiveSpell> {
+        self.0.iter_mut().find(|s| s.index == index)
+    }
+
+    /// Adds a spell to the list, returning the index assigned to it
+    pub fn add_spell(&mut self, mut spell: ActiveSpell) -> i32 {
+        spell.index = self.0.iter().map(|s| s.index + 1).max().unwrap_or(0);
+        let index = spell.index;
+        self.0.push(spell);
+        index
+    }
+
+    /// Removes the spell with the given index, if present
+    pub fn remove_spell(&mut self, index: i32) -> Option<ActiveSpell> {
+        let position = self.0.iter().position(|s| s.index == index)?;
+        Some(self.0.remove(position))
+    }
+
+    /// Removes all active instances of a spell, enchantment, or potion by ID
+    ///
+    /// Returns the number of active spells that were removed.
+    pub fn remove_spells_by_id(&mut self, id: &str) -> usize {
+        let count = self.0.len();
+        self.0.retain(|s| !s.id.eq_ignore_ascii_case(id));
+        count - self.0.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_write_active_spells() {
+        let mut list = ActiveSpellList::new();
+        let mut spell = ActiveSpell::new(MagicType::Spell, "fireball", "player", "").unwrap();
+        spell.set_target(Some(String::from("fargoth")));
+        spell.add_effect(ActiveEffect::new("fargoth", 0, 15, 2.5).unwrap());
+        spell.add_effect(ActiveEffect::new("fargoth", 1, 5, 2.5).unwrap());
+        assert_eq!(list.add_spell(spell), 0);
+        let potion =
+            ActiveSpell::new(MagicType::Potion, "p_restore_health_s", "player", "").unwrap();
+        assert_eq!(list.add_spell(potion), 1);
+
+        assert!(ActiveEffect::new("player", 0, 10, -1.).is_err());
+        assert!(ActiveSpell::new(
+            MagicType::Spell,
+            "an ID that is much too long to fit in the field",
+            "player",
+            ""
+        )
+        .is_err());
+
+        let spell = list.get_mut(0).unwrap();
+        assert!(spell.remove_effect(1).is_some());
+        for effect in spell.effects_mut() {
+            effect.set_seconds_active(1.).unwrap();
+        }
+
+        let mut record = Tes3Record::new(ActiveSpellList::RECORD_TYPE);
+        list.write(&mut record).unwrap();
+        let mut list = ActiveSpellList::read(&record).unwrap();
+
+        assert_eq!(list.len(), 2);
+        let spell = list.get(0).unwrap();
+        assert_eq!(spell.id(), "fireball");
+        assert_eq!(spell.target(), Some("fargoth"));
+        let effects: Vec<_> = spell.effects().collect();
+        assert_eq!(effects.len(), 1);
+        assert_eq!(effects[0].magnitude(), 15);
+        assert_eq!(effects[0].seconds_active(), 1.);
+        assert_eq!(list.get(1).unwrap().magic_type(), MagicType::Potion);
+
+        assert_eq!(list.remove_spells_by_id("P_Restore_Health_S"), 1);
+        assert!(list.get(1).is_none());
+    }
 }
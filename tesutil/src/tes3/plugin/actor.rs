@@ -1,7 +1,5 @@
 use crate::tes3::{Tes3Field, Tes3Record};
-use crate::{
-    decode_failed, decode_failed_because, make_str_vec, read_string, write_str, Field, TesError,
-};
+use crate::{decode_failed, decode_failed_because, read_string, write_str, Field, TesError};
 use binrw::{binrw, BinReaderExt, BinWriterExt};
 use bitflags::bitflags;
 use std::io::{Cursor, Read, Write};
@@ -52,6 +50,41 @@ pub struct Destination {
     cell_name: Option<String>,
 }
 
+impl Destination {
+    /// Creates a destination
+    ///
+    /// `cell_name` is the name of the destination cell if it's an interior; exterior destinations
+    /// have no name, as the cell is determined by the position.
+    pub fn new(
+        position: (f32, f32, f32),
+        rotation: (f32, f32, f32),
+        cell_name: Option<String>,
+    ) -> Destination {
+        Destination {
+            position,
+            rotation,
+            cell_name,
+        }
+    }
+
+    pub fn position(&self) -> (f32, f32, f32) {
+        self.position
+    }
+
+    pub fn rotation(&self) -> (f32, f32, f32) {
+        self.rotation
+    }
+
+    /// Gets the name of the destination cell, or `None` if the destination is in an exterior
+    pub fn cell_name(&self) -> Option<&str> {
+        self.cell_name.as_deref()
+    }
+
+    pub fn set_cell_name(&mut self, cell_name: Option<String>) {
+        self.cell_name = cell_name;
+    }
+}
+
 /// Actor's AI settings
 #[binrw]
 #[derive(Debug, Default)]
@@ -427,14 +460,7 @@ pub trait Actor: ActorState {
 
     /// Write the actor's spells to the provided record
     fn write_spells(&self, record: &mut Tes3Record) -> Result<(), TesError> {
-        for id in self.iter_spells() {
-            record.add_field(Tes3Field::new(
-                b"NPCS",
-                make_str_vec(id, ACTOR_STRING_LENGTH),
-            )?);
-        }
-
-        Ok(())
+        record.set_string_list(b"NPCS", self.iter_spells(), ACTOR_STRING_LENGTH)
     }
 
     /// Write the actor's travel destinations to the provided record
@@ -0,0 +1,264 @@
+use std::io::Cursor;
+
+use crate::tes3::{BodyPartMapping, Enchantable, Item, Tes3Field, Tes3Record};
+use crate::{decode_failed, Field, Form, Record, TesError};
+use binrw::{binrw, BinReaderExt, BinWriterExt};
+
+#[binrw]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[repr(u32)]
+#[brw(repr = u32)]
+pub enum ArmorType {
+    #[default]
+    Helmet,
+    Cuirass,
+    LeftPauldron,
+    RightPauldron,
+    Greaves,
+    Boots,
+    LeftGauntlet,
+    RightGauntlet,
+    Shield,
+    LeftBracer,
+    RightBracer,
+}
+
+#[binrw]
+#[derive(Debug, Default)]
+pub struct ArmorData {
+    pub armor_type: ArmorType,
+    pub weight: f32,
+    pub value: u32,
+    pub health: u32,
+    pub enchantment_points: u32,
+    pub armor_rating: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct Armor {
+    id: String,
+    model: String,
+    name: Option<String>,
+    script: Option<String>,
+    pub data: ArmorData,
+    icon: Option<String>,
+    pub body_parts: Vec<BodyPartMapping>,
+    enchantment: Option<String>,
+}
+
+impl Armor {
+    /// Creates a new piece of armor of the given type
+    pub fn new(id: String, armor_type: ArmorType) -> Armor {
+        Armor {
+            id,
+            data: ArmorData {
+                armor_type,
+                ..ArmorData::default()
+            },
+            ..Armor::default()
+        }
+    }
+}
+
+impl Item for Armor {
+    fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(self.model.as_str())
+    }
+
+    fn set_model(&mut self, model: Option<String>) {
+        self.model = model.unwrap_or_else(String::new);
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn set_name(&mut self, name: Option<String>) {
+        self.name = name;
+    }
+
+    fn weight(&self) -> f32 {
+        self.data.weight
+    }
+
+    fn set_weight(&mut self, weight: f32) {
+        self.data.weight = weight;
+    }
+
+    fn value(&self) -> u32 {
+        self.data.value
+    }
+
+    fn set_value(&mut self, value: u32) {
+        self.data.value = value;
+    }
+
+    fn script(&self) -> Option<&str> {
+        self.script.as_deref()
+    }
+
+    fn set_script(&mut self, script: Option<String>) {
+        self.script = script;
+    }
+
+    fn icon(&self) -> Option<&str> {
+        self.icon.as_deref()
+    }
+
+    fn set_icon(&mut self, icon: Option<String>) {
+        self.icon = icon;
+    }
+}
+
+impl Enchantable for Armor {
+    fn enchantment(&self) -> Option<&str> {
+        self.enchantment.as_deref()
+    }
+
+    fn set_enchantment(&mut self, enchantment: Option<String>) {
+        self.enchantment = enchantment;
+    }
+
+    fn enchantment_points(&self) -> u32 {
+        self.data.enchantment_points
+    }
+
+    fn set_enchantment_points(&mut self, enchantment_points: u32) {
+        self.data.enchantment_points = enchantment_points;
+    }
+}
+
+impl Form for Armor {
+    type Field = Tes3Field;
+    type Record = Tes3Record;
+    const RECORD_TYPE: &'static [u8; 4] = b"ARMO";
+
+    fn read(record: &Self::Record) -> Result<Self, TesError> {
+        Armor::assert(record)?;
+
+        let mut armor = Armor::default();
+        for field in record.iter() {
+            if BodyPartMapping::read_field(&mut armor.body_parts, field)? {
+                continue;
+            }
+
+            match field.name() {
+                b"NAME" => armor.id = String::from(field.get_zstring()?),
+                b"MODL" => armor.model = String::from(field.get_zstring()?),
+                b"FNAM" => armor.name = Some(String::from(field.get_zstring()?)),
+                b"SCRI" => armor.script = Some(String::from(field.get_zstring()?)),
+                b"AODT" => armor.data = field.reader().read_le()?,
+                b"ITEX" => armor.icon = Some(String::from(field.get_zstring()?)),
+                b"ENAM" => armor.enchantment = Some(String::from(field.get_zstring()?)),
+                _ => {
+                    return Err(decode_failed(format!(
+                        "Unexpected {} field in ARMO record",
+                        field.name_as_str()
+                    )))
+                }
+            }
+        }
+
+        Ok(armor)
+    }
+
+    fn write(&self, record: &mut Self::Record) -> Result<(), TesError> {
+        Armor::assert(record)?;
+
+        record.clear();
+
+        record.add_field(Tes3Field::new_zstring(b"NAME", self.id.clone())?);
+        record.add_field(Tes3Field::new_zstring(b"MODL", self.model.clone())?);
+        if let Some(ref name) = self.name {
+            record.add_field(Tes3Field::new_zstring(b"FNAM", name.clone())?);
+        }
+        if let Some(ref script) = self.script {
+            record.add_field(Tes3Field::new_zstring(b"SCRI", script.clone())?);
+        }
+
+        let mut buf = Vec::with_capacity(24);
+        let mut cursor = Cursor::new(&mut buf);
+        cursor.write_le(&self.data)?;
+        record.add_field(Tes3Field::new(b"AODT", buf)?);
+
+        if let Some(ref icon) = self.icon {
+            record.add_field(Tes3Field::new_zstring(b"ITEX", icon.clone())?);
+        }
+        BodyPartMapping::write_all(&self.body_parts, record)?;
+        if let Some(ref enchantment) = self.enchantment {
+            record.add_field(Tes3Field::new_zstring(b"ENAM", enchantment.clone())?);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tes3::BipedObject;
+
+    static ARMOR_RECORD: &[u8] = include_bytes!("test/armo_record.bin");
+
+    #[test]
+    fn test_load() {
+        let mut record_ref = ARMOR_RECORD.as_ref();
+        let cursor = Cursor::new(&mut record_ref);
+        let record = Tes3Record::read(cursor).unwrap();
+        let armor = Armor::read(&record).unwrap();
+        assert_eq!(armor.id(), "netch_leather_boiled_helm");
+        assert_eq!(armor.name(), Some("Boiled Netch Leather Helm"));
+        assert_eq!(armor.data.armor_type, ArmorType::Helmet);
+        assert_eq!(armor.data.weight, 3.);
+        assert_eq!(armor.data.value, 25);
+        assert_eq!(armor.data.health, 250);
+        assert_eq!(armor.data.enchantment_points, 10);
+        assert_eq!(armor.data.armor_rating, 8);
+        assert_eq!(armor.icon(), Some(r"a\TX_Netch_boiled_helm.tga"));
+        assert_eq!(armor.enchantment(), None);
+        assert_eq!(
+            armor.body_parts,
+            [BodyPartMapping::new(
+                BipedObject::Head,
+                String::from("A_Netch_Boiled_Helmet")
+            )]
+        );
+    }
+
+    #[test]
+    fn test_write() {
+        let mut record_ref = ARMOR_RECORD.as_ref();
+        let cursor = Cursor::new(&mut record_ref);
+        let record = Tes3Record::read(cursor).unwrap();
+        let mut armor = Armor::read(&record).unwrap();
+
+        let mut new_record = Tes3Record::new(b"ARMO");
+        armor.write(&mut new_record).unwrap();
+        assert_eq!(new_record.len(), record.len());
+        for (new_field, field) in new_record.iter().zip(record.iter()) {
+            assert_eq!(new_field.name(), field.name());
+            assert_eq!(new_field.get(), field.get());
+        }
+
+        armor.set_enchantment(Some(String::from("shield_en")));
+        armor.write(&mut new_record).unwrap();
+        let armor = Armor::read(&new_record).unwrap();
+        assert_eq!(armor.enchantment(), Some("shield_en"));
+    }
+
+    #[test]
+    fn orphaned_body_part() {
+        let mut record = Tes3Record::new(b"ARMO");
+        record.add_field(Tes3Field::new_zstring(b"NAME", String::from("helm")).unwrap());
+        record.add_field(Tes3Field::new_zstring(b"BNAM", String::from("part")).unwrap());
+        assert!(Armor::read(&record).is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use crate::tes3::{Tes3Field, Tes3Record};
-use crate::{decode_failed, read_string, Field, Form, Record, TesError};
+use crate::{check_size, decode_failed, Field, Form, Record, TesError};
 
 const ID_LENGTH: usize = 32;
 
@@ -13,10 +13,76 @@ pub struct Birthsign {
 }
 
 impl Birthsign {
+    /// Creates a new birthsign with no spells
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ID is longer than 32 bytes.
+    pub fn new(id: String) -> Result<Birthsign, TesError> {
+        check_size(&id, ID_LENGTH, "Birthsign ID too long")?;
+
+        Ok(Birthsign {
+            id,
+            name: None,
+            spells: vec![],
+            texture: None,
+            description: None,
+        })
+    }
+
+    /// Gets the birthsign's ID
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    /// Gets the birthsign's name
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Sets the birthsign's name
+    pub fn set_name(&mut self, name: Option<String>) {
+        self.name = name;
+    }
+
     /// Gets the spells associated with this birthsign
     pub fn spells(&self) -> impl Iterator<Item = &str> + '_ {
         self.spells.iter().map(String::as_str)
     }
+
+    /// Sets the spells associated with this birthsign
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of the spell IDs is longer than 32 bytes.
+    pub fn set_spells(&mut self, spells: Vec<String>) -> Result<(), TesError> {
+        for spell in &spells {
+            check_size(spell, ID_LENGTH, "Birthsign spell ID too long")?;
+        }
+
+        self.spells = spells;
+        Ok(())
+    }
+
+    /// Gets the path to the birthsign's constellation texture
+    pub fn texture(&self) -> Option<&str> {
+        self.texture.as_deref()
+    }
+
+    /// Sets the path to the birthsign's constellation texture
+    pub fn set_texture(&mut self, texture: Option<String>) {
+        self.texture = texture;
+    }
+
+    /// Gets the birthsign's description
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Sets the birthsign's description
+    pub fn set_description(&mut self, description: Option<String>) {
+        self.description = description;
+    }
 }
 
 impl Form for Birthsign {
@@ -31,7 +97,7 @@ impl Form for Birthsign {
         let mut birthsign = Birthsign {
             id: String::new(),
             name: None,
-            spells: vec![],
+            spells: record.get_string_list(b"NPCS", ID_LENGTH)?,
             texture: None,
             description: None,
         };
@@ -40,9 +106,7 @@ impl Form for Birthsign {
             match field.name() {
                 b"NAME" => birthsign.id = String::from(field.get_zstring()?),
                 b"FNAM" => birthsign.name = Some(String::from(field.get_zstring()?)),
-                b"NPCS" => birthsign
-                    .spells
-                    .push(read_string::<ID_LENGTH, _>(&mut field.get())?),
+                b"NPCS" => (), // already collected above
                 b"TNAM" => birthsign.texture = Some(String::from(field.get_zstring()?)),
                 b"DESC" => birthsign.description = Some(String::from(field.get_zstring()?)),
                 _ => {
@@ -57,7 +121,55 @@ impl Form for Birthsign {
         Ok(birthsign)
     }
 
-    fn write(&self, _record: &mut Self::Record) -> Result<(), TesError> {
-        unimplemented!()
+    fn write(&self, record: &mut Self::Record) -> Result<(), TesError> {
+        Birthsign::assert(record)?;
+
+        record.clear();
+
+        record.add_field(Tes3Field::new_zstring(b"NAME", self.id.clone())?);
+
+        if let Some(ref name) = self.name {
+            record.add_field(Tes3Field::new_zstring(b"FNAM", name.clone())?);
+        }
+
+        if let Some(ref texture) = self.texture {
+            record.add_field(Tes3Field::new_zstring(b"TNAM", texture.clone())?);
+        }
+
+        if let Some(ref description) = self.description {
+            record.add_field(Tes3Field::new_zstring(b"DESC", description.clone())?);
+        }
+
+        record.set_string_list(b"NPCS", &self.spells, ID_LENGTH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_birthsign() {
+        let mut birthsign = Birthsign::new(String::from("Beggar Sign")).unwrap();
+        birthsign.set_name(Some(String::from("The Beggar")));
+        birthsign.set_texture(Some(String::from("birthsigns\\beggar.tga")));
+        birthsign
+            .set_spells(vec![String::from("beggar's luck"), String::from("alms")])
+            .unwrap();
+        assert!(birthsign
+            .set_spells(vec![String::from("a spell ID that is far too long to fit")])
+            .is_err());
+
+        let mut record = Tes3Record::new(b"BSGN");
+        birthsign.write(&mut record).unwrap();
+        let new_birthsign = Birthsign::read(&record).unwrap();
+        assert_eq!(new_birthsign.id(), "Beggar Sign");
+        assert_eq!(new_birthsign.name(), Some("The Beggar"));
+        assert_eq!(new_birthsign.texture(), Some("birthsigns\\beggar.tga"));
+        assert!(new_birthsign.description().is_none());
+        assert_eq!(
+            new_birthsign.spells().collect::<Vec<_>>(),
+            vec!["beggar's luck", "alms"]
+        );
     }
 }
@@ -1,6 +1,7 @@
 use crate::tes3::{Enchantable, Item, Skill, Tes3Field, Tes3Record};
 use crate::{decode_failed, Field, Form, Record, TesError};
-use binrw::{binrw, BinReaderExt};
+use binrw::{binrw, BinReaderExt, BinWriterExt};
+use std::io::Cursor;
 
 #[binrw]
 #[derive(Debug, Default)]
@@ -10,8 +11,9 @@ pub struct BookData {
     #[br(map = |v: u32| v & 1 != 0)]
     #[bw(map = |v| if *v { 1u32 } else { 0 })]
     pub is_scroll: bool,
-    #[br(try_map = |s: u8| if s == 0xff { Ok(None) } else { Skill::try_from(s).map(|v| Some(v)) })]
-    #[bw(map = |s| s.map_or(0xff, |v| v as u8))]
+    // the skill is a 4-byte value, with -1 for books that don't teach a skill
+    #[br(try_map = |s: i32| if s < 0 { Ok(None) } else { Skill::try_from(s as u8).map(Some) })]
+    #[bw(map = |s| s.map_or(-1i32, |v| v as i32))]
     pub skill: Option<Skill>,
     pub enchantment_points: u32,
 }
@@ -135,7 +137,78 @@ impl Form for Book {
         Ok(book)
     }
 
-    fn write(&self, _: &mut Self::Record) -> Result<(), TesError> {
-        todo!()
+    fn write(&self, record: &mut Self::Record) -> Result<(), TesError> {
+        Book::assert(record)?;
+
+        record.clear();
+
+        record.add_field(Tes3Field::new_zstring(b"NAME", self.id.clone())?);
+        record.add_field(Tes3Field::new_zstring(b"MODL", self.model.clone())?);
+        if let Some(ref name) = self.name {
+            record.add_field(Tes3Field::new_zstring(b"FNAM", name.clone())?);
+        }
+
+        let mut buf = Vec::with_capacity(20);
+        let mut cursor = Cursor::new(&mut buf);
+        cursor.write_le(&self.data)?;
+        record.add_field(Tes3Field::new(b"BKDT", buf)?);
+
+        if let Some(ref script) = self.script {
+            record.add_field(Tes3Field::new_zstring(b"SCRI", script.clone())?);
+        }
+        if let Some(ref icon) = self.icon {
+            record.add_field(Tes3Field::new_zstring(b"ITEX", icon.clone())?);
+        }
+        if !self.text.is_empty() {
+            record.add_field(Tes3Field::new_string(b"TEXT", self.text.clone())?);
+        }
+        if let Some(ref enchantment) = self.enchantment {
+            record.add_field(Tes3Field::new_zstring(b"ENAM", enchantment.clone())?);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_write_book() {
+        let mut bkdt = vec![];
+        bkdt.extend(3f32.to_le_bytes());
+        bkdt.extend(25u32.to_le_bytes());
+        bkdt.extend(0u32.to_le_bytes());
+        bkdt.extend(24i32.to_le_bytes());
+        bkdt.extend(100u32.to_le_bytes());
+        let text = "<DIV ALIGN=\"CENTER\">The Lusty Argonian Maid<BR>";
+
+        let mut record = Tes3Record::new(b"BOOK");
+        for (name, value) in [
+            (b"NAME", "bk_LustyArgonianMaid"),
+            (b"MODL", r"m\Text_Octavo_08.nif"),
+            (b"FNAM", "The Lusty Argonian Maid"),
+        ] {
+            record.add_field(Tes3Field::new_zstring(name, String::from(value)).unwrap());
+        }
+        record.add_field(Tes3Field::new(b"BKDT", bkdt.clone()).unwrap());
+        record.add_field(Tes3Field::new_string(b"TEXT", String::from(text)).unwrap());
+
+        let mut book = Book::read(&record).unwrap();
+        assert_eq!(book.id(), "bk_LustyArgonianMaid");
+        assert_eq!(book.value(), 25);
+        assert!(!book.data.is_scroll);
+        assert_eq!(book.data.skill, Some(Skill::Mercantile));
+        assert_eq!(book.text, text);
+
+        book.text.push_str("Crantius Colto");
+        book.write(&mut record).unwrap();
+
+        let bkdt_field = record.iter().find(|f| f.name() == b"BKDT").unwrap();
+        assert_eq!(bkdt_field.get(), bkdt.as_slice());
+
+        let book = Book::read(&record).unwrap();
+        assert!(book.text.ends_with("<BR>Crantius Colto"));
     }
 }
@@ -0,0 +1,381 @@
+use std::io::Cursor;
+
+use super::cell_reference::is_reference_start;
+use super::CELL_INTERIOR;
+use crate::tes3::{CellId, CellReference, Tes3Field, Tes3Record};
+use crate::{decode_failed, ExtraFields, Field, FieldOrder, Form, GridCoords, Record, TesError};
+
+use binrw::{binrw, BinReaderExt, BinWriterExt};
+
+/// Cell flag for cells that have water
+const CELL_HAS_WATER: u32 = 0x2;
+/// Cell flag for cells the player can't sleep in
+const CELL_ILLEGAL_TO_SLEEP: u32 = 0x4;
+/// Cell flag for interiors that have a sky and weather like an exterior
+const CELL_BEHAVE_LIKE_EXTERIOR: u32 = 0x80;
+
+/// Lighting of an interior cell from the AMBI field
+#[binrw]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AmbientLight {
+    /// Ambient light color as 0x00BBGGRR
+    pub ambient: u32,
+    /// Sunlight color as 0x00BBGGRR
+    pub sunlight: u32,
+    /// Fog color as 0x00BBGGRR
+    pub fog_color: u32,
+    pub fog_density: f32,
+}
+
+/// A Morrowind cell, along with the objects placed in it
+///
+/// The cell's own fields come first in the record, followed by the fields of its references. Only
+/// the header fields needed for the typed accessors are decoded; other header fields are
+/// preserved as-is. See [`CellReference`] for how references are read.
+///
+/// [`CellReference`]: struct.CellReference.html
+#[derive(Debug, Default)]
+pub struct Cell {
+    name: String,
+    flags: u32,
+    grid: GridCoords,
+    /// ID of the region an exterior cell belongs to
+    pub region: Option<String>,
+    /// Color of the cell on the world map as 0x00BBGGRR
+    pub map_color: Option<u32>,
+    pub water_height: Option<f32>,
+    pub ambient: Option<AmbientLight>,
+    references: Vec<CellReference>,
+    extra_fields: Vec<Tes3Field>,
+    field_order: FieldOrder,
+}
+
+impl Cell {
+    /// Creates a new, empty interior cell
+    pub fn interior(name: String) -> Cell {
+        Cell {
+            name,
+            flags: CELL_INTERIOR,
+            ..Cell::default()
+        }
+    }
+
+    /// Creates a new, empty exterior cell at the given grid coordinates
+    pub fn exterior(grid: GridCoords) -> Cell {
+        Cell {
+            grid,
+            ..Cell::default()
+        }
+    }
+
+    /// Gets the ID identifying this cell
+    pub fn id(&self) -> CellId {
+        if self.is_interior() {
+            CellId::interior(&self.name)
+        } else {
+            CellId::Exterior(self.grid)
+        }
+    }
+
+    /// Gets the cell's name
+    ///
+    /// Exterior cells often have no name, in which case this is empty.
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    pub fn is_interior(&self) -> bool {
+        self.flags & CELL_INTERIOR != 0
+    }
+
+    /// Gets the cell's grid coordinates, or `None` if it's an interior
+    pub fn grid(&self) -> Option<GridCoords> {
+        if self.is_interior() {
+            None
+        } else {
+            Some(self.grid)
+        }
+    }
+
+    /// Makes this cell an exterior cell at the given grid coordinates
+    pub fn set_grid(&mut self, grid: GridCoords) {
+        self.flags &= !CELL_INTERIOR;
+        self.grid = grid;
+    }
+
+    pub fn has_water(&self) -> bool {
+        self.flags & CELL_HAS_WATER != 0
+    }
+
+    pub fn set_has_water(&mut self, value: bool) {
+        self.set_flag(CELL_HAS_WATER, value);
+    }
+
+    pub fn is_illegal_to_sleep(&self) -> bool {
+        self.flags & CELL_ILLEGAL_TO_SLEEP != 0
+    }
+
+    pub fn set_illegal_to_sleep(&mut self, value: bool) {
+        self.set_flag(CELL_ILLEGAL_TO_SLEEP, value);
+    }
+
+    /// Does this interior have a sky and weather like an exterior?
+    pub fn behaves_like_exterior(&self) -> bool {
+        self.flags & CELL_BEHAVE_LIKE_EXTERIOR != 0
+    }
+
+    pub fn set_behaves_like_exterior(&mut self, value: bool) {
+        self.set_flag(CELL_BEHAVE_LIKE_EXTERIOR, value);
+    }
+
+    fn set_flag(&mut self, flag: u32, value: bool) {
+        if value {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+    }
+
+    /// Gets the objects placed in this cell
+    pub fn references(&self) -> &[CellReference] {
+        &self.references
+    }
+
+    /// Gets the objects placed in this cell for modification
+    pub fn references_mut(&mut self) -> &mut Vec<CellReference> {
+        &mut self.references
+    }
+
+    /// Finds a placed object by its index within the cell
+    pub fn get_reference(&self, index: u32) -> Option<&CellReference> {
+        self.references.iter().find(|r| r.index() == index)
+    }
+}
+
+impl ExtraFields for Cell {
+    type Field = Tes3Field;
+
+    fn extra_fields(&self) -> &[Tes3Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes3Field> {
+        &mut self.extra_fields
+    }
+}
+
+impl Form for Cell {
+    type Field = Tes3Field;
+    type Record = Tes3Record;
+
+    const RECORD_TYPE: &'static [u8; 4] = b"CELL";
+
+    /// Reads a cell and its references from a raw record
+    ///
+    /// # Errors
+    ///
+    /// Fails if the record is not a CELL record, if it has no DATA field, or if any of its fields
+    /// or references are invalid.
+    fn read(record: &Tes3Record) -> Result<Cell, TesError> {
+        Cell::assert(record)?;
+
+        let mut cell = Cell::default();
+        let mut has_data = false;
+        // references have NAME and DATA fields too, so stop before the first one
+        let header: Vec<_> = record
+            .iter()
+            .take_while(|f| !is_reference_start(f.name()))
+            .collect();
+        for field in header.iter().copied() {
+            match field.name() {
+                b"NAME" => cell.name = String::from(field.get_zstring()?),
+                b"DATA" => {
+                    let mut reader = field.reader();
+                    cell.flags = reader.read_le()?;
+                    cell.grid.x = reader.read_le()?;
+                    cell.grid.y = reader.read_le()?;
+                    has_data = true;
+                }
+                b"RGNN" => cell.region = Some(String::from(field.get_zstring()?)),
+                b"NAM5" => cell.map_color = Some(field.get_u32()?),
+                b"WHGT" => cell.water_height = Some(field.get_f32()?),
+                b"AMBI" => cell.ambient = Some(field.reader().read_le()?),
+                _ => cell.extra_fields.push(field.clone()),
+            }
+        }
+
+        if !has_data {
+            return Err(decode_failed("Cell has no DATA field"));
+        }
+
+        cell.field_order = FieldOrder::from_fields(header);
+        cell.references = CellReference::read_all(record)?;
+
+        Ok(cell)
+    }
+
+    fn write(&self, record: &mut Tes3Record) -> Result<(), TesError> {
+        Cell::assert(record)?;
+
+        let mut fields = vec![Tes3Field::new_zstring(b"NAME", self.name.clone())?];
+
+        let mut buf = Vec::with_capacity(12);
+        let mut cursor = Cursor::new(&mut buf);
+        cursor.write_le(&self.flags)?;
+        cursor.write_le(&self.grid.x)?;
+        cursor.write_le(&self.grid.y)?;
+        fields.push(Tes3Field::new(b"DATA", buf)?);
+
+        if let Some(ref region) = self.region {
+            fields.push(Tes3Field::new_zstring(b"RGNN", region.clone())?);
+        }
+
+        if let Some(map_color) = self.map_color {
+            fields.push(Tes3Field::new_u32(b"NAM5", map_color));
+        }
+
+        if let Some(water_height) = self.water_height {
+            fields.push(Tes3Field::new_f32(b"WHGT", water_height));
+        }
+
+        if let Some(ref ambient) = self.ambient {
+            let mut buf = vec![];
+            let mut cursor = Cursor::new(&mut buf);
+            cursor.write_le(ambient)?;
+            fields.push(Tes3Field::new(b"AMBI", buf)?);
+        }
+
+        fields.extend(self.extra_fields.iter().cloned());
+        self.field_order.apply(&mut fields);
+
+        CellReference::write_references(&self.references, &mut fields)?;
+
+        record.clear();
+        for field in fields {
+            record.add_field(field);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zstring(name: &[u8; 4], value: &str) -> Tes3Field {
+        Tes3Field::new_zstring(name, String::from(value)).unwrap()
+    }
+
+    #[test]
+    fn read_write_interior() {
+        let mut data = vec![];
+        for value in [CELL_INTERIOR | CELL_HAS_WATER, 0, 0] {
+            data.extend(value.to_le_bytes());
+        }
+        let mut ambient = vec![];
+        for value in [0x202020u32, 0x808080, 0x101010] {
+            ambient.extend(value.to_le_bytes());
+        }
+        ambient.extend(0.75f32.to_le_bytes());
+
+        let mut record = Tes3Record::new(b"CELL");
+        record.add_field(zstring(b"NAME", "Seyda Neen, Census and Excise Office"));
+        record.add_field(Tes3Field::new(b"DATA", data).unwrap());
+        record.add_field(Tes3Field::new_f32(b"WHGT", -256.));
+        record.add_field(Tes3Field::new(b"AMBI", ambient).unwrap());
+        record.add_field(Tes3Field::new_u32(b"FRMR", 1));
+        record.add_field(zstring(b"NAME", "chargen door captain"));
+        record.add_field(Tes3Field::new(b"DATA", vec![0; 24]).unwrap());
+        record.add_field(Tes3Field::new_u32(b"FRMR", 2));
+        record.add_field(zstring(b"NAME", "chargen_shipdoor"));
+        record.add_field(Tes3Field::new(b"DATA", vec![0; 24]).unwrap());
+
+        let mut cell = Cell::read(&record).unwrap();
+        assert_eq!(
+            cell.id(),
+            CellId::interior("Seyda Neen, Census and Excise Office")
+        );
+        assert!(cell.is_interior());
+        assert!(cell.has_water());
+        assert!(!cell.is_illegal_to_sleep());
+        assert_eq!(cell.grid(), None);
+        assert_eq!(cell.water_height, Some(-256.));
+        assert_eq!(cell.ambient.as_ref().unwrap().fog_density, 0.75);
+        assert_eq!(cell.references().len(), 2);
+        assert_eq!(cell.get_reference(2).unwrap().base_id(), "chargen_shipdoor");
+
+        cell.set_illegal_to_sleep(true);
+        cell.references_mut().remove(0);
+        cell.write(&mut record).unwrap();
+
+        let names: Vec<_> = record.iter().map(|f| f.name_as_str()).collect();
+        assert_eq!(
+            names,
+            ["NAME", "DATA", "WHGT", "AMBI", "FRMR", "NAME", "DATA"]
+        );
+
+        let cell = Cell::read(&record).unwrap();
+        assert!(cell.is_illegal_to_sleep());
+        assert_eq!(cell.references().len(), 1);
+        assert!(cell.get_reference(1).is_none());
+    }
+
+    #[test]
+    fn read_write_temporary_references() {
+        let mut record = Tes3Record::new(b"CELL");
+        record.add_field(zstring(b"NAME", "Balmora, Hlaalo Manor"));
+        record
+            .add_field(Tes3Field::new(b"DATA", vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]).unwrap());
+        record.add_field(Tes3Field::new_u32(b"NAM0", 2));
+        record.add_field(Tes3Field::new_u32(b"FRMR", 1));
+        record.add_field(zstring(b"NAME", "misc_com_plate_01"));
+        record.add_field(Tes3Field::new_u32(b"FRMR", 2));
+        record.add_field(zstring(b"NAME", "misc_com_plate_02"));
+
+        // a cell with no persistent references starts right at NAM0
+        let mut cell = Cell::read(&record).unwrap();
+        assert!(cell.extra_fields().is_empty());
+        assert_eq!(cell.references().len(), 2);
+        assert!(cell.references().iter().all(|r| !r.is_persistent()));
+
+        cell.references_mut().remove(0);
+        let mut persistent = CellReference::new(3, String::from("ralen hlaalo"));
+        persistent.set_persistent(true);
+        cell.references_mut().push(persistent);
+        cell.write(&mut record).unwrap();
+
+        let names: Vec<_> = record.iter().map(|f| f.name_as_str()).collect();
+        assert_eq!(
+            names,
+            ["NAME", "DATA", "FRMR", "NAME", "NAM0", "FRMR", "NAME"]
+        );
+        let count = record.iter().find(|f| f.name() == b"NAM0").unwrap();
+        assert_eq!(count.get_u32().unwrap(), 1);
+    }
+
+    #[test]
+    fn read_write_exterior() {
+        let mut cell = Cell::exterior(GridCoords::new(-2, -9));
+        cell.region = Some(String::from("Bitter Coast Region"));
+        cell.map_color = Some(0x3f5f3f);
+        cell.references_mut()
+            .push(CellReference::new(1, String::from("flora_bc_tree_02")));
+
+        let mut record = Tes3Record::new(b"CELL");
+        cell.write(&mut record).unwrap();
+
+        let cell = Cell::read(&record).unwrap();
+        assert_eq!(cell.id(), CellId::Exterior(GridCoords::new(-2, -9)));
+        assert_eq!(cell.name(), "");
+        assert_eq!(cell.region.as_deref(), Some("Bitter Coast Region"));
+        assert_eq!(cell.map_color, Some(0x3f5f3f));
+        assert_eq!(cell.references()[0].base_id(), "flora_bc_tree_02");
+
+        assert!(Cell::read(&Tes3Record::new(b"CELL")).is_err());
+    }
+}
@@ -0,0 +1,674 @@
+use crate::tes3::{Destination, Tes3Field, Tes3Record};
+use crate::{
+    checked_len, decode_failed, ExtraFields, Field, FieldOrder, GridCoords, Record, TesError,
+};
+use binrw::{BinReaderExt, BinWriterExt};
+use std::io::Cursor;
+
+/// Number of hex digits in the index at the end of an indexed base ID
+const INDEX_DIGITS: usize = 8;
+
+/// Ownership of a placed reference
+///
+/// Taking or using an owned reference is a crime unless the player is the owning NPC, is a member
+/// of the owning faction with at least the required rank, or the ownership global variable is set.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Ownership {
+    /// ID of the owning NPC
+    pub npc: Option<String>,
+    /// ID of a global variable that, when non-zero, lets the player use the reference freely
+    pub global: Option<String>,
+    /// ID of the owning faction
+    pub faction: Option<String>,
+    /// Minimum rank in the owning faction required to use the reference
+    pub faction_rank: Option<i32>,
+}
+
+/// Lock on a placed reference
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Lock {
+    /// Lock level, from 1 to 100
+    pub level: i32,
+    /// ID of the key that opens the lock
+    pub key: Option<String>,
+}
+
+/// The cell a reference was moved to
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MovedTo {
+    /// An interior cell, by name
+    Interior(String),
+    /// An exterior cell, by grid coordinates
+    Exterior(GridCoords),
+}
+
+/// A note that a reference was moved out of the cell it was placed in
+///
+/// This is stored in the MVRF field and a following CNAM or CNDT field, which come just before the
+/// FRMR of the reference that was moved.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MovedReference {
+    /// Index of the moved reference
+    pub index: u32,
+    /// Cell the reference was moved to
+    pub cell: MovedTo,
+}
+
+impl MovedReference {
+    /// Reads a moved reference from the fields before a reference's FRMR, if there are any
+    fn read(fields: &[Tes3Field]) -> Result<Option<MovedReference>, TesError> {
+        match fields {
+            [] => Ok(None),
+            [index, cell] if index.name() == b"MVRF" => {
+                let cell = match cell.name() {
+                    b"CNAM" => MovedTo::Interior(String::from(cell.get_zstring()?)),
+                    b"CNDT" => {
+                        let mut reader = cell.reader();
+                        MovedTo::Exterior(GridCoords::new(reader.read_le()?, reader.read_le()?))
+                    }
+                    _ => return Err(decode_failed("Moved reference has no destination cell")),
+                };
+                Ok(Some(MovedReference {
+                    index: index.get_u32()?,
+                    cell,
+                }))
+            }
+            _ => Err(decode_failed("Cell reference must start with FRMR")),
+        }
+    }
+
+    fn write(&self) -> Result<Vec<Tes3Field>, TesError> {
+        Ok(vec![
+            Tes3Field::new_u32(b"MVRF", self.index),
+            match self.cell {
+                MovedTo::Interior(ref name) => Tes3Field::new_zstring(b"CNAM", name.clone())?,
+                MovedTo::Exterior(grid) => {
+                    let mut buf = Vec::with_capacity(8);
+                    let mut cursor = Cursor::new(&mut buf);
+                    cursor.write_le(&grid.x)?;
+                    cursor.write_le(&grid.y)?;
+                    Tes3Field::new(b"CNDT", buf)?
+                }
+            },
+        ])
+    }
+}
+
+/// Is this the name of a field that starts a reference or the list of temporary references?
+///
+/// The cell's own fields end at the first of these.
+pub(crate) fn is_reference_start(name: &[u8]) -> bool {
+    name == b"FRMR" || name == b"MVRF" || name == b"NAM0"
+}
+
+/// An object placed in a cell
+///
+/// In Morrowind, placed objects aren't records of their own, but groups of fields within a CELL
+/// record, each group starting with an FRMR field, or with MVRF if the reference was moved to
+/// another cell. A cell's persistent references come first, followed by a NAM0 field with the
+/// number of temporary references and then the temporary references themselves. Only the fields
+/// needed for the typed accessors are decoded; all other fields are preserved as-is.
+#[derive(Debug, Default)]
+pub struct CellReference {
+    index: u32,
+    base_id: String,
+    moved: Option<MovedReference>,
+    is_persistent: bool,
+    ownership: Option<Ownership>,
+    lock: Option<Lock>,
+    trap: Option<String>,
+    teleport: Option<Destination>,
+    extra_fields: Vec<Tes3Field>,
+    field_order: FieldOrder,
+}
+
+impl CellReference {
+    /// Creates a new reference to a base object
+    ///
+    /// `index` is the reference's index within the cell, with the index of the plugin that placed
+    /// it in the upper 8 bits.
+    pub fn new(index: u32, base_id: String) -> CellReference {
+        CellReference {
+            index,
+            base_id,
+            ..CellReference::default()
+        }
+    }
+
+    /// Reads a reference from its group of fields, which must start with FRMR
+    ///
+    /// The FRMR may be preceded by MVRF and CNAM or CNDT fields if the reference was moved. The
+    /// reference is read as temporary; whether it's persistent depends on where it is in the cell.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the fields don't start with FRMR, if there's more than one FRMR, or if any of the
+    /// fields are invalid.
+    pub fn read(fields: &[Tes3Field]) -> Result<CellReference, TesError> {
+        let start = fields
+            .iter()
+            .position(|f| f.name() == b"FRMR")
+            .unwrap_or(fields.len());
+        let moved = MovedReference::read(&fields[..start])?;
+        let fields = &fields[start..];
+
+        let mut iter = fields.iter();
+        let mut reference = match iter.next() {
+            Some(field) if field.name() == b"FRMR" => {
+                CellReference::new(field.get_u32()?, String::new())
+            }
+            _ => return Err(decode_failed("Cell reference must start with FRMR")),
+        };
+
+        for field in iter {
+            match field.name() {
+                b"FRMR" => return Err(decode_failed("Unexpected FRMR in cell reference")),
+                b"NAME" => reference.base_id = String::from(field.get_zstring()?),
+                b"ANAM" => reference.ownership_mut().npc = Some(String::from(field.get_zstring()?)),
+                b"BNAM" => {
+                    reference.ownership_mut().global = Some(String::from(field.get_zstring()?))
+                }
+                b"CNAM" => {
+                    reference.ownership_mut().faction = Some(String::from(field.get_zstring()?))
+                }
+                b"INDX" => reference.ownership_mut().faction_rank = Some(field.get_i32()?),
+                b"FLTV" => reference.lock_mut().level = field.get_i32()?,
+                b"KNAM" => reference.lock_mut().key = Some(String::from(field.get_zstring()?)),
+                b"TNAM" => reference.trap = Some(String::from(field.get_zstring()?)),
+                b"DODT" => reference.teleport = Some(field.reader().read_le()?),
+                b"DNAM" => match reference.teleport {
+                    Some(ref mut teleport) if teleport.cell_name().is_none() => {
+                        teleport.set_cell_name(Some(String::from(field.get_zstring()?)))
+                    }
+                    _ => return Err(decode_failed("Orphaned DNAM field")),
+                },
+                _ => reference.extra_fields.push(field.clone()),
+            }
+        }
+
+        reference.moved = moved;
+        reference.field_order = FieldOrder::from_fields(fields);
+
+        Ok(reference)
+    }
+
+    /// Writes this reference to a group of fields
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of the string values contain null bytes.
+    pub fn write(&self) -> Result<Vec<Tes3Field>, TesError> {
+        let mut fields = vec![
+            Tes3Field::new_u32(b"FRMR", self.index),
+            Tes3Field::new_zstring(b"NAME", self.base_id.clone())?,
+        ];
+
+        if let Some(ref ownership) = self.ownership {
+            for (name, value) in [
+                (b"ANAM", &ownership.npc),
+                (b"BNAM", &ownership.global),
+                (b"CNAM", &ownership.faction),
+            ] {
+                if let Some(value) = value {
+                    fields.push(Tes3Field::new_zstring(name, value.clone())?);
+                }
+            }
+
+            if let Some(rank) = ownership.faction_rank {
+                fields.push(Tes3Field::new_i32(b"INDX", rank));
+            }
+        }
+
+        if let Some(ref lock) = self.lock {
+            fields.push(Tes3Field::new_i32(b"FLTV", lock.level));
+            if let Some(ref key) = lock.key {
+                fields.push(Tes3Field::new_zstring(b"KNAM", key.clone())?);
+            }
+        }
+
+        if let Some(ref trap) = self.trap {
+            fields.push(Tes3Field::new_zstring(b"TNAM", trap.clone())?);
+        }
+
+        if let Some(ref teleport) = self.teleport {
+            let mut buf = vec![];
+            let mut cursor = Cursor::new(&mut buf);
+            cursor.write_le(teleport)?;
+            fields.push(Tes3Field::new(b"DODT", buf)?);
+
+            if let Some(cell_name) = teleport.cell_name() {
+                fields.push(Tes3Field::new_zstring(b"DNAM", String::from(cell_name))?);
+            }
+        }
+
+        fields.extend(self.extra_fields.iter().cloned());
+        self.field_order.apply(&mut fields);
+
+        if let Some(ref moved) = self.moved {
+            let mut prefix = moved.write()?;
+            prefix.append(&mut fields);
+            fields = prefix;
+        }
+
+        Ok(fields)
+    }
+
+    /// Reads all references in a CELL record
+    ///
+    /// References before the record's NAM0 field, or all of them if there is none, are persistent.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the record is not a CELL record or if any of the references are invalid.
+    pub fn read_all(record: &Tes3Record) -> Result<Vec<CellReference>, TesError> {
+        check_cell(record)?;
+
+        let mut references = vec![];
+        let mut fields: Vec<Tes3Field> = vec![];
+        let mut is_persistent = true;
+        for field in record.iter().skip_while(|f| !is_reference_start(f.name())) {
+            // a reference ends where the next one starts: at its FRMR, or at its MVRF if it was
+            // moved. the CNAM after an MVRF is part of the move, not the reference's owner.
+            let name = field.name();
+            let ends_reference = name == b"NAM0"
+                || name == b"MVRF"
+                || (name == b"FRMR" && fields.iter().any(|f| f.name() == b"FRMR"));
+            if ends_reference && !fields.is_empty() {
+                let mut reference = CellReference::read(&fields)?;
+                reference.is_persistent = is_persistent;
+                references.push(reference);
+                fields.clear();
+            }
+
+            if name == b"NAM0" {
+                // the count of temporary references is recalculated when the cell is written
+                is_persistent = false;
+            } else {
+                fields.push(field.clone());
+            }
+        }
+
+        if !fields.is_empty() {
+            let mut reference = CellReference::read(&fields)?;
+            reference.is_persistent = is_persistent;
+            references.push(reference);
+        }
+
+        Ok(references)
+    }
+
+    /// Writes a cell's references to a list of fields
+    ///
+    /// Persistent references are written first, followed by a NAM0 field with the number of
+    /// temporary references and then the temporary references, if there are any.
+    pub(crate) fn write_references(
+        references: &[CellReference],
+        fields: &mut Vec<Tes3Field>,
+    ) -> Result<(), TesError> {
+        for reference in references.iter().filter(|r| r.is_persistent) {
+            fields.extend(reference.write()?);
+        }
+
+        let temporary: Vec<_> = references.iter().filter(|r| !r.is_persistent).collect();
+        if !temporary.is_empty() {
+            fields.push(Tes3Field::new_u32(
+                b"NAM0",
+                checked_len(temporary.len(), "Too many temporary references")?,
+            ));
+            for reference in temporary {
+                fields.extend(reference.write()?);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replaces all references in a CELL record
+    ///
+    /// The cell's own fields, which come before the first reference, are left untouched.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the record is not a CELL record or if any of the references can't be written.
+    pub fn write_all(
+        record: &mut Tes3Record,
+        references: &[CellReference],
+    ) -> Result<(), TesError> {
+        check_cell(record)?;
+
+        let mut fields: Vec<Tes3Field> = record
+            .iter()
+            .take_while(|f| !is_reference_start(f.name()))
+            .cloned()
+            .collect();
+        CellReference::write_references(references, &mut fields)?;
+
+        record.clear();
+        for field in fields {
+            record.add_field(field);
+        }
+
+        Ok(())
+    }
+
+    /// Gets this reference's index within its cell
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Gets the ID of the base object this is a reference to
+    pub fn base_id(&self) -> &str {
+        self.base_id.as_str()
+    }
+
+    /// Splits an indexed base ID into the base object's ID and the index
+    ///
+    /// In saves, a reference whose state is kept in a separate record, such as a container whose
+    /// contents changed, has that record's index appended to its base ID as 8 hex digits. Returns
+    /// `None` if the base ID doesn't end in an index.
+    pub fn indexed_id(&self) -> Option<(&str, u32)> {
+        let split = self.base_id.len().checked_sub(INDEX_DIGITS)?;
+        let index = self.base_id.get(split..)?;
+        if split == 0 || !index.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let index = u32::from_str_radix(index, 16).ok()?;
+        Some((&self.base_id[..split], index))
+    }
+
+    pub fn set_base_id(&mut self, base_id: String) {
+        self.base_id = base_id;
+    }
+
+    /// Gets where this reference was moved to, if it was moved out of the cell it was placed in
+    pub fn moved(&self) -> Option<&MovedReference> {
+        self.moved.as_ref()
+    }
+
+    pub fn set_moved(&mut self, moved: Option<MovedReference>) {
+        self.moved = moved;
+    }
+
+    /// Does this reference persist when its cell isn't loaded?
+    pub fn is_persistent(&self) -> bool {
+        self.is_persistent
+    }
+
+    pub fn set_persistent(&mut self, is_persistent: bool) {
+        self.is_persistent = is_persistent;
+    }
+
+    pub fn ownership(&self) -> Option<&Ownership> {
+        self.ownership.as_ref()
+    }
+
+    pub fn set_ownership(&mut self, ownership: Option<Ownership>) {
+        self.ownership = ownership;
+    }
+
+    /// Gets the reference's ownership, creating an empty one if the reference is unowned
+    pub fn ownership_mut(&mut self) -> &mut Ownership {
+        self.ownership.get_or_insert_with(Ownership::default)
+    }
+
+    pub fn lock(&self) -> Option<&Lock> {
+        self.lock.as_ref()
+    }
+
+    pub fn set_lock(&mut self, lock: Option<Lock>) {
+        self.lock = lock;
+    }
+
+    /// Gets the reference's lock, creating one if the reference is unlocked
+    pub fn lock_mut(&mut self) -> &mut Lock {
+        self.lock.get_or_insert_with(Lock::default)
+    }
+
+    /// Gets the ID of the spell that's cast on whoever opens the reference
+    pub fn trap(&self) -> Option<&str> {
+        self.trap.as_deref()
+    }
+
+    pub fn set_trap(&mut self, trap: Option<String>) {
+        self.trap = trap;
+    }
+
+    /// Gets where the reference takes the player when activated, if it's a door that teleports
+    pub fn teleport(&self) -> Option<&Destination> {
+        self.teleport.as_ref()
+    }
+
+    pub fn set_teleport(&mut self, teleport: Option<Destination>) {
+        self.teleport = teleport;
+    }
+}
+
+impl ExtraFields for CellReference {
+    type Field = Tes3Field;
+
+    fn extra_fields(&self) -> &[Tes3Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes3Field> {
+        &mut self.extra_fields
+    }
+}
+
+fn check_cell(record: &Tes3Record) -> Result<(), TesError> {
+    if record.name() != b"CELL" {
+        return Err(decode_failed(format!(
+            "Expected CELL record, got {}",
+            record.display_name()
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zstring(name: &[u8; 4], value: &str) -> Tes3Field {
+        Tes3Field::new_zstring(name, String::from(value)).unwrap()
+    }
+
+    #[test]
+    fn read_write_cell_references() {
+        let mut record = Tes3Record::new(b"CELL");
+        record.add_field(zstring(b"NAME", "Seyda Neen, Arrille's Tradehouse"));
+        record.add_field(Tes3Field::new(b"DATA", vec![0; 12]).unwrap());
+        record.add_field(Tes3Field::new_u32(b"FRMR", 1));
+        record.add_field(zstring(b"NAME", "chest_small_01"));
+        record.add_field(zstring(b"ANAM", "arrille"));
+        record.add_field(Tes3Field::new_i32(b"FLTV", 20));
+        record.add_field(zstring(b"KNAM", "key_arrille"));
+        record.add_field(zstring(b"TNAM", "trap_fire00"));
+        record.add_field(Tes3Field::new(b"DATA", vec![0; 24]).unwrap());
+        record.add_field(Tes3Field::new_u32(b"FRMR", 2));
+        record.add_field(zstring(b"NAME", "misc_com_bottle_01"));
+        record.add_field(zstring(b"CNAM", "Thieves Guild"));
+        record.add_field(Tes3Field::new_i32(b"INDX", 3));
+        record.add_field(Tes3Field::new(b"DATA", vec![0; 24]).unwrap());
+
+        let mut references = CellReference::read_all(&record).unwrap();
+        assert_eq!(references.len(), 2);
+
+        let chest = &references[0];
+        assert_eq!(chest.index(), 1);
+        assert_eq!(chest.base_id(), "chest_small_01");
+        assert_eq!(chest.ownership().unwrap().npc.as_deref(), Some("arrille"));
+        assert_eq!(
+            chest.lock(),
+            Some(&Lock {
+                level: 20,
+                key: Some(String::from("key_arrille")),
+            })
+        );
+        assert_eq!(chest.trap(), Some("trap_fire00"));
+
+        let bottle = &mut references[1];
+        assert_eq!(
+            bottle.ownership(),
+            Some(&Ownership {
+                faction: Some(String::from("Thieves Guild")),
+                faction_rank: Some(3),
+                ..Ownership::default()
+            })
+        );
+        assert_eq!(bottle.lock(), None);
+
+        references[0].set_trap(None);
+        references[1].set_ownership(None);
+        CellReference::write_all(&mut record, &references).unwrap();
+
+        let names: Vec<_> = record.iter().map(|f| f.name_as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "NAME", "DATA", "FRMR", "NAME", "ANAM", "FLTV", "KNAM", "DATA", "FRMR", "NAME",
+                "DATA"
+            ]
+        );
+
+        let references = CellReference::read_all(&record).unwrap();
+        assert_eq!(references[0].trap(), None);
+        assert_eq!(references[1].ownership(), None);
+    }
+
+    #[test]
+    fn read_write_teleport() {
+        let mut destination = vec![];
+        for value in [-2048f32, 4096., 512., 0., 0., 1.5] {
+            destination.extend(value.to_le_bytes());
+        }
+
+        let mut record = Tes3Record::new(b"CELL");
+        record.add_field(zstring(b"NAME", "Balmora, Caius Cosades' House"));
+        record.add_field(Tes3Field::new(b"DATA", vec![0; 12]).unwrap());
+        record.add_field(Tes3Field::new_u32(b"FRMR", 1));
+        record.add_field(zstring(b"NAME", "in_hlaalu_door_01"));
+        record.add_field(Tes3Field::new(b"DODT", destination.clone()).unwrap());
+        record.add_field(Tes3Field::new(b"DATA", vec![0; 24]).unwrap());
+        record.add_field(Tes3Field::new_u32(b"FRMR", 2));
+        record.add_field(zstring(b"NAME", "in_hlaalu_loaddoor_01"));
+        record.add_field(Tes3Field::new(b"DODT", destination).unwrap());
+        record.add_field(zstring(b"DNAM", "Balmora, Guild of Mages"));
+        record.add_field(Tes3Field::new(b"DATA", vec![0; 24]).unwrap());
+
+        let references = CellReference::read_all(&record).unwrap();
+        let outside = references[0].teleport().unwrap();
+        assert_eq!(outside.position(), (-2048., 4096., 512.));
+        assert_eq!(outside.rotation(), (0., 0., 1.5));
+        assert_eq!(outside.cell_name(), None);
+        assert_eq!(
+            references[1].teleport().unwrap().cell_name(),
+            Some("Balmora, Guild of Mages")
+        );
+
+        CellReference::write_all(&mut record, &references).unwrap();
+        let names: Vec<_> = record.iter().map(|f| f.name_as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "NAME", "DATA", "FRMR", "NAME", "DODT", "DATA", "FRMR", "NAME", "DODT", "DNAM",
+                "DATA"
+            ]
+        );
+    }
+
+    #[test]
+    fn read_write_moved_and_temporary_references() {
+        let mut record = Tes3Record::new(b"CELL");
+        record.add_field(zstring(b"NAME", "Balmora, Hlaalo Manor"));
+        record.add_field(Tes3Field::new(b"DATA", vec![0; 12]).unwrap());
+        record.add_field(Tes3Field::new_u32(b"FRMR", 1));
+        record.add_field(zstring(b"NAME", "ralen hlaalo"));
+        record.add_field(Tes3Field::new(b"DATA", vec![0; 24]).unwrap());
+        record.add_field(Tes3Field::new_u32(b"MVRF", 2));
+        record.add_field(zstring(b"CNAM", "Balmora, Council Club"));
+        record.add_field(Tes3Field::new_u32(b"FRMR", 2));
+        record.add_field(zstring(b"NAME", "misc_com_bottle_01"));
+        record.add_field(zstring(b"CNAM", "Camonna Tong"));
+        record.add_field(Tes3Field::new(b"DATA", vec![0; 24]).unwrap());
+        record.add_field(Tes3Field::new_u32(b"NAM0", 5));
+        record.add_field(Tes3Field::new_u32(b"FRMR", 3));
+        record.add_field(zstring(b"NAME", "misc_com_plate_01"));
+        record.add_field(Tes3Field::new(b"DATA", vec![0; 24]).unwrap());
+
+        let mut references = CellReference::read_all(&record).unwrap();
+        assert_eq!(references.len(), 3);
+        assert!(references[0].is_persistent());
+        assert!(references[0].moved().is_none());
+        assert_eq!(
+            references[1].moved(),
+            Some(&MovedReference {
+                index: 2,
+                cell: MovedTo::Interior(String::from("Balmora, Council Club")),
+            })
+        );
+        assert_eq!(
+            references[1].ownership().unwrap().faction.as_deref(),
+            Some("Camonna Tong")
+        );
+        assert!(references[1].is_persistent());
+        assert!(!references[2].is_persistent());
+        assert!(references[2]
+            .extra_fields()
+            .iter()
+            .all(|f| f.name() != b"NAM0"));
+
+        references[1].set_moved(Some(MovedReference {
+            index: 2,
+            cell: MovedTo::Exterior(GridCoords::new(-3, -2)),
+        }));
+        references.push(CellReference::new(4, String::from("misc_com_plate_02")));
+        CellReference::write_all(&mut record, &references).unwrap();
+
+        let names: Vec<_> = record.iter().map(|f| f.name_as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "NAME", "DATA", "FRMR", "NAME", "DATA", "MVRF", "CNDT", "FRMR", "NAME", "CNAM",
+                "DATA", "NAM0", "FRMR", "NAME", "DATA", "FRMR", "NAME"
+            ]
+        );
+        let count = record.iter().find(|f| f.name() == b"NAM0").unwrap();
+        assert_eq!(count.get_u32().unwrap(), 2);
+
+        let references = CellReference::read_all(&record).unwrap();
+        assert_eq!(
+            references[1].moved().unwrap().cell,
+            MovedTo::Exterior(GridCoords::new(-3, -2))
+        );
+        assert_eq!(references.iter().filter(|r| r.is_persistent()).count(), 2);
+    }
+
+    #[test]
+    fn orphaned_teleport_cell() {
+        let fields = [
+            Tes3Field::new_u32(b"FRMR", 1),
+            zstring(b"NAME", "in_hlaalu_loaddoor_01"),
+            zstring(b"DNAM", "Balmora, Guild of Mages"),
+        ];
+        assert!(CellReference::read(&fields).is_err());
+    }
+
+    #[test]
+    fn split_indexed_id() {
+        let reference = CellReference::new(1, String::from("chest_small_0100000003"));
+        assert_eq!(reference.indexed_id(), Some(("chest_small_01", 3)));
+        let reference = CellReference::new(1, String::from("chest_small_01"));
+        assert_eq!(reference.indexed_id(), None);
+        let reference = CellReference::new(1, String::from("0000000a"));
+        assert_eq!(reference.indexed_id(), None);
+    }
+
+    #[test]
+    fn read_invalid_reference() {
+        assert!(CellReference::read(&[zstring(b"NAME", "chest_small_01")]).is_err());
+        assert!(CellReference::read_all(&Tes3Record::new(b"NPC_")).is_err());
+    }
+}
@@ -1,11 +1,14 @@
 use std::convert::TryFrom;
+use std::io::Cursor;
 
 use crate::tes3::plugin::*;
 use crate::tes3::{Skill, SkillType};
 
-use binrw::BinReaderExt;
+use binrw::{BinReaderExt, BinWriterExt};
 use bitflags::bitflags;
 
+const ID_LENGTH: usize = 32;
+
 bitflags! {
     struct AutoCalcFlags: u32 {
         const WEAPON = 0x0001;
@@ -120,12 +123,73 @@ impl Form for Class {
         Ok(class)
     }
 
-    fn write(&self, _: &mut Tes3Record) -> Result<(), TesError> {
-        unimplemented!()
+    fn write(&self, record: &mut Tes3Record) -> Result<(), TesError> {
+        Class::assert(record)?;
+
+        record.clear();
+
+        record.add_field(Tes3Field::new_zstring(b"NAME", self.id.clone())?);
+        record.add_field(Tes3Field::new_zstring(b"FNAM", self.name.clone())?);
+
+        let mut buf = Vec::with_capacity(60);
+        let mut writer = Cursor::new(&mut buf);
+        for attribute in self.primary_attributes.iter() {
+            writer.write_le(&(*attribute as u32))?;
+        }
+
+        writer.write_le(&(self.specialization as u32))?;
+
+        for (major, minor) in self.major_skills.iter().zip(self.minor_skills.iter()) {
+            writer.write_le(&(*minor as u32))?;
+            writer.write_le(&(*major as u32))?;
+        }
+
+        writer.write_le(&(self.is_playable as u32))?;
+        writer.write_le(&self.auto_calc_flags.bits)?;
+        record.add_field(Tes3Field::new(b"CLDT", buf)?);
+
+        if let Some(ref description) = self.description {
+            record.add_field(Tes3Field::new_string(b"DESC", description.clone())?);
+        }
+
+        Ok(())
     }
 }
 
 impl Class {
+    /// Creates a new class with default settings
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ID is longer than 32 bytes.
+    pub fn new(id: String, name: String) -> Result<Class, TesError> {
+        check_size(&id, ID_LENGTH, "Class ID too long")?;
+
+        Ok(Class {
+            id,
+            name,
+            description: None,
+            primary_attributes: [Attribute::Strength, Attribute::Intelligence],
+            specialization: Specialization::Combat,
+            major_skills: [
+                Skill::Block,
+                Skill::Armorer,
+                Skill::MediumArmor,
+                Skill::HeavyArmor,
+                Skill::Blunt,
+            ],
+            minor_skills: [
+                Skill::LongBlade,
+                Skill::Axe,
+                Skill::Spear,
+                Skill::Athletics,
+                Skill::Enchant,
+            ],
+            is_playable: false,
+            auto_calc_flags: AutoCalcFlags::empty(),
+        })
+    }
+
     /// Returns whether a given skill is a major, minor, or misc skill for this class
     pub fn get_skill_type(&self, skill: Skill) -> SkillType {
         if self.major_skills.iter().any(|s| *s == skill) {
@@ -147,11 +211,42 @@ impl Class {
         self.name.as_str()
     }
 
+    /// Sets this class's name
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    /// Gets this class's description
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Sets this class's description
+    pub fn set_description(&mut self, description: Option<String>) {
+        self.description = description;
+    }
+
     /// Gets this class's primary attributes
     pub fn primary_attributes(&self) -> &[Attribute; 2] {
         &self.primary_attributes
     }
 
+    /// Sets this class's primary attributes
+    ///
+    /// # Errors
+    ///
+    /// Fails if both provided attributes are the same.
+    pub fn set_primary_attributes(&mut self, attributes: [Attribute; 2]) -> Result<(), TesError> {
+        if attributes[0] == attributes[1] {
+            return Err(TesError::RequirementFailed(String::from(
+                "Class primary attributes must be unique",
+            )));
+        }
+
+        self.primary_attributes = attributes;
+        Ok(())
+    }
+
     /// Gets this class's major skills
     pub fn major_skills(&self) -> &[Skill; 5] {
         &self.major_skills
@@ -161,6 +256,26 @@ impl Class {
     pub fn minor_skills(&self) -> &[Skill; 5] {
         &self.minor_skills
     }
+
+    /// Sets this class's major and minor skills
+    ///
+    /// # Errors
+    ///
+    /// Fails if the same skill appears more than once across both arrays.
+    pub fn set_skills(&mut self, major: [Skill; 5], minor: [Skill; 5]) -> Result<(), TesError> {
+        let skills: Vec<_> = major.iter().chain(minor.iter()).collect();
+        for (i, skill) in skills.iter().enumerate() {
+            if skills.iter().skip(i + 1).any(|s| s == skill) {
+                return Err(TesError::RequirementFailed(String::from(
+                    "Class major and minor skills must be unique",
+                )));
+            }
+        }
+
+        self.major_skills = major;
+        self.minor_skills = minor;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -182,4 +297,39 @@ mod tests {
         assert_eq!(class.specialization, Specialization::Combat);
         assert!(class.is_playable);
     }
+
+    #[test]
+    fn test_write() {
+        let mut record_ref = CLASS_RECORD.as_ref();
+        let cursor = Cursor::new(&mut record_ref);
+        let record = Tes3Record::read(cursor).unwrap();
+        let class = Class::read(&record).unwrap();
+
+        let mut new_record = Tes3Record::new(b"CLAS");
+        class.write(&mut new_record).unwrap();
+        assert_eq!(new_record.len(), record.len());
+        for (new_field, field) in new_record.iter().zip(record.iter()) {
+            assert_eq!(new_field.name(), field.name());
+            assert_eq!(new_field.get(), field.get());
+        }
+
+        let mut class = Class::new(String::from("Spellsword"), String::from("Spellsword")).unwrap();
+        let result = class.set_skills(
+            [
+                Skill::Block,
+                Skill::Restoration,
+                Skill::LongBlade,
+                Skill::Destruction,
+                Skill::Alteration,
+            ],
+            [
+                Skill::Blunt,
+                Skill::Enchant,
+                Skill::Alchemy,
+                Skill::MediumArmor,
+                Skill::Block,
+            ],
+        );
+        assert!(matches!(result, Err(TesError::RequirementFailed(_))));
+    }
 }
@@ -0,0 +1,263 @@
+use std::io::Cursor;
+
+use crate::tes3::{BodyPartMapping, Enchantable, Item, Tes3Field, Tes3Record};
+use crate::{decode_failed, Field, Form, Record, TesError};
+use binrw::{binrw, BinReaderExt, BinWriterExt};
+
+#[binrw]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[repr(u32)]
+#[brw(repr = u32)]
+pub enum ClothingType {
+    #[default]
+    Pants,
+    Shoes,
+    Shirt,
+    Belt,
+    Robe,
+    RightGlove,
+    LeftGlove,
+    Skirt,
+    Ring,
+    Amulet,
+}
+
+#[binrw]
+#[derive(Debug, Default)]
+pub struct ClothingData {
+    pub clothing_type: ClothingType,
+    pub weight: f32,
+    pub value: u16,
+    pub enchantment_points: u16,
+}
+
+#[derive(Debug, Default)]
+pub struct Clothing {
+    id: String,
+    model: String,
+    name: Option<String>,
+    script: Option<String>,
+    pub data: ClothingData,
+    icon: Option<String>,
+    pub body_parts: Vec<BodyPartMapping>,
+    enchantment: Option<String>,
+}
+
+impl Clothing {
+    /// Creates a new piece of clothing of the given type
+    pub fn new(id: String, clothing_type: ClothingType) -> Clothing {
+        Clothing {
+            id,
+            data: ClothingData {
+                clothing_type,
+                ..ClothingData::default()
+            },
+            ..Clothing::default()
+        }
+    }
+
+    /// Is this clothing a ring or amulet?
+    ///
+    /// Accessories don't cover any part of the body, so they have no body parts.
+    pub fn is_accessory(&self) -> bool {
+        matches!(
+            self.data.clothing_type,
+            ClothingType::Ring | ClothingType::Amulet
+        )
+    }
+}
+
+impl Item for Clothing {
+    fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(self.model.as_str())
+    }
+
+    fn set_model(&mut self, model: Option<String>) {
+        self.model = model.unwrap_or_else(String::new);
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn set_name(&mut self, name: Option<String>) {
+        self.name = name;
+    }
+
+    fn weight(&self) -> f32 {
+        self.data.weight
+    }
+
+    fn set_weight(&mut self, weight: f32) {
+        self.data.weight = weight;
+    }
+
+    fn value(&self) -> u32 {
+        self.data.value as u32
+    }
+
+    fn set_value(&mut self, value: u32) {
+        self.data.value = value as u16;
+    }
+
+    fn script(&self) -> Option<&str> {
+        self.script.as_deref()
+    }
+
+    fn set_script(&mut self, script: Option<String>) {
+        self.script = script;
+    }
+
+    fn icon(&self) -> Option<&str> {
+        self.icon.as_deref()
+    }
+
+    fn set_icon(&mut self, icon: Option<String>) {
+        self.icon = icon;
+    }
+}
+
+impl Enchantable for Clothing {
+    fn enchantment(&self) -> Option<&str> {
+        self.enchantment.as_deref()
+    }
+
+    fn set_enchantment(&mut self, enchantment: Option<String>) {
+        self.enchantment = enchantment;
+    }
+
+    fn enchantment_points(&self) -> u32 {
+        self.data.enchantment_points as u32
+    }
+
+    fn set_enchantment_points(&mut self, enchantment_points: u32) {
+        self.data.enchantment_points = enchantment_points as u16;
+    }
+}
+
+impl Form for Clothing {
+    type Field = Tes3Field;
+    type Record = Tes3Record;
+    const RECORD_TYPE: &'static [u8; 4] = b"CLOT";
+
+    fn read(record: &Self::Record) -> Result<Self, TesError> {
+        Clothing::assert(record)?;
+
+        let mut clothing = Clothing::default();
+        for field in record.iter() {
+            if BodyPartMapping::read_field(&mut clothing.body_parts, field)? {
+                continue;
+            }
+
+            match field.name() {
+                b"NAME" => clothing.id = String::from(field.get_zstring()?),
+                b"MODL" => clothing.model = String::from(field.get_zstring()?),
+                b"FNAM" => clothing.name = Some(String::from(field.get_zstring()?)),
+                b"SCRI" => clothing.script = Some(String::from(field.get_zstring()?)),
+                b"CTDT" => clothing.data = field.reader().read_le()?,
+                b"ITEX" => clothing.icon = Some(String::from(field.get_zstring()?)),
+                b"ENAM" => clothing.enchantment = Some(String::from(field.get_zstring()?)),
+                _ => {
+                    return Err(decode_failed(format!(
+                        "Unexpected {} field in CLOT record",
+                        field.name_as_str()
+                    )))
+                }
+            }
+        }
+
+        Ok(clothing)
+    }
+
+    fn write(&self, record: &mut Self::Record) -> Result<(), TesError> {
+        Clothing::assert(record)?;
+
+        record.clear();
+
+        record.add_field(Tes3Field::new_zstring(b"NAME", self.id.clone())?);
+        record.add_field(Tes3Field::new_zstring(b"MODL", self.model.clone())?);
+        if let Some(ref name) = self.name {
+            record.add_field(Tes3Field::new_zstring(b"FNAM", name.clone())?);
+        }
+        if let Some(ref script) = self.script {
+            record.add_field(Tes3Field::new_zstring(b"SCRI", script.clone())?);
+        }
+
+        let mut buf = Vec::with_capacity(12);
+        let mut cursor = Cursor::new(&mut buf);
+        cursor.write_le(&self.data)?;
+        record.add_field(Tes3Field::new(b"CTDT", buf)?);
+
+        if let Some(ref icon) = self.icon {
+            record.add_field(Tes3Field::new_zstring(b"ITEX", icon.clone())?);
+        }
+        BodyPartMapping::write_all(&self.body_parts, record)?;
+        if let Some(ref enchantment) = self.enchantment {
+            record.add_field(Tes3Field::new_zstring(b"ENAM", enchantment.clone())?);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tes3::BipedObject;
+
+    static CLOTHING_RECORD: &[u8] = include_bytes!("test/clot_record.bin");
+
+    #[test]
+    fn test_load() {
+        let mut record_ref = CLOTHING_RECORD.as_ref();
+        let cursor = Cursor::new(&mut record_ref);
+        let record = Tes3Record::read(cursor).unwrap();
+        let clothing = Clothing::read(&record).unwrap();
+        assert_eq!(clothing.id(), "common_shoes_01");
+        assert_eq!(clothing.name(), Some("Common Shoes"));
+        assert_eq!(clothing.data.clothing_type, ClothingType::Shoes);
+        assert_eq!(clothing.weight(), 3.);
+        assert_eq!(clothing.value(), 2);
+        assert_eq!(clothing.enchantment_points(), 5);
+        assert!(!clothing.is_accessory());
+        assert_eq!(clothing.body_parts.len(), 2);
+        assert_eq!(clothing.body_parts[0].object, BipedObject::RightFoot);
+        assert_eq!(
+            clothing.body_parts[0].female_part.as_deref(),
+            Some("c_f_shoes_common_01_foot_r")
+        );
+        assert_eq!(clothing.body_parts[1].object, BipedObject::LeftFoot);
+        assert_eq!(clothing.body_parts[1].female_part, None);
+    }
+
+    #[test]
+    fn test_write() {
+        let mut record_ref = CLOTHING_RECORD.as_ref();
+        let cursor = Cursor::new(&mut record_ref);
+        let record = Tes3Record::read(cursor).unwrap();
+        let mut clothing = Clothing::read(&record).unwrap();
+
+        let mut new_record = Tes3Record::new(b"CLOT");
+        clothing.write(&mut new_record).unwrap();
+        assert_eq!(new_record.len(), record.len());
+        for (new_field, field) in new_record.iter().zip(record.iter()) {
+            assert_eq!(new_field.name(), field.name());
+            assert_eq!(new_field.get(), field.get());
+        }
+
+        clothing.data.clothing_type = ClothingType::Ring;
+        clothing.set_enchantment(Some(String::from("fireball_en")));
+        clothing.write(&mut new_record).unwrap();
+        let clothing = Clothing::read(&new_record).unwrap();
+        assert!(clothing.is_accessory());
+        assert_eq!(clothing.enchantment(), Some("fireball_en"));
+    }
+}
@@ -0,0 +1,299 @@
+use std::io::Cursor;
+
+use crate::tes3::{Tes3Field, Tes3Record, ACTOR_STRING_LENGTH};
+use crate::{check_size, decode_failed, read_string, write_str, Field, Form, Record, TesError};
+use binrw::{BinReaderExt, BinWriterExt};
+use bitflags::bitflags;
+
+bitflags! {
+    struct ContainerFlags: u32 {
+        const ORGANIC = 0x1;
+        const RESPAWNS = 0x2;
+        // always set
+        const DEFAULT = 0x8;
+    }
+}
+
+impl Default for ContainerFlags {
+    fn default() -> Self {
+        ContainerFlags::DEFAULT
+    }
+}
+
+/// A container, such as a chest, barrel, or plant
+///
+/// Each item in the container is a base item ID and a count. A negative count means the stack
+/// restocks, which is used for merchants' containers.
+#[derive(Debug, Default)]
+pub struct Container {
+    id: String,
+    model: String,
+    name: Option<String>,
+    /// Maximum total weight of the items the container can hold
+    pub capacity: f32,
+    flags: ContainerFlags,
+    script: Option<String>,
+    items: Vec<(String, i32)>,
+}
+
+impl Container {
+    /// Creates a new, empty container
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ID is longer than 32 bytes.
+    pub fn new(id: String, capacity: f32) -> Result<Container, TesError> {
+        check_size(&id, ACTOR_STRING_LENGTH, "Container ID too long")?;
+
+        Ok(Container {
+            id,
+            capacity,
+            ..Container::default()
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    pub fn model(&self) -> &str {
+        self.model.as_str()
+    }
+
+    pub fn set_model(&mut self, model: String) {
+        self.model = model;
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn set_name(&mut self, name: Option<String>) {
+        self.name = name;
+    }
+
+    pub fn script(&self) -> Option<&str> {
+        self.script.as_deref()
+    }
+
+    pub fn set_script(&mut self, script: Option<String>) {
+        self.script = script;
+    }
+
+    /// Is this container a plant or other organic container, which can be harvested?
+    pub fn is_organic(&self) -> bool {
+        self.flags.contains(ContainerFlags::ORGANIC)
+    }
+
+    pub fn set_organic(&mut self, is_organic: bool) {
+        self.flags.set(ContainerFlags::ORGANIC, is_organic);
+    }
+
+    /// Does this container's contents respawn?
+    pub fn respawns(&self) -> bool {
+        self.flags.contains(ContainerFlags::RESPAWNS)
+    }
+
+    pub fn set_respawns(&mut self, respawns: bool) {
+        self.flags.set(ContainerFlags::RESPAWNS, respawns);
+    }
+
+    /// Iterates through the container's items as (ID, count) pairs
+    pub fn iter_items(&self) -> impl Iterator<Item = (&str, i32)> + '_ {
+        self.items.iter().map(|(id, count)| (id.as_str(), *count))
+    }
+
+    /// Gets the count of the stack of an item that restocks or doesn't, or 0 if there isn't one
+    fn stack_count(&self, id: &str, restocks: bool) -> i32 {
+        self.items
+            .iter()
+            .find(|(i, count)| i.eq_ignore_ascii_case(id) && (*count < 0) == restocks)
+            .map_or(0, |(_, count)| *count)
+    }
+
+    /// Gets the count of an item in the container, or 0 if the container doesn't have it
+    ///
+    /// Restocking stacks aren't included; see [`restock_count`]. Item IDs are case-insensitive.
+    ///
+    /// [`restock_count`]: #method.restock_count
+    pub fn item_count(&self, id: &str) -> i32 {
+        self.stack_count(id, false)
+    }
+
+    /// Gets the negative count of an item's restocking stack, or 0 if the item doesn't restock
+    ///
+    /// Item IDs are case-insensitive.
+    pub fn restock_count(&self, id: &str) -> i32 {
+        self.stack_count(id, true)
+    }
+
+    /// Adds items to the container
+    ///
+    /// If the container already has a stack of the item, the count is added to it. Restocking
+    /// stacks (negative counts) and normal stacks are kept separate, because merging them would
+    /// change whether the item restocks.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ID is longer than 32 bytes or if the stack's count would overflow.
+    pub fn add_item(&mut self, id: String, count: i32) -> Result<(), TesError> {
+        check_size(&id, ACTOR_STRING_LENGTH, "Container item ID too long")?;
+
+        match self
+            .items
+            .iter_mut()
+            .find(|(i, existing)| i.eq_ignore_ascii_case(&id) && (*existing < 0) == (count < 0))
+        {
+            Some((_, existing)) => {
+                *existing = existing.checked_add(count).ok_or_else(|| {
+                    TesError::RequirementFailed(format!("Too many {} in container", id))
+                })?
+            }
+            None => self.items.push((id, count)),
+        }
+        Ok(())
+    }
+
+    /// Removes every stack of an item from the container, returning the number of stacks removed
+    pub fn remove_item(&mut self, id: &str) -> usize {
+        let len = self.items.len();
+        self.items.retain(|(i, _)| !i.eq_ignore_ascii_case(id));
+        len - self.items.len()
+    }
+}
+
+impl Form for Container {
+    type Field = Tes3Field;
+    type Record = Tes3Record;
+
+    const RECORD_TYPE: &'static [u8; 4] = b"CONT";
+
+    /// Reads a container from a raw record
+    ///
+    /// # Errors
+    ///
+    /// Fails if the record is not a CONT record or if any of the fields are invalid.
+    fn read(record: &Tes3Record) -> Result<Container, TesError> {
+        Container::assert(record)?;
+
+        let mut container = Container::default();
+        for field in record.iter() {
+            match field.name() {
+                b"NAME" => container.id = String::from(field.get_zstring()?),
+                b"MODL" => container.model = String::from(field.get_zstring()?),
+                b"FNAM" => container.name = Some(String::from(field.get_zstring()?)),
+                b"CNDT" => container.capacity = field.get_f32()?,
+                b"FLAG" => container.flags = ContainerFlags::from_bits_truncate(field.get_u32()?),
+                b"SCRI" => container.script = Some(String::from(field.get_zstring()?)),
+                b"NPCO" => {
+                    let mut reader = field.reader();
+                    let count = reader.read_le()?;
+                    let id = read_string::<ACTOR_STRING_LENGTH, _>(&mut reader)?;
+                    container.items.push((id, count));
+                }
+                _ => {
+                    return Err(decode_failed(format!(
+                        "Unexpected {} field in CONT record",
+                        field.name_as_str()
+                    )))
+                }
+            }
+        }
+
+        Ok(container)
+    }
+
+    fn write(&self, record: &mut Tes3Record) -> Result<(), TesError> {
+        Container::assert(record)?;
+
+        record.clear();
+
+        record.add_field(Tes3Field::new_zstring(b"NAME", self.id.clone())?);
+        record.add_field(Tes3Field::new_zstring(b"MODL", self.model.clone())?);
+        if let Some(ref name) = self.name {
+            record.add_field(Tes3Field::new_zstring(b"FNAM", name.clone())?);
+        }
+        record.add_field(Tes3Field::new_f32(b"CNDT", self.capacity));
+        record.add_field(Tes3Field::new_u32(b"FLAG", self.flags.bits));
+        if let Some(ref script) = self.script {
+            record.add_field(Tes3Field::new_zstring(b"SCRI", script.clone())?);
+        }
+
+        for (id, count) in &self.items {
+            let mut buf = vec![];
+            let mut cursor = Cursor::new(&mut buf);
+            cursor.write_le(count)?;
+            write_str::<ACTOR_STRING_LENGTH, _>(id, &mut cursor)?;
+            record.add_field(Tes3Field::new(b"NPCO", buf)?);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_write_container() {
+        let mut container = Container::new(String::from("flora_marshmerrow_01"), 0.).unwrap();
+        container.set_model(String::from(r"o\flora_marshmerrow_01.nif"));
+        container.set_name(Some(String::from("Marshmerrow")));
+        container.set_organic(true);
+        container.set_respawns(true);
+        container
+            .add_item(String::from("ingred_marshmerrow_01"), 1)
+            .unwrap();
+        container.add_item(String::from("Gold_001"), 5).unwrap();
+        container.add_item(String::from("gold_001"), 10).unwrap();
+        container
+            .add_item(String::from("ingred_bittergreen_petals_01"), -2)
+            .unwrap();
+        container
+            .add_item(String::from("ingred_bittergreen_petals_01"), 3)
+            .unwrap();
+        container.add_item(String::from("gold_001"), -1).unwrap();
+        assert!(container
+            .add_item(String::from("Gold_001"), i32::MAX)
+            .is_err());
+        assert!(container
+            .add_item(String::from("an_item_id_that_is_far_too_long_to_use"), 1)
+            .is_err());
+
+        let mut record = Tes3Record::new(b"CONT");
+        container.write(&mut record).unwrap();
+        let flags = record.iter().find(|f| f.name() == b"FLAG").unwrap();
+        assert_eq!(flags.get_u32().unwrap(), 0xb);
+        // unknown flags are ignored rather than failing the read
+        let mut unknown_flags = Tes3Record::new(b"CONT");
+        container.write(&mut unknown_flags).unwrap();
+        for field in unknown_flags.iter_mut() {
+            if field.name() == b"FLAG" {
+                field.set_u32(0x10b);
+            }
+        }
+        assert!(Container::read(&unknown_flags).unwrap().respawns());
+
+        let mut container = Container::read(&record).unwrap();
+        assert_eq!(container.id(), "flora_marshmerrow_01");
+        assert!(container.is_organic());
+        assert!(container.respawns());
+        assert_eq!(container.item_count("GOLD_001"), 15);
+        assert_eq!(container.restock_count("GOLD_001"), -1);
+        assert_eq!(container.item_count("ingred_bittergreen_petals_01"), 3);
+        assert_eq!(container.restock_count("ingred_bittergreen_petals_01"), -2);
+        assert_eq!(container.item_count("misc_com_bottle_01"), 0);
+
+        assert_eq!(container.remove_item("ingred_marshmerrow_01"), 1);
+        assert_eq!(container.remove_item("ingred_marshmerrow_01"), 0);
+        assert_eq!(container.remove_item("gold_001"), 2);
+        assert_eq!(
+            container.iter_items().collect::<Vec<_>>(),
+            [
+                ("ingred_bittergreen_petals_01", -2),
+                ("ingred_bittergreen_petals_01", 3)
+            ]
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use super::field::Tes3Field;
+use super::record::Tes3Record;
+use crate::plugin::Field;
+use crate::*;
+
+use crate::tes3::{InventoryItem, ACTOR_STRING_LENGTH};
+use binrw::BinReaderExt;
+
+/// Changes to a container's contents in a save game
+///
+/// Only the ID and count of each stack of items are read; the state of individual items, such as
+/// their condition or enchantment charge, is ignored. Because of that, container changes can't be
+/// written back to a record, so unlike most record types, this isn't a [`Form`].
+///
+/// [`Form`]: ../trait.Form.html
+#[derive(Debug, Default)]
+pub struct ContainerChange {
+    id: String,
+    index: u32,
+    inventory: Vec<InventoryItem>,
+}
+
+impl ContainerChange {
+    /// Gets the ID of the container this change applies to
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    /// Gets the index that distinguishes this container from others with the same ID
+    ///
+    /// The placed reference with this change has the index appended to its base ID; see
+    /// [`CellReference::indexed_id`].
+    ///
+    /// [`CellReference::indexed_id`]: struct.CellReference.html#method.indexed_id
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Iterates through the container's contents
+    pub fn iter_inventory(&self) -> impl Iterator<Item = &InventoryItem> {
+        self.inventory.iter()
+    }
+
+    /// Reads container changes from a raw record
+    ///
+    /// # Errors
+    ///
+    /// Fails if the record is not a CNTC record or if any of the fields are invalid.
+    pub fn read(record: &Tes3Record) -> Result<ContainerChange, TesError> {
+        if record.name() != b"CNTC" {
+            return Err(decode_failed(format!(
+                "Expected CNTC record, got {}",
+                record.display_name()
+            )));
+        }
+
+        let mut container_change = ContainerChange::default();
+        for field in record.iter() {
+            match field.name() {
+                b"NAME" => container_change.id = String::from(field.get_zstring()?),
+                b"INDX" => container_change.index = field.get_u32()?,
+                b"NPCO" => {
+                    let mut reader = field.reader();
+                    let count: i32 = reader.read_le()?;
+                    let id = read_string::<ACTOR_STRING_LENGTH, _>(&mut reader)?;
+                    // the count includes the items whose state follows
+                    container_change
+                        .inventory
+                        .push(InventoryItem::new(id, count.unsigned_abs()));
+                }
+                // per-item state
+                _ => (),
+            }
+        }
+
+        Ok(container_change)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn npco(count: i32, id: &str) -> Tes3Field {
+        let mut data = count.to_le_bytes().to_vec();
+        let mut name = [0u8; ACTOR_STRING_LENGTH];
+        name[..id.len()].copy_from_slice(id.as_bytes());
+        data.extend_from_slice(&name);
+        Tes3Field::new(b"NPCO", data).unwrap()
+    }
+
+    #[test]
+    fn read_container_change() {
+        let mut record = Tes3Record::new(b"CNTC");
+        record.add_field(Tes3Field::new_zstring(b"NAME", String::from("chest_small_01")).unwrap());
+        record.add_field(Tes3Field::new_u32(b"INDX", 3));
+        record.add_field(npco(2, "iron dagger"));
+        record.add_field(Tes3Field::new_u32(b"XIDX", 0));
+        record.add_field(Tes3Field::new_u32(b"XHLT", 120));
+        record.add_field(npco(150, "Gold_001"));
+
+        let container_change = ContainerChange::read(&record).unwrap();
+        assert_eq!(container_change.id(), "chest_small_01");
+        assert_eq!(container_change.index(), 3);
+        assert_eq!(
+            container_change
+                .iter_inventory()
+                .map(|i| (i.id.as_str(), i.count))
+                .collect::<Vec<_>>(),
+            [("iron dagger", 2), ("Gold_001", 150)]
+        );
+
+        assert!(ContainerChange::read(&Tes3Record::new(b"CONT")).is_err());
+    }
+}
@@ -0,0 +1,427 @@
+use std::convert::TryFrom;
+use std::io::Cursor;
+
+use crate::tes3::{Skill, Tes3Field, Tes3Record};
+use crate::{
+    check_size, decode_failed, decode_failed_because, read_string_bytes, write_str, Attribute,
+    ExtraFields, Field, FieldOrder, Form, Record, TesError,
+};
+
+use binrw::{binrw, BinReaderExt, BinWriterExt};
+
+const ID_LENGTH: usize = 32;
+const RANK_NAME_LENGTH: usize = 32;
+/// Size of FADT: the two attributes, the rank requirements, the skills, and the flags
+const FADT_SIZE: usize = 240;
+const HIDDEN_FROM_PLAYER: u32 = 0x1;
+
+/// Maximum number of ranks a faction can have
+pub const MAX_FACTION_RANKS: usize = 10;
+/// Maximum number of skills a faction can have
+pub const MAX_FACTION_SKILLS: usize = 7;
+
+/// Requirements a character must meet to advance to a faction rank
+#[binrw]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct RankRequirements {
+    /// Minimum values of the faction's two attributes
+    pub attributes: [u32; 2],
+    /// Minimum value of one of the faction's skills
+    pub primary_skill: u32,
+    /// Minimum value of two of the faction's skills
+    pub favored_skill: u32,
+    pub reputation: u32,
+}
+
+/// A rank in a faction
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FactionRank {
+    pub name: String,
+    pub requirements: RankRequirements,
+}
+
+impl FactionRank {
+    pub fn new(name: String, requirements: RankRequirements) -> FactionRank {
+        FactionRank { name, requirements }
+    }
+}
+
+/// How members of a faction feel about members of another faction
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FactionReaction {
+    pub faction: String,
+    pub reaction: i32,
+}
+
+/// A faction, such as a guild or Great House
+#[derive(Debug)]
+pub struct Faction {
+    id: String,
+    name: String,
+    ranks: Vec<FactionRank>,
+    attributes: [Attribute; 2],
+    skills: Vec<Skill>,
+    /// Whether the faction is hidden from the player's stats menu
+    pub is_hidden: bool,
+    reactions: Vec<FactionReaction>,
+    extra_fields: Vec<Tes3Field>,
+    field_order: FieldOrder,
+}
+
+impl Faction {
+    /// Creates a new faction with no ranks or skills
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ID is longer than 32 bytes.
+    pub fn new(id: String, name: String) -> Result<Faction, TesError> {
+        check_size(&id, ID_LENGTH, "Faction ID too long")?;
+
+        Ok(Faction {
+            id,
+            name,
+            ranks: vec![],
+            attributes: [Attribute::Strength, Attribute::Intelligence],
+            skills: vec![],
+            is_hidden: false,
+            reactions: vec![],
+            extra_fields: vec![],
+            field_order: FieldOrder::new(),
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    /// Gets the faction's ranks, from lowest to highest
+    pub fn ranks(&self) -> &[FactionRank] {
+        self.ranks.as_slice()
+    }
+
+    /// Gets the faction's ranks mutably
+    pub fn ranks_mut(&mut self) -> &mut [FactionRank] {
+        self.ranks.as_mut_slice()
+    }
+
+    /// Sets the faction's ranks, from lowest to highest
+    ///
+    /// # Errors
+    ///
+    /// Fails if there are more than 10 ranks or if any rank name is longer than 32 bytes.
+    pub fn set_ranks(&mut self, ranks: Vec<FactionRank>) -> Result<(), TesError> {
+        check_size(&ranks, MAX_FACTION_RANKS, "Too many faction ranks")?;
+        for rank in &ranks {
+            check_size(&rank.name, RANK_NAME_LENGTH, "Faction rank name too long")?;
+        }
+
+        self.ranks = ranks;
+        Ok(())
+    }
+
+    /// Gets the two attributes the faction's rank requirements refer to
+    pub fn attributes(&self) -> [Attribute; 2] {
+        self.attributes
+    }
+
+    pub fn set_attributes(&mut self, attributes: [Attribute; 2]) {
+        self.attributes = attributes;
+    }
+
+    /// Gets the skills the faction's rank requirements refer to
+    pub fn skills(&self) -> &[Skill] {
+        self.skills.as_slice()
+    }
+
+    /// Sets the skills the faction's rank requirements refer to
+    ///
+    /// # Errors
+    ///
+    /// Fails if there are more than 7 skills.
+    pub fn set_skills(&mut self, skills: Vec<Skill>) -> Result<(), TesError> {
+        check_size(&skills, MAX_FACTION_SKILLS, "Too many faction skills")?;
+        self.skills = skills;
+        Ok(())
+    }
+
+    /// Is this skill one of the faction's skills?
+    pub fn has_skill(&self, skill: Skill) -> bool {
+        self.skills.contains(&skill)
+    }
+
+    /// Gets this faction's reactions to other factions
+    pub fn reactions(&self) -> &[FactionReaction] {
+        self.reactions.as_slice()
+    }
+
+    /// Gets this faction's reaction to another faction, if it has one
+    ///
+    /// Faction IDs are case-insensitive.
+    pub fn reaction(&self, faction: &str) -> Option<i32> {
+        self.reactions
+            .iter()
+            .find(|r| r.faction.eq_ignore_ascii_case(faction))
+            .map(|r| r.reaction)
+    }
+
+    /// Sets this faction's reaction to another faction, replacing any existing reaction
+    pub fn set_reaction(&mut self, faction: String, reaction: i32) {
+        match self
+            .reactions
+            .iter_mut()
+            .find(|r| r.faction.eq_ignore_ascii_case(&faction))
+        {
+            Some(existing) => existing.reaction = reaction,
+            None => self.reactions.push(FactionReaction { faction, reaction }),
+        }
+    }
+
+    /// Removes this faction's reaction to another faction, returning the reaction if there was one
+    pub fn remove_reaction(&mut self, faction: &str) -> Option<i32> {
+        let index = self
+            .reactions
+            .iter()
+            .position(|r| r.faction.eq_ignore_ascii_case(faction))?;
+        Some(self.reactions.remove(index).reaction)
+    }
+}
+
+impl ExtraFields for Faction {
+    type Field = Tes3Field;
+
+    fn extra_fields(&self) -> &[Tes3Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes3Field> {
+        &mut self.extra_fields
+    }
+}
+
+impl Form for Faction {
+    type Field = Tes3Field;
+    type Record = Tes3Record;
+
+    const RECORD_TYPE: &'static [u8; 4] = b"FACT";
+
+    /// Reads a faction from a raw record
+    ///
+    /// # Errors
+    ///
+    /// Fails if the record is not a FACT record, if it has more than 10 rank names, or if the
+    /// FADT field is missing or invalid.
+    fn read(record: &Tes3Record) -> Result<Faction, TesError> {
+        Faction::assert(record)?;
+
+        let mut faction = Faction::new(String::new(), String::new())?;
+        let mut rank_names = vec![];
+        let mut requirements = None;
+        for field in record.iter() {
+            match field.name() {
+                b"NAME" => faction.id = String::from(field.get_zstring()?),
+                b"FNAM" => faction.name = String::from(field.get_zstring()?),
+                b"RNAM" => rank_names.push(read_string_bytes(field.get())?),
+                b"FADT" => {
+                    let size = field.get().len();
+                    if size != FADT_SIZE {
+                        return Err(decode_failed(format!("Unexpected FADT size {}", size)));
+                    }
+
+                    let mut reader = field.reader();
+                    for attribute in &mut faction.attributes {
+                        *attribute = Attribute::try_from(reader.read_le::<u32>()? as u8)
+                            .map_err(|e| decode_failed_because("Invalid attribute value", e))?;
+                    }
+                    let mut rank_requirements = [RankRequirements::default(); MAX_FACTION_RANKS];
+                    for rank in &mut rank_requirements {
+                        *rank = reader.read_le()?;
+                    }
+                    requirements = Some(rank_requirements);
+                    for _ in 0..MAX_FACTION_SKILLS {
+                        // unused skill slots are -1
+                        let skill = reader.read_le::<i32>()?;
+                        if skill >= 0 {
+                            faction
+                                .skills
+                                .push(Skill::try_from(skill as u8).map_err(|e| {
+                                    decode_failed_because("Invalid skill value", e)
+                                })?);
+                        }
+                    }
+                    faction.is_hidden = reader.read_le::<u32>()? & HIDDEN_FROM_PLAYER != 0;
+                }
+                b"ANAM" => faction.reactions.push(FactionReaction {
+                    faction: read_string_bytes(field.get())?,
+                    reaction: 0,
+                }),
+                b"INTV" => match faction.reactions.last_mut() {
+                    Some(reaction) => reaction.reaction = field.get_i32()?,
+                    None => return Err(decode_failed("Orphaned INTV field")),
+                },
+                _ => faction.extra_fields.push(field.clone()),
+            }
+        }
+
+        if rank_names.len() > MAX_FACTION_RANKS {
+            return Err(decode_failed(format!(
+                "Faction {} has {} ranks; the maximum is {}",
+                faction.id,
+                rank_names.len(),
+                MAX_FACTION_RANKS
+            )));
+        }
+
+        let requirements =
+            requirements.ok_or_else(|| decode_failed("Missing FADT field in FACT record"))?;
+        faction.ranks = rank_names
+            .into_iter()
+            .zip(requirements)
+            .map(|(name, requirements)| FactionRank::new(name, requirements))
+            .collect();
+        faction.field_order = FieldOrder::from_record(record);
+
+        Ok(faction)
+    }
+
+    fn write(&self, record: &mut Tes3Record) -> Result<(), TesError> {
+        Faction::assert(record)?;
+
+        record.clear();
+
+        record.add_field(Tes3Field::new_zstring(b"NAME", self.id.clone())?);
+        record.add_field(Tes3Field::new_zstring(b"FNAM", self.name.clone())?);
+        for rank in &self.ranks {
+            check_size(&rank.name, RANK_NAME_LENGTH, "Faction rank name too long")?;
+            let mut buf = Vec::with_capacity(RANK_NAME_LENGTH);
+            write_str::<RANK_NAME_LENGTH, _>(&rank.name, &mut buf)?;
+            record.add_field(Tes3Field::new(b"RNAM", buf)?);
+        }
+
+        let mut buf = Vec::with_capacity(FADT_SIZE);
+        let mut writer = Cursor::new(&mut buf);
+        for attribute in self.attributes.iter() {
+            writer.write_le(&(*attribute as u32))?;
+        }
+        for i in 0..MAX_FACTION_RANKS {
+            let requirements = self
+                .ranks
+                .get(i)
+                .map_or_else(RankRequirements::default, |r| r.requirements);
+            writer.write_le(&requirements)?;
+        }
+        for i in 0..MAX_FACTION_SKILLS {
+            writer.write_le(&self.skills.get(i).map_or(-1i32, |s| *s as i32))?;
+        }
+        writer.write_le(&if self.is_hidden {
+            HIDDEN_FROM_PLAYER
+        } else {
+            0
+        })?;
+        record.add_field(Tes3Field::new(b"FADT", buf)?);
+
+        for reaction in &self.reactions {
+            record.add_field(Tes3Field::new_string(b"ANAM", reaction.faction.clone())?);
+            record.add_field(Tes3Field::new_i32(b"INTV", reaction.reaction));
+        }
+
+        for field in &self.extra_fields {
+            record.add_field(field.clone());
+        }
+        record.sort_fields(&self.field_order);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_write_faction() {
+        let mut faction = Faction::new(
+            String::from("Fighters Guild"),
+            String::from("Fighters Guild"),
+        )
+        .unwrap();
+        faction
+            .set_ranks(vec![
+                FactionRank::new(String::from("Associate"), RankRequirements::default()),
+                FactionRank::new(
+                    String::from("Apprentice"),
+                    RankRequirements {
+                        attributes: [30, 30],
+                        primary_skill: 10,
+                        favored_skill: 0,
+                        reputation: 0,
+                    },
+                ),
+            ])
+            .unwrap();
+        faction.set_attributes([Attribute::Strength, Attribute::Endurance]);
+        faction
+            .set_skills(vec![Skill::Axe, Skill::LongBlade, Skill::Block])
+            .unwrap();
+        faction.set_reaction(String::from("Thieves Guild"), -3);
+        faction.set_reaction(String::from("Mages Guild"), 1);
+        faction.set_reaction(String::from("thieves guild"), -5);
+
+        let mut record = Tes3Record::new(b"FACT");
+        faction.write(&mut record).unwrap();
+        // attributes, then each rank's requirements, then skills, then flags
+        let mut fadt = vec![];
+        for value in [0u32, 5, 0, 0, 0, 0, 0, 30, 30, 10, 0, 0] {
+            fadt.extend(value.to_le_bytes());
+        }
+        fadt.resize(8 + 20 * MAX_FACTION_RANKS, 0);
+        for value in [6i32, 5, 0, -1, -1, -1, -1, 0] {
+            fadt.extend(value.to_le_bytes());
+        }
+        let fadt_field = record.iter().find(|f| f.name() == b"FADT").unwrap();
+        assert_eq!(fadt_field.get(), fadt.as_slice());
+        let reactions: Vec<_> = record
+            .iter()
+            .filter(|f| f.name() == b"INTV")
+            .map(|f| f.get_i32().unwrap())
+            .collect();
+        assert_eq!(reactions, [-5, 1]);
+
+        let mut faction = Faction::read(&record).unwrap();
+        assert_eq!(faction.id(), "Fighters Guild");
+        assert_eq!(faction.ranks().len(), 2);
+        assert_eq!(faction.ranks()[1].name, "Apprentice");
+        assert_eq!(faction.ranks()[1].requirements.attributes, [30, 30]);
+        assert_eq!(
+            faction.attributes(),
+            [Attribute::Strength, Attribute::Endurance]
+        );
+        assert!(faction.has_skill(Skill::LongBlade));
+        assert!(!faction.has_skill(Skill::Destruction));
+        assert!(!faction.is_hidden);
+        assert_eq!(faction.reaction("Thieves Guild"), Some(-5));
+        assert_eq!(faction.remove_reaction("MAGES GUILD"), Some(1));
+        assert_eq!(faction.reaction("Mages Guild"), None);
+    }
+
+    #[test]
+    fn too_many_ranks() {
+        let mut faction = Faction::new(String::from("test"), String::from("Test")).unwrap();
+        let ranks = vec![FactionRank::new(String::from("rank"), RankRequirements::default()); 11];
+        assert!(faction.set_ranks(ranks).is_err());
+        assert!(faction.set_skills(vec![Skill::Axe; 8]).is_err());
+        assert!(faction
+            .set_ranks(vec![FactionRank::new(
+                "a".repeat(RANK_NAME_LENGTH + 1),
+                RankRequirements::default()
+            )])
+            .is_err());
+    }
+}
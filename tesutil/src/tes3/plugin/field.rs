@@ -20,7 +20,7 @@ use binrw::{binrw, BinReaderExt, BinWriterExt};
 /// The data, on the other hand, is taken as an owned value, because this is much more likely to be
 /// dynamic.
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Tes3Field {
     name: [u8; 4],
     #[br(temp)]
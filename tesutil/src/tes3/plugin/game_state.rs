@@ -0,0 +1,211 @@
+use std::convert::TryFrom;
+use std::io::{Cursor, Read, Seek};
+
+use crate::tes3::plugin::*;
+
+use binrw::{BinReaderExt, BinWriterExt};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+/// Size of GMDT in saves made before Secunda's phase was added to it
+const GMDT_SIZE_NO_SECUNDA: usize = 92;
+/// Size of GMDT with Secunda's phase
+const GMDT_SIZE: usize = 96;
+
+/// Kinds of weather
+#[derive(Debug, Copy, Clone, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
+#[repr(u32)]
+pub enum Weather {
+    Clear,
+    Cloudy,
+    Foggy,
+    Overcast,
+    Rain,
+    Thunder,
+    Ash,
+    Blight,
+    /// Added by Bloodmoon
+    Snow,
+    /// Added by Bloodmoon
+    Blizzard,
+}
+
+/// The state of the game world when a save was made, including the weather
+///
+/// Only the low byte of the weather transition and moon phases is meaningful; the rest of their
+/// bytes sometimes hold garbage. The garbage is preserved so that an unmodified record is written
+/// back exactly as it was read.
+#[derive(Debug, Clone)]
+pub struct GameState {
+    current_cell: String,
+    fog_color: u32,
+    fog_density: f32,
+    pub current_weather: Weather,
+    pub next_weather: Weather,
+    weather_transition: u32,
+    /// Game hour at which the weather will next change
+    pub time_of_next_transition: f32,
+    masser_phase: u32,
+    secunda_phase: Option<u32>,
+}
+
+impl GameState {
+    /// Gets the name of the cell the player was in
+    pub fn current_cell(&self) -> &str {
+        self.current_cell.as_str()
+    }
+
+    /// Sets the name of the cell the player is in
+    ///
+    /// # Errors
+    ///
+    /// Fails if the length of the name exceeds [`CELL_LENGTH`].
+    ///
+    /// [`CELL_LENGTH`]: constant.CELL_LENGTH.html
+    pub fn set_current_cell(&mut self, cell: String) -> Result<(), TesError> {
+        check_size(&cell, CELL_LENGTH, "cell name too long")?;
+        self.current_cell = cell;
+        Ok(())
+    }
+
+    /// Gets how far the weather is through changing to the next weather, from 0 to 100
+    pub fn weather_transition(&self) -> u8 {
+        self.weather_transition as u8
+    }
+
+    /// Gets the phase of Masser
+    pub fn masser_phase(&self) -> u8 {
+        self.masser_phase as u8
+    }
+
+    /// Gets the phase of Secunda, if the save has it
+    pub fn secunda_phase(&self) -> Option<u8> {
+        self.secunda_phase.map(|p| p as u8)
+    }
+
+    fn read_weather<T: Read + Seek>(mut reader: T) -> Result<Weather, TesError> {
+        let value: u32 = reader.read_le()?;
+        Weather::try_from(value).map_err(|e| decode_failed_because("Invalid weather", e))
+    }
+}
+
+impl Form for GameState {
+    type Field = Tes3Field;
+    type Record = Tes3Record;
+
+    const RECORD_TYPE: &'static [u8; 4] = b"GAME";
+
+    /// Reads the game state from a raw record
+    ///
+    /// # Errors
+    ///
+    /// Fails if the provided record is not a `b"GAME"` record or if the record data is invalid.
+    fn read(record: &Tes3Record) -> Result<GameState, TesError> {
+        GameState::assert(record)?;
+
+        let mut state = None;
+        for field in record.iter() {
+            match field.name() {
+                b"GMDT" => {
+                    let size = field.get().len();
+                    if size != GMDT_SIZE && size != GMDT_SIZE_NO_SECUNDA {
+                        return Err(decode_failed(format!("Unexpected GMDT size {}", size)));
+                    }
+
+                    let mut reader = field.reader();
+                    state = Some(GameState {
+                        current_cell: read_string::<CELL_LENGTH, _>(&mut reader)?,
+                        fog_color: reader.read_le()?,
+                        fog_density: reader.read_le()?,
+                        current_weather: GameState::read_weather(&mut reader)?,
+                        next_weather: GameState::read_weather(&mut reader)?,
+                        weather_transition: reader.read_le()?,
+                        time_of_next_transition: reader.read_le()?,
+                        masser_phase: reader.read_le()?,
+                        secunda_phase: if size == GMDT_SIZE {
+                            Some(reader.read_le()?)
+                        } else {
+                            None
+                        },
+                    });
+                }
+                _ => {
+                    return Err(decode_failed(format!(
+                        "Unexpected field {} in GAME record",
+                        field.name_as_str()
+                    )))
+                }
+            }
+        }
+
+        state.ok_or_else(|| decode_failed("Missing GMDT field in GAME record"))
+    }
+
+    fn write(&self, record: &mut Tes3Record) -> Result<(), TesError> {
+        GameState::assert(record)?;
+
+        record.clear();
+
+        let mut buf = Vec::with_capacity(GMDT_SIZE);
+        let mut writer = Cursor::new(&mut buf);
+        write_str::<CELL_LENGTH, _>(&self.current_cell, &mut writer)?;
+        writer.write_le(&self.fog_color)?;
+        writer.write_le(&self.fog_density)?;
+        writer.write_le(&u32::from(self.current_weather))?;
+        writer.write_le(&u32::from(self.next_weather))?;
+        writer.write_le(&self.weather_transition)?;
+        writer.write_le(&self.time_of_next_transition)?;
+        writer.write_le(&self.masser_phase)?;
+        if let Some(secunda_phase) = self.secunda_phase {
+            writer.write_le(&secunda_phase)?;
+        }
+        record.add_field(Tes3Field::new(b"GMDT", buf)?);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_gmdt(secunda_phase: Option<u32>) -> Vec<u8> {
+        let mut buf = make_str_vec("Solstheim, Skaal Village", CELL_LENGTH);
+        buf.extend_from_slice(&0x808080u32.to_le_bytes());
+        buf.extend_from_slice(&0.5f32.to_le_bytes());
+        buf.extend_from_slice(&8u32.to_le_bytes());
+        buf.extend_from_slice(&9u32.to_le_bytes());
+        // garbage in the upper bytes should be ignored but kept
+        buf.extend_from_slice(&0x1234_0032u32.to_le_bytes());
+        buf.extend_from_slice(&14.5f32.to_le_bytes());
+        buf.extend_from_slice(&3u32.to_le_bytes());
+        if let Some(phase) = secunda_phase {
+            buf.extend_from_slice(&phase.to_le_bytes());
+        }
+        buf
+    }
+
+    #[test]
+    fn read_write_game_state() {
+        for secunda_phase in [Some(5), None] {
+            let gmdt = make_gmdt(secunda_phase);
+            let mut record = Tes3Record::new(b"GAME");
+            record.add_field(Tes3Field::new(b"GMDT", gmdt.clone()).unwrap());
+
+            let state = GameState::read(&record).unwrap();
+            assert_eq!(state.current_cell(), "Solstheim, Skaal Village");
+            assert_eq!(state.current_weather, Weather::Snow);
+            assert_eq!(state.next_weather, Weather::Blizzard);
+            assert_eq!(state.weather_transition(), 50);
+            assert_eq!(state.masser_phase(), 3);
+            assert_eq!(state.secunda_phase(), secunda_phase.map(|p| p as u8));
+
+            let mut new_record = Tes3Record::new(b"GAME");
+            state.write(&mut new_record).unwrap();
+            assert_eq!(new_record.iter().next().unwrap().get(), gmdt.as_slice());
+        }
+
+        let mut record = Tes3Record::new(b"GAME");
+        record.add_field(Tes3Field::new(b"GMDT", vec![0; 40]).unwrap());
+        assert!(GameState::read(&record).is_err());
+    }
+}
@@ -2,15 +2,53 @@ use super::*;
 use crate::Form;
 
 /// Game setting variant - string, int, or float
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum GameSettingValue {
     String(String),
     Int(i32),
     Float(f32),
 }
 
+impl GameSettingValue {
+    /// Gets the prefix that the ID of a setting of this type starts with
+    fn id_prefix(&self) -> char {
+        match self {
+            GameSettingValue::String(_) => 's',
+            GameSettingValue::Int(_) => 'i',
+            GameSettingValue::Float(_) => 'f',
+        }
+    }
+}
+
+impl From<String> for GameSettingValue {
+    fn from(value: String) -> Self {
+        GameSettingValue::String(value)
+    }
+}
+
+impl From<&str> for GameSettingValue {
+    fn from(value: &str) -> Self {
+        GameSettingValue::String(String::from(value))
+    }
+}
+
+impl From<i32> for GameSettingValue {
+    fn from(value: i32) -> Self {
+        GameSettingValue::Int(value)
+    }
+}
+
+impl From<f32> for GameSettingValue {
+    fn from(value: f32) -> Self {
+        GameSettingValue::Float(value)
+    }
+}
+
 /// Game setting
-#[derive(Debug)]
+///
+/// The first letter of a game setting's ID indicates the type of its value: `s` for strings, `i`
+/// for integers, and `f` for floats.
+#[derive(Debug, Clone)]
 pub struct GameSetting {
     id: String,
     value: GameSettingValue,
@@ -55,12 +93,65 @@ impl Form for GameSetting {
         Ok(setting)
     }
 
-    fn write(&self, _: &mut Tes3Record) -> Result<(), TesError> {
-        unimplemented!()
+    fn write(&self, record: &mut Tes3Record) -> Result<(), TesError> {
+        GameSetting::assert(record)?;
+
+        record.clear();
+
+        record.add_field(Tes3Field::new_string(b"NAME", self.id.clone())?);
+        record.add_field(match self.value {
+            GameSettingValue::String(ref value) => Tes3Field::new_string(b"STRV", value.clone())?,
+            GameSettingValue::Int(value) => Tes3Field::new_i32(b"INTV", value),
+            GameSettingValue::Float(value) => Tes3Field::new_f32(b"FLTV", value),
+        });
+
+        Ok(())
     }
 }
 
 impl GameSetting {
+    /// Creates a new game setting
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ID doesn't start with the prefix for the value's type.
+    pub fn new(id: String, value: GameSettingValue) -> Result<GameSetting, TesError> {
+        GameSetting::check_type(&id, &value)?;
+        Ok(GameSetting { id, value })
+    }
+
+    fn check_type(id: &str, value: &GameSettingValue) -> Result<(), TesError> {
+        if id.starts_with(value.id_prefix()) {
+            Ok(())
+        } else {
+            Err(TesError::RequirementFailed(format!(
+                "Game setting {} can't hold a value of type {:?}",
+                id, value
+            )))
+        }
+    }
+
+    /// Gets the setting's ID
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    /// Gets the setting's value
+    pub fn value(&self) -> &GameSettingValue {
+        &self.value
+    }
+
+    /// Sets the setting's value
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value is not of the type indicated by the setting's ID.
+    pub fn set_value(&mut self, value: GameSettingValue) -> Result<(), TesError> {
+        GameSetting::check_type(&self.id, &value)?;
+        self.value = value;
+        Ok(())
+    }
+
     /// Gets the value as a float if appropriate
     pub fn get_float(&self) -> Option<f32> {
         if let GameSettingValue::Float(value) = self.value {
@@ -88,3 +179,23 @@ impl GameSetting {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_game_setting() {
+        let mut setting =
+            GameSetting::new(String::from("fJumpAcroMultiplier"), 4.5f32.into()).unwrap();
+        let mut record = Tes3Record::new(b"GMST");
+        setting.write(&mut record).unwrap();
+        let new_setting = GameSetting::read(&record).unwrap();
+        assert_eq!(new_setting.id(), "fJumpAcroMultiplier");
+        assert_eq!(new_setting.get_float(), Some(4.5));
+
+        assert!(setting.set_value(GameSettingValue::Int(5)).is_err());
+        assert!(GameSetting::new(String::from("sLevelUpMsg"), "".into()).is_ok());
+        assert!(GameSetting::new(String::from("iLevelupTotal"), 1.5f32.into()).is_err());
+    }
+}
@@ -0,0 +1,477 @@
+use std::io::{Cursor, Read};
+
+use crate::tes3::{Tes3Field, Tes3Record};
+use crate::{
+    check_range, decode_failed, ExtraFields, Field, FieldOrder, Form, GridCoords, Record, TesError,
+};
+
+use binrw::{BinReaderExt, BinWriterExt};
+
+/// Number of vertices along each side of a cell's landscape
+pub const LAND_SIZE: usize = 65;
+/// Number of texture tiles along each side of a cell's landscape
+pub const LAND_TEXTURE_SIZE: usize = 16;
+/// Number of game units in each step of a landscape height map
+pub const LAND_HEIGHT_SCALE: f32 = 8.;
+
+const NUM_VERTICES: usize = LAND_SIZE * LAND_SIZE;
+const NUM_TEXTURES: usize = LAND_TEXTURE_SIZE * LAND_TEXTURE_SIZE;
+/// Textures are stored in 4x4 blocks of 4x4 tiles
+const TEXTURE_BLOCK_SIZE: usize = 4;
+/// Size of VHGT: the height offset, one delta per vertex, and 3 bytes of padding
+const VHGT_SIZE: usize = 4 + NUM_VERTICES + 3;
+
+/// Landscape flag for cells with VNML, VHGT, and WNAM fields
+const LAND_HAS_HEIGHTS: u32 = 0x1;
+/// Landscape flag for cells with a VCLR field
+const LAND_HAS_COLORS: u32 = 0x2;
+/// Landscape flag for cells with a VTEX field
+const LAND_HAS_TEXTURES: u32 = 0x4;
+
+/// A height map as it's stored in VHGT
+///
+/// Each vertex's height is stored as the difference from the previous vertex in its row, or for
+/// the first vertex in a row, from the first vertex in the previous row.
+#[derive(Debug, Clone)]
+struct HeightMap {
+    offset: f32,
+    deltas: Vec<i8>,
+    padding: [u8; 3],
+}
+
+/// The terrain of an exterior cell
+///
+/// Vertex data is stored row by row, starting from the south-west corner of the cell, with
+/// [`LAND_SIZE`] vertices per row; the vertices on each edge are shared with the neighboring cell.
+/// Texture indices are likewise row by row, with [`LAND_TEXTURE_SIZE`] tiles per row.
+///
+/// The low-resolution height map in WNAM, which is used for the world map, is preserved as-is and
+/// isn't updated when the heights change.
+///
+/// [`LAND_SIZE`]: constant.LAND_SIZE.html
+/// [`LAND_TEXTURE_SIZE`]: constant.LAND_TEXTURE_SIZE.html
+#[derive(Debug, Default)]
+pub struct Landscape {
+    grid: GridCoords,
+    flags: u32,
+    normals: Option<Vec<[i8; 3]>>,
+    heights: Option<HeightMap>,
+    colors: Option<Vec<[u8; 3]>>,
+    textures: Option<Vec<u16>>,
+    extra_fields: Vec<Tes3Field>,
+    field_order: FieldOrder,
+}
+
+fn check_count(len: usize, expected: usize, what: &str) -> Result<(), TesError> {
+    if len != expected {
+        return Err(TesError::RequirementFailed(format!(
+            "Landscape must have {} {}, got {}",
+            expected, what, len
+        )));
+    }
+
+    Ok(())
+}
+
+/// Gets the position in VTEX of the texture tile at a row-major index
+fn texture_position(index: usize) -> usize {
+    let (y, x) = (index / LAND_TEXTURE_SIZE, index % LAND_TEXTURE_SIZE);
+    let block = (y / TEXTURE_BLOCK_SIZE) * TEXTURE_BLOCK_SIZE + x / TEXTURE_BLOCK_SIZE;
+    let tile = (y % TEXTURE_BLOCK_SIZE) * TEXTURE_BLOCK_SIZE + x % TEXTURE_BLOCK_SIZE;
+    block * TEXTURE_BLOCK_SIZE * TEXTURE_BLOCK_SIZE + tile
+}
+
+impl Landscape {
+    /// Creates a landscape with no terrain data for the exterior cell at the given grid coordinates
+    pub fn new(grid: GridCoords) -> Landscape {
+        Landscape {
+            grid,
+            ..Landscape::default()
+        }
+    }
+
+    /// Gets the grid coordinates of the cell this landscape belongs to
+    pub fn grid(&self) -> GridCoords {
+        self.grid
+    }
+
+    pub fn set_grid(&mut self, grid: GridCoords) {
+        self.grid = grid;
+    }
+
+    /// Gets the height of each vertex in game units
+    pub fn heights(&self) -> Option<Vec<f32>> {
+        let height_map = self.heights.as_ref()?;
+        let mut heights = Vec::with_capacity(NUM_VERTICES);
+        let mut row_height = height_map.offset;
+        for row in height_map.deltas.chunks(LAND_SIZE) {
+            row_height += row[0] as f32;
+            let mut height = row_height;
+            heights.push(height * LAND_HEIGHT_SCALE);
+            for delta in &row[1..] {
+                height += *delta as f32;
+                heights.push(height * LAND_HEIGHT_SCALE);
+            }
+        }
+
+        Some(heights)
+    }
+
+    /// Sets the height of each vertex in game units
+    ///
+    /// Heights are stored in steps of [`LAND_HEIGHT_SCALE`] units relative to the first vertex,
+    /// so they're rounded to the nearest step.
+    ///
+    /// # Errors
+    ///
+    /// Fails if there isn't exactly one height per vertex or if neighboring vertices differ by
+    /// more than 127 steps, which the format can't represent.
+    ///
+    /// [`LAND_HEIGHT_SCALE`]: constant.LAND_HEIGHT_SCALE.html
+    pub fn set_heights(&mut self, heights: Option<&[f32]>) -> Result<(), TesError> {
+        let heights = match heights {
+            Some(heights) => heights,
+            None => {
+                self.heights = None;
+                return Ok(());
+            }
+        };
+
+        check_count(heights.len(), NUM_VERTICES, "heights")?;
+
+        let offset = heights[0] / LAND_HEIGHT_SCALE;
+        let steps: Vec<i32> = heights
+            .iter()
+            .map(|h| (h / LAND_HEIGHT_SCALE - offset).round() as i32)
+            .collect();
+        let mut deltas = Vec::with_capacity(NUM_VERTICES);
+        for (i, step) in steps.iter().enumerate() {
+            let previous = if i % LAND_SIZE != 0 {
+                steps[i - 1]
+            } else if i >= LAND_SIZE {
+                steps[i - LAND_SIZE]
+            } else {
+                0
+            };
+
+            let delta = step - previous;
+            check_range(
+                delta,
+                i8::MIN as i32,
+                i8::MAX as i32,
+                "height difference between neighboring vertices too large",
+            )?;
+            deltas.push(delta as i8);
+        }
+
+        let padding = self.heights.as_ref().map_or([0; 3], |h| h.padding);
+        self.heights = Some(HeightMap {
+            offset,
+            deltas,
+            padding,
+        });
+        Ok(())
+    }
+
+    /// Gets the normal vector of each vertex
+    pub fn normals(&self) -> Option<&[[i8; 3]]> {
+        self.normals.as_deref()
+    }
+
+    /// Sets the normal vector of each vertex
+    ///
+    /// # Errors
+    ///
+    /// Fails if there isn't exactly one normal per vertex.
+    pub fn set_normals(&mut self, normals: Option<Vec<[i8; 3]>>) -> Result<(), TesError> {
+        if let Some(ref normals) = normals {
+            check_count(normals.len(), NUM_VERTICES, "normals")?;
+        }
+        self.normals = normals;
+        Ok(())
+    }
+
+    /// Gets the RGB color of each vertex
+    pub fn colors(&self) -> Option<&[[u8; 3]]> {
+        self.colors.as_deref()
+    }
+
+    /// Sets the RGB color of each vertex
+    ///
+    /// # Errors
+    ///
+    /// Fails if there isn't exactly one color per vertex.
+    pub fn set_colors(&mut self, colors: Option<Vec<[u8; 3]>>) -> Result<(), TesError> {
+        if let Some(ref colors) = colors {
+            check_count(colors.len(), NUM_VERTICES, "colors")?;
+        }
+        self.colors = colors;
+        Ok(())
+    }
+
+    /// Gets the texture index of each tile
+    ///
+    /// An index of 0 is the default texture; any other index is one more than the index of an
+    /// LTEX record in the plugin.
+    pub fn textures(&self) -> Option<&[u16]> {
+        self.textures.as_deref()
+    }
+
+    /// Sets the texture index of each tile
+    ///
+    /// # Errors
+    ///
+    /// Fails if there isn't exactly one index per tile.
+    pub fn set_textures(&mut self, textures: Option<Vec<u16>>) -> Result<(), TesError> {
+        if let Some(ref textures) = textures {
+            check_count(textures.len(), NUM_TEXTURES, "texture indices")?;
+        }
+        self.textures = textures;
+        Ok(())
+    }
+
+    fn update_flags(&self) -> u32 {
+        let mut flags = self.flags & !(LAND_HAS_HEIGHTS | LAND_HAS_COLORS | LAND_HAS_TEXTURES);
+        if self.heights.is_some() && self.normals.is_some() {
+            flags |= LAND_HAS_HEIGHTS;
+        }
+        if self.colors.is_some() {
+            flags |= LAND_HAS_COLORS;
+        }
+        if self.textures.is_some() {
+            flags |= LAND_HAS_TEXTURES;
+        }
+        flags
+    }
+}
+
+impl ExtraFields for Landscape {
+    type Field = Tes3Field;
+
+    fn extra_fields(&self) -> &[Tes3Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes3Field> {
+        &mut self.extra_fields
+    }
+}
+
+impl Form for Landscape {
+    type Field = Tes3Field;
+    type Record = Tes3Record;
+
+    const RECORD_TYPE: &'static [u8; 4] = b"LAND";
+
+    /// Reads a landscape from a raw record
+    ///
+    /// # Errors
+    ///
+    /// Fails if the record is not a LAND record or if any of its vertex or texture data is the
+    /// wrong size.
+    fn read(record: &Tes3Record) -> Result<Landscape, TesError> {
+        Landscape::assert(record)?;
+
+        let mut landscape = Landscape::default();
+        for field in record.iter() {
+            match field.name() {
+                b"INTV" => {
+                    let mut reader = field.reader();
+                    landscape.grid.x = reader.read_le()?;
+                    landscape.grid.y = reader.read_le()?;
+                }
+                b"DATA" => landscape.flags = field.get_u32()?,
+                b"VNML" => {
+                    let data = field.get();
+                    if data.len() != NUM_VERTICES * 3 {
+                        return Err(decode_failed(format!(
+                            "Unexpected VNML size {}",
+                            data.len()
+                        )));
+                    }
+
+                    landscape.normals = Some(
+                        data.chunks(3)
+                            .map(|c| [c[0] as i8, c[1] as i8, c[2] as i8])
+                            .collect(),
+                    );
+                }
+                b"VHGT" => {
+                    let size = field.get().len();
+                    if size != VHGT_SIZE {
+                        return Err(decode_failed(format!("Unexpected VHGT size {}", size)));
+                    }
+
+                    let mut reader = field.reader();
+                    let offset = reader.read_le()?;
+                    let mut deltas = vec![0u8; NUM_VERTICES];
+                    reader.read_exact(&mut deltas)?;
+                    let mut padding = [0u8; 3];
+                    reader.read_exact(&mut padding)?;
+                    landscape.heights = Some(HeightMap {
+                        offset,
+                        deltas: deltas.into_iter().map(|d| d as i8).collect(),
+                        padding,
+                    });
+                }
+                b"VCLR" => {
+                    let data = field.get();
+                    if data.len() != NUM_VERTICES * 3 {
+                        return Err(decode_failed(format!(
+                            "Unexpected VCLR size {}",
+                            data.len()
+                        )));
+                    }
+
+                    landscape.colors = Some(data.chunks(3).map(|c| [c[0], c[1], c[2]]).collect());
+                }
+                b"VTEX" => {
+                    let size = field.get().len();
+                    if size != NUM_TEXTURES * 2 {
+                        return Err(decode_failed(format!("Unexpected VTEX size {}", size)));
+                    }
+
+                    let mut reader = field.reader();
+                    let mut stored = [0u16; NUM_TEXTURES];
+                    for texture in stored.iter_mut() {
+                        *texture = reader.read_le()?;
+                    }
+                    landscape.textures = Some(
+                        (0..NUM_TEXTURES)
+                            .map(|i| stored[texture_position(i)])
+                            .collect(),
+                    );
+                }
+                _ => landscape.extra_fields.push(field.clone()),
+            }
+        }
+
+        landscape.field_order = FieldOrder::from_record(record);
+
+        Ok(landscape)
+    }
+
+    fn write(&self, record: &mut Tes3Record) -> Result<(), TesError> {
+        Landscape::assert(record)?;
+
+        record.clear();
+
+        let mut buf = Vec::with_capacity(8);
+        let mut cursor = Cursor::new(&mut buf);
+        cursor.write_le(&self.grid.x)?;
+        cursor.write_le(&self.grid.y)?;
+        record.add_field(Tes3Field::new(b"INTV", buf)?);
+        record.add_field(Tes3Field::new_u32(b"DATA", self.update_flags()));
+
+        if let Some(ref normals) = self.normals {
+            let buf = normals.iter().flatten().map(|n| *n as u8).collect();
+            record.add_field(Tes3Field::new(b"VNML", buf)?);
+        }
+
+        if let Some(ref heights) = self.heights {
+            let mut buf = Vec::with_capacity(VHGT_SIZE);
+            buf.extend(heights.offset.to_le_bytes());
+            buf.extend(heights.deltas.iter().map(|d| *d as u8));
+            buf.extend(heights.padding);
+            record.add_field(Tes3Field::new(b"VHGT", buf)?);
+        }
+
+        if let Some(ref colors) = self.colors {
+            let buf = colors.iter().flatten().copied().collect();
+            record.add_field(Tes3Field::new(b"VCLR", buf)?);
+        }
+
+        if let Some(ref textures) = self.textures {
+            let mut stored = [0u16; NUM_TEXTURES];
+            for (i, texture) in textures.iter().enumerate() {
+                stored[texture_position(i)] = *texture;
+            }
+
+            let buf = stored.iter().flat_map(|t| t.to_le_bytes()).collect();
+            record.add_field(Tes3Field::new(b"VTEX", buf)?);
+        }
+
+        for field in &self.extra_fields {
+            record.add_field(field.clone());
+        }
+        record.sort_fields(&self.field_order);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_write_heights() {
+        let mut landscape = Landscape::new(GridCoords::new(-3, 5));
+        // a slope rising to the north-east from below sea level
+        let heights: Vec<f32> = (0..NUM_VERTICES)
+            .map(|i| ((i / LAND_SIZE + i % LAND_SIZE) as f32 * 2. - 40.) * LAND_HEIGHT_SCALE)
+            .collect();
+        landscape.set_heights(Some(&heights)).unwrap();
+        landscape
+            .set_normals(Some(vec![[0, 0, 127]; NUM_VERTICES]))
+            .unwrap();
+
+        let mut record = Tes3Record::new(b"LAND");
+        landscape.write(&mut record).unwrap();
+
+        let landscape = Landscape::read(&record).unwrap();
+        assert_eq!(landscape.grid(), GridCoords::new(-3, 5));
+        assert_eq!(landscape.heights().unwrap(), heights);
+        assert_eq!(landscape.normals().unwrap()[LAND_SIZE], [0, 0, 127]);
+        assert!(landscape.colors().is_none());
+
+        let data = record.iter().find(|f| f.name() == b"DATA").unwrap();
+        assert_eq!(data.get_u32().unwrap(), LAND_HAS_HEIGHTS);
+        let vhgt = record.iter().find(|f| f.name() == b"VHGT").unwrap();
+        assert_eq!(vhgt.get().len(), VHGT_SIZE);
+    }
+
+    #[test]
+    fn height_difference_too_large() {
+        let mut landscape = Landscape::new(GridCoords::new(0, 0));
+        let mut heights = vec![0.; NUM_VERTICES];
+        heights[1] = 128. * LAND_HEIGHT_SCALE;
+        assert!(landscape.set_heights(Some(&heights)).is_err());
+        assert!(landscape.set_heights(Some(&heights[..10])).is_err());
+        assert!(landscape.heights().is_none());
+    }
+
+    #[test]
+    fn read_write_textures() {
+        // the second tile of the first row is the second tile of the first block, and the first
+        // tile of the second row is the fifth tile of the first block
+        let mut vtex = vec![0u8; NUM_TEXTURES * 2];
+        vtex[2] = 7;
+        vtex[8] = 9;
+        // the first tile of the second block is the fifth tile of the first row
+        vtex[32] = 3;
+
+        let mut record = Tes3Record::new(b"LAND");
+        record.add_field(Tes3Field::new(b"INTV", vec![0; 8]).unwrap());
+        record.add_field(Tes3Field::new_u32(b"DATA", LAND_HAS_TEXTURES));
+        record.add_field(Tes3Field::new(b"VTEX", vtex.clone()).unwrap());
+
+        let mut landscape = Landscape::read(&record).unwrap();
+        let textures = landscape.textures().unwrap();
+        assert_eq!(textures[1], 7);
+        assert_eq!(textures[LAND_TEXTURE_SIZE], 9);
+        assert_eq!(textures[4], 3);
+
+        landscape
+            .set_colors(Some(vec![[255, 255, 255]; NUM_VERTICES]))
+            .unwrap();
+        landscape.write(&mut record).unwrap();
+
+        let names: Vec<_> = record.iter().map(|f| f.name_as_str()).collect();
+        assert_eq!(names, ["INTV", "DATA", "VCLR", "VTEX"]);
+        let vtex_field = record.iter().find(|f| f.name() == b"VTEX").unwrap();
+        assert_eq!(vtex_field.get(), vtex.as_slice());
+        let data = record.iter().find(|f| f.name() == b"DATA").unwrap();
+        assert_eq!(data.get_u32().unwrap(), LAND_HAS_COLORS | LAND_HAS_TEXTURES);
+    }
+}
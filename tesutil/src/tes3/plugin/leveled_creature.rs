@@ -0,0 +1,184 @@
+use crate::tes3::{LeveledEntry, LeveledList, Tes3Field, Tes3Record};
+use crate::{decode_failed, ExtraFields, Field, FieldOrder, Form, Record, TesError};
+use bitflags::bitflags;
+
+bitflags! {
+    #[derive(Default)]
+    struct LeveledCreatureFlags: u32 {
+        const ALL_LEVELS = 0x1;
+    }
+}
+
+/// A list of creatures the game chooses from based on the player's level
+#[derive(Debug, Default)]
+pub struct LeveledCreature {
+    id: String,
+    flags: LeveledCreatureFlags,
+    chance_none: u8,
+    entries: Vec<LeveledEntry>,
+    extra_fields: Vec<Tes3Field>,
+    field_order: FieldOrder,
+}
+
+impl LeveledCreature {
+    /// Creates a new, empty leveled creature list
+    pub fn new(id: String) -> LeveledCreature {
+        LeveledCreature {
+            id,
+            ..LeveledCreature::default()
+        }
+    }
+}
+
+impl LeveledList for LeveledCreature {
+    fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+
+    fn chance_none(&self) -> u8 {
+        self.chance_none
+    }
+
+    fn set_chance_none(&mut self, chance_none: u8) {
+        self.chance_none = chance_none;
+    }
+
+    fn calc_from_all_levels(&self) -> bool {
+        self.flags.contains(LeveledCreatureFlags::ALL_LEVELS)
+    }
+
+    fn set_calc_from_all_levels(&mut self, value: bool) {
+        self.flags.set(LeveledCreatureFlags::ALL_LEVELS, value);
+    }
+
+    fn entries(&self) -> &[LeveledEntry] {
+        self.entries.as_slice()
+    }
+
+    fn entries_mut(&mut self) -> &mut Vec<LeveledEntry> {
+        &mut self.entries
+    }
+
+    fn merge(&mut self, other: &Self) {
+        LeveledEntry::merge_all(&mut self.entries, &other.entries);
+        self.chance_none = other.chance_none;
+        self.flags = other.flags;
+    }
+}
+
+impl ExtraFields for LeveledCreature {
+    type Field = Tes3Field;
+
+    fn extra_fields(&self) -> &[Tes3Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes3Field> {
+        &mut self.extra_fields
+    }
+}
+
+impl Form for LeveledCreature {
+    type Field = Tes3Field;
+    type Record = Tes3Record;
+
+    const RECORD_TYPE: &'static [u8; 4] = b"LEVC";
+
+    /// Reads a leveled creature list from a raw record
+    ///
+    /// # Errors
+    ///
+    /// Fails if the record is not a LEVC record or if the number of entries doesn't match the
+    /// count in the INDX field.
+    fn read(record: &Tes3Record) -> Result<LeveledCreature, TesError> {
+        LeveledCreature::assert(record)?;
+
+        let mut list = LeveledCreature::default();
+        let mut count = None;
+        for field in record.iter() {
+            if LeveledEntry::read_field(&mut list.entries, field, b"CNAM")? {
+                continue;
+            }
+
+            match field.name() {
+                b"NAME" => list.id = String::from(field.get_zstring()?),
+                b"DATA" => {
+                    list.flags = LeveledCreatureFlags::from_bits(field.get_u32()?)
+                        .ok_or_else(|| decode_failed("Invalid leveled creature flags"))?
+                }
+                b"NNAM" => list.chance_none = field.get_u8()?,
+                b"INDX" => count = Some(field.get_u32()?),
+                _ => list.extra_fields.push(field.clone()),
+            }
+        }
+
+        LeveledEntry::check_count(&list.entries, count, &list.id)?;
+        list.field_order = FieldOrder::from_record(record);
+
+        Ok(list)
+    }
+
+    fn write(&self, record: &mut Tes3Record) -> Result<(), TesError> {
+        LeveledCreature::assert(record)?;
+
+        record.clear();
+
+        record.add_field(Tes3Field::new_zstring(b"NAME", self.id.clone())?);
+        record.add_field(Tes3Field::new_u32(b"DATA", self.flags.bits));
+        record.add_field(Tes3Field::new_u8(b"NNAM", self.chance_none));
+        LeveledEntry::write_all(&self.entries, record, b"CNAM")?;
+
+        for field in &self.extra_fields {
+            record.add_field(field.clone());
+        }
+        record.sort_fields(&self.field_order);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_write_leveled_creature() {
+        let mut record = Tes3Record::new(b"LEVC");
+        record
+            .add_field(Tes3Field::new_zstring(b"NAME", String::from("ex_ashland_lev+0")).unwrap());
+        record.add_field(Tes3Field::new_u32(b"DATA", 1));
+        record.add_field(Tes3Field::new_u8(b"NNAM", 0));
+        record.add_field(Tes3Field::new_u32(b"INDX", 2));
+        for (id, level) in [("cliff racer", 1u16), ("kagouti", 3)] {
+            record.add_field(Tes3Field::new_zstring(b"CNAM", String::from(id)).unwrap());
+            record.add_field(Tes3Field::new_u16(b"INTV", level));
+        }
+
+        let mut list = LeveledCreature::read(&record).unwrap();
+        assert!(list.calc_from_all_levels());
+        assert_eq!(
+            list.entries()[1],
+            LeveledEntry::new(3, String::from("kagouti"))
+        );
+
+        let mut other = LeveledCreature::new(String::from("ex_ashland_lev+0"));
+        other.add_entry(2, String::from("nix-hound"));
+        other.set_chance_none(50);
+        list.merge(&other);
+        assert!(!list.calc_from_all_levels());
+
+        list.write(&mut record).unwrap();
+        let list = LeveledCreature::read(&record).unwrap();
+        assert_eq!(list.chance_none(), 50);
+        let ids: Vec<_> = list.entries().iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["cliff racer", "nix-hound", "kagouti"]);
+
+        let mut record = Tes3Record::new(b"LEVC");
+        record.add_field(Tes3Field::new_u16(b"INTV", 1));
+        assert!(LeveledCreature::read(&record).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::tes3::{LeveledEntry, LeveledList, Tes3Field, Tes3Record};
+use crate::{decode_failed, ExtraFields, Field, FieldOrder, Form, Record, TesError};
+use bitflags::bitflags;
+
+bitflags! {
+    #[derive(Default)]
+    struct LeveledItemFlags: u32 {
+        const ALL_LEVELS = 0x1;
+        const EACH_ITEM = 0x2;
+    }
+}
+
+/// A list of items the game chooses from based on the player's level
+#[derive(Debug, Default)]
+pub struct LeveledItem {
+    id: String,
+    flags: LeveledItemFlags,
+    chance_none: u8,
+    entries: Vec<LeveledEntry>,
+    extra_fields: Vec<Tes3Field>,
+    field_order: FieldOrder,
+}
+
+impl LeveledItem {
+    /// Creates a new, empty leveled item list
+    pub fn new(id: String) -> LeveledItem {
+        LeveledItem {
+            id,
+            ..LeveledItem::default()
+        }
+    }
+
+    /// Is an item chosen separately for each item in the count of the reference using this list?
+    pub fn calc_for_each_item(&self) -> bool {
+        self.flags.contains(LeveledItemFlags::EACH_ITEM)
+    }
+
+    pub fn set_calc_for_each_item(&mut self, value: bool) {
+        self.flags.set(LeveledItemFlags::EACH_ITEM, value);
+    }
+}
+
+impl LeveledList for LeveledItem {
+    fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+
+    fn chance_none(&self) -> u8 {
+        self.chance_none
+    }
+
+    fn set_chance_none(&mut self, chance_none: u8) {
+        self.chance_none = chance_none;
+    }
+
+    fn calc_from_all_levels(&self) -> bool {
+        self.flags.contains(LeveledItemFlags::ALL_LEVELS)
+    }
+
+    fn set_calc_from_all_levels(&mut self, value: bool) {
+        self.flags.set(LeveledItemFlags::ALL_LEVELS, value);
+    }
+
+    fn entries(&self) -> &[LeveledEntry] {
+        self.entries.as_slice()
+    }
+
+    fn entries_mut(&mut self) -> &mut Vec<LeveledEntry> {
+        &mut self.entries
+    }
+
+    fn merge(&mut self, other: &Self) {
+        LeveledEntry::merge_all(&mut self.entries, &other.entries);
+        self.chance_none = other.chance_none;
+        self.flags = other.flags;
+    }
+}
+
+impl ExtraFields for LeveledItem {
+    type Field = Tes3Field;
+
+    fn extra_fields(&self) -> &[Tes3Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes3Field> {
+        &mut self.extra_fields
+    }
+}
+
+impl Form for LeveledItem {
+    type Field = Tes3Field;
+    type Record = Tes3Record;
+
+    const RECORD_TYPE: &'static [u8; 4] = b"LEVI";
+
+    /// Reads a leveled item list from a raw record
+    ///
+    /// # Errors
+    ///
+    /// Fails if the record is not a LEVI record or if the number of entries doesn't match the
+    /// count in the INDX field.
+    fn read(record: &Tes3Record) -> Result<LeveledItem, TesError> {
+        LeveledItem::assert(record)?;
+
+        let mut list = LeveledItem::default();
+        let mut count = None;
+        for field in record.iter() {
+            if LeveledEntry::read_field(&mut list.entries, field, b"INAM")? {
+                continue;
+            }
+
+            match field.name() {
+                b"NAME" => list.id = String::from(field.get_zstring()?),
+                b"DATA" => {
+                    list.flags = LeveledItemFlags::from_bits(field.get_u32()?)
+                        .ok_or_else(|| decode_failed("Invalid leveled item flags"))?
+                }
+                b"NNAM" => list.chance_none = field.get_u8()?,
+                b"INDX" => count = Some(field.get_u32()?),
+                _ => list.extra_fields.push(field.clone()),
+            }
+        }
+
+        LeveledEntry::check_count(&list.entries, count, &list.id)?;
+        list.field_order = FieldOrder::from_record(record);
+
+        Ok(list)
+    }
+
+    fn write(&self, record: &mut Tes3Record) -> Result<(), TesError> {
+        LeveledItem::assert(record)?;
+
+        record.clear();
+
+        record.add_field(Tes3Field::new_zstring(b"NAME", self.id.clone())?);
+        record.add_field(Tes3Field::new_u32(b"DATA", self.flags.bits));
+        record.add_field(Tes3Field::new_u8(b"NNAM", self.chance_none));
+        LeveledEntry::write_all(&self.entries, record, b"INAM")?;
+
+        for field in &self.extra_fields {
+            record.add_field(field.clone());
+        }
+        record.sort_fields(&self.field_order);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_list(entries: &[(u16, &str)]) -> LeveledItem {
+        let mut list = LeveledItem::new(String::from("random_de_weapon"));
+        for (level, id) in entries {
+            list.add_entry(*level, String::from(*id));
+        }
+        list
+    }
+
+    #[test]
+    fn read_write_leveled_item() {
+        let mut list = make_list(&[(1, "chitin dagger"), (5, "iron saber")]);
+        list.set_chance_none(25);
+        list.set_calc_for_each_item(true);
+
+        let mut record = Tes3Record::new(b"LEVI");
+        list.write(&mut record).unwrap();
+        let field = |name: &[u8; 4]| record.iter().find(|f| f.name() == name).unwrap();
+        assert_eq!(field(b"DATA").get_u32().unwrap(), 0x2);
+        assert_eq!(field(b"NNAM").get_u8().unwrap(), 25);
+        assert_eq!(field(b"INDX").get_u32().unwrap(), 2);
+        let levels: Vec<_> = record
+            .iter()
+            .filter(|f| f.name() == b"INTV")
+            .map(|f| f.get_u16().unwrap())
+            .collect();
+        assert_eq!(levels, [1, 5]);
+
+        let list = LeveledItem::read(&record).unwrap();
+        assert_eq!(list.id(), "random_de_weapon");
+        assert_eq!(list.chance_none(), 25);
+        assert!(list.calc_for_each_item());
+        assert!(!list.calc_from_all_levels());
+        assert_eq!(
+            list.entries(),
+            [
+                LeveledEntry::new(1, String::from("chitin dagger")),
+                LeveledEntry::new(5, String::from("iron saber"))
+            ]
+        );
+
+        record.add_field(Tes3Field::new_zstring(b"INAM", String::from("extra")).unwrap());
+        assert!(LeveledItem::read(&record).is_err());
+    }
+
+    #[test]
+    fn merge_lists() {
+        let mut list = make_list(&[
+            (1, "chitin dagger"),
+            (1, "chitin dagger"),
+            (5, "iron saber"),
+        ]);
+        let mut other = make_list(&[
+            (1, "Chitin Dagger"),
+            (3, "steel dagger"),
+            (5, "iron saber"),
+            (5, "iron saber"),
+        ]);
+        other.set_chance_none(10);
+        other.set_calc_from_all_levels(true);
+
+        list.merge(&other);
+        assert_eq!(list.chance_none(), 10);
+        assert!(list.calc_from_all_levels());
+        let entries: Vec<_> = list
+            .entries()
+            .iter()
+            .map(|e| (e.level, e.id.as_str()))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                (1, "chitin dagger"),
+                (1, "chitin dagger"),
+                (3, "steel dagger"),
+                (5, "iron saber"),
+                (5, "iron saber")
+            ]
+        );
+
+        assert_eq!(list.remove_entries("IRON SABER"), 2);
+        assert_eq!(list.entries().len(), 3);
+    }
+}
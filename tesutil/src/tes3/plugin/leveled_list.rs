@@ -0,0 +1,151 @@
+use crate::tes3::{Tes3Field, Tes3Record};
+use crate::{checked_len, decode_failed, Field, Record, TesError};
+
+/// One entry in a leveled list: an object that can be chosen once the player reaches a level
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LeveledEntry {
+    pub level: u16,
+    pub id: String,
+}
+
+impl LeveledEntry {
+    pub fn new(level: u16, id: String) -> LeveledEntry {
+        LeveledEntry { level, id }
+    }
+
+    /// Does this entry refer to the same object at the same level as another entry?
+    ///
+    /// IDs are case-insensitive.
+    pub fn matches(&self, other: &LeveledEntry) -> bool {
+        self.level == other.level && self.id.eq_ignore_ascii_case(&other.id)
+    }
+
+    /// Reads a leveled list entry field into a list of entries
+    ///
+    /// `entry_name` is the name of the field holding entry IDs, which differs between list types.
+    /// Returns `false` if the field isn't part of an entry.
+    pub(crate) fn read_field(
+        entries: &mut Vec<LeveledEntry>,
+        field: &Tes3Field,
+        entry_name: &[u8; 4],
+    ) -> Result<bool, TesError> {
+        if field.name() == entry_name {
+            entries.push(LeveledEntry::new(0, String::from(field.get_zstring()?)));
+        } else if field.name() == b"INTV" {
+            match entries.last_mut() {
+                Some(entry) => entry.level = field.get_u16()?,
+                None => return Err(decode_failed("Orphaned INTV field")),
+            }
+        } else {
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    /// Writes the entry count and a list of entries to a record
+    pub(crate) fn write_all(
+        entries: &[LeveledEntry],
+        record: &mut Tes3Record,
+        entry_name: &[u8; 4],
+    ) -> Result<(), TesError> {
+        record.add_field(Tes3Field::new_u32(
+            b"INDX",
+            checked_len(entries.len(), "Too many leveled list entries")?,
+        ));
+        for entry in entries {
+            record.add_field(Tes3Field::new_zstring(entry_name, entry.id.clone())?);
+            record.add_field(Tes3Field::new_u16(b"INTV", entry.level));
+        }
+
+        Ok(())
+    }
+
+    /// Merges one version of a list's entries into another
+    ///
+    /// Each entry is kept as many times as it appears in whichever of the two lists has more
+    /// copies of it, and entries are sorted by level afterwards.
+    pub(crate) fn merge_all(entries: &mut Vec<LeveledEntry>, other: &[LeveledEntry]) {
+        let mut additions = vec![];
+        for (i, entry) in other.iter().enumerate() {
+            let theirs = other[..=i].iter().filter(|e| e.matches(entry)).count();
+            let ours = entries.iter().filter(|e| e.matches(entry)).count();
+            if theirs > ours {
+                additions.push(entry.clone());
+            }
+        }
+
+        entries.extend(additions);
+        entries.sort_by_key(|e| e.level);
+    }
+
+    /// Checks that the number of entries read matches the count in the INDX field
+    pub(crate) fn check_count(
+        entries: &[LeveledEntry],
+        count: Option<u32>,
+        list_id: &str,
+    ) -> Result<(), TesError> {
+        let count = count.unwrap_or(0) as usize;
+        if entries.len() != count {
+            Err(decode_failed(format!(
+                "Leveled list {} should have {} entries but has {}",
+                list_id,
+                count,
+                entries.len()
+            )))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A list of objects the game chooses from based on the player's level
+pub trait LeveledList {
+    /// Get this list's ID
+    fn id(&self) -> &str;
+
+    /// Set this list's ID
+    fn set_id(&mut self, id: String);
+
+    /// Get the percent chance that nothing is chosen from this list
+    fn chance_none(&self) -> u8;
+
+    /// Set the percent chance that nothing is chosen from this list
+    fn set_chance_none(&mut self, chance_none: u8);
+
+    /// Are all entries up to the player's level considered, or only those closest to it?
+    fn calc_from_all_levels(&self) -> bool;
+
+    /// Set whether all entries up to the player's level are considered
+    fn set_calc_from_all_levels(&mut self, value: bool);
+
+    /// Get this list's entries
+    fn entries(&self) -> &[LeveledEntry];
+
+    /// Get this list's entries mutably
+    fn entries_mut(&mut self) -> &mut Vec<LeveledEntry>;
+
+    /// Add an entry to this list
+    fn add_entry(&mut self, level: u16, id: String) {
+        self.entries_mut().push(LeveledEntry::new(level, id));
+    }
+
+    /// Remove all entries for an object from this list, returning the number of entries removed
+    ///
+    /// IDs are case-insensitive.
+    fn remove_entries(&mut self, id: &str) -> usize {
+        let entries = self.entries_mut();
+        let len = entries.len();
+        entries.retain(|e| !e.id.eq_ignore_ascii_case(id));
+        len - entries.len()
+    }
+
+    /// Merges another version of this list into this one
+    ///
+    /// This works like the merge_lists option of tes3cmd multipatch: entries added by either
+    /// version survive. The other list is assumed to be loaded later, so its chance-none and flags
+    /// replace this list's.
+    fn merge(&mut self, other: &Self)
+    where
+        Self: Sized;
+}
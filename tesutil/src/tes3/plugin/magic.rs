@@ -152,6 +152,34 @@ pub enum MagicEffectType {
     SummonCreature05,
 }
 
+impl MagicEffectType {
+    /// Gets the game settings that name the items this effect summons, if it's a bound effect
+    ///
+    /// Each entry is the name of a setting along with the item ID it holds in Morrowind.esm. Bound
+    /// gloves summon a separate gauntlet for each hand, so that effect has two entries.
+    pub fn bound_item_settings(&self) -> &'static [(&'static str, &'static str)] {
+        use MagicEffectType::*;
+
+        match self {
+            BoundDagger => &[("sMagicBoundDaggerID", "bound_dagger")],
+            BoundLongsword => &[("sMagicBoundLongswordID", "bound_longsword")],
+            BoundMace => &[("sMagicBoundMaceID", "bound_mace")],
+            BoundBattleAxe => &[("sMagicBoundBattleAxeID", "bound_battle_axe")],
+            BoundSpear => &[("sMagicBoundSpearID", "bound_spear")],
+            BoundLongbow => &[("sMagicBoundLongbowID", "bound_longbow")],
+            BoundCuirass => &[("sMagicBoundCuirassID", "bound_cuirass")],
+            BoundHelm => &[("sMagicBoundHelmID", "bound_helm")],
+            BoundBoots => &[("sMagicBoundBootsID", "bound_boots")],
+            BoundShield => &[("sMagicBoundShieldID", "bound_shield")],
+            BoundGloves => &[
+                ("sMagicBoundLeftGauntletID", "bound_gauntlet_left"),
+                ("sMagicBoundRightGauntletID", "bound_gauntlet_right"),
+            ],
+            _ => &[],
+        }
+    }
+}
+
 /// An individual effect of a spell or potion
 #[binrw]
 #[derive(Debug)]
@@ -165,8 +193,8 @@ pub struct SpellEffect {
     #[br(try_map = |a: u8| if a == 0xff { Ok(None) } else { Attribute::try_from(a).map(|v| Some(v)) })]
     #[bw(map = |a| a.map_or(0xff, |v| v as u8))]
     attribute: Option<Attribute>,
-    #[br(try_map = |e: u32| EffectRange::try_from(e as u8))]
-    #[bw(map = |e| *e as u32)]
+    #[br(try_map = |e: u32| EffectRange::from_tes3(e).map_err(|_| "Invalid effect range"))]
+    #[bw(map = |e| e.to_tes3())]
     range: EffectRange,
     area: u32,
     duration: u32,
@@ -206,6 +234,27 @@ impl SpellEffect {
     pub fn magnitude(&self) -> (u32, u32) {
         (self.min_magnitude, self.max_magnitude)
     }
+
+    /// Calculates the magicka cost of this effect
+    ///
+    /// `base_cost` is the base cost of the effect's magic effect definition and `effect_cost_mult`
+    /// is the value of the fEffectCostMult game setting.
+    pub fn cost(&self, base_cost: f32, effect_cost_mult: f32) -> f32 {
+        let min_magnitude = self.min_magnitude.max(1) as f32;
+        let max_magnitude = self.max_magnitude.max(1) as f32;
+        let duration = self.duration.max(1) as f32;
+        let area = self.area.max(1) as f32;
+
+        let cost = (0.5 * (min_magnitude + max_magnitude) * 0.1 * base_cost * (1. + duration)
+            + 0.05 * area * base_cost)
+            * effect_cost_mult;
+
+        if self.range == EffectRange::Target {
+            cost * 1.5
+        } else {
+            cost
+        }
+    }
 }
 
 /// A form which contains magic effects
@@ -219,3 +268,33 @@ pub trait Magic {
     /// Add an effect to this magical entity
     fn add_effect(&mut self, effect: SpellEffect);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use binrw::BinReaderExt;
+    use std::io::Cursor;
+
+    #[test]
+    fn effect_cost() {
+        // Fire Damage 10-20 pts for 5 secs on target
+        let data: [u8; 24] = [
+            14, 0, 0xff, 0xff, 2, 0, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 10, 0, 0, 0, 20, 0, 0, 0,
+        ];
+        let effect: SpellEffect = Cursor::new(&data[..]).read_le().unwrap();
+        assert_eq!(effect.effect(), MagicEffectType::FireDamage);
+        assert!((effect.cost(5., 0.5) - 33.9375).abs() < 0.001);
+    }
+
+    #[test]
+    fn bound_item_settings() {
+        assert_eq!(MagicEffectType::BoundGloves.bound_item_settings().len(), 2);
+        assert_eq!(
+            MagicEffectType::BoundSpear.bound_item_settings(),
+            &[("sMagicBoundSpearID", "bound_spear")]
+        );
+        assert!(MagicEffectType::SummonScamp
+            .bound_item_settings()
+            .is_empty());
+    }
+}
@@ -0,0 +1,282 @@
+use std::convert::TryFrom;
+use std::io::Cursor;
+
+use crate::tes3::{MagicEffectType, Tes3Field, Tes3Record};
+use crate::{
+    decode_failed, decode_failed_because, ExtraFields, Field, FieldOrder, Form, MagicSchool,
+    Record, TesError,
+};
+
+use binrw::{binrw, BinReaderExt, BinWriterExt};
+use bitflags::bitflags;
+
+bitflags! {
+    #[derive(Default)]
+    pub struct EffectFlags: u32 {
+        const TARGET_SKILL = 0x00001;
+        const TARGET_ATTRIBUTE = 0x00002;
+        const NO_DURATION = 0x00004;
+        const NO_MAGNITUDE = 0x00008;
+        const HARMFUL = 0x00010;
+        const CONTINUOUS_VFX = 0x00020;
+        const CAST_SELF = 0x00040;
+        const CAST_TOUCH = 0x00080;
+        const CAST_TARGET = 0x00100;
+        const SPELLMAKING = 0x00200;
+        const ENCHANTING = 0x00400;
+        const NEGATIVE_LIGHTING = 0x00800;
+        const APPLIED_ONCE = 0x01000;
+        const STEALTH = 0x02000;
+        const NON_RECASTABLE = 0x04000;
+        const ILLEGAL_DAEDRA = 0x08000;
+        const UNREFLECTABLE = 0x10000;
+        const CASTER_LINKED = 0x20000;
+    }
+}
+
+/// Magic effect data from the MEDT field
+#[binrw]
+#[derive(Debug)]
+pub struct MagicEffectData {
+    #[br(try_map = |s: u32| MagicSchool::from_tes3(s).map_err(|_| "Invalid magic school"))]
+    #[bw(map = |s| s.to_tes3())]
+    pub school: MagicSchool,
+    pub base_cost: f32,
+    // some mods set flags we don't know about; ignore them rather than rejecting the record
+    #[br(map = EffectFlags::from_bits_truncate)]
+    #[bw(map = |f| f.bits)]
+    pub flags: EffectFlags,
+    pub color: [u32; 3],
+    pub size_multiplier: f32,
+    pub speed_multiplier: f32,
+    pub size_cap: f32,
+}
+
+impl Default for MagicEffectData {
+    fn default() -> Self {
+        MagicEffectData {
+            school: MagicSchool::Alteration,
+            base_cost: 0.,
+            flags: EffectFlags::empty(),
+            color: [0; 3],
+            size_multiplier: 1.,
+            speed_multiplier: 1.,
+            size_cap: 0.,
+        }
+    }
+}
+
+/// A Morrowind magic effect definition
+///
+/// Unlike most Morrowind records, magic effects have no ID; they're identified by their effect
+/// type instead.
+#[derive(Debug)]
+pub struct MagicEffect {
+    effect_type: MagicEffectType,
+    pub data: MagicEffectData,
+    pub icon: Option<String>,
+    pub particle_texture: Option<String>,
+    pub bolt_sound: Option<String>,
+    pub cast_sound: Option<String>,
+    pub hit_sound: Option<String>,
+    pub area_sound: Option<String>,
+    pub cast_visual: Option<String>,
+    pub bolt_visual: Option<String>,
+    pub hit_visual: Option<String>,
+    pub area_visual: Option<String>,
+    pub description: Option<String>,
+    extra_fields: Vec<Tes3Field>,
+    field_order: FieldOrder,
+}
+
+impl MagicEffect {
+    /// Creates a new magic effect of the given type
+    pub fn new(effect_type: MagicEffectType) -> MagicEffect {
+        MagicEffect {
+            effect_type,
+            data: MagicEffectData::default(),
+            icon: None,
+            particle_texture: None,
+            bolt_sound: None,
+            cast_sound: None,
+            hit_sound: None,
+            area_sound: None,
+            cast_visual: None,
+            bolt_visual: None,
+            hit_visual: None,
+            area_visual: None,
+            description: None,
+            extra_fields: vec![],
+            field_order: FieldOrder::new(),
+        }
+    }
+
+    /// Gets this effect's effect type
+    pub fn effect_type(&self) -> MagicEffectType {
+        self.effect_type
+    }
+
+    /// Gets this effect's base cost
+    pub fn base_cost(&self) -> f32 {
+        self.data.base_cost
+    }
+
+    /// Gets this effect's school
+    pub fn school(&self) -> MagicSchool {
+        self.data.school
+    }
+
+    /// Can this effect be used to make custom spells?
+    pub fn allowed_for_spellmaking(&self) -> bool {
+        self.data.flags.contains(EffectFlags::SPELLMAKING)
+    }
+
+    /// Can this effect be used to enchant items?
+    pub fn allowed_for_enchanting(&self) -> bool {
+        self.data.flags.contains(EffectFlags::ENCHANTING)
+    }
+
+    /// Gets the effect type of a raw MGEF record without reading the whole record
+    pub fn read_effect_type(record: &Tes3Record) -> Result<MagicEffectType, TesError> {
+        for field in record.iter() {
+            if field.name() == b"INDX" {
+                return MagicEffectType::try_from(field.get_u32()? as u8)
+                    .map_err(|e| decode_failed_because("Invalid magic effect index", e));
+            }
+        }
+
+        Err(decode_failed("MGEF record has no INDX field"))
+    }
+}
+
+impl ExtraFields for MagicEffect {
+    type Field = Tes3Field;
+
+    fn extra_fields(&self) -> &[Tes3Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes3Field> {
+        &mut self.extra_fields
+    }
+}
+
+impl Form for MagicEffect {
+    type Field = Tes3Field;
+    type Record = Tes3Record;
+
+    const RECORD_TYPE: &'static [u8; 4] = b"MGEF";
+
+    fn read(record: &Self::Record) -> Result<Self, TesError> {
+        MagicEffect::assert(record)?;
+
+        let mut effect = MagicEffect::new(MagicEffect::read_effect_type(record)?);
+
+        for field in record.iter() {
+            match field.name() {
+                b"INDX" => (),
+                b"MEDT" => effect.data = field.reader().read_le()?,
+                b"ITEX" => effect.icon = Some(String::from(field.get_zstring()?)),
+                b"PTEX" => effect.particle_texture = Some(String::from(field.get_zstring()?)),
+                b"BSND" => effect.bolt_sound = Some(String::from(field.get_zstring()?)),
+                b"CSND" => effect.cast_sound = Some(String::from(field.get_zstring()?)),
+                b"HSND" => effect.hit_sound = Some(String::from(field.get_zstring()?)),
+                b"ASND" => effect.area_sound = Some(String::from(field.get_zstring()?)),
+                b"CVFX" => effect.cast_visual = Some(String::from(field.get_zstring()?)),
+                b"BVFX" => effect.bolt_visual = Some(String::from(field.get_zstring()?)),
+                b"HVFX" => effect.hit_visual = Some(String::from(field.get_zstring()?)),
+                b"AVFX" => effect.area_visual = Some(String::from(field.get_zstring()?)),
+                b"DESC" => effect.description = Some(String::from(field.get_string()?)),
+                _ => effect.extra_fields.push(field.clone()),
+            }
+        }
+
+        effect.field_order = FieldOrder::from_record(record);
+
+        Ok(effect)
+    }
+
+    fn write(&self, record: &mut Self::Record) -> Result<(), TesError> {
+        MagicEffect::assert(record)?;
+
+        record.clear();
+
+        let index: u8 = self.effect_type.into();
+        record.add_field(Tes3Field::new_u32(b"INDX", index as u32));
+
+        let mut buf = vec![];
+        let mut cursor = Cursor::new(&mut buf);
+        cursor.write_le(&self.data)?;
+        record.add_field(Tes3Field::new(b"MEDT", buf)?);
+
+        for (name, value) in [
+            (b"ITEX", &self.icon),
+            (b"PTEX", &self.particle_texture),
+            (b"BSND", &self.bolt_sound),
+            (b"CSND", &self.cast_sound),
+            (b"HSND", &self.hit_sound),
+            (b"ASND", &self.area_sound),
+            (b"CVFX", &self.cast_visual),
+            (b"BVFX", &self.bolt_visual),
+            (b"HVFX", &self.hit_visual),
+            (b"AVFX", &self.area_visual),
+        ] {
+            if let Some(value) = value {
+                record.add_field(Tes3Field::new_zstring(name, value.clone())?);
+            }
+        }
+
+        if let Some(ref description) = self.description {
+            record.add_field(Tes3Field::new_string(b"DESC", description.clone())?);
+        }
+
+        for field in &self.extra_fields {
+            record.add_field(field.clone());
+        }
+        record.sort_fields(&self.field_order);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_write_magic_effect() {
+        let mut effect = MagicEffect::new(MagicEffectType::FireDamage);
+        effect.data.school = MagicSchool::Destruction;
+        effect.data.base_cost = 5.;
+        effect.data.flags = EffectFlags::SPELLMAKING | EffectFlags::ENCHANTING;
+        effect.icon = Some(String::from(r"s\Tx_S_fire_damage.tga"));
+
+        let mut record = Tes3Record::new(b"MGEF");
+        effect.write(&mut record).unwrap();
+
+        let effect = MagicEffect::read(&record).unwrap();
+        assert_eq!(effect.effect_type(), MagicEffectType::FireDamage);
+        assert_eq!(effect.school(), MagicSchool::Destruction);
+        assert_eq!(effect.base_cost(), 5.);
+        assert!(effect.data.flags.contains(EffectFlags::ENCHANTING));
+        assert_eq!(effect.icon.as_deref(), Some(r"s\Tx_S_fire_damage.tga"));
+        assert!(effect.cast_sound.is_none());
+    }
+
+    #[test]
+    fn unknown_effect_flags() {
+        let mut record = Tes3Record::new(b"MGEF");
+        MagicEffect::new(MagicEffectType::FireDamage)
+            .write(&mut record)
+            .unwrap();
+        let medt = record.iter().find(|f| f.name() == b"MEDT").unwrap();
+        let mut data = medt.get().to_vec();
+        data[8..12].copy_from_slice(&0x40200u32.to_le_bytes());
+        let mut record = Tes3Record::new(b"MGEF");
+        record.add_field(Tes3Field::new_u32(b"INDX", 14));
+        record.add_field(Tes3Field::new(b"MEDT", data).unwrap());
+
+        let effect = MagicEffect::read(&record).unwrap();
+        assert_eq!(effect.data.flags, EffectFlags::SPELLMAKING);
+    }
+}
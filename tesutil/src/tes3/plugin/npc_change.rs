@@ -1,10 +1,12 @@
+use std::io::Cursor;
+
 use super::field::Tes3Field;
 use super::record::Tes3Record;
 use crate::plugin::Field;
 use crate::*;
 
-use crate::tes3::{ActorState, Package};
-use binrw::{binrw, BinReaderExt};
+use crate::tes3::{ActorState, Package, ACTOR_STRING_LENGTH};
+use binrw::{binrw, BinReaderExt, BinWriterExt};
 
 /// NPC disposition and reputation
 #[binrw]
@@ -19,26 +21,61 @@ struct Disposition {
 
 /// Script state attached to an item
 #[derive(Debug, Default, Clone)]
-pub struct Script {
+pub struct ItemScript {
+    /// ID of the script
     pub name: String,
+    /// Values of the script's short variables
     pub shorts: Vec<i16>,
+    /// Values of the script's long variables
     pub longs: Vec<i32>,
+    /// Values of the script's float variables
     pub floats: Vec<f32>,
 }
 
+impl ItemScript {
+    /// Creates script state with no variables
+    pub fn new(name: String) -> ItemScript {
+        ItemScript {
+            name,
+            ..ItemScript::default()
+        }
+    }
+}
+
 /// An item in the NPC's inventory
+///
+/// Items with no state of their own are stored as a stack with a count. Every other item is
+/// stored individually, so its count should be 1; a higher count is written as that many copies of
+/// the item.
 #[derive(Debug, Default, Clone)]
 pub struct InventoryItem {
+    /// ID of the base item
     pub id: String,
+    /// Number of items in the stack
     pub count: u32,
+    /// Whether the item is equipped
     pub is_equipped: bool,
+    /// ID of the creature whose soul is trapped in this soul gem
     pub soul: Option<String>,
+    /// Remaining enchantment charge
     pub enchantment_charge: Option<f32>,
+    /// Remaining condition
     pub remaining_durability: Option<u32>,
-    pub script: Option<Script>,
+    /// State of the item's script
+    pub script: Option<ItemScript>,
 }
 
 impl InventoryItem {
+    /// Creates a stack of items with no state of their own
+    pub fn new(id: String, count: u32) -> InventoryItem {
+        InventoryItem {
+            id,
+            count,
+            ..InventoryItem::default()
+        }
+    }
+
+    /// Does this item have no state of its own?
     pub fn is_pristine(&self) -> bool {
         !self.is_equipped
             && self.soul.is_none()
@@ -58,9 +95,97 @@ pub struct NpcChange {
 }
 
 impl NpcChange {
+    /// Creates an empty change record for an NPC
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ID is longer than 32 bytes.
+    pub fn new(id: String) -> Result<NpcChange, TesError> {
+        check_size(&id, ACTOR_STRING_LENGTH, "NPC ID too long")?;
+        Ok(NpcChange {
+            id,
+            ..NpcChange::default()
+        })
+    }
+
+    /// Gets the ID of the NPC this change applies to
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    /// Iterates through the NPC's inventory
     pub fn iter_inventory(&self) -> impl Iterator<Item = &InventoryItem> {
         self.inventory.iter()
     }
+
+    /// Iterates through the NPC's inventory mutably
+    pub fn iter_inventory_mut(&mut self) -> impl Iterator<Item = &mut InventoryItem> {
+        self.inventory.iter_mut()
+    }
+
+    /// Adds an item or stack of items to the NPC's inventory
+    pub fn add_inventory_item(&mut self, item: InventoryItem) {
+        self.inventory.push(item);
+    }
+
+    /// Removes all items with the given ID from the NPC's inventory
+    ///
+    /// Returns the number of inventory entries that were removed.
+    pub fn remove_items_by_id(&mut self, id: &str) -> usize {
+        let count = self.inventory.len();
+        self.inventory.retain(|i| !i.id.eq_ignore_ascii_case(id));
+        count - self.inventory.len()
+    }
+
+    /// Removes all items from the NPC's inventory
+    pub fn clear_inventory(&mut self) {
+        self.inventory.clear();
+    }
+
+    fn write_item_state(item: &InventoryItem, record: &mut Tes3Record) -> Result<(), TesError> {
+        if let Some(ref script) = item.script {
+            record.add_field(Tes3Field::new_zstring(b"SCRI", script.name.clone())?);
+
+            let mut buf = vec![];
+            let mut cursor = Cursor::new(&mut buf);
+            cursor.write_le(&checked_len::<u32>(script.shorts.len(), "Script shorts")?)?;
+            cursor.write_le(&checked_len::<u32>(script.longs.len(), "Script longs")?)?;
+            cursor.write_le(&checked_len::<u32>(script.floats.len(), "Script floats")?)?;
+            record.add_field(Tes3Field::new(b"SLCS", buf)?);
+
+            if !script.shorts.is_empty() {
+                let mut buf = vec![];
+                Cursor::new(&mut buf).write_le(&script.shorts)?;
+                record.add_field(Tes3Field::new(b"SLSD", buf)?);
+            }
+
+            if !script.longs.is_empty() {
+                let mut buf = vec![];
+                Cursor::new(&mut buf).write_le(&script.longs)?;
+                record.add_field(Tes3Field::new(b"SLLD", buf)?);
+            }
+
+            if !script.floats.is_empty() {
+                let mut buf = vec![];
+                Cursor::new(&mut buf).write_le(&script.floats)?;
+                record.add_field(Tes3Field::new(b"SLFD", buf)?);
+            }
+        }
+
+        if let Some(ref soul) = item.soul {
+            record.add_field(Tes3Field::new_zstring(b"XSOL", soul.clone())?);
+        }
+
+        if let Some(charge) = item.enchantment_charge {
+            record.add_field(Tes3Field::new_f32(b"XCHG", charge));
+        }
+
+        if let Some(durability) = item.remaining_durability {
+            record.add_field(Tes3Field::new_u32(b"XHLT", durability));
+        }
+
+        Ok(())
+    }
 }
 
 impl ActorState for NpcChange {
@@ -133,7 +258,7 @@ impl Form for NpcChange {
                     npc_change.inventory.push(new);
                 }
                 b"SCRI" => {
-                    let script = Script {
+                    let script = ItemScript {
                         name: String::from(field.get_zstring()?),
                         shorts: vec![],
                         longs: vec![],
@@ -234,8 +359,66 @@ impl Form for NpcChange {
         Ok(npc_change)
     }
 
-    fn write(&self, _: &mut Self::Record) -> Result<(), TesError> {
-        todo!()
+    fn write(&self, record: &mut Self::Record) -> Result<(), TesError> {
+        NpcChange::assert(record)?;
+
+        record.clear();
+
+        record.add_field(Tes3Field::new_zstring(b"NAME", self.id.clone())?);
+
+        let mut buf = vec![];
+        Cursor::new(&mut buf).write_le(&self.disposition)?;
+        record.add_field(Tes3Field::new(b"NPDT", buf)?);
+
+        // items with the same ID are written as one NPCO stack, followed by an XIDX and the state
+        // of each item in the stack that isn't pristine
+        let mut stack_ids: Vec<&str> = vec![];
+        for item in &self.inventory {
+            if !stack_ids.iter().any(|id| id.eq_ignore_ascii_case(&item.id)) {
+                stack_ids.push(&item.id);
+            }
+        }
+
+        let mut equipped = vec![];
+        for (index, stack_id) in stack_ids.into_iter().enumerate() {
+            check_size(stack_id, ACTOR_STRING_LENGTH, "Inventory item ID too long")?;
+            let stack: Vec<_> = self
+                .inventory
+                .iter()
+                .filter(|i| i.id.eq_ignore_ascii_case(stack_id))
+                .collect();
+            let count: u32 = stack.iter().map(|i| i.count).sum();
+
+            let mut buf = vec![];
+            let mut cursor = Cursor::new(&mut buf);
+            cursor.write_le(&count)?;
+            write_str::<ACTOR_STRING_LENGTH, _>(stack_id, &mut cursor)?;
+            record.add_field(Tes3Field::new(b"NPCO", buf)?);
+
+            let mut slot = 0u32;
+            for item in stack.into_iter().filter(|i| !i.is_pristine()) {
+                for _ in 0..item.count {
+                    record.add_field(Tes3Field::new_u32(b"XIDX", slot));
+                    NpcChange::write_item_state(item, record)?;
+                    if item.is_equipped {
+                        equipped.push((index as u32, slot));
+                    }
+                    slot += 1;
+                }
+            }
+        }
+
+        for (index, slot) in equipped {
+            let mut buf = vec![];
+            let mut cursor = Cursor::new(&mut buf);
+            cursor.write_le(&index)?;
+            cursor.write_le(&slot)?;
+            record.add_field(Tes3Field::new(b"WIDX", buf)?);
+        }
+
+        self.write_packages(record)?;
+
+        Ok(())
     }
 }
 
@@ -287,4 +470,66 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn write_record() {
+        let mut record_ref = NPCC_RECORD.as_ref();
+        let cursor = Cursor::new(&mut record_ref);
+        let record = Tes3Record::read(cursor).unwrap();
+        let mut npc_change = NpcChange::read(&record).unwrap();
+
+        for item in npc_change.iter_inventory_mut() {
+            if item.id == "iron club" && item.is_equipped {
+                item.remaining_durability = Some(100);
+            }
+        }
+        assert_eq!(npc_change.remove_items_by_id("Gold_001"), 1);
+        let mut soul_gem = InventoryItem::new(String::from("Misc_SoulGem_Petty"), 1);
+        soul_gem.soul = Some(String::from("rat"));
+        npc_change.add_inventory_item(soul_gem);
+        npc_change.add_inventory_item(InventoryItem::new(String::from("iron club"), 1));
+
+        let mut new_record = Tes3Record::new(b"NPCC");
+        npc_change.write(&mut new_record).unwrap();
+        let new_change = NpcChange::read(&new_record).unwrap();
+        assert_eq!(new_change.id(), "PlayerSaveGame");
+        assert!(new_change.iter_inventory().all(|i| i.id != "Gold_001"));
+
+        let clubs: Vec<_> = new_change
+            .iter_inventory()
+            .filter(|i| i.id == "iron club")
+            .collect();
+        assert_eq!(clubs.iter().map(|i| i.count).sum::<u32>(), 5);
+        let equipped_club = clubs.iter().find(|i| i.is_equipped).unwrap();
+        assert_eq!(equipped_club.remaining_durability, Some(100));
+        assert_eq!(
+            clubs
+                .iter()
+                .filter(|i| i.remaining_durability == Some(389))
+                .count(),
+            1
+        );
+
+        let ring = new_change
+            .iter_inventory()
+            .find(|i| i.id == "ring_keley")
+            .unwrap();
+        assert!(ring.is_equipped);
+        let script = ring.script.as_ref().unwrap();
+        assert_eq!(script.name, "CharGen_ring_keley");
+        assert_eq!(script.shorts, vec![30, 1]);
+
+        let soul_gem = new_change
+            .iter_inventory()
+            .find(|i| i.id == "Misc_SoulGem_Petty")
+            .unwrap();
+        assert_eq!(soul_gem.soul.as_deref(), Some("rat"));
+        assert_eq!(
+            new_change
+                .iter_inventory()
+                .filter(|i| i.is_equipped)
+                .count(),
+            6
+        );
+    }
 }
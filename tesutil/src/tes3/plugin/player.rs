@@ -5,6 +5,11 @@ use crate::Form;
 
 use binrw::{binrw, BinReaderExt};
 
+/// Player flag that's set while the player is serving time in jail
+///
+/// This is `PlayerFlags_InJail` in OpenMW's save importer (apps/essimporter/importplayer.hpp).
+const PLAYER_FLAG_IN_JAIL: u32 = 0x200;
+
 /// Information about the state of a resting player
 #[binrw]
 #[derive(Debug)]
@@ -34,7 +39,7 @@ pub struct Lnam {
 
 /// Player faction membership
 #[derive(Debug)]
-pub struct Faction {
+pub struct FactionMembership {
     rank: u32,
     reputation: i32,
     flags: u32,
@@ -68,6 +73,22 @@ impl Default for QuickKey {
     }
 }
 
+/// Where the player last cast Mark
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkLocation {
+    /// Name of the cell the mark is in
+    ///
+    /// For exterior cells, this is the name of the region or of the cell if it has one.
+    pub cell: String,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub rotation: f32,
+    /// Grid coordinates of the cell, which identify the cell when it's an exterior
+    pub grid_x: i32,
+    pub grid_y: i32,
+}
+
 /// Player-specific data
 #[derive(Debug, Default)]
 pub struct PlayerData {
@@ -111,7 +132,7 @@ pub struct PlayerData {
     // LNAM
     lnam: Option<Lnam>,
     // FNAM
-    factions: Vec<Faction>,
+    factions: Vec<FactionMembership>,
     // AADT
     animation_data: Option<AnimationData>,
     // KNAM
@@ -126,6 +147,36 @@ impl PlayerData {
     pub fn birthsign(&self) -> Option<&str> {
         self.birthsign.as_deref()
     }
+
+    /// Gets the player's bounty
+    pub fn bounty(&self) -> i32 {
+        self.bounty.unwrap_or(0)
+    }
+
+    /// Returns whether the player was serving time in jail when the save was made
+    pub fn is_in_jail(&self) -> bool {
+        self.player_flags & PLAYER_FLAG_IN_JAIL != 0
+    }
+
+    /// Returns whether the save has werewolf data for the player
+    ///
+    /// Bloodmoon only adds this data to the save once the player has become a werewolf.
+    pub fn is_werewolf(&self) -> bool {
+        !self.werewolf_data.is_empty()
+    }
+
+    /// Gets the location the player last cast Mark at, if there is one
+    pub fn mark(&self) -> Option<MarkLocation> {
+        self.mark_cell.as_ref().map(|cell| MarkLocation {
+            cell: cell.clone(),
+            x: self.mark_x,
+            y: self.mark_y,
+            z: self.mark_z,
+            rotation: self.mark_rot,
+            grid_x: self.mark_grid_x,
+            grid_y: self.mark_grid_y,
+        })
+    }
 }
 
 impl Form for PlayerData {
@@ -215,7 +266,7 @@ impl Form for PlayerData {
                 }
                 b"FNAM" => {
                     let mut reader = field.reader();
-                    player_data.factions.push(Faction {
+                    player_data.factions.push(FactionMembership {
                         rank: reader.read_le()?,
                         reputation: reader.read_le()?,
                         flags: reader.read_le()?,
@@ -260,3 +311,33 @@ impl Form for PlayerData {
         unimplemented!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a PNAM field with the given player flags and everything else zeroed
+    fn player_flags_field(flags: u32) -> Tes3Field {
+        // flags, level progress, 27 skill progresses, 8 attribute progresses, 5 magic bonuses,
+        // the Mark location, 40 unknown bytes, 3 specialization increases, and 1 unknown byte
+        let mut data = vec![0u8; 4 + 4 + 27 * 4 + 8 + 5 * 4 + 6 * 4 + 40 + 3 + 1];
+        data[..4].copy_from_slice(&flags.to_le_bytes());
+        Tes3Field::new(b"PNAM", data).unwrap()
+    }
+
+    #[test]
+    fn read_jail_state() {
+        let mut record = Tes3Record::new(b"PCDT");
+        record.add_field(player_flags_field(PLAYER_FLAG_IN_JAIL | 0x80));
+        record.add_field(Tes3Field::new_i32(b"CNAM", 250));
+        let player_data = PlayerData::read(&record).unwrap();
+        assert!(player_data.is_in_jail());
+        assert_eq!(player_data.bounty(), 250);
+
+        let mut record = Tes3Record::new(b"PCDT");
+        record.add_field(player_flags_field(0x80));
+        let player_data = PlayerData::read(&record).unwrap();
+        assert!(!player_data.is_in_jail());
+        assert_eq!(player_data.bounty(), 0);
+    }
+}
@@ -1,20 +1,34 @@
 use std::convert::TryFrom;
+use std::io::Cursor;
 
 use crate::tes3::{Skill, Tes3Field, Tes3Record};
 use crate::{
-    decode_failed, decode_failed_because, read_string, Attribute, Attributes, Field, Form, Record,
-    TesError,
+    check_range, check_size, decode_failed, decode_failed_because, Attribute, Attributes, Field,
+    Form, Record, TesError,
 };
 
-use binrw::BinReaderExt;
+use binrw::{BinReaderExt, BinWriterExt};
+use bitflags::bitflags;
 
 const ID_LENGTH: usize = 32;
+const NUM_SKILL_BONUSES: usize = 7;
 
+bitflags! {
+    struct RaceFlags: u32 {
+        const PLAYABLE = 0x01;
+        const BEAST_RACE = 0x02;
+    }
+}
+
+/// A character race
+///
+/// Morrowind races don't include any body part data; body parts are separate records that refer
+/// to the race they belong to.
 #[derive(Debug)]
 pub struct Race {
     id: String,
     name: String,
-    skills: [Option<(Skill, u32)>; 7],
+    skills: [Option<(Skill, u32)>; NUM_SKILL_BONUSES],
     attributes: Attributes<(u32, u32)>,
     height: (f32, f32),
     weight: (f32, f32),
@@ -25,6 +39,77 @@ pub struct Race {
 }
 
 impl Race {
+    /// Creates a new race with no skill bonuses, attributes, or specials
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ID is longer than 32 bytes.
+    pub fn new(id: String, name: String) -> Result<Race, TesError> {
+        check_size(&id, ID_LENGTH, "Race ID too long")?;
+
+        Ok(Race {
+            id,
+            name,
+            skills: [None; NUM_SKILL_BONUSES],
+            attributes: Attributes::default(),
+            height: (1., 1.),
+            weight: (1., 1.),
+            is_playable: false,
+            is_beast_race: false,
+            specials: vec![],
+            description: None,
+        })
+    }
+
+    /// Gets the race's ID
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    /// Gets the race's name
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Sets the race's name
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    /// Gets the race's skill bonuses
+    pub fn skill_bonuses(&self) -> impl Iterator<Item = (Skill, u32)> + '_ {
+        self.skills.iter().flatten().copied()
+    }
+
+    /// Sets the race's skill bonuses
+    ///
+    /// # Errors
+    ///
+    /// Fails if there are more than 7 bonuses or if the same skill appears more than once.
+    pub fn set_skill_bonuses(&mut self, bonuses: &[(Skill, u32)]) -> Result<(), TesError> {
+        check_range(
+            bonuses.len() as u32,
+            0,
+            NUM_SKILL_BONUSES as u32,
+            "Wrong number of race skill bonuses",
+        )?;
+
+        for (i, (skill, _)) in bonuses.iter().enumerate() {
+            if bonuses.iter().skip(i + 1).any(|(s, _)| s == skill) {
+                return Err(TesError::RequirementFailed(String::from(
+                    "Race skill bonuses must be unique",
+                )));
+            }
+        }
+
+        self.skills = [None; NUM_SKILL_BONUSES];
+        for (slot, bonus) in self.skills.iter_mut().zip(bonuses) {
+            *slot = Some(*bonus);
+        }
+
+        Ok(())
+    }
+
     /// Gets the starting attributes for a male character of this race
     pub fn attributes_male(&self) -> impl Iterator<Item = (Attribute, u32)> + '_ {
         self.attributes.iter().map(|(a, (v, _))| (a, *v))
@@ -35,6 +120,11 @@ impl Race {
         self.attributes[attribute].0
     }
 
+    /// Sets a starting attribute for a male character of this race
+    pub fn set_attribute_male(&mut self, attribute: Attribute, value: u32) {
+        self.attributes[attribute].0 = value;
+    }
+
     /// Gets the starting attributes for a female character of this race
     pub fn attributes_female(&self) -> impl Iterator<Item = (Attribute, u32)> + '_ {
         self.attributes.iter().map(|(a, (_, v))| (a, *v))
@@ -45,10 +135,86 @@ impl Race {
         self.attributes[attribute].1
     }
 
+    /// Sets a starting attribute for a female character of this race
+    pub fn set_attribute_female(&mut self, attribute: Attribute, value: u32) {
+        self.attributes[attribute].1 = value;
+    }
+
+    /// Gets the male and female height multipliers for this race
+    pub fn height(&self) -> (f32, f32) {
+        self.height
+    }
+
+    /// Sets the male and female height multipliers for this race
+    pub fn set_height(&mut self, male: f32, female: f32) {
+        self.height = (male, female);
+    }
+
+    /// Gets the male and female weight multipliers for this race
+    pub fn weight(&self) -> (f32, f32) {
+        self.weight
+    }
+
+    /// Sets the male and female weight multipliers for this race
+    pub fn set_weight(&mut self, male: f32, female: f32) {
+        self.weight = (male, female);
+    }
+
+    /// Can the player choose this race?
+    pub fn is_playable(&self) -> bool {
+        self.is_playable
+    }
+
+    /// Sets whether the player can choose this race
+    pub fn set_is_playable(&mut self, is_playable: bool) {
+        self.is_playable = is_playable;
+    }
+
+    /// Is this a beast race?
+    pub fn is_beast_race(&self) -> bool {
+        self.is_beast_race
+    }
+
+    /// Sets whether this is a beast race
+    pub fn set_is_beast_race(&mut self, is_beast_race: bool) {
+        self.is_beast_race = is_beast_race;
+    }
+
     /// Gets the specials (abilities and powers) for a character of this race
     pub fn specials(&self) -> impl Iterator<Item = &str> + '_ {
         self.specials.iter().map(String::as_str)
     }
+
+    /// Sets the specials for a character of this race
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of the spell IDs is longer than 32 bytes.
+    pub fn set_specials(&mut self, specials: Vec<String>) -> Result<(), TesError> {
+        for special in &specials {
+            check_size(special, ID_LENGTH, "Race special ID too long")?;
+        }
+
+        self.specials = specials;
+        Ok(())
+    }
+
+    /// Gets the race's description
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Sets the race's description
+    pub fn set_description(&mut self, description: Option<String>) {
+        self.description = description;
+    }
+
+    fn flags(&self) -> RaceFlags {
+        let mut flags = RaceFlags::empty();
+        flags.set(RaceFlags::PLAYABLE, self.is_playable);
+        flags.set(RaceFlags::BEAST_RACE, self.is_beast_race);
+        flags
+    }
 }
 
 impl Form for Race {
@@ -63,13 +229,13 @@ impl Form for Race {
         let mut race = Race {
             id: String::new(),
             name: String::new(),
-            skills: [None, None, None, None, None, None, None],
+            skills: [None; NUM_SKILL_BONUSES],
             attributes: Attributes::default(),
             height: (0., 0.),
             weight: (0., 0.),
             is_playable: false,
             is_beast_race: false,
-            specials: vec![],
+            specials: record.get_string_list(b"NPCS", ID_LENGTH)?,
             description: None,
         };
 
@@ -98,12 +264,10 @@ impl Form for Race {
                     race.weight = (reader.read_le()?, reader.read_le()?);
 
                     let flags: u32 = reader.read_le()?;
-                    race.is_playable = flags & 1 != 0;
-                    race.is_beast_race = flags & 2 != 0;
+                    race.is_playable = flags & RaceFlags::PLAYABLE.bits != 0;
+                    race.is_beast_race = flags & RaceFlags::BEAST_RACE.bits != 0;
                 }
-                b"NPCS" => race
-                    .specials
-                    .push(read_string::<ID_LENGTH, _>(&mut field.get())?),
+                b"NPCS" => (), // already collected above
                 b"DESC" => race.description = Some(String::from(field.get_string()?)),
                 _ => {
                     return Err(decode_failed(format!(
@@ -117,7 +281,89 @@ impl Form for Race {
         Ok(race)
     }
 
-    fn write(&self, _record: &mut Self::Record) -> Result<(), TesError> {
-        unimplemented!()
+    fn write(&self, record: &mut Self::Record) -> Result<(), TesError> {
+        Race::assert(record)?;
+
+        record.clear();
+
+        record.add_field(Tes3Field::new_zstring(b"NAME", self.id.clone())?);
+        record.add_field(Tes3Field::new_zstring(b"FNAM", self.name.clone())?);
+
+        let mut buf = Vec::with_capacity(140);
+        let mut writer = Cursor::new(&mut buf);
+        for skill in &self.skills {
+            match skill {
+                Some((skill, bonus)) => {
+                    writer.write_le(&(*skill as i32))?;
+                    writer.write_le(bonus)?;
+                }
+                None => {
+                    writer.write_le(&-1i32)?;
+                    writer.write_le(&0u32)?;
+                }
+            }
+        }
+
+        for (male_value, female_value) in self.attributes.values() {
+            writer.write_le(male_value)?;
+            writer.write_le(female_value)?;
+        }
+
+        writer.write_le(&self.height.0)?;
+        writer.write_le(&self.height.1)?;
+        writer.write_le(&self.weight.0)?;
+        writer.write_le(&self.weight.1)?;
+        writer.write_le(&self.flags().bits)?;
+        record.add_field(Tes3Field::new(b"RADT", buf)?);
+
+        record.set_string_list(b"NPCS", &self.specials, ID_LENGTH)?;
+
+        if let Some(ref description) = self.description {
+            record.add_field(Tes3Field::new_string(b"DESC", description.clone())?);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_race() {
+        let mut race = Race::new(String::from("Snow Elf"), String::from("Falmer")).unwrap();
+        race.set_skill_bonuses(&[(Skill::Destruction, 10), (Skill::Illusion, 5)])
+            .unwrap();
+        race.set_attribute_male(Attribute::Intelligence, 50);
+        race.set_attribute_female(Attribute::Willpower, 45);
+        race.set_height(1.1, 1.05);
+        race.set_is_playable(true);
+        race.set_specials(vec![String::from("frost resistance")])
+            .unwrap();
+        race.set_description(Some(String::from("The lost elves of Skyrim.")));
+
+        let mut record = Tes3Record::new(b"RACE");
+        race.write(&mut record).unwrap();
+        let new_race = Race::read(&record).unwrap();
+        assert_eq!(new_race.id(), "Snow Elf");
+        assert_eq!(new_race.name(), "Falmer");
+        assert_eq!(
+            new_race.skill_bonuses().collect::<Vec<_>>(),
+            vec![(Skill::Destruction, 10), (Skill::Illusion, 5)]
+        );
+        assert_eq!(new_race.attribute_male(Attribute::Intelligence), 50);
+        assert_eq!(new_race.attribute_female(Attribute::Willpower), 45);
+        assert_eq!(new_race.height(), (1.1, 1.05));
+        assert!(new_race.is_playable());
+        assert!(!new_race.is_beast_race());
+        assert_eq!(
+            new_race.specials().collect::<Vec<_>>(),
+            vec!["frost resistance"]
+        );
+        assert_eq!(new_race.description(), Some("The lost elves of Skyrim."));
+
+        let result = race.set_skill_bonuses(&[(Skill::Alchemy, 5), (Skill::Alchemy, 10)]);
+        assert!(matches!(result, Err(TesError::RequirementFailed(_))));
     }
 }
@@ -3,6 +3,7 @@ use std::mem::size_of;
 use std::str;
 
 use super::field::Tes3Field;
+use super::RECORD_SCHEMA;
 use crate::plugin::*;
 use crate::*;
 
@@ -24,7 +25,7 @@ const FLAG_BLOCKED: u32 = 0x2000;
 /// would be cumbersome to have to explicitly clone these everywhere.
 ///
 /// [`Field::new`]: #method.new
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Tes3Record {
     name: [u8; 4],
     /// Whether the record is deleted
@@ -45,10 +46,21 @@ pub struct Tes3Record {
     raw_data: Vec<u8>,
     changed: bool,
     fields: Vec<Tes3Field>,
+    span: Option<RecordSpan>,
 }
 
 const DELETED_FIELD_SIZE: usize = 12;
 
+impl JournalRecord for Tes3Record {
+    fn snapshot(&self) -> Tes3Record {
+        self.clone()
+    }
+
+    fn restore(&mut self, snapshot: Tes3Record) {
+        *self = snapshot;
+    }
+}
+
 impl IntoIterator for Tes3Record {
     type Item = Tes3Field;
     type IntoIter = <Vec<Tes3Field> as IntoIterator>::IntoIter;
@@ -61,6 +73,7 @@ impl IntoIterator for Tes3Record {
 
 impl Record<Tes3Field> for Tes3Record {
     fn read_lazy<T: Read + Seek>(mut f: T) -> Result<Tes3Record, TesError> {
+        let offset = f.seek(SeekFrom::Current(0))?;
         let mut name = [0u8; 4];
         f.read_exact(&mut name)?;
 
@@ -72,6 +85,7 @@ impl Record<Tes3Field> for Tes3Record {
         let mut data = vec![0u8; size];
         // read in the field data
         f.read_exact(&mut data)?;
+        let end = f.seek(SeekFrom::Current(0))?;
 
         Ok(Tes3Record {
             name,
@@ -83,6 +97,10 @@ impl Record<Tes3Field> for Tes3Record {
             raw_data: data,
             changed: false,
             fields: vec![],
+            span: Some(RecordSpan {
+                offset,
+                length: end - offset,
+            }),
         })
     }
 
@@ -91,6 +109,14 @@ impl Record<Tes3Field> for Tes3Record {
         &self.name
     }
 
+    fn span(&self) -> Option<RecordSpan> {
+        self.span
+    }
+
+    fn data_size(&self) -> usize {
+        self.raw_data.len() + self.fields.iter().map(|f| f.size()).sum::<usize>()
+    }
+
     fn status(&self) -> RecordStatus {
         self.status
     }
@@ -145,6 +171,10 @@ impl Record<Tes3Field> for Tes3Record {
         Box::new(self.fields.iter_mut())
     }
 
+    fn field_kind(&self, field_name: &[u8]) -> FieldKind {
+        RECORD_SCHEMA.field_kind(&self.name, field_name)
+    }
+
     /// Writes the record to the provided writer
     ///
     /// Writes a record to any type that implements [`Write`] or a mutable reference to such a type.
@@ -171,7 +201,7 @@ impl Record<Tes3Field> for Tes3Record {
         f.write_all(&self.name)?;
 
         if !self.changed {
-            f.write_le(&(self.raw_data.len() as u32))?;
+            f.write_le(&checked_len::<u32>(self.raw_data.len(), "Record data")?)?;
             f.write_all(b"\0\0\0\0")?; // dummy field
             f.write_le(&flags)?;
             f.write_all(&self.raw_data)?;
@@ -220,6 +250,7 @@ impl Tes3Record {
             raw_data: vec![],
             changed: false,
             fields: vec![],
+            span: None,
         }
     }
 
@@ -329,6 +360,93 @@ impl Tes3Record {
         self.changed = true;
         self.fields.clear();
     }
+
+    /// Collects the values of every field with the given name, in order
+    ///
+    /// Some records store a list as a field repeated once per entry, like an NPC's NPCS spell list.
+    /// `read` decodes a single one of those fields.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `read` fails for any of the fields.
+    pub fn get_list<T, F>(&self, name: &[u8; 4], read: F) -> Result<Vec<T>, TesError>
+    where
+        F: FnMut(&Tes3Field) -> Result<T, TesError>,
+    {
+        self.iter().filter(|f| f.name() == name).map(read).collect()
+    }
+
+    /// Replaces every field with the given name with one field per value
+    ///
+    /// The new fields go where the first of the old ones was, or at the end of the record if there
+    /// were none, so the rest of the record's field order is preserved. `write` encodes a single
+    /// value.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `write` fails for any of the values. The record is left unchanged in that case.
+    pub fn set_list<T, I, F>(&mut self, name: &[u8; 4], values: I, write: F) -> Result<(), TesError>
+    where
+        I: IntoIterator<Item = T>,
+        F: FnMut(T) -> Result<Tes3Field, TesError>,
+    {
+        self.require_finalized();
+        let new_fields = values
+            .into_iter()
+            .map(write)
+            .collect::<Result<Vec<_>, _>>()?;
+        let position = self
+            .fields
+            .iter()
+            .position(|f| f.name() == name)
+            .unwrap_or(self.fields.len());
+        self.fields.retain(|f| f.name() != name);
+        self.fields.splice(position..position, new_fields);
+        self.changed = true;
+        Ok(())
+    }
+
+    /// Collects the values of every field with the given name as fixed-length strings
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of the fields is shorter than `size` or doesn't contain a valid string.
+    pub fn get_string_list(&self, name: &[u8; 4], size: usize) -> Result<Vec<String>, TesError> {
+        self.get_list(name, |f| {
+            read_string_dyn(size, f.get()).map_err(|e| {
+                decode_failed_because(format!("Could not parse {}", f.name_as_str()), e)
+            })
+        })
+    }
+
+    /// Replaces every field with the given name with fixed-length string fields
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of the strings is longer than `size`.
+    pub fn set_string_list<S, I>(
+        &mut self,
+        name: &[u8; 4],
+        values: I,
+        size: usize,
+    ) -> Result<(), TesError>
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = S>,
+    {
+        self.set_list(name, values, |s| {
+            let s = s.as_ref();
+            check_size(s, size, "String too long")?;
+            Tes3Field::new(name, make_str_vec(s, size))
+        })
+    }
+
+    /// Rearranges this record's fields to match a previously captured field order
+    pub fn sort_fields(&mut self, order: &FieldOrder) {
+        self.require_finalized();
+        self.changed = true;
+        order.apply(&mut self.fields);
+    }
 }
 
 #[cfg(test)]
@@ -348,6 +466,49 @@ mod tests {
         assert_eq!(record.fields.len(), 3);
     }
 
+    #[test]
+    fn iter_typed() {
+        let data = b"GLOB\x27\0\0\0\0\0\0\0\0\0\0\0NAME\x0a\0\0\0TimeScale\0FNAM\x01\0\0\0fFLTV\x04\0\0\0\0\0\x20\x41".to_vec();
+        let record = Tes3Record::read(io::Cursor::new(data)).unwrap();
+        let values: Vec<_> = record.iter_typed().map(|(_, v)| v).collect();
+        assert_eq!(
+            values,
+            [
+                FieldValue::String("TimeScale".into()),
+                FieldValue::String("f".into()),
+                FieldValue::Float(10.)
+            ]
+        );
+
+        let spell = Tes3Record::new(b"SPEL");
+        assert_eq!(spell.field_kind(b"ENAM"), FieldKind::Bytes);
+        let weapon = Tes3Record::new(b"WEAP");
+        assert_eq!(weapon.field_kind(b"ENAM"), FieldKind::String);
+        assert_eq!(weapon.field_kind(b"WPDT"), FieldKind::Bytes);
+
+        let mut region = Tes3Record::new(b"REGN");
+        region.add_field(Tes3Field::new(b"CNAM", vec![0x80, 0x60, 0x40, 0]).unwrap());
+        let mut sound = b"Bird 1".to_vec();
+        sound.resize(33, 0);
+        sound[32] = 20;
+        region.add_field(Tes3Field::new(b"SNAM", sound).unwrap());
+        assert!(region.check_fields().is_ok());
+    }
+
+    #[test]
+    fn record_span() {
+        let data = b"GLOB\x27\0\0\0\0\0\0\0\0\0\0\0NAME\x0a\0\0\0TimeScale\0FNAM\x01\0\0\0fFLTV\x04\0\0\0\0\0\x20\x41".to_vec();
+        let len = data.len() as u64;
+        let mut cursor = io::Cursor::new(data.repeat(2));
+        let first = Tes3Record::read(&mut cursor).unwrap();
+        let second = Tes3Record::read(&mut cursor).unwrap();
+        assert_eq!(first.span().unwrap().offset, 0);
+        assert_eq!(first.span().unwrap().length, len);
+        assert_eq!(second.span().unwrap().offset, len);
+        assert_eq!(second.span().unwrap().end(), len * 2);
+        assert!(Tes3Record::new(b"GLOB").span().is_none());
+    }
+
     #[test]
     fn read_deleted_record() {
         let data = b"DIAL\x2b\0\0\0\0\0\0\0\x20\0\0\0NAME\x0b\0\0\0Berel Sala\0DATA\x04\0\0\0\0\0\0\0DELE\x04\0\0\0\0\0\0\0".to_vec();
@@ -370,4 +531,31 @@ mod tests {
         record.write(&mut cursor).unwrap();
         assert_eq!(cursor.into_inner(), b"DIAL\x2b\0\0\0\0\0\0\0\x20\0\0\0NAME\x0b\0\0\0Berel Sala\0DATA\x04\0\0\0\0\0\0\0DELE\x04\0\0\0\0\0\0\0".to_vec());
     }
+
+    #[test]
+    fn replace_repeated_fields() {
+        let mut record = Tes3Record::new(b"BSGN");
+        record.add_field(Tes3Field::new_zstring(b"NAME", String::from("Lady")).unwrap());
+        record.add_field(Tes3Field::new(b"NPCS", make_str_vec("lady's favor", 32)).unwrap());
+        record.add_field(Tes3Field::new(b"NPCS", make_str_vec("lady's grace", 32)).unwrap());
+        record.add_field(Tes3Field::new_zstring(b"TNAM", String::from("lady.tga")).unwrap());
+
+        assert_eq!(
+            record.get_string_list(b"NPCS", 32).unwrap(),
+            ["lady's favor", "lady's grace"]
+        );
+
+        record.set_string_list(b"NPCS", ["fireball"], 32).unwrap();
+        let names: Vec<_> = record.iter().map(|f| f.name_as_str()).collect();
+        assert_eq!(names, ["NAME", "NPCS", "TNAM"]);
+        assert_eq!(record.get_string_list(b"NPCS", 32).unwrap(), ["fireball"]);
+
+        assert!(record
+            .set_string_list(b"NPCS", ["x".repeat(33)], 32)
+            .is_err());
+        assert_eq!(record.len(), 3);
+
+        record.set_string_list(b"SPEL", ["fireball"], 32).unwrap();
+        assert_eq!(record.iter().last().unwrap().name(), b"SPEL");
+    }
 }
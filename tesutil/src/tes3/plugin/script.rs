@@ -0,0 +1,353 @@
+use std::io::Cursor;
+
+use crate::tes3::{Tes3Field, Tes3Record};
+use crate::{
+    check_size, checked_len, decode_failed, read_string, read_string_bytes, write_str, ExtraFields,
+    Field, FieldOrder, Form, Record, TesError,
+};
+
+use binrw::{BinReaderExt, BinWriterExt};
+
+const ID_LENGTH: usize = 32;
+/// Size of SCHD: the ID, the three variable counts, and the sizes of SCDT and SCVR
+const SCHD_SIZE: usize = ID_LENGTH + 20;
+
+/// Types of local variables a script can declare
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum VariableType {
+    Short,
+    Long,
+    Float,
+}
+
+/// A Morrowind script
+///
+/// The game runs a script's compiled bytecode, not its source text, so the compiled data must be
+/// regenerated by the Construction Set or another compiler after the text changes. Local variables
+/// are referred to by their type and index within that type, so the variable lists are kept in
+/// declaration order.
+#[derive(Debug, Default)]
+pub struct Script {
+    id: String,
+    shorts: Vec<String>,
+    longs: Vec<String>,
+    floats: Vec<String>,
+    compiled: Vec<u8>,
+    text: String,
+    extra_fields: Vec<Tes3Field>,
+    field_order: FieldOrder,
+}
+
+impl Script {
+    /// Creates a new, empty script
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ID is longer than 32 bytes.
+    pub fn new(id: String) -> Result<Script, TesError> {
+        check_size(&id, ID_LENGTH, "Script ID too long")?;
+
+        Ok(Script {
+            id,
+            ..Script::default()
+        })
+    }
+
+    /// Gets the script's ID
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    /// Sets the script's ID
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ID is longer than 32 bytes.
+    pub fn set_id(&mut self, id: String) -> Result<(), TesError> {
+        check_size(&id, ID_LENGTH, "Script ID too long")?;
+        self.id = id;
+        Ok(())
+    }
+
+    /// Gets the names of the script's local variables of a given type
+    pub fn variables(&self, variable_type: VariableType) -> impl Iterator<Item = &str> + '_ {
+        match variable_type {
+            VariableType::Short => &self.shorts,
+            VariableType::Long => &self.longs,
+            VariableType::Float => &self.floats,
+        }
+        .iter()
+        .map(String::as_str)
+    }
+
+    /// Finds a local variable by name, returning its type and index within that type
+    ///
+    /// Variable names are case-insensitive.
+    pub fn find_variable(&self, name: &str) -> Option<(VariableType, usize)> {
+        [
+            (VariableType::Short, &self.shorts),
+            (VariableType::Long, &self.longs),
+            (VariableType::Float, &self.floats),
+        ]
+        .into_iter()
+        .find_map(|(variable_type, names)| {
+            names
+                .iter()
+                .position(|n| n.eq_ignore_ascii_case(name))
+                .map(|i| (variable_type, i))
+        })
+    }
+
+    /// Sets the names of the script's local variables of a given type
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of the names contain null bytes.
+    pub fn set_variables(
+        &mut self,
+        variable_type: VariableType,
+        names: Vec<String>,
+    ) -> Result<(), TesError> {
+        if names.iter().any(|n| n.contains('\0')) {
+            return Err(TesError::RequirementFailed(String::from(
+                "Variable names may not contain null bytes",
+            )));
+        }
+
+        match variable_type {
+            VariableType::Short => self.shorts = names,
+            VariableType::Long => self.longs = names,
+            VariableType::Float => self.floats = names,
+        }
+        Ok(())
+    }
+
+    /// Gets the script's compiled bytecode
+    pub fn compiled(&self) -> &[u8] {
+        self.compiled.as_slice()
+    }
+
+    pub fn set_compiled(&mut self, compiled: Vec<u8>) {
+        self.compiled = compiled;
+    }
+
+    /// Gets the script's source text
+    pub fn text(&self) -> &str {
+        self.text.as_str()
+    }
+
+    /// Replaces the script's source text and updates the local variables to match its declarations
+    ///
+    /// The compiled bytecode is left as it is.
+    pub fn set_text(&mut self, text: String) {
+        self.shorts.clear();
+        self.longs.clear();
+        self.floats.clear();
+
+        for line in text.lines() {
+            // everything after a semicolon is a comment
+            let line = line.split(';').next().unwrap_or("");
+            let mut tokens = line
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|t| !t.is_empty());
+            let names = match tokens.next().map(str::to_ascii_lowercase).as_deref() {
+                Some("short") => &mut self.shorts,
+                Some("long") => &mut self.longs,
+                Some("float") => &mut self.floats,
+                _ => continue,
+            };
+
+            if let Some(name) = tokens.next() {
+                names.push(String::from(name));
+            }
+        }
+
+        self.text = text;
+    }
+}
+
+impl ExtraFields for Script {
+    type Field = Tes3Field;
+
+    fn extra_fields(&self) -> &[Tes3Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes3Field> {
+        &mut self.extra_fields
+    }
+}
+
+impl Form for Script {
+    type Field = Tes3Field;
+    type Record = Tes3Record;
+
+    const RECORD_TYPE: &'static [u8; 4] = b"SCPT";
+
+    /// Reads a script from a raw record
+    ///
+    /// # Errors
+    ///
+    /// Fails if the record is not a SCPT record, if it has no SCHD field, or if the number of
+    /// variables in SCVR doesn't match the counts in SCHD.
+    fn read(record: &Tes3Record) -> Result<Script, TesError> {
+        Script::assert(record)?;
+
+        let mut script = Script::default();
+        let mut counts = None;
+        let mut variables = vec![];
+        for field in record.iter() {
+            match field.name() {
+                b"SCHD" => {
+                    let size = field.get().len();
+                    if size != SCHD_SIZE {
+                        return Err(decode_failed(format!("Unexpected SCHD size {}", size)));
+                    }
+
+                    let mut reader = field.reader();
+                    script.id = read_string::<ID_LENGTH, _>(&mut reader)?;
+                    let num_shorts: u32 = reader.read_le()?;
+                    let num_longs: u32 = reader.read_le()?;
+                    let num_floats: u32 = reader.read_le()?;
+                    // the remaining values are the sizes of SCDT and SCVR, which we get from the
+                    // fields themselves
+                    counts = Some([num_shorts, num_longs, num_floats].map(|n| n as usize));
+                }
+                b"SCVR" => {
+                    for name in field.get().split(|b| *b == 0).filter(|n| !n.is_empty()) {
+                        variables.push(read_string_bytes(name)?);
+                    }
+                }
+                b"SCDT" => script.compiled = field.get().to_vec(),
+                b"SCTX" => script.text = String::from(field.get_string()?),
+                _ => script.extra_fields.push(field.clone()),
+            }
+        }
+
+        let [num_shorts, num_longs, num_floats] =
+            counts.ok_or_else(|| decode_failed("Missing SCHD field in SCPT record"))?;
+        if variables.len() != num_shorts + num_longs + num_floats {
+            return Err(decode_failed(format!(
+                "Script {} declares {} variables but has {} variable names",
+                script.id,
+                num_shorts + num_longs + num_floats,
+                variables.len()
+            )));
+        }
+
+        script.floats = variables.split_off(num_shorts + num_longs);
+        script.longs = variables.split_off(num_shorts);
+        script.shorts = variables;
+        script.field_order = FieldOrder::from_record(record);
+
+        Ok(script)
+    }
+
+    fn write(&self, record: &mut Tes3Record) -> Result<(), TesError> {
+        Script::assert(record)?;
+
+        record.clear();
+
+        let mut variables = vec![];
+        for name in self.shorts.iter().chain(&self.longs).chain(&self.floats) {
+            variables.extend_from_slice(name.as_bytes());
+            variables.push(0);
+        }
+
+        let mut buf = Vec::with_capacity(SCHD_SIZE);
+        let mut writer = Cursor::new(&mut buf);
+        write_str::<ID_LENGTH, _>(&self.id, &mut writer)?;
+        for names in [&self.shorts, &self.longs, &self.floats] {
+            writer.write_le(&checked_len::<u32>(names.len(), "Too many variables")?)?;
+        }
+        writer.write_le(&checked_len::<u32>(
+            self.compiled.len(),
+            "Compiled script too large",
+        )?)?;
+        writer.write_le(&checked_len::<u32>(
+            variables.len(),
+            "Variable names too long",
+        )?)?;
+        record.add_field(Tes3Field::new(b"SCHD", buf)?);
+
+        if !variables.is_empty() {
+            record.add_field(Tes3Field::new(b"SCVR", variables)?);
+        }
+        record.add_field(Tes3Field::new(b"SCDT", self.compiled.clone())?);
+        record.add_field(Tes3Field::new_string(b"SCTX", self.text.clone())?);
+
+        for field in &self.extra_fields {
+            record.add_field(field.clone());
+        }
+        record.sort_fields(&self.field_order);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::make_str_vec;
+
+    const TEXT: &str = "Begin LocalState\r\n\
+        short done ; whether the player has been here\r\n\
+        float timer\r\n\
+        short doOnce\r\n\
+        long count\r\n\
+        ; float notAVariable\r\n\
+        End";
+
+    #[test]
+    fn read_write_script() {
+        let mut script = Script::new(String::from("LocalState")).unwrap();
+        script.set_text(String::from(TEXT));
+        assert_eq!(
+            script.variables(VariableType::Short).collect::<Vec<_>>(),
+            ["done", "doOnce"]
+        );
+        assert_eq!(
+            script.find_variable("TIMER"),
+            Some((VariableType::Float, 0))
+        );
+        assert_eq!(script.find_variable("notAVariable"), None);
+        script.set_compiled(vec![1, 2, 3, 4]);
+
+        let mut record = Tes3Record::new(b"SCPT");
+        script.write(&mut record).unwrap();
+
+        let schd = record.iter().next().unwrap().get();
+        assert_eq!(schd.len(), SCHD_SIZE);
+        // two shorts, one long, one float, 4 bytes of bytecode, and 24 bytes of names
+        assert_eq!(
+            &schd[ID_LENGTH..],
+            &[2, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 4, 0, 0, 0, 24, 0, 0, 0]
+        );
+
+        let script = Script::read(&record).unwrap();
+        assert_eq!(script.id(), "LocalState");
+        assert_eq!(
+            script.find_variable("doonce"),
+            Some((VariableType::Short, 1))
+        );
+        assert_eq!(script.find_variable("count"), Some((VariableType::Long, 0)));
+        assert_eq!(script.compiled(), &[1, 2, 3, 4]);
+        assert_eq!(script.text(), TEXT);
+    }
+
+    #[test]
+    fn mismatched_variables() {
+        let mut schd = make_str_vec("BadScript", ID_LENGTH);
+        for value in [1u32, 0, 0, 0, 0] {
+            schd.extend(value.to_le_bytes());
+        }
+
+        let mut record = Tes3Record::new(b"SCPT");
+        record.add_field(Tes3Field::new(b"SCHD", schd).unwrap());
+        record.add_field(Tes3Field::new(b"SCVR", b"one\0two\0".to_vec()).unwrap());
+        assert!(Script::read(&record).is_err());
+
+        assert!(Script::new("a".repeat(ID_LENGTH)).is_ok());
+        assert!(Script::new("a".repeat(ID_LENGTH + 1)).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::cell_reference::is_reference_start;
+use super::CELL_INTERIOR;
+use crate::tes3::Tes3Record;
+use crate::{decode_failed, Field, GridCoords, Record, TesError};
+use binrw::BinReaderExt;
+
+/// Identifies a cell for the purpose of traveling between cells
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum CellId {
+    /// An interior cell, by lowercase name
+    Interior(String),
+    /// An exterior cell, by grid coordinates
+    Exterior(GridCoords),
+}
+
+impl CellId {
+    /// Creates the ID of an interior cell
+    ///
+    /// Cell names are case-insensitive, so the name is converted to lowercase.
+    pub fn interior(name: &str) -> CellId {
+        CellId::Interior(name.to_lowercase())
+    }
+
+    /// Gets the ID of the cell a CELL record defines
+    ///
+    /// # Errors
+    ///
+    /// Fails if the record is not a CELL record, if it has no DATA field, or if it's an interior
+    /// with no name.
+    pub fn read(record: &Tes3Record) -> Result<CellId, TesError> {
+        if record.name() != b"CELL" {
+            return Err(decode_failed(format!(
+                "Expected CELL record, got {}",
+                record.display_name()
+            )));
+        }
+
+        let mut name = None;
+        let mut data = None;
+        // references have NAME and DATA fields too, so stop before the first one
+        for field in record.iter().take_while(|f| !is_reference_start(f.name())) {
+            match field.name() {
+                b"NAME" => name = Some(field.get_zstring()?),
+                b"DATA" => {
+                    let mut reader = field.reader();
+                    let flags: u32 = reader.read_le()?;
+                    let x = reader.read_le()?;
+                    let y = reader.read_le()?;
+                    data = Some((flags, x, y));
+                }
+                _ => (),
+            }
+        }
+
+        match data {
+            Some((flags, _, _)) if flags & CELL_INTERIOR != 0 => match name {
+                Some(name) => Ok(CellId::interior(name)),
+                None => Err(decode_failed("Interior cell has no name")),
+            },
+            Some((_, x, y)) => Ok(CellId::Exterior(GridCoords::new(x, y))),
+            None => Err(decode_failed("Cell has no DATA field")),
+        }
+    }
+}
+
+/// How a traveler gets from one cell to another
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TravelKind {
+    /// A door, by its reference index in the cell it's in
+    Door(u32),
+    /// A travel service, by the ID of the NPC offering it
+    Service(String),
+}
+
+/// A way to get from one cell to another
+#[derive(Debug, Clone, PartialEq)]
+pub struct TravelLink {
+    pub from: CellId,
+    pub to: CellId,
+    pub kind: TravelKind,
+    /// Where the traveler arrives
+    pub position: (f32, f32, f32),
+    /// Which way the traveler faces on arrival
+    pub rotation: (f32, f32, f32),
+}
+
+/// The network of doors and travel services connecting cells
+#[derive(Debug, Default)]
+pub struct TravelGraph {
+    links: Vec<TravelLink>,
+    by_cell: HashMap<CellId, Vec<usize>>,
+}
+
+impl TravelGraph {
+    /// Creates an empty travel graph
+    pub fn new() -> TravelGraph {
+        TravelGraph::default()
+    }
+
+    pub fn add_link(&mut self, link: TravelLink) {
+        self.by_cell
+            .entry(link.from.clone())
+            .or_insert_with(Vec::new)
+            .push(self.links.len());
+        self.links.push(link);
+    }
+
+    /// Gets all links in the graph
+    pub fn links(&self) -> impl Iterator<Item = &TravelLink> + '_ {
+        self.links.iter()
+    }
+
+    /// Gets the links leading out of a cell
+    pub fn links_from<'a>(&'a self, cell: &CellId) -> impl Iterator<Item = &'a TravelLink> + 'a {
+        self.by_cell
+            .get(cell)
+            .into_iter()
+            .flatten()
+            .map(|&i| &self.links[i])
+    }
+
+    /// Finds the route with the fewest links from one cell to another
+    ///
+    /// Returns `None` if the destination can't be reached. If the cells are the same, the route is
+    /// empty.
+    pub fn route(&self, from: &CellId, to: &CellId) -> Option<Vec<&TravelLink>> {
+        self.find_route(from, |cell| cell == to)
+    }
+
+    /// Finds the closest exterior reachable from a cell
+    ///
+    /// This is the link by which the traveler finally arrives outside, which is a sensible place to
+    /// put a character whose own cell doesn't exist. Returns `None` if the cell is an exterior or
+    /// no exterior can be reached.
+    pub fn nearest_exterior(&self, from: &CellId) -> Option<&TravelLink> {
+        self.find_route(from, |cell| matches!(cell, CellId::Exterior(_)))?
+            .pop()
+    }
+
+    fn find_route<F>(&self, from: &CellId, is_destination: F) -> Option<Vec<&TravelLink>>
+    where
+        F: Fn(&CellId) -> bool,
+    {
+        // breadth-first search, remembering the link we arrived at each cell by
+        let mut arrivals: HashMap<&CellId, Option<usize>> = HashMap::new();
+        let mut queue = VecDeque::new();
+        arrivals.insert(from, None);
+        queue.push_back(from);
+
+        while let Some(cell) = queue.pop_front() {
+            if is_destination(cell) {
+                let mut route = vec![];
+                let mut current = cell;
+                while let Some(i) = arrivals[current] {
+                    let link = &self.links[i];
+                    route.push(link);
+                    current = &link.from;
+                }
+
+                route.reverse();
+                return Some(route);
+            }
+
+            for &i in self.by_cell.get(cell).into_iter().flatten() {
+                let next = &self.links[i].to;
+                if !arrivals.contains_key(next) {
+                    arrivals.insert(next, Some(i));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Gets all the cells reachable from a cell, including the cell itself
+    pub fn reachable_from(&self, from: &CellId) -> HashSet<CellId> {
+        let mut reachable = HashSet::new();
+        let mut stack = vec![from];
+        while let Some(cell) = stack.pop() {
+            if reachable.insert(cell.clone()) {
+                stack.extend(self.links_from(cell).map(|l| &l.to));
+            }
+        }
+
+        reachable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(from: CellId, to: CellId, kind: TravelKind) -> TravelLink {
+        TravelLink {
+            from,
+            to,
+            kind,
+            position: (0., 0., 0.),
+            rotation: (0., 0., 0.),
+        }
+    }
+
+    #[test]
+    fn find_routes() {
+        let cellar = CellId::interior("Balmora, Caius Cosades' House");
+        let house = CellId::Interior(String::from("balmora, caius cosades' house, upstairs"));
+        let balmora = CellId::Exterior(GridCoords::new(-3, -2));
+        let vivec = CellId::Exterior(GridCoords::new(3, -10));
+
+        let mut graph = TravelGraph::new();
+        graph.add_link(link(cellar.clone(), house.clone(), TravelKind::Door(1)));
+        graph.add_link(link(house.clone(), cellar.clone(), TravelKind::Door(2)));
+        graph.add_link(link(house.clone(), balmora.clone(), TravelKind::Door(3)));
+        graph.add_link(link(
+            balmora.clone(),
+            vivec.clone(),
+            TravelKind::Service(String::from("dilami androm")),
+        ));
+
+        let route = graph.route(&cellar, &vivec).unwrap();
+        let kinds: Vec<_> = route.iter().map(|l| l.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            [
+                TravelKind::Door(1),
+                TravelKind::Door(3),
+                TravelKind::Service(String::from("dilami androm"))
+            ]
+        );
+        assert!(graph.route(&vivec, &cellar).is_none());
+        assert!(graph.route(&vivec, &vivec).unwrap().is_empty());
+
+        assert_eq!(graph.nearest_exterior(&cellar).unwrap().to, balmora);
+        assert!(graph.nearest_exterior(&balmora).is_none());
+
+        assert_eq!(graph.reachable_from(&house).len(), 4);
+        assert_eq!(graph.links_from(&house).count(), 2);
+    }
+}
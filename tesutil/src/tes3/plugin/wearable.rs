@@ -0,0 +1,117 @@
+use std::convert::TryFrom;
+
+use crate::tes3::{Tes3Field, Tes3Record};
+use crate::{decode_failed, decode_failed_because, Field, Record, TesError};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+/// A place on a character's body that a piece of armor or clothing can cover
+#[derive(Debug, Copy, Clone, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
+#[repr(u8)]
+pub enum BipedObject {
+    Head,
+    Hair,
+    Neck,
+    Cuirass,
+    Groin,
+    Skirt,
+    RightHand,
+    LeftHand,
+    RightWrist,
+    LeftWrist,
+    Shield,
+    RightForearm,
+    LeftForearm,
+    RightUpperArm,
+    LeftUpperArm,
+    RightFoot,
+    LeftFoot,
+    RightAnkle,
+    LeftAnkle,
+    RightKnee,
+    LeftKnee,
+    RightUpperLeg,
+    LeftUpperLeg,
+    RightPauldron,
+    LeftPauldron,
+    Weapon,
+    Tail,
+}
+
+/// The body parts a piece of armor or clothing shows on one place on the body
+///
+/// Each mapping is stored as an INDX field with the place on the body, followed by BNAM and CNAM
+/// fields with the IDs of the BODY records to show on male and female characters.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BodyPartMapping {
+    pub object: BipedObject,
+    /// ID of the body part shown on male characters
+    pub male_part: Option<String>,
+    /// ID of the body part shown on female characters
+    ///
+    /// If this is `None`, female characters use the male part.
+    pub female_part: Option<String>,
+}
+
+impl BodyPartMapping {
+    /// Creates a mapping that shows the same body part on male and female characters
+    pub fn new(object: BipedObject, part: String) -> BodyPartMapping {
+        BodyPartMapping {
+            object,
+            male_part: Some(part),
+            female_part: None,
+        }
+    }
+
+    /// Reads a body part mapping field into a list of mappings
+    ///
+    /// Returns `false` if the field isn't part of a body part mapping.
+    pub(crate) fn read_field(
+        mappings: &mut Vec<BodyPartMapping>,
+        field: &Tes3Field,
+    ) -> Result<bool, TesError> {
+        match field.name() {
+            b"INDX" => {
+                let index = field
+                    .get()
+                    .first()
+                    .copied()
+                    .ok_or_else(|| decode_failed("Empty INDX field"))?;
+                mappings.push(BodyPartMapping {
+                    object: BipedObject::try_from(index)
+                        .map_err(|e| decode_failed_because("Invalid body part index", e))?,
+                    male_part: None,
+                    female_part: None,
+                });
+            }
+            b"BNAM" => match mappings.last_mut() {
+                Some(mapping) => mapping.male_part = Some(String::from(field.get_zstring()?)),
+                None => return Err(decode_failed("Orphaned BNAM field")),
+            },
+            b"CNAM" => match mappings.last_mut() {
+                Some(mapping) => mapping.female_part = Some(String::from(field.get_zstring()?)),
+                None => return Err(decode_failed("Orphaned CNAM field")),
+            },
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    /// Writes a list of mappings to a record
+    pub(crate) fn write_all(
+        mappings: &[BodyPartMapping],
+        record: &mut Tes3Record,
+    ) -> Result<(), TesError> {
+        for mapping in mappings {
+            record.add_field(Tes3Field::new(b"INDX", vec![mapping.object.into()])?);
+            if let Some(ref male_part) = mapping.male_part {
+                record.add_field(Tes3Field::new_zstring(b"BNAM", male_part.clone())?);
+            }
+            if let Some(ref female_part) = mapping.female_part {
+                record.add_field(Tes3Field::new_zstring(b"CNAM", female_part.clone())?);
+            }
+        }
+
+        Ok(())
+    }
+}
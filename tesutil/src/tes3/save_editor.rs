@@ -0,0 +1,365 @@
+use crate::tes3::{
+    Actor, InventoryItem, Npc, NpcChange, PlayerReference, Tes3Field, Tes3Plugin,
+    ACTOR_STRING_LENGTH, NAME_LENGTH,
+};
+use crate::*;
+
+/// ID of the player's NPC record
+pub const PLAYER_ID: &str = "player";
+/// ID of the player's reference in a save
+pub const PLAYER_REF_ID: &str = "PlayerSaveGame";
+/// ID of the gold item
+pub const GOLD_ID: &str = "Gold_001";
+
+// size of the NPDT field when an NPC's stats aren't auto-calculated, as is the case for the player
+const FULL_NPDT_SIZE: usize = 52;
+
+fn missing_record(id: &str) -> TesError {
+    TesError::RequirementFailed(format!("Save is missing the {} record", id))
+}
+
+/// A high-level editor for common changes to the player in a Morrowind save
+///
+/// Changes are staged in the editor and written to the save by [`commit`]. Because the player's
+/// NPC record can't yet be fully written, the editor only replaces the fields it changes and leaves
+/// the rest of the record untouched. The player's gold is in their inventory, which is stored in
+/// the NPC change record with the same ID as the player's reference.
+///
+/// [`commit`]: #method.commit
+pub struct SaveEditor<'a> {
+    save: &'a mut Tes3Plugin,
+    player_ref: PlayerReference,
+    player_change: Option<NpcChange>,
+    is_inventory_changed: bool,
+    name: String,
+    level: u16,
+    spells: Vec<String>,
+}
+
+impl<'a> SaveEditor<'a> {
+    /// Creates an editor for a save
+    ///
+    /// # Errors
+    ///
+    /// Fails if the save doesn't have the player's NPC record and reference or if they or the
+    /// player's NPC change record are invalid.
+    pub fn new(save: &'a mut Tes3Plugin) -> Result<SaveEditor<'a>, TesError> {
+        let player: Npc = save
+            .get(PLAYER_ID)?
+            .ok_or_else(|| missing_record(PLAYER_ID))?;
+        let player_ref = save
+            .get(PLAYER_REF_ID)?
+            .ok_or_else(|| missing_record(PLAYER_REF_ID))?;
+        let player_change = save.get(PLAYER_REF_ID)?;
+
+        Ok(SaveEditor {
+            name: String::from(player.name().unwrap_or("")),
+            level: player.level,
+            spells: player.iter_spells().map(String::from).collect(),
+            save,
+            player_ref,
+            player_change,
+            is_inventory_changed: false,
+        })
+    }
+
+    /// Gets the player's name
+    pub fn player_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Renames the player, both in game and in the save menu
+    ///
+    /// # Errors
+    ///
+    /// Fails if the name is longer than [`NAME_LENGTH`].
+    ///
+    /// [`NAME_LENGTH`]: constant.NAME_LENGTH.html
+    pub fn set_player_name(&mut self, name: &str) -> Result<(), TesError> {
+        check_size(name, NAME_LENGTH, "Player name too long")?;
+        self.name = String::from(name);
+        Ok(())
+    }
+
+    /// Gets the player's level
+    pub fn level(&self) -> u16 {
+        self.level
+    }
+
+    /// Sets the player's level
+    ///
+    /// # Errors
+    ///
+    /// Fails if the level is 0.
+    pub fn set_level(&mut self, level: u16) -> Result<(), TesError> {
+        check_range(level, 1, u16::MAX, "Level must be at least 1")?;
+        self.level = level;
+        Ok(())
+    }
+
+    /// Gets the IDs of the player's spells
+    pub fn spells(&self) -> impl Iterator<Item = &str> {
+        self.spells.iter().map(|s| s.as_str())
+    }
+
+    /// Does the player know a spell?
+    pub fn has_spell(&self, spell: &str) -> bool {
+        self.spells.iter().any(|s| s.eq_ignore_ascii_case(spell))
+    }
+
+    /// Adds a spell to the player's spell list
+    ///
+    /// Returns false if the player already knew the spell.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the spell ID is longer than [`ACTOR_STRING_LENGTH`].
+    ///
+    /// [`ACTOR_STRING_LENGTH`]: constant.ACTOR_STRING_LENGTH.html
+    pub fn add_spell(&mut self, spell: &str) -> Result<bool, TesError> {
+        check_size(spell, ACTOR_STRING_LENGTH, "Spell ID too long")?;
+        if self.has_spell(spell) {
+            return Ok(false);
+        }
+
+        self.spells.push(String::from(spell));
+        Ok(true)
+    }
+
+    /// Removes a spell from the player's spell list
+    ///
+    /// Returns false if the player didn't know the spell.
+    pub fn remove_spell(&mut self, spell: &str) -> bool {
+        let count = self.spells.len();
+        self.spells.retain(|s| !s.eq_ignore_ascii_case(spell));
+        self.spells.len() != count
+    }
+
+    /// Gets how much gold the player is carrying
+    pub fn gold(&self) -> u32 {
+        self.player_change.as_ref().map_or(0, |change| {
+            change
+                .iter_inventory()
+                .filter(|i| i.id.eq_ignore_ascii_case(GOLD_ID))
+                .fold(0, |total, i| total.saturating_add(i.count))
+        })
+    }
+
+    /// Gives the player gold, or takes it away if `amount` is negative
+    ///
+    /// If more gold is taken away than the player has, they're left with none.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the save doesn't have the player's NPC change record, which holds their inventory.
+    pub fn add_gold(&mut self, amount: i32) -> Result<(), TesError> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let total = i64::from(self.gold()) + i64::from(amount);
+        let total = u32::try_from(total.max(0)).unwrap_or(u32::MAX);
+        let change = self.player_change.as_mut().ok_or_else(|| {
+            TesError::RequirementFailed(format!(
+                "Save is missing the {} NPC change record",
+                PLAYER_REF_ID
+            ))
+        })?;
+        change.remove_items_by_id(GOLD_ID);
+        if total > 0 {
+            change.add_inventory_item(InventoryItem::new(String::from(GOLD_ID), total));
+        }
+        self.is_inventory_changed = true;
+
+        Ok(())
+    }
+
+    /// Gets one of the player's base attributes
+    pub fn attribute(&self, attribute: Attribute) -> f32 {
+        self.player_ref.attributes[attribute].base
+    }
+
+    /// Sets one of the player's base attributes
+    ///
+    /// The attribute's current value changes by the same amount, so any active fortify or drain
+    /// effects are preserved.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value is negative or greater than 255.
+    pub fn set_attribute(&mut self, attribute: Attribute, value: f32) -> Result<(), TesError> {
+        check_range(value, 0., 255., "Attribute must be between 0 and 255")?;
+        let stat = &mut self.player_ref.attributes[attribute];
+        stat.current += value - stat.base;
+        stat.base = value;
+        Ok(())
+    }
+
+    /// Gets the save being edited
+    ///
+    /// Changes made through the editor aren't visible in the save until they're committed.
+    pub fn save(&self) -> &Tes3Plugin {
+        self.save
+    }
+
+    /// Builds the fields of the player's NPC record with the staged changes applied
+    fn player_fields(&self) -> Result<Vec<Tes3Field>, TesError> {
+        let record = self
+            .save
+            .get_record_with_type(PLAYER_ID, Npc::RECORD_TYPE)?
+            .ok_or_else(|| missing_record(PLAYER_ID))?;
+
+        let mut fields = Vec::with_capacity(record.len());
+        for field in record.iter() {
+            match field.name() {
+                b"FNAM" => fields.push(Tes3Field::new_zstring(b"FNAM", self.name.clone())?),
+                b"NPDT" => {
+                    let mut data = field.get().to_vec();
+                    data[..2].copy_from_slice(&self.level.to_le_bytes());
+                    if data.len() == FULL_NPDT_SIZE {
+                        for (i, stat) in self.player_ref.attributes.values().enumerate() {
+                            data[2 + i] = stat.base as u8;
+                        }
+                    }
+                    fields.push(Tes3Field::new(b"NPDT", data)?);
+                }
+                _ => fields.push(field.clone()),
+            }
+        }
+
+        Ok(fields)
+    }
+
+    /// Writes the staged changes to the save
+    ///
+    /// # Errors
+    ///
+    /// Fails if the player's records can't be written.
+    pub fn commit(self) -> Result<(), TesError> {
+        let fields = self.player_fields()?;
+        {
+            let mut player = self
+                .save
+                .get_record_with_type_mut(PLAYER_ID, Npc::RECORD_TYPE)?
+                .ok_or_else(|| missing_record(PLAYER_ID))?;
+            player.clear();
+            for field in fields {
+                player.add_field(field);
+            }
+            player.set_string_list(b"NPCS", &self.spells, ACTOR_STRING_LENGTH)?;
+        }
+
+        {
+            let mut player_ref = self
+                .save
+                .get_record_with_type_mut(PLAYER_REF_ID, PlayerReference::RECORD_TYPE)?
+                .ok_or_else(|| missing_record(PLAYER_REF_ID))?;
+            self.player_ref.write(&mut player_ref)?;
+        }
+
+        // the change record is only rewritten if we changed it, so nothing is lost if any of its
+        // contents can't be represented exactly
+        if let Some(player_change) = self
+            .player_change
+            .as_ref()
+            .filter(|_| self.is_inventory_changed)
+        {
+            let mut record = self
+                .save
+                .get_record_with_type_mut(PLAYER_REF_ID, NpcChange::RECORD_TYPE)?
+                .ok_or_else(|| missing_record(PLAYER_REF_ID))?;
+            player_change.write(&mut record)?;
+        }
+
+        if let Some(save_info) = self.save.get_save_info_mut() {
+            save_info.set_player_name(self.name.clone())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tes3::Tes3Record;
+    use std::io::Cursor;
+
+    static NPC_RECORD: &[u8] = include_bytes!("plugin/test/npc_record.bin");
+    static REFR_RECORD: &[u8] = include_bytes!("plugin/test/refr_record.bin");
+    static NPCC_RECORD: &[u8] = include_bytes!("plugin/test/npcc_record.bin");
+
+    #[test]
+    fn edit_player() {
+        let mut save = Tes3Plugin::new(String::new(), String::new()).unwrap();
+        save.add_record(Tes3Record::read(Cursor::new(NPC_RECORD)).unwrap())
+            .unwrap();
+        save.add_record(Tes3Record::read(Cursor::new(REFR_RECORD)).unwrap())
+            .unwrap();
+
+        let mut editor = SaveEditor::new(&mut save).unwrap();
+        assert_eq!(editor.player_name(), "Cirfenath");
+        let spell = editor.spells().next().map(String::from).unwrap();
+        editor.set_player_name("Edited").unwrap();
+        editor.set_level(12).unwrap();
+        assert!(editor.add_spell("fireball").unwrap());
+        assert!(!editor.add_spell("Fireball").unwrap());
+        assert!(editor.remove_spell(&spell));
+        editor.set_attribute(Attribute::Luck, 60.).unwrap();
+        assert!(editor.set_attribute(Attribute::Luck, 300.).is_err());
+        editor.commit().unwrap();
+
+        let player: Npc = save.get(PLAYER_ID).unwrap().unwrap();
+        assert_eq!(player.name(), Some("Edited"));
+        assert_eq!(player.level, 12);
+        assert_eq!(player.attributes[Attribute::Luck], 60);
+        assert!(player.iter_spells().any(|s| s == "fireball"));
+        assert!(!player.iter_spells().any(|s| s == spell));
+
+        let player_ref: PlayerReference = save.get(PLAYER_REF_ID).unwrap().unwrap();
+        assert_eq!(player_ref.attributes[Attribute::Luck].base, 60.);
+    }
+
+    #[test]
+    fn edit_gold() {
+        let mut save = Tes3Plugin::new(String::new(), String::new()).unwrap();
+        for record in [NPC_RECORD, REFR_RECORD, NPCC_RECORD] {
+            save.add_record(Tes3Record::read(Cursor::new(record)).unwrap())
+                .unwrap();
+        }
+
+        let mut editor = SaveEditor::new(&mut save).unwrap();
+        let gold = editor.gold();
+        editor.add_gold(250).unwrap();
+        assert_eq!(editor.gold(), gold + 250);
+        editor.commit().unwrap();
+
+        let player_change: NpcChange = save.get(PLAYER_REF_ID).unwrap().unwrap();
+        let stacks: Vec<_> = player_change
+            .iter_inventory()
+            .filter(|i| i.id == GOLD_ID)
+            .collect();
+        assert_eq!(stacks.len(), 1);
+        assert_eq!(stacks[0].count, gold + 250);
+        assert!(player_change
+            .iter_inventory()
+            .any(|i| i.id == "ring_keley" && i.is_equipped));
+
+        // taking away more gold than the player has leaves them with none
+        let mut editor = SaveEditor::new(&mut save).unwrap();
+        assert_eq!(editor.gold(), gold + 250);
+        editor.add_gold(-i32::MAX).unwrap();
+        editor.commit().unwrap();
+        assert_eq!(SaveEditor::new(&mut save).unwrap().gold(), 0);
+
+        // without the change record, there's no inventory to add gold to
+        let mut save = Tes3Plugin::new(String::new(), String::new()).unwrap();
+        for record in [NPC_RECORD, REFR_RECORD] {
+            save.add_record(Tes3Record::read(Cursor::new(record)).unwrap())
+                .unwrap();
+        }
+        let mut editor = SaveEditor::new(&mut save).unwrap();
+        assert_eq!(editor.gold(), 0);
+        assert!(editor.add_gold(100).is_err());
+    }
+}
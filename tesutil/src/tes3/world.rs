@@ -1,12 +1,23 @@
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::iter;
 use std::ops::Deref;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use ini::Ini;
 
 use super::plugin::*;
-use crate::{decode_failed, Form, Plugin, Record, TesError, World};
+use super::Bsa;
+use crate::{
+    decode_failed, list_saves, matches_search, Asset, Field, Form, FormCache, GamePaths,
+    GridCoords, IndexCache, LoadOptions, LoadStats, Plugin, Provenance, Record, SaveList,
+    SaveMetadata, SearchMatch, TesError, Vfs, World,
+};
 
 const INI_FILE: &str = "Morrowind.ini";
+const SAVE_EXTENSION: &str = "ess";
+const DEFAULT_EFFECT_COST_MULT: f32 = 0.5;
 
 /// The full set of objects in the game world
 ///
@@ -14,22 +25,27 @@ const INI_FILE: &str = "Morrowind.ini";
 /// the appropriate plugin based on load order.
 #[derive(Debug)]
 pub struct Tes3World {
-    plugins: Vec<Tes3Plugin>,
+    plugins: Vec<(String, Tes3Plugin)>,
     has_save: bool, // if we have one, it's always the last plugin
+    form_cache: FormCache<String>,
+    vfs: Vfs,
 }
 
 impl Tes3World {
-    fn load_from_plugins<'a, P, T>(game_dir: P, plugin_names: T) -> Result<Tes3World, TesError>
+    fn load_from_plugins<'a, T>(
+        paths: &GamePaths,
+        plugin_names: T,
+        cache: Option<&IndexCache>,
+    ) -> Result<Tes3World, TesError>
     where
-        P: AsRef<Path>,
         T: Iterator<Item = &'a str>,
     {
-        let plugin_dir = game_dir.as_ref().join(Self::PLUGIN_DIR);
-        let plugins = Tes3World::load_plugins(plugin_dir, plugin_names)?;
-        let plugins = plugins.into_iter().map(|(_, p)| p).collect();
+        let plugins = Tes3World::load_plugins(paths, plugin_names, cache)?;
         Ok(Tes3World {
             plugins,
             has_save: false,
+            form_cache: FormCache::new(),
+            vfs: Vfs::new(paths.clone()),
         })
     }
 
@@ -40,13 +56,86 @@ impl Tes3World {
     /// Returns an error if an I/O error occurs while reading Morrowind.ini or a plugin file,
     /// if Morrowind.ini contains invalid data, or if a plugin file contains invalid data.
     pub fn load_world<P: AsRef<Path>>(game_dir: P) -> Result<Tes3World, TesError> {
-        let path = game_dir.as_ref();
-        let ini_path = path.join(INI_FILE);
+        Tes3World::load_world_with(&Tes3World::game_paths(game_dir), LoadOptions::new())
+    }
+
+    /// Loads the world from a game whose data directories may be outside its install root
+    ///
+    /// Morrowind.ini is read from the install root, and each plugin it lists is loaded from the
+    /// data directory with the highest precedence that has it. The archives in its Archives
+    /// section are registered with the world's [`Vfs`]; any that can't be found are skipped, and
+    /// any that can't be opened are recorded in the [`Vfs`] as bad archives.
+    ///
+    /// [`Vfs`]: ../struct.Vfs.html
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading Morrowind.ini or a plugin file, or if
+    /// Morrowind.ini or a plugin file contains invalid data.
+    pub fn load_world_with(paths: &GamePaths, options: LoadOptions) -> Result<Tes3World, TesError> {
+        let ini_path = paths.root().join(INI_FILE);
         let ini = Ini::load_from_file(ini_path)?;
         let game_files = ini
             .section(Some("Game Files"))
             .ok_or_else(|| decode_failed(format!("No Game Files section in {}", INI_FILE)))?;
-        Tes3World::load_from_plugins(game_dir, game_files.iter().map(|(_, v)| v))
+        let mut world =
+            Tes3World::load_from_plugins(paths, game_files.iter().map(|(_, v)| v), options.cache)?;
+
+        if let Some(archives) = ini.section(Some("Archives")) {
+            // archives listed later take precedence, the same as plugins
+            for (_, archive_name) in archives.iter() {
+                if let Some(archive_path) = paths.find_data_file(archive_name) {
+                    match Bsa::open(&archive_path) {
+                        Ok(archive) => world.vfs.add_archive(archive),
+                        Err(e) => world.vfs.add_bad_archive(archive_path, e),
+                    }
+                }
+            }
+        }
+
+        Ok(world)
+    }
+
+    /// Loads the world from an explicit list of plugin files, in load order
+    ///
+    /// Unlike [`load_world`], this doesn't read Morrowind.ini or sort the plugins, so it's suitable
+    /// for analyzing plugins outside of a game installation.
+    ///
+    /// [`load_world`]: #method.load_world
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading a plugin file or if a plugin file
+    /// contains invalid data.
+    pub fn from_plugins<P, T>(paths: T) -> Result<Tes3World, TesError>
+    where
+        P: AsRef<Path>,
+        T: IntoIterator<Item = P>,
+    {
+        Tes3World::from_plugins_with(paths, LoadOptions::new())
+    }
+
+    /// Loads the world from an explicit list of plugin files with the given options
+    ///
+    /// See [`from_plugins`].
+    ///
+    /// [`from_plugins`]: #method.from_plugins
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading a plugin file or if a plugin file
+    /// contains invalid data.
+    pub fn from_plugins_with<P, T>(paths: T, options: LoadOptions) -> Result<Tes3World, TesError>
+    where
+        P: AsRef<Path>,
+        T: IntoIterator<Item = P>,
+    {
+        Ok(Tes3World {
+            plugins: Tes3World::load_plugin_files(paths, options.cache)?,
+            has_save: false,
+            form_cache: FormCache::new(),
+            vfs: Vfs::default(),
+        })
     }
 
     /// Loads the world from a save file
@@ -60,17 +149,103 @@ impl Tes3World {
         P: AsRef<Path>,
         Q: AsRef<Path>,
     {
+        Tes3World::load_from_save_with(
+            &Tes3World::game_paths(game_dir),
+            save_path,
+            LoadOptions::new(),
+        )
+    }
+
+    /// Loads the world from a save file, looking for its plugins in a game's data directories
+    ///
+    /// The save itself changes too often to be worth caching, so it's always read in full, even
+    /// if the options have an index cache. Morrowind saves can't be loaded without their plugins,
+    /// so a missing plugin fails the load whatever the options' missing plugin policy is.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading a plugin file or if a plugin file
+    /// contains invalid data.
+    pub fn load_from_save_with<P: AsRef<Path>>(
+        paths: &GamePaths,
+        save_path: P,
+        options: LoadOptions,
+    ) -> Result<Tes3World, TesError> {
+        let save_path = save_path.as_ref();
         let save = Tes3Plugin::load_file(save_path)?;
-        let mut world = Tes3World::load_from_plugins(game_dir, save.iter_masters())?;
-        world.plugins.push(save);
+        let mut world = Tes3World::load_from_plugins(paths, save.iter_masters(), options.cache)?;
+        let save_name = save_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        world.plugins.push((save_name, save));
         world.has_save = true;
         Ok(world)
     }
 
+    /// Lists the saves in a directory, from most to least recently modified
+    ///
+    /// Only the header of each save is read. Files that can't be read as saves are listed
+    /// separately instead of failing the whole listing.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs while reading the directory.
+    pub fn list_saves<P: AsRef<Path>>(dir: P) -> Result<SaveList, TesError> {
+        list_saves(dir, SAVE_EXTENSION, |reader| {
+            let plugin = Tes3Plugin::read_header(reader)?;
+            let save = plugin
+                .get_save_info()
+                .ok_or_else(|| decode_failed("Plugin is not a save game"))?;
+            Ok(SaveMetadata {
+                path: PathBuf::new(),
+                modified: None,
+                player_name: String::from(save.player_name()),
+                // Morrowind doesn't store these in the save header
+                player_level: None,
+                location: String::from(save.current_cell()),
+                play_time: None,
+                screenshot: plugin.screenshot(),
+            })
+        })
+    }
+
+    /// Gets the most recently modified save in a directory, if there are any
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs while reading the directory.
+    pub fn latest_save<P: AsRef<Path>>(dir: P) -> Result<Option<SaveMetadata>, TesError> {
+        Ok(Self::list_saves(dir)?.saves.into_iter().next())
+    }
+
+    /// Gets the virtual file system the world's assets are read from
+    pub fn vfs(&self) -> &Vfs {
+        &self.vfs
+    }
+
+    /// Gets the virtual file system the world's assets are read from mutably
+    ///
+    /// This can be used to register additional archives.
+    pub fn vfs_mut(&mut self) -> &mut Vfs {
+        &mut self.vfs
+    }
+
+    /// Opens an asset, such as a mesh or texture, by its path relative to the data directory
+    ///
+    /// Returns `None` if the asset isn't a loose file and isn't in any registered archive.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs or if the asset can't be opened from its archive.
+    pub fn open_asset(&self, path: &str) -> Result<Option<Asset>, TesError> {
+        self.vfs.open(path)
+    }
+
     /// Gets the currently loaded save, if there is one
     pub fn get_save(&self) -> Option<&Tes3Plugin> {
         if self.has_save {
-            self.plugins.iter().last()
+            self.plugins.iter().last().map(|(_, p)| p)
         } else {
             None
         }
@@ -79,7 +254,9 @@ impl Tes3World {
     /// Gets the currently load save mutably, if there is one
     pub fn get_save_mut(&mut self) -> Option<&mut Tes3Plugin> {
         if self.has_save {
-            self.plugins.iter_mut().last()
+            // we can't tell what the caller will change
+            self.form_cache.clear();
+            self.plugins.iter_mut().last().map(|(_, p)| p)
         } else {
             None
         }
@@ -96,7 +273,7 @@ impl Tes3World {
         &self,
         id: &str,
     ) -> Result<Option<impl Deref<Target = Tes3Record> + '_>, TesError> {
-        for plugin in self.plugins.iter().rev() {
+        for (_, plugin) in self.plugins.iter().rev() {
             if let Some(record) = plugin.get_record(id)? {
                 return Ok(Some(record));
             }
@@ -111,16 +288,210 @@ impl Tes3World {
     ///
     /// # Errors
     ///
-    /// Fails if there is more than one record with the given ID and type.
+    /// Fails if the active version of the record contains invalid data.
     pub fn get_record_with_type(
         &self,
         id: &str,
         name: &[u8; 4],
-    ) -> Option<impl Deref<Target = Tes3Record> + '_> {
-        self.plugins
-            .iter()
-            .rev()
-            .fold(None, |a, p| a.or_else(|| p.get_record_with_type(id, name)))
+    ) -> Result<Option<impl Deref<Target = Tes3Record> + '_>, TesError> {
+        for (_, plugin) in self.plugins.iter().rev() {
+            if let Some(record) = plugin.get_record_with_type(id, name)? {
+                return Ok(Some(record));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Gets the active version of the exterior cell at the given grid coordinates
+    ///
+    /// Returns None if no plugin defines the cell.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a CELL record contains invalid data.
+    pub fn get_exterior_cell(
+        &self,
+        grid: GridCoords,
+    ) -> Result<Option<impl Deref<Target = Tes3Record> + '_>, TesError> {
+        for (_, plugin) in self.plugins.iter().rev() {
+            if let Some(cell) = plugin.get_exterior_cell(grid)? {
+                return Ok(Some(cell));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Gets the active version of the exterior cell containing a world position
+    ///
+    /// # Errors
+    ///
+    /// Fails if a CELL record contains invalid data.
+    pub fn get_cell_at(
+        &self,
+        x: f32,
+        y: f32,
+    ) -> Result<Option<impl Deref<Target = Tes3Record> + '_>, TesError> {
+        self.get_exterior_cell(Tes3World::world_to_grid(x, y))
+    }
+
+    /// Builds the network of doors and NPC travel services connecting the world's cells
+    ///
+    /// Plugins add references to the cells of their masters rather than replacing them, so the
+    /// doors of every version of a cell are included, with later versions of a door taking
+    /// precedence. An NPC's travel services start from every cell the NPC is placed in.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a CELL or NPC_ record contains invalid data.
+    pub fn travel_graph(&self) -> Result<TravelGraph, TesError> {
+        let mut graph = TravelGraph::new();
+        let mut seen = HashSet::new();
+        let mut npcs: HashMap<String, Option<Npc>> = HashMap::new();
+        // the save's cells record what the player changed, not the layout of the world
+        let num_plugins = self.plugins.len() - if self.has_save { 1 } else { 0 };
+        for (_, plugin) in self.plugins[..num_plugins].iter().rev() {
+            let cells = match plugin.get_records_by_type(b"CELL") {
+                Some(cells) => cells,
+                None => continue,
+            };
+
+            for cell in cells {
+                let from = CellId::read(&cell)?;
+                for reference in CellReference::read_all(&cell)? {
+                    // we're going through the load order backwards, so the first version we see
+                    // of a reference is the active one
+                    if !seen.insert((from.clone(), reference.index())) {
+                        continue;
+                    }
+
+                    if let Some(teleport) = reference.teleport() {
+                        graph.add_link(Tes3World::travel_link(
+                            &from,
+                            teleport,
+                            TravelKind::Door(reference.index()),
+                        ));
+                    }
+
+                    let npc_id = reference.base_id().to_lowercase();
+                    if !npcs.contains_key(&npc_id) {
+                        npcs.insert(npc_id.clone(), self.get(reference.base_id())?);
+                    }
+
+                    if let Some(ref npc) = npcs[&npc_id] {
+                        for destination in npc.iter_destinations() {
+                            graph.add_link(Tes3World::travel_link(
+                                &from,
+                                destination,
+                                TravelKind::Service(String::from(reference.base_id())),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(graph)
+    }
+
+    /// Calls a function for the active version of every reference placed in the world
+    ///
+    /// The function is given each reference along with the CELL record its active version was
+    /// found in. As with [`travel_graph`], the references of every version of a cell are included,
+    /// with later versions of a reference taking precedence. If the world was loaded from a save,
+    /// the references the player changed are included as they are in the save.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a CELL record contains invalid data.
+    ///
+    /// [`travel_graph`]: #method.travel_graph
+    pub fn for_each_reference<F>(&self, mut f: F) -> Result<(), TesError>
+    where
+        F: FnMut(&Tes3Record, &CellReference),
+    {
+        let mut seen = HashSet::new();
+        for (_, plugin) in self.plugins.iter().rev() {
+            let cells = match plugin.get_records_by_type(b"CELL") {
+                Some(cells) => cells,
+                None => continue,
+            };
+
+            for cell in cells {
+                let cell_id = CellId::read(&cell)?;
+                for reference in CellReference::read_all(&cell)? {
+                    if seen.insert((cell_id.clone(), reference.index())) {
+                        f(&cell, &reference);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn travel_link(from: &CellId, destination: &Destination, kind: TravelKind) -> TravelLink {
+        let position = destination.position();
+        let to = match destination.cell_name() {
+            Some(name) => CellId::interior(name),
+            None => CellId::Exterior(Tes3World::world_to_grid(position.0, position.1)),
+        };
+
+        TravelLink {
+            from: from.clone(),
+            to,
+            kind,
+            position,
+            rotation: destination.rotation(),
+        }
+    }
+
+    /// Searches the load order for records whose ID or name contains some text, ignoring case
+    ///
+    /// Only the active version of each record is included. Results are sorted by ID.
+    pub fn search(&self, text: &str) -> Vec<SearchMatch<String>> {
+        let text = text.to_lowercase();
+        let mut seen = HashSet::new();
+        let mut matches = vec![];
+        for (plugin_name, plugin) in self.plugins.iter().rev() {
+            for record in plugin.records() {
+                let id = match record.id() {
+                    Some(id) => id,
+                    None => continue,
+                };
+
+                // we're going through the load order backwards, so the first version we see of a
+                // record is the active one
+                if !seen.insert((id.to_lowercase(), *record.name())) {
+                    continue;
+                }
+
+                // globals use FNAM for their type, not a name
+                let name = if record.name() == b"GLOB" {
+                    None
+                } else {
+                    record
+                        .iter()
+                        .find(|f| f.name() == b"FNAM")
+                        .and_then(|f| f.get_zstring().ok())
+                        .map(String::from)
+                };
+
+                if matches_search(&text, iter::once(id).chain(name.as_deref())) {
+                    matches.push(SearchMatch {
+                        id: String::from(id),
+                        record_type: *record.name(),
+                        editor_id: Some(String::from(id)),
+                        name,
+                        plugin: plugin_name.clone(),
+                    });
+                }
+            }
+        }
+
+        matches.sort_by_key(|m| m.id.to_lowercase());
+        matches
     }
 
     /// Loads a form by ID and type
@@ -132,7 +503,7 @@ impl Tes3World {
         &self,
         id: &str,
     ) -> Result<Option<T>, TesError> {
-        self.plugins.iter().rev().fold(Ok(None), |a, p| {
+        self.plugins.iter().rev().fold(Ok(None), |a, (_, p)| {
             if a.is_ok() && a.as_ref().unwrap().is_none() {
                 Ok(p.get(id)?)
             } else {
@@ -141,6 +512,60 @@ impl Tes3World {
         })
     }
 
+    /// Loads a form by ID and type, reusing the previously loaded form if there is one
+    ///
+    /// This is faster than [`get`] for forms that are looked up repeatedly. The cache is cleared
+    /// whenever the save is borrowed mutably.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the matching record contains invalid data.
+    ///
+    /// [`get`]: #method.get
+    pub fn get_cached<T>(&self, id: &str) -> Result<Option<Arc<T>>, TesError>
+    where
+        T: Form<Field = Tes3Field, Record = Tes3Record> + Any + Send + Sync,
+    {
+        // IDs are case-insensitive
+        self.form_cache
+            .get_or_read(id.to_lowercase(), || self.get(id))
+    }
+
+    /// Finds which plugin provides the active version of a record
+    ///
+    /// Returns None if there is no record with the given ID and type.
+    pub fn get_provenance(&self, id: &str, name: &[u8; 4]) -> Option<Provenance<usize>> {
+        self.plugins
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, (plugin_name, plugin))| {
+                plugin.record_index(id, name).map(|position| Provenance {
+                    plugin: plugin_name.clone(),
+                    load_order_index: i,
+                    position,
+                })
+            })
+    }
+
+    /// Loads a form by ID and type along with where it was loaded from
+    ///
+    /// # Errors
+    ///
+    /// Fails if the matching record contains invalid data.
+    pub fn get_with_provenance<T: Form<Field = Tes3Field, Record = Tes3Record>>(
+        &self,
+        id: &str,
+    ) -> Result<Option<(T, Provenance<usize>)>, TesError> {
+        Ok(match self.get_provenance(id, T::RECORD_TYPE) {
+            Some(provenance) => {
+                let plugin = &self.plugins[provenance.load_order_index].1;
+                plugin.get(id)?.map(|form| (form, provenance))
+            }
+            None => None,
+        })
+    }
+
     /// Loads a form by ID and type
     ///
     /// # Errors
@@ -154,6 +579,87 @@ impl Tes3World {
             .ok_or_else(|| TesError::InvalidId(String::from(id)))
     }
 
+    /// Gets the active version of a magic effect definition
+    ///
+    /// # Errors
+    ///
+    /// Fails if the matching record contains invalid data or no matching record is found.
+    pub fn get_magic_effect(&self, effect_type: MagicEffectType) -> Result<MagicEffect, TesError> {
+        for (_, plugin) in self.plugins.iter().rev() {
+            if let Some(effect) = plugin.get_magic_effect(effect_type)? {
+                return Ok(effect);
+            }
+        }
+
+        Err(TesError::RequirementFailed(format!(
+            "No magic effect definition for {:?}",
+            effect_type
+        )))
+    }
+
+    /// Gets the magic effects whose active definitions allow them to be used to enchant items
+    ///
+    /// # Errors
+    ///
+    /// Fails if one of the magic effect definitions is invalid.
+    pub fn enchanting_effects(&self) -> Result<Vec<MagicEffectType>, TesError> {
+        self.effects_where(MagicEffect::allowed_for_enchanting)
+    }
+
+    /// Gets the magic effects whose active definitions allow them to be used to make custom spells
+    ///
+    /// # Errors
+    ///
+    /// Fails if one of the magic effect definitions is invalid.
+    pub fn spellmaking_effects(&self) -> Result<Vec<MagicEffectType>, TesError> {
+        self.effects_where(MagicEffect::allowed_for_spellmaking)
+    }
+
+    fn effects_where<F: Fn(&MagicEffect) -> bool>(
+        &self,
+        predicate: F,
+    ) -> Result<Vec<MagicEffectType>, TesError> {
+        let mut seen = HashSet::new();
+        let mut effects = vec![];
+        for (_, plugin) in self.plugins.iter().rev() {
+            for record in plugin
+                .get_records_by_type(MagicEffect::RECORD_TYPE)
+                .into_iter()
+                .flatten()
+            {
+                let effect = MagicEffect::read(&record)?;
+                if seen.insert(u8::from(effect.effect_type())) && predicate(&effect) {
+                    effects.push(effect.effect_type());
+                }
+            }
+        }
+
+        effects.sort_by_key(|e| u8::from(*e));
+        Ok(effects)
+    }
+
+    /// Calculates the auto-calculated magicka cost of a spell or other magic form
+    ///
+    /// # Errors
+    ///
+    /// Fails if the definition of one of the magic effects can't be found or is invalid.
+    pub fn calculate_magic_cost<M: Magic + ?Sized>(&self, magic: &M) -> Result<u32, TesError> {
+        let effect_cost_mult = match self.get::<GameSetting>("fEffectCostMult")? {
+            Some(setting) => setting
+                .get_float()
+                .ok_or_else(|| decode_failed("Invalid value for game setting fEffectCostMult"))?,
+            None => DEFAULT_EFFECT_COST_MULT,
+        };
+
+        let mut total_cost = 0f32;
+        for effect in magic.iter_effects() {
+            let base_effect = self.get_magic_effect(effect.effect())?;
+            total_cost += effect.cost(base_effect.base_cost(), effect_cost_mult);
+        }
+
+        Ok(total_cost as u32)
+    }
+
     /// Gets an item from the given record
     ///
     /// # Errors
@@ -182,12 +688,24 @@ impl Tes3World {
             None => None,
         })
     }
+
+    /// Gets statistics about the records loaded from all plugins, including the save if there is
+    /// one
+    pub fn load_stats(&self) -> LoadStats {
+        let mut stats = LoadStats::new();
+        for (_, plugin) in &self.plugins {
+            stats.merge(&plugin.load_stats());
+        }
+        stats
+    }
 }
 
 impl World for Tes3World {
     type Plugin = Tes3Plugin;
 
     const PLUGIN_DIR: &'static str = "Data Files";
+
+    const CELL_SIZE: f32 = 8192.;
 }
 
 #[cfg(test)]
@@ -207,12 +725,106 @@ mod tests {
         assert_eq!(world.plugins.len(), 2);
     }
 
+    #[test]
+    fn load_stats() {
+        let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let game_dir = base_dir.join(TEST_GAME_DIR);
+        let world = Tes3World::load_world(&game_dir).unwrap();
+        let stats = world.load_stats();
+        let total = stats.total();
+        assert!(total.count > 0);
+        assert!(total.bytes > 0);
+        assert_eq!(total.finalized + total.lazy + total.failed, total.count);
+        assert_eq!(
+            stats.by_type().iter().map(|(_, s)| s.count).sum::<usize>(),
+            total.count
+        );
+    }
+
     #[test]
     fn test_explicit_plugins() {
         let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
         let game_dir = base_dir.join(TEST_GAME_DIR);
         let plugins = vec!["test1.esp", "test2.esp"];
-        let world = Tes3World::load_from_plugins(&game_dir, plugins.into_iter()).unwrap();
+        let paths = Tes3World::game_paths(&game_dir);
+        let world = Tes3World::load_from_plugins(&paths, plugins.into_iter(), None).unwrap();
         assert_eq!(world.plugins.len(), 2);
     }
+
+    #[test]
+    fn from_plugins() {
+        let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let plugin_dir = base_dir.join(TEST_GAME_DIR).join(Tes3World::PLUGIN_DIR);
+
+        // the given order is kept even though it's not the order the game would use
+        let paths = vec![plugin_dir.join("test2.esp"), plugin_dir.join("test1.esp")];
+        let world = Tes3World::from_plugins(&paths).unwrap();
+        let names: Vec<_> = world.plugins.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, ["test2.esp", "test1.esp"]);
+        assert!(world.get_save().is_none());
+    }
+
+    #[test]
+    fn search() {
+        let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let game_dir = base_dir.join(TEST_GAME_DIR);
+        let world = Tes3World::load_world(&game_dir).unwrap();
+
+        // both plugins define the same creatures, but only the active versions should be returned
+        let matches = world.search("GRIZZLY");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].id, "BM_bear_black_summon");
+        assert_eq!(matches[0].record_type, *b"CREA");
+        assert_eq!(matches[0].name.as_deref(), Some("Grizzly Bear"));
+
+        let ids: Vec<_> = world.search("_summon").into_iter().map(|m| m.id).collect();
+        assert_eq!(
+            ids,
+            [
+                "atronach_frost_summon",
+                "BM_bear_black_summon",
+                "BM_wolf_grey_summon"
+            ]
+        );
+    }
+
+    #[test]
+    fn provenance() {
+        let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let game_dir = base_dir.join(TEST_GAME_DIR);
+        let world = Tes3World::load_world(&game_dir).unwrap();
+
+        let (_, provenance) = world
+            .get_with_provenance::<Creature>("BM_bear_black_summon")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            provenance,
+            Provenance {
+                plugin: String::from("test2.esp"),
+                load_order_index: 1,
+                position: 6,
+            }
+        );
+
+        assert!(world
+            .get_provenance("BM_bear_black_summon", b"NPC_")
+            .is_none());
+    }
+
+    #[test]
+    fn grid_coords() {
+        assert_eq!(
+            Tes3World::world_to_grid(-8192., 8191.5),
+            GridCoords::new(-1, 0)
+        );
+        assert_eq!(
+            Tes3World::world_to_grid(-1., 16384.),
+            GridCoords::new(-1, 2)
+        );
+        assert_eq!(
+            Tes3World::grid_to_world(GridCoords::new(-2, 3)),
+            (-16384., 24576.)
+        );
+    }
 }
@@ -1,4 +1,5 @@
 use std::convert::TryFrom;
+use std::fmt;
 
 use crate::{Attribute, Specialization, TesError};
 use binrw::binrw;
@@ -9,12 +10,31 @@ use num_enum::{IntoPrimitive, TryFromPrimitive};
 mod plugin;
 pub use plugin::*;
 
+mod bsa;
+pub use bsa::*;
+
+mod forms;
+pub use forms::*;
+
 pub mod save;
+// the save types are used often enough to keep them as close at hand as Morrowind's
+pub use save::{Save, SaveEditor};
+
+#[cfg(feature = "world")]
+mod ini_file;
+#[cfg(feature = "world")]
+pub use ini_file::*;
 
+// the world keeps the co-save alongside the save
+#[cfg(all(feature = "world", feature = "cosave"))]
 mod world;
+#[cfg(all(feature = "world", feature = "cosave"))]
 pub use world::*;
 
+#[cfg(feature = "cosave")]
 pub mod cosave;
+#[cfg(feature = "cosave")]
+pub use cosave::{CoSave, ObConvert};
 
 bitflags! {
     #[derive(Default)]
@@ -33,25 +53,81 @@ bitflags! {
     }
 }
 
+/// A plugin's position in the load order
+///
+/// The plugin index makes up the top byte of a form ID and identifies which master the form
+/// originates from. Index FF is reserved for forms created in a save game.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct PluginIndex(pub u8);
+
+impl PluginIndex {
+    /// The index used for forms created in a save game
+    pub const SAVE: PluginIndex = PluginIndex(0xff);
+
+    /// Is this the index reserved for forms created in a save game?
+    pub fn is_save(&self) -> bool {
+        *self == PluginIndex::SAVE
+    }
+}
+
+impl From<u8> for PluginIndex {
+    fn from(index: u8) -> Self {
+        PluginIndex(index)
+    }
+}
+
+impl From<PluginIndex> for usize {
+    fn from(index: PluginIndex) -> Self {
+        index.0 as usize
+    }
+}
+
+impl fmt::Display for PluginIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02X}", self.0)
+    }
+}
+
 /// A unique identifier for a record
 #[binrw]
-#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct FormId(pub u32);
 
 impl FormId {
-    /// Gets a form ID's index (i.e., which plugin in the load order it belongs to)
-    pub fn index(&self) -> u8 {
-        (self.0 >> 24) as u8
+    const LOCAL_ID_MASK: u32 = 0x00ffffff;
+
+    /// Creates a form ID from a plugin index and an ID local to that plugin
+    ///
+    /// Any bits of `local_id` above the low 24 are ignored.
+    pub fn new(index: PluginIndex, local_id: u32) -> FormId {
+        FormId(((index.0 as u32) << 24) | (local_id & FormId::LOCAL_ID_MASK))
+    }
+
+    /// Gets the index of the master this form ID belongs to (i.e., which plugin in the load order)
+    pub fn master_index(&self) -> PluginIndex {
+        PluginIndex((self.0 >> 24) as u8)
+    }
+
+    /// Gets a form ID's ID portion (i.e. excluding the plugin index)
+    pub fn local_id(&self) -> u32 {
+        self.0 & FormId::LOCAL_ID_MASK
+    }
+
+    /// Gets a copy of this form ID with a different master index
+    pub fn with_master_index(self, index: PluginIndex) -> FormId {
+        FormId::new(index, self.local_id())
     }
 
-    /// Sets a form ID's index
-    pub fn set_index(&mut self, index: u8) {
-        self.0 = ((index as u32) << 24) | (self.0 & 0xffffff);
+    /// Gets a copy of this form ID with a different ID portion
+    pub fn with_local_id(self, local_id: u32) -> FormId {
+        FormId::new(self.master_index(), local_id)
     }
+}
 
-    /// Sets a form ID's ID portion (i.e. excluding the plugin index)
-    pub fn set_id(&mut self, id: u32) {
-        self.0 = (self.0 & 0xff000000) | (id & 0x00ffffff);
+impl fmt::Display for FormId {
+    /// Formats the form ID as eight hex digits, the way xEdit and the construction set show it
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08X}", self.0)
     }
 }
 
@@ -67,19 +143,17 @@ impl<'a> FindForm<'a> {
     pub fn form_id<'b, T: Iterator<Item = &'b str>>(&self, mut plugins: T) -> Option<FormId> {
         Some(match self {
             FindForm::ByMaster(plugin, id) => {
-                let mut id = FormId(*id);
-                let index = match *plugin {
+                let index = PluginIndex(match *plugin {
                     Some(name) => plugins.position(|p| p == name)?,
                     None => plugins.count(),
-                } as u8;
+                } as u8);
 
-                if index == 0xff {
+                if index.is_save() {
                     // index FF is reserved for saves and will never be a plugin index
                     return None;
                 }
 
-                id.set_index(index);
-                id
+                FormId(*id).with_master_index(index)
             }
             FindForm::ByIndex(id) => *id,
         })
@@ -269,6 +343,19 @@ impl Skill {
             _ => Specialization::Magic,
         }
     }
+
+    /// Gets the attribute that governs the skill
+    pub fn attribute(&self) -> Attribute {
+        match *self {
+            Skill::Blade | Skill::Blunt | Skill::HandToHand => Attribute::Strength,
+            Skill::Armorer | Skill::Block | Skill::HeavyArmor => Attribute::Endurance,
+            Skill::Athletics | Skill::Acrobatics | Skill::LightArmor => Attribute::Speed,
+            Skill::Alchemy | Skill::Conjuration | Skill::Mysticism => Attribute::Intelligence,
+            Skill::Alteration | Skill::Destruction | Skill::Restoration => Attribute::Willpower,
+            Skill::Marksman | Skill::Security | Skill::Sneak => Attribute::Agility,
+            Skill::Illusion | Skill::Mercantile | Skill::Speechcraft => Attribute::Personality,
+        }
+    }
 }
 
 impl TryFrom<ActorValue> for Skill {
@@ -307,3 +394,22 @@ impl TryFrom<ActorValue> for Skill {
 
 /// Character skills
 pub type Skills<T> = EnumMap<Skill, T>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn form_id_parts() {
+        let form_id = FormId(0x01012eb7);
+        assert_eq!(form_id.master_index(), PluginIndex(1));
+        assert_eq!(form_id.local_id(), 0x012eb7);
+        assert_eq!(
+            form_id.with_master_index(PluginIndex::SAVE),
+            FormId(0xff012eb7)
+        );
+        assert_eq!(FormId::new(PluginIndex(2), 0xff000014), FormId(0x02000014));
+        assert_eq!(form_id.to_string(), "01012EB7");
+        assert_eq!(PluginIndex(10).to_string(), "0A");
+    }
+}
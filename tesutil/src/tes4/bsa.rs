@@ -0,0 +1,1042 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::{
+    checked_len, decode_failed, decode_legacy, normalize_archive_path, process_entries_in_parallel,
+    read_bstring_raw, write_bzstring, ArchiveEntry, SharedReader, TesError,
+};
+
+use binrw::BinReaderExt;
+use bitflags::bitflags;
+use flate2::bufread::{ZlibDecoder, ZlibEncoder};
+use flate2::read::ZlibDecoder as StreamingZlibDecoder;
+use flate2::Compression;
+
+const BSA_MAGIC: &[u8; 4] = b"BSA\0";
+const BSA_VERSION: u32 = 103;
+/// Bit in a file's size that inverts the archive's default compression for that file
+const COMPRESSION_TOGGLE: u32 = 0x40000000;
+const SIZE_MASK: u32 = 0x3fffffff;
+const HEADER_SIZE: usize = 36;
+const FOLDER_RECORD_SIZE: usize = 16;
+const FILE_RECORD_SIZE: usize = 16;
+/// The most that zlib can compress data by, which bounds the decompressed size of a file
+const MAX_COMPRESSION_RATIO: usize = 1032;
+/// Flags the header sets for each kind of file the archive contains, by extension
+const FILE_TYPE_FLAGS: &[(&str, u32)] = &[
+    ("nif", 0x001),
+    ("dds", 0x002),
+    ("xml", 0x004),
+    ("wav", 0x008),
+    ("mp3", 0x010),
+    ("txt", 0x020),
+    ("html", 0x020),
+    ("bat", 0x020),
+    ("scc", 0x020),
+    ("spt", 0x040),
+    ("tex", 0x080),
+    ("fnt", 0x080),
+    ("ctl", 0x100),
+];
+
+bitflags! {
+    struct ArchiveFlags: u32 {
+        const DIRECTORY_NAMES = 0x001;
+        const FILE_NAMES = 0x002;
+        const COMPRESSED = 0x004;
+    }
+}
+
+/// Hashes a string the way Oblivion hashes the middle of a name and its extension
+fn hash_string(s: &[u8]) -> u32 {
+    s.iter().fold(0u32, |hash, c| {
+        hash.wrapping_mul(0x1003f).wrapping_add(*c as u32)
+    })
+}
+
+/// Hashes a folder name, or a file name split into its stem and extension
+fn hash_name(stem: &[u8], extension: &[u8]) -> u64 {
+    let mut hash = 0u64;
+    if let (Some(first), Some(last)) = (stem.first(), stem.last()) {
+        let second_last = if stem.len() > 2 {
+            stem[stem.len() - 2]
+        } else {
+            0
+        };
+        hash = *last as u64
+            + ((second_last as u64) << 8)
+            + ((stem.len() as u64) << 16)
+            + ((*first as u64) << 24);
+        if stem.len() > 3 {
+            hash = hash.wrapping_add((hash_string(&stem[1..stem.len() - 2]) as u64) << 32);
+        }
+    }
+
+    if !extension.is_empty() {
+        hash = hash.wrapping_add((hash_string(extension) as u64) << 32);
+
+        // some common extensions also set bits in the low bytes
+        let kind: u8 = match extension {
+            b".nif" => 1,
+            b".kf" => 2,
+            b".dds" => 3,
+            b".wav" => 4,
+            _ => 0,
+        };
+        if kind != 0 {
+            let a = ((kind & 0xfc) << 5).wrapping_add((hash >> 24) as u8);
+            let b = ((kind & 0xfe) << 6).wrapping_add(hash as u8);
+            let c = (kind << 7).wrapping_add((hash >> 8) as u8);
+            hash -= hash & 0xff00ffff;
+            hash += ((a as u64) << 24) + b as u64 + ((c as u64) << 8);
+        }
+    }
+
+    hash
+}
+
+/// Computes the hashes Oblivion uses to look up a file in a BSA archive
+///
+/// Returns the hash of the folder the file is in followed by the hash of the file's name. The path
+/// is matched case-insensitively, and either `/` or `\` may be used as the separator. A file with
+/// no folder has a folder hash of 0.
+pub fn bsa_hash_pair(path: &str) -> (u64, u64) {
+    let path = normalize_archive_path(path);
+    let (folder, name) = match path.rfind('\\') {
+        Some(i) => (&path[..i], &path[i + 1..]),
+        None => ("", path.as_str()),
+    };
+    let (stem, extension) = match name.rfind('.') {
+        Some(i) => name.split_at(i),
+        None => (name, ""),
+    };
+
+    (
+        hash_name(folder.as_bytes(), b""),
+        hash_name(stem.as_bytes(), extension.as_bytes()),
+    )
+}
+
+/// A file stored in a BSA archive
+#[derive(Debug, Clone)]
+pub struct BsaFile {
+    path: String,
+    size: u32,
+    offset: u32,
+    is_compressed: bool,
+}
+
+impl BsaFile {
+    /// Gets the file's path within the archive, such as `meshes\clutter\wine\wine01.nif`
+    pub fn path(&self) -> &str {
+        self.path.as_str()
+    }
+
+    /// Gets the size in bytes of the file's data as stored in the archive
+    ///
+    /// For compressed files, this is the compressed size.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Is the file's data compressed?
+    pub fn is_compressed(&self) -> bool {
+        self.is_compressed
+    }
+
+    /// Decompresses the file's data as read from the archive, if it's compressed
+    fn decompress(&self, data: Vec<u8>) -> Result<Vec<u8>, TesError> {
+        if !self.is_compressed {
+            return Ok(data);
+        }
+
+        if data.len() < 4 {
+            return Err(decode_failed("Compressed BSA file is missing its size"));
+        }
+        let original_size = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        if original_size > (data.len() - 4).saturating_mul(MAX_COMPRESSION_RATIO) {
+            return Err(decode_failed(format!(
+                "Compressed BSA file {} is too small to be {} bytes",
+                self.path, original_size
+            )));
+        }
+
+        let mut decompressed = Vec::with_capacity(original_size);
+        // read one byte past the expected size so a file that's too big is caught below
+        ZlibDecoder::new(&data[4..])
+            .take(original_size as u64 + 1)
+            .read_to_end(&mut decompressed)?;
+        if decompressed.len() != original_size {
+            return Err(decode_failed(format!(
+                "Compressed BSA file {} should be {} bytes but was {}",
+                self.path,
+                original_size,
+                decompressed.len()
+            )));
+        }
+
+        Ok(decompressed)
+    }
+}
+
+/// The decompressed data of a compressed file in a BSA archive
+#[derive(Debug)]
+struct CompressedEntry<T: Read + Seek> {
+    // only None while the decoder is being restarted
+    decoder: Option<StreamingZlibDecoder<ArchiveEntry<T>>>,
+    size: u64,
+    pos: u64,
+}
+
+impl<T: Read + Seek> CompressedEntry<T> {
+    fn decoder(&mut self) -> &mut StreamingZlibDecoder<ArchiveEntry<T>> {
+        self.decoder
+            .as_mut()
+            .expect("Compressed BSA entry has no decoder")
+    }
+
+    /// Starts decompressing again from the beginning of the file
+    fn restart(&mut self) -> io::Result<()> {
+        let mut entry = self
+            .decoder
+            .take()
+            .expect("Compressed BSA entry has no decoder")
+            .into_inner();
+        // skip the decompressed size
+        entry.seek(SeekFrom::Start(4))?;
+        self.decoder = Some(StreamingZlibDecoder::new(entry));
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+impl<T: Read + Seek> Read for CompressedEntry<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.size.saturating_sub(self.pos);
+        if remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let size = buf.len().min(remaining.try_into().unwrap_or(usize::MAX));
+        let bytes_read = self.decoder().read(&mut buf[..size])?;
+        if bytes_read == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Compressed BSA file is smaller than its decompressed size",
+            ));
+        }
+
+        self.pos += bytes_read as u64;
+        Ok(bytes_read)
+    }
+}
+
+impl<T: Read + Seek> Seek for CompressedEntry<T> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Seek to a position before the start of a BSA file",
+            )
+        })?;
+
+        // zlib streams can only be read forward
+        if new_pos < self.pos {
+            self.restart()?;
+        }
+        let skip = new_pos.min(self.size).saturating_sub(self.pos);
+        io::copy(&mut self.by_ref().take(skip), &mut io::sink())?;
+        self.pos = new_pos;
+        Ok(self.pos)
+    }
+}
+
+#[derive(Debug)]
+enum EntryData<T: Read + Seek> {
+    Stored(ArchiveEntry<T>),
+    Compressed(CompressedEntry<T>),
+}
+
+/// A reader over the data of a single file in a BSA archive
+///
+/// Compressed files are decompressed as they're read, so a large file can be streamed without
+/// buffering all of it. Seeking forward in a compressed file decompresses and discards the data in
+/// between, and seeking backward decompresses it again from the start, so random access into a
+/// compressed file is slow.
+#[derive(Debug)]
+pub struct BsaEntry<T: Read + Seek> {
+    data: EntryData<T>,
+}
+
+impl<T: Read + Seek> BsaEntry<T> {
+    /// Gets the size of the file's data in bytes
+    ///
+    /// For compressed files, this is the decompressed size.
+    pub fn len(&self) -> u64 {
+        match &self.data {
+            EntryData::Stored(entry) => entry.len(),
+            EntryData::Compressed(entry) => entry.size,
+        }
+    }
+
+    /// Returns whether the file has no data
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T: Read + Seek> Read for BsaEntry<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.data {
+            EntryData::Stored(entry) => entry.read(buf),
+            EntryData::Compressed(entry) => entry.read(buf),
+        }
+    }
+}
+
+impl<T: Read + Seek> Seek for BsaEntry<T> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match &mut self.data {
+            EntryData::Stored(entry) => entry.seek(pos),
+            EntryData::Compressed(entry) => entry.seek(pos),
+        }
+    }
+}
+
+/// An Oblivion BSA archive
+///
+/// Only the archive's folder and file records are read when it's opened; file data is read from
+/// the underlying reader as it's extracted, and compressed files are decompressed transparently.
+/// Paths are matched case-insensitively, and either `/` or `\` may be used as the separator.
+///
+/// Archives must include folder and file names, as all of the archives that come with the game do;
+/// files can't be looked up by hash alone.
+#[derive(Debug)]
+pub struct Bsa<T: Read + Seek> {
+    reader: SharedReader<T>,
+    start: u64,
+    files: Vec<BsaFile>,
+    paths: HashMap<String, usize>,
+}
+
+impl Bsa<BufReader<File>> {
+    /// Opens a BSA archive file
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs or if the archive is invalid.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Bsa<BufReader<File>>, TesError> {
+        let f = File::open(path)?;
+        Bsa::read(BufReader::new(f))
+    }
+}
+
+impl<T: Read + Seek> Bsa<T> {
+    /// Reads an archive's folder and file records from a reader positioned at the start of the
+    /// archive
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs, if the archive is invalid, or if it doesn't include folder
+    /// and file names.
+    ///
+    /// Folder and file names that aren't valid UTF-8 are decoded as Windows-1252, like the game's
+    /// own text.
+    pub fn read(mut reader: T) -> Result<Bsa<T>, TesError> {
+        let start = reader.stream_position()?;
+        let end = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(start))?;
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        let version: u32 = reader.read_le()?;
+        if magic != *BSA_MAGIC || version != BSA_VERSION {
+            return Err(decode_failed("Not a supported Oblivion BSA archive"));
+        }
+
+        let folder_offset: u32 = reader.read_le()?;
+        let flags = ArchiveFlags::from_bits_truncate(reader.read_le()?);
+        let num_folders: u32 = reader.read_le()?;
+        let num_files: u32 = reader.read_le()?;
+        let _total_folder_name_length: u32 = reader.read_le()?;
+        let total_file_name_length: u32 = reader.read_le()?;
+        let _file_flags: u32 = reader.read_le()?;
+
+        if !flags.contains(ArchiveFlags::DIRECTORY_NAMES | ArchiveFlags::FILE_NAMES) {
+            return Err(decode_failed(
+                "BSA archives without folder and file names are not supported",
+            ));
+        }
+
+        reader.seek(SeekFrom::Start(start + folder_offset as u64))?;
+        let mut folder_sizes = vec![];
+        for _ in 0..num_folders {
+            let _hash: u64 = reader.read_le()?;
+            let count: u32 = reader.read_le()?;
+            let _offset: u32 = reader.read_le()?;
+            folder_sizes.push(count);
+        }
+
+        // each folder's name is followed by the records of the files in it
+        let mut records = vec![];
+        for count in folder_sizes {
+            let mut folder_name = read_bstring_raw(&mut reader)?;
+            if folder_name.pop() != Some(0) {
+                return Err(decode_failed("BSA folder name is not null-terminated"));
+            }
+            let folder_name = decode_legacy(&folder_name).into_owned();
+            for _ in 0..count {
+                let _hash: u64 = reader.read_le()?;
+                let size: u32 = reader.read_le()?;
+                let offset: u32 = reader.read_le()?;
+                records.push((folder_name.clone(), size, offset));
+            }
+        }
+
+        if records.len() != num_files as usize {
+            return Err(decode_failed(
+                "BSA file count doesn't match folder contents",
+            ));
+        }
+
+        // the sizes come from the header, so check them against the archive before allocating
+        if total_file_name_length as u64 > end - reader.stream_position()? {
+            return Err(decode_failed(
+                "BSA file name table extends past the end of the archive",
+            ));
+        }
+        let mut names = vec![0u8; total_file_name_length as usize];
+        reader.read_exact(&mut names)?;
+        let mut names = names.split(|b| *b == 0);
+
+        let mut files = vec![];
+        let mut paths = HashMap::new();
+        for (folder_name, size, offset) in records {
+            let name = names
+                .next()
+                .ok_or_else(|| decode_failed("Missing file name in BSA"))?;
+            let name = decode_legacy(name);
+            let path = if folder_name.is_empty() || folder_name == "." {
+                name.into_owned()
+            } else {
+                format!("{}\\{}", folder_name, name)
+            };
+            let is_compressed =
+                flags.contains(ArchiveFlags::COMPRESSED) != (size & COMPRESSION_TOGGLE != 0);
+            let size = size & SIZE_MASK;
+            if start + offset as u64 + size as u64 > end {
+                return Err(decode_failed(format!(
+                    "BSA file {} extends past the end of the archive",
+                    path
+                )));
+            }
+
+            paths.insert(normalize_archive_path(&path), files.len());
+            files.push(BsaFile {
+                path,
+                size,
+                offset,
+                is_compressed,
+            });
+        }
+
+        Ok(Bsa {
+            reader: SharedReader::new(reader),
+            start,
+            files,
+            paths,
+        })
+    }
+
+    /// Iterates through the files in the archive
+    pub fn files(&self) -> impl Iterator<Item = &BsaFile> + '_ {
+        self.files.iter()
+    }
+
+    /// Gets the number of files in the archive
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns whether the archive contains no files
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Gets the file at a path in the archive, if it exists
+    pub fn get(&self, path: &str) -> Option<&BsaFile> {
+        self.paths
+            .get(&normalize_archive_path(path))
+            .map(|index| &self.files[*index])
+    }
+
+    /// Returns whether the archive contains a file at a path
+    pub fn contains(&self, path: &str) -> bool {
+        self.paths.contains_key(&normalize_archive_path(path))
+    }
+
+    /// Reads the data of the file at a path in the archive, decompressing it if necessary
+    ///
+    /// Returns `None` if the archive has no file at that path.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs or if the file's compressed data is invalid.
+    pub fn extract(&mut self, path: &str) -> Result<Option<Vec<u8>>, TesError> {
+        let file = match self.paths.get(&normalize_archive_path(path)) {
+            Some(index) => &self.files[*index],
+            None => return Ok(None),
+        };
+
+        self.reader
+            .seek(SeekFrom::Start(self.start + file.offset as u64))?;
+        let mut data = vec![0u8; file.size as usize];
+        self.reader.read_exact(&mut data)?;
+        Ok(Some(file.decompress(data)?))
+    }
+
+    /// Extracts every file in the archive, decompressing files on multiple threads
+    ///
+    /// The files' data is read in order on the calling thread and decompressed on `num_threads`
+    /// worker threads, which then call `f` with each file and its data. Since `f` is called from
+    /// several threads at once, it can write the files out concurrently. Extraction stops at the
+    /// first error.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs, if a file's compressed data is invalid, or if `f` fails.
+    pub fn extract_all<F, E>(&mut self, num_threads: usize, f: F) -> Result<(), E>
+    where
+        F: Fn(&BsaFile, Vec<u8>) -> Result<(), E> + Sync,
+        E: From<TesError> + Send,
+    {
+        let start = self.start;
+        let files = &self.files;
+        process_entries_in_parallel(
+            &mut self.reader,
+            files
+                .iter()
+                .enumerate()
+                .map(|(i, file)| (i, start + file.offset as u64, file.size as u64)),
+            num_threads,
+            |index, data| {
+                let file = &files[index];
+                f(file, file.decompress(data)?)
+            },
+        )
+    }
+
+    /// Opens the file at a path in the archive for streaming, decompressing it as it's read
+    ///
+    /// Unlike [`extract`], this doesn't read the file's data up front, so it's better suited to
+    /// large files. The entry doesn't borrow the archive, and several entries can be read at once.
+    ///
+    /// Returns `None` if the archive has no file at that path.
+    ///
+    /// [`extract`]: #method.extract
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs while reading a compressed file's decompressed size or if the
+    /// file is too small to have one.
+    pub fn open_entry(&self, path: &str) -> Result<Option<BsaEntry<SharedReader<T>>>, TesError> {
+        let file = match self.paths.get(&normalize_archive_path(path)) {
+            Some(index) => &self.files[*index],
+            None => return Ok(None),
+        };
+
+        let mut entry = ArchiveEntry::new(
+            self.reader.clone(),
+            self.start + file.offset as u64,
+            file.size as u64,
+        );
+        let data = if file.is_compressed {
+            if file.size < 4 {
+                return Err(decode_failed("Compressed BSA file is missing its size"));
+            }
+            let size: u32 = entry.read_le()?;
+            EntryData::Compressed(CompressedEntry {
+                decoder: Some(StreamingZlibDecoder::new(entry)),
+                size: size as u64,
+                pos: 0,
+            })
+        } else {
+            EntryData::Stored(entry)
+        };
+
+        Ok(Some(BsaEntry { data }))
+    }
+}
+
+/// Builds a new Oblivion BSA archive
+///
+/// Paths are stored in lowercase with backslashes, like in the archives that come with the game,
+/// and folders and files are written in hash order so that the game can look them up. Every file
+/// must be in a folder.
+#[derive(Debug, Default)]
+pub struct BsaBuilder {
+    files: HashMap<String, Vec<u8>>,
+    compressed: bool,
+}
+
+impl BsaBuilder {
+    /// Starts building an empty archive whose files aren't compressed
+    pub fn new() -> BsaBuilder {
+        BsaBuilder::default()
+    }
+
+    /// Sets whether the archive's files are compressed
+    pub fn set_compressed(&mut self, compressed: bool) {
+        self.compressed = compressed;
+    }
+
+    /// Adds a file to the archive, replacing any file already added at the same path
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file isn't in a folder, if its folder's name is too long, or if the file is
+    /// too large to be stored in a BSA.
+    pub fn add_file(&mut self, path: &str, data: Vec<u8>) -> Result<(), TesError> {
+        let path = normalize_archive_path(path);
+        match path.rfind('\\') {
+            Some(i) if i > 0 && i < path.len() - 1 => {
+                // folder names are stored as bzstrings, so the null terminator counts too
+                checked_len::<u8>(i + 1, "BSA folder name too long")?;
+            }
+            _ => {
+                return Err(TesError::RequirementFailed(format!(
+                    "BSA file {} must be in a folder",
+                    path
+                )))
+            }
+        }
+        checked_len::<u32>(data.len(), "File too large for BSA")?;
+        self.files.insert(path, data);
+        Ok(())
+    }
+
+    /// Gets the number of files that have been added
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns whether no files have been added
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Writes the archive, compressing its files if it's compressed
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs or if the archive would be too large.
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<(), TesError> {
+        let mut folders: HashMap<&str, Vec<(&str, u64, &[u8])>> = HashMap::new();
+        let mut file_flags = 0;
+        for (path, data) in &self.files {
+            // add_file made sure every path has a folder
+            let (folder, name) = path.split_at(path.rfind('\\').unwrap());
+            let (_, file_hash) = bsa_hash_pair(path);
+            folders
+                .entry(folder)
+                .or_default()
+                .push((&name[1..], file_hash, data.as_slice()));
+
+            if let Some((_, extension)) = name.rsplit_once('.') {
+                if let Some((_, flag)) = FILE_TYPE_FLAGS.iter().find(|(e, _)| *e == extension) {
+                    file_flags |= flag;
+                }
+            }
+        }
+
+        let mut folders: Vec<_> = folders
+            .into_iter()
+            .map(|(folder, mut files)| {
+                files.sort_by_key(|(_, hash, _)| *hash);
+                (hash_name(folder.as_bytes(), b""), folder, files)
+            })
+            .collect();
+        folders.sort_by_key(|(hash, _, _)| *hash);
+
+        let folder_names_size: usize = folders.iter().map(|(_, f, _)| f.len() + 1).sum();
+        let file_names_size: usize = self
+            .files
+            .keys()
+            .map(|p| p.len() - p.rfind('\\').unwrap())
+            .sum();
+        let folder_blocks_size = folders.len() * (FOLDER_RECORD_SIZE + 1)
+            + folder_names_size
+            + self.files.len() * FILE_RECORD_SIZE;
+        let data_start = HEADER_SIZE + folder_blocks_size + file_names_size;
+
+        let mut flags = ArchiveFlags::DIRECTORY_NAMES | ArchiveFlags::FILE_NAMES;
+        if self.compressed {
+            flags |= ArchiveFlags::COMPRESSED;
+        }
+
+        writer.write_all(BSA_MAGIC)?;
+        for value in [
+            BSA_VERSION,
+            HEADER_SIZE as u32,
+            flags.bits,
+            checked_len(folders.len(), "Too many folders for BSA")?,
+            checked_len(self.files.len(), "Too many files for BSA")?,
+            checked_len(folder_names_size, "BSA folder names too long")?,
+            checked_len(file_names_size, "BSA file names too long")?,
+            file_flags,
+        ] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+
+        // each folder's offset points at its name, plus the size of the file names for some reason
+        let mut folder_offset = HEADER_SIZE + folders.len() * FOLDER_RECORD_SIZE + file_names_size;
+        for (folder_hash, folder, files) in &folders {
+            writer.write_all(&folder_hash.to_le_bytes())?;
+            writer.write_all(
+                &checked_len::<u32>(files.len(), "Too many files in BSA folder")?.to_le_bytes(),
+            )?;
+            writer.write_all(&checked_len::<u32>(folder_offset, "BSA too large")?.to_le_bytes())?;
+            folder_offset += folder.len() + 2 + files.len() * FILE_RECORD_SIZE;
+        }
+
+        let mut stored_data = Vec::with_capacity(self.files.len());
+        let mut data_offset = data_start;
+        for (_, folder, files) in &folders {
+            write_bzstring(&mut writer, folder)?;
+            for (_, file_hash, data) in files {
+                let stored = if self.compressed {
+                    let mut stored = (data.len() as u32).to_le_bytes().to_vec();
+                    ZlibEncoder::new(*data, Compression::default()).read_to_end(&mut stored)?;
+                    stored
+                } else {
+                    data.to_vec()
+                };
+
+                // the top bits of the size are flags
+                if stored.len() > SIZE_MASK as usize {
+                    return Err(TesError::LimitExceeded {
+                        description: String::from("File too large for BSA"),
+                        max_size: SIZE_MASK as usize,
+                        actual_size: stored.len(),
+                    });
+                }
+                writer.write_all(&file_hash.to_le_bytes())?;
+                writer.write_all(&(stored.len() as u32).to_le_bytes())?;
+                writer
+                    .write_all(&checked_len::<u32>(data_offset, "BSA too large")?.to_le_bytes())?;
+                data_offset += stored.len();
+                stored_data.push(stored);
+            }
+        }
+
+        for (_, _, files) in &folders {
+            for (name, _, _) in files {
+                writer.write_all(name.as_bytes())?;
+                writer.write_all(b"\0")?;
+            }
+        }
+
+        for data in stored_data {
+            writer.write_all(&data)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "world")]
+impl<T: Read + Seek + Send + std::fmt::Debug + 'static> crate::Archive for Bsa<T> {
+    fn contains(&self, path: &str) -> bool {
+        Bsa::contains(self, path)
+    }
+
+    fn extract(&mut self, path: &str) -> Result<Option<Vec<u8>>, TesError> {
+        Bsa::extract(self, path)
+    }
+
+    fn open_entry(&mut self, path: &str) -> Result<Option<Box<dyn crate::AssetStream>>, TesError> {
+        Ok(
+            Bsa::open_entry(self, path)?
+                .map(|entry| Box::new(entry) as Box<dyn crate::AssetStream>),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::bufread::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Cursor;
+
+    /// Builds an archive with one folder, compressing the files marked as compressed
+    fn make_archive(folder: &str, files: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        let mut folder_name = vec![folder.len() as u8 + 1];
+        folder_name.extend_from_slice(folder.as_bytes());
+        folder_name.push(0);
+        let file_names_size: usize = files.iter().map(|(name, _, _)| name.len() + 1).sum();
+        let data_start = HEADER_SIZE + 16 + folder_name.len() + files.len() * 16 + file_names_size;
+
+        let mut records = vec![];
+        let mut names = vec![];
+        let mut data = vec![];
+        for (name, contents, compress) in files {
+            let stored = if *compress {
+                let mut stored = (contents.len() as u32).to_le_bytes().to_vec();
+                ZlibEncoder::new(*contents, Compression::default())
+                    .read_to_end(&mut stored)
+                    .unwrap();
+                stored
+            } else {
+                contents.to_vec()
+            };
+
+            let mut size = stored.len() as u32;
+            if *compress {
+                size |= COMPRESSION_TOGGLE;
+            }
+            records.extend_from_slice(&0u64.to_le_bytes());
+            records.extend_from_slice(&size.to_le_bytes());
+            records.extend_from_slice(&((data_start + data.len()) as u32).to_le_bytes());
+            names.extend_from_slice(name.as_bytes());
+            names.push(0);
+            data.extend(stored);
+        }
+
+        let mut archive = BSA_MAGIC.to_vec();
+        for value in [
+            BSA_VERSION,
+            HEADER_SIZE as u32,
+            (ArchiveFlags::DIRECTORY_NAMES | ArchiveFlags::FILE_NAMES).bits,
+            1,
+            files.len() as u32,
+            folder_name.len() as u32,
+            file_names_size as u32,
+            0,
+        ] {
+            archive.extend_from_slice(&value.to_le_bytes());
+        }
+        archive.extend_from_slice(&0u64.to_le_bytes());
+        archive.extend_from_slice(&(files.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&0u32.to_le_bytes());
+        archive.extend(folder_name);
+        archive.extend(records);
+        archive.extend(names);
+        archive.extend(data);
+        archive
+    }
+
+    #[test]
+    fn read_archive() {
+        let data = make_archive(
+            "textures\\menus\\icons\\clutter",
+            &[
+                ("wine01.dds", &b"uncompressed icon"[..], false),
+                ("wine02.dds", &b"compressed icon compressed icon"[..], true),
+            ],
+        );
+        let mut bsa = Bsa::read(Cursor::new(data)).unwrap();
+
+        assert_eq!(bsa.len(), 2);
+        let file = bsa.get("Textures/Menus/Icons/Clutter/Wine02.dds").unwrap();
+        assert_eq!(file.path(), "textures\\menus\\icons\\clutter\\wine02.dds");
+        assert!(file.is_compressed());
+        assert_eq!(
+            bsa.extract("textures\\menus\\icons\\clutter\\wine02.dds")
+                .unwrap(),
+            Some(b"compressed icon compressed icon".to_vec())
+        );
+        assert_eq!(
+            bsa.extract("textures/menus/icons/clutter/wine01.dds")
+                .unwrap(),
+            Some(b"uncompressed icon".to_vec())
+        );
+        assert!(!bsa.contains("textures\\menus\\icons\\clutter\\wine03.dds"));
+
+        let mut entry = bsa
+            .open_entry("textures\\menus\\icons\\clutter\\wine02.dds")
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.len(), 31);
+        let mut buf = [0u8; 4];
+        entry.seek(SeekFrom::Start(6)).unwrap();
+        entry.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ssed");
+        // seeking backward starts decompressing over
+        assert_eq!(entry.seek(SeekFrom::End(-27)).unwrap(), 4);
+        let mut data = vec![];
+        entry.read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"ressed icon compressed icon");
+
+        let mut entry = bsa
+            .open_entry("textures/menus/icons/clutter/wine01.dds")
+            .unwrap()
+            .unwrap();
+        entry.seek(SeekFrom::Current(13)).unwrap();
+        let mut data = String::new();
+        entry.read_to_string(&mut data).unwrap();
+        assert_eq!(data, "icon");
+        assert!(bsa
+            .open_entry("textures/menus/icons/clutter/wine03.dds")
+            .unwrap()
+            .is_none());
+
+        assert!(Bsa::read(Cursor::new(b"BSA\0\x67\0\0\0".to_vec())).is_err());
+    }
+
+    #[test]
+    fn read_bad_archives() {
+        // names in Windows-1252 rather than UTF-8
+        let mut data = make_archive("sound\\voice\\caf", &[("cafe.mp3", &b"MP3"[..], false)]);
+        for _ in 0..2 {
+            let pos = data
+                .windows(4)
+                .position(|w| w == b"caf\0" || w == b"cafe")
+                .unwrap();
+            data[pos + 2] = 0xe7;
+        }
+        let mut bsa = Bsa::read(Cursor::new(data.clone())).unwrap();
+        assert_eq!(
+            bsa.files().next().unwrap().path(),
+            "sound\\voice\\ca\u{e7}\\ca\u{e7}e.mp3"
+        );
+        assert_eq!(
+            bsa.extract("sound\\voice\\ca\u{e7}\\ca\u{e7}e.mp3")
+                .unwrap(),
+            Some(b"MP3".to_vec())
+        );
+
+        // a file whose data runs past the end of the archive
+        data.pop();
+        assert!(Bsa::read(Cursor::new(data)).is_err());
+
+        // a file name table far larger than the archive
+        let mut data = make_archive("meshes", &[("wine01.nif", &b"NIF"[..], false)]);
+        data[28..32].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(Bsa::read(Cursor::new(data)).is_err());
+
+        // a compressed file claiming a decompressed size zlib couldn't produce
+        let mut data = make_archive("meshes", &[("wine01.nif", &b"NIF"[..], true)]);
+        // the file's data starts with its decompressed size
+        let offset = u32::from_le_bytes(data[72..76].try_into().unwrap()) as usize;
+        data[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let mut bsa = Bsa::read(Cursor::new(data)).unwrap();
+        assert!(bsa.extract("meshes\\wine01.nif").is_err());
+    }
+
+    #[test]
+    fn extract_all_files() {
+        let data = make_archive(
+            "textures\\menus\\icons\\clutter",
+            &[
+                ("wine01.dds", &b"uncompressed icon"[..], false),
+                ("wine02.dds", &b"compressed icon compressed icon"[..], true),
+                ("wine03.dds", &b"another compressed icon"[..], true),
+            ],
+        );
+        let mut bsa = Bsa::read(Cursor::new(data)).unwrap();
+
+        let extracted = std::sync::Mutex::new(HashMap::new());
+        bsa.extract_all(2, |file, data| {
+            extracted
+                .lock()
+                .unwrap()
+                .insert(String::from(file.path()), data);
+            Ok::<_, TesError>(())
+        })
+        .unwrap();
+
+        let extracted = extracted.into_inner().unwrap();
+        assert_eq!(extracted.len(), 3);
+        assert_eq!(
+            extracted["textures\\menus\\icons\\clutter\\wine02.dds"],
+            b"compressed icon compressed icon"
+        );
+        assert_eq!(
+            extracted["textures\\menus\\icons\\clutter\\wine03.dds"],
+            b"another compressed icon"
+        );
+
+        let result = bsa.extract_all(2, |file, _| {
+            if file.is_compressed() {
+                Err(TesError::RequirementFailed(String::from(
+                    "no compressed files",
+                )))
+            } else {
+                Ok(())
+            }
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn build_archive() {
+        for compressed in [false, true] {
+            let mut builder = BsaBuilder::new();
+            builder.set_compressed(compressed);
+            builder
+                .add_file("Meshes/Clutter/Wine/Wine01.NIF", b"wine mesh".to_vec())
+                .unwrap();
+            builder
+                .add_file(
+                    "meshes\\clutter\\wine\\wine02.nif",
+                    b"other wine mesh".to_vec(),
+                )
+                .unwrap();
+            builder
+                .add_file("textures\\clutter\\wine01.dds", b"wine texture".to_vec())
+                .unwrap();
+            assert!(builder.add_file("wine01.nif", vec![]).is_err());
+            assert_eq!(builder.len(), 3);
+
+            let mut data = vec![];
+            builder.write(&mut data).unwrap();
+            let mut bsa = Bsa::read(Cursor::new(data)).unwrap();
+
+            assert_eq!(bsa.len(), 3);
+            assert!(bsa.files().all(|f| f.is_compressed() == compressed));
+            // the game requires folders and the files in them to be in hash order
+            let hashes: Vec<_> = bsa.files().map(|f| bsa_hash_pair(f.path())).collect();
+            assert!(hashes.windows(2).all(|w| w[0] < w[1]));
+            assert_eq!(
+                bsa.extract("meshes/clutter/wine/wine01.nif").unwrap(),
+                Some(b"wine mesh".to_vec())
+            );
+            assert_eq!(
+                bsa.extract("meshes/clutter/wine/wine02.nif").unwrap(),
+                Some(b"other wine mesh".to_vec())
+            );
+            assert_eq!(
+                bsa.extract("textures/clutter/wine01.dds").unwrap(),
+                Some(b"wine texture".to_vec())
+            );
+        }
+    }
+
+    #[test]
+    fn hash_paths() {
+        let (folder, file) = bsa_hash_pair("textures\\menus\\icons\\clutter\\wine01.dds");
+        assert_eq!(
+            (folder, file),
+            bsa_hash_pair("Textures/Menus/Icons/Clutter/Wine01.DDS")
+        );
+        assert_eq!(
+            folder,
+            bsa_hash_pair("textures\\menus\\icons\\clutter\\x").0
+        );
+        // the first, last, and second-to-last characters and the length are stored as-is
+        assert_eq!(folder & 0xffffffff, 0x741c6572);
+        // .dds adds 0x80 to the low two bytes
+        assert_eq!(file & 0xffffffff, 0x7706b0b1);
+        assert_eq!(bsa_hash_pair("ab").1, 0x61020062);
+        assert_eq!(bsa_hash_pair("wine01").0, 0);
+    }
+}
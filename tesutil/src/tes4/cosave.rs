@@ -2,13 +2,14 @@ use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Seek, Write};
 use std::path::Path;
 
-use crate::TesError;
+use crate::{checked_len, TesError};
 
 use binrw::{binrw, BinReaderExt, BinWriterExt};
 
 mod obconvert;
 pub use obconvert::*;
 
+/// Opcode base of the cosave data belonging to tesconvert
 pub const OPCODE_BASE: u32 = 0x4000;
 pub const FORMAT_VERSION: u32 = 1;
 
@@ -46,12 +47,18 @@ impl Chunk {
     }
 
     pub fn write<T: Write + Seek>(&self, mut f: T) -> Result<(), TesError> {
+        checked_len::<u32>(self.data.len(), "Cosave chunk data")?;
         f.write_le(&self)?;
 
         Ok(())
     }
 }
 
+fn chunks_size(chunks: &[Chunk]) -> usize {
+    chunks.iter().map(|c| c.size()).sum()
+}
+
+/// The cosave data belonging to one OBSE plugin, identified by its opcode base
 #[binrw]
 #[derive(Debug)]
 pub struct Plugin {
@@ -60,13 +67,22 @@ pub struct Plugin {
     #[bw(calc = chunks.len() as u32)]
     num_chunks: u32,
     #[br(temp)]
-    #[bw(calc = chunks.iter().map(|c| c.size()).sum::<usize>() as u32)]
+    #[bw(calc = chunks_size(chunks) as u32)]
     data_len: u32,
-    #[br(count = num_chunks)]
+    // if the declared length didn't match the chunks, we couldn't write the plugin back as it was
+    #[br(count = num_chunks, assert(chunks_size(&chunks) == data_len as usize))]
     chunks: Vec<Chunk>,
 }
 
 impl Plugin {
+    /// Creates cosave data with no chunks for the plugin with the given opcode base
+    pub fn new(opcode_base: u32) -> Plugin {
+        Plugin {
+            opcode_base,
+            chunks: vec![],
+        }
+    }
+
     pub fn read<T: Read + Seek>(mut f: T) -> Result<Plugin, TesError> {
         Ok(f.read_le()?)
     }
@@ -87,13 +103,29 @@ impl Plugin {
         self.chunks.push(chunk);
     }
 
+    /// Checks that the plugin's chunks will fit in the sizes they're written with
+    fn check_lengths(&self) -> Result<(), TesError> {
+        checked_len::<u32>(self.chunks.len(), "Cosave plugin chunks")?;
+        checked_len::<u32>(chunks_size(&self.chunks), "Cosave plugin data")?;
+        Ok(())
+    }
+
     pub fn write<T: Write + Seek>(&self, mut f: T) -> Result<(), TesError> {
+        self.check_lengths()?;
         f.write_le(&self)?;
 
         Ok(())
     }
 }
 
+/// An OBSE cosave
+///
+/// A cosave holds data for every OBSE plugin the player has installed, not just ours. Data is only
+/// ever changed through [`get_plugin_by_opcode_mut`] or [`add_plugin`], so when a cosave is saved,
+/// the chunks of every other plugin are written back byte-for-byte as they were read.
+///
+/// [`get_plugin_by_opcode_mut`]: CoSave::get_plugin_by_opcode_mut
+/// [`add_plugin`]: CoSave::add_plugin
 #[binrw]
 #[derive(Debug)]
 #[brw(magic = b"OBSE")]
@@ -118,6 +150,35 @@ impl CoSave {
         Ok(f.read_le()?)
     }
 
+    /// Iterates through the data of each plugin in the cosave, in the order it was read
+    pub fn iter_plugins(&self) -> impl Iterator<Item = &Plugin> + '_ {
+        self.plugins.iter()
+    }
+
+    /// Iterates through the data of each plugin in the cosave other than tesconvert
+    pub fn foreign_plugins(&self) -> impl Iterator<Item = &Plugin> + '_ {
+        self.plugins
+            .iter()
+            .filter(|p| p.opcode_base() != OPCODE_BASE)
+    }
+
+    /// Adds data for a new plugin to the end of the cosave
+    ///
+    /// # Errors
+    ///
+    /// Fails if the cosave already has data for a plugin with the same opcode base.
+    pub fn add_plugin(&mut self, plugin: Plugin) -> Result<(), TesError> {
+        if self.get_plugin_by_opcode(plugin.opcode_base()).is_some() {
+            return Err(TesError::RequirementFailed(format!(
+                "Cosave already has data for opcode base {:#x}",
+                plugin.opcode_base()
+            )));
+        }
+
+        self.plugins.push(plugin);
+        Ok(())
+    }
+
     pub fn get_plugin_by_opcode(&self, opcode_base: u32) -> Option<&Plugin> {
         self.plugins
             .iter()
@@ -137,8 +198,176 @@ impl CoSave {
     }
 
     pub fn write<T: Write + Seek>(&self, mut f: T) -> Result<(), TesError> {
+        checked_len::<u32>(self.plugins.len(), "Cosave plugins")?;
+        for plugin in self.plugins.iter() {
+            plugin.check_lengths()?;
+        }
         f.write_le(&self)?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tes3::MarkLocation;
+    use crate::tes4::FormId;
+    use std::io::Cursor;
+    use std::ops::Range;
+
+    const FOREIGN_OPCODE_BASE: u32 = 0x2330;
+
+    fn write_chunk(buf: &mut Vec<u8>, tag: &[u8; 4], version: u32, data: &[u8]) {
+        buf.extend_from_slice(tag);
+        buf.extend_from_slice(&version.to_le_bytes());
+        buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        buf.extend_from_slice(data);
+    }
+
+    fn write_plugin(buf: &mut Vec<u8>, opcode_base: u32, chunks: &[u8], num_chunks: u32) {
+        buf.extend_from_slice(&opcode_base.to_le_bytes());
+        buf.extend_from_slice(&num_chunks.to_le_bytes());
+        buf.extend_from_slice(&(chunks.len() as u32).to_le_bytes());
+        buf.extend_from_slice(chunks);
+    }
+
+    /// Builds a cosave with a foreign plugin before and after ours
+    ///
+    /// Returns the cosave and the ranges of the foreign plugins' data within it.
+    fn build_cosave() -> (Vec<u8>, Vec<Range<usize>>) {
+        let mut foreign_chunks = vec![];
+        write_chunk(
+            &mut foreign_chunks,
+            b"ABCD",
+            3,
+            &[0xde, 0xad, 0xbe, 0xef, 0],
+        );
+        write_chunk(&mut foreign_chunks, b"EMPT", 0, &[]);
+        write_chunk(&mut foreign_chunks, b"ABCD", 7, &[0xff; 9]);
+
+        let mut our_chunks = vec![];
+        write_chunk(&mut our_chunks, b"ASPL", 1, &[]);
+
+        let mut buf = vec![];
+        buf.extend_from_slice(b"OBSE");
+        buf.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        buf.extend_from_slice(&21u16.to_le_bytes());
+        buf.extend_from_slice(&0u16.to_le_bytes());
+        buf.extend_from_slice(&0x01020304u32.to_le_bytes());
+        buf.extend_from_slice(&3u32.to_le_bytes());
+
+        let mut ranges = vec![];
+        let start = buf.len();
+        write_plugin(&mut buf, FOREIGN_OPCODE_BASE, &foreign_chunks, 3);
+        ranges.push(start..buf.len());
+        write_plugin(&mut buf, OPCODE_BASE, &our_chunks, 1);
+        let start = buf.len();
+        write_plugin(&mut buf, 0x2500, &[], 0);
+        ranges.push(start..buf.len());
+
+        (buf, ranges)
+    }
+
+    fn write_cosave(cosave: &CoSave) -> Vec<u8> {
+        let mut buf = vec![];
+        cosave.write(Cursor::new(&mut buf)).unwrap();
+        buf
+    }
+
+    #[test]
+    fn roundtrip_unmodified() {
+        let (data, _) = build_cosave();
+        let cosave = CoSave::read(Cursor::new(&data)).unwrap();
+        assert_eq!(write_cosave(&cosave), data);
+    }
+
+    #[test]
+    fn list_foreign_plugins() {
+        let (data, _) = build_cosave();
+        let cosave = CoSave::read(Cursor::new(&data)).unwrap();
+        assert_eq!(cosave.iter_plugins().count(), 3);
+        assert_eq!(
+            cosave
+                .foreign_plugins()
+                .map(|p| p.opcode_base())
+                .collect::<Vec<_>>(),
+            vec![FOREIGN_OPCODE_BASE, 0x2500]
+        );
+    }
+
+    #[test]
+    fn modify_own_plugin_only() {
+        let (data, ranges) = build_cosave();
+        let mut cosave = CoSave::read(Cursor::new(&data)).unwrap();
+
+        let plugin = cosave.get_plugin_by_opcode_mut(OPCODE_BASE).unwrap();
+        let mut convert = ObConvert::read(plugin).unwrap();
+        convert.add_active_spell(FormId(0x01000ce7), 12.5);
+        convert.write(plugin).unwrap();
+
+        let new_data = write_cosave(&cosave);
+        assert_ne!(new_data, data);
+        // our plugin grew, so the plugin after it moved
+        let growth = new_data.len() - data.len();
+        assert_eq!(new_data[ranges[0].clone()], data[ranges[0].clone()]);
+        let moved = ranges[1].start + growth..ranges[1].end + growth;
+        assert_eq!(new_data[moved], data[ranges[1].clone()]);
+
+        let new_cosave = CoSave::read(Cursor::new(&new_data)).unwrap();
+        let plugin = new_cosave.get_plugin_by_opcode(OPCODE_BASE).unwrap();
+        let active_spells = plugin.iter().find(|c| c.tag == *b"ASPL").unwrap();
+        assert_eq!(active_spells.data[..4], 0x01000ce7u32.to_le_bytes());
+    }
+
+    #[test]
+    fn add_plugin() {
+        let (data, _) = build_cosave();
+        let mut cosave = CoSave::read(Cursor::new(&data)).unwrap();
+        assert!(cosave.add_plugin(Plugin::new(OPCODE_BASE)).is_err());
+
+        cosave.add_plugin(Plugin::new(0x2600)).unwrap();
+        let new_data = write_cosave(&cosave);
+        let new_cosave = CoSave::read(Cursor::new(&new_data)).unwrap();
+        assert_eq!(new_cosave.foreign_plugins().count(), 3);
+    }
+
+    #[test]
+    fn store_morrowind_mark() {
+        let mark = MarkLocation {
+            cell: String::from("Balmora, Guild of Mages"),
+            x: 12.5,
+            y: -340.,
+            z: 96.25,
+            rotation: 1.5,
+            grid_x: -3,
+            grid_y: -2,
+        };
+
+        let mut plugin = Plugin::new(OPCODE_BASE);
+        let mut convert = ObConvert::new();
+        convert.set_morrowind_mark(Some(mark.clone()));
+        convert.write(&mut plugin).unwrap();
+        assert_eq!(
+            ObConvert::read(&plugin).unwrap().morrowind_mark(),
+            Some(&mark)
+        );
+
+        convert.set_morrowind_mark(None);
+        convert.write(&mut plugin).unwrap();
+        assert_eq!(ObConvert::read(&plugin).unwrap().morrowind_mark(), None);
+    }
+
+    #[test]
+    fn reject_bad_data_length() {
+        let mut chunks = vec![];
+        write_chunk(&mut chunks, b"ABCD", 1, &[1, 2, 3]);
+        let mut plugin = vec![];
+        write_plugin(&mut plugin, FOREIGN_OPCODE_BASE, &chunks, 1);
+        // claim one more byte than the chunks take up
+        plugin[8] += 1;
+        plugin.push(0);
+
+        assert!(Plugin::read(Cursor::new(&plugin)).is_err());
+    }
+}
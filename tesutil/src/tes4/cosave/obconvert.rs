@@ -1,10 +1,10 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 
 use super::Plugin;
-use crate::tes3::{InventoryItem, Script};
+use crate::tes3::{InventoryItem, ItemScript, MarkLocation};
 use crate::tes4::FormId;
-use crate::{decode_failed, read_bstring, write_bstring, TesError};
+use crate::{checked_len, decode_failed, read_bstring, write_bstring, TesError};
 
 use crate::tes4::cosave::Chunk;
 use binrw::{BinReaderExt, BinWriterExt};
@@ -12,13 +12,16 @@ use binrw::{BinReaderExt, BinWriterExt};
 const FORM_MAP_VERSION: u32 = 0;
 const ACTIVE_SPELL_VERSION: u32 = 0;
 const MW_INVENTORY_VERSION: u32 = 0;
+const MW_MARK_VERSION: u32 = 0;
 
 #[derive(Debug)]
 pub struct ObConvert {
-    form_map: HashMap<String, FormId>,
-    active_spells: HashMap<FormId, f32>,
+    form_map: BTreeMap<String, FormId>,
+    active_spells: BTreeMap<FormId, f32>,
     // inconvertible inventory items from other games
     morrowind_inventory: Vec<InventoryItem>,
+    // the player's Mark location from Morrowind, for a mod to make use of
+    morrowind_mark: Option<MarkLocation>,
 }
 
 impl Default for ObConvert {
@@ -30,9 +33,10 @@ impl Default for ObConvert {
 impl ObConvert {
     pub fn new() -> ObConvert {
         ObConvert {
-            form_map: HashMap::new(),
-            active_spells: HashMap::new(),
+            form_map: BTreeMap::new(),
+            active_spells: BTreeMap::new(),
             morrowind_inventory: vec![],
+            morrowind_mark: None,
         }
     }
 
@@ -76,7 +80,7 @@ impl ObConvert {
                 *float = f.read_le()?;
             }
 
-            Some(Script {
+            Some(ItemScript {
                 name: script_id,
                 shorts,
                 longs,
@@ -123,17 +127,17 @@ impl ObConvert {
         if let Some(ref script) = item.script {
             write_bstring(&mut f, &script.name)?;
 
-            f.write_le(&(script.shorts.len() as u32))?;
+            f.write_le(&checked_len::<u32>(script.shorts.len(), "Script shorts")?)?;
             for short in &script.shorts {
                 f.write_le(short)?;
             }
 
-            f.write_le(&(script.longs.len() as u32))?;
+            f.write_le(&checked_len::<u32>(script.longs.len(), "Script longs")?)?;
             for long in &script.longs {
                 f.write_le(long)?;
             }
 
-            f.write_le(&(script.floats.len() as u32))?;
+            f.write_le(&checked_len::<u32>(script.floats.len(), "Script floats")?)?;
             for float in &script.floats {
                 f.write_le(float)?;
             }
@@ -197,6 +201,28 @@ impl ObConvert {
                             .push(Self::read_morrowind_item(&mut reader)?);
                     }
                 }
+                b"MWMK" => {
+                    if chunk.version != MW_MARK_VERSION {
+                        return Err(decode_failed(format!(
+                            "Unexpected Morrowind mark version {}",
+                            chunk.version
+                        )));
+                    }
+
+                    // an empty chunk means there's no mark
+                    if !chunk.data.is_empty() {
+                        let mut reader = Cursor::new(&chunk.data);
+                        convert.morrowind_mark = Some(MarkLocation {
+                            cell: read_bstring(&mut reader)?,
+                            x: reader.read_le()?,
+                            y: reader.read_le()?,
+                            z: reader.read_le()?,
+                            rotation: reader.read_le()?,
+                            grid_x: reader.read_le()?,
+                            grid_y: reader.read_le()?,
+                        });
+                    }
+                }
                 _ => return Err(decode_failed("Unexpected chunk")),
             }
         }
@@ -204,8 +230,11 @@ impl ObConvert {
         Ok(convert)
     }
 
-    pub fn set_active_spells(&mut self, active_spells: HashMap<FormId, f32>) {
-        self.active_spells = active_spells;
+    /// Replaces the active spells with (form ID, seconds active) pairs
+    ///
+    /// If a spell appears more than once, the last time it appears wins.
+    pub fn set_active_spells<T: IntoIterator<Item = (FormId, f32)>>(&mut self, active_spells: T) {
+        self.active_spells = active_spells.into_iter().collect();
     }
 
     pub fn add_active_spell(&mut self, form_id: FormId, seconds_active: f32) {
@@ -228,8 +257,16 @@ impl ObConvert {
         self.morrowind_inventory.clear();
     }
 
+    pub fn morrowind_mark(&self) -> Option<&MarkLocation> {
+        self.morrowind_mark.as_ref()
+    }
+
+    pub fn set_morrowind_mark(&mut self, mark: Option<MarkLocation>) {
+        self.morrowind_mark = mark;
+    }
+
     pub fn write(&self, plugin: &mut Plugin) -> Result<(), TesError> {
-        let missing_tags = [b"FMAP", b"ASPL", b"MWIN"]
+        let missing_tags = [b"FMAP", b"ASPL", b"MWIN", b"MWMK"]
             .iter()
             .filter(|t| !plugin.iter().any(|c| c.tag == ***t))
             .collect::<Vec<&&[u8; 4]>>();
@@ -260,12 +297,29 @@ impl ObConvert {
                 b"MWIN" => {
                     let mut data = vec![];
                     let mut writer = Cursor::new(&mut data);
-                    writer.write_le(&(self.morrowind_inventory.len() as u32))?;
+                    writer.write_le(&checked_len::<u32>(
+                        self.morrowind_inventory.len(),
+                        "Morrowind inventory",
+                    )?)?;
                     for item in &self.morrowind_inventory {
                         Self::write_morrowind_item(&mut writer, item)?;
                     }
                     chunk.set_data(data);
                 }
+                b"MWMK" => {
+                    let mut data = vec![];
+                    if let Some(ref mark) = self.morrowind_mark {
+                        let mut writer = Cursor::new(&mut data);
+                        write_bstring(&mut writer, &mark.cell)?;
+                        writer.write_le(&mark.x)?;
+                        writer.write_le(&mark.y)?;
+                        writer.write_le(&mark.z)?;
+                        writer.write_le(&mark.rotation)?;
+                        writer.write_le(&mark.grid_x)?;
+                        writer.write_le(&mark.grid_y)?;
+                    }
+                    chunk.set_data(data);
+                }
                 _ => (), // ignore
             }
         }
@@ -0,0 +1,23 @@
+use crate::tes4::FormId;
+
+// Form IDs of well-known forms in Oblivion.esm
+//
+// These forms are hard-coded in the game engine or are otherwise needed regardless of what the
+// user's load order contains, so they have the same form ID in every game.
+
+/// Form ID of the player's base record
+pub const FORM_PLAYER: FormId = FormId(7);
+/// Form ID of the player's reference
+pub const FORM_PLAYER_REF: FormId = FormId(0x14);
+/// Form ID of the player's custom class
+///
+/// The class the player creates in character generation replaces this placeholder.
+pub const FORM_PLAYER_CUSTOM_CLASS: FormId = FormId(0x00022843);
+/// Form ID of gold
+pub const FORM_GOLD: FormId = FormId(0xf);
+/// Form ID of lockpicks
+pub const FORM_LOCKPICK: FormId = FormId(0xa);
+/// Form ID of the Skeleton Key, the unbreakable lockpick
+pub const FORM_SKELETON_KEY: FormId = FormId(0xb);
+/// Form ID of repair hammers
+pub const FORM_REPAIR_HAMMER: FormId = FormId(0xc);
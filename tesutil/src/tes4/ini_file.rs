@@ -0,0 +1,188 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use ini::{Ini, ParseOption};
+
+use crate::{decode_failed, TesError};
+
+/// Name of the file Oblivion reads its settings from
+pub const INI_FILE: &str = "Oblivion.ini";
+
+const DEFAULT_SAVE_PATH: &str = "Saves\\";
+
+// paths in the INI are written with backslashes, which aren't escapes
+const PARSE_OPTIONS: ParseOption = ParseOption {
+    enabled_quote: false,
+    enabled_escape: false,
+};
+
+/// Settings from Oblivion.ini
+///
+/// Oblivion looks up sections and settings without regard to case, so lookups here ignore case
+/// too. The first letter of a setting's name gives its type: `f` for floats, `i` and `u` for
+/// integers, `b` for booleans (written as 0 or 1), and `s` for strings.
+#[derive(Debug)]
+pub struct OblivionIni {
+    ini: Ini,
+}
+
+impl OblivionIni {
+    /// Reads Oblivion.ini from a file
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs or if the file isn't a valid INI file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<OblivionIni, TesError> {
+        Ok(OblivionIni {
+            ini: Ini::load_from_file_opt(path, PARSE_OPTIONS)?,
+        })
+    }
+
+    /// Parses the contents of Oblivion.ini
+    ///
+    /// # Errors
+    ///
+    /// Fails if the text isn't valid INI data.
+    pub fn parse(text: &str) -> Result<OblivionIni, TesError> {
+        Ok(OblivionIni {
+            ini: Ini::load_from_str_opt(text, PARSE_OPTIONS).map_err(ini::Error::Parse)?,
+        })
+    }
+
+    /// Gets the raw value of a setting, if it's present
+    pub fn get(&self, section: &str, name: &str) -> Option<&str> {
+        self.ini
+            .iter()
+            .filter(|(s, _)| s.map_or(false, |s| s.eq_ignore_ascii_case(section)))
+            .flat_map(|(_, properties)| properties.iter())
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim())
+    }
+
+    fn get_parsed<T: FromStr>(&self, section: &str, name: &str) -> Result<Option<T>, TesError> {
+        self.get(section, name)
+            .map(|value| {
+                value.parse().map_err(|_| {
+                    decode_failed(format!(
+                        "Invalid value {} for setting {} in [{}] of {}",
+                        value, name, section, INI_FILE
+                    ))
+                })
+            })
+            .transpose()
+    }
+
+    /// Gets a float setting, if it's present
+    ///
+    /// # Errors
+    ///
+    /// Fails if the setting's value isn't a number.
+    pub fn get_float(&self, section: &str, name: &str) -> Result<Option<f32>, TesError> {
+        self.get_parsed(section, name)
+    }
+
+    /// Gets an integer setting, if it's present
+    ///
+    /// # Errors
+    ///
+    /// Fails if the setting's value isn't an integer.
+    pub fn get_int(&self, section: &str, name: &str) -> Result<Option<i32>, TesError> {
+        self.get_parsed(section, name)
+    }
+
+    /// Gets a boolean setting, if it's present
+    ///
+    /// Like the game, any non-zero value counts as true.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the setting's value isn't an integer.
+    pub fn get_bool(&self, section: &str, name: &str) -> Result<Option<bool>, TesError> {
+        Ok(self.get_int(section, name)?.map(|value| value != 0))
+    }
+
+    /// Returns whether the game keeps its INI and saves in the user's My Games directory
+    ///
+    /// When this is false, they're kept in the install directory instead.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the setting's value isn't an integer.
+    pub fn uses_my_games_directory(&self) -> Result<bool, TesError> {
+        Ok(self
+            .get_bool("General", "bUseMyGamesDirectory")?
+            .unwrap_or(true))
+    }
+
+    /// Gets the path of the saves directory, relative to the directory the INI is in
+    pub fn local_save_path(&self) -> &str {
+        self.get("General", "SLocalSavePath")
+            .filter(|path| !path.is_empty())
+            .unwrap_or(DEFAULT_SAVE_PATH)
+    }
+
+    /// Gets the names of the archives the game loads before any plugins' archives, in load order
+    pub fn archive_list(&self) -> Vec<&str> {
+        self.get("Archive", "SArchiveList")
+            .map(|list| {
+                list.split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Gets the saves directory for an INI file that's in `ini_dir`
+    pub fn saves_dir<P: AsRef<Path>>(&self, ini_dir: P) -> PathBuf {
+        // the path is always written with backslashes, so split it up ourselves to get a path
+        // that works on any platform
+        let mut dir = ini_dir.as_ref().to_path_buf();
+        dir.extend(self.local_save_path().split('\\').filter(|c| !c.is_empty()));
+        dir
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INI: &str = "[General]\r\n\
+        SLocalSavePath=Saves\\Modded\\\r\n\
+        bUseMyGamesDirectory=0\r\n\
+        [GamePlay]\r\n\
+        fDifficulty=0.2500\r\n\
+        bInstantLevelUp=oops\r\n\
+        [Archive]\r\n\
+        SArchiveList=Oblivion - Meshes.bsa, Oblivion - Textures - Compressed.bsa,\r\n";
+
+    #[test]
+    fn read_settings() {
+        let ini = OblivionIni::parse(TEST_INI).unwrap();
+        assert_eq!(ini.local_save_path(), "Saves\\Modded\\");
+        assert!(!ini.uses_my_games_directory().unwrap());
+        assert_eq!(
+            ini.get_float("gameplay", "FDIFFICULTY").unwrap(),
+            Some(0.25)
+        );
+        assert_eq!(ini.get_float("GamePlay", "fMissing").unwrap(), None);
+        assert!(ini.get_bool("GamePlay", "bInstantLevelUp").is_err());
+        assert_eq!(
+            ini.archive_list(),
+            [
+                "Oblivion - Meshes.bsa",
+                "Oblivion - Textures - Compressed.bsa"
+            ]
+        );
+
+        assert_eq!(
+            ini.saves_dir("Oblivion"),
+            ["Oblivion", "Saves", "Modded"].iter().collect::<PathBuf>()
+        );
+
+        let ini = OblivionIni::parse("").unwrap();
+        assert_eq!(ini.local_save_path(), DEFAULT_SAVE_PATH);
+        assert!(ini.uses_my_games_directory().unwrap());
+        assert!(ini.archive_list().is_empty());
+    }
+}
@@ -1,11 +1,12 @@
 use std::collections::HashMap;
 use std::io::{Cursor, Read, Seek};
-use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
 
-use super::{FindForm, FormId};
+use super::{FindForm, FormId, PluginIndex};
 use crate::*;
 
 use binrw::BinReaderExt;
+use lazy_static::lazy_static;
 
 mod field;
 pub use field::*;
@@ -61,6 +62,18 @@ pub use ammo::*;
 mod book;
 pub use book::*;
 
+mod wearable;
+pub use wearable::*;
+
+mod armor;
+pub use armor::*;
+
+mod clothing;
+pub use clothing::*;
+
+mod reference;
+pub use reference::*;
+
 /// Maximum number of masters that a plugin can have
 // - 2 because index FF is reserved for saves, and we also need at least one index for ourselves
 pub const MAX_MASTERS: usize = u8::MAX as usize - 2;
@@ -93,22 +106,100 @@ pub struct Tes4Plugin {
     version: f32,
     is_master: bool,
     next_form_id: u32,
+    num_records: u32,
+    maintain_header: bool,
     author: Option<String>,
     description: Option<String>,
     // these two fields are optional and only seen in Oblivion.esm; we store them so we can write
     // them back out, but we don't do anything with them
     offsets: Option<Tes4Field>,
     deleted: Option<Tes4Field>,
+    overridden_forms: Option<Tes4Field>,
     masters: Vec<(String, String)>,
     groups: HashMap<[u8; 4], Group>,
     id_map: HashMap<FormId, Arc<RwLock<Tes4Record>>>,
     settings: HashMap<String, Arc<RwLock<Tes4Record>>>,
     magic_effects: HashMap<MagicEffectType, Arc<RwLock<Tes4Record>>>,
+    // editor IDs taken from an index for records that haven't been decoded
+    indexed_editor_ids: HashMap<FormId, String>,
+    journal: Option<Journal<Tes4Record>>,
 }
 
 /// Version value for Oblivion plugins
 pub const VERSION: f32 = 1.;
 
+/// Version values that Oblivion plugin headers are known to have
+///
+/// Plugins saved by early versions of the Construction Set have version 0.8.
+pub const KNOWN_VERSIONS: [f32; 2] = [0.8, VERSION];
+
+/// A flag in a plugin's header
+///
+/// Oblivion only has the master flag, but later games add others, such as light masters, so more
+/// flags may be added to this enum.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum HeaderFlag {
+    /// The plugin is a master, which other plugins can depend on
+    Master,
+}
+
+/// The kinds of values held by well-known Oblivion fields, which make up the built-in schema
+const FIELD_KINDS: [FieldKindRule; 40] = [
+    (b"EDID", None, FieldKind::String),
+    (b"FULL", None, FieldKind::String),
+    (b"DESC", None, FieldKind::String),
+    (b"MODL", None, FieldKind::String),
+    (b"ICON", None, FieldKind::String),
+    (b"EFID", None, FieldKind::String),
+    (b"MAST", None, FieldKind::String),
+    (b"CNAM", Some(b"TES4"), FieldKind::String),
+    (b"SNAM", Some(b"TES4"), FieldKind::String),
+    (b"FNAM", Some(b"GLOB"), FieldKind::String),
+    (b"SCTX", None, FieldKind::String),
+    (b"NAME", None, FieldKind::FormId),
+    (b"SCRI", None, FieldKind::FormId),
+    (b"ENAM", None, FieldKind::FormId),
+    (b"INAM", None, FieldKind::FormId),
+    (b"RNAM", None, FieldKind::FormId),
+    (b"CNAM", Some(b"NPC_"), FieldKind::FormId),
+    (b"SPLO", None, FieldKind::FormId),
+    (b"PKID", None, FieldKind::FormId),
+    (b"HNAM", None, FieldKind::FormId),
+    // a race's eyes and hairs
+    (b"ENAM", Some(b"RACE"), FieldKind::FormIdList),
+    (b"HNAM", Some(b"RACE"), FieldKind::FormIdList),
+    (b"ZNAM", None, FieldKind::FormId),
+    (b"QSTI", None, FieldKind::FormId),
+    (b"XOWN", None, FieldKind::FormId),
+    (b"XGLB", None, FieldKind::FormId),
+    (b"XRTM", None, FieldKind::FormId),
+    (b"XPCI", None, FieldKind::FormId),
+    (b"ANAM", None, FieldKind::Int),
+    (b"INTV", None, FieldKind::Int),
+    (b"XCNT", None, FieldKind::Int),
+    (b"XRNK", None, FieldKind::Int),
+    (b"XLCM", None, FieldKind::Int),
+    (b"FNAM", Some(b"NPC_"), FieldKind::Int),
+    (b"FLTV", None, FieldKind::Float),
+    (b"MODB", None, FieldKind::Float),
+    (b"XSCL", None, FieldKind::Float),
+    (b"XCHG", None, FieldKind::Float),
+    (b"LNAM", Some(b"NPC_"), FieldKind::Float),
+    (b"DATA", Some(b"ALCH"), FieldKind::Float),
+];
+
+lazy_static! {
+    /// Handlers for custom record types, used when reading and writing Oblivion plugins
+    pub static ref RECORD_REGISTRY: RecordRegistry<Tes4Record> = RecordRegistry::new();
+    /// Kinds of values held by Oblivion fields, used for records without a dedicated form
+    pub static ref RECORD_SCHEMA: RecordSchema = RecordSchema::with_rules(&FIELD_KINDS);
+}
+
+// exterior cells are grouped into blocks of 32x32 cells, which are split into sub-blocks of 8x8
+const CELL_BLOCK_SIZE: i32 = 32;
+const CELL_SUB_BLOCK_SIZE: i32 = 8;
+
 /// Order that top-level groups should be written in
 const GROUP_ORDER: [&'static [u8; 4]; 56] = [
     b"GMST", b"GLOB", b"CLAS", b"FACT", b"HAIR", b"EYES", b"RACE", b"SOUN", b"SKIL", b"MGEF",
@@ -125,30 +216,50 @@ impl Tes4Plugin {
             version: VERSION,
             is_master: false,
             next_form_id: 0x800, // not sure what the significance of this number is, but this is what TES4Edit uses
+            num_records: 0,
+            maintain_header: true,
             author,
             description,
             offsets: None,
             deleted: None,
+            overridden_forms: None,
             masters: vec![],
             groups: HashMap::new(),
             id_map: HashMap::new(),
             settings: HashMap::new(),
             magic_effects: HashMap::new(),
+            indexed_editor_ids: HashMap::new(),
+            journal: None,
         }
     }
 
     fn add_group_record(&mut self, record: Arc<RwLock<Tes4Record>>) -> Result<(), TesError> {
+        self.add_indexed_group_record(record, None, false)
+            .map(|_| ())
+    }
+
+    /// Adds a record from a group, taking its editor ID from an index if one was found there
+    ///
+    /// If `decode_editor_id` is true, records of any type are decoded to get their editor IDs;
+    /// otherwise, only records that are tracked by editor ID are. Returns the record's editor ID if
+    /// it's known.
+    fn add_indexed_group_record(
+        &mut self,
+        record: Arc<RwLock<Tes4Record>>,
+        indexed_editor_id: Option<&str>,
+        decode_editor_id: bool,
+    ) -> Result<Option<String>, TesError> {
         let num_masters = self.masters.len();
         // + 1 for our own index
-        let max_index = num_masters as u8 + 1;
+        let max_index = PluginIndex(num_masters as u8 + 1);
 
         let rb = record.read().unwrap();
         let id = rb.id();
         let record_type = *rb.name();
-        let index = id.index();
+        let index = id.master_index();
         if index > max_index {
             return Err(decode_failed(format!(
-                "Expected maximum index {:02X} but found index {:02X}",
+                "Expected maximum index {} but found index {}",
                 max_index, index
             )));
         }
@@ -156,38 +267,60 @@ impl Tes4Plugin {
         drop(rb);
 
         // GMSTs and MGEFs don't have fixed form IDs, so we have to track them separately
-        if record_type == *b"GMST" {
-            let mut rbm = record.write().unwrap();
-            rbm.finalize()?;
-            for field in rbm.iter() {
-                if field.name() == b"EDID" {
-                    let key = String::from(field.get_zstring()?);
-                    self.settings.insert(key, Arc::clone(&record));
-                    break;
-                }
-            }
-        } else if record_type == *b"MGEF" {
-            let mut rbm = record.write().unwrap();
-            rbm.finalize()?;
-            for field in rbm.iter() {
-                if field.name() == b"EDID" {
-                    // strip the trailing \0
-                    let mgef_id = field.get_zstring()?.as_bytes();
-                    let key = MagicEffectType::from_id(mgef_id).ok_or_else(|| {
-                        TesError::RequirementFailed(format!(
-                            "Invalid magic effect type with form ID {:08X}",
-                            id.0
-                        ))
-                    })?;
-                    self.magic_effects.insert(key, Arc::clone(&record));
-                    break;
-                }
+        let is_tracked = record_type == *b"GMST" || record_type == *b"MGEF";
+        let editor_id = match indexed_editor_id {
+            Some(editor_id) => Some(String::from(editor_id)),
+            None if is_tracked => Tes4Plugin::decode_editor_id(&record)?,
+            // a record we can't decode is left for whoever uses it to report
+            None if decode_editor_id => Tes4Plugin::decode_editor_id(&record).unwrap_or(None),
+            None => None,
+        };
+
+        if let Some(ref editor_id) = editor_id {
+            if record_type == *b"GMST" {
+                self.settings.insert(editor_id.clone(), Arc::clone(&record));
+            } else if record_type == *b"MGEF" {
+                let key = MagicEffectType::from_id(editor_id.as_bytes()).ok_or_else(|| {
+                    TesError::RequirementFailed(format!(
+                        "Invalid magic effect type with form ID {}",
+                        id
+                    ))
+                })?;
+                self.magic_effects.insert(key, Arc::clone(&record));
+            } else if indexed_editor_id.is_some() {
+                self.indexed_editor_ids.insert(id, editor_id.clone());
             }
         }
 
         self.id_map.insert(id, record);
 
-        Ok(())
+        Ok(editor_id)
+    }
+
+    /// Decodes a record and gets its editor ID, if it has one
+    fn decode_editor_id(record: &RwLock<Tes4Record>) -> Result<Option<String>, TesError> {
+        let mut rbm = record.write().unwrap();
+        rbm.finalize()?;
+        for field in rbm.iter() {
+            if field.name() == b"EDID" {
+                return Ok(Some(String::from(field.get_zstring()?)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Locks a GMST or MGEF record for reading, decoding its fields first if it hasn't been yet
+    ///
+    /// These records are only left undecoded when a plugin is read with an index.
+    fn read_tracked_record(
+        record: &Arc<RwLock<Tes4Record>>,
+    ) -> Result<RwLockReadGuard<Tes4Record>, TesError> {
+        if record.read().unwrap().status() == RecordStatus::Initialized {
+            record.write().unwrap().finalize()?;
+        }
+
+        Ok(record.read().unwrap())
     }
 
     pub fn add_record(&mut self, record: Tes4Record) -> Result<(), TesError> {
@@ -197,7 +330,83 @@ impl Tes4Plugin {
             .entry(record_type)
             .or_insert_with(|| Group::new(GroupKind::Top(record_type)));
         let group_record = group.add_record(record)?;
-        self.add_group_record(group_record)
+        let replaced = self.id_map.get(&group_record.read().unwrap().id()).cloned();
+        let editor_id = self.add_indexed_group_record(Arc::clone(&group_record), None, false)?;
+        if let Some(ref mut journal) = self.journal {
+            let mut mutation = Mutation::new(MutationKind::Added, editor_id, &group_record);
+            mutation.replaced.extend(replaced);
+            journal.push(mutation);
+        }
+
+        Ok(())
+    }
+
+    /// Starts keeping a journal of the changes made to this plugin's records
+    ///
+    /// Any journal that was already being kept is discarded. Changes to the plugin's header, such
+    /// as its author or masters, are not recorded. Records can't be removed from a TES4 plugin, so
+    /// the journal only records additions and modifications.
+    pub fn start_journal(&mut self) {
+        self.journal = Some(Journal::new());
+    }
+
+    /// Stops keeping a journal and returns the changes that were recorded
+    pub fn stop_journal(&mut self) -> Option<Journal<Tes4Record>> {
+        self.journal.take()
+    }
+
+    /// Gets the journal of changes made since [`start_journal`] was called, if one is being kept
+    ///
+    /// [`start_journal`]: #method.start_journal
+    pub fn journal(&self) -> Option<&Journal<Tes4Record>> {
+        self.journal.as_ref()
+    }
+
+    /// Reverts the change at an index in the journal and removes it from the journal
+    ///
+    /// Returns `false` if no journal is being kept or it has no change at that index. Reverting a
+    /// change doesn't revert later changes to the same record, so changes should be reverted from
+    /// newest to oldest. Reverting an addition doesn't give back the form ID the record was
+    /// assigned.
+    pub fn revert(&mut self, index: usize) -> bool {
+        let mutation = match self.journal.as_mut().and_then(|j| j.take(index)) {
+            Some(mutation) => mutation,
+            None => return false,
+        };
+
+        match mutation.kind {
+            MutationKind::Added => {
+                let (record_type, form_id) = {
+                    let rb = mutation.record();
+                    (*rb.name(), rb.id())
+                };
+                if let Some(group) = self.groups.get_mut(&record_type) {
+                    group.remove_record(&mutation.record);
+                }
+                self.settings
+                    .retain(|_, r| !Arc::ptr_eq(r, &mutation.record));
+                self.magic_effects
+                    .retain(|_, r| !Arc::ptr_eq(r, &mutation.record));
+                if matches!(self.id_map.get(&form_id), Some(r) if Arc::ptr_eq(r, &mutation.record))
+                {
+                    self.id_map.remove(&form_id);
+                }
+                for record in mutation.replaced {
+                    // the replaced record was indexed the same way when it was added, so this
+                    // can't fail
+                    let _ = self.add_group_record(record);
+                }
+            }
+            MutationKind::Modified => {
+                if let Some(previous) = mutation.take_previous() {
+                    mutation.record.write().unwrap().restore(previous);
+                }
+            }
+            // records are never removed from a TES4 plugin
+            MutationKind::Removed => (),
+        }
+
+        true
     }
 
     /// Adds a record to this plugin, assigning it the next available form ID
@@ -209,12 +418,174 @@ impl Tes4Plugin {
 
     /// Returns and increments the next available form ID
     pub fn get_next_form_id(&mut self) -> FormId {
-        let mut id = FormId(self.next_form_id);
-        id.set_index(self.masters.len() as u8);
+        let id = FormId::new(PluginIndex(self.masters.len() as u8), self.next_form_id);
         self.next_form_id += 1;
         id
     }
 
+    /// Counts the records and groups in this plugin, as stored in the plugin header
+    pub fn record_count(&self) -> u32 {
+        self.groups.values().map(|g| g.len()).sum::<usize>() as u32
+    }
+
+    /// Gets statistics about the records in this plugin
+    ///
+    /// This includes the records in records' associated groups, such as the references in a
+    /// cell's children. Records are not finalized.
+    pub fn load_stats(&self) -> LoadStats {
+        let mut stats = LoadStats::new();
+        for group in self.groups.values() {
+            group.for_each_record(&mut |record| stats.add(record));
+        }
+        stats
+    }
+
+    /// Gets the lowest form ID that isn't used by any record in this plugin
+    pub fn next_unused_form_id(&self) -> FormId {
+        FormId::new(
+            PluginIndex(self.masters.len() as u8),
+            self.next_unused_local_id(),
+        )
+    }
+
+    /// Gets the local part of the next unused form ID, which may be too big for a form ID
+    fn next_unused_local_id(&self) -> u32 {
+        let index = PluginIndex(self.masters.len() as u8);
+        // a next ID too big to use can only have come from a header we read, and since the header
+        // is being recalculated anyway, we go by the records instead
+        let mut next_id = if self.next_form_id > FormId::LOCAL_ID_MASK {
+            0
+        } else {
+            self.next_form_id
+        };
+        for group in self.groups.values() {
+            group.for_each_record(&mut |record| {
+                let id = record.id();
+                if id.master_index() == index && id.local_id() >= next_id {
+                    next_id = id.local_id() + 1;
+                }
+            });
+        }
+
+        next_id
+    }
+
+    /// Gets the persistent references from this plugin's masters that this plugin overrides
+    ///
+    /// Master files list these in the ONAM field of their header so the game knows to load the
+    /// overrides along with the originals. The form IDs are relative to this plugin's masters and in
+    /// ascending order.
+    pub fn overridden_forms(&self) -> Vec<FormId> {
+        let index = PluginIndex(self.masters.len() as u8);
+        let mut ids = vec![];
+        for group in self.groups.values() {
+            group.for_each_record(&mut |record| {
+                if matches!(record.name(), b"REFR" | b"ACHR" | b"ACRE")
+                    && record.is_persistent()
+                    && record.id().master_index() < index
+                {
+                    ids.push(record.id());
+                }
+            });
+        }
+
+        ids.sort_by_key(|id| id.0);
+        ids
+    }
+
+    /// Gets the version in this plugin's header
+    pub fn version(&self) -> f32 {
+        self.version
+    }
+
+    /// Sets the version in this plugin's header
+    ///
+    /// # Errors
+    ///
+    /// Fails if the version isn't one of the [`KNOWN_VERSIONS`].
+    ///
+    /// [`KNOWN_VERSIONS`]: constant.KNOWN_VERSIONS.html
+    pub fn set_version(&mut self, version: f32) -> Result<(), TesError> {
+        Tes4Plugin::check_version(version)?;
+        self.version = version;
+        Ok(())
+    }
+
+    fn check_version(version: f32) -> Result<(), TesError> {
+        if KNOWN_VERSIONS.contains(&version) {
+            Ok(())
+        } else {
+            Err(TesError::RequirementFailed(format!(
+                "Unsupported plugin version {}",
+                version
+            )))
+        }
+    }
+
+    /// Returns whether a flag is set in this plugin's header
+    pub fn has_header_flag(&self, flag: HeaderFlag) -> bool {
+        match flag {
+            HeaderFlag::Master => self.is_master,
+        }
+    }
+
+    /// Sets or clears a flag in this plugin's header
+    pub fn set_header_flag(&mut self, flag: HeaderFlag, value: bool) {
+        match flag {
+            HeaderFlag::Master => self.is_master = value,
+        }
+    }
+
+    /// Gets the record count that will be written in this plugin's header
+    ///
+    /// This is the same as [`record_count`] if the plugin [`maintains_header`]; otherwise, it's the
+    /// count read from the original header.
+    ///
+    /// [`record_count`]: #method.record_count
+    /// [`maintains_header`]: #method.maintains_header
+    pub fn header_record_count(&self) -> u32 {
+        if self.maintain_header {
+            self.record_count()
+        } else {
+            self.num_records
+        }
+    }
+
+    /// Gets the next available form ID that will be written in this plugin's header
+    ///
+    /// This is the same as [`next_unused_form_id`] if the plugin [`maintains_header`]; otherwise,
+    /// it's the ID from the original header, advanced by any calls to [`get_next_form_id`].
+    ///
+    /// [`next_unused_form_id`]: #method.next_unused_form_id
+    /// [`maintains_header`]: #method.maintains_header
+    /// [`get_next_form_id`]: #method.get_next_form_id
+    pub fn header_next_form_id(&self) -> FormId {
+        if self.maintain_header {
+            self.next_unused_form_id()
+        } else {
+            FormId::new(PluginIndex(self.masters.len() as u8), self.next_form_id)
+        }
+    }
+
+    /// Does this plugin recalculate its header statistics when it's written?
+    pub fn maintains_header(&self) -> bool {
+        self.maintain_header
+    }
+
+    /// Sets whether this plugin should recalculate its header statistics when it's written
+    ///
+    /// By default, the record count and next available form ID in the plugin header, as well as the
+    /// list of [`overridden_forms`] for master files, are recalculated when the plugin is written.
+    /// If this is disabled, the record count and overridden forms read from the original header are
+    /// written unchanged, and the next form ID is only advanced by [`get_next_form_id`].
+    ///
+    /// [`overridden_forms`]: #method.overridden_forms
+    ///
+    /// [`get_next_form_id`]: #method.get_next_form_id
+    pub fn set_maintain_header(&mut self, value: bool) {
+        self.maintain_header = value;
+    }
+
     /// Gets a record by form ID
     pub fn get_record(&self, search: &FindForm) -> Option<RwLockReadGuard<Tes4Record>> {
         self.id_map
@@ -235,33 +606,241 @@ impl Tes4Plugin {
             })
     }
 
-    /// Gets a record by form ID
-    pub fn get_record_mut(&self, search: &FindForm) -> Option<RwLockWriteGuard<Tes4Record>> {
-        self.id_map
-            .get(&search.form_id(self.masters.iter().map(|(s, _)| s.as_str()))?)
-            .and_then(|r| {
-                let mut rbm = r.write().unwrap();
+    /// Finds the form ID a form has in this plugin, if this plugin contains the form
+    ///
+    /// Unlike [`get_record`], master names are compared case-insensitively and the record is not
+    /// finalized.
+    ///
+    /// [`get_record`]: #method.get_record
+    pub fn find_form_id(&self, search: &FindForm) -> Option<FormId> {
+        let lc_masters = self.masters.iter().map(|(_, lc)| lc.as_str());
+        let form_id = match *search {
+            FindForm::ByMaster(Some(master), id) => {
+                let master = master.to_lowercase();
+                FindForm::ByMaster(Some(&master), id).form_id(lc_masters)?
+            }
+            _ => search.form_id(lc_masters)?,
+        };
+
+        if self.id_map.contains_key(&form_id) {
+            Some(form_id)
+        } else {
+            None
+        }
+    }
+
+    /// Gets a record by form ID for writing, and journals the modification
+    pub fn get_record_mut(&mut self, search: &FindForm) -> Option<RecordMut<Tes4Record>> {
+        let record = self
+            .id_map
+            .get(&search.form_id(self.masters.iter().map(|(s, _)| s.as_str()))?)?;
+        let mut rbm = record.write().unwrap();
+        if rbm.status() == RecordStatus::Initialized {
+            // FIXME: for now we're just suppressing records that fail to finalize. we
+            //  really ought to return this result to the caller, but I don't feel like
+            //  updating the plugin interface right now.
+            let _ = rbm.finalize();
+        }
+
+        if rbm.status() == RecordStatus::Failed {
+            return None;
+        }
+
+        let mutation = self.journal.as_mut().map(|j| {
+            let editor_id = rbm
+                .iter()
+                .find(|f| f.name() == b"EDID")
+                .and_then(|f| f.get_zstring().ok())
+                .map(String::from);
+            j.push(Mutation::new(MutationKind::Modified, editor_id, record))
+        });
+
+        Some(RecordMut::new(rbm, mutation))
+    }
+
+    /// Gets the editor IDs of all records of the given type, along with their form IDs
+    ///
+    /// The form IDs are relative to this plugin's masters. Records without an editor ID are
+    /// skipped.
+    pub fn editor_ids(&self, record_type: &[u8; 4]) -> Result<Vec<(String, FormId)>, TesError> {
+        let mut ids = vec![];
+        if let Some(group) = self.groups.get(record_type) {
+            for record in group.iter_rc() {
+                let mut rbm = record.write().unwrap();
                 if rbm.status() == RecordStatus::Initialized {
-                    // FIXME: for now we're just suppressing records that fail to finalize. we
-                    //  really ought to return this result to the caller, but I don't feel like
-                    //  updating the plugin interface right now.
-                    let _ = rbm.finalize();
+                    // records are only left undecoded with an index when nothing has changed them
+                    if let Some(editor_id) = self.indexed_editor_ids.get(&rbm.id()) {
+                        ids.push((editor_id.clone(), rbm.id()));
+                        continue;
+                    }
+
+                    rbm.finalize()?;
+                }
+
+                for field in rbm.iter() {
+                    if field.name() == b"EDID" {
+                        ids.push((String::from(field.get_zstring()?), rbm.id()));
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// Iterates through the record types of this plugin's top-level groups
+    ///
+    /// Types are returned in the order their groups are written in: the game's own group order,
+    /// followed by any types the game doesn't define, sorted.
+    pub fn group_types(&self) -> impl Iterator<Item = &[u8; 4]> + '_ {
+        // groups the game doesn't define go at the end so mod-added record types aren't lost
+        let mut extra_labels: Vec<_> = self
+            .groups
+            .keys()
+            .filter(|label| !GROUP_ORDER.contains(label))
+            .collect();
+        extra_labels.sort();
+
+        GROUP_ORDER
+            .iter()
+            .copied()
+            .filter(move |label| self.groups.contains_key(*label))
+            .chain(extra_labels)
+    }
+
+    /// Iterates through all records of the given type
+    ///
+    /// Only records in the top-level group for that type are included, so references and other
+    /// records stored in records' associated groups (such as a cell's children) are not. Records
+    /// are finalized as they're visited, and records that fail to load are skipped.
+    pub fn records_of_type(
+        &self,
+        record_type: &[u8; 4],
+    ) -> impl Iterator<Item = RwLockReadGuard<Tes4Record>> + '_ {
+        self.groups
+            .get(record_type)
+            .into_iter()
+            .flat_map(|g| g.iter_rc())
+            .filter_map(|r| {
+                if r.read().unwrap().status() == RecordStatus::Initialized {
+                    let _ = r.write().unwrap().finalize();
                 }
 
-                match rbm.status() {
+                let rb = r.read().unwrap();
+                match rb.status() {
                     RecordStatus::Failed => None,
-                    _ => Some(rbm),
+                    _ => Some(rb),
                 }
             })
     }
 
+    /// Finds the exterior cell at the given grid coordinates in a worldspace
+    ///
+    /// Returns the cell's form ID relative to this plugin's masters, or `None` if this plugin
+    /// doesn't contain the cell.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a record in the worldspace contains invalid data.
+    pub fn get_exterior_cell(
+        &self,
+        worldspace: &FindForm,
+        grid: GridCoords,
+    ) -> Result<Option<FormId>, TesError> {
+        let worldspace = match worldspace.form_id(self.masters.iter().map(|(s, _)| s.as_str())) {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+        let group = match self.groups.get(b"WRLD") {
+            Some(group) => group,
+            None => return Ok(None),
+        };
+
+        // skip the blocks the cell can't be in
+        let block = (
+            grid.y.div_euclid(CELL_BLOCK_SIZE),
+            grid.x.div_euclid(CELL_BLOCK_SIZE),
+        );
+        let sub_block = (
+            grid.y.div_euclid(CELL_SUB_BLOCK_SIZE),
+            grid.x.div_euclid(CELL_SUB_BLOCK_SIZE),
+        );
+        let enter = |kind: GroupKind| match kind {
+            GroupKind::WorldChildren(id) => id == worldspace.0,
+            GroupKind::ExteriorCellBlock(y, x) => (y as i32, x as i32) == block,
+            GroupKind::ExteriorCellSubBlock(y, x) => (y as i32, x as i32) == sub_block,
+            _ => false,
+        };
+        let matches = |record: &Tes4Record| -> Result<bool, TesError> {
+            if record.name() != b"CELL" {
+                return Ok(false);
+            }
+
+            for field in record.iter() {
+                if field.name() == b"XCLC" {
+                    let mut reader = field.reader();
+                    let x: i32 = reader.read_le()?;
+                    let y: i32 = reader.read_le()?;
+                    return Ok(x == grid.x && y == grid.y);
+                }
+            }
+
+            Ok(false)
+        };
+
+        group.find_record(&enter, &matches)
+    }
+
+    /// Finds records that are flagged as visible when distant but can't be
+    ///
+    /// Returns the records' form IDs, relative to this plugin's masters, in ascending order.
+    pub fn find_invalid_visible_when_distant(&self) -> Vec<FormId> {
+        let mut ids = vec![];
+        for group in self.groups.values() {
+            group.for_each_record(&mut |record| {
+                if record.has_invalid_visible_when_distant() {
+                    ids.push(record.id());
+                }
+            });
+        }
+
+        ids.sort_by_key(|id| id.0);
+        ids
+    }
+
+    /// Calls a function for every record in this plugin, finalizing each record first
+    ///
+    /// # Errors
+    ///
+    /// Fails if a record can't be finalized or if `f` fails.
+    pub(crate) fn try_for_each_record<F>(&self, mut f: F) -> Result<(), TesError>
+    where
+        F: FnMut(&Tes4Record) -> Result<(), TesError>,
+    {
+        for group in self.groups.values() {
+            group.try_for_each_record(&mut f)?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the lowercase name of the master a plugin index refers to
+    ///
+    /// Returns `None` if the index refers to this plugin itself or is out of range.
+    pub fn master_name(&self, index: PluginIndex) -> Option<&str> {
+        self.masters
+            .get(usize::from(index))
+            .map(|(_, lc)| lc.as_str())
+    }
+
     /// Gets a magic effect by magic effect type
     pub fn get_magic_effect(
         &self,
         effect_type: MagicEffectType,
     ) -> Result<Option<MagicEffect>, TesError> {
         if let Some(record) = self.magic_effects.get(&effect_type) {
-            let rb = record.read().unwrap();
+            let rb = Tes4Plugin::read_tracked_record(record)?;
             Ok(Some(MagicEffect::read(&*rb)?))
         } else {
             Ok(None)
@@ -281,9 +860,8 @@ impl Tes4Plugin {
 
     /// Gets a game setting as a float by name
     pub fn get_float_setting(&self, name: &str) -> Result<Option<f32>, TesError> {
-        // GMST records are eagerly finalized, so we don't need to worry about their status here
         if let Some(record) = self.settings.get(name) {
-            for field in record.read().unwrap().iter() {
+            for field in Tes4Plugin::read_tracked_record(record)?.iter() {
                 if field.name() == b"DATA" {
                     return Ok(Some(field.get_f32()?));
                 }
@@ -335,15 +913,13 @@ impl Tes4Plugin {
             Ok(())
         }
     }
-}
 
-impl Plugin for Tes4Plugin {
-    /// Reads a plugin from a binary stream
-    ///
-    /// # Errors
-    ///
-    /// Fails if an I/O error occurs or if the plugin data is invalid.
-    fn read<T: Read + Seek>(mut f: T) -> Result<Tes4Plugin, TesError> {
+    /// Reads a plugin, optionally using and building an index of its records' editor IDs
+    fn read_impl<T: Read + Seek>(
+        mut f: T,
+        index: Option<&RecordIndex>,
+        build_index: bool,
+    ) -> Result<(Tes4Plugin, RecordIndex), TesError> {
         let record = Tes4Record::read(&mut f)?;
         if record.name() != b"TES4" {
             return Err(decode_failed("Not a valid TES4 plugin file"));
@@ -358,11 +934,12 @@ impl Plugin for Tes4Plugin {
                     let data = field.consume();
                     let mut reader = Cursor::new(&data);
                     plugin.version = reader.read_le()?;
-                    reader.seek(SeekFrom::Current(4))?; // number of records and groups; not needed
+                    plugin.num_records = reader.read_le()?;
                     plugin.next_form_id = reader.read_le()?;
                 }
                 b"OFST" => plugin.offsets = Some(field),
                 b"DELE" => plugin.deleted = Some(field),
+                b"ONAM" => plugin.overridden_forms = Some(field),
                 b"CNAM" => plugin.author = Some(String::from(field.get_zstring()?)),
                 b"SNAM" => plugin.description = Some(String::from(field.get_zstring()?)),
                 b"MAST" => {
@@ -390,6 +967,7 @@ impl Plugin for Tes4Plugin {
             )));
         }
 
+        let mut new_index = RecordIndex::new();
         let mut here = f.seek(SeekFrom::Current(0))?;
         let eof = f.seek(SeekFrom::End(0))?;
         f.seek(SeekFrom::Start(here))?;
@@ -397,7 +975,32 @@ impl Plugin for Tes4Plugin {
         while here != eof {
             let group = Group::read(&mut f)?;
             for record in group.iter_rc() {
-                plugin.add_group_record(record)?;
+                let handler = RECORD_REGISTRY.get(record.read().unwrap().name());
+                if let Some(handler) = handler {
+                    let mut rbm = record.write().unwrap();
+                    let record_name = format!("{} {}", rbm.display_name(), rbm.id());
+                    let offset = rbm.span().map(|s| s.offset);
+                    let context = |e| in_record(record_name.as_str(), offset, e);
+                    rbm.finalize().map_err(context)?;
+                    handler.parse(&mut rbm).map_err(context)?;
+                }
+
+                let (offset, record_type) = {
+                    let rb = record.read().unwrap();
+                    (rb.span().map(|s| s.offset), *rb.name())
+                };
+                let indexed_editor_id = match (index, offset) {
+                    (Some(index), Some(offset)) => index.get(offset, &record_type),
+                    _ => None,
+                };
+                let editor_id = plugin.add_indexed_group_record(
+                    record,
+                    indexed_editor_id,
+                    build_index && index.is_none(),
+                )?;
+                if let (Some(editor_id), Some(offset)) = (editor_id, offset) {
+                    new_index.insert(offset, record_type, editor_id);
+                }
             }
 
             if let GroupKind::Top(label) = group.kind() {
@@ -409,7 +1012,31 @@ impl Plugin for Tes4Plugin {
             here = f.seek(SeekFrom::Current(0))?;
         }
 
-        Ok(plugin)
+        Ok((plugin, new_index))
+    }
+}
+
+impl Plugin for Tes4Plugin {
+    /// Reads a plugin from a binary stream
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs or if the plugin data is invalid.
+    fn read<T: Read + Seek>(f: T) -> Result<Tes4Plugin, TesError> {
+        Ok(Tes4Plugin::read_impl(f, None, false)?.0)
+    }
+
+    /// Reads a plugin using an index of the editor IDs of its records
+    ///
+    /// GMST and MGEF records are looked up by editor ID, so they're decoded while the plugin is
+    /// read unless their editor IDs are in the index. Without an index, every record is decoded
+    /// so the returned index has all the plugin's editor IDs; with one, other records are decoded
+    /// lazily and their editor IDs come from the index.
+    fn read_indexed<T: Read + Seek>(
+        f: T,
+        index: Option<&RecordIndex>,
+    ) -> Result<(Tes4Plugin, RecordIndex), TesError> {
+        Tes4Plugin::read_impl(f, index, true)
     }
 
     /// Returns whether this plugin is a master on which other plugins can depend
@@ -428,13 +1055,27 @@ impl Plugin for Tes4Plugin {
 
     fn write<T: Write + Seek>(&self, mut f: T) -> Result<(), TesError> {
         let mut header = Tes4Record::new(b"TES4");
-        let num_records: usize = self.groups.iter().map(|(_, g)| g.len()).sum();
+        header.set_master(self.is_master);
+        let num_records = self.header_record_count();
+        // like the version, a header we're not maintaining is written back exactly as it was read
+        let next_form_id = if self.maintain_header {
+            let next_id = self.next_unused_local_id();
+            if next_id > FormId::LOCAL_ID_MASK {
+                return Err(TesError::RequirementFailed(format!(
+                    "Next form ID {:X} doesn't fit in a form ID",
+                    next_id
+                )));
+            }
+            next_id
+        } else {
+            self.next_form_id
+        };
 
         let mut buf = vec![];
         let mut cursor = Cursor::new(&mut buf);
         cursor.write_le(&self.version)?;
-        cursor.write_le(&(num_records as u32))?;
-        cursor.write_le(&self.next_form_id)?;
+        cursor.write_le(&num_records)?;
+        cursor.write_le(&next_form_id)?;
         header.add_field(Tes4Field::new(b"HEDR", buf)?);
 
         if let Some(ref offsets) = self.offsets {
@@ -458,14 +1099,39 @@ impl Plugin for Tes4Plugin {
             header.add_field(Tes4Field::new_u64(b"DATA", 0));
         }
 
-        header.write(&mut f)?;
+        if !self.maintain_header {
+            if let Some(ref overridden_forms) = self.overridden_forms {
+                header.add_field(overridden_forms.clone());
+            }
+        } else if self.is_master {
+            let overridden_forms = self.overridden_forms();
+            if !overridden_forms.is_empty() {
+                let mut buf = vec![];
+                let mut cursor = Cursor::new(&mut buf);
+                for form_id in overridden_forms {
+                    cursor.write_le(&form_id.0)?;
+                }
+                header.add_field(Tes4Field::new(b"ONAM", buf)?);
+            }
+        }
 
-        for label in GROUP_ORDER.iter().copied() {
-            if let Some(group) = self.groups.get(label) {
-                group.write(&mut f)?;
+        if !RECORD_REGISTRY.is_empty() {
+            for record in self.groups.values().flat_map(|g| g.iter_rc()) {
+                let handler = RECORD_REGISTRY.get(record.read().unwrap().name());
+                if let Some(handler) = handler {
+                    let mut rbm = record.write().unwrap();
+                    rbm.finalize()?;
+                    handler.serialize(&mut rbm)?;
+                }
             }
         }
 
+        header.write(&mut f)?;
+
+        for label in self.group_types() {
+            self.groups[label].write(&mut f)?;
+        }
+
         Ok(())
     }
 }
@@ -507,4 +1173,281 @@ mod tests {
             ["Oblivion.esm", "Knights.esp"]
         );
     }
+
+    #[test]
+    fn read_indexed_plugin() {
+        let (_, index) = Tes4Plugin::read_indexed(Cursor::new(TEST_PLUGIN), None).unwrap();
+        assert!(index
+            .iter()
+            .any(|(_, t, id)| t == b"GMST" && id == "fPotionT1AleDurMult"));
+
+        let (plugin, _) = Tes4Plugin::read_indexed(Cursor::new(TEST_PLUGIN), Some(&index)).unwrap();
+        assert_eq!(
+            plugin.get_float_setting("fPotionT1AleDurMult").unwrap(),
+            Some(-0.01)
+        );
+    }
+
+    #[test]
+    fn index_all_editor_ids() {
+        let mut plugin = Tes4Plugin::new(None, None);
+        let mut record = Tes4Record::new(b"SPEL");
+        record.add_field(
+            Tes4Field::new_zstring(b"EDID", String::from("StandardFireDamage1Novice")).unwrap(),
+        );
+        let form_id = plugin.add_new_record(record).unwrap();
+        plugin.add_new_record(Tes4Record::new(b"SPEL")).unwrap();
+        let mut buf = vec![];
+        plugin.write(&mut Cursor::new(&mut buf)).unwrap();
+
+        let (_, index) = Tes4Plugin::read_indexed(Cursor::new(&buf), None).unwrap();
+        assert_eq!(index.len(), 1);
+        let (_, record_type, editor_id) = index.iter().next().unwrap();
+        assert_eq!(record_type, b"SPEL");
+        assert_eq!(editor_id, "StandardFireDamage1Novice");
+
+        let (plugin, _) = Tes4Plugin::read_indexed(Cursor::new(&buf), Some(&index)).unwrap();
+        assert_eq!(plugin.indexed_editor_ids.len(), 1);
+        assert_eq!(
+            plugin.editor_ids(b"SPEL").unwrap(),
+            [(String::from("StandardFireDamage1Novice"), form_id)]
+        );
+    }
+
+    #[test]
+    fn iterate_records_by_type() {
+        let mut plugin = Tes4Plugin::new(None, None);
+        plugin.add_new_record(Tes4Record::new(b"ZZZZ")).unwrap();
+        plugin.add_new_record(Tes4Record::new(b"MISC")).unwrap();
+        plugin.add_new_record(Tes4Record::new(b"GLOB")).unwrap();
+        plugin.add_new_record(Tes4Record::new(b"GLOB")).unwrap();
+
+        assert_eq!(
+            plugin.group_types().collect::<Vec<_>>(),
+            [b"GLOB", b"MISC", b"ZZZZ"]
+        );
+        assert_eq!(plugin.records_of_type(b"GLOB").count(), 2);
+        assert!(plugin.records_of_type(b"MISC").all(|r| r.name() == b"MISC"));
+        assert_eq!(plugin.records_of_type(b"SPEL").count(), 0);
+    }
+
+    #[test]
+    fn maintain_header() {
+        let mut plugin = Tes4Plugin::new(None, None);
+        let mut record = Tes4Record::new(b"GLOB");
+        record.set_id(FormId(0x1000));
+        plugin.add_record(record).unwrap();
+        plugin.add_new_record(Tes4Record::new(b"GLOB")).unwrap();
+        plugin.add_new_record(Tes4Record::new(b"MISC")).unwrap();
+
+        // two top groups and three records
+        assert_eq!(plugin.record_count(), 5);
+        assert_eq!(plugin.next_unused_form_id(), FormId(0x1001));
+
+        let mut buf = vec![];
+        plugin.write(&mut Cursor::new(&mut buf)).unwrap();
+        let mut plugin = Tes4Plugin::read(Cursor::new(&buf)).unwrap();
+        assert_eq!(plugin.num_records, 5);
+        assert_eq!(plugin.next_form_id, 0x1001);
+
+        plugin.set_maintain_header(false);
+        plugin.add_new_record(Tes4Record::new(b"MISC")).unwrap();
+        let mut buf = vec![];
+        plugin.write(&mut Cursor::new(&mut buf)).unwrap();
+        let plugin = Tes4Plugin::read(Cursor::new(&buf)).unwrap();
+        assert_eq!(plugin.num_records, 5);
+        assert_eq!(plugin.next_form_id, 0x1002);
+    }
+
+    #[test]
+    fn header_fields() {
+        let mut plugin = Tes4Plugin::new(None, None);
+        assert_eq!(plugin.version(), VERSION);
+        assert!(!plugin.has_header_flag(HeaderFlag::Master));
+        assert!(plugin.set_version(0.94).is_err());
+        plugin.set_version(0.8).unwrap();
+        plugin.set_header_flag(HeaderFlag::Master, true);
+        assert!(plugin.is_master());
+
+        let mut buf = vec![];
+        plugin.write(&mut Cursor::new(&mut buf)).unwrap();
+        let mut plugin = Tes4Plugin::read(Cursor::new(&buf)).unwrap();
+        assert_eq!(plugin.version(), 0.8);
+        assert!(plugin.has_header_flag(HeaderFlag::Master));
+        assert_eq!(plugin.header_record_count(), 0);
+        assert_eq!(plugin.header_next_form_id(), FormId(0x800));
+
+        // headers read from a file aren't validated, so they're written back as they were
+        plugin.version = 2.;
+        plugin.next_form_id = 0x01000000;
+        plugin.set_maintain_header(false);
+        let mut buf = vec![];
+        plugin.write(&mut Cursor::new(&mut buf)).unwrap();
+        let mut plugin = Tes4Plugin::read(Cursor::new(&buf)).unwrap();
+        assert_eq!(plugin.version(), 2.);
+        assert_eq!(plugin.next_form_id, 0x01000000);
+
+        // a maintained header recalculates the next form ID from the records
+        plugin.set_maintain_header(true);
+        let mut record = Tes4Record::new(b"GLOB");
+        record.set_id(FormId(0x00001000));
+        plugin.add_record(record).unwrap();
+        let mut buf = vec![];
+        plugin.write(&mut Cursor::new(&mut buf)).unwrap();
+        assert_eq!(
+            Tes4Plugin::read(Cursor::new(&buf)).unwrap().next_form_id,
+            0x1001
+        );
+
+        let mut record = Tes4Record::new(b"GLOB");
+        record.set_id(FormId(0x00ffffff));
+        plugin.add_record(record).unwrap();
+        assert!(plugin.write(&mut Cursor::new(vec![])).is_err());
+    }
+
+    #[test]
+    fn journal_and_revert() {
+        let global = |id: u32, value: f32| {
+            let mut record = Tes4Record::new(b"GLOB");
+            record.set_id(FormId(id));
+            record.add_field(Tes4Field::new_f32(b"FLTV", value));
+            record
+        };
+        let value = |plugin: &Tes4Plugin, id: u32| {
+            let record = plugin.get_record(&FindForm::ByIndex(FormId(id)))?;
+            let value = record.iter().next().unwrap().get_f32().unwrap();
+            Some(value)
+        };
+
+        let mut plugin = Tes4Plugin::new(None, None);
+        plugin.add_record(global(0x1000, 1.)).unwrap();
+        assert!(plugin.journal().is_none());
+
+        plugin.start_journal();
+        plugin.add_record(global(0x1000, 2.)).unwrap();
+        plugin.add_record(global(0x1001, 3.)).unwrap();
+        {
+            let mut record = plugin
+                .get_record_mut(&FindForm::ByIndex(FormId(0x1000)))
+                .unwrap();
+            record.clear();
+            record.add_field(Tes4Field::new_f32(b"FLTV", 5.));
+        }
+        {
+            // a record that's only read through a mutable borrow is never copied
+            let record = plugin
+                .get_record_mut(&FindForm::ByIndex(FormId(0x1001)))
+                .unwrap();
+            assert_eq!(record.name(), b"GLOB");
+        }
+
+        let journal = plugin.journal().unwrap();
+        assert_eq!(
+            journal
+                .iter()
+                .map(|m| (m.kind(), m.is_changed()))
+                .collect::<Vec<_>>(),
+            [
+                (MutationKind::Added, true),
+                (MutationKind::Added, true),
+                (MutationKind::Modified, true),
+                (MutationKind::Modified, false),
+            ]
+        );
+        assert_eq!(value(&plugin, 0x1000), Some(5.));
+
+        assert!(plugin.revert(2));
+        assert_eq!(value(&plugin, 0x1000), Some(2.));
+        assert!(plugin.revert(0));
+        assert_eq!(value(&plugin, 0x1000), Some(1.));
+        assert!(plugin.revert(0));
+        assert_eq!(value(&plugin, 0x1001), None);
+        // the top group and the original record
+        assert_eq!(plugin.record_count(), 2);
+        assert!(!plugin.revert(1));
+
+        let journal = plugin.stop_journal().unwrap();
+        assert_eq!(journal.len(), 1);
+        assert!(!plugin.revert(0));
+    }
+
+    #[test]
+    fn write_overridden_forms() {
+        let mut plugin = Tes4Plugin::new(None, None);
+        plugin
+            .set_masters(vec![String::from("Oblivion.esm")])
+            .unwrap();
+        plugin.set_is_master(true);
+        for (id, persistent) in [(0x0001a2b3, true), (0x00001234, false), (0x01000802, true)] {
+            let mut record = Tes4Record::new(b"REFR");
+            record.set_id(FormId(id));
+            record.set_persistent(persistent);
+            plugin.add_record(record).unwrap();
+        }
+
+        assert_eq!(plugin.overridden_forms(), [FormId(0x0001a2b3)]);
+
+        let mut buf = vec![];
+        plugin.write(&mut Cursor::new(&mut buf)).unwrap();
+        let plugin = Tes4Plugin::read(Cursor::new(&buf)).unwrap();
+        assert!(plugin.is_master());
+        assert_eq!(
+            plugin.overridden_forms.unwrap().get(),
+            0x0001a2b3u32.to_le_bytes()
+        );
+    }
+
+    fn field_names(record: &Tes4Record) -> Vec<String> {
+        record
+            .iter()
+            .map(|f| String::from(f.name_as_str()))
+            .collect()
+    }
+
+    fn assert_round_trip<T>(plugin: &Tes4Plugin)
+    where
+        T: Form<Field = Tes4Field, Record = Tes4Record>,
+    {
+        let group = plugin
+            .groups
+            .get(T::RECORD_TYPE)
+            .expect("Master has no records of this type");
+        for record in group.iter_rc() {
+            let mut record = record.write().unwrap();
+            if record.status() == RecordStatus::Initialized {
+                record.finalize().unwrap();
+            }
+
+            let form = T::read(&record).unwrap();
+            let mut written = Tes4Record::new(T::RECORD_TYPE);
+            form.write(&mut written).unwrap();
+            assert_eq!(
+                field_names(&written),
+                field_names(&record),
+                "Field order of {} {} changed",
+                record.display_name(),
+                record.id()
+            );
+            T::read(&written).unwrap();
+        }
+    }
+
+    /// Round-trips every record of each writable form type in a real Oblivion.esm
+    ///
+    /// Run with `TESUTIL_OBLIVION_ESM=path/to/Oblivion.esm cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn round_trip_vanilla_forms() {
+        let path = std::env::var("TESUTIL_OBLIVION_ESM")
+            .expect("TESUTIL_OBLIVION_ESM must be set to the path of Oblivion.esm");
+        let plugin = Tes4Plugin::load_file(path).unwrap();
+
+        assert_round_trip::<Ammo>(&plugin);
+        assert_round_trip::<Book>(&plugin);
+        assert_round_trip::<Enchantment>(&plugin);
+        assert_round_trip::<MagicEffect>(&plugin);
+        assert_round_trip::<Potion>(&plugin);
+        assert_round_trip::<Spell>(&plugin);
+        assert_round_trip::<Weapon>(&plugin);
+    }
 }
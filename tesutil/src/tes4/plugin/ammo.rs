@@ -1,5 +1,5 @@
 use crate::tes4::{Enchantable, EnchantmentType, FormId, Item, Tes4Field, Tes4Record};
-use crate::{Field, Form, Record, TesError};
+use crate::{ExtraFields, Field, FieldOrder, Form, Record, TesError};
 use binrw::{binrw, BinReaderExt, BinWriterExt};
 use std::io::Cursor;
 
@@ -26,6 +26,8 @@ pub struct Ammo {
     enchantment: Option<FormId>,
     enchantment_points: Option<u16>,
     pub data: AmmoData,
+    extra_fields: Vec<Tes4Field>,
+    field_order: FieldOrder,
 }
 
 impl Enchantable for Ammo {
@@ -129,6 +131,18 @@ impl Item for Ammo {
     }
 }
 
+impl ExtraFields for Ammo {
+    type Field = Tes4Field;
+
+    fn extra_fields(&self) -> &[Tes4Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes4Field> {
+        &mut self.extra_fields
+    }
+}
+
 impl Form for Ammo {
     type Field = Tes4Field;
     type Record = Tes4Record;
@@ -147,12 +161,16 @@ impl Form for Ammo {
             }
         }
 
+        ammo.field_order = FieldOrder::from_record(record);
+
         Ok(ammo)
     }
 
     fn write(&self, record: &mut Self::Record) -> Result<(), TesError> {
         Ammo::assert(record)?;
 
+        record.clear();
+
         self.write_item_fields(
             record,
             &[b"EDID", b"FULL", b"MODL", b"MODB", b"MODT", b"ICON"],
@@ -170,6 +188,11 @@ impl Form for Ammo {
         cursor.write_le(&self.data)?;
         record.add_field(Tes4Field::new(b"DATA", buf)?);
 
+        for field in &self.extra_fields {
+            record.add_field(field.clone());
+        }
+        record.sort_fields(&self.field_order);
+
         Ok(())
     }
 }
@@ -0,0 +1,232 @@
+use crate::tes4::{BipedData, Enchantable, EnchantmentType, FormId, Item, Tes4Field, Tes4Record};
+use crate::{ExtraFields, Field, FieldOrder, Form, Record, TesError};
+use binrw::{binrw, BinReaderExt, BinWriterExt};
+use std::io::Cursor;
+
+#[binrw]
+#[derive(Debug, Default)]
+pub struct ArmorData {
+    /// Armor rating times 100
+    pub armor_rating: u16,
+    pub value: u32,
+    pub health: u32,
+    pub weight: f32,
+}
+
+/// A piece of armor
+///
+/// The male biped model is the item's main model. The male ground model, the female models, and
+/// the female icon are kept as extra fields.
+#[derive(Debug, Default)]
+pub struct Armor {
+    editor_id: String,
+    name: String,
+    script: Option<FormId>,
+    enchantment: Option<FormId>,
+    enchantment_points: Option<u16>,
+    pub biped: BipedData,
+    model: Option<String>,
+    bound_radius: Option<f32>,
+    texture_hash: Option<Vec<u8>>,
+    icon: Option<String>,
+    pub data: ArmorData,
+    extra_fields: Vec<Tes4Field>,
+    field_order: FieldOrder,
+}
+
+impl Item for Armor {
+    fn editor_id(&self) -> &str {
+        self.editor_id.as_str()
+    }
+
+    fn set_editor_id(&mut self, id: String) {
+        self.editor_id = id;
+    }
+
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    fn value(&self) -> u32 {
+        self.data.value
+    }
+
+    fn set_value(&mut self, value: u32) {
+        self.data.value = value;
+    }
+
+    fn weight(&self) -> f32 {
+        self.data.weight
+    }
+
+    fn set_weight(&mut self, weight: f32) {
+        self.data.weight = weight;
+    }
+
+    fn script(&self) -> Option<FormId> {
+        self.script
+    }
+
+    fn set_script(&mut self, script: Option<FormId>) {
+        self.script = script;
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    fn set_model(&mut self, model: Option<String>) {
+        self.model = model;
+    }
+
+    fn icon(&self) -> Option<&str> {
+        self.icon.as_deref()
+    }
+
+    fn set_icon(&mut self, icon: Option<String>) {
+        self.icon = icon;
+    }
+
+    fn bound_radius(&self) -> Option<f32> {
+        self.bound_radius
+    }
+
+    fn set_bound_radius(&mut self, bound_radius: Option<f32>) {
+        self.bound_radius = bound_radius;
+    }
+
+    fn texture_hash(&self) -> Option<&[u8]> {
+        self.texture_hash.as_deref()
+    }
+
+    fn set_texture_hash(&mut self, texture_hash: Option<Vec<u8>>) {
+        self.texture_hash = texture_hash;
+    }
+}
+
+impl Enchantable for Armor {
+    fn enchantment(&self) -> Option<FormId> {
+        self.enchantment
+    }
+
+    fn set_enchantment(&mut self, enchantment: Option<FormId>) {
+        self.enchantment = enchantment;
+        if self.enchantment_points.is_none() {
+            self.enchantment_points = Some(0);
+        }
+    }
+
+    fn enchantment_points(&self) -> Option<u32> {
+        self.enchantment_points.map(u32::from)
+    }
+
+    /// Sets the enchantment points
+    ///
+    /// Armor records only have room for 16-bit enchantment points, so larger values are
+    /// clamped to `u16::MAX`.
+    fn set_enchantment_points(&mut self, enchantment_points: Option<u32>) {
+        self.enchantment_points = enchantment_points.map(|v| u16::try_from(v).unwrap_or(u16::MAX));
+    }
+
+    fn enchantment_type(&self) -> EnchantmentType {
+        EnchantmentType::Apparel
+    }
+}
+
+impl ExtraFields for Armor {
+    type Field = Tes4Field;
+
+    fn extra_fields(&self) -> &[Tes4Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes4Field> {
+        &mut self.extra_fields
+    }
+}
+
+impl Form for Armor {
+    type Field = Tes4Field;
+    type Record = Tes4Record;
+    const RECORD_TYPE: &'static [u8; 4] = b"ARMO";
+
+    fn read(record: &Self::Record) -> Result<Self, TesError> {
+        Armor::assert(record)?;
+
+        let mut armor = Armor::default();
+        for field in record.iter() {
+            match field.name() {
+                b"ENAM" => armor.enchantment = Some(FormId(field.get_u32()?)),
+                b"ANAM" => armor.enchantment_points = Some(field.get_u16()?),
+                b"BMDT" => armor.biped = field.reader().read_le()?,
+                b"DATA" => armor.data = field.reader().read_le()?,
+                _ => armor.read_item_field(field)?,
+            }
+        }
+
+        armor.field_order = FieldOrder::from_record(record);
+
+        Ok(armor)
+    }
+
+    fn write(&self, record: &mut Self::Record) -> Result<(), TesError> {
+        Armor::assert(record)?;
+
+        record.clear();
+
+        self.write_item_fields(record, &[b"EDID", b"FULL", b"SCRI"])?;
+        if let Some(enchantment_id) = self.enchantment {
+            record.add_field(Tes4Field::new_u32(b"ENAM", enchantment_id.0));
+        }
+        if let Some(enchantment_points) = self.enchantment_points {
+            record.add_field(Tes4Field::new_u16(b"ANAM", enchantment_points));
+        }
+
+        let mut buf = vec![];
+        let mut cursor = Cursor::new(&mut buf);
+        cursor.write_le(&self.biped)?;
+        record.add_field(Tes4Field::new(b"BMDT", buf)?);
+
+        self.write_item_fields(record, &[b"MODL", b"MODB", b"MODT", b"ICON"])?;
+
+        let mut buf = vec![];
+        let mut cursor = Cursor::new(&mut buf);
+        cursor.write_le(&self.data)?;
+        record.add_field(Tes4Field::new(b"DATA", buf)?);
+
+        for field in &self.extra_fields {
+            record.add_field(field.clone());
+        }
+        record.sort_fields(&self.field_order);
+
+        Ok(())
+    }
+}
+
+impl Armor {
+    pub fn new(editor_id: String, name: String) -> Armor {
+        Armor {
+            editor_id,
+            name,
+            ..Armor::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamp_enchantment_points() {
+        let mut armor = Armor::new(String::from("ArmorTest"), String::from("Test"));
+        armor.set_enchantment_points(Some(1200));
+        assert_eq!(armor.enchantment_points(), Some(1200));
+        armor.set_enchantment_points(Some(100_000));
+        assert_eq!(armor.enchantment_points(), Some(u32::from(u16::MAX)));
+    }
+}
@@ -1,5 +1,5 @@
 use crate::tes4::{Enchantable, EnchantmentType, FormId, Item, Skill, Tes4Field, Tes4Record};
-use crate::{Field, Form, Record, TesError};
+use crate::{ExtraFields, Field, FieldOrder, Form, Record, TesError};
 use binrw::{binrw, BinReaderExt, BinWriterExt};
 use bitflags::bitflags;
 use std::io::Cursor;
@@ -38,6 +38,8 @@ pub struct Book {
     enchantment_points: Option<u16>,
     enchantment: Option<FormId>,
     pub data: BookData,
+    extra_fields: Vec<Tes4Field>,
+    field_order: FieldOrder,
 }
 
 impl Enchantable for Book {
@@ -136,6 +138,18 @@ impl Item for Book {
     }
 }
 
+impl ExtraFields for Book {
+    type Field = Tes4Field;
+
+    fn extra_fields(&self) -> &[Tes4Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes4Field> {
+        &mut self.extra_fields
+    }
+}
+
 impl Form for Book {
     type Field = Tes4Field;
     type Record = Tes4Record;
@@ -155,12 +169,16 @@ impl Form for Book {
             }
         }
 
+        book.field_order = FieldOrder::from_record(record);
+
         Ok(book)
     }
 
     fn write(&self, record: &mut Self::Record) -> Result<(), TesError> {
         Book::assert(record)?;
 
+        record.clear();
+
         self.write_item_fields(
             record,
             &[
@@ -182,6 +200,11 @@ impl Form for Book {
         cursor.write_le(&self.data)?;
         record.add_field(Tes4Field::new(b"DATA", buf)?);
 
+        for field in &self.extra_fields {
+            record.add_field(field.clone());
+        }
+        record.sort_fields(&self.field_order);
+
         Ok(())
     }
 }
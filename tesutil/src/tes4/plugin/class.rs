@@ -110,8 +110,53 @@ impl Form for Class {
         Ok(class)
     }
 
-    fn write(&self, _: &mut Self::Record) -> Result<(), TesError> {
-        unimplemented!()
+    /// Writes class data to a raw record
+    ///
+    /// # Errors
+    ///
+    /// Fails if the provided record is not a `b"CLAS"` record or if a string field is too long.
+    fn write(&self, record: &mut Self::Record) -> Result<(), TesError> {
+        Class::assert(record)?;
+
+        record.clear();
+
+        if let Some(ref editor_id) = self.editor_id {
+            record.add_field(Tes4Field::new_zstring(b"EDID", editor_id.clone())?);
+        }
+
+        record.add_field(Tes4Field::new_zstring(b"FULL", self.name.clone())?);
+
+        if let Some(ref description) = self.description {
+            record.add_field(Tes4Field::new_zstring(b"DESC", description.clone())?);
+        }
+
+        if let Some(ref icon) = self.icon {
+            record.add_field(Tes4Field::new_zstring(b"ICON", icon.clone())?);
+        }
+
+        let mut buf = Vec::with_capacity(60);
+        let mut writer = Cursor::new(&mut buf);
+        for attribute in self.primary_attributes.iter() {
+            let av: u8 = ActorValue::from(*attribute).into();
+            writer.write_le(&(av as u32))?;
+        }
+
+        writer.write_le(&(self.specialization as u32))?;
+
+        for skill in self.major_skills.iter() {
+            let av: u8 = ActorValue::from(*skill).into();
+            writer.write_le(&(av as u32))?;
+        }
+
+        writer.write_le(&self.flags().bits)?;
+        writer.write_le(&self.services.bits)?;
+        writer.write_le(&(self.skill_trained as u8))?;
+        writer.write_le(&self.max_training_level)?;
+        writer.write_le(&0u16)?;
+
+        record.add_field(Tes4Field::new(b"DATA", buf)?);
+
+        Ok(())
     }
 }
 
@@ -136,6 +181,21 @@ impl Class {
         })
     }
 
+    /// Starts building a new class
+    pub fn builder(name: String) -> ClassBuilder {
+        ClassBuilder {
+            name,
+            editor_id: None,
+            description: None,
+            icon: None,
+            primary_attributes: vec![],
+            specialization: Specialization::Combat,
+            major_skills: vec![],
+            is_playable: false,
+            is_guard: false,
+        }
+    }
+
     //noinspection RsTypeCheck
     /// Reads a custom class from a binary stream
     ///
@@ -190,6 +250,28 @@ impl Class {
         })
     }
 
+    /// Gets the class's editor ID, which custom classes don't have
+    pub fn editor_id(&self) -> Option<&str> {
+        self.editor_id.as_deref()
+    }
+
+    /// Sets the class's editor ID
+    pub fn set_editor_id(&mut self, editor_id: Option<String>) {
+        self.editor_id = editor_id;
+    }
+
+    fn flags(&self) -> ClassFlags {
+        let mut class_flags = ClassFlags::empty();
+        if self.is_playable {
+            class_flags |= ClassFlags::PLAYABLE;
+        }
+        if self.is_guard {
+            class_flags |= ClassFlags::GUARD;
+        }
+
+        class_flags
+    }
+
     /// Gets the primary attributes of this class
     pub fn primary_attribute(&self) -> &[Attribute; 2] {
         &self.primary_attributes
@@ -250,6 +332,35 @@ impl Class {
         Ok(())
     }
 
+    /// Gets the skill members of this class train the player in and the level they train it to,
+    /// if they offer training
+    pub fn training(&self) -> Option<(Skill, u8)> {
+        if self.services.contains(ServiceFlags::TRAINING) {
+            Some((self.skill_trained, self.max_training_level))
+        } else {
+            None
+        }
+    }
+
+    /// Sets the skill members of this class train the player in and the level they train it to
+    ///
+    /// Passing `None` stops the class from offering training and zeroes the training fields, which
+    /// is how the game stores custom classes.
+    pub fn set_training(&mut self, training: Option<(Skill, u8)>) {
+        match training {
+            Some((skill, max_level)) => {
+                self.services |= ServiceFlags::TRAINING;
+                self.skill_trained = skill;
+                self.max_training_level = max_level;
+            }
+            None => {
+                self.services -= ServiceFlags::TRAINING;
+                self.skill_trained = Skill::Armorer;
+                self.max_training_level = 0;
+            }
+        }
+    }
+
     /// Checks whether a given skill is a major skill for this class
     pub fn is_major_skill(&self, skill: Skill) -> bool {
         self.major_skills.iter().any(|s| *s == skill)
@@ -272,15 +383,7 @@ impl Class {
             f.write_le(&(<ActorValue as Enum>::into_usize(av) as u32))?;
         }
 
-        let mut class_flags = ClassFlags::empty();
-        if self.is_playable {
-            class_flags |= ClassFlags::PLAYABLE;
-        }
-        if self.is_guard {
-            class_flags |= ClassFlags::GUARD;
-        }
-
-        f.write_le(&class_flags.bits)?;
+        f.write_le(&self.flags().bits)?;
         f.write_le(&self.services.bits)?;
         f.write_le(&(self.skill_trained as u8))?;
         f.write_le(&self.max_training_level)?;
@@ -298,6 +401,96 @@ impl Class {
     }
 }
 
+/// Builds a class, validating it once all its settings have been provided
+///
+/// Unlike [`Class::new`], which fills in placeholder attributes and skills, a built class must be
+/// given its primary attributes and major skills explicitly.
+///
+/// [`Class::new`]: struct.Class.html#method.new
+#[derive(Debug)]
+pub struct ClassBuilder {
+    name: String,
+    editor_id: Option<String>,
+    description: Option<String>,
+    icon: Option<String>,
+    primary_attributes: Vec<Attribute>,
+    specialization: Specialization,
+    major_skills: Vec<Skill>,
+    is_playable: bool,
+    is_guard: bool,
+}
+
+impl ClassBuilder {
+    /// Sets the class's editor ID
+    ///
+    /// Classes in plugins need an editor ID; only custom classes in saves go without.
+    pub fn editor_id(mut self, editor_id: String) -> ClassBuilder {
+        self.editor_id = Some(editor_id);
+        self
+    }
+
+    /// Sets the class's description
+    pub fn description(mut self, description: String) -> ClassBuilder {
+        self.description = Some(description);
+        self
+    }
+
+    /// Sets the class's icon
+    pub fn icon(mut self, icon: String) -> ClassBuilder {
+        self.icon = Some(icon);
+        self
+    }
+
+    /// Sets the class's primary attributes
+    pub fn primary_attributes(mut self, attributes: &[Attribute]) -> ClassBuilder {
+        self.primary_attributes = attributes.to_vec();
+        self
+    }
+
+    /// Sets the class's specialization
+    pub fn specialization(mut self, specialization: Specialization) -> ClassBuilder {
+        self.specialization = specialization;
+        self
+    }
+
+    /// Sets the class's major skills
+    pub fn major_skills(mut self, skills: &[Skill]) -> ClassBuilder {
+        self.major_skills = skills.to_vec();
+        self
+    }
+
+    /// Sets whether the player can choose the class
+    pub fn playable(mut self, value: bool) -> ClassBuilder {
+        self.is_playable = value;
+        self
+    }
+
+    /// Sets whether the class is a guard class
+    pub fn guard(mut self, value: bool) -> ClassBuilder {
+        self.is_guard = value;
+        self
+    }
+
+    /// Builds the class
+    ///
+    /// # Errors
+    ///
+    /// Fails if the name is too long or if the class doesn't have exactly 2 unique primary
+    /// attributes and 7 unique major skills.
+    pub fn build(self) -> Result<Class, TesError> {
+        let mut class = Class::new(self.name)?;
+        class.editor_id = self.editor_id;
+        class.description = self.description;
+        class.icon = self.icon;
+        class.set_primary_attributes(&self.primary_attributes)?;
+        class.specialization = self.specialization;
+        class.set_major_skills(&self.major_skills)?;
+        class.is_playable = self.is_playable;
+        class.is_guard = self.is_guard;
+        Ok(class)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -316,4 +509,73 @@ mod tests {
         assert_eq!(class.description.unwrap(), "");
         assert!(class.icon.is_none());
     }
+
+    #[test]
+    fn test_write() {
+        let mut record_ref = CLASS_RECORD.as_ref();
+        let cursor = Cursor::new(&mut record_ref);
+        let record = Tes4Record::read(cursor).unwrap();
+        let class = Class::read(&record).unwrap();
+
+        let mut new_record = Tes4Record::new(b"CLAS");
+        class.write(&mut new_record).unwrap();
+        let new_class = Class::read(&new_record).unwrap();
+        assert_eq!(new_class.editor_id(), Some("SE32Smith"));
+        assert_eq!(new_class.name, class.name);
+        assert_eq!(new_class.primary_attributes, class.primary_attributes);
+        assert_eq!(new_class.specialization, class.specialization);
+        assert_eq!(new_class.major_skills, class.major_skills);
+        assert_eq!(new_class.services, class.services);
+        assert_eq!(new_class.skill_trained, class.skill_trained);
+        assert_eq!(new_class.max_training_level, class.max_training_level);
+    }
+
+    #[test]
+    fn set_training() {
+        let mut class = Class::new(String::from("Smith")).unwrap();
+        assert_eq!(class.training(), None);
+
+        class.set_training(Some((Skill::Armorer, 50)));
+        assert_eq!(class.training(), Some((Skill::Armorer, 50)));
+        assert!(class.services.contains(ServiceFlags::TRAINING));
+
+        class.set_training(None);
+        assert_eq!(class.training(), None);
+        assert_eq!(class.max_training_level, 0);
+    }
+    #[test]
+    fn build_class() {
+        let skills = [
+            Skill::Alchemy,
+            Skill::Alteration,
+            Skill::Conjuration,
+            Skill::Destruction,
+            Skill::Illusion,
+            Skill::Mysticism,
+            Skill::Restoration,
+        ];
+        let class = Class::builder(String::from("Archmage"))
+            .editor_id(String::from("tcArchmage"))
+            .primary_attributes(&[Attribute::Intelligence, Attribute::Willpower])
+            .specialization(Specialization::Magic)
+            .major_skills(&skills)
+            .playable(true)
+            .build()
+            .unwrap();
+        assert_eq!(class.editor_id(), Some("tcArchmage"));
+        assert!(class.is_major_skill(Skill::Mysticism));
+        assert_eq!(class.flags(), ClassFlags::PLAYABLE);
+
+        let result = Class::builder(String::from("Archmage"))
+            .primary_attributes(&[Attribute::Intelligence, Attribute::Intelligence])
+            .major_skills(&skills)
+            .build();
+        assert!(matches!(result, Err(TesError::RequirementFailed(_))));
+
+        let result = Class::builder(String::from("Archmage"))
+            .primary_attributes(&[Attribute::Intelligence, Attribute::Willpower])
+            .major_skills(&skills[..5])
+            .build();
+        assert!(matches!(result, Err(TesError::OutOfRange { .. })));
+    }
 }
@@ -1,5 +1,5 @@
-use crate::tes4::{Magic, SpellEffect, Tes4Field, Tes4Record};
-use crate::{Field, Form, Record, TesError};
+use crate::tes4::{effect_index_error, Magic, SpellEffect, Tes4Field, Tes4Record};
+use crate::{ExtraFields, Field, FieldOrder, Form, Record, TesError};
 use binrw::{binrw, BinReaderExt, BinWriterExt};
 use std::io::Cursor;
 
@@ -32,6 +32,8 @@ pub struct Enchantment {
     name: Option<String>,
     pub data: EnchantmentData,
     effects: Vec<SpellEffect>,
+    extra_fields: Vec<Tes4Field>,
+    field_order: FieldOrder,
 }
 
 impl Magic for Enchantment {
@@ -47,6 +49,23 @@ impl Magic for Enchantment {
         self.effects.push(effect);
     }
 
+    fn insert_effect(&mut self, index: usize, effect: SpellEffect) -> Result<(), TesError> {
+        if index > self.effects.len() {
+            return Err(effect_index_error(index, self.effects.len()));
+        }
+
+        self.effects.insert(index, effect);
+        Ok(())
+    }
+
+    fn remove_effect(&mut self, index: usize) -> Option<SpellEffect> {
+        if index < self.effects.len() {
+            Some(self.effects.remove(index))
+        } else {
+            None
+        }
+    }
+
     fn name(&self) -> Option<&str> {
         self.name.as_deref()
     }
@@ -56,6 +75,18 @@ impl Magic for Enchantment {
     }
 }
 
+impl ExtraFields for Enchantment {
+    type Field = Tes4Field;
+
+    fn extra_fields(&self) -> &[Tes4Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes4Field> {
+        &mut self.extra_fields
+    }
+}
+
 impl Form for Enchantment {
     type Field = Tes4Field;
     type Record = Tes4Record;
@@ -73,6 +104,8 @@ impl Form for Enchantment {
             }
         }
 
+        enchantment.field_order = FieldOrder::from_record(record);
+
         Ok(enchantment)
     }
 
@@ -93,6 +126,11 @@ impl Form for Enchantment {
 
         self.write_magic_effects(record)?;
 
+        for field in &self.extra_fields {
+            record.add_field(field.clone());
+        }
+        record.sort_fields(&self.field_order);
+
         Ok(())
     }
 }
@@ -2,6 +2,7 @@ use std::io::{Read, Seek, SeekFrom, Write};
 use std::sync::{Arc, RwLock};
 
 use super::record::Tes4Record;
+use crate::tes4::FormId;
 use crate::*;
 
 /// Indicates the type of group a group is
@@ -132,6 +133,109 @@ impl GroupKind {
             _ => vec![], // remaining group kinds can only contain other groups
         }
     }
+
+    /// Gets the numeric group type
+    fn code(&self) -> u32 {
+        match self {
+            GroupKind::Top(_) => 0,
+            GroupKind::WorldChildren(_) => 1,
+            GroupKind::InteriorCellBlock(_) => 2,
+            GroupKind::InteriorCellSubBlock(_) => 3,
+            GroupKind::ExteriorCellBlock(_, _) => 4,
+            GroupKind::ExteriorCellSubBlock(_, _) => 5,
+            GroupKind::CellChildren(_) => 6,
+            GroupKind::TopicChildren(_) => 7,
+            GroupKind::CellPersistentChildren(_) => 8,
+            GroupKind::CellTemporaryChildren(_) => 9,
+            GroupKind::CellVisibleDistantChildren(_) => 10,
+        }
+    }
+
+    /// Is this one of the groups that holds a cell's references?
+    fn is_cell_reference_group(&self) -> bool {
+        matches!(
+            self,
+            GroupKind::CellPersistentChildren(_)
+                | GroupKind::CellTemporaryChildren(_)
+                | GroupKind::CellVisibleDistantChildren(_)
+        )
+    }
+
+    /// Determines which of a cell's children groups a record belongs in
+    ///
+    /// Persistent references go in the persistent group regardless of their other flags.
+    /// Non-persistent references that are visible when distant go in the visible distant group, and
+    /// everything else, including the cell's landscape and path grid, goes in the temporary group.
+    pub fn for_cell_child(cell: u32, record: &Tes4Record) -> GroupKind {
+        let is_reference = matches!(record.name(), b"REFR" | b"ACHR" | b"ACRE");
+        if is_reference && record.is_persistent() {
+            GroupKind::CellPersistentChildren(cell)
+        } else if is_reference && record.is_visible_when_distant() {
+            GroupKind::CellVisibleDistantChildren(cell)
+        } else {
+            GroupKind::CellTemporaryChildren(cell)
+        }
+    }
+}
+
+/// A group of a cell's references with its stamp
+type ReferenceGroup<'a> = (GroupKind, u32, Vec<&'a Arc<RwLock<Tes4Record>>>);
+
+/// A cell's children, sorted into the groups they should be written in
+struct CellChildrenLayout<'a> {
+    /// Persistent, temporary, and visible distant groups, in that order, with their stamps
+    references: [ReferenceGroup<'a>; 3],
+    others: Vec<&'a Group>,
+}
+
+impl<'a> CellChildrenLayout<'a> {
+    fn new(cell: u32, group: &'a Group) -> CellChildrenLayout<'a> {
+        let mut references = [
+            (GroupKind::CellPersistentChildren(cell), group.stamp, vec![]),
+            (GroupKind::CellTemporaryChildren(cell), group.stamp, vec![]),
+            (
+                GroupKind::CellVisibleDistantChildren(cell),
+                group.stamp,
+                vec![],
+            ),
+        ];
+        let mut others = vec![];
+
+        let mut records: Vec<_> = group.records.iter().collect();
+        for child in &group.groups {
+            if child.kind.is_cell_reference_group() {
+                // keep the stamps of groups that already existed
+                CellChildrenLayout::entry(&mut references, child.kind).1 = child.stamp;
+                records.extend(child.records.iter());
+            } else {
+                others.push(child);
+            }
+        }
+
+        for record in records {
+            let kind = GroupKind::for_cell_child(cell, &record.read().unwrap());
+            CellChildrenLayout::entry(&mut references, kind)
+                .2
+                .push(record);
+        }
+
+        CellChildrenLayout { references, others }
+    }
+
+    fn entry<'b>(
+        references: &'b mut [ReferenceGroup<'a>; 3],
+        kind: GroupKind,
+    ) -> &'b mut ReferenceGroup<'a> {
+        references
+            .iter_mut()
+            .find(|(k, _, _)| k.code() == kind.code())
+            .unwrap()
+    }
+
+    /// Non-empty reference groups
+    fn reference_groups(&self) -> impl Iterator<Item = &ReferenceGroup<'a>> + '_ {
+        self.references.iter().filter(|(_, _, r)| !r.is_empty())
+    }
 }
 
 /// A group of records
@@ -225,7 +329,26 @@ impl Group {
     }
 
     /// Add a record to this group
+    ///
+    /// Records added to a cell children group are placed in the persistent, temporary, or visible
+    /// distant subgroup as appropriate for the record's flags, creating the subgroup if necessary.
     pub fn add_record(&mut self, record: Tes4Record) -> Result<Arc<RwLock<Tes4Record>>, TesError> {
+        if let GroupKind::CellChildren(cell) = self.kind {
+            let kind = GroupKind::for_cell_child(cell, &record);
+            let index = match self
+                .groups
+                .iter()
+                .position(|g| g.kind.code() == kind.code())
+            {
+                Some(index) => index,
+                None => {
+                    self.groups.push(Group::new(kind));
+                    self.groups.len() - 1
+                }
+            };
+            return self.groups[index].add_record(record);
+        }
+
         // ensure the record is appropriate for this group type
         if !self.kind.acceptable_records().contains(&record.name()) {
             Err(TesError::RequirementFailed(format!(
@@ -239,6 +362,16 @@ impl Group {
         }
     }
 
+    /// Removes a record from this group or its subgroups, returning whether it was found
+    pub(crate) fn remove_record(&mut self, record: &Arc<RwLock<Tes4Record>>) -> bool {
+        if let Some(index) = self.records.iter().position(|r| Arc::ptr_eq(r, record)) {
+            self.records.remove(index);
+            return true;
+        }
+
+        self.groups.iter_mut().any(|g| g.remove_record(record))
+    }
+
     /// Returns an iterator over Rc smart pointers to this group's records
     // need Box because the iterator is recursive
     pub fn iter_rc(&self) -> Box<dyn Iterator<Item = Arc<RwLock<Tes4Record>>> + '_> {
@@ -250,6 +383,93 @@ impl Group {
         )
     }
 
+    /// Searches this group's hierarchy for a record, returning the first match's form ID
+    ///
+    /// The search descends into subgroups, including records' associated groups, but only those for
+    /// which `enter` returns true. Records are finalized as they're visited.
+    pub(crate) fn find_record<G, R>(
+        &self,
+        enter: &G,
+        matches: &R,
+    ) -> Result<Option<FormId>, TesError>
+    where
+        G: Fn(GroupKind) -> bool,
+        R: Fn(&Tes4Record) -> Result<bool, TesError>,
+    {
+        for record in &self.records {
+            let mut rbm = record.write().unwrap();
+            if rbm.status() == RecordStatus::Initialized {
+                rbm.finalize()?;
+            }
+
+            if matches(&rbm)? {
+                return Ok(Some(rbm.id()));
+            }
+
+            for group in rbm.groups().iter().filter(|g| enter(g.kind)) {
+                if let Some(id) = group.find_record(enter, matches)? {
+                    return Ok(Some(id));
+                }
+            }
+        }
+
+        for group in self.groups.iter().filter(|g| enter(g.kind)) {
+            if let Some(id) = group.find_record(enter, matches)? {
+                return Ok(Some(id));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Calls a function for every record in this group's hierarchy
+    ///
+    /// Unlike [`iter_rc`], this includes the records in records' associated groups, such as the
+    /// references in a cell's children. Records are not finalized.
+    ///
+    /// [`iter_rc`]: #method.iter_rc
+    pub(crate) fn for_each_record<F: FnMut(&Tes4Record)>(&self, f: &mut F) {
+        for record in &self.records {
+            let rb = record.read().unwrap();
+            f(&rb);
+            for group in rb.groups() {
+                group.for_each_record(f);
+            }
+        }
+
+        for group in &self.groups {
+            group.for_each_record(f);
+        }
+    }
+
+    /// Calls a function for every record in this group's hierarchy, finalizing each record first
+    ///
+    /// # Errors
+    ///
+    /// Fails if a record can't be finalized or if `f` fails.
+    pub(crate) fn try_for_each_record<F>(&self, f: &mut F) -> Result<(), TesError>
+    where
+        F: FnMut(&Tes4Record) -> Result<(), TesError>,
+    {
+        for record in &self.records {
+            let mut rbm = record.write().unwrap();
+            if rbm.status() == RecordStatus::Initialized {
+                rbm.finalize()?;
+            }
+
+            f(&rbm)?;
+            for group in rbm.groups() {
+                group.try_for_each_record(f)?;
+            }
+        }
+
+        for group in &self.groups {
+            group.try_for_each_record(f)?;
+        }
+
+        Ok(())
+    }
+
     /// Gets this group's [`GroupKind`]
     ///
     /// [`GroupKind`]: enum.GroupKind.html
@@ -267,21 +487,59 @@ impl Group {
     //  creates an infinite number of versions of this method each with an extra `&mut` at the
     //  beginning, which fails to compile. With `f: &mut T`, we can pass group.write(&mut *f), which
     //  ensures each recursive call sees the same type for T. Is there a better way?
+    //
+    // A cell's references are re-sorted into the persistent, temporary, and visible distant
+    // subgroups when the cell's children are written, so that records whose flags have changed end
+    // up in the right place.
     pub fn write<T: Write + Seek>(&self, f: &mut T) -> Result<(), TesError> {
+        Group::write_with(f, self.kind, self.stamp, |f| {
+            if let GroupKind::CellChildren(cell) = self.kind {
+                let layout = CellChildrenLayout::new(cell, self);
+                for (kind, stamp, records) in layout.reference_groups() {
+                    Group::write_with(f, *kind, *stamp, |f| {
+                        for record in records {
+                            record.read().unwrap().write(&mut *f)?;
+                        }
+                        Ok(())
+                    })?;
+                }
+
+                for group in layout.others {
+                    group.write(&mut *f)?;
+                }
+            } else {
+                for record in &self.records {
+                    record.read().unwrap().write(&mut *f)?;
+                }
+
+                for group in &self.groups {
+                    group.write(&mut *f)?;
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Writes a group header, then the group's contents using the provided function
+    fn write_with<T, W>(
+        f: &mut T,
+        kind: GroupKind,
+        stamp: u32,
+        write_body: W,
+    ) -> Result<(), TesError>
+    where
+        T: Write + Seek,
+        W: FnOnce(&mut T) -> Result<(), TesError>,
+    {
         f.write_all(b"GRUP")?;
 
         let len_offset = f.seek(SeekFrom::Current(0))?;
         f.write_le(&0u32)?;
-        self.kind.write(&mut *f)?;
-        f.write_le(&self.stamp)?;
-
-        for record in &self.records {
-            record.read().unwrap().write(&mut *f)?;
-        }
+        kind.write(&mut *f)?;
+        f.write_le(&stamp)?;
 
-        for group in &self.groups {
-            group.write(&mut *f)?;
-        }
+        write_body(&mut *f)?;
 
         let end_offset = f.seek(SeekFrom::Current(0))?;
         // write the group size now that we know how much we wrote
@@ -295,7 +553,72 @@ impl Group {
     }
 
     /// Number of records in this group, including the group record itself
+    ///
+    /// Subgroups and records' associated groups are included, with each group counted as a record,
+    /// which is how the plugin header counts them.
     pub fn len(&self) -> usize {
-        1 + self.records.len() + self.groups.iter().map(|g| g.len()).sum::<usize>()
+        if let GroupKind::CellChildren(cell) = self.kind {
+            // count the groups as they'll be written
+            let layout = CellChildrenLayout::new(cell, self);
+            return 1
+                + layout
+                    .reference_groups()
+                    .map(|(_, _, r)| 1 + r.len())
+                    .sum::<usize>()
+                + layout.others.iter().map(|g| g.len()).sum::<usize>();
+        }
+
+        let mut count = 1;
+        for record in &self.records {
+            // records' associated groups are written right after them, so they count too
+            count += 1 + record
+                .read()
+                .unwrap()
+                .groups()
+                .iter()
+                .map(|g| g.len())
+                .sum::<usize>();
+        }
+
+        count + self.groups.iter().map(|g| g.len()).sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn reference(persistent: bool, visible_when_distant: bool) -> Tes4Record {
+        let mut record = Tes4Record::new(b"REFR");
+        record.set_persistent(persistent);
+        record.set_visible_when_distant(visible_when_distant);
+        record
+    }
+
+    #[test]
+    fn classify_cell_children() {
+        let mut children = Group::new(GroupKind::CellChildren(0x1234));
+        children.add_record(reference(true, true)).unwrap();
+        children.add_record(Tes4Record::new(b"LAND")).unwrap();
+        let distant = children.add_record(reference(false, true)).unwrap();
+        children.add_record(reference(false, false)).unwrap();
+        assert!(children.add_record(Tes4Record::new(b"NPC_")).is_err());
+        assert_eq!(children.len(), 8);
+
+        // a reference that's made persistent after being added should move when written
+        distant.write().unwrap().set_persistent(true);
+        assert_eq!(children.len(), 7);
+
+        let mut buf = vec![];
+        children.write(&mut Cursor::new(&mut buf)).unwrap();
+        let children = Group::read(Cursor::new(buf)).unwrap();
+
+        let layout: Vec<_> = children
+            .groups
+            .iter()
+            .map(|g| (g.kind.code(), g.records.len()))
+            .collect();
+        assert_eq!(layout, [(8, 2), (9, 2)]);
     }
 }
@@ -1,7 +1,7 @@
 use crate::tes4::{FormId, Tes4Field, Tes4Record};
-use crate::{decode_failed, Field, TesError};
+use crate::{ExtraFields, Field, TesError};
 
-pub trait Item {
+pub trait Item: ExtraFields<Field = Tes4Field> {
     /// Get this item's editor ID
     fn editor_id(&self) -> &str;
 
@@ -66,12 +66,7 @@ pub trait Item {
             b"MODT" => self.set_texture_hash(Some(field.get().to_vec())),
             b"ICON" => self.set_icon(Some(String::from(field.get_zstring()?))),
             b"SCRI" => self.set_script(Some(FormId(field.get_u32()?))),
-            _ => {
-                return Err(decode_failed(format!(
-                    "Unexpected field {} in item record",
-                    field.name_as_str()
-                )))
-            }
+            _ => self.extra_fields_mut().push(field.clone()),
         }
 
         Ok(())
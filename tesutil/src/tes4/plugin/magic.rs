@@ -1,18 +1,167 @@
-use crate::tes4::{ActorValue, FormId, MagicEffectType, Tes4Field, Tes4Record, MAGIC_EFFECTS};
-use crate::{decode_failed, decode_failed_because, EffectRange, Field, MagicSchool, TesError};
+use crate::tes4::{
+    ActorValue, FormId, MagicEffect, MagicEffectType, Tes4Field, Tes4Record, MAGIC_EFFECTS,
+};
+use crate::{
+    decode_failed, decode_failed_because, EffectRange, ExtraFields, Field, MagicSchool, TesError,
+};
 use binrw::{BinReaderExt, BinWriterExt};
 use std::io::{Cursor, Write};
 
 /// Script details for a scripted magic effect
+///
+/// Script effects (`SEFF`) run a script on their target instead of having a built-in effect. The
+/// script effect data gives the effect its own name, school, and visuals.
 #[derive(Debug)]
 pub struct ScriptEffect {
     script: FormId,
-    school: MagicSchool,
+    school: Option<MagicSchool>,
     visual_effect: Option<MagicEffectType>,
     pub is_hostile: bool,
+    /// Flag bits other than the hostile flag, which are preserved as-is
+    other_flags: u32,
     name: String,
 }
 
+impl ScriptEffect {
+    /// Flag bit indicating a hostile effect
+    const HOSTILE: u32 = 1;
+
+    /// Creates script effect data that runs the given script
+    pub fn new(script: FormId, name: String, school: MagicSchool) -> ScriptEffect {
+        ScriptEffect {
+            script,
+            school: Some(school),
+            visual_effect: None,
+            is_hostile: false,
+            other_flags: 0,
+            name,
+        }
+    }
+
+    /// Gets the form ID of the script this effect runs
+    pub fn script(&self) -> FormId {
+        self.script
+    }
+
+    /// Sets the form ID of the script this effect runs
+    pub fn set_script(&mut self, script: FormId) {
+        self.script = script;
+    }
+
+    /// Gets the magic school this script effect belongs to
+    ///
+    /// Returns `None` if the script effect data only names the script, in which case the effect
+    /// belongs to the school of the base script effect.
+    pub fn school(&self) -> Option<MagicSchool> {
+        self.school
+    }
+
+    /// Sets the magic school this script effect belongs to
+    pub fn set_school(&mut self, school: Option<MagicSchool>) {
+        self.school = school;
+    }
+
+    /// Gets the effect whose visuals this script effect uses, if any
+    pub fn visual_effect(&self) -> Option<MagicEffectType> {
+        self.visual_effect
+    }
+
+    /// Sets the effect whose visuals this script effect uses
+    pub fn set_visual_effect(&mut self, visual_effect: Option<MagicEffectType>) {
+        self.visual_effect = visual_effect;
+    }
+
+    /// Gets the name of this script effect
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Sets the name of this script effect
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    /// Reads script effect data from a `SCIT` field
+    ///
+    /// Some plugins have `SCIT` fields with only the script form ID, so everything else is
+    /// optional.
+    fn read(field: &Tes4Field) -> Result<ScriptEffect, TesError> {
+        let mut reader = field.reader();
+        let mut script_effect = ScriptEffect {
+            script: FormId(reader.read_le()?),
+            school: None,
+            visual_effect: None,
+            is_hostile: false,
+            other_flags: 0,
+            name: String::new(),
+        };
+        if field.get().len() <= 4 {
+            return Ok(script_effect);
+        }
+
+        script_effect.school = Some(
+            MagicSchool::from_tes4(reader.read_le()?)
+                .map_err(|e| decode_failed_because("Invalid script effect magic school", e))?,
+        );
+        let id: u32 = reader.read_le()?;
+        if id != 0 {
+            let id_bytes = id.to_le_bytes();
+            script_effect.visual_effect = Some(
+                MagicEffectType::from_id(&id_bytes)
+                    .ok_or_else(|| decode_failed(format!("Unexpected effect ID {:?}", id)))?,
+            );
+        }
+        // only the hostile bit is known; the rest are often garbage, but we keep them anyway
+        let flags: u32 = reader.read_le()?;
+        script_effect.is_hostile = flags & ScriptEffect::HOSTILE != 0;
+        script_effect.other_flags = flags & !ScriptEffect::HOSTILE;
+
+        Ok(script_effect)
+    }
+
+    /// Writes script effect data to a `SCIT` field
+    ///
+    /// Without a school, only the script form ID is written.
+    ///
+    /// # Errors
+    ///
+    /// Fails if there's no school but there is a visual effect or flags, which can't be stored
+    /// without one.
+    fn write(&self) -> Result<Tes4Field, TesError> {
+        let mut buf = Vec::with_capacity(16);
+        let mut writer = Cursor::new(&mut buf);
+        let flags = if self.is_hostile {
+            self.other_flags | ScriptEffect::HOSTILE
+        } else {
+            self.other_flags
+        };
+        writer.write_le(&self.script.0)?;
+        let school = match self.school {
+            Some(school) => school,
+            None if self.visual_effect.is_none() && flags == 0 => {
+                return Tes4Field::new(b"SCIT", buf);
+            }
+            None => {
+                return Err(TesError::RequirementFailed(String::from(
+                    "Script effect visuals and flags can't be saved without a school",
+                )))
+            }
+        };
+        writer.write_le(&school.to_tes4())?;
+        match self.visual_effect {
+            Some(ref effect) => {
+                writer.write_all(&effect.id())?;
+            }
+            None => {
+                writer.write_le(&0u32)?;
+            }
+        }
+        writer.write_le(&flags)?;
+
+        Tes4Field::new(b"SCIT", buf)
+    }
+}
+
 /// An individual effect of a spell
 #[derive(Debug)]
 pub struct SpellEffect {
@@ -61,6 +210,28 @@ impl SpellEffect {
         }
     }
 
+    /// Creates a new spell effect with the given range, magnitude, area, and duration
+    ///
+    /// The range is set first, so an area is accepted for any range but self.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the vanilla definition of the effect doesn't allow the range or any of the values.
+    pub fn with_values(
+        effect: MagicEffectType,
+        range: EffectRange,
+        magnitude: u32,
+        area: u32,
+        duration: u32,
+    ) -> Result<SpellEffect, TesError> {
+        let mut spell_effect = SpellEffect::new(effect);
+        spell_effect.set_range(range)?;
+        spell_effect.set_magnitude(magnitude)?;
+        spell_effect.set_area(area)?;
+        spell_effect.set_duration(duration)?;
+        Ok(spell_effect)
+    }
+
     /// Gets this effect's effect type
     pub fn effect_type(&self) -> MagicEffectType {
         self.effect
@@ -72,26 +243,32 @@ impl SpellEffect {
     }
 
     /// Sets the effect's magnitude
+    ///
+    /// The magnitude is checked against the vanilla definition of the effect. To check against a
+    /// definition from a plugin, use [`check_values`].
+    ///
+    /// [`check_values`]: #method.check_values
     pub fn set_magnitude(&mut self, value: u32) -> Result<(), TesError> {
         // we can "cheat" by using the hard-coded default effects instead of looking up the actual
         // effect because the properties that we're checking are hard-coded and can't be changed by
         // mods
-        if !MAGIC_EFFECTS[self.effect].has_magnitude() {
+        self.magnitude = self.check_magnitude_with(value, &MAGIC_EFFECTS[self.effect])?;
+        Ok(())
+    }
+
+    fn check_magnitude_with(&self, value: u32, base_effect: &MagicEffect) -> Result<u32, TesError> {
+        if base_effect.has_magnitude() {
+            Ok(value)
+        } else if value > 1 {
+            Err(TesError::LimitExceeded {
+                description: format!("{:?} cannot have a magnitude", self.effect),
+                max_size: 0,
+                actual_size: value as usize,
+            })
+        } else {
             // Morrowind uses a magnitude of 1 for spells with no magnitude, so we'll accept a value
             // of 1 and treat it as 0
-            if value > 1 {
-                Err(TesError::LimitExceeded {
-                    description: format!("{:?} cannot have a magnitude", self.effect),
-                    max_size: 0,
-                    actual_size: value as usize,
-                })
-            } else {
-                self.magnitude = 0;
-                Ok(())
-            }
-        } else {
-            self.magnitude = value;
-            Ok(())
+            Ok(0)
         }
     }
 
@@ -101,22 +278,33 @@ impl SpellEffect {
     }
 
     /// Sets the effect's area
+    ///
+    /// The area is checked against the vanilla definition of the effect. To check against a
+    /// definition from a plugin, use [`check_values`].
+    ///
+    /// [`check_values`]: #method.check_values
     pub fn set_area(&mut self, value: u32) -> Result<(), TesError> {
+        self.area = self.check_area_with(value, &MAGIC_EFFECTS[self.effect])?;
+        Ok(())
+    }
+
+    fn check_area_with(&self, value: u32, base_effect: &MagicEffect) -> Result<u32, TesError> {
         if self.range == EffectRange::Self_ && value > 1 {
             Err(TesError::LimitExceeded {
                 description: String::from("Cast-on-self spells cannot have an area"),
                 max_size: 0,
                 actual_size: value as usize,
             })
-        } else if !MAGIC_EFFECTS[self.effect].has_area() && value > 1 {
+        } else if !base_effect.has_area() && value > 1 {
             Err(TesError::LimitExceeded {
                 description: format!("{:?} cannot have an area", self.effect),
                 max_size: 0,
                 actual_size: value as usize,
             })
+        } else if self.range == EffectRange::Self_ || !base_effect.has_area() {
+            Ok(0)
         } else {
-            self.area = value;
-            Ok(())
+            Ok(value)
         }
     }
 
@@ -126,37 +314,85 @@ impl SpellEffect {
     }
 
     /// Sets the effect's duration
+    ///
+    /// The duration is checked against the vanilla definition of the effect. To check against a
+    /// definition from a plugin, use [`check_values`].
+    ///
+    /// [`check_values`]: #method.check_values
     pub fn set_duration(&mut self, value: u32) -> Result<(), TesError> {
-        if !MAGIC_EFFECTS[self.effect].has_duration() {
-            if value > 1 {
-                Err(TesError::LimitExceeded {
-                    description: format!("{:?} cannot have a duration", self.effect),
-                    max_size: 0,
-                    actual_size: value as usize,
-                })
-            } else {
-                self.duration = 0;
-                Ok(())
-            }
+        self.duration = self.check_duration_with(value, &MAGIC_EFFECTS[self.effect])?;
+        Ok(())
+    }
+
+    fn check_duration_with(&self, value: u32, base_effect: &MagicEffect) -> Result<u32, TesError> {
+        if base_effect.has_duration() {
+            Ok(value)
+        } else if value > 1 {
+            Err(TesError::LimitExceeded {
+                description: format!("{:?} cannot have a duration", self.effect),
+                max_size: 0,
+                actual_size: value as usize,
+            })
         } else {
-            self.duration = value;
-            Ok(())
+            Ok(0)
         }
     }
 
+    /// Checks that the effect's magnitude, area, and duration are allowed by its magic effect
+    /// definition
+    ///
+    /// Values of 1 for properties the effect doesn't have are accepted, because Morrowind uses them
+    /// in place of 0; they're written out as 0.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the effect has a magnitude, area, or duration that the definition doesn't allow, or
+    /// if it has an area but is cast on self.
+    pub fn check_values(&self, base_effect: &MagicEffect) -> Result<(), TesError> {
+        self.check_magnitude_with(self.magnitude, base_effect)?;
+        self.check_area_with(self.area, base_effect)?;
+        self.check_duration_with(self.duration, base_effect)?;
+        Ok(())
+    }
+
+    /// Gets the magnitude, area, and duration that will be written out for this effect
+    ///
+    /// Properties the effect doesn't have are always written as 0, even if an invalid value was
+    /// read from a plugin.
+    fn effective_values(&self) -> (u32, u32, u32) {
+        let base_effect = &MAGIC_EFFECTS[self.effect];
+        let magnitude = if base_effect.has_magnitude() {
+            self.magnitude
+        } else {
+            0
+        };
+        let area = if base_effect.has_area() && self.range != EffectRange::Self_ {
+            self.area
+        } else {
+            0
+        };
+        let duration = if base_effect.has_duration() {
+            self.duration
+        } else {
+            0
+        };
+
+        (magnitude, area, duration)
+    }
+
     /// Gets the effect's range
     pub fn range(&self) -> EffectRange {
         self.range
     }
 
     /// Sets the effect's range
+    ///
+    /// The range is checked against the vanilla definition of the effect. To check against a
+    /// definition from a plugin, use [`check_range`].
+    ///
+    /// [`check_range`]: #method.check_range
     pub fn set_range(&mut self, range: EffectRange) -> Result<(), TesError> {
-        if !MAGIC_EFFECTS[self.effect].allows_range(range) {
-            return Err(TesError::RequirementFailed(format!(
-                "{:?} does not allow range {:?}",
-                self.effect, range
-            )));
-        }
+        self.check_range_with(range, &MAGIC_EFFECTS[self.effect])?;
 
         if range == EffectRange::Self_ {
             self.area = 0;
@@ -166,6 +402,30 @@ impl SpellEffect {
         Ok(())
     }
 
+    /// Checks that the effect's range is allowed by its magic effect definition
+    ///
+    /// # Errors
+    ///
+    /// Fails if the definition doesn't allow the range.
+    pub fn check_range(&self, base_effect: &MagicEffect) -> Result<(), TesError> {
+        self.check_range_with(self.range, base_effect)
+    }
+
+    fn check_range_with(
+        &self,
+        range: EffectRange,
+        base_effect: &MagicEffect,
+    ) -> Result<(), TesError> {
+        if base_effect.allows_range(range) {
+            Ok(())
+        } else {
+            Err(TesError::RequirementFailed(format!(
+                "{:?} does not allow range {:?}",
+                self.effect, range
+            )))
+        }
+    }
+
     /// Gets the effect's actor value
     pub fn actor_value(&self) -> ActorValue {
         self.actor_value
@@ -183,13 +443,56 @@ impl SpellEffect {
     }
 
     /// Sets the effect's script effect data
+    ///
+    /// Only script effects use script effect data, so setting it also changes the effect type to
+    /// [`MagicEffectType::ScriptEffect`].
+    ///
+    /// [`MagicEffectType::ScriptEffect`]: enum.MagicEffectType.html#variant.ScriptEffect
     pub fn set_script_effect(&mut self, script_effect: Option<ScriptEffect>) {
+        if script_effect.is_some() {
+            self.effect = MagicEffectType::ScriptEffect;
+        }
         self.script_effect = script_effect;
     }
+
+    /// Is this effect hostile?
+    ///
+    /// Scripted effects take their hostility from the script effect data rather than the base
+    /// effect.
+    pub fn is_hostile(&self) -> bool {
+        match self.script_effect {
+            Some(ref script_effect) => script_effect.is_hostile,
+            None => MAGIC_EFFECTS[self.effect].is_hostile(),
+        }
+    }
+
+    /// Calculates the magicka cost of this effect given the base cost of its magic effect
+    pub fn cost(&self, base_cost: f32) -> f32 {
+        // formula from the Oblivion construction set wiki
+        let magnitude = (self.magnitude.max(1) as f32).powf(1.28);
+        let duration = self.duration.max(1) as f32;
+        let area = (self.area as f32 * 0.15).max(1.);
+        let range = if self.range == EffectRange::Target {
+            1.5
+        } else {
+            1.
+        };
+
+        base_cost / 10. * magnitude * duration * area * range
+    }
+}
+
+/// Error for an effect index that's out of range for an effect list
+pub(crate) fn effect_index_error(index: usize, count: usize) -> TesError {
+    TesError::LimitExceeded {
+        description: format!("Effect index {} out of range", index),
+        max_size: count,
+        actual_size: index,
+    }
 }
 
 /// A form which contains magic effects
-pub trait Magic {
+pub trait Magic: ExtraFields<Field = Tes4Field> {
     /// Iterate through this magical entity's effects
     fn iter_effects(&self) -> Box<dyn Iterator<Item = &SpellEffect> + '_>;
 
@@ -199,6 +502,71 @@ pub trait Magic {
     /// Add an effect to this magical entity
     fn add_effect(&mut self, effect: SpellEffect);
 
+    /// Insert an effect at the given position in this magical entity's effect list
+    ///
+    /// # Errors
+    ///
+    /// Fails if `index` is greater than the number of effects.
+    fn insert_effect(&mut self, index: usize, effect: SpellEffect) -> Result<(), TesError>;
+
+    /// Remove the effect at the given position, returning it if it exists
+    fn remove_effect(&mut self, index: usize) -> Option<SpellEffect>;
+
+    /// Get the number of effects this magical entity has
+    fn effect_count(&self) -> usize {
+        self.iter_effects().count()
+    }
+
+    /// Replace the effect at the given position, returning the old effect
+    ///
+    /// # Errors
+    ///
+    /// Fails if there is no effect at `index`.
+    fn replace_effect(
+        &mut self,
+        index: usize,
+        effect: SpellEffect,
+    ) -> Result<SpellEffect, TesError> {
+        let count = self.effect_count();
+        match self.iter_effects_mut().nth(index) {
+            Some(old_effect) => Ok(std::mem::replace(old_effect, effect)),
+            None => Err(effect_index_error(index, count)),
+        }
+    }
+
+    /// Move the effect at position `from` to position `to`, shifting the effects in between
+    ///
+    /// # Errors
+    ///
+    /// Fails if either index is out of range.
+    fn move_effect(&mut self, from: usize, to: usize) -> Result<(), TesError> {
+        let count = self.effect_count();
+        if to >= count {
+            return Err(effect_index_error(to, count));
+        }
+
+        let effect = self
+            .remove_effect(from)
+            .ok_or_else(|| effect_index_error(from, count))?;
+        self.insert_effect(to, effect)
+    }
+
+    /// Remove all effects of the given type, returning the number of effects removed
+    fn remove_effects_of_type(&mut self, effect_type: MagicEffectType) -> usize {
+        let mut removed = 0;
+        let mut i = 0;
+        while i < self.effect_count() {
+            if self.iter_effects().nth(i).map(SpellEffect::effect_type) == Some(effect_type) {
+                self.remove_effect(i);
+                removed += 1;
+            } else {
+                i += 1;
+            }
+        }
+
+        removed
+    }
+
     /// Get this magical entity's name if it has one
     fn name(&self) -> Option<&str>;
 
@@ -222,7 +590,7 @@ pub trait Magic {
                     last_effect.magnitude = reader.read_le()?;
                     last_effect.area = reader.read_le()?;
                     last_effect.duration = reader.read_le()?;
-                    last_effect.range = EffectRange::try_from(reader.read_le::<u32>()? as u8)
+                    last_effect.range = EffectRange::from_tes4(reader.read_le()?)
                         .map_err(|e| decode_failed_because("Invalid effect range", e))?;
                     last_effect.actor_value = ActorValue::try_from(reader.read_le::<u32>()? as u8)
                         .map_err(|e| decode_failed_because("Invalid effect actor value", e))?;
@@ -232,26 +600,7 @@ pub trait Magic {
             }
             b"SCIT" => {
                 if let Some(last_effect) = self.iter_effects_mut().last() {
-                    let mut reader = field.reader();
-                    last_effect.script_effect = Some(ScriptEffect {
-                        script: FormId(reader.read_le()?),
-                        school: MagicSchool::try_from(reader.read_le::<u32>()? as u8).map_err(
-                            |e| decode_failed_because("Invalid script effect magic school", e),
-                        )?,
-                        visual_effect: {
-                            let id: u32 = reader.read_le()?;
-                            if id == 0 {
-                                None
-                            } else {
-                                let id_bytes = id.to_le_bytes();
-                                Some(MagicEffectType::from_id(&id_bytes).ok_or_else(|| {
-                                    decode_failed(format!("Unexpected effect ID {:?}", id))
-                                })?)
-                            }
-                        },
-                        is_hostile: reader.read_le::<u32>()? & 1 != 0, // other "flag" bits are garbage?
-                        name: String::new(),
-                    });
+                    last_effect.script_effect = Some(ScriptEffect::read(field)?);
                 } else {
                     return Err(decode_failed("Orphaned SCIT field in magic record"));
                 }
@@ -269,12 +618,7 @@ pub trait Magic {
                     self.set_name(Some(String::from(field.get_zstring()?)));
                 }
             }
-            _ => {
-                return Err(decode_failed(format!(
-                    "Unexpected {} field while parsing magic effect",
-                    field.name_as_str()
-                )))
-            }
+            _ => self.extra_fields_mut().push(field.clone()),
         }
 
         Ok(())
@@ -288,35 +632,19 @@ pub trait Magic {
 
             let mut buf = Vec::with_capacity(24);
             let mut writer = Cursor::new(&mut buf);
-            let range: u8 = effect.range.into();
             let av: u8 = effect.actor_value.into();
             writer.write_all(&effect_id)?;
-            writer.write_le(&effect.magnitude)?;
-            writer.write_le(&effect.area)?;
-            writer.write_le(&effect.duration)?;
-            writer.write_le(&(range as u32))?;
+            let (magnitude, area, duration) = effect.effective_values();
+            writer.write_le(&magnitude)?;
+            writer.write_le(&area)?;
+            writer.write_le(&duration)?;
+            writer.write_le(&effect.range.to_tes4())?;
             writer.write_le(&(av as u32))?;
 
             record.add_field(Tes4Field::new(b"EFIT", buf)?);
 
             if let Some(ref script_effect) = effect.script_effect {
-                let mut buf = Vec::with_capacity(16);
-                let mut writer = Cursor::new(&mut buf);
-                let school: u8 = script_effect.school.into();
-                let flags = if script_effect.is_hostile { 1u32 } else { 0 };
-                writer.write_le(&script_effect.script.0)?;
-                writer.write_le(&(school as u32))?;
-                match script_effect.visual_effect {
-                    Some(ref effect) => {
-                        writer.write_all(&effect.id())?;
-                    }
-                    None => {
-                        writer.write_le(&0u32)?;
-                    }
-                }
-                writer.write_le(&flags)?;
-
-                record.add_field(Tes4Field::new(b"SCIT", buf)?);
+                record.add_field(script_effect.write()?);
                 record.add_field(Tes4Field::new_zstring(b"FULL", script_effect.name.clone())?);
             }
         }
@@ -324,3 +652,148 @@ pub trait Magic {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tes4::{EffectFlags, Spell};
+    use crate::Form;
+
+    fn effect_types(spell: &Spell) -> Vec<MagicEffectType> {
+        spell.iter_effects().map(SpellEffect::effect_type).collect()
+    }
+
+    #[test]
+    fn mutate_effects() {
+        use MagicEffectType::*;
+
+        let mut spell = Spell::new(None, None);
+        spell.add_effect(SpellEffect::new(FireDamage));
+        spell.add_effect(SpellEffect::new(FrostDamage));
+        spell.add_effect(SpellEffect::new(FireDamage));
+        spell.add_effect(SpellEffect::new(ShockDamage));
+
+        spell.move_effect(3, 0).unwrap();
+        assert_eq!(
+            effect_types(&spell),
+            vec![ShockDamage, FireDamage, FrostDamage, FireDamage]
+        );
+
+        let old = spell.replace_effect(2, SpellEffect::new(Burden)).unwrap();
+        assert_eq!(old.effect_type(), FrostDamage);
+
+        assert_eq!(spell.remove_effects_of_type(FireDamage), 2);
+        assert_eq!(effect_types(&spell), vec![ShockDamage, Burden]);
+
+        assert!(spell.move_effect(0, 2).is_err());
+        assert!(spell.replace_effect(2, SpellEffect::new(Burden)).is_err());
+        assert!(spell.remove_effect(2).is_none());
+    }
+
+    #[test]
+    fn check_range() {
+        let mut effect = SpellEffect::new(MagicEffectType::FireDamage);
+        effect.set_range(EffectRange::Target).unwrap();
+        assert!(effect.check_range(&MAGIC_EFFECTS[effect.effect]).is_ok());
+
+        // a plugin could redefine an effect to forbid ranges the vanilla definition allows
+        let touch_only = MagicEffect::new(
+            MagicEffectType::FireDamage,
+            "Fire Damage",
+            MagicSchool::Destruction,
+            None,
+            EffectFlags::TOUCH,
+            vec![],
+        );
+        assert!(effect.check_range(&touch_only).is_err());
+
+        let mut effect = SpellEffect::new(MagicEffectType::Telekinesis);
+        assert!(effect.set_range(EffectRange::Self_).is_err());
+    }
+
+    #[test]
+    fn check_values() {
+        let mut effect = SpellEffect::new(MagicEffectType::Paralyze);
+        assert!(effect.set_magnitude(10).is_err());
+        effect.set_magnitude(1).unwrap();
+        assert_eq!(effect.magnitude(), 0);
+        effect.set_duration(30).unwrap();
+
+        let mut effect = SpellEffect::new(MagicEffectType::FireDamage);
+        effect.set_magnitude(20).unwrap();
+        effect.set_duration(5).unwrap();
+        assert!(effect.set_area(10).is_err());
+        effect.set_range(EffectRange::Target).unwrap();
+        effect.set_area(10).unwrap();
+        assert!(effect.check_values(&MAGIC_EFFECTS[effect.effect]).is_ok());
+
+        let no_duration = MagicEffect::new(
+            MagicEffectType::FireDamage,
+            "Fire Damage",
+            MagicSchool::Destruction,
+            None,
+            EffectFlags::TARGET | EffectFlags::NO_DURATION,
+            vec![],
+        );
+        assert!(effect.check_values(&no_duration).is_err());
+
+        // invalid values read from a plugin are never written back out
+        effect.range = EffectRange::Self_;
+        assert_eq!(effect.effective_values(), (20, 0, 5));
+
+        let effect =
+            SpellEffect::with_values(MagicEffectType::FireDamage, EffectRange::Target, 20, 10, 5)
+                .unwrap();
+        assert_eq!(effect.range(), EffectRange::Target);
+        assert_eq!(effect.area(), 10);
+        assert!(SpellEffect::with_values(
+            MagicEffectType::FireDamage,
+            EffectRange::Self_,
+            20,
+            10,
+            5
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn script_effect() {
+        let mut script_effect = ScriptEffect::new(
+            FormId(0x1234),
+            String::from("Mark of the Wolf"),
+            MagicSchool::Mysticism,
+        );
+        script_effect.set_visual_effect(Some(MagicEffectType::Light));
+        script_effect.is_hostile = true;
+        script_effect.other_flags = 0x100;
+        let mut effect = SpellEffect::new(MagicEffectType::FireDamage);
+        effect.set_script_effect(Some(script_effect));
+        assert_eq!(effect.effect_type(), MagicEffectType::ScriptEffect);
+
+        let mut spell = Spell::new(None, None);
+        spell.add_effect(effect);
+        let mut record = Tes4Record::new(b"SPEL");
+        spell.write(&mut record).unwrap();
+        let spell = Spell::read(&record).unwrap();
+        let effect = spell.iter_effects().next().unwrap();
+        assert!(effect.is_hostile());
+        let script_effect = effect.script_effect().unwrap();
+        assert_eq!(script_effect.script(), FormId(0x1234));
+        assert_eq!(script_effect.name(), "Mark of the Wolf");
+        assert_eq!(script_effect.school(), Some(MagicSchool::Mysticism));
+        assert_eq!(script_effect.visual_effect(), Some(MagicEffectType::Light));
+        assert_eq!(script_effect.other_flags, 0x100);
+
+        // some plugins only store the script
+        let field = Tes4Field::new(b"SCIT", vec![0x34, 0x12, 0, 0]).unwrap();
+        let mut script_effect = ScriptEffect::read(&field).unwrap();
+        assert_eq!(script_effect.script(), FormId(0x1234));
+        assert!(script_effect.school().is_none());
+        assert!(script_effect.visual_effect().is_none());
+        assert!(!script_effect.is_hostile);
+        assert_eq!(script_effect.write().unwrap().get(), field.get());
+
+        script_effect.is_hostile = true;
+        assert!(script_effect.write().is_err());
+    }
+}
@@ -1,11 +1,13 @@
 use std::convert::{Into, TryFrom};
+use std::io::Cursor;
 
 use crate::tes4::{ActorValue, FormId, Tes4Field, Tes4Record};
 use crate::{
-    decode_failed, decode_failed_because, EffectRange, Field, Form, MagicSchool, Record, TesError,
+    checked_len, decode_failed, decode_failed_because, EffectRange, ExtraFields, Field, FieldOrder,
+    Form, MagicSchool, Record, TesError,
 };
 
-use binrw::BinReaderExt;
+use binrw::{BinReaderExt, BinWriterExt};
 use bitflags::bitflags;
 use enum_map::*;
 use lazy_static::lazy_static;
@@ -356,7 +358,7 @@ impl MagicEffectType {
 
         // sometimes effect IDs having a trailing null in the data. for convenience, we'll just
         // truncate anything past the 4th byte
-        let id = &id[..4];
+        let id = id.get(..4)?;
 
         match id {
             b"ABAT" => Some(AbsorbAttribute),
@@ -685,6 +687,8 @@ pub struct MagicEffect {
     constant_effect_enchantment_factor: f32,
     constant_effect_barter_factor: f32,
     counter_effects: Vec<MagicEffectType>,
+    extra_fields: Vec<Tes4Field>,
+    field_order: FieldOrder,
 }
 
 impl MagicEffect {
@@ -718,6 +722,8 @@ impl MagicEffect {
             constant_effect_enchantment_factor: 0.,
             constant_effect_barter_factor: 0.,
             counter_effects,
+            extra_fields: vec![],
+            field_order: FieldOrder::new(),
         }
     }
 
@@ -756,6 +762,25 @@ impl MagicEffect {
         self.flags.contains(EffectFlags::MAGNITUDE_PERCENT)
     }
 
+    /// Can this effect be used to make custom spells?
+    pub fn allowed_for_spellmaking(&self) -> bool {
+        self.flags.contains(EffectFlags::SPELLMAKING)
+    }
+
+    /// Can this effect be used to enchant items?
+    pub fn allowed_for_enchanting(&self) -> bool {
+        self.flags.contains(EffectFlags::ENCHANTING)
+    }
+
+    /// Gets this effect's flags as they're defined
+    ///
+    /// See [`sanitized_flags`] for flags that are safe to write back to a plugin.
+    ///
+    /// [`sanitized_flags`]: #method.sanitized_flags
+    pub fn flags(&self) -> EffectFlags {
+        self.flags
+    }
+
     /// Gets this effect's projectile type
     pub fn projectile_type(&self) -> ProjectileType {
         if self
@@ -780,6 +805,116 @@ impl MagicEffect {
             EffectRange::Target => EffectFlags::TARGET,
         })
     }
+
+    /// Gets the effects that counter this effect
+    pub fn counter_effects(&self) -> &[MagicEffectType] {
+        self.counter_effects.as_slice()
+    }
+
+    /// Gets this effect's flags with nonsensical combinations fixed
+    ///
+    /// The game misbehaves when given flag combinations that contradict each other. Where the
+    /// intent is clear, the offending flag is cleared; otherwise, an error is returned.
+    pub fn sanitized_flags(&self) -> Result<EffectFlags, TesError> {
+        let mut flags = self.flags;
+
+        // a percentage only makes sense if there's a magnitude for it to apply to
+        if flags.contains(EffectFlags::NO_MAGNITUDE) {
+            flags.remove(EffectFlags::MAGNITUDE_PERCENT);
+        }
+
+        // the associated form or actor value can only be interpreted one way
+        let uses = flags
+            & (EffectFlags::USE_WEAPON
+                | EffectFlags::USE_ARMOR
+                | EffectFlags::USE_CREATURE
+                | EffectFlags::USE_SKILL
+                | EffectFlags::USE_ATTRIBUTE
+                | EffectFlags::USE_ACTOR_VALUE);
+        if uses.bits.count_ones() > 1 {
+            return Err(TesError::RequirementFailed(format!(
+                "{:?} has conflicting associated item flags {:?}",
+                self.effect_type, uses
+            )));
+        }
+
+        // effects available to players must be castable at some range. effects that aren't
+        // available for spellmaking or enchanting (e.g. DiseaseInfo) may have no range at all.
+        let ranges = EffectFlags::SELF | EffectFlags::TOUCH | EffectFlags::TARGET;
+        if flags.intersects(EffectFlags::SPELLMAKING | EffectFlags::ENCHANTING)
+            && !flags.intersects(ranges)
+        {
+            return Err(TesError::RequirementFailed(format!(
+                "{:?} is available for spellmaking or enchanting but allows no range",
+                self.effect_type
+            )));
+        }
+
+        Ok(flags)
+    }
+}
+
+/// Graph of which magic effects counter which other effects
+///
+/// Each magic effect lists the effects that counter it, e.g. Feather counters Burden. This type
+/// indexes those lists in both directions.
+#[derive(Debug)]
+pub struct CounterEffectGraph {
+    countered_by: EnumMap<MagicEffectType, Vec<MagicEffectType>>,
+    counters: EnumMap<MagicEffectType, Vec<MagicEffectType>>,
+}
+
+impl CounterEffectGraph {
+    /// Builds a counter effect graph from a set of magic effects
+    ///
+    /// Effect types that aren't present in `effects` are treated as having no counter effects.
+    pub fn new<'a, T: IntoIterator<Item = &'a MagicEffect>>(effects: T) -> CounterEffectGraph {
+        let mut graph = CounterEffectGraph {
+            countered_by: EnumMap::default(),
+            counters: EnumMap::default(),
+        };
+
+        for effect in effects {
+            for counter in effect.counter_effects() {
+                graph.countered_by[effect.effect_type].push(*counter);
+                graph.counters[*counter].push(effect.effect_type);
+            }
+        }
+
+        graph
+    }
+
+    /// Gets the effects that counter the given effect
+    pub fn counters(&self, effect: MagicEffectType) -> &[MagicEffectType] {
+        self.countered_by[effect].as_slice()
+    }
+
+    /// Gets the effects that the given effect counters
+    pub fn countered_effects(&self, effect: MagicEffectType) -> &[MagicEffectType] {
+        self.counters[effect].as_slice()
+    }
+
+    /// Is `effect` countered by `counter`?
+    pub fn is_countered_by(&self, effect: MagicEffectType, counter: MagicEffectType) -> bool {
+        self.countered_by[effect].contains(&counter)
+    }
+
+    /// Can the given effect be removed with a Dispel effect?
+    pub fn is_dispellable(&self, effect: MagicEffectType) -> bool {
+        self.is_countered_by(effect, MagicEffectType::Dispel)
+    }
+}
+
+impl ExtraFields for MagicEffect {
+    type Field = Tes4Field;
+
+    fn extra_fields(&self) -> &[Tes4Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes4Field> {
+        &mut self.extra_fields
+    }
 }
 
 impl Form for MagicEffect {
@@ -812,6 +947,8 @@ impl Form for MagicEffect {
             constant_effect_enchantment_factor: 0.,
             constant_effect_barter_factor: 0.,
             counter_effects: vec![],
+            extra_fields: vec![],
+            field_order: FieldOrder::new(),
         };
 
         for field in record.iter() {
@@ -834,7 +971,7 @@ impl Form for MagicEffect {
                         .ok_or_else(|| decode_failed("Invalid magic effect flags"))?;
                     effect.base_cost = reader.read_le()?;
                     effect.associated_form = FormId(reader.read_le()?);
-                    effect.school = MagicSchool::try_from(reader.read_le::<u32>()? as u8)
+                    effect.school = MagicSchool::from_tes4(reader.read_le()?)
                         .map_err(|e| decode_failed_because("Invalid school in magic effect", e))?;
                     effect.resist_value = {
                         let resist: u32 = reader.read_le()?;
@@ -861,20 +998,69 @@ impl Form for MagicEffect {
                     MagicEffectType::from_id(field.get())
                         .ok_or_else(|| decode_failed("Invalid counter effect type"))?,
                 ),
-                _ => {
-                    return Err(decode_failed(format!(
-                        "Unexpected field {} in MGEF record",
-                        field.name_as_str()
-                    )))
-                }
+                _ => effect.extra_fields.push(field.clone()),
             }
         }
 
+        effect.field_order = FieldOrder::from_record(record);
+
         Ok(effect)
     }
 
-    fn write(&self, _: &mut Self::Record) -> Result<(), TesError> {
-        unimplemented!()
+    fn write(&self, record: &mut Self::Record) -> Result<(), TesError> {
+        MagicEffect::assert(record)?;
+
+        let flags = self.sanitized_flags()?;
+
+        record.clear();
+
+        record.add_field(Tes4Field::new_zstring(
+            b"EDID",
+            String::from_utf8_lossy(&self.effect_type.id()).into_owned(),
+        )?);
+        record.add_field(Tes4Field::new_zstring(b"FULL", self.name.clone())?);
+        record.add_field(Tes4Field::new_zstring(b"DESC", self.description.clone())?);
+        if let Some(ref icon) = self.icon {
+            record.add_field(Tes4Field::new_zstring(b"ICON", icon.clone())?);
+        }
+        if let Some((ref model, bound_radius)) = self.model {
+            record.add_field(Tes4Field::new_zstring(b"MODL", model.clone())?);
+            record.add_field(Tes4Field::new_f32(b"MODB", bound_radius));
+        }
+
+        let mut buf = Vec::with_capacity(64);
+        let mut writer = Cursor::new(&mut buf);
+        let resist_value: u8 = self.resist_value.map_or(0, |av| av.into());
+        writer.write_le(&flags.bits)?;
+        writer.write_le(&self.base_cost)?;
+        writer.write_le(&self.associated_form.0)?;
+        writer.write_le(&self.school.to_tes4())?;
+        writer.write_le(&(resist_value as u32))?;
+        writer.write_le(&checked_len::<u32>(
+            self.counter_effects.len(),
+            "Magic effect counter effects",
+        )?)?;
+        writer.write_le(&self.light.0)?;
+        writer.write_le(&self.projectile_speed)?;
+        writer.write_le(&self.effect_shader.0)?;
+        writer.write_le(&self.casting_sound.0)?;
+        writer.write_le(&self.bolt_sound.0)?;
+        writer.write_le(&self.hit_sound.0)?;
+        writer.write_le(&self.area_sound.0)?;
+        writer.write_le(&self.constant_effect_enchantment_factor)?;
+        writer.write_le(&self.constant_effect_barter_factor)?;
+        record.add_field(Tes4Field::new(b"DATA", buf)?);
+
+        for counter in &self.counter_effects {
+            record.add_field(Tes4Field::new(b"ESCE", counter.id().to_vec())?);
+        }
+
+        for field in &self.extra_fields {
+            record.add_field(field.clone());
+        }
+        record.sort_fields(&self.field_order);
+
+        Ok(())
     }
 }
 
@@ -1692,3 +1878,95 @@ lazy_static! {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counter_effects() {
+        let graph = CounterEffectGraph::new(MAGIC_EFFECTS.values());
+        assert!(graph.is_countered_by(MagicEffectType::Burden, MagicEffectType::Feather));
+        assert!(!graph.is_countered_by(MagicEffectType::Feather, MagicEffectType::Shield));
+        assert!(graph
+            .countered_effects(MagicEffectType::RestoreHealth)
+            .contains(&MagicEffectType::DamageHealth));
+        assert!(graph.is_dispellable(MagicEffectType::DamageHealth));
+    }
+
+    #[test]
+    fn write_sanitizes_flags() {
+        let mut effect = MagicEffect::new(
+            MagicEffectType::Burden,
+            "Burden",
+            MagicSchool::Alteration,
+            None,
+            EffectFlags::HOSTILE
+                | EffectFlags::TOUCH
+                | EffectFlags::SPELLMAKING
+                | EffectFlags::NO_MAGNITUDE
+                | EffectFlags::MAGNITUDE_PERCENT,
+            vec![MagicEffectType::Feather, MagicEffectType::Dispel],
+        );
+
+        let mut record = Tes4Record::new(b"MGEF");
+        effect.write(&mut record).unwrap();
+        let read_effect = MagicEffect::read(&record).unwrap();
+        assert_eq!(read_effect.effect_type, MagicEffectType::Burden);
+        assert!(!read_effect.is_magnitude_percent());
+        assert!(!read_effect.has_magnitude());
+        assert!(read_effect.allowed_for_spellmaking());
+        assert!(!read_effect.allowed_for_enchanting());
+        assert_eq!(read_effect.counter_effects(), effect.counter_effects());
+
+        effect.flags |= EffectFlags::USE_WEAPON | EffectFlags::USE_ARMOR;
+        assert!(effect.write(&mut record).is_err());
+    }
+
+    #[test]
+    fn preserve_extra_fields() {
+        let effect = MagicEffect::new(
+            MagicEffectType::Feather,
+            "Feather",
+            MagicSchool::Alteration,
+            None,
+            EffectFlags::SELF | EffectFlags::SPELLMAKING,
+            vec![],
+        );
+
+        let mut record = Tes4Record::new(b"MGEF");
+        effect.write(&mut record).unwrap();
+        record.add_field(Tes4Field::new(b"XMOD", vec![1, 2, 3, 4]).unwrap());
+
+        let mut effect = MagicEffect::read(&record).unwrap();
+        assert_eq!(effect.extra_fields().len(), 1);
+        effect.name = String::from("Featherweight");
+        effect.write(&mut record).unwrap();
+
+        let field = record.iter().last().unwrap();
+        assert_eq!(field.name(), b"XMOD");
+        assert_eq!(field.get(), &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn editor_id_is_zstring() {
+        let effect = MagicEffect::new(
+            MagicEffectType::Feather,
+            "Feather",
+            MagicSchool::Alteration,
+            None,
+            EffectFlags::SELF | EffectFlags::SPELLMAKING,
+            vec![],
+        );
+
+        let mut record = Tes4Record::new(b"MGEF");
+        effect.write(&mut record).unwrap();
+        let field = record.iter().find(|f| f.name() == b"EDID").unwrap();
+        assert_eq!(field.get(), b"FTHR\0");
+        assert_eq!(
+            MagicEffect::read(&record).unwrap().effect_type,
+            MagicEffectType::Feather
+        );
+        assert_eq!(MagicEffectType::from_id(b"FT"), None);
+    }
+}
@@ -1,7 +1,9 @@
+use std::io::Cursor;
+
 use crate::tes4::{ActorFlags, FormId, Skills, Tes4Field, Tes4Record};
-use crate::{decode_failed, Attributes, Field, Form, Record, TesError};
+use crate::{check_range, decode_failed, Attributes, Field, Form, Record, TesError};
 
-use binrw::{binrw, BinReaderExt};
+use binrw::{binrw, BinReaderExt, BinWriterExt};
 use bitflags::bitflags;
 
 bitflags! {
@@ -133,12 +135,147 @@ impl Default for Npc {
 }
 
 impl Npc {
+    /// Starts building a new NPC
+    pub fn builder(editor_id: String) -> NpcBuilder {
+        NpcBuilder {
+            npc: Npc {
+                editor_id,
+                ..Npc::default()
+            },
+            race: None,
+            class: None,
+        }
+    }
+
     /// Iterate through the contents of the NPC's inventory
     pub fn iter_inventory(&self) -> impl Iterator<Item = (FormId, i32)> + '_ {
         self.inventory.iter().copied()
     }
 }
 
+/// Builds an NPC, validating it once all its settings have been provided
+///
+/// Every NPC needs a race and a class. Anything not provided is left at its default, so an NPC
+/// with no face data gets the race's default face.
+#[derive(Debug)]
+pub struct NpcBuilder {
+    npc: Npc,
+    race: Option<FormId>,
+    class: Option<FormId>,
+}
+
+impl NpcBuilder {
+    /// Sets the NPC's name
+    pub fn name(mut self, name: String) -> NpcBuilder {
+        self.npc.name = name;
+        self
+    }
+
+    /// Sets the NPC's race
+    pub fn race(mut self, race: FormId) -> NpcBuilder {
+        self.race = Some(race);
+        self
+    }
+
+    /// Sets the NPC's class
+    pub fn class(mut self, class: FormId) -> NpcBuilder {
+        self.class = Some(class);
+        self
+    }
+
+    /// Sets the NPC's level
+    pub fn level(mut self, level: i16) -> NpcBuilder {
+        self.npc.actor_settings.level = level;
+        self
+    }
+
+    /// Sets whether the NPC is female
+    pub fn female(mut self, value: bool) -> NpcBuilder {
+        self.npc.actor_settings.flags.set(ActorFlags::FEMALE, value);
+        self
+    }
+
+    /// Sets whether the NPC is essential
+    pub fn essential(mut self, value: bool) -> NpcBuilder {
+        self.npc
+            .actor_settings
+            .flags
+            .set(ActorFlags::ESSENTIAL, value);
+        self
+    }
+
+    /// Sets the NPC's attributes
+    pub fn attributes(mut self, attributes: Attributes<u8>) -> NpcBuilder {
+        self.npc.stats.attributes = attributes;
+        self
+    }
+
+    /// Sets the NPC's skills
+    pub fn skills(mut self, skills: Skills<u8>) -> NpcBuilder {
+        self.npc.stats.skills = skills;
+        self
+    }
+
+    /// Sets the NPC's base health
+    pub fn health(mut self, health: u32) -> NpcBuilder {
+        self.npc.stats.health = health;
+        self
+    }
+
+    /// Adds a spell to the NPC
+    pub fn spell(mut self, spell: FormId) -> NpcBuilder {
+        self.npc.spells.push(spell);
+        self
+    }
+
+    /// Adds an item to the NPC's inventory
+    pub fn item(mut self, item: FormId, count: i32) -> NpcBuilder {
+        self.npc.inventory.push((item, count));
+        self
+    }
+
+    /// Sets the NPC's hair and hair color
+    pub fn hair(mut self, hair: FormId, color: (u8, u8, u8)) -> NpcBuilder {
+        self.npc.hair = hair;
+        self.npc.hair_color = (color.0, color.1, color.2, 0);
+        self
+    }
+
+    /// Sets the NPC's eyes
+    pub fn eyes(mut self, eyes: FormId) -> NpcBuilder {
+        self.npc.eyes = Some(eyes);
+        self
+    }
+
+    /// Builds the NPC
+    ///
+    /// # Errors
+    ///
+    /// Fails if the NPC has no editor ID, race, or class, or if its level is less than 1.
+    pub fn build(mut self) -> Result<Npc, TesError> {
+        if self.npc.editor_id.is_empty() {
+            return Err(TesError::RequirementFailed(String::from(
+                "NPC must have an editor ID",
+            )));
+        }
+
+        self.npc.race = self
+            .race
+            .ok_or_else(|| TesError::RequirementFailed(String::from("NPC must have a race")))?;
+        self.npc.class = self
+            .class
+            .ok_or_else(|| TesError::RequirementFailed(String::from("NPC must have a class")))?;
+        check_range(
+            self.npc.actor_settings.level,
+            1,
+            i16::MAX,
+            "NPC level out of range",
+        )?;
+
+        Ok(self.npc)
+    }
+}
+
 impl Form for Npc {
     type Field = Tes4Field;
     type Record = Tes4Record;
@@ -200,7 +337,145 @@ impl Form for Npc {
         Ok(npc)
     }
 
-    fn write(&self, _: &mut Self::Record) -> Result<(), TesError> {
-        todo!()
+    fn write(&self, record: &mut Self::Record) -> Result<(), TesError> {
+        Npc::assert(record)?;
+
+        record.clear();
+
+        record.add_field(Tes4Field::new_zstring(b"EDID", self.editor_id.clone())?);
+        if !self.name.is_empty() {
+            record.add_field(Tes4Field::new_zstring(b"FULL", self.name.clone())?);
+        }
+        if !self.model.is_empty() {
+            record.add_field(Tes4Field::new_zstring(b"MODL", self.model.clone())?);
+            record.add_field(Tes4Field::new_f32(b"MODB", self.bound_radius));
+        }
+
+        let mut buf = vec![];
+        let mut cursor = Cursor::new(&mut buf);
+        cursor.write_le(&self.actor_settings)?;
+        record.add_field(Tes4Field::new(b"ACBS", buf)?);
+
+        for faction in &self.factions {
+            let mut buf = vec![];
+            let mut cursor = Cursor::new(&mut buf);
+            cursor.write_le(faction)?;
+            record.add_field(Tes4Field::new(b"SNAM", buf)?);
+        }
+
+        if let Some(death_item) = self.death_item {
+            record.add_field(Tes4Field::new_u32(b"INAM", death_item.0));
+        }
+        record.add_field(Tes4Field::new_u32(b"RNAM", self.race.0));
+        for spell in &self.spells {
+            record.add_field(Tes4Field::new_u32(b"SPLO", spell.0));
+        }
+        if let Some(script) = self.script {
+            record.add_field(Tes4Field::new_u32(b"SCRI", script.0));
+        }
+
+        for (item, count) in &self.inventory {
+            let mut buf = Vec::with_capacity(8);
+            let mut cursor = Cursor::new(&mut buf);
+            cursor.write_le(&item.0)?;
+            cursor.write_le(count)?;
+            record.add_field(Tes4Field::new(b"CNTO", buf)?);
+        }
+
+        let mut buf = vec![];
+        let mut cursor = Cursor::new(&mut buf);
+        cursor.write_le(&self.ai_settings)?;
+        record.add_field(Tes4Field::new(b"AIDT", buf)?);
+
+        for package in &self.packages {
+            record.add_field(Tes4Field::new_u32(b"PKID", package.0));
+        }
+        record.add_field(Tes4Field::new_u32(b"CNAM", self.class.0));
+
+        let mut buf = vec![];
+        let mut cursor = Cursor::new(&mut buf);
+        cursor.write_le(&self.stats)?;
+        record.add_field(Tes4Field::new(b"DATA", buf)?);
+
+        // NPCs without hair have no HNAM field, and form ID 0 is never valid hair
+        if self.hair.0 != 0 {
+            record.add_field(Tes4Field::new_u32(b"HNAM", self.hair.0));
+        }
+        if let Some(hair_length) = self.hair_length {
+            record.add_field(Tes4Field::new_f32(b"LNAM", hair_length));
+        }
+        if let Some(eyes) = self.eyes {
+            record.add_field(Tes4Field::new_u32(b"ENAM", eyes.0));
+        }
+        let (r, g, b, a) = self.hair_color;
+        record.add_field(Tes4Field::new(b"HCLR", vec![r, g, b, a])?);
+        if let Some(combat_style) = self.combat_style {
+            record.add_field(Tes4Field::new_u32(b"ZNAM", combat_style.0));
+        }
+
+        record.add_field(Tes4Field::new(b"FGGS", self.fg_geo_sym.to_vec())?);
+        record.add_field(Tes4Field::new(b"FGGA", self.fg_geo_asym.to_vec())?);
+        record.add_field(Tes4Field::new(b"FGTS", self.fg_tex_sym.to_vec())?);
+        record.add_field(Tes4Field::new_u16(b"FNAM", self.face_race));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tes4::Skill;
+    use crate::Attribute;
+
+    #[test]
+    fn build_npc() {
+        let mut attributes = Attributes::default();
+        attributes[Attribute::Strength] = 60;
+        let mut skills = Skills::default();
+        skills[Skill::Blade] = 45;
+
+        let npc = Npc::builder(String::from("tcGuard"))
+            .name(String::from("Guard"))
+            .race(FormId(0x907))
+            .class(FormId(0x2291d))
+            .level(10)
+            .female(true)
+            .attributes(attributes)
+            .skills(skills)
+            .health(120)
+            .item(FormId(0x25066), 1)
+            .spell(FormId(0x14d1f))
+            .build()
+            .unwrap();
+
+        let mut record = Tes4Record::new(b"NPC_");
+        npc.write(&mut record).unwrap();
+        let new_npc = Npc::read(&record).unwrap();
+        assert_eq!(new_npc.editor_id, "tcGuard");
+        assert_eq!(new_npc.race, FormId(0x907));
+        assert_eq!(new_npc.class, FormId(0x2291d));
+        assert_eq!(new_npc.actor_settings.level, 10);
+        assert!(new_npc.actor_settings.flags.contains(ActorFlags::FEMALE));
+        assert_eq!(new_npc.stats.attributes[Attribute::Strength], 60);
+        assert_eq!(new_npc.stats.skills[Skill::Blade], 45);
+        assert_eq!(new_npc.stats.health, 120);
+        assert_eq!(new_npc.spells, vec![FormId(0x14d1f)]);
+        assert_eq!(
+            new_npc.iter_inventory().collect::<Vec<_>>(),
+            vec![(FormId(0x25066), 1)]
+        );
+
+        let result = Npc::builder(String::from("tcGuard"))
+            .class(FormId(0x2291d))
+            .level(10)
+            .build();
+        assert!(matches!(result, Err(TesError::RequirementFailed(_))));
+
+        let result = Npc::builder(String::from("tcGuard"))
+            .race(FormId(0x907))
+            .class(FormId(0x2291d))
+            .build();
+        assert!(matches!(result, Err(TesError::OutOfRange { .. })));
     }
 }
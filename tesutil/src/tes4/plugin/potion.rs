@@ -1,9 +1,135 @@
-use crate::tes4::{FormId, Item, Magic, SpellEffect, Tes4Field, Tes4Record, MAGIC_EFFECTS};
+use crate::tes4::{
+    effect_index_error, FormId, Item, Magic, MagicEffectType, SpellEffect, Tes4Field, Tes4Record,
+    MAGIC_EFFECTS,
+};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::io::{Cursor, Read, Write};
 
-use crate::{Field, Form, Record, TesError};
+use crate::{EffectRange, ExtraFields, Field, FieldOrder, Form, Record, TesError};
 use binrw::{BinReaderExt, BinWriterExt};
 
+/// Model and texture info for a potion
+#[derive(Debug, Clone)]
+struct PotionGraphics {
+    model: Cow<'static, str>,
+    bound_radius: f32,
+    texture_hash: Option<Cow<'static, [u8]>>,
+    icon: Cow<'static, str>,
+}
+
+// values copied from PotionCureDisease in Oblivion.esm
+static POTION_GRAPHICS: PotionGraphics = PotionGraphics {
+    model: Cow::Borrowed(r"Clutter\Potions\Potion01.NIF"),
+    bound_radius: 8.10082,
+    texture_hash: Some(Cow::Borrowed(&[
+        0xB1, 0xB0, 0x08, 0x70, 0x68, 0xE9, 0x92, 0x4A, 0x31, 0x30, 0x08, 0x70, 0x6D, 0xE9, 0x92,
+        0x4A, 0x73, 0x6E, 0x18, 0x74, 0xE8, 0x78, 0x8E, 0xC2,
+    ])),
+    icon: Cow::Borrowed(r"Clutter\Potions\IconPotion01.dds"),
+};
+
+// values copied from PotionBurden in Oblivion.esm
+static POISON_GRAPHICS: PotionGraphics = PotionGraphics {
+    model: Cow::Borrowed(r"Clutter\Potions\PotionPoison.NIF"),
+    bound_radius: 8.08878,
+    texture_hash: Some(Cow::Borrowed(&[
+        0xEE, 0xEF, 0x0E, 0x70, 0xB2, 0xD6, 0xB7, 0x70, 0x6E, 0x6F, 0x0E, 0x70, 0xB7, 0xD6, 0xB7,
+        0x70, 0x73, 0x6E, 0x18, 0x74, 0xE8, 0x78, 0x8E, 0xC2,
+    ])),
+    icon: Cow::Borrowed(r"Clutter\Potions\IconPotionPoison01.dds"),
+};
+
+/// Potion graphics and effect costs used to pick graphics for new potions
+///
+/// The table is filled from existing potions: each potion's model and texture info is recorded
+/// under its strongest effect, so a new potion can look like an existing potion with the same main
+/// effect. Effects no potion has fall back to the generic potion or poison graphics. Effects are
+/// ranked by their base costs, which should come from the game's magic effect records; the
+/// hard-coded effect table has no costs, so a new table ranks effects by magnitude, duration, and
+/// area alone until real costs are set.
+#[derive(Debug, Clone)]
+pub struct PotionGraphicsTable {
+    base_costs: HashMap<MagicEffectType, f32>,
+    graphics: HashMap<(MagicEffectType, bool), PotionGraphics>,
+}
+
+impl PotionGraphicsTable {
+    /// Creates an empty table
+    pub fn new() -> PotionGraphicsTable {
+        PotionGraphicsTable {
+            base_costs: HashMap::new(),
+            graphics: HashMap::new(),
+        }
+    }
+
+    /// Gets the base cost used to rank an effect
+    ///
+    /// Effects without a known cost are treated as having a base cost of 1, so they're still
+    /// ranked by their magnitude, duration, and area.
+    pub fn base_cost(&self, effect: MagicEffectType) -> f32 {
+        let base_cost = self
+            .base_costs
+            .get(&effect)
+            .copied()
+            .unwrap_or_else(|| MAGIC_EFFECTS[effect].base_cost());
+        if base_cost > 0. {
+            base_cost
+        } else {
+            1.
+        }
+    }
+
+    /// Sets the base cost used to rank an effect
+    pub fn set_base_cost(&mut self, effect: MagicEffectType, base_cost: f32) {
+        self.base_costs.insert(effect, base_cost);
+    }
+
+    /// Records a potion's graphics under its strongest effect
+    ///
+    /// If a potion with the same strongest effect was already added, it's replaced, so potions
+    /// should be added in load order. Potions without a model or icon are ignored.
+    pub fn add_potion(&mut self, potion: &Potion) {
+        let (model, icon) = match (potion.model.as_ref(), potion.icon.as_ref()) {
+            (Some(model), Some(icon)) => (model, icon),
+            _ => return,
+        };
+
+        if let Some(effect) = potion.strongest_effect_with(|e| self.base_cost(e)) {
+            self.graphics.insert(
+                (effect.effect_type(), potion.is_poison()),
+                PotionGraphics {
+                    model: Cow::Owned(model.clone()),
+                    bound_radius: potion.bound_radius.unwrap_or(0.),
+                    texture_hash: potion.texture_hash.clone().map(Cow::Owned),
+                    icon: Cow::Owned(icon.clone()),
+                },
+            );
+        }
+    }
+
+    /// Does the table have graphics for potions or poisons with this strongest effect?
+    pub fn contains(&self, effect: MagicEffectType, is_poison: bool) -> bool {
+        self.graphics.contains_key(&(effect, is_poison))
+    }
+
+    fn graphics(&self, effect: MagicEffectType, is_poison: bool) -> &PotionGraphics {
+        self.graphics
+            .get(&(effect, is_poison))
+            .unwrap_or(if is_poison {
+                &POISON_GRAPHICS
+            } else {
+                &POTION_GRAPHICS
+            })
+    }
+}
+
+impl Default for PotionGraphicsTable {
+    fn default() -> Self {
+        PotionGraphicsTable::new()
+    }
+}
+
 /// An Oblivion potion
 #[derive(Debug)]
 pub struct Potion {
@@ -20,6 +146,8 @@ pub struct Potion {
     pub is_food_item: bool,
     unknown: [u8; 3],
     effects: Vec<SpellEffect>,
+    extra_fields: Vec<Tes4Field>,
+    field_order: FieldOrder,
 }
 
 impl Magic for Potion {
@@ -35,6 +163,23 @@ impl Magic for Potion {
         self.effects.push(effect);
     }
 
+    fn insert_effect(&mut self, index: usize, effect: SpellEffect) -> Result<(), TesError> {
+        if index > self.effects.len() {
+            return Err(effect_index_error(index, self.effects.len()));
+        }
+
+        self.effects.insert(index, effect);
+        Ok(())
+    }
+
+    fn remove_effect(&mut self, index: usize) -> Option<SpellEffect> {
+        if index < self.effects.len() {
+            Some(self.effects.remove(index))
+        } else {
+            None
+        }
+    }
+
     fn name(&self) -> Option<&str> {
         Some(self.name.as_str())
     }
@@ -127,45 +272,195 @@ impl Potion {
         }
     }
 
+    /// Starts building a new potion
+    pub fn builder(editor_id: String, name: String) -> PotionBuilder {
+        PotionBuilder {
+            potion: Potion::new(editor_id, name),
+            error: None,
+        }
+    }
+
+    fn use_graphics(&mut self, graphics: &PotionGraphics) {
+        self.model = Some(String::from(graphics.model.as_ref()));
+        self.bound_radius = Some(graphics.bound_radius);
+        self.texture_hash = graphics.texture_hash.as_ref().map(|h| h.to_vec());
+        self.icon = Some(String::from(graphics.icon.as_ref()));
+    }
+
     /// Set the model and texture info appropriately for a user-created potion
     pub fn use_potion_graphics(&mut self) {
-        // values copied from PotionCureDisease in Oblivion.esm
-        self.model = Some(String::from(r"Clutter\Potions\Potion01.NIF"));
-        self.bound_radius = Some(8.10082);
-        self.texture_hash = Some(vec![
-            0xB1, 0xB0, 0x08, 0x70, 0x68, 0xE9, 0x92, 0x4A, 0x31, 0x30, 0x08, 0x70, 0x6D, 0xE9,
-            0x92, 0x4A, 0x73, 0x6E, 0x18, 0x74, 0xE8, 0x78, 0x8E, 0xC2,
-        ]);
-        self.icon = Some(String::from(r"Clutter\Potions\IconPotion01.dds"));
+        self.use_graphics(&POTION_GRAPHICS);
     }
 
     /// Set the model and texture info appropriately for a user-created poison
     pub fn use_poison_graphics(&mut self) {
-        // values copied from PotionBurden in Oblivion.esm
-        self.model = Some(String::from(r"Clutter\Potions\PotionPoison.NIF"));
-        self.bound_radius = Some(8.08878);
-        self.texture_hash = Some(vec![
-            0xEE, 0xEF, 0x0E, 0x70, 0xB2, 0xD6, 0xB7, 0x70, 0x6E, 0x6F, 0x0E, 0x70, 0xB7, 0xD6,
-            0xB7, 0x70, 0x73, 0x6E, 0x18, 0x74, 0xE8, 0x78, 0x8E, 0xC2,
-        ]);
-        self.icon = Some(String::from(r"Clutter\Potions\IconPotionPoison01.dds"));
+        self.use_graphics(&POISON_GRAPHICS);
     }
 
     /// Is this potion a poison?
+    ///
+    /// As in the game, a potion is a poison if it has at least one effect and all of its effects
+    /// are hostile.
     pub fn is_poison(&self) -> bool {
-        self.effects.iter().all(|e| {
-            MAGIC_EFFECTS[e.effect_type()].is_hostile()
-                || e.script_effect().map_or(false, |s| s.is_hostile)
-        })
+        !self.effects.is_empty() && self.effects.iter().all(SpellEffect::is_hostile)
+    }
+
+    /// Gets this potion's strongest effect, ranked without the game's magic effect base costs
+    ///
+    /// Every effect is treated as having a base cost of 1, so effects are ranked by magnitude,
+    /// duration, area, and range alone, and the result may not be the effect with the highest
+    /// magicka cost in game. Use [`strongest_effect_with`] to rank effects by their real base
+    /// costs. If multiple effects are equally strong, the first one is returned.
+    ///
+    /// [`strongest_effect_with`]: #method.strongest_effect_with
+    pub fn strongest_effect(&self) -> Option<&SpellEffect> {
+        let table = PotionGraphicsTable::new();
+        self.strongest_effect_with(|e| table.base_cost(e))
+    }
+
+    /// Gets this potion's strongest effect, using the given function to look up base costs
+    ///
+    /// If multiple effects are equally strong, the first one is returned.
+    pub fn strongest_effect_with<F>(&self, base_cost: F) -> Option<&SpellEffect>
+    where
+        F: Fn(MagicEffectType) -> f32,
+    {
+        let mut strongest: Option<(&SpellEffect, f32)> = None;
+        for effect in &self.effects {
+            let cost = effect.cost(base_cost(effect.effect_type()));
+            match strongest {
+                Some((_, max_cost)) if max_cost >= cost => (),
+                _ => strongest = Some((effect, cost)),
+            }
+        }
+
+        strongest.map(|(effect, _)| effect)
     }
 
     /// Automatically set the model and texture info for a user-created potion of this type
+    ///
+    /// This uses the generic potion or poison graphics; see [`use_auto_graphics_from`] to pick
+    /// graphics from existing potions. If the potion has no effects, the graphics are left
+    /// unchanged.
+    ///
+    /// [`use_auto_graphics_from`]: #method.use_auto_graphics_from
     pub fn use_auto_graphics(&mut self) {
-        if self.is_poison() {
-            self.use_poison_graphics();
-        } else {
-            self.use_potion_graphics();
+        self.use_auto_graphics_from(&PotionGraphicsTable::new());
+    }
+
+    /// Automatically set the model and texture info for a user-created potion from a table of
+    /// existing potions' graphics
+    ///
+    /// The graphics are chosen based on the potion's strongest effect, falling back to the generic
+    /// potion or poison graphics if the table has no potion for that effect. If the potion has no
+    /// effects, the graphics are left unchanged.
+    pub fn use_auto_graphics_from(&mut self, table: &PotionGraphicsTable) {
+        let is_poison = self.is_poison();
+        let graphics = match self.strongest_effect_with(|e| table.base_cost(e)) {
+            Some(effect) => table.graphics(effect.effect_type(), is_poison),
+            None => return,
+        };
+
+        self.use_graphics(graphics);
+    }
+}
+
+/// Builds a potion, validating it once all its settings have been provided
+///
+/// Errors from individual settings are held until [`build`] is called. The potion's graphics are
+/// chosen automatically from its effects unless a model is provided.
+///
+/// [`build`]: #method.build
+#[derive(Debug)]
+pub struct PotionBuilder {
+    potion: Potion,
+    error: Option<TesError>,
+}
+
+impl PotionBuilder {
+    /// Sets the potion's weight
+    pub fn weight(mut self, weight: f32) -> PotionBuilder {
+        self.potion.weight = weight;
+        self
+    }
+
+    /// Sets a manual value for the potion instead of having it auto-calculated
+    pub fn value(mut self, value: u32) -> PotionBuilder {
+        self.potion.value = value;
+        self.potion.is_auto_calc = false;
+        self
+    }
+
+    /// Sets whether the potion is food
+    pub fn food_item(mut self, value: bool) -> PotionBuilder {
+        self.potion.is_food_item = value;
+        self
+    }
+
+    /// Sets the potion's script
+    pub fn script(mut self, script: FormId) -> PotionBuilder {
+        self.potion.script = Some(script);
+        self
+    }
+
+    /// Sets the potion's model, bound radius, and icon
+    pub fn model(mut self, model: String, bound_radius: f32, icon: String) -> PotionBuilder {
+        self.potion.model = Some(model);
+        self.potion.bound_radius = Some(bound_radius);
+        self.potion.icon = Some(icon);
+        self
+    }
+
+    /// Adds an effect to the potion
+    ///
+    /// The effect is checked as by [`SpellEffect::with_values`].
+    ///
+    /// [`SpellEffect::with_values`]: struct.SpellEffect.html#method.with_values
+    pub fn effect(
+        mut self,
+        effect: MagicEffectType,
+        range: EffectRange,
+        magnitude: u32,
+        area: u32,
+        duration: u32,
+    ) -> PotionBuilder {
+        match SpellEffect::with_values(effect, range, magnitude, area, duration) {
+            Ok(spell_effect) => self.potion.add_effect(spell_effect),
+            Err(e) => {
+                self.error.get_or_insert(e);
+            }
+        }
+        self
+    }
+
+    /// Builds the potion
+    ///
+    /// # Errors
+    ///
+    /// Fails if any effect was invalid, if the potion has no effects, or if it's a poison marked as
+    /// food.
+    pub fn build(mut self) -> Result<Potion, TesError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+
+        if self.potion.effects.is_empty() {
+            return Err(TesError::RequirementFailed(String::from(
+                "Potion must have at least one effect",
+            )));
+        }
+
+        if self.potion.is_food_item && self.potion.is_poison() {
+            return Err(TesError::RequirementFailed(String::from(
+                "Poisons cannot be food items",
+            )));
+        }
+
+        if self.potion.model.is_none() {
+            self.potion.use_auto_graphics();
         }
+
+        Ok(self.potion)
     }
 }
 
@@ -185,10 +480,24 @@ impl Default for Potion {
             is_food_item: false,
             unknown: [0; 3],
             effects: vec![],
+            extra_fields: vec![],
+            field_order: FieldOrder::new(),
         }
     }
 }
 
+impl ExtraFields for Potion {
+    type Field = Tes4Field;
+
+    fn extra_fields(&self) -> &[Tes4Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes4Field> {
+        &mut self.extra_fields
+    }
+}
+
 impl Form for Potion {
     type Field = Tes4Field;
     type Record = Tes4Record;
@@ -215,6 +524,8 @@ impl Form for Potion {
             }
         }
 
+        potion.field_order = FieldOrder::from_record(record);
+
         Ok(potion)
     }
 
@@ -239,6 +550,134 @@ impl Form for Potion {
 
         self.write_magic_effects(&mut record)?;
 
+        for field in &self.extra_fields {
+            record.add_field(field.clone());
+        }
+        record.sort_fields(&self.field_order);
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_effect(effect_type: MagicEffectType, magnitude: u32, duration: u32) -> SpellEffect {
+        let mut effect = SpellEffect::new(effect_type);
+        effect.set_magnitude(magnitude).unwrap();
+        effect.set_duration(duration).unwrap();
+        effect
+    }
+
+    #[test]
+    fn auto_graphics() {
+        // mirrors PotionCureDisease
+        let mut potion = Potion::new(String::from("CureDisease"), String::from("Cure Disease"));
+        potion.add_effect(make_effect(MagicEffectType::CureDisease, 0, 0));
+        assert!(!potion.is_poison());
+        potion.use_auto_graphics();
+        assert_eq!(potion.model(), Some(r"Clutter\Potions\Potion01.NIF"));
+
+        // mirrors PotionBurden
+        let mut poison = Potion::new(String::from("Burden"), String::from("Burden"));
+        poison.add_effect(make_effect(MagicEffectType::Burden, 10, 30));
+        poison.add_effect(make_effect(MagicEffectType::DamageFatigue, 5, 10));
+        assert!(poison.is_poison());
+        assert_eq!(
+            poison.strongest_effect().map(SpellEffect::effect_type),
+            Some(MagicEffectType::Burden)
+        );
+        poison.use_auto_graphics();
+        assert_eq!(
+            poison.icon(),
+            Some(r"Clutter\Potions\IconPotionPoison01.dds")
+        );
+
+        // a potion whose strongest effect is hostile is still a potion if any effect isn't
+        let mut mixed = Potion::new(String::from("Mixed"), String::from("Mixed"));
+        mixed.add_effect(make_effect(MagicEffectType::RestoreHealth, 5, 1));
+        mixed.add_effect(make_effect(MagicEffectType::Burden, 10, 30));
+        assert!(!mixed.is_poison());
+        mixed.use_auto_graphics();
+        assert_eq!(mixed.model(), Some(r"Clutter\Potions\Potion01.NIF"));
+
+        let mut empty = Potion::new(String::from("Empty"), String::new());
+        assert!(!empty.is_poison());
+        empty.use_auto_graphics();
+        assert_eq!(empty.model(), None);
+    }
+
+    #[test]
+    fn graphics_table() {
+        let mut vanilla = Potion::new(String::from("PotionRestoreHealthS"), String::new());
+        vanilla.add_effect(make_effect(MagicEffectType::RestoreHealth, 5, 1));
+        vanilla.add_effect(make_effect(MagicEffectType::FortifyHealth, 20, 10));
+        vanilla.set_model(Some(String::from(r"Clutter\Potions\PotionHealth.NIF")));
+        vanilla.set_icon(Some(String::from(r"Clutter\Potions\IconPotionHealth.dds")));
+
+        // without costs, fortify health wins on magnitude and duration
+        let mut table = PotionGraphicsTable::new();
+        table.add_potion(&vanilla);
+        assert!(table.contains(MagicEffectType::FortifyHealth, false));
+        assert!(!table.contains(MagicEffectType::RestoreHealth, false));
+
+        let mut table = PotionGraphicsTable::new();
+        table.set_base_cost(MagicEffectType::RestoreHealth, 10.);
+        table.set_base_cost(MagicEffectType::FortifyHealth, 0.14);
+        table.add_potion(&vanilla);
+        assert!(table.contains(MagicEffectType::RestoreHealth, false));
+
+        let mut potion = Potion::new(String::from("Healing"), String::from("Healing"));
+        potion.add_effect(make_effect(MagicEffectType::RestoreHealth, 10, 1));
+        potion.use_auto_graphics_from(&table);
+        assert_eq!(potion.model(), Some(r"Clutter\Potions\PotionHealth.NIF"));
+        assert_eq!(potion.texture_hash(), None);
+
+        // a potion added later with the same strongest effect replaces the earlier one
+        let mut replacement = Potion::new(String::from("PotionRestoreHealthM"), String::new());
+        replacement.add_effect(make_effect(MagicEffectType::RestoreHealth, 10, 1));
+        replacement.set_model(Some(String::from(r"Clutter\Potions\PotionHealthM.NIF")));
+        replacement.set_icon(Some(String::from(r"Clutter\Potions\IconPotionHealthM.dds")));
+        table.add_potion(&replacement);
+        potion.use_auto_graphics_from(&table);
+        assert_eq!(potion.model(), Some(r"Clutter\Potions\PotionHealthM.NIF"));
+
+        // a poison with the same effect doesn't use the potion's graphics
+        let mut poison = Potion::new(String::from("Burden"), String::from("Burden"));
+        poison.add_effect(make_effect(MagicEffectType::Burden, 10, 30));
+        poison.use_auto_graphics_from(&table);
+        assert_eq!(poison.model(), Some(r"Clutter\Potions\PotionPoison.NIF"));
+    }
+
+    #[test]
+    fn build_potion() {
+        let potion = Potion::builder(String::from("tcBurden"), String::from("Burden"))
+            .weight(0.5)
+            .value(25)
+            .effect(MagicEffectType::Burden, EffectRange::Touch, 10, 0, 30)
+            .build()
+            .unwrap();
+        assert!(potion.is_poison());
+        assert!(!potion.is_auto_calc);
+        assert_eq!(potion.model(), Some(r"Clutter\Potions\PotionPoison.NIF"));
+
+        let result = Potion::builder(String::from("tcBurdenFood"), String::from("Burden"))
+            .food_item(true)
+            .effect(MagicEffectType::Burden, EffectRange::Touch, 10, 0, 30)
+            .build();
+        assert!(matches!(result, Err(TesError::RequirementFailed(_))));
+
+        let result = Potion::builder(String::from("tcNightEye"), String::from("Night-Eye"))
+            .effect(MagicEffectType::NightEye, EffectRange::Self_, 0, 0, 60)
+            .effect(MagicEffectType::Paralyze, EffectRange::Touch, 10, 0, 5)
+            .build();
+        assert!(matches!(result, Err(TesError::LimitExceeded { .. })));
+
+        assert!(
+            Potion::builder(String::from("tcEmpty"), String::from("Empty"))
+                .build()
+                .is_err()
+        );
+    }
+}
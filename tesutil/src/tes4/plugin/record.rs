@@ -1,8 +1,9 @@
 use std::io::{Cursor, Read, Seek, Write};
+use std::mem;
 
 use super::field::Tes4Field;
 use super::group::Group;
-use super::FormId;
+use super::{FormId, RECORD_SCHEMA};
 use crate::plugin::*;
 use crate::*;
 
@@ -49,6 +50,11 @@ bitflags! {
 
 const COMPRESSION_LEVEL: u32 = 6;
 
+/// Record types that can be flagged as visible when distant
+///
+/// Only placed objects have distant LOD; the flag is meaningless on anything else.
+pub const VISIBLE_WHEN_DISTANT_TYPES: [&[u8; 4]; 1] = [b"REFR"];
+
 /// A game object in a plugin
 ///
 /// A record represents an object in the game, such as an NPC, container, global variable, etc.
@@ -83,6 +89,8 @@ pub struct Tes4Record {
     fields: Vec<Tes4Field>,
     #[brw(ignore)]
     groups: Vec<Group>,
+    #[brw(ignore)]
+    span: Option<RecordSpan>,
 }
 
 impl IntoIterator for Tes4Record {
@@ -95,6 +103,31 @@ impl IntoIterator for Tes4Record {
     }
 }
 
+impl JournalRecord for Tes4Record {
+    /// Copies everything but the record's groups, whose records are journaled individually
+    fn snapshot(&self) -> Tes4Record {
+        Tes4Record {
+            name: self.name,
+            flags: self.flags,
+            form_id: self.form_id,
+            vcs_info: self.vcs_info,
+            status: self.status,
+            raw_data: self.raw_data.clone(),
+            changed: self.changed,
+            fields: self.fields.clone(),
+            groups: vec![],
+            span: self.span,
+        }
+    }
+
+    /// Restores everything but the record's groups, which are left as they are
+    fn restore(&mut self, snapshot: Tes4Record) {
+        let groups = mem::take(&mut self.groups);
+        *self = snapshot;
+        self.groups = groups;
+    }
+}
+
 impl Record<Tes4Field> for Tes4Record {
     /// Reads a record from a binary stream and returns it with the number of bytes read
     ///
@@ -110,7 +143,14 @@ impl Record<Tes4Field> for Tes4Record {
     /// [`std::io::Error`]: https://doc.rust-lang.org/std/io/struct.Error.html
     /// [`Group`']: struct.Group.html
     fn read_lazy<T: Read + Seek>(mut f: T) -> Result<Tes4Record, TesError> {
-        Ok(f.read_le()?)
+        let offset = f.seek(SeekFrom::Current(0))?;
+        let mut record: Tes4Record = f.read_le()?;
+        let end = f.seek(SeekFrom::Current(0))?;
+        record.span = Some(RecordSpan {
+            offset,
+            length: end - offset,
+        });
+        Ok(record)
     }
 
     /// Returns a reference to the record name
@@ -118,6 +158,14 @@ impl Record<Tes4Field> for Tes4Record {
         &self.name
     }
 
+    fn span(&self) -> Option<RecordSpan> {
+        self.span
+    }
+
+    fn data_size(&self) -> usize {
+        self.raw_data.len() + self.fields.iter().map(|f| f.size()).sum::<usize>()
+    }
+
     fn status(&self) -> RecordStatus {
         self.status
     }
@@ -148,15 +196,18 @@ impl Record<Tes4Field> for Tes4Record {
             let mut cursor = Cursor::new(buf);
 
             while size > 0 {
+                let start = cursor.position();
                 match Tes4Field::read(&mut cursor) {
                     Ok(field) => {
-                        let field_size = field.size();
+                        // measure what we actually read rather than using field.size(), because
+                        // plugins may use an XXXX prefix even for fields that don't need it
+                        let field_size = (cursor.position() - start) as usize;
                         if field_size > size {
                             self.status = RecordStatus::Failed;
                             return Err(decode_failed("Field size exceeds record size"));
                         }
 
-                        size -= field.size();
+                        size -= field_size;
                         self.fields.push(field);
                     }
                     Err(e) => {
@@ -193,6 +244,10 @@ impl Record<Tes4Field> for Tes4Record {
         Box::new(self.fields.iter_mut())
     }
 
+    fn field_kind(&self, field_name: &[u8]) -> FieldKind {
+        RECORD_SCHEMA.field_kind(&self.name, field_name)
+    }
+
     /// Writes the record to the provided writer
     ///
     /// Writes a record to any type that implements [`Write`] and [`Seek`] or a mutable reference to such a type.
@@ -238,7 +293,10 @@ impl Record<Tes4Field> for Tes4Record {
                 let mut comp_buf: Vec<u8> = vec![];
                 encoder.read_to_end(&mut comp_buf)?;
 
-                f.write_le(&(comp_buf.len() as u32))?;
+                f.write_le(&checked_len::<u32>(
+                    comp_buf.len(),
+                    "Compressed record data",
+                )?)?;
                 f.write_le(&self.flags.bits)?;
                 f.write_le(&self.form_id.0)?;
                 f.write_le(&self.vcs_info)?;
@@ -302,6 +360,14 @@ impl Tes4Record {
     flag_property!(is_dangerous, set_dangerous, OFF_LIMITS);
     flag_property!(is_off_limits, set_off_limits, OFF_LIMITS);
 
+    /// Is this record flagged as visible when distant even though the flag doesn't apply to it?
+    ///
+    /// Setting the flag on records other than placed objects is a common mistake in mods. The
+    /// game ignores it, but it usually means the author meant to flag something else.
+    pub fn has_invalid_visible_when_distant(&self) -> bool {
+        self.is_visible_when_distant() && !VISIBLE_WHEN_DISTANT_TYPES.contains(&&self.name)
+    }
+
     pub fn uses_compression(&self) -> bool {
         self.flags.contains(RecordFlags::COMPRESSED)
     }
@@ -370,6 +436,11 @@ impl Tes4Record {
         self.groups.push(group);
     }
 
+    /// Gets this record's associated groups
+    pub(crate) fn groups(&self) -> &[Group] {
+        &self.groups
+    }
+
     /// Returns the number of fields currently in the record
     pub fn len(&self) -> usize {
         self.fields.len()
@@ -390,6 +461,17 @@ impl Tes4Record {
         self.changed = true;
         self.fields.clear();
     }
+
+    /// Rearranges this record's fields to match a previously captured field order
+    ///
+    /// # Panics
+    ///
+    /// Panics if this record has not been finalized.
+    pub fn sort_fields(&mut self, order: &FieldOrder) {
+        self.require_finalized();
+        self.changed = true;
+        order.apply(&mut self.fields);
+    }
 }
 
 #[cfg(test)]
@@ -410,6 +492,110 @@ mod tests {
         assert_eq!(record.fields.len(), 3);
     }
 
+    #[test]
+    fn record_span() {
+        let data = b"GLOB\x21\0\0\0\0\0\0\0\x3a\0\0\0\0\0\0\0EDID\x0a\0TimeScale\0FNAM\x01\0sFLTV\x04\0\0\0\xf0\x41".to_vec();
+        let len = data.len() as u64;
+        let mut cursor = Cursor::new(data.repeat(2));
+        let first = Tes4Record::read(&mut cursor).unwrap();
+        let second = Tes4Record::read(&mut cursor).unwrap();
+        assert_eq!(first.span().unwrap().offset, 0);
+        assert_eq!(first.span().unwrap().length, len);
+        assert_eq!(second.span().unwrap().offset, len);
+        assert_eq!(second.span().unwrap().end(), len * 2);
+        assert!(Tes4Record::new(b"GLOB").span().is_none());
+    }
+
+    #[test]
+    fn iter_typed() {
+        let data = b"GLOB\x21\0\0\0\0\0\0\0\x3a\0\0\0\0\0\0\0EDID\x0a\0TimeScale\0FNAM\x01\0sFLTV\x04\0\0\0\xf0\x41".to_vec();
+        let record = Tes4Record::read(Cursor::new(data)).unwrap();
+        let values: Vec<_> = record.iter_typed().map(|(_, v)| v).collect();
+        assert_eq!(
+            values,
+            [
+                FieldValue::String("TimeScale".into()),
+                FieldValue::String("s".into()),
+                FieldValue::Float(30.)
+            ]
+        );
+        assert!(record.check_fields().is_ok());
+
+        let mut record = Tes4Record::new(b"NPC_");
+        record.add_field(Tes4Field::new_u32(b"CNAM", 0x2291d));
+        record.add_field(Tes4Field::new_u16(b"FNAM", 0x1a));
+        record.add_field(Tes4Field::new(b"HNAM", vec![0xff; 3]).unwrap());
+        let values: Vec<_> = record.iter_typed().map(|(_, v)| v).collect();
+        assert_eq!(
+            values,
+            [
+                FieldValue::FormId(0x2291d),
+                FieldValue::Int(0x1a),
+                FieldValue::Bytes(&[0xff; 3])
+            ]
+        );
+        // HNAM should be a single form ID
+        assert!(record.check_fields().is_err());
+
+        // but races have lists of hairs and eyes
+        let mut record = Tes4Record::new(b"RACE");
+        record.add_field(Tes4Field::new_zstring(b"FULL", String::from("Nord")).unwrap());
+        record.add_field(Tes4Field::new(b"HNAM", vec![1, 0, 0, 0, 2, 0, 0, 0]).unwrap());
+        record.add_field(Tes4Field::new_u32(b"ENAM", 3));
+        record.add_field(Tes4Field::new(b"DESC", b"Caf\xe9 \x93Nord\x94\0".to_vec()).unwrap());
+        let values: Vec<_> = record.iter_typed().map(|(_, v)| v).collect();
+        assert_eq!(
+            values,
+            [
+                FieldValue::String("Nord".into()),
+                FieldValue::FormIds(vec![1, 2]),
+                FieldValue::FormIds(vec![3]),
+                FieldValue::String("Café “Nord”".into()),
+            ]
+        );
+        assert!(record.check_fields().is_ok());
+    }
+
+    #[test]
+    fn invalid_visible_when_distant() {
+        let mut record = Tes4Record::new(b"REFR");
+        record.set_visible_when_distant(true);
+        assert!(!record.has_invalid_visible_when_distant());
+
+        let mut record = Tes4Record::new(b"ACHR");
+        assert!(!record.has_invalid_visible_when_distant());
+        record.set_visible_when_distant(true);
+        assert!(record.has_invalid_visible_when_distant());
+    }
+
+    #[test]
+    fn read_write_long_field() {
+        let mut record = Tes4Record::new(b"SCPT");
+        record.add_field(Tes4Field::new(b"SCDA", vec![0xab; 70000]).unwrap());
+        record.add_field(Tes4Field::new(b"SCTX", b"\0".to_vec()).unwrap());
+
+        let mut writer = Cursor::new(vec![]);
+        record.write(&mut writer).unwrap();
+        let data = writer.into_inner();
+        assert_eq!(&data[20..30], b"XXXX\x04\0\x70\x11\x01\0");
+
+        let mut record = Tes4Record::read_lazy(Cursor::new(data)).unwrap();
+        record.finalize().unwrap();
+        assert_eq!(record.fields.len(), 2);
+        assert_eq!(record.fields[0].name(), b"SCDA");
+        assert_eq!(record.fields[0].get().len(), 70000);
+        assert_eq!(record.fields[1].name(), b"SCTX");
+    }
+
+    #[test]
+    fn read_unnecessary_xxxx() {
+        let data = b"GLOB\x1c\0\0\0\0\0\0\0\x3a\0\0\0\0\0\0\0XXXX\x04\0\x02\0\0\0FNAM\0\0s\0FLTV\x04\0\0\0\xf0\x41".to_vec();
+        let mut record = Tes4Record::read_lazy(Cursor::new(data)).unwrap();
+        record.finalize().unwrap();
+        assert_eq!(record.fields.len(), 2);
+        assert_eq!(record.fields[0].get(), b"s\0");
+    }
+
     #[test]
     fn write_record() {
         let mut record = Tes4Record::new(b"DIAL");
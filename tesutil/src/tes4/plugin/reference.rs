@@ -0,0 +1,387 @@
+use crate::tes4::{FormId, Tes4Field, Tes4Record};
+use crate::{decode_failed, ExtraFields, Field, FieldOrder, Form, Record, TesError};
+use binrw::{BinReaderExt, BinWriterExt};
+use std::io::{Cursor, Seek, SeekFrom, Write};
+
+/// Ownership of a placed reference
+///
+/// Taking or using an owned reference is a crime unless the player is the owner, is a member of
+/// the owning faction with at least the required rank, or the ownership global variable is set.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Ownership {
+    /// Form ID of the owning NPC or faction
+    pub owner: FormId,
+    /// Minimum rank in the owning faction required to use the reference
+    ///
+    /// This is only meaningful if the owner is a faction.
+    pub faction_rank: Option<i32>,
+    /// Form ID of a global variable that, when non-zero, makes the player an owner
+    pub global: Option<FormId>,
+}
+
+impl Ownership {
+    /// Creates ownership by an NPC or faction
+    pub fn new(owner: FormId) -> Ownership {
+        Ownership {
+            owner,
+            faction_rank: None,
+            global: None,
+        }
+    }
+}
+
+/// Lock on a placed reference
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Lock {
+    /// Lock level from 0 to 100, where 100 can only be opened with the key
+    pub level: u8,
+    /// Form ID of the key that opens the lock
+    pub key: Option<FormId>,
+    /// Whether the lock level scales with the player's level
+    pub leveled: bool,
+}
+
+/// A reference whose enabled state controls another reference's
+///
+/// Oblivion references have no activation parents or scripts of their own; a reference runs the
+/// script of its base object, so the enable parent is the only link between references in a
+/// plugin. The state of the base object's script is saved with the reference's change record;
+/// see [`InventoryItem::script`] and [`PlayerReferenceChange::script`].
+///
+/// [`InventoryItem::script`]: save/struct.InventoryItem.html#method.script
+/// [`PlayerReferenceChange::script`]: save/struct.PlayerReferenceChange.html#method.script
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct EnableParent {
+    /// Form ID of the parent reference
+    pub reference: FormId,
+    /// Whether the child is enabled when the parent is disabled, and vice versa
+    pub opposite: bool,
+}
+
+const ENABLE_PARENT_OPPOSITE: u8 = 0x01;
+const ENABLE_PARENT_SIZE: usize = 8;
+
+const LOCK_LEVELED: u8 = 0x04;
+// XLOC is either 12 or 16 bytes; the longer form has 4 unused bytes between the key and the flags
+const LOCK_SIZE: usize = 12;
+const LOCK_SIZE_LONG: usize = 16;
+
+/// An object placed in the world
+///
+/// Only the fields needed for the typed accessors are decoded; all other fields are preserved
+/// as-is.
+#[derive(Debug, Default)]
+pub struct Reference {
+    editor_id: Option<String>,
+    base: FormId,
+    ownership: Option<Ownership>,
+    lock: Option<Lock>,
+    lock_size: usize,
+    enable_parent: Option<EnableParent>,
+    visible_when_distant: bool,
+    extra_fields: Vec<Tes4Field>,
+    field_order: FieldOrder,
+}
+
+impl Reference {
+    /// Creates a new reference to a base object
+    pub fn new(base: FormId) -> Reference {
+        Reference {
+            base,
+            lock_size: LOCK_SIZE,
+            ..Reference::default()
+        }
+    }
+
+    pub fn editor_id(&self) -> Option<&str> {
+        self.editor_id.as_deref()
+    }
+
+    pub fn set_editor_id(&mut self, editor_id: Option<String>) {
+        self.editor_id = editor_id;
+    }
+
+    /// Gets the form ID of the base object this is a reference to
+    pub fn base(&self) -> FormId {
+        self.base
+    }
+
+    pub fn set_base(&mut self, base: FormId) {
+        self.base = base;
+    }
+
+    pub fn ownership(&self) -> Option<&Ownership> {
+        self.ownership.as_ref()
+    }
+
+    pub fn ownership_mut(&mut self) -> Option<&mut Ownership> {
+        self.ownership.as_mut()
+    }
+
+    pub fn set_ownership(&mut self, ownership: Option<Ownership>) {
+        self.ownership = ownership;
+    }
+
+    pub fn lock(&self) -> Option<&Lock> {
+        self.lock.as_ref()
+    }
+
+    pub fn lock_mut(&mut self) -> Option<&mut Lock> {
+        self.lock.as_mut()
+    }
+
+    pub fn set_lock(&mut self, lock: Option<Lock>) {
+        self.lock = lock;
+    }
+
+    pub fn enable_parent(&self) -> Option<&EnableParent> {
+        self.enable_parent.as_ref()
+    }
+
+    pub fn set_enable_parent(&mut self, enable_parent: Option<EnableParent>) {
+        self.enable_parent = enable_parent;
+    }
+
+    /// Is the reference drawn using its distant LOD model when its cell isn't loaded?
+    pub fn is_visible_when_distant(&self) -> bool {
+        self.visible_when_distant
+    }
+
+    pub fn set_visible_when_distant(&mut self, value: bool) {
+        self.visible_when_distant = value;
+    }
+
+    fn ownership_or_err(&mut self, field: &Tes4Field) -> Result<&mut Ownership, TesError> {
+        self.ownership.as_mut().ok_or_else(|| {
+            decode_failed(format!(
+                "Orphaned {} in REFR without XOWN",
+                field.name_as_str()
+            ))
+        })
+    }
+}
+
+impl ExtraFields for Reference {
+    type Field = Tes4Field;
+
+    fn extra_fields(&self) -> &[Tes4Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes4Field> {
+        &mut self.extra_fields
+    }
+}
+
+impl Form for Reference {
+    type Field = Tes4Field;
+    type Record = Tes4Record;
+    const RECORD_TYPE: &'static [u8; 4] = b"REFR";
+
+    fn read(record: &Self::Record) -> Result<Self, TesError> {
+        Reference::assert(record)?;
+
+        let mut reference = Reference::new(FormId(0));
+        reference.visible_when_distant = record.is_visible_when_distant();
+        for field in record.iter() {
+            match field.name() {
+                b"EDID" => reference.editor_id = Some(String::from(field.get_zstring()?)),
+                b"NAME" => reference.base = FormId(field.get_u32()?),
+                b"XOWN" => reference.ownership = Some(Ownership::new(FormId(field.get_u32()?))),
+                b"XRNK" => reference.ownership_or_err(field)?.faction_rank = Some(field.get_i32()?),
+                b"XGLB" => {
+                    reference.ownership_or_err(field)?.global = Some(FormId(field.get_u32()?))
+                }
+                b"XLOC" => {
+                    let size = field.get().len();
+                    if size != LOCK_SIZE && size != LOCK_SIZE_LONG {
+                        return Err(decode_failed(format!("Unexpected XLOC size {}", size)));
+                    }
+
+                    let mut reader = field.reader();
+                    let level = reader.read_le()?;
+                    reader.seek(SeekFrom::Current(3))?;
+                    let key: u32 = reader.read_le()?;
+                    if size == LOCK_SIZE_LONG {
+                        reader.seek(SeekFrom::Current(4))?;
+                    }
+                    let flags: u8 = reader.read_le()?;
+
+                    reference.lock = Some(Lock {
+                        level,
+                        key: if key == 0 { None } else { Some(FormId(key)) },
+                        leveled: flags & LOCK_LEVELED != 0,
+                    });
+                    reference.lock_size = size;
+                }
+                b"XESP" => {
+                    let size = field.get().len();
+                    if size != ENABLE_PARENT_SIZE {
+                        return Err(decode_failed(format!("Unexpected XESP size {}", size)));
+                    }
+
+                    let mut reader = field.reader();
+                    let parent: u32 = reader.read_le()?;
+                    let flags: u8 = reader.read_le()?;
+                    reference.enable_parent = Some(EnableParent {
+                        reference: FormId(parent),
+                        opposite: flags & ENABLE_PARENT_OPPOSITE != 0,
+                    });
+                }
+                _ => reference.extra_fields.push(field.clone()),
+            }
+        }
+
+        reference.field_order = FieldOrder::from_record(record);
+
+        Ok(reference)
+    }
+
+    fn write(&self, record: &mut Self::Record) -> Result<(), TesError> {
+        Reference::assert(record)?;
+
+        record.clear();
+        record.set_visible_when_distant(self.visible_when_distant);
+
+        if let Some(ref editor_id) = self.editor_id {
+            record.add_field(Tes4Field::new_zstring(b"EDID", editor_id.clone())?);
+        }
+
+        record.add_field(Tes4Field::new_u32(b"NAME", self.base.0));
+
+        if let Some(ref ownership) = self.ownership {
+            record.add_field(Tes4Field::new_u32(b"XOWN", ownership.owner.0));
+            if let Some(rank) = ownership.faction_rank {
+                record.add_field(Tes4Field::new_i32(b"XRNK", rank));
+            }
+            if let Some(global) = ownership.global {
+                record.add_field(Tes4Field::new_u32(b"XGLB", global.0));
+            }
+        }
+
+        if let Some(ref lock) = self.lock {
+            let mut buf = vec![];
+            let mut writer = Cursor::new(&mut buf);
+            writer.write_le(&lock.level)?;
+            writer.write_all(&[0; 3])?;
+            writer.write_le(&lock.key.unwrap_or(FormId(0)).0)?;
+            if self.lock_size == LOCK_SIZE_LONG {
+                writer.write_all(&[0; 4])?;
+            }
+            writer.write_le(&if lock.leveled { LOCK_LEVELED } else { 0 })?;
+            writer.write_all(&[0; 3])?;
+            record.add_field(Tes4Field::new(b"XLOC", buf)?);
+        }
+
+        if let Some(ref enable_parent) = self.enable_parent {
+            let mut buf = vec![];
+            let mut writer = Cursor::new(&mut buf);
+            writer.write_le(&enable_parent.reference.0)?;
+            writer.write_le(&if enable_parent.opposite {
+                ENABLE_PARENT_OPPOSITE
+            } else {
+                0
+            })?;
+            writer.write_all(&[0; 3])?;
+            record.add_field(Tes4Field::new(b"XESP", buf)?);
+        }
+
+        for field in &self.extra_fields {
+            record.add_field(field.clone());
+        }
+        record.sort_fields(&self.field_order);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_write_ownership_and_lock() {
+        let mut record = Tes4Record::new(b"REFR");
+        record.add_field(Tes4Field::new_u32(b"NAME", 0x0002f1a7));
+        record.add_field(Tes4Field::new(b"DATA", vec![0; 24]).unwrap());
+        record.add_field(Tes4Field::new_u32(b"XOWN", 0x00023e75));
+        record.add_field(Tes4Field::new_i32(b"XRNK", 2));
+        record.add_field(
+            Tes4Field::new(b"XLOC", vec![50, 0, 0, 0, 0x0a, 0x7a, 0x01, 0, 4, 0, 0, 0]).unwrap(),
+        );
+
+        let mut reference = Reference::read(&record).unwrap();
+        assert_eq!(reference.base(), FormId(0x0002f1a7));
+        let ownership = reference.ownership().unwrap();
+        assert_eq!(ownership.owner, FormId(0x00023e75));
+        assert_eq!(ownership.faction_rank, Some(2));
+        assert_eq!(ownership.global, None);
+        assert_eq!(
+            reference.lock(),
+            Some(&Lock {
+                level: 50,
+                key: Some(FormId(0x00017a0a)),
+                leveled: true,
+            })
+        );
+
+        assert!(!reference.is_visible_when_distant());
+
+        reference.set_ownership(Some(Ownership::new(FormId(0x7))));
+        reference.lock_mut().unwrap().key = None;
+        reference.set_visible_when_distant(true);
+        reference.write(&mut record).unwrap();
+        assert!(record.is_visible_when_distant());
+
+        let names: Vec<_> = record.iter().map(|f| f.name_as_str()).collect();
+        assert_eq!(names, ["NAME", "DATA", "XOWN", "XLOC"]);
+
+        let reference = Reference::read(&record).unwrap();
+        assert_eq!(reference.ownership(), Some(&Ownership::new(FormId(0x7))));
+        assert_eq!(reference.lock().unwrap().key, None);
+        assert!(reference.is_visible_when_distant());
+        assert_eq!(reference.extra_fields().len(), 1);
+    }
+
+    #[test]
+    fn read_write_enable_parent() {
+        let mut record = Tes4Record::new(b"REFR");
+        record.add_field(Tes4Field::new_u32(b"NAME", 0x0002f1a7));
+        record.add_field(Tes4Field::new(b"XESP", vec![0x2c, 0x9f, 0x01, 0, 1, 0, 0, 0]).unwrap());
+        record.add_field(Tes4Field::new(b"DATA", vec![0; 24]).unwrap());
+
+        let mut reference = Reference::read(&record).unwrap();
+        assert_eq!(
+            reference.enable_parent(),
+            Some(&EnableParent {
+                reference: FormId(0x00019f2c),
+                opposite: true,
+            })
+        );
+
+        reference.set_enable_parent(Some(EnableParent {
+            reference: FormId(0x00019f2d),
+            opposite: false,
+        }));
+        reference.write(&mut record).unwrap();
+
+        let names: Vec<_> = record.iter().map(|f| f.name_as_str()).collect();
+        assert_eq!(names, ["NAME", "XESP", "DATA"]);
+        let reference = Reference::read(&record).unwrap();
+        assert_eq!(
+            reference.enable_parent().unwrap().reference,
+            FormId(0x00019f2d)
+        );
+        assert!(!reference.enable_parent().unwrap().opposite);
+
+        record.add_field(Tes4Field::new(b"XESP", vec![0; 4]).unwrap());
+        assert!(Reference::read(&record).is_err());
+    }
+
+    #[test]
+    fn orphaned_rank() {
+        let mut record = Tes4Record::new(b"REFR");
+        record.add_field(Tes4Field::new_i32(b"XRNK", 2));
+        assert!(Reference::read(&record).is_err());
+    }
+}
@@ -1,22 +1,10 @@
 use crate::tes4::{FormId, Item, Tes4Field, Tes4Record};
-use crate::{decode_failed_because, Field, Form, Record, TesError};
-use binrw::{binrw, BinReaderExt};
-use enum_map::Enum;
-use num_enum::TryFromPrimitive;
-
-#[binrw]
-#[derive(Debug, Default, Enum, TryFromPrimitive)]
-#[repr(u8)]
-#[brw(repr = u8)]
-pub enum SoulType {
-    #[default]
-    None,
-    Petty,
-    Lesser,
-    Common,
-    Greater,
-    Grand,
-}
+use crate::{ExtraFields, Field, Form, Record, SoulSize, TesError};
+use binrw::BinReaderExt;
+
+/// The size of a soul, now shared between games
+#[deprecated(note = "use tesutil::SoulSize instead")]
+pub type SoulType = SoulSize;
 
 #[derive(Debug, Default)]
 pub struct SoulGem {
@@ -24,13 +12,36 @@ pub struct SoulGem {
     name: String,
     value: u32,
     weight: f32,
-    contained_soul: SoulType,
-    max_soul: SoulType,
+    contained_soul: SoulSize,
+    max_soul: SoulSize,
     model: Option<String>,
     bound_radius: Option<f32>,
     texture_hash: Option<Vec<u8>>,
     icon: Option<String>,
     script: Option<FormId>,
+    extra_fields: Vec<Tes4Field>,
+}
+
+impl SoulGem {
+    /// Gets the size of the soul the soul gem comes with
+    pub fn contained_soul(&self) -> SoulSize {
+        self.contained_soul
+    }
+
+    /// Sets the size of the soul the soul gem comes with
+    pub fn set_contained_soul(&mut self, soul: SoulSize) {
+        self.contained_soul = soul;
+    }
+
+    /// Gets the size of the largest soul the soul gem can hold
+    pub fn max_soul(&self) -> SoulSize {
+        self.max_soul
+    }
+
+    /// Sets the size of the largest soul the soul gem can hold
+    pub fn set_max_soul(&mut self, soul: SoulSize) {
+        self.max_soul = soul;
+    }
 }
 
 impl Item for SoulGem {
@@ -107,6 +118,18 @@ impl Item for SoulGem {
     }
 }
 
+impl ExtraFields for SoulGem {
+    type Field = Tes4Field;
+
+    fn extra_fields(&self) -> &[Tes4Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes4Field> {
+        &mut self.extra_fields
+    }
+}
+
 impl Form for SoulGem {
     type Field = Tes4Field;
     type Record = Tes4Record;
@@ -123,16 +146,8 @@ impl Form for SoulGem {
                     soul_gem.value = reader.read_le()?;
                     soul_gem.weight = reader.read_le()?;
                 }
-                b"SOUL" => {
-                    soul_gem.contained_soul = SoulType::try_from(field.get_u8()?).map_err(|e| {
-                        decode_failed_because("Invalid contained soul type in soul game", e)
-                    })?
-                }
-                b"SLCP" => {
-                    soul_gem.max_soul = SoulType::try_from(field.get_u8()?).map_err(|e| {
-                        decode_failed_because("Invalid max soul type in soul game", e)
-                    })?
-                }
+                b"SOUL" => soul_gem.contained_soul = SoulSize::from_tes4(field.get_u8()?)?,
+                b"SLCP" => soul_gem.max_soul = SoulSize::from_tes4(field.get_u8()?)?,
                 _ => soul_gem.read_item_field(&field)?,
             }
         }
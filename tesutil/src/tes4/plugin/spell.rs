@@ -1,8 +1,11 @@
 use std::convert::TryFrom;
 use std::io::Cursor;
 
-use crate::tes4::{Magic, SpellEffect, Tes4Field, Tes4Record};
-use crate::{decode_failed, decode_failed_because, Field, Form, Record, TesError};
+use crate::tes4::{effect_index_error, Magic, MagicEffectType, SpellEffect, Tes4Field, Tes4Record};
+use crate::{
+    decode_failed, decode_failed_because, EffectRange, ExtraFields, Field, FieldOrder, Form,
+    Record, TesError,
+};
 
 use binrw::{BinReaderExt, BinWriterExt};
 use bitflags::bitflags;
@@ -51,6 +54,8 @@ pub struct Spell {
     pub level: SpellLevel,
     flags: SpellFlags,
     effects: Vec<SpellEffect>,
+    extra_fields: Vec<Tes4Field>,
+    field_order: FieldOrder,
 }
 
 impl Spell {
@@ -64,9 +69,29 @@ impl Spell {
             level: SpellLevel::Novice,
             flags: SpellFlags::empty(),
             effects: vec![],
+            extra_fields: vec![],
+            field_order: FieldOrder::new(),
         }
     }
 
+    /// Starts building a new spell
+    pub fn builder(editor_id: String) -> SpellBuilder {
+        SpellBuilder {
+            spell: Spell::new(Some(editor_id), None),
+            error: None,
+        }
+    }
+
+    /// Gets the spell's editor ID
+    pub fn editor_id(&self) -> Option<&str> {
+        self.editor_id.as_deref()
+    }
+
+    /// Sets the spell's editor ID
+    pub fn set_editor_id(&mut self, editor_id: Option<String>) {
+        self.editor_id = editor_id;
+    }
+
     /// Is this spell's cost auto-calculated?
     pub fn is_auto_calc(&self) -> bool {
         !self.flags.contains(SpellFlags::MANUAL_SPELL_COST)
@@ -134,6 +159,98 @@ impl Spell {
     }
 }
 
+/// Builds a spell, validating it once all its settings have been provided
+///
+/// Errors from individual settings are held until [`build`] is called, so a spell can be described
+/// in a single chain of calls.
+///
+/// [`build`]: #method.build
+#[derive(Debug)]
+pub struct SpellBuilder {
+    spell: Spell,
+    error: Option<TesError>,
+}
+
+impl SpellBuilder {
+    /// Sets the spell's name
+    pub fn name(mut self, name: String) -> SpellBuilder {
+        self.spell.name = Some(name);
+        self
+    }
+
+    /// Sets the spell's type
+    pub fn spell_type(mut self, spell_type: SpellType) -> SpellBuilder {
+        self.spell.spell_type = spell_type;
+        self
+    }
+
+    /// Sets the spell's mastery level
+    pub fn level(mut self, level: SpellLevel) -> SpellBuilder {
+        self.spell.level = level;
+        self
+    }
+
+    /// Sets a manual magicka cost for the spell instead of having it auto-calculated
+    pub fn cost(mut self, cost: u32) -> SpellBuilder {
+        self.spell.cost = cost;
+        self.spell.set_auto_calc(false);
+        self
+    }
+
+    /// Sets whether the spell is immune to silence
+    pub fn immune_to_silence(mut self, value: bool) -> SpellBuilder {
+        self.spell.set_immune_to_silence(value);
+        self
+    }
+
+    /// Sets whether the player starts with the spell
+    pub fn player_start_spell(mut self, value: bool) -> SpellBuilder {
+        self.spell.set_player_start_spell(value);
+        self
+    }
+
+    /// Adds an effect to the spell
+    ///
+    /// The effect is checked as by [`SpellEffect::with_values`].
+    ///
+    /// [`SpellEffect::with_values`]: struct.SpellEffect.html#method.with_values
+    pub fn effect(
+        mut self,
+        effect: MagicEffectType,
+        range: EffectRange,
+        magnitude: u32,
+        area: u32,
+        duration: u32,
+    ) -> SpellBuilder {
+        match SpellEffect::with_values(effect, range, magnitude, area, duration) {
+            Ok(spell_effect) => self.spell.add_effect(spell_effect),
+            Err(e) => {
+                self.error.get_or_insert(e);
+            }
+        }
+        self
+    }
+
+    /// Builds the spell
+    ///
+    /// # Errors
+    ///
+    /// Fails if any effect was invalid or if the spell has no effects.
+    pub fn build(self) -> Result<Spell, TesError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+
+        if self.spell.effects.is_empty() {
+            return Err(TesError::RequirementFailed(String::from(
+                "Spell must have at least one effect",
+            )));
+        }
+
+        Ok(self.spell)
+    }
+}
+
 impl Magic for Spell {
     fn iter_effects(&self) -> Box<dyn Iterator<Item = &SpellEffect> + '_> {
         Box::new(self.effects.iter())
@@ -147,6 +264,23 @@ impl Magic for Spell {
         self.effects.push(effect);
     }
 
+    fn insert_effect(&mut self, index: usize, effect: SpellEffect) -> Result<(), TesError> {
+        if index > self.effects.len() {
+            return Err(effect_index_error(index, self.effects.len()));
+        }
+
+        self.effects.insert(index, effect);
+        Ok(())
+    }
+
+    fn remove_effect(&mut self, index: usize) -> Option<SpellEffect> {
+        if index < self.effects.len() {
+            Some(self.effects.remove(index))
+        } else {
+            None
+        }
+    }
+
     fn name(&self) -> Option<&str> {
         self.name.as_deref()
     }
@@ -156,6 +290,18 @@ impl Magic for Spell {
     }
 }
 
+impl ExtraFields for Spell {
+    type Field = Tes4Field;
+
+    fn extra_fields(&self) -> &[Tes4Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes4Field> {
+        &mut self.extra_fields
+    }
+}
+
 impl Form for Spell {
     type Field = Tes4Field;
     type Record = Tes4Record;
@@ -184,6 +330,8 @@ impl Form for Spell {
             }
         }
 
+        spell.field_order = FieldOrder::from_record(record);
+
         Ok(spell)
     }
 
@@ -214,6 +362,41 @@ impl Form for Spell {
 
         self.write_magic_effects(record)?;
 
+        for field in &self.extra_fields {
+            record.add_field(field.clone());
+        }
+        record.sort_fields(&self.field_order);
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_spell() {
+        let spell = Spell::builder(String::from("tcFlameBolt"))
+            .name(String::from("Flame Bolt"))
+            .level(SpellLevel::Apprentice)
+            .effect(MagicEffectType::FireDamage, EffectRange::Target, 20, 5, 1)
+            .effect(MagicEffectType::Burden, EffectRange::Target, 30, 5, 10)
+            .build()
+            .unwrap();
+        assert_eq!(spell.editor_id(), Some("tcFlameBolt"));
+        assert_eq!(spell.effect_count(), 2);
+        assert!(spell.is_auto_calc());
+
+        // the first invalid effect is reported even though later calls succeed
+        let result = Spell::builder(String::from("tcBadSpell"))
+            .effect(MagicEffectType::FireDamage, EffectRange::Self_, 20, 5, 1)
+            .effect(MagicEffectType::Burden, EffectRange::Target, 30, 5, 10)
+            .build();
+        assert!(matches!(result, Err(TesError::LimitExceeded { .. })));
+
+        assert!(Spell::builder(String::from("tcEmptySpell"))
+            .build()
+            .is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use crate::tes4::{Enchantable, EnchantmentType, FormId, Item, Tes4Field, Tes4Record};
-use crate::{Field, Form, Record, TesError};
+use crate::{ExtraFields, Field, FieldOrder, Form, Record, TesError};
 use binrw::{binrw, BinReaderExt, BinWriterExt};
 use std::io::Cursor;
 
@@ -44,6 +44,8 @@ pub struct Weapon {
     enchantment_points: Option<u32>,
     enchantment: Option<FormId>,
     pub data: WeaponData,
+    extra_fields: Vec<Tes4Field>,
+    field_order: FieldOrder,
 }
 
 impl Item for Weapon {
@@ -120,6 +122,18 @@ impl Item for Weapon {
     }
 }
 
+impl ExtraFields for Weapon {
+    type Field = Tes4Field;
+
+    fn extra_fields(&self) -> &[Tes4Field] {
+        &self.extra_fields
+    }
+
+    fn extra_fields_mut(&mut self) -> &mut Vec<Tes4Field> {
+        &mut self.extra_fields
+    }
+}
+
 impl Form for Weapon {
     type Field = Tes4Field;
     type Record = Tes4Record;
@@ -138,6 +152,8 @@ impl Form for Weapon {
             }
         }
 
+        weapon.field_order = FieldOrder::from_record(record);
+
         Ok(weapon)
     }
 
@@ -164,6 +180,11 @@ impl Form for Weapon {
         cursor.write_le(&self.data)?;
         record.add_field(Tes4Field::new(b"DATA", buf)?);
 
+        for field in &self.extra_fields {
+            record.add_field(field.clone());
+        }
+        record.sort_fields(&self.field_order);
+
         Ok(())
     }
 }
@@ -0,0 +1,132 @@
+use binrw::binrw;
+use bitflags::bitflags;
+
+bitflags! {
+    /// The places on a character's body that a piece of armor or clothing covers
+    ///
+    /// A character can only wear one item in each place, so equipping an item unequips anything
+    /// else covering any of the same places.
+    #[derive(Default)]
+    pub struct BipedSlots: u16 {
+        const HEAD = 0x0001;
+        const HAIR = 0x0002;
+        const UPPER_BODY = 0x0004;
+        const LOWER_BODY = 0x0008;
+        const HAND = 0x0010;
+        const FOOT = 0x0020;
+        const RIGHT_RING = 0x0040;
+        const LEFT_RING = 0x0080;
+        const AMULET = 0x0100;
+        const WEAPON = 0x0200;
+        const BACK_WEAPON = 0x0400;
+        const SIDE_WEAPON = 0x0800;
+        const QUIVER = 0x1000;
+        const SHIELD = 0x2000;
+        const TORCH = 0x4000;
+        const TAIL = 0x8000;
+    }
+}
+
+const HIDE_RINGS: u8 = 0x01;
+const HIDE_AMULET: u8 = 0x02;
+const NON_PLAYABLE: u8 = 0x40;
+const HEAVY_ARMOR: u8 = 0x80;
+
+/// How a piece of armor or clothing is worn, as stored in its BMDT field
+#[binrw]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct BipedData {
+    #[br(map = BipedSlots::from_bits_truncate)]
+    #[bw(map = |s| s.bits)]
+    pub slots: BipedSlots,
+    flags: u8,
+    unused: u8,
+}
+
+impl BipedData {
+    pub fn new(slots: BipedSlots) -> BipedData {
+        BipedData {
+            slots,
+            ..BipedData::default()
+        }
+    }
+
+    fn set_flag(&mut self, flag: u8, value: bool) {
+        if value {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+    }
+
+    /// Is this a ring or amulet?
+    pub fn is_accessory(&self) -> bool {
+        self.slots
+            .intersects(BipedSlots::RIGHT_RING | BipedSlots::LEFT_RING | BipedSlots::AMULET)
+    }
+
+    /// Does wearing this item hide any rings the character is wearing?
+    pub fn hides_rings(&self) -> bool {
+        self.flags & HIDE_RINGS != 0
+    }
+
+    pub fn set_hides_rings(&mut self, value: bool) {
+        self.set_flag(HIDE_RINGS, value);
+    }
+
+    /// Does wearing this item hide any amulet the character is wearing?
+    pub fn hides_amulet(&self) -> bool {
+        self.flags & HIDE_AMULET != 0
+    }
+
+    pub fn set_hides_amulet(&mut self, value: bool) {
+        self.set_flag(HIDE_AMULET, value);
+    }
+
+    /// Can the player wear this item?
+    pub fn is_playable(&self) -> bool {
+        self.flags & NON_PLAYABLE == 0
+    }
+
+    pub fn set_playable(&mut self, value: bool) {
+        self.set_flag(NON_PLAYABLE, !value);
+    }
+
+    /// Is this heavy armor, as opposed to light armor? Meaningless for clothing.
+    pub fn is_heavy_armor(&self) -> bool {
+        self.flags & HEAVY_ARMOR != 0
+    }
+
+    pub fn set_heavy_armor(&mut self, value: bool) {
+        self.set_flag(HEAVY_ARMOR, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use binrw::{BinReaderExt, BinWriterExt};
+    use std::io::Cursor;
+
+    #[test]
+    fn read_write_biped_data() {
+        // a heavy cuirass that hides the amulet
+        let bmdt = [0x04u8, 0x00, 0x82, 0x00];
+        let mut data: BipedData = Cursor::new(&bmdt).read_le().unwrap();
+        assert_eq!(data.slots, BipedSlots::UPPER_BODY);
+        assert!(data.is_heavy_armor());
+        assert!(data.hides_amulet());
+        assert!(!data.hides_rings());
+        assert!(data.is_playable());
+        assert!(!data.is_accessory());
+
+        data.slots = BipedSlots::RIGHT_RING | BipedSlots::LEFT_RING;
+        data.set_heavy_armor(false);
+        data.set_playable(false);
+        assert!(data.is_accessory());
+
+        let mut buf = vec![];
+        Cursor::new(&mut buf).write_le(&data).unwrap();
+        assert_eq!(buf, [0xc0, 0x00, 0x42, 0x00]);
+    }
+}
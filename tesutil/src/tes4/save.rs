@@ -1,8 +1,13 @@
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
-use std::path::Path;
-use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::thread;
+use std::time::Duration;
 
 use crate::tes4::{FormId, Tes4Field, Tes4Record};
 use crate::*;
@@ -19,12 +24,18 @@ pub use actor::*;
 mod actorref;
 pub use actorref::*;
 
-/// Form ID of the player's base record
-pub const FORM_PLAYER: FormId = FormId(7);
-/// Form ID of the player's reference
-pub const FORM_PLAYER_REF: FormId = FormId(0x14);
-/// Form ID of the player's custom class
-pub const FORM_PLAYER_CUSTOM_CLASS: FormId = FormId(0x00022843);
+mod editor;
+pub use editor::*;
+
+// the player forms and gold were originally defined here, so keep them available from here
+pub use crate::tes4::{FORM_GOLD, FORM_PLAYER, FORM_PLAYER_CUSTOM_CLASS, FORM_PLAYER_REF};
+
+/// Irefs above this are form IDs themselves rather than indexes into the save's form ID list
+const MAX_IREF: u32 = 0xff000000;
+/// The fewest forms worth handing to their own thread in [`Save::add_forms`]
+const MIN_BATCH_CHUNK: usize = 64;
+/// Largest screenshot we'll read; the game's own are a small fraction of this
+const MAX_SCREENSHOT_SIZE: usize = 0x1000000;
 
 /// An Oblivion save game
 ///
@@ -61,16 +72,40 @@ pub struct Save {
     weather_data: Vec<u8>,
     player_combat_count: u32,
     created_ids: Vec<FormId>,
-    created_records: HashMap<FormId, RwLock<Tes4Record>>,
+    created_records: HashMap<FormId, Arc<RwLock<Tes4Record>>>,
     quick_keys: Vec<Option<u32>>,
     reticle_data: Vec<u8>,
     interface_data: Vec<u8>,
     region_data: Vec<u8>,
     change_ids: Vec<FormId>,
-    change_records: HashMap<FormId, ChangeRecord>,
+    change_records: HashMap<FormId, Arc<RwLock<ChangeRecord>>>,
     temporary_effects: Vec<u8>,
     form_ids: Vec<FormId>,
     world_spaces: Vec<u32>,
+    journal: Option<SaveJournal>,
+}
+
+/// The changes made to a save's records while it's being edited
+///
+/// Created records and change records are different types, so each has its own [`Journal`].
+///
+/// [`Journal`]: ../struct.Journal.html
+#[derive(Debug, Default)]
+pub struct SaveJournal {
+    created: Journal<Tes4Record>,
+    changes: Journal<ChangeRecord>,
+}
+
+impl SaveJournal {
+    /// Gets the changes made to the save's created records, such as custom spells
+    pub fn created(&self) -> &Journal<Tes4Record> {
+        &self.created
+    }
+
+    /// Gets the changes made to the save's change records
+    pub fn changes(&self) -> &Journal<ChangeRecord> {
+        &self.changes
+    }
 }
 
 // creating a new save from scratch isn't currently supported, so no need for this
@@ -105,12 +140,7 @@ impl Save {
         let mut game_time = [0u8; 16];
         f.read_exact(&mut game_time)?;
 
-        let screen_size = f.read_le::<u32>()? as usize;
-        let screen_width = f.read_le()?;
-        let screen_height = f.read_le()?;
-        // - 8 because we already read the width and height
-        let mut screen_data = vec![0u8; screen_size - 8];
-        f.read_exact(&mut screen_data)?;
+        let (screen_width, screen_height, screen_data) = Save::read_screenshot(&mut f)?;
 
         let num_plugins = f.read_le::<u8>()? as usize;
         let mut plugins = Vec::with_capacity(num_plugins);
@@ -169,7 +199,7 @@ impl Save {
             let record = Tes4Record::read(&mut f)?;
             let form_id = record.id();
             created_ids.push(form_id);
-            created_records.insert(form_id, RwLock::new(record));
+            created_records.insert(form_id, Arc::new(RwLock::new(record)));
         }
 
         let quick_keys_size = f.read_le::<u16>()? as usize;
@@ -215,7 +245,7 @@ impl Save {
             let record = ChangeRecord::read(&mut f)?;
             let form_id = record.form_id();
             change_ids.push(form_id);
-            change_records.insert(form_id, record);
+            change_records.insert(form_id, Arc::new(RwLock::new(record)));
         }
 
         let temp_effects_size = f.read_le::<u32>()? as usize;
@@ -275,9 +305,76 @@ impl Save {
             temporary_effects,
             form_ids,
             world_spaces,
+            journal: None,
+        })
+    }
+
+    /// Read only the summary information from the header of a save file
+    ///
+    /// The returned metadata's path and modification time are not filled in.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs or if the save format is invalid.
+    pub fn read_metadata<T: Read + Seek>(mut f: T) -> Result<SaveMetadata, TesError> {
+        let mut magic = [0u8; 12];
+        f.read_exact(&mut magic)?;
+        if magic != *b"TES4SAVEGAME" {
+            return Err(decode_failed("Not a valid Oblivion save"));
+        }
+
+        // version, exe time, header version, header size, and save number
+        f.seek(SeekFrom::Current(30))?;
+        let player_name = read_bzstring(&mut f)?;
+        let player_level = f.read_le()?;
+        let location = read_bzstring(&mut f)?;
+        f.seek(SeekFrom::Current(4))?; // game days
+        let game_ticks: u32 = f.read_le()?;
+        f.seek(SeekFrom::Current(16))?; // game time
+
+        let (width, height, data) = Save::read_screenshot(&mut f)?;
+
+        Ok(SaveMetadata {
+            path: PathBuf::new(),
+            modified: None,
+            player_name,
+            player_level: Some(player_level),
+            location,
+            play_time: Some(Duration::from_millis(game_ticks as u64)),
+            screenshot: Some(Screenshot {
+                width,
+                height,
+                data,
+            }),
         })
     }
 
+    /// Reads the screenshot from a save header as its width, height, and pixel data
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs or if the screenshot size is invalid or unreasonably large.
+    fn read_screenshot<T: Read + Seek>(mut f: T) -> Result<(u32, u32, Vec<u8>), TesError> {
+        let screen_size = f.read_le::<u32>()? as usize;
+        let width = f.read_le()?;
+        let height = f.read_le()?;
+        // - 8 because we already read the width and height
+        let data_size = screen_size
+            .checked_sub(8)
+            .ok_or_else(|| decode_failed("Screenshot size too small"))?;
+        if data_size > MAX_SCREENSHOT_SIZE {
+            return Err(TesError::LimitExceeded {
+                description: String::from("Screenshot too large"),
+                max_size: MAX_SCREENSHOT_SIZE,
+                actual_size: data_size,
+            });
+        }
+
+        let mut data = vec![0u8; data_size];
+        f.read_exact(&mut data)?;
+        Ok((width, height, data))
+    }
+
     /// Load a save file
     ///
     /// # Errors
@@ -291,6 +388,29 @@ impl Save {
         Save::read(reader)
     }
 
+    /// Gets the version of the save format
+    ///
+    /// There's no way to change the version, because the version determines how the rest of the
+    /// save is laid out.
+    pub fn version(&self) -> (u8, u8) {
+        self.version
+    }
+
+    /// Gets the version of the save header
+    pub fn header_version(&self) -> u32 {
+        self.header_version
+    }
+
+    /// Gets the save's number as shown in the save menu
+    pub fn save_number(&self) -> u32 {
+        self.save_number
+    }
+
+    /// Sets the save's number as shown in the save menu
+    pub fn set_save_number(&mut self, save_number: u32) {
+        self.save_number = save_number;
+    }
+
     /// Gets the player's name
     pub fn player_name(&self) -> &str {
         &self.player_name
@@ -309,24 +429,145 @@ impl Save {
         Ok(())
     }
 
+    /// Gets the player's level as shown in the save menu
+    pub fn player_level(&self) -> u16 {
+        self.player_level
+    }
+
+    /// Sets the player's level as shown in the save menu
+    ///
+    /// Note: this does not change the player's actual level, which is stored in the player's
+    /// change record.
+    pub fn set_player_level(&mut self, level: u16) {
+        self.player_level = level;
+    }
+
+    /// Gets the name of the player's location as shown in the save menu
+    pub fn player_location(&self) -> &str {
+        &self.player_location
+    }
+
+    /// Sets the name of the player's location as shown in the save menu
+    ///
+    /// Note: this does not move the player, whose actual location is stored in the player's
+    /// reference.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the location is longer than [`MAX_BSTRING`].
+    ///
+    /// [`MAX_BSTRING`]: constant.MAX_BSTRING.html
+    pub fn set_player_location(&mut self, location: String) -> Result<(), TesError> {
+        check_size(&location, MAX_BSTRING, "Player location too long")?;
+        self.player_location = location;
+        Ok(())
+    }
+
+    /// Gets the number of in-game days that have passed
+    pub fn game_days(&self) -> f32 {
+        self.game_days
+    }
+
+    /// Sets the number of in-game days that have passed as shown in the save menu
+    ///
+    /// # Errors
+    ///
+    /// Fails if the number of days is negative.
+    pub fn set_game_days(&mut self, days: f32) -> Result<(), TesError> {
+        check_range(days, 0., f32::MAX, "Days passed must not be negative")?;
+        self.game_days = days;
+        Ok(())
+    }
+
+    /// Gets the real time the player has spent playing
+    pub fn play_time(&self) -> Duration {
+        Duration::from_millis(self.game_ticks as u64)
+    }
+
+    /// Sets the real time the player has spent playing as shown in the save menu
+    ///
+    /// # Errors
+    ///
+    /// Fails if the play time is longer than the save can store, which is about 49 days.
+    pub fn set_play_time(&mut self, play_time: Duration) -> Result<(), TesError> {
+        let millis = play_time.as_millis();
+        self.game_ticks = u32::try_from(millis).map_err(|_| TesError::LimitExceeded {
+            description: String::from("Play time too long"),
+            max_size: u32::MAX as usize,
+            actual_size: millis as usize,
+        })?;
+        Ok(())
+    }
+
+    /// Gets the save's screenshot
+    pub fn screenshot(&self) -> Screenshot {
+        Screenshot {
+            width: self.screen_width,
+            height: self.screen_height,
+            data: self.screen_data.clone(),
+        }
+    }
+
+    /// Sets the save's screenshot
+    ///
+    /// # Errors
+    ///
+    /// Fails if the size of the screenshot data doesn't match its dimensions.
+    pub fn set_screenshot(&mut self, screenshot: Screenshot) -> Result<(), TesError> {
+        let expected_size = screenshot.width as usize * screenshot.height as usize * 3;
+        if screenshot.data.len() != expected_size {
+            return Err(TesError::RequirementFailed(format!(
+                "Expected {} bytes of screenshot data for a {}x{} screenshot, found {}",
+                expected_size,
+                screenshot.width,
+                screenshot.height,
+                screenshot.data.len()
+            )));
+        }
+
+        self.screen_width = screenshot.width;
+        self.screen_height = screenshot.height;
+        self.screen_data = screenshot.data;
+        Ok(())
+    }
+
+    /// Gets statistics about the records created in the save, such as custom spells
+    pub fn load_stats(&self) -> LoadStats {
+        let mut stats = LoadStats::new();
+        for record in self.created_records.values() {
+            stats.add(&*record.read().unwrap());
+        }
+        stats
+    }
+
     /// Gets a change record by form ID
     ///
     /// Returns `None` if no change record exists for the given form ID.
-    pub fn get_change_record(&self, form_id: FormId) -> Option<&ChangeRecord> {
-        self.change_records.get(&form_id)
+    pub fn get_change_record(&self, form_id: FormId) -> Option<RwLockReadGuard<ChangeRecord>> {
+        self.change_records.get(&form_id).map(|r| r.read().unwrap())
     }
 
-    /// Gets a change record by form ID, mutably
+    /// Gets a change record by form ID, mutably, and journals the modification
     ///
     /// Returns `None` if no change record exists for the given form ID.
-    pub fn get_change_record_mut(&mut self, form_id: FormId) -> Option<&mut ChangeRecord> {
-        self.change_records.get_mut(&form_id)
+    pub fn get_change_record_mut(&mut self, form_id: FormId) -> Option<RecordMut<ChangeRecord>> {
+        let record = self.change_records.get(&form_id)?;
+        let mutation = self.journal.as_mut().map(|j| {
+            j.changes
+                .push(Mutation::new(MutationKind::Modified, None, record))
+        });
+        Some(RecordMut::new(record.write().unwrap(), mutation))
+    }
+
+    /// Iterates over the form IDs of this save's change records, in the order they're stored
+    pub fn iter_change_ids(&self) -> impl Iterator<Item = FormId> + '_ {
+        self.change_ids.iter().copied()
     }
 
     /// Gets a form change by form ID
     pub fn get_form_change<T: FormChange>(&self, form_id: FormId) -> Result<Option<T>, TesError> {
         Ok(match self.get_change_record(form_id) {
-            Some(record) => Some(T::read(record)?),
+            Some(record) => Some(T::read(&record)?),
             None => None,
         })
     }
@@ -353,13 +594,16 @@ impl Save {
             .map(|r| r.read().unwrap())
     }
 
-    /// Gets a created record by form ID, mutably
+    /// Gets a created record by form ID, mutably, and journals the modification
     ///
     /// Returns `None` if no created record exists for the given form ID.
-    pub fn get_record_mut(&self, form_id: FormId) -> Option<RwLockWriteGuard<Tes4Record>> {
-        self.created_records
-            .get(&form_id)
-            .map(|r| r.write().unwrap())
+    pub fn get_record_mut(&mut self, form_id: FormId) -> Option<RecordMut<Tes4Record>> {
+        let record = self.created_records.get(&form_id)?;
+        let mutation = self.journal.as_mut().map(|j| {
+            j.created
+                .push(Mutation::new(MutationKind::Modified, None, record))
+        });
+        Some(RecordMut::new(record.write().unwrap(), mutation))
     }
 
     /// Adds a created record
@@ -372,7 +616,13 @@ impl Save {
         self.next_form_id += 1;
         record.set_id(form_id);
 
-        self.created_records.insert(form_id, RwLock::new(record));
+        let record = Arc::new(RwLock::new(record));
+        if let Some(ref mut journal) = self.journal {
+            journal
+                .created
+                .push(Mutation::new(MutationKind::Added, None, &record));
+        }
+        self.created_records.insert(form_id, record);
         self.created_ids.push(form_id);
 
         let new_iref = self.form_ids.len() as u32;
@@ -380,6 +630,83 @@ impl Save {
         new_iref
     }
 
+    /// Starts keeping a journal of the changes made to this save's records
+    ///
+    /// Any journal that was already being kept is discarded. Only created records and change
+    /// records are journaled; changes to the rest of the save, such as its header or globals, are
+    /// not recorded.
+    pub fn start_journal(&mut self) {
+        self.journal = Some(SaveJournal::default());
+    }
+
+    /// Stops keeping a journal and returns the changes that were recorded
+    pub fn stop_journal(&mut self) -> Option<SaveJournal> {
+        self.journal.take()
+    }
+
+    /// Gets the journal of changes made since [`start_journal`] was called, if one is being kept
+    ///
+    /// [`start_journal`]: #method.start_journal
+    pub fn journal(&self) -> Option<&SaveJournal> {
+        self.journal.as_ref()
+    }
+
+    /// Reverts the change at an index in the created records journal and removes it from the
+    /// journal
+    ///
+    /// Returns `false` if no journal is being kept or it has no change at that index. Reverting a
+    /// change doesn't revert later changes to the same record, so changes should be reverted from
+    /// newest to oldest. A reverted record's form ID and iref are only given back if no record
+    /// was added after it.
+    pub fn revert_created(&mut self, index: usize) -> bool {
+        let mutation = match self.journal.as_mut().and_then(|j| j.created.take(index)) {
+            Some(mutation) => mutation,
+            None => return false,
+        };
+
+        match mutation.kind {
+            MutationKind::Added => {
+                let form_id = mutation.record().id();
+                self.created_records.remove(&form_id);
+                self.created_ids.retain(|id| *id != form_id);
+                if self.form_ids.last() == Some(&form_id) {
+                    self.form_ids.pop();
+                }
+                if self.next_form_id == form_id.0.wrapping_add(1) {
+                    self.next_form_id = form_id.0;
+                }
+            }
+            MutationKind::Modified => {
+                if let Some(previous) = mutation.take_previous() {
+                    mutation.record.write().unwrap().restore(previous);
+                }
+            }
+            // created records are never removed
+            MutationKind::Removed => (),
+        }
+
+        true
+    }
+
+    /// Reverts the change at an index in the change records journal and removes it from the
+    /// journal
+    ///
+    /// Returns `false` if no journal is being kept or it has no change at that index. Change
+    /// records are only ever modified, so this restores the record as it was before that
+    /// modification.
+    pub fn revert_change(&mut self, index: usize) -> bool {
+        let mutation = match self.journal.as_mut().and_then(|j| j.changes.take(index)) {
+            Some(mutation) => mutation,
+            None => return false,
+        };
+
+        if let Some(previous) = mutation.take_previous() {
+            mutation.record.write().unwrap().restore(previous);
+        }
+
+        true
+    }
+
     /// Gets a created form by form ID, mutably
     pub fn get_form<T>(&self, form_id: FormId) -> Result<Option<T>, TesError>
     where
@@ -415,11 +742,70 @@ impl Save {
         Ok(self.add_record(record))
     }
 
+    /// Adds a batch of forms to the save
+    ///
+    /// The forms are serialized in parallel and then added in the order they appear in `forms`,
+    /// so they get consecutive form IDs and irefs. Returns the range of irefs of the new records.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the save doesn't have enough form IDs or irefs left for all the forms or if any of
+    /// the forms can't be serialized. Nothing is added to the save if this fails.
+    pub fn add_forms<T>(&mut self, forms: &[T]) -> Result<Range<u32>, TesError>
+    where
+        T: Form<Field = Tes4Field, Record = Tes4Record> + Sync,
+    {
+        let first_iref = self.form_ids.len() as u32;
+        let available = (u32::MAX - self.next_form_id).min(MAX_IREF.saturating_sub(first_iref));
+        check_size(forms, available as usize, "Created forms")?;
+
+        let serialize = |chunk: &[T]| {
+            chunk
+                .iter()
+                .map(|form| {
+                    let mut record = Tes4Record::new(T::RECORD_TYPE);
+                    form.write(&mut record)?;
+                    Ok(record)
+                })
+                .collect::<Result<Vec<_>, TesError>>()
+        };
+
+        let num_threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let chunk_size = forms.len().div_ceil(num_threads).max(MIN_BATCH_CHUNK);
+        let records = if forms.len() <= chunk_size {
+            serialize(forms)?
+        } else {
+            thread::scope(|scope| {
+                let handles: Vec<_> = forms
+                    .chunks(chunk_size)
+                    .map(|chunk| scope.spawn(move || serialize(chunk)))
+                    .collect();
+                let mut records = Vec::with_capacity(forms.len());
+                for handle in handles {
+                    match handle.join() {
+                        Ok(chunk) => records.extend(chunk?),
+                        Err(e) => panic::resume_unwind(e),
+                    }
+                }
+                Ok::<_, TesError>(records)
+            })?
+        };
+
+        self.created_ids.reserve(records.len());
+        self.created_records.reserve(records.len());
+        self.form_ids.reserve(records.len());
+        for record in records {
+            self.add_record(record);
+        }
+
+        Ok(first_iref..self.form_ids.len() as u32)
+    }
+
     /// Gets the form ID for an iref, if one exists
     ///
     /// Returns `None` if there is no form ID for the given iref
     pub fn iref_to_form_id(&self, iref: u32) -> Option<FormId> {
-        if iref > 0xff000000 {
+        if iref > MAX_IREF {
             Some(FormId(iref))
         } else {
             self.form_ids.get(iref as usize).copied()
@@ -471,7 +857,7 @@ impl Save {
         // header size = screenshot size + hard-coded fields + name and location bzstrings
         let header_size =
             self.screen_data.len() + 46 + self.player_name.len() + self.player_location.len();
-        f.write_le(&(header_size as u32))?;
+        f.write_le(&checked_len::<u32>(header_size, "Save header")?)?;
         f.write_le(&self.save_number)?;
         write_bzstring(&mut f, &self.player_name)?;
         f.write_le(&self.player_level)?;
@@ -480,12 +866,12 @@ impl Save {
         f.write_le(&self.game_ticks)?;
         f.write_all(&self.game_time)?;
         let screen_size = self.screen_data.len() + 8;
-        f.write_le(&(screen_size as u32))?;
+        f.write_le(&checked_len::<u32>(screen_size, "Save screenshot")?)?;
         f.write_le(&self.screen_width)?;
         f.write_le(&self.screen_height)?;
         f.write_all(&self.screen_data)?;
 
-        f.write_le(&(self.plugins.len() as u8))?;
+        f.write_le(&checked_len::<u8>(self.plugins.len(), "Save plugin list")?)?;
         for plugin in self.plugins.iter() {
             write_bstring(&mut f, plugin)?;
         }
@@ -494,7 +880,10 @@ impl Save {
         let form_id_offset = f.seek(SeekFrom::Current(0))?;
         // write dummy value
         f.write_all(b"\0\0\0\0")?;
-        f.write_le(&(self.change_records.len() as u32))?;
+        f.write_le(&checked_len::<u32>(
+            self.change_records.len(),
+            "Save change records",
+        )?)?;
         f.write_le(&self.next_form_id)?;
         f.write_le(&self.world_id)?;
         f.write_le(&self.world_x)?;
@@ -504,15 +893,15 @@ impl Save {
         f.write_le(&self.player_y)?;
         f.write_le(&self.player_z)?;
 
-        f.write_le(&(self.globals.len() as u16))?;
+        f.write_le(&checked_len::<u16>(self.globals.len(), "Save globals")?)?;
         for (iref, value) in self.globals.iter() {
             f.write_le(&iref)?;
             f.write_le(&value)?;
         }
 
         let tes_class_size = self.deaths.len() * 6 + 8;
-        f.write_le(&(tes_class_size as u16))?;
-        f.write_le(&(self.deaths.len() as u32))?;
+        f.write_le(&checked_len::<u16>(tes_class_size, "Save death counts")?)?;
+        f.write_le(&checked_len::<u32>(self.deaths.len(), "Save death counts")?)?;
         for (actor, count) in self.deaths.iter() {
             f.write_le(&actor)?;
             f.write_le(&count)?;
@@ -520,18 +909,30 @@ impl Save {
 
         f.write_le(&self.game_seconds)?;
 
-        f.write_le(&(self.processes_data.len() as u16))?;
+        f.write_le(&checked_len::<u16>(
+            self.processes_data.len(),
+            "Save processes data",
+        )?)?;
         f.write_all(&self.processes_data)?;
 
-        f.write_le(&(self.spec_event_data.len() as u16))?;
+        f.write_le(&checked_len::<u16>(
+            self.spec_event_data.len(),
+            "Save spec event data",
+        )?)?;
         f.write_all(&self.spec_event_data)?;
 
-        f.write_le(&(self.weather_data.len() as u16))?;
+        f.write_le(&checked_len::<u16>(
+            self.weather_data.len(),
+            "Save weather data",
+        )?)?;
         f.write_all(&self.weather_data)?;
 
         f.write_le(&self.player_combat_count)?;
 
-        f.write_le(&(self.created_records.len() as u32))?;
+        f.write_le(&checked_len::<u32>(
+            self.created_records.len(),
+            "Save created records",
+        )?)?;
         for form_id in &self.created_ids {
             if let Some(created_record) = self.created_records.get(form_id) {
                 created_record.read().unwrap().write(&mut f)?;
@@ -551,39 +952,60 @@ impl Save {
         // calculate the number of bytes we just wrote and update the size at the beginning
         let end = f.seek(SeekFrom::Current(0))?;
         f.seek(SeekFrom::Start(start - 2))?;
-        f.write_le(&((end - start) as u16))?;
+        f.write_le(&checked_len::<u16>(
+            (end - start) as usize,
+            "Save quick keys",
+        )?)?;
         f.seek(SeekFrom::Start(end))?;
 
-        f.write_le(&(self.reticle_data.len() as u16))?;
+        f.write_le(&checked_len::<u16>(
+            self.reticle_data.len(),
+            "Save reticle data",
+        )?)?;
         f.write_all(&self.reticle_data)?;
 
-        f.write_le(&(self.interface_data.len() as u16))?;
+        f.write_le(&checked_len::<u16>(
+            self.interface_data.len(),
+            "Save interface data",
+        )?)?;
         f.write_all(&self.interface_data)?;
 
-        f.write_le(&(self.region_data.len() as u16))?;
+        f.write_le(&checked_len::<u16>(
+            self.region_data.len(),
+            "Save region data",
+        )?)?;
         f.write_all(&self.region_data)?;
 
         for id in &self.change_ids {
             if let Some(change_record) = self.change_records.get(id) {
-                change_record.write(&mut f)?;
+                change_record.read().unwrap().write(&mut f)?;
             }
         }
 
-        f.write_le(&(self.temporary_effects.len() as u32))?;
+        f.write_le(&checked_len::<u32>(
+            self.temporary_effects.len(),
+            "Save temporary effects",
+        )?)?;
         f.write_all(&self.temporary_effects)?;
 
         // now go back and fill in the form ID offset
         let current_pos = f.seek(SeekFrom::Current(0))?;
         f.seek(SeekFrom::Start(form_id_offset))?;
-        f.write_le(&(current_pos as u32))?;
+        f.write_le(&checked_len::<u32>(current_pos as usize, "Save too large")?)?;
         f.seek(SeekFrom::Start(current_pos))?;
 
-        f.write_le(&(self.form_ids.len() as u32))?;
+        f.write_le(&checked_len::<u32>(
+            self.form_ids.len(),
+            "Save form ID table",
+        )?)?;
         for form_id in self.form_ids.iter() {
             f.write_le(&form_id.0)?;
         }
 
-        f.write_le(&(self.world_spaces.len() as u32))?;
+        f.write_le(&checked_len::<u32>(
+            self.world_spaces.len(),
+            "Save world spaces",
+        )?)?;
         for world_space in self.world_spaces.iter() {
             f.write_le(&world_space)?;
         }
@@ -621,6 +1043,31 @@ mod tests {
         assert_eq!(save.plugins.len(), 11);
     }
 
+    #[test]
+    fn read_metadata() {
+        let metadata = Save::read_metadata(Cursor::new(TEST_SAVE)).unwrap();
+        assert_eq!(metadata.player_name, "test");
+        assert_eq!(metadata.location, "Vilverin Canosel");
+        let screenshot = metadata.screenshot.unwrap();
+        assert_eq!(
+            screenshot.data.len(),
+            (screenshot.width * screenshot.height * 3) as usize
+        );
+    }
+
+    #[test]
+    fn read_bad_screenshot_size() {
+        let mut data = vec![];
+        data.extend_from_slice(&4u32.to_le_bytes());
+        data.extend_from_slice(&[0; 8]);
+        assert!(Save::read_screenshot(Cursor::new(&data)).is_err());
+
+        let mut data = vec![];
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        data.extend_from_slice(&[0; 8]);
+        assert!(Save::read_screenshot(Cursor::new(&data)).is_err());
+    }
+
     #[test]
     fn set_name() {
         let mut record_ref = TEST_SAVE.as_ref();
@@ -632,6 +1079,31 @@ mod tests {
         ).unwrap_err();
     }
 
+    #[test]
+    fn edit_header() {
+        let mut save = Save::read(Cursor::new(TEST_SAVE)).unwrap();
+        save.set_player_level(12);
+        save.set_player_location(String::from("Balmora")).unwrap();
+        save.set_game_days(42.5).unwrap();
+        save.set_game_days(-1.).unwrap_err();
+        save.set_play_time(Duration::from_secs(3600)).unwrap();
+        let sixty_days = Duration::from_secs(60 * 24 * 60 * 60);
+        save.set_play_time(sixty_days).unwrap_err();
+        save.set_save_number(7);
+
+        let mut buf = vec![];
+        save.write(Cursor::new(&mut buf)).unwrap();
+        let metadata = Save::read_metadata(Cursor::new(&buf)).unwrap();
+        assert_eq!(metadata.player_level, Some(12));
+        assert_eq!(metadata.location, "Balmora");
+        assert_eq!(metadata.play_time, Some(Duration::from_secs(3600)));
+
+        let save = Save::read(Cursor::new(&buf)).unwrap();
+        assert_eq!(save.game_days(), 42.5);
+        assert_eq!(save.save_number(), 7);
+        assert_eq!(save.version(), (0, 125));
+    }
+
     #[test]
     fn write_save() {
         let mut record_ref = TEST_SAVE.as_ref();
@@ -642,4 +1114,123 @@ mod tests {
         save.write(cursor).unwrap();
         assert_eq!(TEST_SAVE, buf.as_slice());
     }
+
+    /// Asserts that writing a save fails because a length doesn't fit in its prefix
+    fn assert_write_too_large(save: &Save, max_size: usize) {
+        assert!(matches!(
+            save.write(Cursor::new(vec![])),
+            Err(TesError::LimitExceeded { max_size: m, .. }) if m == max_size
+        ));
+    }
+
+    #[test]
+    fn write_oversized_collections() {
+        let read_save = || Save::read(Cursor::new(TEST_SAVE)).unwrap();
+
+        let mut globals = read_save();
+        globals.globals = vec![(0, 0.); 0x10000];
+        assert_write_too_large(&globals, 0xffff);
+
+        // the death counts fit in their own count but not in the size of the block they're in
+        let mut deaths = read_save();
+        deaths.deaths = vec![(0, 0); 10922];
+        assert_write_too_large(&deaths, 0xffff);
+
+        let mut processes = read_save();
+        processes.processes_data = vec![0; 0x10000];
+        assert_write_too_large(&processes, 0xffff);
+
+        let mut plugins = read_save();
+        plugins.plugins = vec![String::from("a.esp"); 0x100];
+        assert_write_too_large(&plugins, 0xff);
+    }
+
+    #[test]
+    fn add_forms() {
+        use crate::tes4::Spell;
+
+        let mut save = Save::read(Cursor::new(TEST_SAVE)).unwrap();
+        let first_form_id = save.next_form_id;
+        let spells: Vec<_> = (0..200)
+            .map(|i| Spell::new(Some(format!("batch{}", i)), None))
+            .collect();
+
+        let irefs = save.add_forms(&spells).unwrap();
+        assert_eq!(irefs.len(), spells.len());
+        for (i, iref) in irefs.enumerate() {
+            let form_id = save.iref_to_form_id(iref).unwrap();
+            assert_eq!(form_id, FormId(first_form_id + i as u32));
+            let spell: Spell = save.get_form(form_id).unwrap().unwrap();
+            assert_eq!(spell.editor_id(), Some(format!("batch{}", i).as_str()));
+        }
+
+        save.next_form_id = u32::MAX - 1;
+        let num_forms = save.form_ids.len();
+        save.add_forms(&spells[..2]).unwrap_err();
+        assert_eq!(save.form_ids.len(), num_forms);
+    }
+
+    #[test]
+    fn journal_and_revert() {
+        use crate::tes4::Spell;
+
+        let mut save = Save::read(Cursor::new(TEST_SAVE)).unwrap();
+        let next_form_id = save.next_form_id;
+        let num_form_ids = save.form_ids.len();
+        let player_data = save.get_change_record(FORM_PLAYER).unwrap().data().to_vec();
+        assert!(save.journal().is_none());
+
+        save.start_journal();
+        let spell = Spell::new(Some(String::from("journaled")), None);
+        let iref = save.add_form(&spell).unwrap();
+        let form_id = save.iref_to_form_id(iref).unwrap();
+        let spell = Spell::new(Some(String::from("renamed")), None);
+        save.update_form(&spell, form_id).unwrap();
+        {
+            let mut player = save.get_change_record_mut(FORM_PLAYER).unwrap();
+            let flags = player.flags();
+            player.set_data(flags, vec![]).unwrap();
+        }
+        {
+            // a record that's only read through a mutable borrow is never copied
+            let player = save.get_change_record_mut(FORM_PLAYER).unwrap();
+            assert!(player.data().is_empty());
+        }
+
+        let journal = save.journal().unwrap();
+        assert_eq!(
+            journal
+                .created()
+                .iter()
+                .map(|m| m.kind())
+                .collect::<Vec<_>>(),
+            [MutationKind::Added, MutationKind::Modified]
+        );
+        assert_eq!(
+            journal
+                .changes()
+                .iter()
+                .map(|m| m.is_changed())
+                .collect::<Vec<_>>(),
+            [true, false]
+        );
+
+        assert!(save.revert_change(0));
+        assert_eq!(
+            save.get_change_record(FORM_PLAYER).unwrap().data(),
+            player_data
+        );
+        assert!(save.revert_created(1));
+        let spell: Spell = save.get_form(form_id).unwrap().unwrap();
+        assert_eq!(spell.editor_id(), Some("journaled"));
+        assert!(save.revert_created(0));
+        assert!(save.get_record(form_id).is_none());
+        assert_eq!(save.next_form_id, next_form_id);
+        assert_eq!(save.form_ids.len(), num_form_ids);
+        assert!(!save.revert_created(0));
+
+        let journal = save.stop_journal().unwrap();
+        assert_eq!(journal.changes().len(), 1);
+        assert!(!save.revert_change(0));
+    }
 }
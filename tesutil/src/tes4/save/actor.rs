@@ -52,6 +52,11 @@ impl Default for ActorBase {
     }
 }
 
+// the lists in an actor's change record
+type Factions = PrefixedList<u16>;
+type Spells = PrefixedList<u16>;
+type Modifiers = PrefixedList<u16>;
+
 /// A change record for an NPC or creature
 #[derive(Debug)]
 pub struct ActorChange {
@@ -122,19 +127,12 @@ impl FormChange for ActorChange {
         }
 
         if change_flags.contains(ActorChangeFlags::FACTIONS) {
-            let num_factions: u16 = reader.read_le()?;
-            for _ in 0..num_factions {
-                actor_change
-                    .factions
-                    .push((reader.read_le()?, reader.read_le()?));
-            }
+            actor_change.factions =
+                Factions::read(&mut reader, |f| Ok((f.read_le()?, f.read_le()?)))?;
         }
 
         if change_flags.contains(ActorChangeFlags::SPELL_LIST) {
-            let num_spells: u16 = reader.read_le()?;
-            for _ in 0..num_spells {
-                actor_change.spells.push(reader.read_le()?);
-            }
+            actor_change.spells = Spells::read(&mut reader, |f| Ok(f.read_le()?))?;
         }
 
         if change_flags.contains(ActorChangeFlags::AI_DATA) {
@@ -148,12 +146,8 @@ impl FormChange for ActorChange {
         }
 
         if change_flags.contains(ActorChangeFlags::BASE_MODIFIERS) {
-            let num_modifiers: u16 = reader.read_le()?;
-            for _ in 0..num_modifiers {
-                actor_change
-                    .modifiers
-                    .push((reader.read_le()?, reader.read_le()?));
-            }
+            actor_change.modifiers =
+                Modifiers::read(&mut reader, |f| Ok((f.read_le()?, f.read_le()?)))?;
         }
 
         if change_flags.contains(ActorChangeFlags::FULL_NAME) {
@@ -205,21 +199,23 @@ impl FormChange for ActorChange {
 
         if !self.factions.is_empty() {
             flags |= ActorChangeFlags::FACTIONS;
-            let len = self.factions.len() as u16;
-            writer.write_le(&len)?;
-            for faction in self.factions.iter() {
-                writer.write_le(&faction.0)?;
-                writer.write_le(&faction.1)?;
-            }
+            Factions::write(
+                &mut writer,
+                &self.factions,
+                "Actor factions",
+                |f, faction| {
+                    f.write_le(&faction.0)?;
+                    f.write_le(&faction.1)?;
+                    Ok(())
+                },
+            )?;
         }
 
         if !self.spells.is_empty() {
             flags |= ActorChangeFlags::SPELL_LIST;
-            let len = self.spells.len() as u16;
-            writer.write_le(&len)?;
-            for spell in self.spells.iter() {
-                writer.write_le(&spell)?;
-            }
+            Spells::write(&mut writer, &self.spells, "Actor spells", |f, spell| {
+                Ok(f.write_le(spell)?)
+            })?;
         }
 
         if let Some(ref ai_data) = self.ai_data {
@@ -234,12 +230,16 @@ impl FormChange for ActorChange {
 
         if !self.modifiers.is_empty() {
             flags |= ActorChangeFlags::BASE_MODIFIERS;
-            let len = self.modifiers.len() as u16;
-            writer.write_le(&len)?;
-            for modifier in self.modifiers.iter() {
-                writer.write_le(&modifier.0)?;
-                writer.write_le(&modifier.1)?;
-            }
+            Modifiers::write(
+                &mut writer,
+                &self.modifiers,
+                "Actor modifiers",
+                |f, modifier| {
+                    f.write_le(&modifier.0)?;
+                    f.write_le(&modifier.1)?;
+                    Ok(())
+                },
+            )?;
         }
 
         if let Some(ref name) = self.full_name {
@@ -354,10 +354,23 @@ mod tests {
         let cursor = Cursor::new(&mut record_ref);
         let save = Save::read(cursor).unwrap();
         let player = save.get_change_record(FORM_PLAYER).unwrap();
-        let actor_change = ActorChange::read(player).unwrap();
+        let actor_change = ActorChange::read(&player).unwrap();
         assert_eq!(
             actor_change.attributes.unwrap()[Attribute::Intelligence],
             40
         );
     }
+    #[test]
+    fn write_too_many_spells() {
+        let mut record_ref = TEST_SAVE.as_ref();
+        let cursor = Cursor::new(&mut record_ref);
+        let mut save = Save::read(cursor).unwrap();
+        let mut player = save.get_change_record_mut(FORM_PLAYER).unwrap();
+        let mut actor_change = ActorChange::read(&player).unwrap();
+        actor_change.set_spells(vec![1; u16::MAX as usize + 1]);
+        assert!(matches!(
+            actor_change.write(&mut player),
+            Err(TesError::LimitExceeded { .. })
+        ));
+    }
 }
@@ -4,7 +4,7 @@ use std::io::{Cursor, Seek, SeekFrom};
 
 use crate::tes4::plugin::Class;
 use crate::tes4::save::{ChangeRecord, ChangeType, FormChange, FORM_PLAYER_REF};
-use crate::tes4::{ActorValues, Skills, SoulType};
+use crate::tes4::{ActorValue, ActorValues, Skills};
 use crate::*;
 
 use binrw::{binrw, BinReaderExt, BinWriterExt};
@@ -54,7 +54,7 @@ pub enum ActorFlag {
 
 /// Value of a script variable
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ScriptVariableValue {
     #[brw(magic = 0u16)]
     Reference(u32),
@@ -86,13 +86,33 @@ impl ScriptVariableValue {
 
 /// Variables of a script referenced by a script property
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ScriptVariable {
     index: u16,
     value: ScriptVariableValue,
 }
 
 impl ScriptVariable {
+    /// Creates a script variable with the given index and value
+    pub fn new(index: u16, value: ScriptVariableValue) -> ScriptVariable {
+        ScriptVariable { index, value }
+    }
+
+    /// Gets the index of the variable in its script
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    /// Gets the variable's value
+    pub fn value(&self) -> &ScriptVariableValue {
+        &self.value
+    }
+
+    /// Sets the variable's value
+    pub fn set_value(&mut self, value: ScriptVariableValue) {
+        self.value = value;
+    }
+
     /// Reads a script variable from a binary stream
     ///
     /// # Errors
@@ -154,7 +174,8 @@ pub enum Property {
     #[brw(magic = 0x2eu8)]
     EnchantmentPoints(f32),
     #[brw(magic = 0x2fu8)]
-    Soul(SoulType),
+    Soul(SoulSize),
+    /// The item came from a leveled list; the meaning of the data is unknown
     #[brw(magic = 0x36u8)]
     LeveledItem([u8; 5]),
     #[brw(magic = 0x37u8)]
@@ -169,6 +190,7 @@ pub enum Property {
     Poison(u32),
     #[brw(magic = 0x4fu8)]
     Unknown4f(u32),
+    /// The item was summoned by a bound item effect and disappears when the effect ends
     #[brw(magic = 0x50u8)]
     BoundItem,
     #[brw(magic = 0x55u8)]
@@ -189,14 +211,72 @@ impl Property {
     ///
     /// # Errors
     ///
-    /// Fails if an I/O error occurs
+    /// Fails if an I/O error occurs or if the property has too many values to be written
     pub fn write<T: Write + Seek>(&self, mut f: T) -> Result<(), TesError> {
+        match self {
+            Property::Script { variables, .. } => {
+                checked_len::<u16>(variables.len(), "Script property variables")?;
+            }
+            Property::Unknown23 { items, .. } => {
+                checked_len::<u16>(items.len(), "Property items")?;
+            }
+            _ => (),
+        }
         f.write_le(&self)?;
 
         Ok(())
     }
 }
 
+/// Finds the script property in a list of properties
+///
+/// Returns the iref of the script and the saved values of its variables.
+fn find_script<'a, I>(properties: I) -> Option<(u32, &'a [ScriptVariable])>
+where
+    I: IntoIterator<Item = &'a Property>,
+{
+    properties.into_iter().find_map(|p| match p {
+        Property::Script {
+            script, variables, ..
+        } => Some((*script, variables.as_slice())),
+        _ => None,
+    })
+}
+
+/// Sets the saved value of a variable in the script property in a list of properties
+///
+/// # Errors
+///
+/// Fails if there is no script property.
+fn set_script_variable<'a, I>(
+    properties: I,
+    index: u16,
+    value: ScriptVariableValue,
+) -> Result<(), TesError>
+where
+    I: IntoIterator<Item = &'a mut Property>,
+{
+    let variables = properties
+        .into_iter()
+        .find_map(|p| match p {
+            Property::Script { variables, .. } => Some(variables),
+            _ => None,
+        })
+        .ok_or_else(|| TesError::RequirementFailed(String::from("No script is attached")))?;
+
+    match variables.iter_mut().find(|v| v.index == index) {
+        Some(variable) => variable.value = value,
+        None => variables.push(ScriptVariable::new(index, value)),
+    }
+
+    Ok(())
+}
+
+/// The change sets on an inventory item
+type ItemChanges = PrefixedList<u32>;
+/// The properties in one change set
+type ChangeProperties = PrefixedList<u16>;
+
 /// An item in the player's inventory
 #[derive(Debug)]
 pub struct InventoryItem {
@@ -220,6 +300,52 @@ impl InventoryItem {
         !self.changes.is_empty()
     }
 
+    /// Returns whether this item was summoned by a bound item effect
+    pub fn is_bound(&self) -> bool {
+        self.changes
+            .iter()
+            .flatten()
+            .any(|p| matches!(p, Property::BoundItem))
+    }
+
+    /// Returns whether this item came from a leveled list
+    pub fn is_leveled(&self) -> bool {
+        self.changes
+            .iter()
+            .flatten()
+            .any(|p| matches!(p, Property::LeveledItem(_)))
+    }
+
+    /// Removes the properties that don't match a predicate from every change set
+    ///
+    /// Change sets that are left with no properties are removed as well.
+    pub fn retain_properties<F: FnMut(&Property) -> bool>(&mut self, mut f: F) {
+        for properties in &mut self.changes {
+            properties.retain(|p| f(p));
+        }
+        self.changes.retain(|properties| !properties.is_empty());
+    }
+
+    /// Gets the iref of the script attached to this item and the saved values of its variables
+    ///
+    /// Returns `None` if the item's changes don't include any script state.
+    pub fn script(&self) -> Option<(u32, &[ScriptVariable])> {
+        find_script(self.changes.iter().flatten())
+    }
+
+    /// Sets the saved value of a variable of the script attached to this item
+    ///
+    /// # Errors
+    ///
+    /// Fails if the item's changes don't include any script state.
+    pub fn set_script_variable(
+        &mut self,
+        index: u16,
+        value: ScriptVariableValue,
+    ) -> Result<(), TesError> {
+        set_script_variable(self.changes.iter_mut().flatten(), index, value)
+    }
+
     /// Add a change set to the item stack
     pub fn add_change(&mut self, properties: Vec<Property>) {
         self.changes.push(properties);
@@ -233,16 +359,8 @@ impl InventoryItem {
     pub fn read<T: Read + Seek>(mut f: T) -> Result<InventoryItem, TesError> {
         let iref = f.read_le()?;
         let stack_count = f.read_le()?;
-        let num_changes = f.read_le::<u32>()? as usize;
-        let mut changes = Vec::with_capacity(num_changes);
-        for _ in 0..num_changes {
-            let num_properties = f.read_le::<u16>()? as usize;
-            let mut properties = Vec::with_capacity(num_properties);
-            for _ in 0..num_properties {
-                properties.push(Property::read(&mut f)?);
-            }
-            changes.push(properties);
-        }
+        let changes =
+            ItemChanges::read(&mut f, |f| ChangeProperties::read(f, |f| Property::read(f)))?;
 
         Ok(InventoryItem {
             iref,
@@ -255,17 +373,18 @@ impl InventoryItem {
     ///
     /// # Errors
     ///
-    /// Fails if an I/O error occurs
+    /// Fails if an I/O error occurs or if the item has too many changes to be written
     pub fn write<T: Write + Seek>(&self, mut f: T) -> Result<(), TesError> {
         f.write_le(&self.iref)?;
         f.write_le(&self.stack_count)?;
-        f.write_le(&(self.changes.len() as u32))?;
-        for change in self.changes.iter() {
-            f.write_le(&(change.len() as u16))?;
-            for property in change.iter() {
-                property.write(&mut f)?;
-            }
-        }
+        ItemChanges::write(
+            &mut f,
+            &self.changes,
+            "Inventory item changes",
+            |f, change| {
+                ChangeProperties::write(f, change, "Inventory item change", |f, p| p.write(f))
+            },
+        )?;
 
         Ok(())
     }
@@ -298,14 +417,82 @@ impl ActiveEffect {
     ///
     /// # Errors
     ///
-    /// Fails if an I/O error occurs
+    /// Fails if an I/O error occurs or if the effect's details are too large to be written
     pub fn write<T: Write + Seek>(&self, mut f: T) -> Result<(), TesError> {
+        checked_len::<u16>(self.details.len(), "Active effect details")?;
         f.write_le(&self)?;
 
         Ok(())
     }
 }
 
+// the lists in the player's change record
+type Inventory = PrefixedList<u16>;
+type Properties = PrefixedList<u16>;
+type OblivionDoors = PrefixedList<u16>;
+type ActiveEffects = PrefixedList<u16>;
+type Advancements = PrefixedList<u32>;
+type KnownTopics = PrefixedList<u16>;
+type OpenQuests = PrefixedList<u16>;
+type KnownMagicEffects = PrefixedList<u32>;
+
+/// Damage to an actor's actor values
+///
+/// Oblivion stores damage as a modifier that's added to an actor value's base value along with its
+/// magic and script modifiers, so damage is negative: an attribute with a base value of 50 that's
+/// been damaged down to 40 has a damage modifier of -10. Restoring the attribute raises the
+/// modifier back towards zero but never past it, so the game never stores a positive damage
+/// modifier. This type takes and gives damage as a positive amount and handles the sign itself.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DamageModifiers(ActorValues<f32>);
+
+impl DamageModifiers {
+    /// Creates modifiers with no damage to any actor value
+    pub fn new() -> DamageModifiers {
+        DamageModifiers::default()
+    }
+
+    /// Gets how much an actor value is damaged
+    pub fn damage(&self, actor_value: ActorValue) -> f32 {
+        -self.0[actor_value]
+    }
+
+    /// Sets how much an actor value is damaged
+    ///
+    /// Damage can't raise an actor value above its base value, so an amount of zero or less means
+    /// the actor value isn't damaged.
+    pub fn set_damage(&mut self, actor_value: ActorValue, amount: f32) {
+        // avoid writing -0.0
+        self.0[actor_value] = if amount > 0. { -amount } else { 0. };
+    }
+
+    /// Sets the damage to an actor value from its base value and the current value it's damaged to
+    ///
+    /// A current value at or above the base value means the actor value isn't damaged.
+    pub fn set_from_values(&mut self, actor_value: ActorValue, base: f32, current: f32) {
+        self.set_damage(actor_value, base - current);
+    }
+
+    /// Removes all damage
+    pub fn clear(&mut self) {
+        for value in self.0.values_mut() {
+            *value = 0.;
+        }
+    }
+
+    /// Gets the modifier for an actor value as it's stored in the save
+    pub fn modifier(&self, actor_value: ActorValue) -> f32 {
+        self.0[actor_value]
+    }
+
+    /// Iterates through the actor values and how much each is damaged
+    pub fn iter(&self) -> impl Iterator<Item = (ActorValue, f32)> + '_ {
+        self.0
+            .iter()
+            .map(|(actor_value, value)| (actor_value, -*value))
+    }
+}
+
 /// Changes to the player
 ///
 /// This is a subset of the functionality for change records detailing changes to a placed instance
@@ -324,7 +511,7 @@ pub struct PlayerReferenceChange {
     // temporary attribute changes
     temp_active_effects: ActorValues<f32>,
     tac_unknown: ActorValues<f32>,
-    damage: ActorValues<f32>,
+    damage: DamageModifiers,
     health_delta: f32,
     magicka_delta: f32,
     fatigue_delta: f32,
@@ -352,7 +539,7 @@ pub struct PlayerReferenceChange {
     pub spec_increases: Specializations<u8>,
     pub skill_usage: Skills<u32>,
     pub major_skill_advancements: u32,
-    stat_unknown7: u8,
+    training_sessions_used: u8,
     active_quest: u32,
     known_topics: Vec<u32>,
     open_quests: Vec<(u32, u8, u8)>,
@@ -409,7 +596,7 @@ impl FormChange for PlayerReferenceChange {
 
         let mut temp_active_effects = ActorValues::default();
         let mut tac_unknown = ActorValues::default();
-        let mut damage = ActorValues::default();
+        let mut damage = DamageModifiers::new();
 
         for effect in temp_active_effects.values_mut() {
             *effect = reader.read_le()?;
@@ -419,7 +606,7 @@ impl FormChange for PlayerReferenceChange {
             *tac = reader.read_le()?;
         }
 
-        for dmg in damage.values_mut() {
+        for dmg in damage.0.values_mut() {
             *dmg = reader.read_le()?;
         }
 
@@ -432,21 +619,12 @@ impl FormChange for PlayerReferenceChange {
 
         // inventory might not be present if the save is from the very beginning of the game
         let inventory = if flags & 0x08000000 != 0 {
-            let num_items = reader.read_le::<u16>()? as usize;
-            let mut inventory = Vec::with_capacity(num_items);
-            for _ in 0..num_items {
-                inventory.push(InventoryItem::read(&mut reader)?);
-            }
-            inventory
+            Inventory::read(&mut reader, |f| InventoryItem::read(f))?
         } else {
             vec![]
         };
 
-        let num_properties = reader.read_le::<u16>()? as usize;
-        let mut properties = Vec::with_capacity(num_properties);
-        for _ in 0..num_properties {
-            properties.push(Property::read(&mut reader)?);
-        }
+        let properties = Properties::read(&mut reader, |f| Property::read(f))?;
 
         // the following data is not fully decoded and/or not relevant to us here, so we just grab
         // it all raw so we can spit it back out later
@@ -504,36 +682,27 @@ impl FormChange for PlayerReferenceChange {
         let mut stat_unknown5 = [0u8; 2];
         reader.read_exact(&mut stat_unknown5)?;
 
-        let num_doors = reader.read_le::<u16>()? as usize;
-        let mut oblivion_doors = Vec::with_capacity(num_doors);
-        for _ in 0..num_doors {
-            oblivion_doors.push((reader.read_le()?, reader.read_le()?));
-        }
+        let oblivion_doors =
+            OblivionDoors::read(&mut reader, |f| Ok((f.read_le()?, f.read_le()?)))?;
 
         let mut stat_unknown6 = [0u8; 2];
         reader.read_exact(&mut stat_unknown6)?;
 
-        let num_active_effects = reader.read_le::<u16>()? as usize;
-        let mut stat_active_effects = Vec::with_capacity(num_active_effects);
-        for _ in 0..num_active_effects {
-            stat_active_effects.push(ActiveEffect::read(&mut reader)?);
-        }
+        let stat_active_effects = ActiveEffects::read(&mut reader, |f| ActiveEffect::read(f))?;
 
         let mut skill_xp = Skills::default();
         for skill in skill_xp.values_mut() {
             *skill = reader.read_le()?;
         }
 
-        let num_advancements = reader.read_le::<u32>()? as usize;
-        let mut advancements = Vec::with_capacity(num_advancements);
-        for _ in 0..num_advancements {
+        let advancements = Advancements::read(&mut reader, |f| {
             let mut attributes = Attributes::default();
             for attribute in attributes.values_mut() {
-                *attribute = reader.read_le()?;
+                *attribute = f.read_le()?;
             }
 
-            advancements.push(attributes);
-        }
+            Ok(attributes)
+        })?;
 
         let mut spec_increases = Specializations::default();
         for specialization in spec_increases.values_mut() {
@@ -546,28 +715,18 @@ impl FormChange for PlayerReferenceChange {
         }
 
         let major_skill_advancements = reader.read_le()?;
-        let stat_unknown7 = reader.read_le()?;
+        let training_sessions_used = reader.read_le()?;
         let active_quest = reader.read_le()?;
 
-        let num_known_topics = reader.read_le::<u16>()? as usize;
-        let mut known_topics = Vec::with_capacity(num_known_topics);
-        for _ in 0..num_known_topics {
-            known_topics.push(reader.read_le()?);
-        }
-
-        let num_open_quests = reader.read_le::<u16>()? as usize;
-        let mut open_quests = Vec::with_capacity(num_open_quests);
-        for _ in 0..num_open_quests {
-            open_quests.push((reader.read_le()?, reader.read_le()?, reader.read_le()?));
-        }
-
-        let num_magic_effects = reader.read_le::<u32>()? as usize;
-        let mut known_magic_effects = Vec::with_capacity(num_magic_effects);
-        for _ in 0..num_magic_effects {
+        let known_topics = KnownTopics::read(&mut reader, |f| Ok(f.read_le()?))?;
+        let open_quests = OpenQuests::read(&mut reader, |f| {
+            Ok((f.read_le()?, f.read_le()?, f.read_le()?))
+        })?;
+        let known_magic_effects = KnownMagicEffects::read(&mut reader, |f| {
             let mut buf = [0u8; 4];
-            reader.read_exact(&mut buf)?;
-            known_magic_effects.push(buf);
-        }
+            f.read_exact(&mut buf)?;
+            Ok(buf)
+        })?;
 
         let mut facegen_symmetric = [0u8; 200];
         reader.read_exact(&mut facegen_symmetric)?;
@@ -640,7 +799,7 @@ impl FormChange for PlayerReferenceChange {
             spec_increases,
             skill_usage,
             major_skill_advancements,
-            stat_unknown7,
+            training_sessions_used,
             active_quest,
             known_topics,
             open_quests,
@@ -687,7 +846,7 @@ impl FormChange for PlayerReferenceChange {
             writer.write_le(&unknown)?;
         }
 
-        for damage in self.damage.values() {
+        for damage in self.damage.0.values() {
             writer.write_le(&damage)?;
         }
 
@@ -698,16 +857,20 @@ impl FormChange for PlayerReferenceChange {
         writer.write_le(&Into::<u8>::into(self.actor_flag))?;
 
         if self.flags & 0x08000000 != 0 {
-            writer.write_le(&(self.inventory.len() as u16))?;
-            for item in self.inventory.iter() {
-                item.write(&mut writer)?;
-            }
+            Inventory::write(
+                &mut writer,
+                &self.inventory,
+                "Player inventory",
+                |f, item| item.write(f),
+            )?;
         }
 
-        writer.write_le(&(self.properties.len() as u16))?;
-        for property in self.properties.iter() {
-            property.write(&mut writer)?;
-        }
+        Properties::write(
+            &mut writer,
+            &self.properties,
+            "Player properties",
+            |f, p| p.write(f),
+        )?;
 
         writer.write_all(&self.raw)?;
 
@@ -721,34 +884,51 @@ impl FormChange for PlayerReferenceChange {
             writer.write_le(&unknown)?;
         }
 
-        writer.write_le(&(self.stat_unknown4.len() as u16))?;
+        writer.write_le(&checked_len::<u16>(
+            self.stat_unknown4.len(),
+            "Player statistics data",
+        )?)?;
         writer.write_all(&self.stat_unknown3)?;
         for unknown in self.stat_unknown4.iter() {
             writer.write_le(&unknown)?;
         }
         writer.write_all(&self.stat_unknown5)?;
 
-        writer.write_le(&(self.oblivion_doors.len() as u16))?;
-        for (door, flag) in self.oblivion_doors.iter() {
-            writer.write_le(&door)?;
-            writer.write_le(&flag)?;
-        }
+        OblivionDoors::write(
+            &mut writer,
+            &self.oblivion_doors,
+            "Player Oblivion gates",
+            |f, (door, flag)| {
+                f.write_le(door)?;
+                f.write_le(flag)?;
+                Ok(())
+            },
+        )?;
 
         writer.write_all(&self.stat_unknown6)?;
 
-        writer.write_le(&(self.stat_active_effects.len() as u16))?;
-        for effect in self.stat_active_effects.iter() {
-            effect.write(&mut writer)?;
-        }
+        ActiveEffects::write(
+            &mut writer,
+            &self.stat_active_effects,
+            "Player active effects",
+            |f, effect| effect.write(f),
+        )?;
 
         for skill in self.skill_xp.values() {
             writer.write_le(&skill)?;
         }
 
-        writer.write_le(&(self.advancements.len() as u32))?;
-        for adv in self.advancements.iter().flat_map(|v| v.values()) {
-            writer.write_le(&adv)?;
-        }
+        Advancements::write(
+            &mut writer,
+            &self.advancements,
+            "Player advancements",
+            |f, attributes| {
+                for adv in attributes.values() {
+                    f.write_le(adv)?;
+                }
+                Ok(())
+            },
+        )?;
 
         for spec in self.spec_increases.values() {
             writer.write_le(&spec)?;
@@ -759,25 +939,34 @@ impl FormChange for PlayerReferenceChange {
         }
 
         writer.write_le(&self.major_skill_advancements)?;
-        writer.write_le(&self.stat_unknown7)?;
+        writer.write_le(&self.training_sessions_used)?;
         writer.write_le(&self.active_quest)?;
 
-        writer.write_le(&(self.known_topics.len() as u16))?;
-        for topic in self.known_topics.iter() {
-            writer.write_le(&topic)?;
-        }
-
-        writer.write_le(&(self.open_quests.len() as u16))?;
-        for (quest, stage, log_entry) in self.open_quests.iter() {
-            writer.write_le(&quest)?;
-            writer.write_le(&stage)?;
-            writer.write_le(&log_entry)?;
-        }
-
-        writer.write_le(&(self.known_magic_effects.len() as u32))?;
-        for effect in self.known_magic_effects.iter() {
-            writer.write_all(effect)?;
-        }
+        KnownTopics::write(
+            &mut writer,
+            &self.known_topics,
+            "Player known topics",
+            |f, topic| Ok(f.write_le(topic)?),
+        )?;
+
+        OpenQuests::write(
+            &mut writer,
+            &self.open_quests,
+            "Player open quests",
+            |f, (quest, stage, log_entry)| {
+                f.write_le(quest)?;
+                f.write_le(stage)?;
+                f.write_le(log_entry)?;
+                Ok(())
+            },
+        )?;
+
+        KnownMagicEffects::write(
+            &mut writer,
+            &self.known_magic_effects,
+            "Player known magic effects",
+            |f, effect| Ok(f.write_all(effect)?),
+        )?;
 
         writer.write_all(&self.facegen_symmetric)?;
         writer.write_all(&self.facegen_asymmetric)?;
@@ -824,6 +1013,18 @@ impl PlayerReferenceChange {
         Ok(())
     }
 
+    /// Gets how many times the player has paid for training since they last leveled up
+    ///
+    /// The game limits how many training sessions the player can buy per level.
+    pub fn training_sessions_used(&self) -> u8 {
+        self.training_sessions_used
+    }
+
+    /// Sets how many times the player has paid for training since they last leveled up
+    pub fn set_training_sessions_used(&mut self, sessions: u8) {
+        self.training_sessions_used = sessions;
+    }
+
     /// Gets the player's custom class, if any
     pub fn class(&self) -> Option<&Class> {
         self.custom_class.as_ref()
@@ -886,12 +1087,12 @@ impl PlayerReferenceChange {
     }
 
     /// Gets damage to player actor values
-    pub fn damage_modifiers(&self) -> &ActorValues<f32> {
+    pub fn damage_modifiers(&self) -> &DamageModifiers {
         &self.damage
     }
 
-    /// Gets damage to player actor values
-    pub fn damage_modifiers_mut(&mut self) -> &mut ActorValues<f32> {
+    /// Gets damage to player actor values mutably
+    pub fn damage_modifiers_mut(&mut self) -> &mut DamageModifiers {
         &mut self.damage
     }
 
@@ -925,6 +1126,24 @@ impl PlayerReferenceChange {
         self.fatigue_delta = value;
     }
 
+    /// Gets the iref of the script attached to the player and the saved values of its variables
+    pub fn script(&self) -> Option<(u32, &[ScriptVariable])> {
+        find_script(&self.properties)
+    }
+
+    /// Sets the saved value of a variable of the script attached to the player
+    ///
+    /// # Errors
+    ///
+    /// Fails if the player's properties don't include any script state.
+    pub fn set_script_variable(
+        &mut self,
+        index: u16,
+        value: ScriptVariableValue,
+    ) -> Result<(), TesError> {
+        set_script_variable(&mut self.properties, index, value)
+    }
+
     /// Clears the player's inventory
     ///
     /// Note that this is not the same as making the inventory empty; rather, it reverts the
@@ -961,11 +1180,82 @@ mod tests {
         let cursor = Cursor::new(&mut record_ref);
         let save = Save::read(cursor).unwrap();
         let player = save.get_change_record(FORM_PLAYER_REF).unwrap();
-        let player_change = PlayerReferenceChange::read(player).unwrap();
+        let player_change = PlayerReferenceChange::read(&player).unwrap();
         assert_eq!(player_change.name, "test");
         assert!(!player_change.is_female);
     }
 
+    #[test]
+    fn damage_is_stored_negative() {
+        let mut record_ref = TEST_SAVE.as_ref();
+        let cursor = Cursor::new(&mut record_ref);
+        let mut save = Save::read(cursor).unwrap();
+        let mut player = save.get_change_record_mut(FORM_PLAYER_REF).unwrap();
+        let mut player_change = PlayerReferenceChange::read(&player).unwrap();
+        // the player in the test save is undamaged, so it can't tell us anything about the sign
+        for (actor_value, damage) in player_change.damage_modifiers().iter() {
+            assert_eq!(damage, 0., "{:?} has damage {}", actor_value, damage);
+        }
+
+        // damage is written to the save as a negative modifier
+        player_change
+            .damage_modifiers_mut()
+            .set_damage(ActorValue::Strength, 10.);
+        player_change.write(&mut player).unwrap();
+        // location, then temporary effects and an unknown value for each actor value
+        let offset = 7 * 4 + 72 * 4 * 2 + ActorValue::Strength as usize * 4;
+        let stored = f32::from_le_bytes(player.data()[offset..offset + 4].try_into().unwrap());
+        assert_eq!(stored, -10.);
+        let player_change = PlayerReferenceChange::read(&player).unwrap();
+        assert_eq!(
+            player_change
+                .damage_modifiers()
+                .damage(ActorValue::Strength),
+            10.
+        );
+
+        let mut damage = DamageModifiers::new();
+        damage.set_from_values(ActorValue::Strength, 50., 40.);
+        assert_eq!(damage.damage(ActorValue::Strength), 10.);
+        assert_eq!(damage.modifier(ActorValue::Strength), -10.);
+
+        // a fortified value isn't damaged
+        damage.set_from_values(ActorValue::Blade, 30., 35.);
+        assert_eq!(damage.modifier(ActorValue::Blade), 0.);
+        assert!(damage.modifier(ActorValue::Blade).is_sign_positive());
+
+        damage.clear();
+        assert!(damage.iter().all(|(_, d)| d == 0.));
+    }
+
+    #[test]
+    fn bound_items() {
+        let mut item = InventoryItem::new(0x1000, 1);
+        assert!(!item.is_bound());
+        item.add_change(vec![Property::EquippedItem]);
+        assert!(!item.is_bound());
+        item.add_change(vec![Property::EquippedItem, Property::BoundItem]);
+        assert!(item.is_bound());
+    }
+
+    #[test]
+    fn leveled_items() {
+        let mut item = InventoryItem::new(0x1000, 1);
+        item.add_change(vec![Property::LeveledItem([0; 5])]);
+        item.add_change(vec![Property::EquippedItem, Property::BoundItem]);
+        assert!(item.is_leveled());
+
+        item.retain_properties(|p| !matches!(p, Property::LeveledItem(_)));
+        assert!(!item.is_leveled());
+        assert!(item.is_bound());
+        // the leveled item change set had nothing else in it, so it's gone
+        assert_eq!(item.changes.len(), 1);
+        assert_eq!(item.changes[0].len(), 2);
+
+        item.retain_properties(|p| !matches!(p, Property::EquippedItem | Property::BoundItem));
+        assert!(!item.has_changes());
+    }
+
     #[test]
     fn write_player_ref_change() {
         let mut record_ref = TEST_SAVE.as_ref();
@@ -973,8 +1263,84 @@ mod tests {
         let mut save = Save::read(cursor).unwrap();
         let mut player = save.get_change_record_mut(FORM_PLAYER_REF).unwrap();
         let original = player.data().to_vec();
-        let player_change = PlayerReferenceChange::read(player).unwrap();
+        let mut player_change = PlayerReferenceChange::read(&player).unwrap();
         player_change.write(&mut player).unwrap();
         assert_eq!(original, player.data());
+
+        player_change.set_training_sessions_used(3);
+        player_change.write(&mut player).unwrap();
+        let player_change = PlayerReferenceChange::read(&player).unwrap();
+        assert_eq!(player_change.training_sessions_used(), 3);
+    }
+
+    #[test]
+    fn inventory_item_round_trip() {
+        let mut item = InventoryItem::new(0x1234, 3);
+        item.add_change(vec![Property::EquippedItem, Property::Owner(7)]);
+        let mut buf = vec![];
+        item.write(Cursor::new(&mut buf)).unwrap();
+        // iref, stack count, number of changes, number of properties, properties
+        assert_eq!(buf.len(), 4 + 4 + 4 + 2 + 1 + 5);
+
+        let read_item = InventoryItem::read(Cursor::new(&buf)).unwrap();
+        assert_eq!(read_item.iref, 0x1234);
+        assert_eq!(read_item.stack_count, 3);
+        assert_eq!(read_item.changes.len(), 1);
+        assert!(matches!(
+            read_item.changes[0][..],
+            [Property::EquippedItem, Property::Owner(7)]
+        ));
+    }
+
+    #[test]
+    fn item_script_variables() {
+        let mut item = InventoryItem::new(0x1234, 1);
+        assert!(item.script().is_none());
+        assert!(item
+            .set_script_variable(0, ScriptVariableValue::Number(1.))
+            .is_err());
+
+        item.add_change(vec![
+            Property::EquippedItem,
+            Property::Script {
+                script: 0x20,
+                variables: vec![ScriptVariable::new(0, ScriptVariableValue::Number(1.))],
+                unknown: 0,
+            },
+        ]);
+        item.set_script_variable(0, ScriptVariableValue::Number(2.))
+            .unwrap();
+        item.set_script_variable(3, ScriptVariableValue::Reference(0x21))
+            .unwrap();
+
+        let mut buf = vec![];
+        item.write(Cursor::new(&mut buf)).unwrap();
+        let item = InventoryItem::read(Cursor::new(&buf)).unwrap();
+        let (script, variables) = item.script().unwrap();
+        assert_eq!(script, 0x20);
+        assert_eq!(
+            variables,
+            [
+                ScriptVariable::new(0, ScriptVariableValue::Number(2.)),
+                ScriptVariable::new(3, ScriptVariableValue::Reference(0x21)),
+            ]
+        );
+        assert_eq!(variables[1].index(), 3);
+        assert_eq!(variables[1].value(), &ScriptVariableValue::Reference(0x21));
+    }
+
+    #[test]
+    fn write_oversized_item_change() {
+        let mut item = InventoryItem::new(0x1234, 1);
+        item.add_change((0..=u16::MAX).map(|_| Property::EquippedItem).collect());
+        let mut buf = vec![];
+        assert!(matches!(
+            item.write(Cursor::new(&mut buf)),
+            Err(TesError::LimitExceeded {
+                max_size: 0xffff,
+                actual_size: 0x10000,
+                ..
+            })
+        ));
     }
 }
@@ -37,7 +37,7 @@ pub enum ChangeType {
 
 /// A record in a save that records changes to objects
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ChangeRecord {
     form_id: FormId,
     change_type: ChangeType,
@@ -50,6 +50,16 @@ pub struct ChangeRecord {
     data: Vec<u8>,
 }
 
+impl JournalRecord for ChangeRecord {
+    fn snapshot(&self) -> ChangeRecord {
+        self.clone()
+    }
+
+    fn restore(&mut self, snapshot: ChangeRecord) {
+        *self = snapshot;
+    }
+}
+
 impl ChangeRecord {
     /// Reads a change record from a binary stream
     pub fn read<T: Read + Seek>(mut f: T) -> Result<ChangeRecord, TesError> {
@@ -0,0 +1,271 @@
+use crate::tes4::save::{ActorBase, ActorChange, InventoryItem, PlayerReferenceChange, Save};
+use crate::tes4::{FormId, FORM_GOLD, FORM_PLAYER, FORM_PLAYER_REF};
+use crate::*;
+
+/// A high-level editor for common changes to the player in an Oblivion save
+///
+/// The editor reads the player's change records when it's created and works in terms of form IDs,
+/// taking care of translating them to and from the save's irefs. Changes are staged in the editor
+/// and written to the save by [`commit`].
+///
+/// [`commit`]: #method.commit
+pub struct SaveEditor<'a> {
+    save: &'a mut Save,
+    player_base: ActorChange,
+    player_ref: PlayerReferenceChange,
+    // the copies of the player's name and level in the save header, for the save menu
+    header_name: Option<String>,
+    header_level: Option<u16>,
+}
+
+impl<'a> SaveEditor<'a> {
+    /// Creates an editor for a save
+    ///
+    /// # Errors
+    ///
+    /// Fails if the save doesn't have change records for the player or if they're invalid.
+    pub fn new(save: &'a mut Save) -> Result<SaveEditor<'a>, TesError> {
+        let player_base = save
+            .get_form_change(FORM_PLAYER)?
+            .ok_or(TesError::InvalidFormId {
+                form_id: FORM_PLAYER,
+            })?;
+        let player_ref = save
+            .get_form_change(FORM_PLAYER_REF)?
+            .ok_or(TesError::InvalidFormId {
+                form_id: FORM_PLAYER_REF,
+            })?;
+
+        Ok(SaveEditor {
+            save,
+            player_base,
+            player_ref,
+            header_name: None,
+            header_level: None,
+        })
+    }
+
+    /// Gets the player's name
+    pub fn player_name(&self) -> &str {
+        self.player_ref.name()
+    }
+
+    /// Renames the player, both in game and in the save menu
+    ///
+    /// # Errors
+    ///
+    /// Fails if the name is longer than [`MAX_BSTRING`].
+    ///
+    /// [`MAX_BSTRING`]: constant.MAX_BSTRING.html
+    pub fn set_player_name(&mut self, name: &str) -> Result<(), TesError> {
+        check_size(name, MAX_BSTRING, "Player name too long")?;
+        self.player_ref.set_name(String::from(name))?;
+        self.header_name = Some(String::from(name));
+        Ok(())
+    }
+
+    /// Gets the player's level
+    pub fn level(&self) -> u16 {
+        match self.player_base.actor_base() {
+            Some(base) => base.level as u16,
+            // the player's base data isn't saved until it changes from the default
+            None => 1,
+        }
+    }
+
+    /// Sets the player's level, both in game and in the save menu
+    ///
+    /// # Errors
+    ///
+    /// Fails if the level is 0 or greater than 32767.
+    pub fn set_level(&mut self, level: u16) -> Result<(), TesError> {
+        check_range(
+            level,
+            1,
+            i16::MAX as u16,
+            "Level must be between 1 and 32767",
+        )?;
+
+        if self.player_base.actor_base().is_none() {
+            self.player_base.set_actor_base(Some(ActorBase::default()));
+        }
+        self.player_base.actor_base_mut().unwrap().level = level as i16;
+        self.header_level = Some(level);
+        Ok(())
+    }
+
+    /// Gets the form IDs of the player's spells
+    pub fn spells(&self) -> Vec<FormId> {
+        self.player_base
+            .spells()
+            .filter_map(|iref| self.save.iref_to_form_id(iref))
+            .collect()
+    }
+
+    /// Does the player know a spell?
+    pub fn has_spell(&self, spell: FormId) -> bool {
+        match self.save.form_id_to_iref(spell) {
+            Some(iref) => self.player_base.spells().any(|s| s == iref),
+            None => false,
+        }
+    }
+
+    /// Adds a spell to the player's spell list
+    ///
+    /// Returns false if the player already knew the spell.
+    pub fn add_spell(&mut self, spell: FormId) -> bool {
+        if self.has_spell(spell) {
+            return false;
+        }
+
+        let iref = self.save.insert_form_id(spell);
+        let mut spells: Vec<u32> = self.player_base.spells().collect();
+        spells.push(iref);
+        self.player_base.set_spells(spells);
+        true
+    }
+
+    /// Removes a spell from the player's spell list
+    ///
+    /// Returns false if the player didn't know the spell.
+    pub fn remove_spell(&mut self, spell: FormId) -> bool {
+        let iref = match self.save.form_id_to_iref(spell) {
+            Some(iref) => iref,
+            None => return false,
+        };
+
+        let mut spells: Vec<u32> = self.player_base.spells().collect();
+        let count = spells.len();
+        spells.retain(|s| *s != iref);
+        let removed = spells.len() != count;
+        self.player_base.set_spells(spells);
+        removed
+    }
+
+    /// Gets how much gold the player has gained or lost since the start of the game
+    ///
+    /// Oblivion saves only store changes to the player's starting inventory, so this is not
+    /// necessarily the amount of gold the player is carrying.
+    pub fn gold(&self) -> i32 {
+        match self.save.form_id_to_iref(FORM_GOLD) {
+            Some(iref) => self
+                .player_ref
+                .iter_inventory()
+                .filter(|i| i.iref == iref)
+                .map(|i| i.stack_count)
+                .sum(),
+            None => 0,
+        }
+    }
+
+    /// Gives the player gold, or takes it away if `amount` is negative
+    pub fn add_gold(&mut self, amount: i32) {
+        if amount == 0 {
+            return;
+        }
+
+        let iref = self.save.insert_form_id(FORM_GOLD);
+        let stack = self
+            .player_ref
+            .iter_inventory_mut()
+            .find(|i| i.iref == iref);
+        if let Some(item) = stack {
+            item.stack_count = item.stack_count.saturating_add(amount);
+        } else {
+            self.player_ref.add_item(InventoryItem::new(iref, amount));
+        }
+    }
+
+    /// Gets one of the player's base attributes
+    ///
+    /// Returns `None` if the save doesn't record the player's attributes.
+    pub fn attribute(&self, attribute: Attribute) -> Option<u8> {
+        self.player_base.attributes().map(|a| a[attribute])
+    }
+
+    /// Sets one of the player's base attributes
+    ///
+    /// # Errors
+    ///
+    /// Fails if the save doesn't record the player's attributes.
+    pub fn set_attribute(&mut self, attribute: Attribute, value: u8) -> Result<(), TesError> {
+        let attributes = self.player_base.attributes_mut().ok_or_else(|| {
+            TesError::RequirementFailed(String::from(
+                "Player attributes are not present in the save",
+            ))
+        })?;
+        attributes[attribute] = value;
+        Ok(())
+    }
+
+    /// Gets the save being edited
+    ///
+    /// Changes made through the editor aren't visible in the save until they're committed.
+    pub fn save(&self) -> &Save {
+        self.save
+    }
+
+    /// Writes the staged changes to the save
+    ///
+    /// # Errors
+    ///
+    /// Fails if the player's change records can't be written.
+    pub fn commit(self) -> Result<(), TesError> {
+        self.save
+            .update_form_change(&self.player_base, FORM_PLAYER)?;
+        self.save
+            .update_form_change(&self.player_ref, FORM_PLAYER_REF)?;
+
+        if let Some(name) = self.header_name {
+            self.save.set_player_name(name)?;
+        }
+        if let Some(level) = self.header_level {
+            self.save.set_player_level(level);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tes4::save::TEST_SAVE;
+    use std::io::Cursor;
+
+    #[test]
+    fn edit_player() {
+        let mut save = Save::read(Cursor::new(TEST_SAVE)).unwrap();
+        let spell = FormId(0x000a97b5);
+        let original_level = save.player_level();
+
+        let mut editor = SaveEditor::new(&mut save).unwrap();
+        editor.set_player_name("Edited").unwrap();
+        editor.set_level(12).unwrap();
+        assert!(editor.add_spell(spell));
+        assert!(!editor.add_spell(spell));
+        let gold = editor.gold();
+        editor.add_gold(250);
+        editor.set_attribute(Attribute::Luck, 77).unwrap();
+        assert!(editor.set_level(0).is_err());
+        // nothing reaches the save until the changes are committed
+        assert_eq!(editor.save().player_name(), "test");
+        assert_eq!(editor.save().player_level(), original_level);
+        editor.commit().unwrap();
+
+        let mut buf = vec![];
+        save.write(Cursor::new(&mut buf)).unwrap();
+        let mut save = Save::read(Cursor::new(buf)).unwrap();
+        assert_eq!(save.player_name(), "Edited");
+        assert_eq!(save.player_level(), 12);
+
+        let mut editor = SaveEditor::new(&mut save).unwrap();
+        assert_eq!(editor.player_name(), "Edited");
+        assert_eq!(editor.level(), 12);
+        assert!(editor.has_spell(spell));
+        assert_eq!(editor.gold(), gold + 250);
+        assert_eq!(editor.attribute(Attribute::Luck), Some(77));
+        assert!(editor.remove_spell(spell));
+        assert!(!editor.has_spell(spell));
+    }
+}
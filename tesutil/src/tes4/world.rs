@@ -1,14 +1,57 @@
+use std::any::Any;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::ops::{Deref, DerefMut, Index, IndexMut};
+use std::ops::{Deref, Index, IndexMut};
 use std::path::Path;
+use std::sync::Arc;
 
 use super::cosave::*;
 use super::plugin::*;
 use super::save::*;
-use super::{FindForm, FormId, MagicEffectType, MAGIC_EFFECTS};
-use crate::{Form, OwnedOrRef, Record, TesError, World};
+use super::{Bsa, FindForm, FormId, MagicEffectType, OblivionIni, PluginIndex, MAGIC_EFFECTS};
+use crate::{
+    list_saves, matches_search, Asset, Field, Form, FormCache, GamePaths, GridCoords, LoadOptions,
+    LoadStats, MagicSchool, MissingPluginPolicy, OwnedOrRef, Provenance, Record, RecordMut,
+    SaveList, SaveMetadata, SearchMatch, SearchResults, TesError, Vfs, World,
+};
 
 static BASE_GAME: &str = "Oblivion.esm";
+static SAVE_EXTENSION: &str = "ess";
+
+/// How a form ID resolves across the load order
+///
+/// See [`Tes4World::explain_form`].
+///
+/// [`Tes4World::explain_form`]: struct.Tes4World.html#method.explain_form
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FormExplanation {
+    /// The form ID being explained, as seen by the world
+    pub form_id: FormId,
+    /// Lowercase file name of the plugin the form originates from
+    pub master: String,
+    /// Each plugin that contains a version of the form, in load order
+    ///
+    /// The first entry is the master if the master actually defines the form; every later entry
+    /// overrides the ones before it.
+    pub versions: Vec<Provenance<FormId>>,
+}
+
+impl FormExplanation {
+    /// Gets the plugin providing the version of the form that's active in game
+    ///
+    /// Returns `None` if no plugin contains the form.
+    pub fn winner(&self) -> Option<&Provenance<FormId>> {
+        self.versions.last()
+    }
+
+    /// Gets the plugins overriding the master's version of the form, in load order
+    pub fn overrides(&self) -> impl Iterator<Item = &Provenance<FormId>> {
+        self.versions
+            .iter()
+            .filter(move |v| v.plugin != self.master)
+    }
+}
 
 /// The full set of objects in the game world
 ///
@@ -18,6 +61,10 @@ static BASE_GAME: &str = "Oblivion.esm";
 pub struct Tes4World {
     plugins: Vec<(String, Tes4Plugin)>,
     save: Option<(Save, CoSave)>,
+    form_cache: FormCache<FormId>,
+    missing_plugins: Vec<(PluginIndex, String)>,
+    ini: Option<OblivionIni>,
+    vfs: Vfs,
 }
 
 impl Tes4World {
@@ -32,6 +79,29 @@ impl Tes4World {
         P: AsRef<Path>,
         Q: AsRef<Path>,
     {
+        Tes4World::load_world_with(
+            &Tes4World::game_paths(game_dir),
+            plugins_path,
+            LoadOptions::new(),
+        )
+    }
+
+    /// Loads the world from Plugins.txt and a game whose data directories may be outside its
+    /// install root
+    ///
+    /// Each plugin is loaded from the data directory with the highest precedence that has it. If
+    /// the options have an index cache, plugins whose indexes are in it load faster, and it's
+    /// updated with the indexes of plugins that weren't.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading Plugins.txt or a plugin file,
+    /// or if a plugin file contains invalid data.
+    pub fn load_world_with<P: AsRef<Path>>(
+        paths: &GamePaths,
+        plugins_path: P,
+        options: LoadOptions,
+    ) -> Result<Tes4World, TesError> {
         let mut plugin_names: Vec<String> = fs::read_to_string(plugins_path)?
             .lines()
             .map(String::from)
@@ -45,15 +115,86 @@ impl Tes4World {
             plugin_names.push(String::from(BASE_GAME));
         }
 
-        let plugin_dir = game_dir.as_ref().join(Self::PLUGIN_DIR);
-        let plugins = Tes4World::load_plugins(plugin_dir, plugin_names.into_iter())?;
+        let plugins = Tes4World::load_plugins(paths, plugin_names.into_iter(), options.cache)?;
+        let vfs = Tes4World::load_plugin_archives(paths, &plugins);
 
         Ok(Tes4World {
             plugins,
             save: None,
+            form_cache: FormCache::new(),
+            missing_plugins: vec![],
+            ini: None,
+            vfs,
         })
     }
 
+    /// Loads the world from an explicit list of plugin files, in load order
+    ///
+    /// Unlike [`load_world`], this doesn't read Plugins.txt, sort the plugins, or add Oblivion.esm
+    /// if it's missing, so it's suitable for analyzing plugins outside of a game installation.
+    ///
+    /// [`load_world`]: #method.load_world
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading a plugin file or if a plugin file
+    /// contains invalid data.
+    pub fn from_plugins<P, T>(paths: T) -> Result<Tes4World, TesError>
+    where
+        P: AsRef<Path>,
+        T: IntoIterator<Item = P>,
+    {
+        Tes4World::from_plugins_with(paths, LoadOptions::new())
+    }
+
+    /// Loads the world from an explicit list of plugin files, with options
+    ///
+    /// This is the same as [`from_plugins`], except that if the options have an index cache,
+    /// plugins whose indexes are in it load faster, and it's updated with the indexes of plugins
+    /// that weren't.
+    ///
+    /// [`from_plugins`]: #method.from_plugins
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading a plugin file or if a plugin file
+    /// contains invalid data.
+    pub fn from_plugins_with<P, T>(paths: T, options: LoadOptions) -> Result<Tes4World, TesError>
+    where
+        P: AsRef<Path>,
+        T: IntoIterator<Item = P>,
+    {
+        Ok(Tes4World {
+            plugins: Tes4World::load_plugin_files(paths, options.cache)?,
+            save: None,
+            form_cache: FormCache::new(),
+            missing_plugins: vec![],
+            ini: None,
+            vfs: Vfs::default(),
+        })
+    }
+
+    /// Lists the saves in a directory, from most to least recently modified
+    ///
+    /// Only the header of each save is read. Files that can't be read as saves are listed
+    /// separately instead of failing the whole listing.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs while reading the directory.
+    pub fn list_saves<P: AsRef<Path>>(dir: P) -> Result<SaveList, TesError> {
+        list_saves(dir, SAVE_EXTENSION, Save::read_metadata)
+    }
+
+    /// Gets the most recently modified save in a directory, if there are any
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs while reading the directory.
+    pub fn latest_save<P: AsRef<Path>>(dir: P) -> Result<Option<SaveMetadata>, TesError> {
+        Ok(Self::list_saves(dir)?.saves.into_iter().next())
+    }
+
     /// Loads the world from the Oblivion game directory and a save
     ///
     /// # Errors
@@ -65,18 +206,114 @@ impl Tes4World {
         P: AsRef<Path>,
         Q: AsRef<Path>,
     {
+        Tes4World::load_from_save_with(
+            &Tes4World::game_paths(game_dir),
+            save_path,
+            LoadOptions::new(),
+        )
+    }
+
+    /// Loads the world from a save, looking for the save's plugins in a game's data directories
+    ///
+    /// The save itself changes too often to be worth caching, so it's always read in full, even
+    /// if the options have an index cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading a plugin file, if a plugin file
+    /// contains invalid data, or if a plugin is missing and the options' policy is
+    /// [`MissingPluginPolicy::Fail`].
+    ///
+    /// [`MissingPluginPolicy::Fail`]: ../enum.MissingPluginPolicy.html#variant.Fail
+    pub fn load_from_save_with<P: AsRef<Path>>(
+        paths: &GamePaths,
+        save_path: P,
+        options: LoadOptions,
+    ) -> Result<Tes4World, TesError> {
         let cosave_path = save_path.as_ref().with_extension("obse");
         let save = Save::load_file(save_path)?;
         let cosave = CoSave::load_file(cosave_path)?;
-        let plugin_dir = game_dir.as_ref().join(Self::PLUGIN_DIR);
-        let plugins = Tes4World::load_plugins(plugin_dir, save.iter_plugins())?;
+
+        let mut missing_plugins = vec![];
+        if options.missing_plugins == MissingPluginPolicy::Placeholder {
+            for (i, name) in save.iter_plugins().enumerate() {
+                if paths.find_data_file(name).is_none() {
+                    missing_plugins.push((PluginIndex(i as u8), String::from(name)));
+                }
+            }
+        }
+
+        let mut plugins = Tes4World::load_plugins(
+            paths,
+            save.iter_plugins()
+                .filter(|name| !missing_plugins.iter().any(|(_, n)| n.as_str() == *name)),
+            options.cache,
+        )?;
+        // insert in order so each placeholder ends up at the index the save expects
+        for (PluginIndex(i), name) in &missing_plugins {
+            let index = cmp::min(*i as usize, plugins.len());
+            plugins.insert(index, (name.to_lowercase(), Tes4Plugin::new(None, None)));
+        }
+        let vfs = Tes4World::load_plugin_archives(paths, &plugins);
 
         Ok(Tes4World {
             plugins,
             save: Some((save, cosave)),
+            form_cache: FormCache::new(),
+            missing_plugins,
+            ini: None,
+            vfs,
         })
     }
 
+    /// Creates a virtual file system with the archive that goes with each plugin, if it has one
+    ///
+    /// Like the game, a plugin's archive is the BSA in the data directory with the same name as
+    /// the plugin, and the archives of later plugins take precedence. Archives that can't be
+    /// opened are recorded as bad archives instead.
+    fn load_plugin_archives(paths: &GamePaths, plugins: &[(String, Tes4Plugin)]) -> Vfs {
+        let mut vfs = Vfs::new(paths.clone());
+        for (name, _) in plugins {
+            let archive_name = Path::new(name).with_extension("bsa");
+            if let Some(archive_path) = paths.find_data_file(archive_name) {
+                match Bsa::open(&archive_path) {
+                    Ok(archive) => vfs.add_archive(archive),
+                    Err(e) => vfs.add_bad_archive(archive_path, e),
+                }
+            }
+        }
+
+        vfs
+    }
+
+    /// Iterates over the names of plugins the save depends on that weren't installed
+    ///
+    /// This is only ever non-empty if the world was loaded with
+    /// [`MissingPluginPolicy::Placeholder`].
+    ///
+    /// [`MissingPluginPolicy::Placeholder`]: ../enum.MissingPluginPolicy.html#variant.Placeholder
+    pub fn missing_plugins(&self) -> impl Iterator<Item = &str> {
+        self.missing_plugins.iter().map(|(_, n)| n.as_str())
+    }
+
+    /// Gets the form IDs of the save's change records for forms from missing plugins
+    ///
+    /// These change records can't be interpreted without the plugin that defines the form, so
+    /// they should be left as they are.
+    pub fn change_records_from_missing_plugins(&self) -> Vec<FormId> {
+        match self.save {
+            Some((ref save, _)) => save
+                .iter_change_ids()
+                .filter(|id| {
+                    self.missing_plugins
+                        .iter()
+                        .any(|(index, _)| *index == id.master_index())
+                })
+                .collect(),
+            None => vec![],
+        }
+    }
+
     /// Gets a plugin by name if the plugin is loaded
     pub fn get_plugin(&self, search: &str) -> Option<&<Self as World>::Plugin> {
         let search = search.to_lowercase();
@@ -88,6 +325,8 @@ impl Tes4World {
 
     /// Gets a plugin mutably by name if the plugin is loaded
     pub fn get_plugin_mut(&mut self, search: &str) -> Option<&mut <Self as World>::Plugin> {
+        // we can't tell what the caller will change
+        self.form_cache.clear();
         let search = search.to_lowercase();
         self.plugins
             .iter_mut()
@@ -143,6 +382,7 @@ impl Tes4World {
 
     /// Gets the currently load save mutably, if there is one
     pub fn get_save_mut(&mut self) -> Option<&mut Save> {
+        self.form_cache.clear();
         self.save.as_mut().map(|(s, _)| s)
     }
 
@@ -164,13 +404,14 @@ impl Tes4World {
     /// Gets a record by form ID
     pub fn get_record(&self, search: &FindForm) -> Option<impl Deref<Target = Tes4Record> + '_> {
         let form_id = self.get_form_id(search)?;
-        let index = form_id.index() as usize;
-        if index == 0xff {
+        let master_index = form_id.master_index();
+        if master_index.is_save() {
             if let Some((ref save, _)) = self.save {
                 return save.get_record(form_id);
             }
         }
 
+        let index = usize::from(master_index);
         if index >= self.plugins.len() {
             return None;
         }
@@ -195,19 +436,87 @@ impl Tes4World {
         None
     }
 
-    /// Gets a record by form ID
-    pub fn get_record_mut(
-        &self,
-        search: &FindForm,
-    ) -> Option<impl Deref<Target = Tes4Record> + DerefMut<Target = Tes4Record> + '_> {
+    /// Finds which plugin provides the active version of a record
+    ///
+    /// Returns `None` if there is no such record or if the record comes from the save.
+    pub fn get_provenance(&self, search: &FindForm) -> Option<Provenance<FormId>> {
         let form_id = self.get_form_id(search)?;
-        let index = form_id.index() as usize;
-        if index == 0xff {
-            if let Some((ref save, _)) = self.save {
+        let index = usize::from(form_id.master_index());
+        if form_id.master_index().is_save() || index >= self.plugins.len() {
+            return None;
+        }
+
+        // same search as in get_record
+        let target_name = &self.plugins[index].0;
+        let self_search = FindForm::ByMaster(None, form_id.0);
+        for (i, (name, plugin)) in self.plugins.iter().enumerate().skip(index).rev() {
+            if let Some(record) = plugin.get_record(if name == target_name {
+                &self_search
+            } else {
+                search
+            }) {
+                return Some(Provenance {
+                    plugin: name.clone(),
+                    load_order_index: i,
+                    position: record.id(),
+                });
+            }
+        }
+
+        None
+    }
+
+    /// Explains which plugin a form comes from and which plugins override it
+    ///
+    /// This is the information xEdit shows in a record's header, and is useful for debugging
+    /// mapping files and finding conflicts. Records are not finalized. Returns `None` if the form
+    /// comes from the save or its master isn't in the load order.
+    pub fn explain_form(&self, form_id: FormId) -> Option<FormExplanation> {
+        let index = usize::from(form_id.master_index());
+        if form_id.master_index().is_save() || index >= self.plugins.len() {
+            return None;
+        }
+
+        let master = &self.plugins[index].0;
+        let mut versions = vec![];
+        for (i, (name, plugin)) in self.plugins.iter().enumerate().skip(index) {
+            let search = if name == master {
+                FindForm::ByMaster(None, form_id.0)
+            } else {
+                FindForm::ByMaster(Some(master), form_id.0)
+            };
+
+            if let Some(position) = plugin.find_form_id(&search) {
+                versions.push(Provenance {
+                    plugin: name.clone(),
+                    load_order_index: i,
+                    position,
+                });
+            }
+        }
+
+        Some(FormExplanation {
+            form_id,
+            master: master.clone(),
+            versions,
+        })
+    }
+
+    /// Gets a record by form ID mutably
+    ///
+    /// Any cached forms read from the record are dropped. This takes `&mut self` so the cache can't
+    /// be refilled from the record while the caller is still changing it.
+    pub fn get_record_mut(&mut self, search: &FindForm) -> Option<RecordMut<Tes4Record>> {
+        let form_id = self.get_form_id(search)?;
+        self.form_cache.invalidate(&form_id);
+        let master_index = form_id.master_index();
+        if master_index.is_save() {
+            if let Some((ref mut save, _)) = self.save {
                 return save.get_record_mut(form_id);
             }
         }
 
+        let index = usize::from(master_index);
         if index >= self.plugins.len() {
             return None;
         }
@@ -217,10 +526,10 @@ impl Tes4World {
         // the record originates from. then we iterate through every plugin at a position >= that in the
         // load order in reverse order, looking for the latest plugin that contains that record from that
         // master.
-        let target_name = &self.plugins[index].0;
+        let target_name = self.plugins[index].0.clone();
         let self_search = FindForm::ByMaster(None, form_id.0);
-        for (name, plugin) in self.plugins.iter().skip(index).rev() {
-            if let Some(record) = plugin.get_record_mut(if name == target_name {
+        for (name, plugin) in self.plugins.iter_mut().skip(index).rev() {
+            if let Some(record) = plugin.get_record_mut(if *name == target_name {
                 &self_search
             } else {
                 search
@@ -232,6 +541,261 @@ impl Tes4World {
         None
     }
 
+    /// Finds the exterior cell at the given grid coordinates in a worldspace
+    ///
+    /// Returns the form ID of the cell, or `None` if no plugin defines it.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a record in the worldspace contains invalid data.
+    pub fn get_exterior_cell(
+        &self,
+        worldspace: &FindForm,
+        grid: GridCoords,
+    ) -> Result<Option<FormId>, TesError> {
+        let form_id = match self.get_form_id(worldspace) {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+        let index = usize::from(form_id.master_index());
+        if index >= self.plugins.len() {
+            return Ok(None);
+        }
+
+        // as with records, the worldspace can only be extended by its own plugin or plugins later in
+        // the load order, and later plugins take precedence
+        let target_name = &self.plugins[index].0;
+        let self_search = FindForm::ByMaster(None, form_id.0);
+        let search = FindForm::ByMaster(Some(target_name.as_str()), form_id.0);
+        for (name, plugin) in self.plugins.iter().skip(index).rev() {
+            let cell = plugin.get_exterior_cell(
+                if name == target_name {
+                    &self_search
+                } else {
+                    &search
+                },
+                grid,
+            )?;
+            if let Some(local_id) = cell {
+                let master = plugin
+                    .master_name(local_id.master_index())
+                    .unwrap_or(name.as_str());
+                return Ok(self.get_form_id(&FindForm::ByMaster(Some(master), local_id.0)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Finds the exterior cell containing a position in a worldspace
+    ///
+    /// # Errors
+    ///
+    /// Fails if a record in the worldspace contains invalid data.
+    pub fn get_cell_at(
+        &self,
+        worldspace: &FindForm,
+        x: f32,
+        y: f32,
+    ) -> Result<Option<FormId>, TesError> {
+        self.get_exterior_cell(worldspace, Tes4World::world_to_grid(x, y))
+    }
+
+    /// Finds the records of a given type whose editor IDs are most similar to a search string
+    ///
+    /// This is intended for suggesting corrections when a lookup by ID fails. Results are returned
+    /// closest first.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a record of the given type contains invalid data.
+    pub fn find_similar_editor_ids(
+        &self,
+        search: &str,
+        record_type: &[u8; 4],
+        max_results: usize,
+    ) -> Result<Vec<(String, FormId)>, TesError> {
+        // later plugins override the editor IDs of earlier ones
+        let mut ids = HashMap::new();
+        for (name, plugin) in &self.plugins {
+            for (editor_id, local_id) in plugin.editor_ids(record_type)? {
+                let master = plugin
+                    .master_name(local_id.master_index())
+                    .unwrap_or(name.as_str());
+                if let Some(form_id) =
+                    self.get_form_id(&FindForm::ByMaster(Some(master), local_id.0))
+                {
+                    ids.insert(form_id, editor_id);
+                }
+            }
+        }
+
+        // if several forms share an editor ID, suggest the lowest form ID so the result is stable
+        let mut by_editor_id = HashMap::new();
+        for (form_id, editor_id) in &ids {
+            let entry = by_editor_id.entry(editor_id.as_str()).or_insert(*form_id);
+            *entry = cmp::min(*entry, *form_id);
+        }
+        Ok(
+            closest_matches(search, ids.values().map(String::as_str), max_results)
+                .into_iter()
+                .map(|e| (String::from(e), by_editor_id[e]))
+                .collect(),
+        )
+    }
+
+    /// Searches the load order for records whose form ID, editor ID, or name contains some text,
+    /// ignoring case
+    ///
+    /// Form IDs are matched as eight hex digits. Only the active version of each record is
+    /// included. Results are sorted by form ID. Records whose editor ID or name can't be read are
+    /// skipped and listed in [`SearchResults::errors`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if a record can't be finalized.
+    ///
+    /// [`SearchResults::errors`]: ../struct.SearchResults.html#structfield.errors
+    pub fn search(&self, text: &str) -> Result<SearchResults<FormId>, TesError> {
+        let text = text.to_lowercase();
+        let mut seen = HashSet::new();
+        let mut results = SearchResults::default();
+        for (name, plugin) in self.plugins.iter().rev() {
+            plugin.try_for_each_record(|record| {
+                let local_id = record.id();
+                let master = plugin
+                    .master_name(local_id.master_index())
+                    .unwrap_or(name.as_str());
+                let form_id = match self.get_form_id(&FindForm::ByMaster(Some(master), local_id.0))
+                {
+                    Some(form_id) => form_id,
+                    None => return Ok(()),
+                };
+
+                // we're going through the load order backwards, so the first version we see of a
+                // record is the active one
+                if !seen.insert(form_id) {
+                    return Ok(());
+                }
+
+                let mut editor_id = None;
+                let mut full_name = None;
+                for field in record.iter() {
+                    let value = match field.name() {
+                        b"EDID" => &mut editor_id,
+                        b"FULL" => &mut full_name,
+                        _ => continue,
+                    };
+
+                    match field.get_zstring() {
+                        Ok(s) => *value = Some(String::from(s)),
+                        Err(e) => {
+                            results.errors.push((form_id, e));
+                            return Ok(());
+                        }
+                    }
+                }
+
+                let hex_id = format!("{}", form_id);
+                if matches_search(
+                    &text,
+                    [
+                        Some(hex_id.as_str()),
+                        editor_id.as_deref(),
+                        full_name.as_deref(),
+                    ]
+                    .into_iter()
+                    .flatten(),
+                ) {
+                    results.matches.push(SearchMatch {
+                        id: form_id,
+                        record_type: *record.name(),
+                        editor_id,
+                        name: full_name,
+                        plugin: name.clone(),
+                    });
+                }
+
+                Ok(())
+            })?;
+        }
+
+        results.matches.sort_by_key(|m| m.id.0);
+        results.errors.sort_by_key(|(id, _)| id.0);
+        Ok(results)
+    }
+
+    /// Gets the settings from Oblivion.ini, if they've been provided
+    pub fn ini(&self) -> Option<&OblivionIni> {
+        self.ini.as_ref()
+    }
+
+    /// Provides the settings from Oblivion.ini
+    ///
+    /// The world doesn't read Oblivion.ini itself, because where it's kept depends on the user's
+    /// Windows profile rather than on the game's install directory.
+    pub fn set_ini(&mut self, ini: Option<OblivionIni>) {
+        self.ini = ini;
+    }
+
+    /// Registers the archives listed in Oblivion.ini with the world's virtual file system
+    ///
+    /// The game loads these before any plugin's archive, so the plugins' archives take precedence
+    /// over them. Archives that can't be found in the data directories are skipped, as is the
+    /// whole step if no INI was provided with [`set_ini`]. Archives that can't be opened are
+    /// recorded in the [`Vfs`] as bad archives.
+    ///
+    /// [`set_ini`]: #method.set_ini
+    /// [`Vfs`]: ../struct.Vfs.html
+    pub fn load_ini_archives(&mut self) {
+        let (ini, paths) = match (self.ini.as_ref(), self.vfs.paths()) {
+            (Some(ini), Some(paths)) => (ini, paths),
+            _ => return,
+        };
+
+        let mut archives = vec![];
+        let mut bad_archives = vec![];
+        for archive_name in ini.archive_list() {
+            if let Some(archive_path) = paths.find_data_file(archive_name) {
+                match Bsa::open(&archive_path) {
+                    Ok(archive) => archives.push(archive),
+                    Err(e) => bad_archives.push((archive_path, e)),
+                }
+            }
+        }
+
+        // the first archive in the list has the lowest precedence
+        for archive in archives.into_iter().rev() {
+            self.vfs.add_base_archive(archive);
+        }
+        for (archive_path, e) in bad_archives {
+            self.vfs.add_bad_archive(archive_path, e);
+        }
+    }
+
+    /// Gets the virtual file system the world's assets are read from
+    pub fn vfs(&self) -> &Vfs {
+        &self.vfs
+    }
+
+    /// Gets the virtual file system the world's assets are read from mutably
+    ///
+    /// This can be used to register additional archives.
+    pub fn vfs_mut(&mut self) -> &mut Vfs {
+        &mut self.vfs
+    }
+
+    /// Opens an asset, such as a mesh or texture, by its path relative to the data directory
+    ///
+    /// Returns `None` if the asset isn't a loose file and isn't in any registered archive.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs or if the asset can't be opened from its archive.
+    pub fn open_asset(&self, path: &str) -> Result<Option<Asset>, TesError> {
+        self.vfs.open(path)
+    }
+
     /// Gets a float game setting by name
     pub fn get_float_setting(&self, name: &str, default: f32) -> Result<f32, TesError> {
         for (_, plugin) in self.plugins.iter().rev() {
@@ -257,6 +821,163 @@ impl Tes4World {
         Ok(OwnedOrRef::Ref(&MAGIC_EFFECTS[effect_type]))
     }
 
+    /// Calculates the auto-calculated magicka cost of a spell or other magic form
+    ///
+    /// # Errors
+    ///
+    /// Fails if the definition of one of the magic effects is invalid.
+    pub fn calculate_magic_cost<M: Magic + ?Sized>(&self, magic: &M) -> Result<f32, TesError> {
+        let mut total_cost = 0f32;
+        for effect in magic.iter_effects() {
+            let base_effect = self.get_magic_effect(effect.effect_type())?;
+            total_cost += effect.cost(base_effect.base_cost());
+        }
+
+        Ok(total_cost)
+    }
+
+    /// Gets the magic school of a spell or other magic form
+    ///
+    /// The school is that of the form's most expensive effect. Returns `None` if the form has no
+    /// effects.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the definition of one of the magic effects is invalid.
+    pub fn get_magic_school<M: Magic + ?Sized>(
+        &self,
+        magic: &M,
+    ) -> Result<Option<MagicSchool>, TesError> {
+        let mut school = None;
+        let mut max_cost = 0f32;
+        for effect in magic.iter_effects() {
+            let base_effect = self.get_magic_effect(effect.effect_type())?;
+            let cost = effect.cost(base_effect.base_cost());
+            if school.is_none() || cost > max_cost {
+                max_cost = cost;
+                school = Some(
+                    effect
+                        .script_effect()
+                        .and_then(ScriptEffect::school)
+                        .unwrap_or_else(|| base_effect.school()),
+                );
+            }
+        }
+
+        Ok(school)
+    }
+
+    /// Checks that the range, magnitude, area, and duration of each of a magic form's effects are
+    /// allowed by the effect's active definition
+    ///
+    /// # Errors
+    ///
+    /// Fails if the definition of one of the magic effects is invalid or if one of an effect's
+    /// properties isn't allowed.
+    pub fn check_effects<M: Magic + ?Sized>(&self, magic: &M) -> Result<(), TesError> {
+        for effect in magic.iter_effects() {
+            let base_effect = self.get_magic_effect(effect.effect_type())?;
+            effect.check_range(&base_effect)?;
+            effect.check_values(&base_effect)?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the magic effects whose active definitions allow them to be used to enchant items
+    ///
+    /// # Errors
+    ///
+    /// Fails if the definition of one of the magic effects is invalid.
+    pub fn enchanting_effects(&self) -> Result<Vec<MagicEffectType>, TesError> {
+        self.effects_where(MagicEffect::allowed_for_enchanting)
+    }
+
+    /// Gets the magic effects whose active definitions allow them to be used to make custom spells
+    ///
+    /// # Errors
+    ///
+    /// Fails if the definition of one of the magic effects is invalid.
+    pub fn spellmaking_effects(&self) -> Result<Vec<MagicEffectType>, TesError> {
+        self.effects_where(MagicEffect::allowed_for_spellmaking)
+    }
+
+    fn effects_where<F: Fn(&MagicEffect) -> bool>(
+        &self,
+        predicate: F,
+    ) -> Result<Vec<MagicEffectType>, TesError> {
+        let mut effects = vec![];
+        for (effect_type, _) in MAGIC_EFFECTS.iter() {
+            if predicate(&*self.get_magic_effect(effect_type)?) {
+                effects.push(effect_type);
+            }
+        }
+
+        Ok(effects)
+    }
+
+    /// Builds the counter effect graph for the magic effects active in this world
+    pub fn counter_effect_graph(&self) -> Result<CounterEffectGraph, TesError> {
+        let mut effects = Vec::with_capacity(MAGIC_EFFECTS.len());
+        for (effect_type, _) in MAGIC_EFFECTS.iter() {
+            effects.push(self.get_magic_effect(effect_type)?);
+        }
+
+        Ok(CounterEffectGraph::new(effects.iter().map(|e| &**e)))
+    }
+
+    /// Builds a table of the potion graphics and magic effect costs in this world
+    ///
+    /// Only the active version of each potion is used, and potions are added in load order, so for
+    /// each strongest effect, the graphics of the last potion with that effect are used. Potions
+    /// that fail to load or that have an effect whose definition is invalid are skipped.
+    pub fn potion_graphics(&self) -> PotionGraphicsTable {
+        let mut table = PotionGraphicsTable::new();
+        let mut invalid_effects = HashSet::new();
+        for (effect_type, _) in MAGIC_EFFECTS.iter() {
+            match self.get_magic_effect(effect_type) {
+                Ok(effect) => table.set_base_cost(effect_type, effect.base_cost()),
+                Err(_) => {
+                    invalid_effects.insert(effect_type);
+                }
+            }
+        }
+
+        // we go through the load order backwards, so the first version we see of a potion is the
+        // active one
+        let mut seen = HashSet::new();
+        let mut potions_by_plugin = vec![];
+        for (name, plugin) in self.plugins.iter().rev() {
+            let mut potions = vec![];
+            for record in plugin.records_of_type(Potion::RECORD_TYPE) {
+                let local_id = record.id();
+                let master = plugin
+                    .master_name(local_id.master_index())
+                    .unwrap_or(name.as_str());
+                match self.get_form_id(&FindForm::ByMaster(Some(master), local_id.0)) {
+                    Some(form_id) if seen.insert(form_id) => (),
+                    _ => continue,
+                }
+
+                if let Ok(potion) = Potion::read(&record) {
+                    if !potion
+                        .iter_effects()
+                        .any(|e| invalid_effects.contains(&e.effect_type()))
+                    {
+                        potions.push(potion);
+                    }
+                }
+            }
+            potions_by_plugin.push(potions);
+        }
+
+        for potion in potions_by_plugin.iter().rev().flatten() {
+            table.add_potion(potion);
+        }
+
+        table
+    }
+
     /// Gets a form by form ID
     pub fn get<T: Form<Field = Tes4Field, Record = Tes4Record>>(
         &self,
@@ -268,6 +989,51 @@ impl Tes4World {
         }
     }
 
+    /// Gets a form by form ID along with where it was loaded from
+    ///
+    /// Forms from the save have no provenance, so they're treated as not found.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the matching record contains invalid data.
+    pub fn get_with_provenance<T: Form<Field = Tes4Field, Record = Tes4Record>>(
+        &self,
+        search: &FindForm,
+    ) -> Result<Option<(T, Provenance<FormId>)>, TesError> {
+        let provenance = match self.get_provenance(search) {
+            Some(provenance) => provenance,
+            None => return Ok(None),
+        };
+
+        let plugin = &self.plugins[provenance.load_order_index].1;
+        let local_search = FindForm::ByIndex(provenance.position);
+        Ok(match plugin.get_record(&local_search) {
+            Some(record) => Some((T::read(&*record)?, provenance)),
+            None => None,
+        })
+    }
+
+    /// Gets a form by form ID, reusing the previously loaded form if there is one
+    ///
+    /// This is faster than [`get`] for forms that are looked up repeatedly. A form is dropped from
+    /// the cache when its record is borrowed mutably, and the whole cache is cleared when a plugin
+    /// or the save is.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the matching record contains invalid data.
+    ///
+    /// [`get`]: #method.get
+    pub fn get_cached<T>(&self, search: &FindForm) -> Result<Option<Arc<T>>, TesError>
+    where
+        T: Form<Field = Tes4Field, Record = Tes4Record> + Any + Send + Sync,
+    {
+        match self.get_form_id(search) {
+            Some(form_id) => self.form_cache.get_or_read(form_id, || self.get(search)),
+            None => Ok(None),
+        }
+    }
+
     /// Loads a form by ID and type
     ///
     /// # Errors
@@ -281,7 +1047,7 @@ impl Tes4World {
     }
 
     /// Updates a form by form ID
-    pub fn update<T>(&self, form: &T, search: &FindForm) -> Result<(), TesError>
+    pub fn update<T>(&mut self, form: &T, search: &FindForm) -> Result<(), TesError>
     where
         T: Form<Field = Tes4Field, Record = Tes4Record>,
     {
@@ -299,7 +1065,9 @@ impl Tes4World {
     pub fn get_item_from_record(&self, record: &Tes4Record) -> Result<Box<dyn Item>, TesError> {
         match record.name() {
             Ammo::RECORD_TYPE => Ok(Box::new(Ammo::read(record)?)),
+            Armor::RECORD_TYPE => Ok(Box::new(Armor::read(record)?)),
             Book::RECORD_TYPE => Ok(Box::new(Book::read(record)?)),
+            Clothing::RECORD_TYPE => Ok(Box::new(Clothing::read(record)?)),
             Potion::RECORD_TYPE => Ok(Box::new(Potion::read(record)?)),
             Weapon::RECORD_TYPE => Ok(Box::new(Weapon::read(record)?)),
             _ => Err(TesError::RequirementFailed(String::from(
@@ -319,12 +1087,29 @@ impl Tes4World {
             None => None,
         })
     }
+
+    /// Gets statistics about the records loaded from all plugins, including records created in
+    /// the save if there is one
+    pub fn load_stats(&self) -> LoadStats {
+        let mut stats = LoadStats::new();
+        for (_, plugin) in &self.plugins {
+            stats.merge(&plugin.load_stats());
+        }
+
+        if let Some((ref save, _)) = self.save {
+            stats.merge(&save.load_stats());
+        }
+
+        stats
+    }
 }
 
 impl World for Tes4World {
     type Plugin = Tes4Plugin;
 
     const PLUGIN_DIR: &'static str = "Data";
+
+    const CELL_SIZE: f32 = 4096.;
 }
 
 impl Index<u8> for Tes4World {
@@ -337,6 +1122,7 @@ impl Index<u8> for Tes4World {
 
 impl IndexMut<u8> for Tes4World {
     fn index_mut(&mut self, index: u8) -> &mut Self::Output {
+        self.form_cache.clear();
         &mut self.plugins[index as usize].1
     }
 }
@@ -344,6 +1130,7 @@ impl IndexMut<u8> for Tes4World {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{EffectRange, IndexCache};
 
     static TEST_GAME_DIR: &str = "src/tes4/plugin/test";
 
@@ -358,4 +1145,144 @@ mod tests {
         let world = Tes4World::load_world(&game_dir, &plugin_path).unwrap();
         assert_eq!(world.plugins.len(), 2);
     }
+
+    #[test]
+    fn load_with_data_dirs() {
+        let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let game_dir = base_dir.join(TEST_GAME_DIR);
+        // the plugins aren't in the first data directory, so they have to come from the second
+        let mut paths = GamePaths::new(&game_dir, base_dir.join("src/tes3/plugin/test/Data Files"));
+        paths.add_data_dir(game_dir.join(Tes4World::PLUGIN_DIR));
+        let cache = IndexCache::new();
+        let world = Tes4World::load_world_with(
+            &paths,
+            game_dir.join("Plugins.txt"),
+            LoadOptions::new().cache(&cache),
+        )
+        .unwrap();
+        assert_eq!(world.plugins.len(), 2);
+        assert_eq!(cache.len(), 2);
+
+        paths.set_data_dirs([base_dir]);
+        assert!(Tes4World::load_world_with(
+            &paths,
+            game_dir.join("Plugins.txt"),
+            LoadOptions::new()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn load_stats() {
+        let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let game_dir = base_dir.join(TEST_GAME_DIR);
+        let plugin_path = game_dir.join("Plugins.txt");
+        let world = Tes4World::load_world(&game_dir, &plugin_path).unwrap();
+        let total = world.load_stats().total();
+        assert!(total.count > 0);
+        assert!(total.bytes > 0);
+        assert_eq!(total.finalized + total.lazy + total.failed, total.count);
+    }
+
+    #[test]
+    fn from_plugins() {
+        let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let plugin_dir = base_dir.join(TEST_GAME_DIR).join(Tes4World::PLUGIN_DIR);
+        let world = Tes4World::from_plugins(&[
+            plugin_dir.join("Oblivion.esm"),
+            plugin_dir.join("sample.esp"),
+        ])
+        .unwrap();
+        assert_eq!(world.plugins.len(), 2);
+        assert!(world.get_plugin("sample.esp").is_some());
+        assert!(world.get_save().is_none());
+    }
+
+    #[test]
+    fn search() {
+        let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let game_dir = base_dir.join(TEST_GAME_DIR);
+        let plugin_path = game_dir.join("Plugins.txt");
+        let mut world = Tes4World::load_world(&game_dir, &plugin_path).unwrap();
+
+        // the sample plugin overrides the setting from the master, so there should only be one match
+        let results = world.search("potiont1ale").unwrap();
+        assert!(results.errors.is_empty());
+        let matches = results.matches;
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].id, FormId(0x000051a8));
+        assert_eq!(matches[0].record_type, *b"GMST");
+        assert_eq!(matches[0].editor_id.as_deref(), Some("fPotionT1AleDurMult"));
+        assert!(matches[0].plugin.eq_ignore_ascii_case("sample.esp"));
+
+        assert_eq!(world.search("000051A8").unwrap().matches.len(), 1);
+        assert!(world
+            .search("not a real record")
+            .unwrap()
+            .matches
+            .is_empty());
+
+        // a record with an unterminated editor ID is skipped rather than ending the search
+        let mut record = Tes4Record::new(b"GMST");
+        record.add_field(Tes4Field::new(b"EDID", b"fPotionT1AleBroken".to_vec()).unwrap());
+        let bad_id = world.add_record_to_plugin("sample.esp", record).unwrap();
+        let results = world.search("potiont1ale").unwrap();
+        assert_eq!(results.matches.len(), 1);
+        assert_eq!(results.errors.len(), 1);
+        assert_eq!(results.errors[0].0, bad_id);
+    }
+
+    #[test]
+    fn explain_form() {
+        let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let game_dir = base_dir.join(TEST_GAME_DIR);
+        let plugin_path = game_dir.join("Plugins.txt");
+        let world = Tes4World::load_world(&game_dir, &plugin_path).unwrap();
+
+        let explanation = world.explain_form(FormId(0x000051a8)).unwrap();
+        assert_eq!(explanation.master, "oblivion.esm");
+        assert_eq!(explanation.versions.len(), 2);
+        assert_eq!(explanation.winner().unwrap().plugin, "sample.esp");
+        assert_eq!(explanation.overrides().count(), 1);
+
+        let missing_master = FormId(0x000051a8).with_master_index(PluginIndex(5));
+        assert!(world.explain_form(missing_master).is_none());
+    }
+
+    fn make_potion(model: &str) -> Tes4Record {
+        let potion = Potion::builder(String::from("TestPotion"), String::from("Test Potion"))
+            .model(String::from(model), 8., String::from("IconTestPotion.dds"))
+            .effect(MagicEffectType::RestoreHealth, EffectRange::Self_, 10, 0, 1)
+            .build()
+            .unwrap();
+        let mut record = Tes4Record::new(Potion::RECORD_TYPE);
+        potion.write(&mut record).unwrap();
+        record
+    }
+
+    #[test]
+    fn potion_graphics_use_active_potions() {
+        let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let game_dir = base_dir.join(TEST_GAME_DIR);
+        let plugin_path = game_dir.join("Plugins.txt");
+        let mut world = Tes4World::load_world(&game_dir, &plugin_path).unwrap();
+
+        let form_id = world
+            .add_record_to_plugin("oblivion.esm", make_potion("Master.nif"))
+            .unwrap();
+        // the sample plugin's only master is Oblivion.esm, so the local form ID is the same
+        let mut record = make_potion("Override.nif");
+        record.set_id(form_id);
+        world
+            .get_plugin_mut("sample.esp")
+            .unwrap()
+            .add_record(record)
+            .unwrap();
+
+        let table = world.potion_graphics();
+        let mut potion = Potion::new(String::from("Healing"), String::from("Healing"));
+        potion.add_effect(SpellEffect::new(MagicEffectType::RestoreHealth));
+        potion.use_auto_graphics_from(&table);
+        assert_eq!(potion.model(), Some("Override.nif"));
+    }
 }
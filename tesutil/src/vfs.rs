@@ -0,0 +1,296 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::{GamePaths, TesError};
+
+/// An archive of game assets, such as a BSA
+pub trait Archive: Send {
+    /// Returns whether the archive contains a file at a path
+    fn contains(&self, path: &str) -> bool;
+
+    /// Reads the data of the file at a path in the archive
+    ///
+    /// Returns `None` if the archive has no file at that path.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs or if the file's data is invalid.
+    fn extract(&mut self, path: &str) -> Result<Option<Vec<u8>>, TesError>;
+
+    /// Opens the file at a path in the archive for streaming
+    ///
+    /// Returns `None` if the archive has no file at that path. By default, this extracts the whole
+    /// file into memory; archives that can read a file's data as it's needed should override it.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs or if the file's data is invalid.
+    fn open_entry(&mut self, path: &str) -> Result<Option<Box<dyn AssetStream>>, TesError> {
+        Ok(self
+            .extract(path)?
+            .map(|data| Box::new(Cursor::new(data)) as Box<dyn AssetStream>))
+    }
+}
+
+/// A reader over a file streamed from an [`Archive`]
+///
+/// This is implemented for every seekable reader that can be sent between threads.
+///
+/// [`Archive`]: trait.Archive.html
+pub trait AssetStream: Read + Seek + Send + fmt::Debug {}
+
+impl<T: Read + Seek + Send + fmt::Debug> AssetStream for T {}
+
+/// An asset opened from a [`Vfs`]
+///
+/// [`Vfs`]: struct.Vfs.html
+#[derive(Debug)]
+pub enum Asset {
+    /// A loose file in one of the data directories
+    Loose(BufReader<File>),
+    /// A file streamed from an archive
+    Archived(Box<dyn AssetStream>),
+}
+
+impl Read for Asset {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Asset::Loose(f) => f.read(buf),
+            Asset::Archived(s) => s.read(buf),
+        }
+    }
+}
+
+impl Seek for Asset {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        match self {
+            Asset::Loose(f) => f.seek(pos),
+            Asset::Archived(s) => s.seek(pos),
+        }
+    }
+}
+
+/// A virtual file system over a game's data directories and archives
+///
+/// Assets are looked up by their game-relative path, such as `meshes\r\wolf_black.nif`, with
+/// either `/` or `\` as the separator. Like the games, a loose file in a data directory takes
+/// precedence over a file in an archive, and when more than one archive has a file, the archive
+/// registered last wins.
+///
+/// Archives are matched case-insensitively, but whether loose files are depends on the file system
+/// they're on.
+///
+/// One bad archive shouldn't stop a game from loading, so archives that fail to open are skipped
+/// and kept track of with [`add_bad_archive`] instead.
+///
+/// [`add_bad_archive`]: #method.add_bad_archive
+#[derive(Default)]
+pub struct Vfs {
+    paths: Option<GamePaths>,
+    archives: Vec<Mutex<Box<dyn Archive>>>,
+    bad_archives: Vec<(PathBuf, TesError)>,
+}
+
+impl Vfs {
+    /// Creates a virtual file system over a game's data directories, with no archives
+    pub fn new(paths: GamePaths) -> Vfs {
+        Vfs {
+            paths: Some(paths),
+            archives: vec![],
+            bad_archives: vec![],
+        }
+    }
+
+    /// Sets the game whose data directories loose files are found in
+    pub fn set_paths(&mut self, paths: Option<GamePaths>) {
+        self.paths = paths;
+    }
+
+    /// Registers an archive, giving it precedence over the archives already registered
+    pub fn add_archive<A: Archive + 'static>(&mut self, archive: A) {
+        self.archives.push(Mutex::new(Box::new(archive)));
+    }
+
+    /// Registers an archive that the archives already registered take precedence over
+    pub fn add_base_archive<A: Archive + 'static>(&mut self, archive: A) {
+        self.archives.insert(0, Mutex::new(Box::new(archive)));
+    }
+
+    /// Gets the number of registered archives
+    pub fn num_archives(&self) -> usize {
+        self.archives.len()
+    }
+
+    /// Records an archive that was skipped because it couldn't be opened
+    pub fn add_bad_archive<P: AsRef<Path>>(&mut self, path: P, error: TesError) {
+        self.bad_archives.push((path.as_ref().to_path_buf(), error));
+    }
+
+    /// Iterates over the paths of archives that were skipped and the errors that opening them gave
+    pub fn bad_archives(&self) -> impl Iterator<Item = (&Path, &TesError)> {
+        self.bad_archives.iter().map(|(p, e)| (p.as_path(), e))
+    }
+
+    fn find_loose_file(&self, path: &str) -> Option<PathBuf> {
+        let relative_path: PathBuf = path
+            .split(|c| c == '/' || c == '\\')
+            .filter(|c| !c.is_empty())
+            .collect();
+        self.paths.as_ref()?.find_data_file(relative_path)
+    }
+
+    /// Returns whether an asset exists
+    pub fn contains(&self, path: &str) -> bool {
+        self.find_loose_file(path).is_some()
+            || self
+                .archives
+                .iter()
+                .any(|a| a.lock().unwrap().contains(path))
+    }
+
+    /// Opens an asset for reading
+    ///
+    /// Returns `None` if neither the data directories nor the archives have the asset.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an I/O error occurs or if the asset can't be opened from its archive.
+    pub fn open(&self, path: &str) -> Result<Option<Asset>, TesError> {
+        if let Some(file_path) = self.find_loose_file(path) {
+            return Ok(Some(Asset::Loose(BufReader::new(File::open(file_path)?))));
+        }
+
+        for archive in self.archives.iter().rev() {
+            if let Some(stream) = archive.lock().unwrap().open_entry(path)? {
+                return Ok(Some(Asset::Archived(stream)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Gets the paths of the game whose data directories loose files are found in, if it's known
+    pub fn paths(&self) -> Option<&GamePaths> {
+        self.paths.as_ref()
+    }
+}
+
+impl fmt::Debug for Vfs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Vfs")
+            .field("paths", &self.paths)
+            .field("num_archives", &self.archives.len())
+            .field("bad_archives", &self.bad_archives)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_failed;
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    struct MemoryArchive(HashMap<String, Vec<u8>>);
+
+    impl MemoryArchive {
+        fn new(files: &[(&str, &[u8])]) -> MemoryArchive {
+            MemoryArchive(
+                files
+                    .iter()
+                    .map(|(p, d)| (p.replace('/', "\\").to_lowercase(), d.to_vec()))
+                    .collect(),
+            )
+        }
+    }
+
+    impl Archive for MemoryArchive {
+        fn contains(&self, path: &str) -> bool {
+            self.0.contains_key(&path.replace('/', "\\").to_lowercase())
+        }
+
+        fn extract(&mut self, path: &str) -> Result<Option<Vec<u8>>, TesError> {
+            Ok(self.0.get(&path.replace('/', "\\").to_lowercase()).cloned())
+        }
+    }
+
+    fn read_asset(vfs: &Vfs, path: &str) -> Option<Vec<u8>> {
+        vfs.open(path).unwrap().map(|mut asset| {
+            let mut data = vec![];
+            asset.read_to_end(&mut data).unwrap();
+            data
+        })
+    }
+
+    #[test]
+    fn resolve_assets() {
+        let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let data_dir = base_dir.join("src/tes3/plugin/test/Data Files");
+        let mut vfs = Vfs::new(GamePaths::new(base_dir, &data_dir));
+
+        vfs.add_archive(MemoryArchive::new(&[
+            ("meshes\\r\\wolf_black.nif", &b"first"[..]),
+            ("test1.esp", &b"archived plugin"[..]),
+        ]));
+        vfs.add_archive(MemoryArchive::new(&[(
+            "Meshes/R/Wolf_Black.nif",
+            &b"second"[..],
+        )]));
+        vfs.add_base_archive(MemoryArchive::new(&[
+            ("meshes\\r\\wolf_black.nif", &b"base"[..]),
+            ("icons\\wolf.dds", &b"icon"[..]),
+        ]));
+
+        assert_eq!(vfs.num_archives(), 3);
+        assert_eq!(
+            read_asset(&vfs, "meshes/r/wolf_black.nif"),
+            Some(b"second".to_vec())
+        );
+        assert_eq!(read_asset(&vfs, "icons\\wolf.dds"), Some(b"icon".to_vec()));
+        // loose files win over archives
+        let loose = read_asset(&vfs, "test1.esp").unwrap();
+        assert_eq!(loose, std::fs::read(data_dir.join("test1.esp")).unwrap());
+        assert!(vfs.contains("icons/wolf.dds"));
+        assert!(!vfs.contains("icons/bear.dds"));
+        assert_eq!(read_asset(&vfs, "icons/bear.dds"), None);
+
+        vfs.add_bad_archive("broken.bsa", decode_failed("Invalid BSA"));
+        assert_eq!(vfs.num_archives(), 3);
+        let bad: Vec<_> = vfs.bad_archives().map(|(p, _)| p).collect();
+        assert_eq!(bad, [Path::new("broken.bsa")]);
+    }
+
+    #[cfg(feature = "tes3")]
+    #[test]
+    fn stream_archived_assets() {
+        let mut builder = crate::tes3::BsaBuilder::new();
+        builder
+            .add_file("meshes\\r\\wolf_black.nif", b"wolf mesh".to_vec())
+            .unwrap();
+        builder
+            .add_file("icons\\wolf.dds", b"wolf icon".to_vec())
+            .unwrap();
+        let mut data = vec![];
+        builder.write(&mut data).unwrap();
+
+        let mut vfs = Vfs::default();
+        vfs.add_archive(crate::tes3::Bsa::read(Cursor::new(data)).unwrap());
+
+        // both assets are streamed from the same archive at once
+        let mut mesh = vfs.open("meshes/r/wolf_black.nif").unwrap().unwrap();
+        let mut icon = vfs.open("icons/wolf.dds").unwrap().unwrap();
+        assert!(matches!(mesh, Asset::Archived(_)));
+        let mut buf = [0u8; 5];
+        mesh.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"wolf ");
+        icon.seek(SeekFrom::Start(5)).unwrap();
+        icon.read_exact(&mut buf[..4]).unwrap();
+        assert_eq!(&buf[..4], b"icon");
+        mesh.read_exact(&mut buf[..4]).unwrap();
+        assert_eq!(&buf[..4], b"mesh");
+    }
+}
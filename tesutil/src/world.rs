@@ -3,6 +3,106 @@ use std::path::Path;
 
 use crate::*;
 
+/// A record found by searching the load order for text
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SearchMatch<K> {
+    /// ID of the matching record
+    pub id: K,
+    /// Type of the matching record
+    pub record_type: [u8; 4],
+    /// The record's editor ID, if it has one
+    ///
+    /// In Morrowind, this is the same as the ID.
+    pub editor_id: Option<String>,
+    /// The record's in-game name, if it has one
+    pub name: Option<String>,
+    /// Name of the plugin that provides the active version of the record
+    pub plugin: String,
+}
+
+/// The results of searching the load order for text
+#[derive(Debug, Default)]
+pub struct SearchResults<K> {
+    /// Records that matched the search
+    pub matches: Vec<SearchMatch<K>>,
+    /// Records that were skipped because they couldn't be read, and why
+    pub errors: Vec<(K, TesError)>,
+}
+
+/// Where the active version of a form was loaded from
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Provenance<P> {
+    /// Lowercase file name of the plugin that provides the form
+    pub plugin: String,
+    /// Position of that plugin in the load order
+    pub load_order_index: usize,
+    /// Position of the form's record within the plugin
+    ///
+    /// In Morrowind, this is the index of the record among all the plugin's records. In Oblivion,
+    /// it's the record's form ID as stored in the plugin, i.e., relative to the plugin's masters.
+    pub position: P,
+}
+
+/// What to do when a save depends on a plugin that isn't installed
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum MissingPluginPolicy {
+    /// Fail to load the save
+    #[default]
+    Fail,
+    /// Load the save anyway, with an empty placeholder in place of each missing plugin
+    ///
+    /// The placeholders keep the remaining plugins at the load order indices the save expects.
+    /// Forms from missing plugins can't be found, and change records for them are left as they
+    /// are; use [`Tes4World::change_records_from_missing_plugins`] to find them.
+    ///
+    /// [`Tes4World::change_records_from_missing_plugins`]: tes4/struct.Tes4World.html#method.change_records_from_missing_plugins
+    Placeholder,
+}
+
+/// Options for loading a world
+///
+/// The defaults load without an index cache and fail if a save's plugins are missing.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct LoadOptions<'a> {
+    pub(crate) cache: Option<&'a IndexCache>,
+    pub(crate) missing_plugins: MissingPluginPolicy,
+}
+
+impl<'a> LoadOptions<'a> {
+    /// Creates the default load options
+    pub fn new() -> LoadOptions<'a> {
+        LoadOptions::default()
+    }
+
+    /// Uses an index cache to speed up loading plugins
+    ///
+    /// Plugins whose indexes are in the cache load faster, and the cache is updated with the
+    /// indexes of plugins that weren't.
+    pub fn cache(mut self, cache: &'a IndexCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Sets what to do when a save depends on a plugin that isn't installed
+    ///
+    /// Only Oblivion saves can be loaded without their plugins; Morrowind saves always fail to
+    /// load if a plugin is missing.
+    pub fn missing_plugins(mut self, policy: MissingPluginPolicy) -> Self {
+        self.missing_plugins = policy;
+        self
+    }
+}
+
+/// Checks whether any of the given strings contain some lowercase text, ignoring case
+pub(crate) fn matches_search<'a, I>(lc_text: &str, values: I) -> bool
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    values
+        .into_iter()
+        .any(|v| v.to_lowercase().contains(lc_text))
+}
+
 /// The full set of objects in the game world
 ///
 /// The World type manages the current load order of plugins and allows looking up records from
@@ -12,21 +112,64 @@ pub trait World {
 
     const PLUGIN_DIR: &'static str;
 
-    fn load_plugins<P, S, T>(
-        plugin_dir: P,
+    /// Width of an exterior cell in world units
+    const CELL_SIZE: f32;
+
+    /// Gets the world position of the south-west corner of an exterior cell
+    fn grid_to_world(grid: GridCoords) -> (f32, f32) {
+        (
+            grid.x as f32 * Self::CELL_SIZE,
+            grid.y as f32 * Self::CELL_SIZE,
+        )
+    }
+
+    /// Gets the coordinates of the exterior cell containing a world position
+    fn world_to_grid(x: f32, y: f32) -> GridCoords {
+        GridCoords {
+            x: (x / Self::CELL_SIZE).floor() as i32,
+            y: (y / Self::CELL_SIZE).floor() as i32,
+        }
+    }
+
+    /// Gets the paths of a game installed in a directory, with the usual data directory
+    fn game_paths<P: AsRef<Path>>(root: P) -> GamePaths {
+        let root = root.as_ref();
+        GamePaths::new(root, root.join(Self::PLUGIN_DIR))
+    }
+
+    /// Loads a plugin from a file, using an index cache if one is given
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs or if the plugin file is invalid.
+    fn load_plugin_file<P: AsRef<Path>>(
+        path: P,
+        cache: Option<&IndexCache>,
+    ) -> Result<Self::Plugin, TesError> {
+        match cache {
+            Some(cache) => cache.load_plugin(path),
+            None => Self::Plugin::load_file(path),
+        }
+    }
+
+    /// Loads plugins by name from a game's data directories, sorted into the game's load order
+    ///
+    /// When a plugin is in more than one data directory, the copy with the highest precedence is
+    /// loaded.
+    fn load_plugins<S, T>(
+        paths: &GamePaths,
         plugin_names: T,
+        cache: Option<&IndexCache>,
     ) -> Result<Vec<(String, Self::Plugin)>, TesError>
     where
-        P: AsRef<Path>,
         S: AsRef<str>,
         T: Iterator<Item = S>,
     {
-        let plugin_path = plugin_dir.as_ref();
         let mut files = vec![];
         for filename in plugin_names {
-            let plugin_path = plugin_path.join(filename.as_ref());
+            let plugin_path = paths.data_file(filename.as_ref());
             let meta = fs::metadata(&plugin_path)?;
-            let plugin = Self::Plugin::load_file(&plugin_path)?;
+            let plugin = Self::load_plugin_file(&plugin_path, cache)?;
             // The file_name() unwrap should be safe because if this wasn't a path that pointed to a
             // file, Plugin::load_file would have failed. The into_string() unwrap should be safe
             // because I got these filenames from an iterator of strings to begin with, so the reverse
@@ -51,4 +194,24 @@ pub trait World {
 
         Ok(files.into_iter().map(|(a, b, _)| (a, b)).collect())
     }
+
+    fn load_plugin_files<P, T>(
+        paths: T,
+        cache: Option<&IndexCache>,
+    ) -> Result<Vec<(String, Self::Plugin)>, TesError>
+    where
+        P: AsRef<Path>,
+        T: IntoIterator<Item = P>,
+    {
+        let mut plugins = vec![];
+        for path in paths {
+            let path = path.as_ref();
+            let plugin = Self::load_plugin_file(path, cache)?;
+            // if the path didn't have a file name, Plugin::load_file would have failed
+            let name = path.file_name().unwrap().to_string_lossy().to_lowercase();
+            plugins.push((name, plugin));
+        }
+
+        Ok(plugins)
+    }
 }